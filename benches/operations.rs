@@ -0,0 +1,158 @@
+//! 插入、主键点查、全表扫描、更新和删除的 Criterion 基准测试
+//!
+//! 每组按表的行数参数化，行数由 `BENCH_ROWS`（逗号分隔，默认 `100,1000`）指定；
+//! `BENCH_SAMPLE_SIZE`（默认 10）和 `BENCH_MEASUREMENT_MILLIS`（默认 1000）控制采样，
+//! 默认值让 `cargo bench` 很快跑完。
+//!
+//! Criterion 把每次的结果保存在 `target/criterion` 下并与上一次比较，报告性能变化；
+//! 也可以用 `cargo bench -- --save-baseline main` 保存基线，
+//! 之后 `cargo bench -- --baseline main` 与之比较。
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use simple_db::SimpleDB;
+use simple_db::testing::{self, PERF_TABLE};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// 各组测试使用的表行数
+fn table_sizes() -> Vec<usize> {
+    std::env::var("BENCH_ROWS")
+        .ok()
+        .map(|val| {
+            val.split(',')
+                .filter_map(|size| size.trim().parse().ok())
+                .filter(|&size| size > 0)
+                .collect()
+        })
+        .filter(|sizes: &Vec<usize>| !sizes.is_empty())
+        .unwrap_or_else(|| vec![100, 1000])
+}
+
+/// 打开临时数据库并写入 `rows` 行
+fn populated(rows: usize) -> (TempDir, SimpleDB) {
+    let (temp_dir, mut db) = testing::temp_db("bench").expect("无法创建临时数据库");
+    testing::populate_table(&mut db, rows).expect("无法写入测试数据");
+    (temp_dir, db)
+}
+
+fn execute(db: &mut SimpleDB, sql: &str) {
+    db.execute_single_sql(sql).expect("基准测试语句执行失败");
+}
+
+/// 向空表逐条插入 `rows` 行
+fn bench_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    for rows in table_sizes() {
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &rows, |b, &rows| {
+            b.iter_batched(
+                || populated(0),
+                |(temp_dir, mut db)| {
+                    for id in 1..=rows {
+                        execute(&mut db, &testing::insert_row_sql(id));
+                    }
+                    // 交给 Criterion 在计时之外丢弃，先关闭数据库再删除目录
+                    (db, temp_dir)
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// 按主键查询一行
+fn bench_point_select(c: &mut Criterion) {
+    let mut group = c.benchmark_group("point_select");
+    for rows in table_sizes() {
+        let (_temp_dir, mut db) = populated(rows);
+        let mut id = 0;
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &rows, |b, &rows| {
+            b.iter(|| {
+                id = id % rows + 1;
+                execute(
+                    &mut db,
+                    &format!("SELECT * FROM {} WHERE id = {}", PERF_TABLE, id),
+                );
+            });
+        });
+    }
+    group.finish();
+}
+
+/// 读出整张表
+fn bench_full_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full_scan");
+    for rows in table_sizes() {
+        let (_temp_dir, mut db) = populated(rows);
+        let sql = format!("SELECT * FROM {}", PERF_TABLE);
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &rows, |b, _| {
+            b.iter(|| execute(&mut db, &sql));
+        });
+    }
+    group.finish();
+}
+
+/// 按主键修改一行
+fn bench_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for rows in table_sizes() {
+        let (_temp_dir, mut db) = populated(rows);
+        let mut id = 0;
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &rows, |b, &rows| {
+            b.iter(|| {
+                id = id % rows + 1;
+                execute(
+                    &mut db,
+                    &format!(
+                        "UPDATE {} SET score = score + 1 WHERE id = {}",
+                        PERF_TABLE, id
+                    ),
+                );
+            });
+        });
+    }
+    group.finish();
+}
+
+/// 按主键删除一行；表删空后重新写入，写入的时间不计入
+fn bench_delete(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delete");
+    for rows in table_sizes() {
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &rows, |b, &rows| {
+            b.iter_custom(|iters| {
+                let (_temp_dir, mut db) = populated(rows);
+                let mut elapsed = Duration::ZERO;
+                for i in 0..iters as usize {
+                    if i > 0 && i % rows == 0 {
+                        testing::populate_table(&mut db, rows).expect("无法写入测试数据");
+                    }
+                    let sql = format!("DELETE FROM {} WHERE id = {}", PERF_TABLE, i % rows + 1);
+                    let start = Instant::now();
+                    execute(&mut db, &sql);
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            });
+        });
+    }
+    group.finish();
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(testing::env_or_default("BENCH_SAMPLE_SIZE", 10).max(10))
+        .warm_up_time(Duration::from_millis(200))
+        .measurement_time(Duration::from_millis(testing::env_or_default(
+            "BENCH_MEASUREMENT_MILLIS",
+            1000,
+        ) as u64))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = bench_insert, bench_point_select, bench_full_scan, bench_update, bench_delete
+}
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+use crate::error::{DBError, Result};
+use crate::executor::{QueryResult, ResultSet};
+use crate::server::protocol::{self, ResultFormat};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+/// `--serve` 模式的客户端
+///
+/// 两种结果格式都解码为同样的 [`QueryResult`] / [`ResultSet`]。
+/// 请求按行发送，SQL 中的换行会替换为空格。
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+    format: ResultFormat,
+}
+
+impl Client {
+    /// 连接服务端并协商结果格式
+    pub fn connect<A: ToSocketAddrs>(addr: A, format: ResultFormat) -> Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let mut client = Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+            format,
+        };
+        client.send_line(format.preamble())?;
+        Ok(client)
+    }
+
+    pub fn format(&self) -> ResultFormat {
+        self.format
+    }
+
+    /// 执行 SQL，返回最后一条语句的结果；服务端的错误原样返回
+    pub fn execute(&mut self, sql: &str) -> Result<QueryResult> {
+        self.send_line(&sql.replace(['\r', '\n'], " "))?;
+
+        match self.format {
+            ResultFormat::Json => {
+                let mut line = String::new();
+                if self.reader.read_line(&mut line)? == 0 {
+                    return Err(DBError::IO("服务端已关闭连接".to_string()));
+                }
+                let json = serde_json::from_str(&line)
+                    .map_err(|e| DBError::IO(format!("无法解析服务端响应: {}", e)))?;
+                protocol::result_from_json(&json)
+            }
+            ResultFormat::Binary => protocol::read_binary_result(&mut self.reader),
+        }
+    }
+
+    /// 执行查询并返回结果集
+    pub fn query(&mut self, sql: &str) -> Result<ResultSet> {
+        match self.execute(sql)? {
+            QueryResult::ResultSet(rs) => Ok(rs),
+            other => Err(DBError::Execution(format!(
+                "语句没有返回结果集: {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn send_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
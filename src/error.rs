@@ -36,6 +36,54 @@ pub enum DBError {
     /// Readline 错误
     #[error("交互式输入错误: {0}")]
     Readline(String),
+
+    /// 语句被嵌入方安装的语句守卫拒绝
+    #[error("语句被拒绝: {0}")]
+    Forbidden(String),
+
+    /// 启动配置有误，例如 `--data-dir` 指向文件或没有权限的目录
+    #[error("{0}")]
+    Config(String),
+
+    /// 语句执行中途被取消，例如交互模式下按 Ctrl+C，信息中带有已处理的行数
+    #[error("{0}")]
+    Cancelled(String),
+}
+
+impl DBError {
+    /// 错误码，供客户端区分错误类别
+    pub fn code(&self) -> u32 {
+        match self {
+            DBError::Other(_) => 1,
+            DBError::Parse(_) => 2,
+            DBError::Planner(_) => 3,
+            DBError::Schema(_) => 4,
+            DBError::Execution(_) => 5,
+            DBError::NotFound(_) => 6,
+            DBError::IO(_) => 7,
+            DBError::Readline(_) => 8,
+            DBError::Forbidden(_) => 9,
+            DBError::Config(_) => 10,
+            DBError::Cancelled(_) => 11,
+        }
+    }
+
+    /// 由错误码和错误信息还原错误，未知错误码视为 `Other`
+    pub fn from_code(code: u32, message: String) -> Self {
+        match code {
+            2 => DBError::Parse(message),
+            3 => DBError::Planner(message),
+            4 => DBError::Schema(message),
+            5 => DBError::Execution(message),
+            6 => DBError::NotFound(message),
+            7 => DBError::IO(message),
+            8 => DBError::Readline(message),
+            9 => DBError::Forbidden(message),
+            10 => DBError::Config(message),
+            11 => DBError::Cancelled(message),
+            _ => DBError::Other(message),
+        }
+    }
 }
 
 impl From<parser::ParserError> for DBError {
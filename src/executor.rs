@@ -1,47 +1,157 @@
 use crate::error::{DBError, Result};
 use crate::planner::Plan;
+use crate::planner::cost::{AccessEstimate, AccessPath, CostEstimator, Predicate};
+use crate::planner::like::{DEFAULT_LIKE_ESCAPE, LikePattern};
+use crate::planner::optimizer;
+use crate::planner::validate::{self, ensure_writable};
+use crate::session::{
+    QUERY_HISTORY_TABLE, Session, VariableSpec, WarningLevel, query_history_columns,
+};
 use crate::storage::StorageEngine;
-use crate::storage::table::{ColumnDef, DataType, Record, Value};
+use crate::storage::io::page::MAX_RECORD_SIZE;
+use crate::storage::table::{
+    AlterColumn, CheckConstraint, ColumnDef, DataType, IndexDef, Record, Table, Value, ValueKey,
+    column_position,
+};
 
-use super::planner::SelectColumns;
+use super::planner::{
+    BinaryOperator, Condition, Expression, InsertSource, Join, SelectColumns, SelectInto,
+    resolve_column,
+};
 
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::fmt;
+use std::ops::Bound;
+use std::time::Duration;
 use regex::Regex;
 
+mod aggregate;
+mod budget;
+mod check_constraint;
+mod foreign_key;
+mod row;
+mod sort;
+mod subquery;
+
+pub use row::{FromValue, Row};
+
 /// 查询结果数据
 #[derive(Debug)]
 pub struct ResultSet {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<Value>>, // 改为 Value 类型
+    /// 附加在表格之后的注释行
+    pub comment: Option<String>,
+    /// 语句耗时，有值时在结果之后输出 `N rows in set (X.XXX sec)`
+    pub elapsed: Option<Duration>,
+    /// 表格输出的行数和列宽限制，只影响 Display，`rows` 中始终是完整的数据
+    ///
+    /// 默认不限制；只有交互模式在输出前设为 `.set maxrows`、`.set maxwidth` 的值，
+    /// 脚本、`-e` 和库调用得到完整的输出。
+    pub limits: DisplayLimits,
 }
 
-impl fmt::Display for ResultSet {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // 如果没有数据行，什么都不输出
-        if self.rows.is_empty() {
-            return Ok(());
+/// 交互模式中表格输出的默认最大行数
+pub const DEFAULT_MAX_ROWS: usize = 1000;
+
+/// 交互模式中单元格的默认最大宽度（字符数）
+pub const DEFAULT_MAX_WIDTH: usize = 64;
+
+/// 超过最大宽度的单元格以此结尾
+const ELLIPSIS: &str = "...";
+
+/// 表格输出的限制（`.set maxrows`、`.set maxwidth`），None 表示不限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayLimits {
+    /// 最多输出的行数，其余的行以 `... N more rows` 代替
+    pub max_rows: Option<usize>,
+    /// 单元格的最大宽度，更长的值截断并以省略号结尾
+    pub max_width: Option<usize>,
+}
+
+impl DisplayLimits {
+    /// 不限制行数和列宽
+    pub const UNLIMITED: DisplayLimits = DisplayLimits {
+        max_rows: None,
+        max_width: None,
+    };
+
+    /// 单元格宽度的下限：截断后至少保留一个字符和省略号
+    pub const MIN_WIDTH: usize = ELLIPSIS.len() + 1;
+}
+
+/// 交互模式的默认限制
+impl Default for DisplayLimits {
+    fn default() -> Self {
+        Self {
+            max_rows: Some(DEFAULT_MAX_ROWS),
+            max_width: Some(DEFAULT_MAX_WIDTH),
         }
-        if self.columns.is_empty() {
-            return Ok(());
+    }
+}
+
+impl ResultSet {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<Value>>) -> Self {
+        Self {
+            columns,
+            rows,
+            comment: None,
+            elapsed: None,
+            limits: DisplayLimits::UNLIMITED,
+        }
+    }
+
+    /// 表格中输出的行，超过最大行数的部分不输出
+    pub fn visible_rows(&self) -> &[Vec<Value>] {
+        let max_rows = self.limits.max_rows.unwrap_or(usize::MAX);
+        &self.rows[..self.rows.len().min(max_rows)]
+    }
+
+    /// 省略了部分行时在表格之后输出的提示，如 `... 42 more rows`
+    pub fn hidden_rows_note(&self) -> Option<String> {
+        match self.rows.len() - self.visible_rows().len() {
+            0 => None,
+            1 => Some(format!("{} 1 more row", ELLIPSIS)),
+            n => Some(format!("{} {} more rows", ELLIPSIS, n)),
         }
+    }
+
+    /// 结果之后的行数和耗时，没有耗时时为 None
+    pub fn footer(&self) -> Option<String> {
+        let elapsed = format_elapsed(self.elapsed?);
+        Some(match self.rows.len() {
+            0 => format!("Empty set {}", elapsed),
+            n => format!("{} in set {}", rows_text(n), elapsed),
+        })
+    }
+}
+
+/// 与 mysql 客户端相同的耗时写法，如 `(0.012 sec)`
+fn format_elapsed(elapsed: Duration) -> String {
+    format!("({:.3} sec)", elapsed.as_secs_f64())
+}
 
-        // 计算每列的最大宽度
+fn rows_text(count: usize) -> String {
+    match count {
+        1 => "1 row".to_string(),
+        n => format!("{} rows", n),
+    }
+}
+
+impl ResultSet {
+    /// 每列的显示宽度（含两侧空格），由表头和所有数据共同决定，按字符数计算
+    pub fn column_widths(&self) -> Vec<usize> {
         let mut column_widths = Vec::new();
 
         for (col_idx, column_name) in self.columns.iter().enumerate() {
-            let mut max_width = format_column_header(column_name).len();
-
-            // 检查该列中所有数据的宽度
-            for row in &self.rows {
-                if col_idx < row.len() {
-                    let cell_str = match &row[col_idx] {
-                        Value::Int(n) => n.to_string(),
-                        Value::Float(f) => f.to_string(),
-                        Value::String(s) => s.clone(),
-                        Value::Boolean(b) => b.to_string(),
-                        Value::Null => "".to_string(),
-                    };
-                    max_width = max_width.max(cell_str.len());
+            let mut max_width = format_column_header(column_name).chars().count();
+
+            // 检查该列中所有输出的数据的宽度
+            for row in self.visible_rows() {
+                if let Some(value) = row.get(col_idx) {
+                    let cell = truncate_cell(format_cell(value), self.limits.max_width);
+                    max_width = max_width.max(cell.chars().count());
                 }
             }
 
@@ -50,88 +160,415 @@ impl fmt::Display for ResultSet {
             let total_width = (actual_content_width + 2).max(5);
             column_widths.push(total_width);
         }
+        column_widths
+    }
 
-        // 打印表头
-        write!(f, "|")?;
-        for (column_name, &width) in self.columns.iter().zip(&column_widths) {
+    /// 表头和分隔线，各占一行
+    pub fn format_header(&self, column_widths: &[usize]) -> String {
+        let mut out = String::from("|");
+        for (column_name, &width) in self.columns.iter().zip(column_widths) {
             let formatted = format_column_header(column_name);
-            write!(f, " {:<width$} |", formatted, width = width - 2)?;
+            out.push_str(&format!(" {:<width$} |", formatted, width = width - 2));
+        }
+        out.push('\n');
+
+        out.push('|');
+        for &width in column_widths {
+            out.push_str(&format!(" {} |", "-".repeat(width - 2)));
         }
-        writeln!(f)?;
+        out.push('\n');
+        out
+    }
 
-        // 打印分隔线
-        write!(f, "|")?;
-        for &width in &column_widths {
-            write!(f, " ")?;
-            write!(f, "{}", "-".repeat(width - 2))?;
-            write!(f, " ")?;
-            write!(f, "|")?;
+    /// 一行数据，以换行结尾；超过最大宽度的值被截断
+    pub fn format_row(&self, row: &[Value], column_widths: &[usize]) -> String {
+        let mut out = String::from("|");
+        for (col_idx, &width) in column_widths.iter().enumerate() {
+            let cell_str = row
+                .get(col_idx)
+                .map(|value| truncate_cell(format_cell(value), self.limits.max_width))
+                .unwrap_or_default();
+            out.push_str(&format!(" {:<width$} |", cell_str, width = width - 2));
         }
-        writeln!(f)?;
+        out.push('\n');
+        out
+    }
 
-        // 打印数据行
+    /// CSV 格式（RFC 4180）：首行为列名，每行以换行结尾
+    ///
+    /// NULL 输出为空字段，空字符串输出为 `""`，两者可以区分；含逗号、引号或换行的字段加引号。
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = self.columns.iter().map(|name| csv_field(name)).collect();
+        out.push_str(&header.join(","));
+        out.push('\n');
         for row in &self.rows {
-            write!(f, "|")?;
-            for (col_idx, &width) in column_widths.iter().enumerate() {
-                let cell_str = if col_idx < row.len() {
-                    match &row[col_idx] {
-                        Value::Int(n) => n.to_string(),
-                        Value::Float(f) => f.to_string(),
-                        Value::String(s) => s.clone(),
-                        Value::Boolean(b) => b.to_string(),
-                        Value::Null => "".to_string(),
-                    }
-                } else {
-                    "".to_string()
-                };
-                write!(f, " {:<width$} |", cell_str, width = width - 2)?;
+            let fields: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    Value::Null => String::new(),
+                    Value::String(s) => csv_field(s),
+                    other => other.to_string(),
+                })
+                .collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// JSON 格式：每行一个以列名为键的对象，键按列的顺序排列，NULL 输出为 null
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = self
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(name, value)| {
+                        format!("{}:{}", serde_json::Value::from(name.as_str()), value.to_json())
+                    })
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            })
+            .collect();
+        format!("[{}]", rows.join(","))
+    }
+}
+
+/// CSV 字段：空串以及含逗号、引号或换行的字段加引号，字段中的引号写两遍
+fn csv_field(text: &str) -> String {
+    if text.is_empty() || text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// 超过 `max_width` 个字符的单元格截断到 `max_width` 个字符，最后几个字符换成省略号
+fn truncate_cell(cell: String, max_width: Option<usize>) -> String {
+    match max_width {
+        Some(max) if cell.chars().count() > max => {
+            let kept = max.saturating_sub(ELLIPSIS.len());
+            let mut truncated: String = cell.chars().take(kept).collect();
+            truncated.push_str(ELLIPSIS);
+            truncated
+        }
+        _ => cell,
+    }
+}
+
+/// 单元格的显示文本，NULL 显示为空
+fn format_cell(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl fmt::Display for ResultSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // 如果没有数据行，只输出耗时
+        if self.rows.is_empty() || self.columns.is_empty() {
+            if let Some(footer) = self.footer() {
+                writeln!(f, "{}", footer)?;
             }
-            writeln!(f)?;
+            return Ok(());
+        }
+
+        let column_widths = self.column_widths();
+        write!(f, "{}", self.format_header(&column_widths))?;
+        for row in self.visible_rows() {
+            write!(f, "{}", self.format_row(row, &column_widths))?;
+        }
+        if let Some(note) = self.hidden_rows_note() {
+            writeln!(f, "{}", note)?;
+        }
+
+        if let Some(comment) = &self.comment {
+            writeln!(f, "-- {}", comment)?;
+        }
+        if let Some(footer) = self.footer() {
+            writeln!(f, "{}", footer)?;
         }
 
         Ok(())
     }
 }
 
+/// INSERT 和 DELETE 的执行摘要
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastInsert {
+    /// 实际插入或删除的行数（INSERT IGNORE 跳过的行不计入）
+    pub rows_affected: usize,
+    /// 最后一行生成的自增值，表中没有自增列或 DELETE 时为 None
+    pub last_insert_id: Option<i64>,
+    /// 语句耗时，有值时输出 `Query OK, N rows affected (X.XXX sec)`
+    pub elapsed: Option<Duration>,
+}
+
+/// UPDATE 的执行摘要（与 MySQL 的 Rows matched / Changed 对应）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateSummary {
+    /// 满足 WHERE 条件的行数
+    pub matched: usize,
+    /// 值确实发生变化、写回存储的行数
+    pub changed: usize,
+    /// 语句耗时，有值时输出影响的行数和耗时
+    pub elapsed: Option<Duration>,
+}
+
 /// 查询执行结果
 #[derive(Debug)]
 pub enum QueryResult {
     ResultSet(ResultSet),
     Success,
+    /// INSERT 或 DELETE 成功，附带影响的行数和 INSERT 生成的自增值
+    Modified(LastInsert),
+    /// UPDATE 成功，附带匹配行数和变更行数
+    Updated(UpdateSummary),
+    /// 执行成功并附带一条提示信息
+    Message(String),
+}
+
+impl QueryResult {
+    /// 返回或影响的行数
+    pub fn row_count(&self) -> usize {
+        match self {
+            QueryResult::ResultSet(rs) => rs.rows.len(),
+            QueryResult::Modified(last) => last.rows_affected,
+            QueryResult::Updated(summary) => summary.changed,
+            QueryResult::Success | QueryResult::Message(_) => 0,
+        }
+    }
+
+    /// 设置结果集表格输出的行数和列宽限制，其它结果没有影响
+    pub fn set_limits(&mut self, limits: DisplayLimits) {
+        if let QueryResult::ResultSet(rs) = self {
+            rs.limits = limits;
+        }
+    }
+
+    /// 设置或清除语句耗时，只有结果集、INSERT、UPDATE 和 DELETE 的结果记录耗时
+    pub fn set_elapsed(&mut self, elapsed: Option<Duration>) {
+        match self {
+            QueryResult::ResultSet(rs) => rs.elapsed = elapsed,
+            QueryResult::Modified(last) => last.elapsed = elapsed,
+            QueryResult::Updated(summary) => summary.elapsed = elapsed,
+            QueryResult::Success | QueryResult::Message(_) => {}
+        }
+    }
 }
 
 impl fmt::Display for QueryResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             QueryResult::ResultSet(rs) => write!(f, "{}", rs),
-            QueryResult::Success => Ok(()),
+            QueryResult::Modified(LastInsert {
+                rows_affected,
+                elapsed: Some(elapsed),
+                ..
+            }) => writeln!(
+                f,
+                "Query OK, {} affected {}",
+                rows_text(*rows_affected),
+                format_elapsed(*elapsed)
+            ),
+            QueryResult::Updated(UpdateSummary {
+                matched,
+                changed,
+                elapsed: Some(elapsed),
+            }) => {
+                writeln!(
+                    f,
+                    "Query OK, {} affected {}",
+                    rows_text(*changed),
+                    format_elapsed(*elapsed)
+                )?;
+                writeln!(f, "Rows matched: {}  Changed: {}", matched, changed)
+            }
+            QueryResult::Success | QueryResult::Modified(_) | QueryResult::Updated(_) => Ok(()),
+            QueryResult::Message(msg) => writeln!(f, "{}", msg),
+        }
+    }
+}
+
+/// 执行器对存储引擎的访问方式
+enum StorageAccess<'a> {
+    /// 共享访问，只能执行不修改数据的语句，见 [`Executor::read_only`]
+    Shared(&'a StorageEngine),
+    /// 独占访问，可以执行所有语句
+    Exclusive(&'a mut StorageEngine),
+}
+
+impl StorageAccess<'_> {
+    fn get(&self) -> &StorageEngine {
+        match self {
+            StorageAccess::Shared(storage) => storage,
+            StorageAccess::Exclusive(storage) => storage,
         }
     }
 }
 
+/// SELECT 要过滤的记录：已经取出的记录，或者扫描整张表（`Some` 时只读取这些位置的列）
+enum RecordSource {
+    Loaded(Vec<Record>),
+    Scan(Option<Vec<usize>>),
+}
+
 /// 统一SQL执行器，处理所有类型的SQL操作
 pub struct Executor<'a> {
-    storage: &'a mut StorageEngine,
+    storage: StorageAccess<'a>,
+    session: &'a mut Session,
+    /// 本条语句的执行时间和扫描行数，超过会话的执行限制时中止
+    budget: budget::ExecutionBudget,
 }
 
 impl<'a> Executor<'a> {
-    pub fn new(storage: &'a mut StorageEngine) -> Self {
-        Self { storage }
+    pub fn new(storage: &'a mut StorageEngine, session: &'a mut Session) -> Self {
+        Self {
+            budget: budget::ExecutionBudget::new(
+                session.execution_limits(),
+                session.cancel_flag().cloned(),
+                storage.in_transaction(),
+            ),
+            storage: StorageAccess::Exclusive(storage),
+            session,
+        }
+    }
+
+    /// 只读执行器：只持有存储引擎的共享引用，多个线程可以各自用自己的会话同时查询
+    ///
+    /// 修改数据或结构的语句（见 [`Plan::is_mutating`]）以及 USE、BEGIN 等需要改动
+    /// 存储引擎状态的语句返回 [`DBError::Forbidden`]。
+    pub fn read_only(storage: &'a StorageEngine, session: &'a mut Session) -> Self {
+        Self {
+            storage: StorageAccess::Shared(storage),
+            budget: budget::ExecutionBudget::new(
+                session.execution_limits(),
+                session.cancel_flag().cloned(),
+                storage.in_transaction(),
+            ),
+            session,
+        }
+    }
+
+    fn storage(&self) -> &StorageEngine {
+        self.storage.get()
+    }
+
+    fn storage_mut(&mut self) -> Result<&mut StorageEngine> {
+        match &mut self.storage {
+            StorageAccess::Shared(_) => {
+                Err(DBError::Forbidden("只读执行器不能修改数据库".to_string()))
+            }
+            StorageAccess::Exclusive(storage) => Ok(storage),
+        }
     }
 
     pub fn execute(&mut self, plan: Plan) -> Result<QueryResult> {
+        // SHOW WARNINGS 查看的是上一条语句的警告，其余语句执行前先清空
+        if !matches!(plan, Plan::ShowWarnings) {
+            self.session.clear_warnings();
+        }
+        // 只读执行器在读取任何数据之前拒绝修改语句，避免 SELECT ... INTO 先查询再失败
+        if plan.is_mutating() {
+            self.storage_mut()?;
+        }
+        log::debug!("执行计划:\n{}", plan.explain().join("\n"));
+
         match &plan {
-            Plan::CreateTable { name, columns } => {
-                match self.storage.create_table(name.clone(), columns.to_vec()) {
-                    Ok(_) => Ok(QueryResult::Success),
-                    Err(e) => Err(DBError::Schema(e.to_string())),
+            Plan::CreateTable {
+                name,
+                columns,
+                indexes,
+                checks,
+                if_not_exists,
+            } => {
+                ensure_writable(name)?;
+                if *if_not_exists && self.storage().get_table(name).is_ok() {
+                    self.session.add_warning(
+                        WarningLevel::Note,
+                        1050,
+                        format!("表 '{}' 已存在", name),
+                    );
+                    return Ok(QueryResult::Success);
+                }
+                self.check_row_size(name, columns)?;
+                let columns =
+                    foreign_key::resolve_definitions(self.storage(), name, columns, indexes)?;
+                if let Err(e) = self
+                    .storage_mut()?
+                    .create_table(name.clone(), columns.clone())
+                {
+                    return Err(DBError::Schema(e.to_string()));
                 }
+
+                // 内联索引和 CHECK 约束与建表是一个整体：任何一个创建失败都撤销整张表
+                for index in indexes {
+                    if let Err(e) = self.storage_mut()?.create_index(name, index.clone()) {
+                        self.storage_mut()?.drop_table(name)?;
+                        return Err(DBError::Schema(e.to_string()));
+                    }
+                }
+                for check in checks {
+                    if let Err(e) = self.storage_mut()?.create_check(name, check.clone()) {
+                        self.storage_mut()?.drop_table(name)?;
+                        return Err(DBError::Schema(e.to_string()));
+                    }
+                }
+                self.note_int_display_widths(&columns);
+                Ok(QueryResult::Success)
+            }
+            Plan::AlterTable {
+                table_name,
+                operations,
+            } => {
+                ensure_writable(table_name)?;
+                let altered = self
+                    .storage()
+                    .get_table(table_name)?
+                    .plan_alter(operations)?;
+                for operation in operations {
+                    if let AlterColumn::Drop { name } = operation {
+                        foreign_key::check_drop_column(self.storage(), table_name, name)?;
+                        check_constraint::check_drop_column(self.storage(), table_name, name)?;
+                    }
+                }
+                self.check_row_size(table_name, &altered.columns)?;
+                self.storage_mut()?.alter_table(table_name, operations)?;
+
+                let added: Vec<ColumnDef> = operations
+                    .iter()
+                    .filter_map(|operation| match operation {
+                        AlterColumn::Add { column, .. } => Some(column.clone()),
+                        AlterColumn::Drop { .. } => None,
+                    })
+                    .collect();
+                self.note_int_display_widths(&added);
+                Ok(QueryResult::Success)
             }
-            Plan::DropTable { name_vec } => {
+            Plan::DropTable { name_vec, if_exists } => {
                 let mut last_err = None;
                 for table_name in name_vec {
-                    match self.storage.drop_table(table_name) {
-                        Ok(_) => {}                   // 删除成功，继续
+                    if self.storage().get_table(table_name).is_ok()
+                        && let Err(e) =
+                            foreign_key::check_drop_table(self.storage(), table_name, name_vec)
+                    {
+                        last_err = Some(e);
+                        continue;
+                    }
+                    match self.storage_mut()?.drop_table(table_name) {
+                        Ok(_) => {} // 删除成功，继续
+                        Err(DBError::NotFound(_)) if *if_exists => {
+                            self.session.add_warning(
+                                WarningLevel::Note,
+                                1051,
+                                format!("未知的表 '{}'", table_name),
+                            );
+                        }
                         Err(e) => last_err = Some(e), // 记录最后一个错误
                     }
                 }
@@ -145,197 +582,403 @@ impl<'a> Executor<'a> {
             Plan::Insert {
                 table_name,
                 columns,
-                rows,
+                source,
+                ignore,
+                returning,
             } => {
+                ensure_writable(table_name)?;
                 // 获取表定义
-                let table_columns = self.storage.get_table_columns(table_name)?;
-
-                if columns.is_empty() {
-                    // 无列名插入：验证值数量是否与表的所有列匹配
-                    for (row_index, row) in rows.iter().enumerate() {
-                        if row.len() != table_columns.len() {
-                            return Err(DBError::Execution(format!(
-                                "第 {} 行的值数量({})与表的列数({})不匹配",
-                                row_index + 1,
-                                row.len(),
-                                table_columns.len()
-                            )));
-                        }
+                let table_columns = self.storage().get_table_columns(table_name)?;
+
+                // 查询先完整执行完再写入，INSERT INTO t SELECT ... FROM t 只复制写入前已有的行
+                let selected: Vec<Vec<Option<Value>>>;
+                let rows = match source {
+                    InsertSource::Values(rows) => rows,
+                    InsertSource::Select(select) => {
+                        let result = match **select {
+                            Plan::Union { .. } => self.execute_union(select, false)?,
+                            _ => self.execute_select(select, false)?,
+                        };
+                        let QueryResult::ResultSet(result_set) = result else {
+                            unreachable!("查询总是返回结果集");
+                        };
+                        let expected = if columns.is_empty() {
+                            table_columns.len()
+                        } else {
+                            columns.len()
+                        };
+                        validate::check_insert_select_arity(result_set.columns.len(), expected)?;
+                        selected = result_set
+                            .rows
+                            .into_iter()
+                            .map(|row| row.into_iter().map(Some).collect())
+                            .collect();
+                        &selected
                     }
+                };
 
-                    // 按表定义顺序插入所有列
-                    for row in rows {
-                        // 验证每个值的类型是否与列定义匹配
-                        for (col_index, value) in row.iter().enumerate() {
-                            let column_def = &table_columns[col_index];
-                            self.validate_value_type(value, &column_def.data_type)?;
+                // 有 RETURNING 或自增列时，需要读回实际写入的行（包含生成的值）
+                let auto_increment_index = table_columns.iter().position(|col| col.auto_increment);
+                let track_rows = returning.is_some() || auto_increment_index.is_some();
+                let mut inserted = Vec::new();
+                let mut rows_affected = 0;
+
+                // 写入前检查所有行，把每个出错的行合并到一个错误中报告，任何一行有问题都不写入；
+                // 值数量不符即使带 IGNORE 也报错
+                let checks = check_constraint::plan_checks(self.storage(), table_name)?;
+                let mut full_rows = Vec::with_capacity(rows.len());
+                let mut row_numbers = Vec::with_capacity(rows.len());
+                let mut errors = Vec::new();
+                let truncate = self.session.truncate_long_strings();
+                let mode = self.session.sql_mode();
+                for (row_index, row) in rows.iter().enumerate() {
+                    self.budget.check()?;
+                    if columns.is_empty()
+                        && let Err(e) =
+                            validate::check_row_arity(row, table_columns.len(), row_index + 1)
+                    {
+                        errors.push(e);
+                        continue;
+                    }
+                    let row_number = (rows.len() > 1).then_some(row_index + 1);
+                    let full_row = validate::build_insert_row(&table_columns, columns, row, mode)
+                        .and_then(|full_row| {
+                            validate::check_not_null(&full_row, &table_columns)?;
+                            check_constraint::check_row(&checks, &full_row, &table_columns, mode)?;
+                            Ok(full_row)
+                        })
+                        .map_err(|e| validate::at_row(e, row_number))
+                        .and_then(|full_row| {
+                            // 不截断时超长字符串在这里报告（INSERT IGNORE 跳过该行）
+                            if !truncate {
+                                validate::check_row_lengths(
+                                    &full_row,
+                                    &table_columns,
+                                    row_index + 1,
+                                )?;
+                            }
+                            Ok(full_row)
+                        });
+                    match full_row {
+                        Ok(full_row) => {
+                            full_rows.push(full_row);
+                            row_numbers.push(row_index + 1);
                         }
-                        self.storage.insert_record(table_name, row.clone())?;
+                        // INSERT IGNORE：跳过违反类型或约束的行
+                        Err(DBError::Schema(_) | DBError::Execution(_)) if *ignore => {}
+                        Err(e @ (DBError::Schema(_) | DBError::Execution(_))) => errors.push(e),
+                        Err(e) => return Err(e),
                     }
+                }
+                if let Some(e) = validate::combine_row_errors(errors) {
+                    return Err(e);
+                }
+                // 超长字符串默认报错，truncate_long_strings 打开时截断并警告
+                let truncations =
+                    self.storage()
+                        .fit_strings(table_name, &mut full_rows, truncate)?;
+                for truncation in truncations {
+                    self.warn_truncated(&truncation.column, row_numbers[truncation.row - 1]);
+                }
+
+                // 外键值必须能在被引用表中找到；IGNORE 跳过找不到的行
+                let missing = foreign_key::find_missing_references(
+                    self.storage(),
+                    table_name,
+                    &table_columns,
+                    &full_rows,
+                    &[],
+                )?;
+                if *ignore {
+                    full_rows = full_rows
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(row_index, _)| !missing.iter().any(|(m, _)| m == row_index))
+                        .map(|(_, row)| row)
+                        .collect();
                 } else {
-                    // 有列名插入：需要重新排列值的顺序以匹配表的列顺序
-                    for row in rows.iter() {
-                        // 创建完整的行数据，未指定的列使用默认值
-                        let mut full_row = Vec::with_capacity(table_columns.len());
-
-                        for table_col in &table_columns {
-                            if let Some(column_index) =
-                                columns.iter().position(|col| col == &table_col.name)
-                            {
-                                // 验证值类型是否与列定义匹配
-                                self.validate_value_type(&row[column_index], &table_col.data_type)?;
-                                // 使用提供的值
-                                full_row.push(row[column_index].clone());
-                            } else {
-                                // 使用默认值或 NULL
-                                if table_col.not_null {
-                                    return Err(DBError::Execution(format!(
-                                        "Field '{}' doesn't have a default value",
-                                        table_col.name
-                                    )));
-                                }
-                                full_row.push(Value::Null);
+                    let errors = missing
+                        .into_iter()
+                        .map(|(row_index, e)| {
+                            let row_number = (rows.len() > 1).then_some(row_numbers[row_index]);
+                            validate::at_row(e, row_number)
+                        })
+                        .collect();
+                    if let Some(e) = validate::combine_row_errors(errors) {
+                        return Err(e);
+                    }
+                }
+
+                // 不带 IGNORE 时整批写入：约束整体检查，任何一行失败都不留下；
+                // IGNORE 逐行写入，跳过违反约束的行
+                let record_ids = if *ignore {
+                    let mut record_ids = Vec::with_capacity(full_rows.len());
+                    for full_row in full_rows {
+                        self.budget.check()?;
+                        match self.storage_mut()?.insert_record(table_name, full_row) {
+                            Ok(record_id) => {
+                                record_ids.push(record_id);
+                                self.budget.record_write();
                             }
+                            Err(DBError::Schema(_) | DBError::Execution(_)) => continue,
+                            Err(e) => return Err(e),
                         }
-
-                        self.storage.insert_record(table_name, full_row)?;
+                    }
+                    record_ids
+                } else {
+                    self.storage_mut()?.insert_records(table_name, full_rows)?
+                };
+                for record_id in record_ids {
+                    rows_affected += 1;
+                    if track_rows {
+                        inserted.push(self.storage().get_record(table_name, record_id)?);
                     }
                 }
 
-                Ok(QueryResult::Success)
+                if let Some(returning) = returning {
+                    let returning = &*returning.expand(&table_columns, &[table_name.as_str()])?;
+                    let result_rows = self.project_columns(&inserted, returning, &table_columns)?;
+                    let result_columns = self.generate_result_columns(returning, &table_columns)?;
+                    return Ok(QueryResult::ResultSet(ResultSet::new(
+                        result_columns,
+                        result_rows,
+                    )));
+                }
+
+                let last_insert_id = auto_increment_index.and_then(|index| {
+                    match inserted.last().map(|record| &record.values()[index]) {
+                        Some(Value::Int(id)) => Some(*id as i64),
+                        _ => None,
+                    }
+                });
+
+                Ok(QueryResult::Modified(LastInsert {
+                    rows_affected,
+                    last_insert_id,
+                    elapsed: None,
+                }))
             }
             Plan::Update {
                 table_name,
                 set_pairs,
                 conditions,
             } => {
-                //todo!() // 更新操作的实现
+                ensure_writable(table_name)?;
                 // 获取表的列定义
-                let table_columns = self.storage.get_table_columns(table_name)?;
+                let table_columns = self.storage().get_table_columns(table_name)?;
+
+                // SET 的目标列位置；目标列、SET 的表达式和 WHERE 条件中找不到的列一起报告
+                let mut targets = Vec::with_capacity(set_pairs.len());
+                let mut unknown = validate::UnknownColumns::default();
+                for (column_name, expr) in set_pairs {
+                    match column_position(&table_columns, column_name) {
+                        Some(index) => targets.push((index, expr)),
+                        None => unknown.add(column_name),
+                    }
+                    unknown.check_expression(expr, &table_columns)?;
+                }
+                if let Some(condition) = conditions {
+                    validate::check_where(condition, &[], &table_columns)?;
+                    unknown.check_condition(condition, &table_columns)?;
+                }
+                unknown.into_result()?;
+                let conditions = &self.resolve_subqueries(conditions.as_ref(), &[table_name])?;
+
+                // 值为字面量时先检查类型，即使没有匹配的行也报告错误
+                let mode = self.session.sql_mode();
+                for (index, expr) in &targets {
+                    if let Expression::Value(value) = expr {
+                        let value = value
+                            .clone()
+                            .coerce_to(&table_columns[*index].data_type, mode);
+                        validate::check_value_kind(&value, &table_columns[*index].data_type)?;
+                    }
+                }
+
+                // 恒假的条件（见 optimizer::simplify_condition）不访问表
+                if matches!(conditions, Some(Condition::Constant(false))) {
+                    return Ok(QueryResult::Updated(UpdateSummary {
+                        matched: 0,
+                        changed: 0,
+                        elapsed: None,
+                    }));
+                }
 
                 // 获取所有记录
-                let records = self.storage.get_all_records(table_name)?;
+                let records = self.storage().get_all_records(table_name)?;
 
                 // 应用WHERE条件过滤，找出需要更新的记录
-                let to_update: Vec<_> = if let Some(condition) = conditions {
-                    records
-                        .into_iter()
-                        .filter(|record| {
-                            condition.evaluate(record, &table_columns).unwrap_or(false)
-                        })
-                        .collect()
-                } else {
-                    records
-                };
+                let to_update =
+                    self.filter_records(records, conditions.as_ref(), &table_columns)?;
 
-                // 执行更新
+                // SET 的表达式都按更新前的值求值，所以 SET a = b, b = a 交换两列
+                let mut record_ids = Vec::with_capacity(to_update.len());
+                let mut new_rows = Vec::with_capacity(to_update.len());
                 for record in &to_update {
-                    if let Some(record_id) = record.id() {
-                        self.storage
-                            .update_record(table_name, record_id, set_pairs)?;
-                    } else {
+                    self.budget.check()?;
+                    let Some(record_id) = record.id() else {
                         return Err(DBError::Execution("记录缺少ID，无法更新".to_string()));
+                    };
+                    let mut values = record.values().to_vec();
+                    for (index, expr) in &targets {
+                        let column = &table_columns[*index];
+                        let value = expr
+                            .evaluate(record, &table_columns, mode)?
+                            .coerce_to(&column.data_type, mode);
+                        validate::check_value_kind(&value, &column.data_type)?;
+                        if value.is_null() && column.not_null {
+                            return Err(DBError::Schema(format!(
+                                "Column '{}' cannot be null",
+                                column.name
+                            )));
+                        }
+                        values[*index] = value;
                     }
+                    record_ids.push(record_id);
+                    new_rows.push(values);
                 }
 
-                Ok(QueryResult::Success)
+                // 按列长度检查或截断，行号为匹配行的序号
+                let truncations = self.storage().fit_strings(
+                    table_name,
+                    &mut new_rows,
+                    self.session.truncate_long_strings(),
+                )?;
+
+                // 新值与原值完全相同的行不写回存储
+                let mut changed_ids = Vec::new();
+                let mut changed_rows = Vec::new();
+                let mut changed_records = Vec::new();
+                for ((record, record_id), values) in to_update.iter().zip(record_ids).zip(new_rows) {
+                    if record.values() != values.as_slice() {
+                        changed_ids.push(record_id);
+                        changed_rows.push(values);
+                        changed_records.push(record.clone());
+                    }
+                }
+
+                // 所有新值一起检查约束和外键（作为引用方和被引用方），通过后才开始写入
+                let checks = check_constraint::plan_checks(self.storage(), table_name)?;
+                for values in &changed_rows {
+                    check_constraint::check_row(&checks, values, &table_columns, mode)?;
+                }
+                self.storage()
+                    .check_constraints(table_name, &changed_rows, &changed_ids)?;
+                let missing = foreign_key::find_missing_references(
+                    self.storage(),
+                    table_name,
+                    &table_columns,
+                    &changed_rows,
+                    &changed_ids,
+                )?;
+                if let Some((_, e)) = missing.into_iter().next() {
+                    return Err(e);
+                }
+                foreign_key::check_not_referenced(
+                    self.storage(),
+                    table_name,
+                    &changed_records,
+                    &changed_rows,
+                )?;
+                for (&record_id, values) in changed_ids.iter().zip(&changed_rows) {
+                    let set_pairs: Vec<(String, Value)> = targets
+                        .iter()
+                        .map(|(index, _)| (table_columns[*index].name.clone(), values[*index].clone()))
+                        .collect();
+                    self.budget.check()?;
+                    self.storage_mut()?
+                        .update_record(table_name, record_id, &set_pairs)?;
+                    self.budget.record_write();
+                }
+                for truncation in &truncations {
+                    self.warn_truncated(&truncation.column, truncation.row);
+                }
+                let changed = changed_ids.len();
+
+                Ok(QueryResult::Updated(UpdateSummary {
+                    matched: to_update.len(),
+                    changed,
+                    elapsed: None,
+                }))
             }
             Plan::Delete {
                 table_name,
                 conditions,
             } => {
-                //todo!() // 删除操作的实现
+                ensure_writable(table_name)?;
                 // 获取表的列定义
-                let table_columns = self.storage.get_table_columns(table_name)?;
+                let table_columns = self.storage().get_table_columns(table_name)?;
+                if let Some(condition) = conditions {
+                    validate::check_where(condition, &[], &table_columns)?;
+                    let mut unknown = validate::UnknownColumns::default();
+                    unknown.check_condition(condition, &table_columns)?;
+                    unknown.into_result()?;
+                }
+                let conditions = &self.resolve_subqueries(conditions.as_ref(), &[table_name])?;
+                // 恒假的条件不访问表
+                if matches!(conditions, Some(Condition::Constant(false))) {
+                    return Ok(QueryResult::Modified(LastInsert {
+                        rows_affected: 0,
+                        last_insert_id: None,
+                        elapsed: None,
+                    }));
+                }
 
                 // 获取所有记录
-                let records = self.storage.get_all_records(table_name)?;
+                let records = self.storage().get_all_records(table_name)?;
 
                 // 应用WHERE条件过滤，找出需要删除的记录
-                let to_delete: Vec<_> = if let Some(condition) = conditions {
-                    records
-                        .into_iter()
-                        .filter(|record| {
-                            condition.evaluate(record, &table_columns).unwrap_or(false)
-                        })
-                        .collect()
-                } else {
-                    records
-                };
+                let to_delete =
+                    self.filter_records(records, conditions.as_ref(), &table_columns)?;
+
+                // 仍被其它行引用的行不能删除
+                foreign_key::check_not_referenced(self.storage(), table_name, &to_delete, &[])?;
 
                 // 执行删除
                 for record in &to_delete {
                     if let Some(record_id) = record.id() {
-                        self.storage.delete_record(table_name, record_id)?;
+                        self.budget.check()?;
+                        self.storage_mut()?.delete_record(table_name, record_id)?;
+                        self.budget.record_write();
                     } else {
                         return Err(DBError::Execution("记录缺少ID，无法删除".to_string()));
                     }
                 }
 
-                Ok(QueryResult::Success)
+                Ok(QueryResult::Modified(LastInsert {
+                    rows_affected: to_delete.len(),
+                    last_insert_id: None,
+                    elapsed: None,
+                }))
             }
-            Plan::Select {
-                table_name,
-                columns,
-                conditions,
-                order_by,
-            } => {
-                // 处理无表查询（如 SELECT 1+1）
-                if table_name.is_none() {
-                    return self.execute_expression_select(columns);
-                }
-
-                let table_name = table_name
-                    .as_ref()
-                    .ok_or(DBError::Execution("SELECT 查询必须指定表名".to_string()))?;
-
-                // 获取表的列定义
-                let table_columns = self.storage.get_table_columns(table_name)?;
-
-                // 获取所有记录
-                let mut records = self.storage.get_all_records(table_name)?;
-
-                // 应用WHERE条件过滤
-                if let Some(condition) = conditions {
-                    records.retain(|record| {
-                            condition.evaluate(record, &table_columns).unwrap_or(false)
-                        });
-                }
-
-                // 应用ORDER BY排序
-                if let Some(order_items) = order_by {
-                    self.sort_records(&mut records, order_items, &table_columns)?;
+            Plan::Select { .. } => self.execute_select(&plan, true),
+            Plan::Union { .. } => self.execute_union(&plan, true),
+            Plan::Explain(inner) => Ok(QueryResult::ResultSet(ResultSet::new(
+                vec!["plan".to_string()],
+                inner
+                    .explain_with(|plan| self.access_method(plan))
+                    .into_iter()
+                    .map(|line| vec![Value::String(line)])
+                    .collect(),
+            ))),
+            Plan::CreateDatabase { name } => {
+                match self.storage_mut()?.create_database(name.clone()) {
+                    Ok(_) => Ok(QueryResult::Success),
+                    Err(e) => Err(DBError::Schema(e.to_string())),
                 }
-
-                // 处理选择列（投影）
-                let result_rows = self.project_columns(&records, columns, &table_columns)?;
-
-                // 生成结果列名
-                let result_columns = self.generate_result_columns(columns, &table_columns)?;
-
-                // 创建结果集
-                let result_set = ResultSet {
-                    columns: result_columns,
-                    rows: result_rows,
-                };
-
-                Ok(QueryResult::ResultSet(result_set))
             }
-            Plan::CreateDatabase { name } => match self.storage.create_database(name.clone()) {
-                Ok(_) => Ok(QueryResult::Success),
-                Err(e) => Err(DBError::Schema(e.to_string())),
-            },
-            Plan::DropDatabase { name } => match self.storage.drop_database(name) {
-                Ok(_) => Ok(QueryResult::Success),
-                Err(e) => Err(DBError::Schema(e.to_string())),
-            },
-            Plan::UseDatabase { name } => match self.storage.use_database(name) {
-                Ok(_) => Ok(QueryResult::Success),
-                Err(e) => Err(DBError::Schema(e.to_string())),
-            },
+            // 数据库不存在时保留 NotFound，便于调用方区分
+            Plan::DropDatabase { name } => {
+                self.storage_mut()?.drop_database(name)?;
+                Ok(QueryResult::Success)
+            }
+            Plan::UseDatabase { name } => {
+                self.storage_mut()?.use_database(name)?;
+                Ok(QueryResult::Success)
+            }
             Plan::ShowDatabases => {
-                // 获取所有数据库名称
-                let database_names = self.storage.get_database_names();
+                // 获取所有数据库名称（按字母顺序）
+                let database_names = self.storage().list_databases();
 
                 // 创建结果集
                 let mut result_rows = Vec::new();
@@ -343,16 +986,14 @@ impl<'a> Executor<'a> {
                     result_rows.push(vec![Value::String(database_name)]);
                 }
 
-                let result_set = ResultSet {
-                    columns: vec!["Database".to_string()],
-                    rows: result_rows,
-                };
+                let result_set = ResultSet::new(vec!["Database".to_string()], result_rows);
 
                 Ok(QueryResult::ResultSet(result_set))
             }
             Plan::ShowTables => {
-                // 获取当前数据库中所有表名
-                let table_names = self.storage.get_table_names()?;
+                // 获取当前数据库中所有表名（按字母顺序），空数据库返回空结果集
+                let table_names = self.storage().get_table_names()?;
+                let database = self.storage().current_database()?.get_name();
 
                 // 创建结果集
                 let mut result_rows = Vec::new();
@@ -360,109 +1001,745 @@ impl<'a> Executor<'a> {
                     result_rows.push(vec![Value::String(table_name)]);
                 }
 
-                let result_set = ResultSet {
-                    columns: vec!["Tables".to_string()],
-                    rows: result_rows,
-                };
+                let result_set = ResultSet::new(vec![tables_column(database)], result_rows);
 
                 Ok(QueryResult::ResultSet(result_set))
             }
             Plan::DescribeTable { name } => {
                 // 获取表的列定义
-                let table_columns = self.storage.get_table_columns(name)?;
+                let table_columns = self.storage().get_table_columns(name)?;
 
-                // 创建结果集
-                let mut result_rows = Vec::new();
-                for column in &table_columns {
-                    let row = vec![
-                        Value::String(column.name.clone()),
-                        Value::String(column.data_type.to_string()),
-                        Value::Boolean(column.not_null),
-                        Value::Boolean(column.is_primary),
-                        Value::Boolean(column.unique),
-                    ];
-                    result_rows.push(row);
-                }
-
-                let result_set = ResultSet {
-                    columns: vec![
-                        "Column".to_string(),
+                // 每列一行：Field, Type, Null, Key, Default
+                let result_rows = table_columns
+                    .iter()
+                    .map(|column| {
+                        let null = if column.not_null { "NO" } else { "YES" };
+                        let key = if column.is_primary {
+                            "PRI"
+                        } else if column.unique {
+                            "UNI"
+                        } else if column.references.is_some() {
+                            "MUL"
+                        } else {
+                            ""
+                        };
+                        vec![
+                            Value::String(column.name.clone()),
+                            Value::String(column.data_type.to_string()),
+                            Value::String(null.to_string()),
+                            Value::String(key.to_string()),
+                            match &column.default {
+                                None | Some(Value::Null) => Value::Null,
+                                Some(default) => Value::String(default.to_string()),
+                            },
+                        ]
+                    })
+                    .collect();
+
+                let mut result_set = ResultSet::new(
+                    vec![
+                        "Field".to_string(),
                         "Type".to_string(),
-                        "Not Null".to_string(),
-                        "Is Primary".to_string(),
-                        "Unique".to_string(),
+                        "Null".to_string(),
+                        "Key".to_string(),
+                        "Default".to_string(),
                     ],
-                    rows: result_rows,
-                };
+                    result_rows,
+                );
+                result_set.comment = Some(match Table::estimate_max_row_size(&table_columns) {
+                    Some(size) => format!(
+                        "最坏情况行大小: {} 字节（单页上限 {} 字节）",
+                        size, MAX_RECORD_SIZE
+                    ),
+                    None => "最坏情况行大小: 无上界（存在未声明长度的 VARCHAR）".to_string(),
+                });
 
                 Ok(QueryResult::ResultSet(result_set))
             }
-        }
-    }
+            Plan::ShowCreateTable { name } => {
+                let table = self.storage().get_table(name)?;
+                let checks = self.storage().get_table_checks(name)?;
+                let statement = show_create_table(name, table.columns(), table.indexes(), &checks);
 
-    /// 验证值类型是否与列定义匹配
-    fn validate_value_type(&self, value: &Value, data_type: &DataType) -> Result<()> {
-        match (value, data_type) {
-            (Value::Int(_), DataType::Int(_)) => Ok(()),
-            (Value::String(s), DataType::Varchar(max_len)) => {
-                if s.len() > *max_len as usize {
-                    Err(DBError::Schema(format!(
-                        "字符串长度({})超过了VARCHAR({})的限制",
-                        s.len(),
-                        max_len
-                    )))
-                } else {
-                    Ok(())
+                Ok(QueryResult::ResultSet(ResultSet::new(
+                    vec!["Table".to_string(), "Create Table".to_string()],
+                    vec![vec![Value::String(name.clone()), Value::String(statement)]],
+                )))
+            }
+            Plan::ShowWarnings => {
+                let rows = self
+                    .session
+                    .warnings()
+                    .iter()
+                    .map(|warning| {
+                        vec![
+                            Value::String(warning.level.to_string()),
+                            Value::Int(warning.code as i32),
+                            Value::String(warning.message.clone()),
+                        ]
+                    })
+                    .collect();
+
+                Ok(QueryResult::ResultSet(ResultSet::new(
+                    vec![
+                        "Level".to_string(),
+                        "Code".to_string(),
+                        "Message".to_string(),
+                    ],
+                    rows,
+                )))
+            }
+            Plan::SetVariable { assignments } => {
+                // 先检查全部赋值，任何一个出错都不修改变量
+                let mut resolved = Vec::new();
+                for (name, value) in assignments {
+                    let Some(spec) = VariableSpec::lookup(name) else {
+                        let message = format!("未知的系统变量 '{}'", name);
+                        if self.session.is_strict() {
+                            return Err(DBError::Execution(message));
+                        }
+                        self.session.add_warning(WarningLevel::Warning, 1193, message);
+                        continue;
+                    };
+                    resolved.push((spec, spec.parse(value.as_ref())?));
                 }
+                for (spec, value) in resolved {
+                    self.session.set_variable(spec, value);
+                }
+                Ok(QueryResult::Success)
             }
-            (Value::Null, _) => {
-                // NULL 值总是被接受，具体的 NOT NULL 约束在 get_default_value 中处理
-                Ok(())
+            Plan::ShowVariables { pattern } => {
+                let pattern = pattern
+                    .as_deref()
+                    .map(|pattern| LikePattern::new(pattern, Some(DEFAULT_LIKE_ESCAPE), true));
+                let rows = self
+                    .session
+                    .variables()
+                    .filter(|(name, _)| pattern.as_ref().is_none_or(|p| p.matches(name)))
+                    .map(|(name, value)| {
+                        vec![Value::String(name.to_string()), Value::String(value.to_string())]
+                    })
+                    .collect();
+
+                Ok(QueryResult::ResultSet(ResultSet::new(
+                    vec!["Variable_name".to_string(), "Value".to_string()],
+                    rows,
+                )))
+            }
+            Plan::Begin => {
+                self.storage_mut()?.begin_transaction()?;
+                Ok(QueryResult::Success)
+            }
+            Plan::Commit => {
+                self.storage_mut()?.commit_transaction();
+                Ok(QueryResult::Success)
+            }
+            Plan::Rollback => {
+                self.storage_mut()?.rollback_transaction()?;
+                Ok(QueryResult::Success)
             }
-            _ => Err(DBError::Schema(format!(
-                "值类型 {:?} 与列类型 {:?} 不匹配",
-                value, data_type
-            ))),
         }
     }
 
-    /// 投影列（正确处理通配符）
-    fn project_columns(
-        &self,
-        records: &[Record],
-        select_columns: &SelectColumns,
-        table_columns: &[ColumnDef],
-    ) -> Result<Vec<Vec<Value>>> {
-        let mut result_rows = Vec::new();
+    /// 执行单个 SELECT；`top_level` 为 false 时作为 UNION 的分支，不受 max_result_rows 限制
+    fn execute_select(&mut self, plan: &Plan, top_level: bool) -> Result<QueryResult> {
+        let Plan::Select {
+            table_name,
+            columns,
+            distinct,
+            conditions,
+            order_by,
+            table_alias,
+            joins,
+            group_by,
+            having,
+            limit,
+            offset,
+            into,
+        } = plan
+        else {
+            unreachable!("execute_select 只处理 SELECT 计划");
+        };
+        // 先把通配符展开成表的各列，之后按普通的列列表执行
+        if let SelectColumns::Mixed(_) = columns
+            && let Some(table_name) = table_name
+        {
+            let mut expanded = plan.clone();
+            if let Plan::Select {
+                columns: target, ..
+            } = &mut expanded
+            {
+                *target =
+                    self.expand_wildcards(columns, table_name, table_alias.as_deref(), joins)?;
+            }
+            return self.execute_select(&expanded, top_level);
+        }
+        validate::validate_select(columns, group_by, having.as_ref())?;
+        let aggregated = validate::is_aggregate_query(columns, group_by, having.as_ref());
+
+        // 处理无表查询（如 SELECT 1+1）
+        let (skip, mut take) = limit_window(*limit, *offset);
+        // max_result_rows 只限制返回给客户端的结果，不影响 SELECT ... INTO
+        if top_level
+            && into.is_none()
+            && let Some(max_rows) = self.session.max_result_rows()
+        {
+            take = take.min(max_rows);
+        }
+        if table_name.is_none() {
+            let mut result_set = self.execute_expression_select(columns)?;
+            result_set.rows = result_set.rows.into_iter().skip(skip).take(take).collect();
+            return match into {
+                Some(target) => self.write_result_into(target, result_set),
+                None => Ok(QueryResult::ResultSet(result_set)),
+            };
+        }
+
+        let table_name = table_name
+            .as_ref()
+            .ok_or(DBError::Execution("SELECT 查询必须指定表名".to_string()))?;
+        let outer_tables: Vec<&String> = std::iter::once(table_name)
+            .chain(joins.iter().map(|join| &join.table_name))
+            .collect();
+        let conditions = &self.resolve_subqueries(conditions.as_ref(), &outer_tables)?;
+
+        // 获取表的列定义和要过滤的记录（会话查询历史是只存在于内存中的伪表）
+        let (table_columns, source) = if !joins.is_empty() {
+            let (table_columns, records) =
+                self.join_tables(table_name, table_alias.as_deref(), joins)?;
+            (table_columns, RecordSource::Loaded(records))
+        } else if table_name == QUERY_HISTORY_TABLE {
+            let (table_columns, records) = self.query_history_table();
+            (table_columns, RecordSource::Loaded(records))
+        } else {
+            // WHERE 限定的主键范围足够窄时按主键索引取行，其余条件仍在下面过滤
+            let table_columns = self.storage().get_table_columns(table_name)?;
+            let access = self.key_access(table_name, conditions.as_ref(), &table_columns)?;
+            // 只读取查询用到的列，列定义按同样的位置裁剪，之后按列名求值的结果不变
+            let required = optimizer::required_columns(plan, &table_columns);
+            let source = match access {
+                Some(KeyAccess { range, estimate }) if estimate.path == AccessPath::IndexLookup => {
+                    RecordSource::Loaded(
+                        self.storage()
+                            .find_by_primary_key_range(
+                                table_name,
+                                range.low.as_ref(),
+                                range.high.as_ref(),
+                            )?
+                            .into_iter()
+                            .map(|record| match &required {
+                                Some(positions) => record.project(positions),
+                                None => Ok(record),
+                            })
+                            .collect::<Result<_>>()?,
+                    )
+                }
+                _ => RecordSource::Scan(required.clone()),
+            };
+            let table_columns = match required {
+                Some(positions) => positions
+                    .iter()
+                    .map(|&position| table_columns[position].clone())
+                    .collect(),
+                None => table_columns,
+            };
+            (table_columns, source)
+        };
+
+        // 逐条求值之前先解析所有引用的列，空表也报告找不到的列
+        validate::check_select_columns(
+            columns,
+            conditions.as_ref(),
+            order_by.as_deref().filter(|_| !aggregated),
+            &table_columns,
+        )?;
+
+        // 应用WHERE条件过滤，扫描整张表时边读边过滤，不满足条件的记录不会留在内存中
+        let records = match source {
+            RecordSource::Loaded(records) => {
+                self.filter_records(records, conditions.as_ref(), &table_columns)?
+            }
+            RecordSource::Scan(positions) => self.scan_filtered(
+                table_name,
+                positions.as_deref(),
+                conditions.as_ref(),
+                &table_columns,
+            )?,
+        };
+
+        // 生成结果列名
+        let result_columns = self.generate_result_columns(columns, &table_columns)?;
+
+        // 分组聚合后按结果列排序和截取
+        if aggregated && let SelectColumns::Columns(items) = columns {
+            let rows = aggregate::aggregate(
+                records,
+                &table_columns,
+                items,
+                group_by,
+                having.as_ref(),
+                self.session.sql_mode(),
+            )?;
+            let spec = match order_by {
+                Some(order_items) => sort::resolve_result_sort_keys(order_items, &result_columns)?,
+                None => sort::SortSpec::default(),
+            };
+            let rows = self.sort_rows(rows, spec, RowWindow::new(skip, take, *distinct))?;
+            let result_set = ResultSet::new(result_columns, rows);
+            return match into {
+                Some(target) => self.write_result_into(target, result_set),
+                None => Ok(QueryResult::ResultSet(result_set)),
+            };
+        }
+
+        // 应用ORDER BY排序，排好序的记录逐条送入投影
+        let mut window = RowWindow::new(skip, take, *distinct);
+        if let Some(order_items) = order_by {
+            // 表达式和别名的排序值追加在记录末尾参与排序，投影前去掉
+            let spec = sort::resolve_sort_keys(order_items, &table_columns, columns)?;
+            let mut sorter = sort::ExternalSorter::new(
+                spec.keys.clone(),
+                self.session.sort_buffer_size(),
+                self.storage().get_base_dir(),
+            );
+            for record in records {
+                self.budget.check()?;
+                sorter.push(spec.prepare(record, self.session.sql_mode())?)?;
+            }
+            // 跳过的记录也要检查读取错误，排序结果只取到 OFFSET + LIMIT 为止
+            let mut sorted = sorter.finish()?;
+            while !window.is_full()
+                && let Some(record) = sorted.next()
+            {
+                self.budget.check()?;
+                let record = spec.strip(record?);
+                if window.skips_next() {
+                    window.skip_row();
+                } else {
+                    window.push(self.project_row(&record, columns, &table_columns)?);
+                }
+            }
+        } else {
+            // 没有 ORDER BY 时按存储顺序截取
+            for record in &records {
+                if window.is_full() {
+                    break;
+                }
+                self.budget.check()?;
+                if window.skips_next() {
+                    window.skip_row();
+                } else {
+                    window.push(self.project_row(record, columns, &table_columns)?);
+                }
+            }
+        }
 
+        // 创建结果集
+        let result_set = ResultSet::new(result_columns, window.into_rows());
+
+        if let Some(target) = into {
+            return self.write_result_into(target, result_set);
+        }
+
+        Ok(QueryResult::ResultSet(result_set))
+    }
+
+    /// 先执行 WHERE 条件中的子查询，返回子查询替换为结果值之后的条件
+    ///
+    /// 每个子查询只执行一次，所以不支持引用外层查询（`outer_tables`）列的相关子查询。
+    fn resolve_subqueries(
+        &mut self,
+        conditions: Option<&Condition>,
+        outer_tables: &[&String],
+    ) -> Result<Option<Condition>> {
+        let Some(condition) = conditions else {
+            return Ok(None);
+        };
+        let mut condition = condition.clone();
+        if !condition.subqueries().is_empty() {
+            let outer: Vec<ColumnDef> = outer_tables
+                .iter()
+                .filter_map(|name| self.storage().get_table_columns(name).ok())
+                .flatten()
+                .collect();
+            subquery::resolve(&mut condition, &mut |plan| self.run_subquery(plan, &outer))?;
+        }
+        Ok(Some(condition))
+    }
+
+    /// 执行一个子查询，返回它的结果集
+    fn run_subquery(&mut self, plan: &Plan, outer: &[ColumnDef]) -> Result<ResultSet> {
+        if let Some(column) = self.outer_reference(plan, outer)? {
+            return Err(DBError::Planner(format!(
+                "暂不支持相关子查询：子查询中的列 '{}' 引用了外层查询",
+                column
+            )));
+        }
+        let result = match plan {
+            Plan::Union { .. } => self.execute_union(plan, false)?,
+            _ => self.execute_select(plan, false)?,
+        };
+        match result {
+            QueryResult::ResultSet(result_set) => Ok(result_set),
+            other => Err(DBError::Execution(format!(
+                "子查询没有返回结果集: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// 单表子查询中第一个只在外层查询的表中存在的列，没有时返回 `None`
+    fn outer_reference(&self, plan: &Plan, outer: &[ColumnDef]) -> Result<Option<String>> {
+        let Plan::Select {
+            table_name: Some(table_name),
+            columns,
+            conditions,
+            joins,
+            ..
+        } = plan
+        else {
+            return Ok(None);
+        };
+        // 子查询的表不存在时由执行子查询报告
+        let Ok(inner) = self.storage().get_table_columns(table_name) else {
+            return Ok(None);
+        };
+        if !joins.is_empty() {
+            return Ok(None);
+        }
+
+        let mut unknown = validate::UnknownColumns::default();
+        if let SelectColumns::Columns(items) = columns {
+            for item in items {
+                unknown.check_expression(&item.expr, &inner)?;
+            }
+        }
+        if let Some(condition) = conditions {
+            unknown.check_condition(condition, &inner)?;
+        }
+        Ok(unknown
+            .names()
+            .iter()
+            .find(|name| {
+                let column = name
+                    .rsplit_once('.')
+                    .map_or(name.as_str(), |(_, column)| column);
+                column_position(outer, column).is_some()
+            })
+            .cloned())
+    }
+
+    /// 逐行检查执行限制并按 WHERE 条件过滤记录，没有条件时保留全部记录
+    ///
+    /// 条件按会话的 SQL 模式求值；任何一行求值出错（如整数溢出、除以零、
+    /// 严格模式下布尔值与整数比较）时整条语句失败，不会把该行当作不匹配跳过。
+    fn filter_records(
+        &mut self,
+        records: Vec<Record>,
+        condition: Option<&Condition>,
+        columns: &[ColumnDef],
+    ) -> Result<Vec<Record>> {
+        let mode = self.session.sql_mode();
+        let mut kept = Vec::new();
         for record in records {
-            let mut row = Vec::new();
+            self.budget.examine()?;
+            let matched = match condition {
+                Some(condition) => condition.evaluate(&record, columns, mode)?,
+                None => true,
+            };
+            if matched {
+                kept.push(record);
+            }
+        }
+        Ok(kept)
+    }
 
-            match select_columns {
-                SelectColumns::Wildcard => {
-                    // 通配符，添加所有列
-                    for value in record.values() {
-                        row.push(value.clone());
-                    }
+    /// 扫描整张表，边读边按条件过滤，每读一行计入扫描行数
+    fn scan_filtered(
+        &mut self,
+        table_name: &str,
+        positions: Option<&[usize]>,
+        condition: Option<&Condition>,
+        columns: &[ColumnDef],
+    ) -> Result<Vec<Record>> {
+        let mode = self.session.sql_mode();
+        let budget = &mut self.budget;
+        let mut kept = Vec::new();
+        self.storage
+            .get()
+            .scan_records(table_name, positions, |record| {
+                budget.examine()?;
+                let matched = match condition {
+                    Some(condition) => condition.evaluate(&record, columns, mode)?,
+                    None => true,
+                };
+                if matched {
+                    kept.push(record);
                 }
-                SelectColumns::Columns(items) => {
-                    // 处理具体的列
-                    for item in items {
-                        let value = item.expr.evaluate(record, table_columns)?;
-                        row.push(value);
-                    }
+                Ok(())
+            })?;
+        Ok(kept)
+    }
+
+    /// 执行 UNION / UNION ALL：两侧结果按位置合并，UNION 去重后再整体排序和截取
+    fn execute_union(&mut self, plan: &Plan, top_level: bool) -> Result<QueryResult> {
+        let Plan::Union {
+            left,
+            right,
+            all,
+            order_by,
+            limit,
+            offset,
+        } = plan
+        else {
+            unreachable!("execute_union 只处理 UNION 计划");
+        };
+
+        let left = self.union_branch(left)?;
+        let right = self.union_branch(right)?;
+        if left.columns.len() != right.columns.len() {
+            return Err(DBError::Execution(format!(
+                "UNION 两侧的列数不同（{} 和 {}）",
+                left.columns.len(),
+                right.columns.len()
+            )));
+        }
+
+        // 结果列名取左侧
+        let columns = left.columns;
+        let mut rows = left.rows;
+        rows.extend(right.rows);
+        if !*all {
+            // 保留每组重复行中第一次出现的那一行
+            let mut seen = BTreeSet::new();
+            rows.retain(|row| seen.insert(row.iter().cloned().map(ValueKey).collect::<Vec<_>>()));
+        }
+
+        let (skip, mut take) = limit_window(*limit, *offset);
+        if top_level && let Some(max_rows) = self.session.max_result_rows() {
+            take = take.min(max_rows);
+        }
+        let spec = match order_by {
+            Some(order_items) => sort::resolve_result_sort_keys(order_items, &columns)?,
+            None => sort::SortSpec::default(),
+        };
+        let rows = self.sort_rows(rows, spec, RowWindow::new(skip, take, false))?;
+
+        Ok(QueryResult::ResultSet(ResultSet::new(columns, rows)))
+    }
+
+    /// 按排序键排序结果行（没有排序键时保持原顺序），再放入 OFFSET / LIMIT 窗口
+    fn sort_rows(
+        &self,
+        rows: Vec<Vec<Value>>,
+        spec: sort::SortSpec,
+        mut window: RowWindow,
+    ) -> Result<Vec<Vec<Value>>> {
+        if spec.keys.is_empty() {
+            for row in rows {
+                if window.is_full() {
+                    break;
                 }
+                window.push(row);
             }
+            return Ok(window.into_rows());
+        }
+        let mut sorter = sort::ExternalSorter::new(
+            spec.keys.clone(),
+            self.session.sort_buffer_size(),
+            self.storage().get_base_dir(),
+        );
+        for row in rows {
+            sorter.push(spec.prepare(Record::new(row), self.session.sql_mode())?)?;
+        }
+        let mut sorted = sorter.finish()?;
+        while !window.is_full()
+            && let Some(record) = sorted.next()
+        {
+            window.push(spec.strip(record?).into_raw_data());
+        }
+        Ok(window.into_rows())
+    }
+
+    /// 执行 UNION 的一个分支，返回其结果集
+    fn union_branch(&mut self, plan: &Plan) -> Result<ResultSet> {
+        let result = match plan {
+            Plan::Select { into: Some(_), .. } => {
+                return Err(DBError::Execution(
+                    "UNION 的分支中不能使用 INTO".to_string(),
+                ));
+            }
+            Plan::Select { .. } => self.execute_select(plan, false)?,
+            Plan::Union { .. } => self.execute_union(plan, false)?,
+            _ => return Err(DBError::Execution("UNION 的分支必须是查询".to_string())),
+        };
+        match result {
+            QueryResult::ResultSet(result_set) => Ok(result_set),
+            _ => Err(DBError::Execution("UNION 的分支必须是查询".to_string())),
+        }
+    }
+
+    /// 把会话查询历史转换为伪表的列定义和记录
+    fn query_history_table(&self) -> (Vec<ColumnDef>, Vec<Record>) {
+        let columns = query_history_columns();
+
+        let records = self
+            .session
+            .query_history()
+            .iter()
+            .map(|entry| {
+                let started_at = entry
+                    .started_at
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                Record::new(vec![
+                    Value::String(entry.statement.clone()),
+                    Value::Int(started_at.min(i32::MAX as u64) as i32),
+                    Value::Int(entry.duration.as_millis().min(i32::MAX as u128) as i32),
+                    Value::Int(entry.rows.min(i32::MAX as usize) as i32),
+                    Value::Int(entry.success as i32),
+                    Value::Int(entry.forbidden as i32),
+                ])
+            })
+            .collect();
+
+        (columns, records)
+    }
+
+    /// 单表 SELECT 的 WHERE 限定了主键范围时，估算范围内的行所占比例并选择取行方式
+    ///
+    /// 命中比例低于会话变量 `index_selectivity_threshold` 时按主键索引取行，否则全表扫描；
+    /// 会话变量 `access_path` 可以强制其中一种。WHERE 没有限定主键或表没有主键时返回 None。
+    fn key_access(
+        &self,
+        table_name: &str,
+        condition: Option<&Condition>,
+        columns: &[ColumnDef],
+    ) -> Result<Option<KeyAccess>> {
+        let Some(range) = condition.and_then(|condition| primary_key_lookup(condition, columns))
+        else {
+            return Ok(None);
+        };
+        let Some(stats) = self.storage().primary_key_stats(table_name)? else {
+            return Ok(None);
+        };
+        let estimator = CostEstimator::new(self.session.index_selectivity_threshold())
+            .with_hint(self.session.access_path_hint());
+        let estimate = estimator.choose(&stats.into(), &range.predicate());
+        Ok(Some(KeyAccess { range, estimate }))
+    }
+
+    /// EXPLAIN 中 SELECT / UPDATE / DELETE 的取行方式，与实际执行时的选择一致
+    ///
+    /// 只有单表 SELECT 的条件限定了主键范围时才可能按主键索引取行，这时同时给出估算的命中比例，
+    /// 见 [`Executor::key_access`]；其余都是全表扫描。
+    fn access_method(&self, plan: &Plan) -> Option<String> {
+        let (table_name, joins, conditions) = match plan {
+            Plan::Select {
+                table_name: Some(table_name),
+                joins,
+                conditions,
+                ..
+            } => (table_name, joins.as_slice(), conditions),
+            Plan::Update { .. } | Plan::Delete { .. } => return Some("full scan".to_string()),
+            _ => return None,
+        };
+        if joins.is_empty()
+            && table_name != QUERY_HISTORY_TABLE
+            && let Ok(columns) = self.storage().get_table_columns(table_name)
+            && let Ok(Some(KeyAccess { range, estimate })) =
+                self.key_access(table_name, conditions.as_ref(), &columns)
+        {
+            let percent = estimate.selectivity * 100.0;
+            return Some(match estimate.path {
+                AccessPath::IndexLookup => format!(
+                    "primary key lookup ({}), estimated {:.1}% of rows",
+                    range, percent
+                ),
+                AccessPath::FullScan => format!(
+                    "full scan, primary key range ({}) estimated {:.1}% of rows",
+                    range, percent
+                ),
+            });
+        }
+        Some("full scan".to_string())
+    }
+
+    /// 检查表的最坏情况行大小是否超过单页容量
+    ///
+    /// 严格模式下直接拒绝建表，宽松模式下记录一条警告。
+    fn check_row_size(&mut self, name: &str, columns: &[ColumnDef]) -> Result<()> {
+        let Some(message) = validate::oversized_row_message(name, columns) else {
+            return Ok(());
+        };
+        if self.session.is_strict() {
+            return Err(DBError::Schema(message));
+        }
+        self.session.add_warning(WarningLevel::Warning, 1118, message);
+        Ok(())
+    }
+
+    /// 记录一个被截断到 VARCHAR 长度的值
+    fn warn_truncated(&mut self, column: &str, row: usize) {
+        let message = format!("Data truncated for column '{}' at row {}", column, row);
+        self.session.add_warning(WarningLevel::Warning, 1265, message);
+    }
 
-            result_rows.push(row);
+    /// INT 的显示宽度不限制取值范围（MySQL 8 已弃用），建表时给出提示
+    fn note_int_display_widths(&mut self, columns: &[ColumnDef]) {
+        for message in columns.iter().filter_map(validate::display_width_note) {
+            self.session.add_warning(WarningLevel::Note, 1681, message);
         }
+    }
+
+    /// 投影列（正确处理通配符）
+    fn project_columns(
+        &self,
+        records: &[Record],
+        select_columns: &SelectColumns,
+        table_columns: &[ColumnDef],
+    ) -> Result<Vec<Vec<Value>>> {
+        records
+            .iter()
+            .map(|record| self.project_row(record, select_columns, table_columns))
+            .collect()
+    }
 
-        Ok(result_rows)
+    /// 投影单条记录
+    fn project_row(
+        &self,
+        record: &Record,
+        select_columns: &SelectColumns,
+        table_columns: &[ColumnDef],
+    ) -> Result<Vec<Value>> {
+        match select_columns {
+            // 通配符，添加所有列；列数不符时结果列会错位
+            SelectColumns::Wildcard if record.values().len() != table_columns.len() => {
+                Err(DBError::Execution(format!(
+                    "记录有 {} 列，表结构有 {} 列：数据文件与表结构不一致，可用 .verify 检查",
+                    record.values().len(),
+                    table_columns.len()
+                )))
+            }
+            SelectColumns::Wildcard => Ok(record.values().to_vec()),
+            // 处理具体的列
+            SelectColumns::Columns(items) => items
+                .iter()
+                .map(|item| {
+                    item.expr
+                        .evaluate(record, table_columns, self.session.sql_mode())
+                })
+                .collect(),
+            SelectColumns::Mixed(_) => unreachable!("通配符应该在前面已经展开"),
+        }
     }
 
     /// 生成结果列名（正确处理通配符）
+    ///
+    /// 多个结果列的名称相同（不区分大小写）时产生警告，按名称取值只能取到第一列。
     fn generate_result_columns(
-        &self,
+        &mut self,
         select_columns: &SelectColumns,
         table_columns: &[ColumnDef],
     ) -> Result<Vec<String>> {
@@ -485,15 +1762,127 @@ impl<'a> Executor<'a> {
                     }
                 }
 
+                for (index, name) in result_columns.iter().enumerate() {
+                    if result_columns[..index]
+                        .iter()
+                        .any(|other| other.eq_ignore_ascii_case(name))
+                    {
+                        self.session.add_warning(
+                            WarningLevel::Warning,
+                            1060,
+                            format!("结果中有重复的列名 '{}'，按列名取值时只能取到第一列", name),
+                        );
+                    }
+                }
+
                 Ok(result_columns)
             }
+            SelectColumns::Mixed(_) => unreachable!("通配符应该在前面已经展开"),
+        }
+    }
+
+    /// 按 FROM 中各表的列展开选择列表中的通配符
+    ///
+    /// 多表连接时列名与 [`join_tables`](Self::join_tables) 的结果一样加上表名或别名限定。
+    fn expand_wildcards(
+        &self,
+        columns: &SelectColumns,
+        table_name: &str,
+        table_alias: Option<&str>,
+        joins: &[Join],
+    ) -> Result<SelectColumns> {
+        let sources: Vec<(&str, &str)> = std::iter::once((table_name, table_alias))
+            .chain(
+                joins
+                    .iter()
+                    .map(|join| (join.table_name.as_str(), join.alias.as_deref())),
+            )
+            .map(|(name, alias)| (name, alias.unwrap_or(name)))
+            .collect();
+        let mut table_columns = Vec::new();
+        for (name, qualifier) in &sources {
+            let columns = if *name == QUERY_HISTORY_TABLE {
+                query_history_columns()
+            } else {
+                self.storage().get_table_columns(name)?
+            };
+            table_columns.extend(columns.into_iter().map(|mut column| {
+                if !joins.is_empty() {
+                    column.name = format!("{}.{}", qualifier, column.name);
+                }
+                column
+            }));
+        }
+        let qualifiers: Vec<&str> = sources.iter().map(|(_, qualifier)| *qualifier).collect();
+        Ok(columns.expand(&table_columns, &qualifiers)?.into_owned())
+    }
+
+    /// 读取一张参与 JOIN 的表，列名加上表名或别名限定
+    fn qualified_table(
+        &mut self,
+        table_name: &str,
+        alias: Option<&str>,
+    ) -> Result<(Vec<ColumnDef>, Vec<Record>)> {
+        let qualifier = alias.unwrap_or(table_name);
+        let (columns, records) = if table_name == QUERY_HISTORY_TABLE {
+            self.query_history_table()
+        } else {
+            (
+                self.storage().get_table_columns(table_name)?,
+                self.storage().get_all_records(table_name)?,
+            )
+        };
+        let columns = columns
+            .into_iter()
+            .map(|mut column| {
+                column.name = format!("{}.{}", qualifier, column.name);
+                column
+            })
+            .collect();
+        Ok((columns, records))
+    }
+
+    /// 嵌套循环 INNER JOIN，返回合并后的列定义和记录
+    ///
+    /// 合并后的记录依次包含各表的值，列名形如 `别名.列名`。
+    fn join_tables(
+        &mut self,
+        table_name: &str,
+        table_alias: Option<&str>,
+        joins: &[Join],
+    ) -> Result<(Vec<ColumnDef>, Vec<Record>)> {
+        let (mut columns, mut records) = self.qualified_table(table_name, table_alias)?;
+
+        for join in joins {
+            let (join_columns, join_records) =
+                self.qualified_table(&join.table_name, join.alias.as_deref())?;
+            columns.extend(join_columns);
+
+            let mut joined = Vec::new();
+            for left in &records {
+                for right in &join_records {
+                    self.budget.check()?;
+                    let mut values = left.values().to_vec();
+                    values.extend_from_slice(right.values());
+                    let record = Record::new(values);
+                    if join
+                        .on
+                        .evaluate(&record, &columns, self.session.sql_mode())?
+                    {
+                        joined.push(record);
+                    }
+                }
+            }
+            records = joined;
         }
+
+        Ok((columns, records))
     }
 
     /// 处理无表查询（如 SELECT 1+1, 'hello'）
-    fn execute_expression_select(&self, columns: &SelectColumns) -> Result<QueryResult> {
+    fn execute_expression_select(&self, columns: &SelectColumns) -> Result<ResultSet> {
         match columns {
-            SelectColumns::Wildcard => {
+            SelectColumns::Wildcard | SelectColumns::Mixed(_) => {
                 Err(DBError::Execution("无表查询不支持通配符 *".to_string()))
             }
             SelectColumns::Columns(items) => {
@@ -506,7 +1895,11 @@ impl<'a> Executor<'a> {
 
                 // 对每个表达式进行求值
                 for item in items {
-                    let value = item.expr.evaluate(&empty_record, &empty_columns)?;
+                    let value = item.expr.evaluate(
+                        &empty_record,
+                        &empty_columns,
+                        self.session.sql_mode(),
+                    )?;
                     result_row.push(value);
 
                     // 生成列名
@@ -517,95 +1910,460 @@ impl<'a> Executor<'a> {
                     }
                 }
 
-                let result_set = ResultSet {
-                    columns: result_columns,
-                    rows: vec![result_row], // 无表查询只返回一行
-                };
+                // 无表查询只返回一行
+                Ok(ResultSet::new(result_columns, vec![result_row]))
+            }
+        }
+    }
 
-                Ok(QueryResult::ResultSet(result_set))
+    /// 将查询结果写入新表（SELECT ... INTO、CREATE TABLE ... AS SELECT）
+    ///
+    /// 目标表的列类型由结果中的值推断；写入失败时删除已创建的目标表，
+    /// 保证语句要么完整生效，要么不留下任何痕迹。
+    ///
+    /// `CREATE OR REPLACE TABLE ... AS SELECT` 覆盖已存在的表：结果先写入一张临时表，
+    /// 全部写入成功后把原表改名让开、临时表改成原表的名字，最后才删除原表；
+    /// 任何一步失败都把原表改回原来的名字，原表保持不变。
+    fn write_result_into(
+        &mut self,
+        into: &SelectInto,
+        result_set: ResultSet,
+    ) -> Result<QueryResult> {
+        let target = into.table.as_str();
+        ensure_writable(target)?;
+        let replaced = self.storage().get_table(target).is_ok();
+        if replaced {
+            if !into.overwrite {
+                return Err(DBError::Schema(format!("表 '{}' 已存在", target)));
+            }
+            // 事务的撤销记录按表名记录，不能跟着临时表改名
+            if self.storage().in_transaction() {
+                return Err(DBError::Execution(
+                    "事务中不能执行 CREATE OR REPLACE TABLE，请先 COMMIT 或 ROLLBACK".to_string(),
+                ));
+            }
+            foreign_key::check_drop_table(self.storage(), target, &[target.to_string()])?;
+        }
+
+        let columns = infer_column_defs(&result_set)?;
+        let staging = if replaced {
+            format!("{}{}", target, OVERWRITE_STAGING_SUFFIX)
+        } else {
+            target.to_string()
+        };
+        self.storage_mut()?.create_table(staging.clone(), columns)?;
+
+        let row_count = result_set.rows.len();
+        for row in result_set.rows {
+            if let Err(e) = self.storage_mut()?.insert_record(&staging, row) {
+                self.storage_mut()?.drop_table(&staging)?;
+                return Err(e);
             }
         }
+
+        if replaced {
+            self.swap_in_staging(target, &staging)?;
+        }
+
+        Ok(QueryResult::Message(format!(
+            "{} rows written to {}",
+            row_count, target
+        )))
     }
 
-    /// 对记录进行排序
-    fn sort_records(
-        &self,
-        records: &mut [Record],
-        order_items: &[super::planner::OrderByItem],
-        table_columns: &[ColumnDef],
-    ) -> Result<()> {
-        use std::cmp::Ordering;
+    /// 用写好的临时表替换目标表
+    ///
+    /// 先把原表改名让开，再把临时表改成原表的名字，成功后才删除原表；
+    /// 改名失败时把原表改回来并删除临时表。
+    fn swap_in_staging(&mut self, target: &str, staging: &str) -> Result<()> {
+        // rename_table 的新名字不带数据库限定
+        let name = target.split_once('.').map_or(target, |(_, table)| table);
+        let aside_name = format!("{}{}", name, OVERWRITE_REPLACED_SUFFIX);
+        let aside = format!("{}{}", target, OVERWRITE_REPLACED_SUFFIX);
+        let storage = self.storage_mut()?;
 
-        records.sort_by(|a, b| {
-            for order_item in order_items {
-                // 找到排序列的索引
-                let column_idx = table_columns
-                    .iter()
-                    .position(|col| col.name == order_item.column)
-                    .ok_or_else(|| {
-                        DBError::Execution(format!("排序列 '{}' 不存在", order_item.column))
-                    });
-
-                let column_idx = match column_idx {
-                    Ok(idx) => idx,
-                    Err(_) => continue, // 跳过不存在的列
-                };
+        if let Err(e) = storage.rename_table(target, &aside_name) {
+            storage.drop_table(staging)?;
+            return Err(e);
+        }
+        if let Err(e) = storage.rename_table(staging, name) {
+            storage.rename_table(&aside, name)?;
+            storage.drop_table(staging)?;
+            return Err(e);
+        }
+        // 新表已经就位，删除原表失败只留下一张改过名的旧表，不影响语句的结果
+        if let Err(e) = storage.drop_table(&aside) {
+            log::warn!("删除被替换的表 '{}' 失败: {}", aside, e);
+        }
+        Ok(())
+    }
+}
 
-                let val_a = &a.values()[column_idx];
-                let val_b = &b.values()[column_idx];
+/// `CREATE OR REPLACE TABLE` 覆盖已存在的表时，临时表名在目标表名后加上的后缀
+const OVERWRITE_STAGING_SUFFIX: &str = "#overwrite";
 
-                let cmp_result = self.compare_values(val_a, val_b);
+/// `CREATE OR REPLACE TABLE` 替换表的过程中，原表暂时改成的名字的后缀
+const OVERWRITE_REPLACED_SUFFIX: &str = "#replaced";
 
-                let final_result = match order_item.direction {
-                    super::planner::SortDirection::Asc => cmp_result,
-                    super::planner::SortDirection::Desc => cmp_result.reverse(),
-                };
+/// OFFSET / LIMIT 窗口：按顺序接收结果行，跳过前 `skip` 行后最多保留 `take` 行
+///
+/// DISTINCT 时重复的行不计数，只保留每组重复行中第一次出现的那一行，
+/// 因此去重发生在排序之后、截取之前。
+struct RowWindow {
+    skip: usize,
+    take: usize,
+    /// 已经计数的行数，包括跳过的行
+    counted: usize,
+    /// DISTINCT 时已经出现过的行
+    seen: Option<BTreeSet<Vec<ValueKey>>>,
+    rows: Vec<Vec<Value>>,
+}
 
-                if final_result != Ordering::Equal {
-                    return final_result;
-                }
-            }
+impl RowWindow {
+    fn new(skip: usize, take: usize, distinct: bool) -> Self {
+        Self {
+            skip,
+            take,
+            counted: 0,
+            seen: distinct.then(BTreeSet::new),
+            rows: Vec::new(),
+        }
+    }
 
-            Ordering::Equal
-        });
+    /// 已经收满 OFFSET + LIMIT 行
+    fn is_full(&self) -> bool {
+        self.counted >= self.skip.saturating_add(self.take)
+    }
 
-        Ok(())
+    /// 不去重时下一行会被跳过，调用方可以不投影，直接调用 [`skip_row`](Self::skip_row)
+    fn skips_next(&self) -> bool {
+        self.seen.is_none() && self.counted < self.skip
+    }
+
+    fn skip_row(&mut self) {
+        self.counted += 1;
+    }
+
+    fn push(&mut self, row: Vec<Value>) {
+        if let Some(seen) = &mut self.seen
+            && !seen.insert(row.iter().cloned().map(ValueKey).collect())
+        {
+            return;
+        }
+        if self.counted >= self.skip {
+            self.rows.push(row);
+        }
+        self.counted += 1;
+    }
+
+    fn into_rows(self) -> Vec<Vec<Value>> {
+        self.rows
     }
+}
 
-    /// 比较两个值
-    fn compare_values(&self, a: &Value, b: &Value) -> std::cmp::Ordering {
-        use std::cmp::Ordering;
+/// 把 LIMIT / OFFSET 换算为 (跳过的行数, 最多返回的行数)
+fn limit_window(limit: Option<u64>, offset: Option<u64>) -> (usize, usize) {
+    let to_usize = |n: u64| usize::try_from(n).unwrap_or(usize::MAX);
+    (
+        offset.map_or(0, to_usize),
+        limit.map_or(usize::MAX, to_usize),
+    )
+}
 
-        match (a, b) {
-            // NULL 值处理：NULL < 任何非 NULL 值
-            (Value::Null, Value::Null) => Ordering::Equal,
-            (Value::Null, _) => Ordering::Less,
-            (_, Value::Null) => Ordering::Greater,
+/// WHERE 中主键列的取值范围：顶层或顶层 AND 中各项 `主键 比较 常量` 的交集
+///
+/// 满足整个 WHERE 的行一定在这个范围内，所以只需取出范围内的行再过滤。
+#[derive(Debug, Clone, PartialEq)]
+struct KeyRange {
+    column: String,
+    low: Bound<Value>,
+    high: Bound<Value>,
+}
 
-            // 整数比较
-            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+impl KeyRange {
+    /// 用一个下界收紧范围，相同的端点上开区间更紧
+    fn raise_low(&mut self, bound: Bound<Value>) {
+        let tighter = match (&self.low, &bound) {
+            (Bound::Unbounded, _) => true,
+            (Bound::Included(current) | Bound::Excluded(current), Bound::Included(new)) => {
+                new.total_cmp(current) == Ordering::Greater
+            }
+            (Bound::Included(current) | Bound::Excluded(current), Bound::Excluded(new)) => {
+                new.total_cmp(current) != Ordering::Less
+            }
+            (_, Bound::Unbounded) => false,
+        };
+        if tighter {
+            self.low = bound;
+        }
+    }
 
-            // 浮点数比较
-            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+    /// 用一个上界收紧范围，相同的端点上开区间更紧
+    fn lower_high(&mut self, bound: Bound<Value>) {
+        let tighter = match (&self.high, &bound) {
+            (Bound::Unbounded, _) => true,
+            (Bound::Included(current) | Bound::Excluded(current), Bound::Included(new)) => {
+                new.total_cmp(current) == Ordering::Less
+            }
+            (Bound::Included(current) | Bound::Excluded(current), Bound::Excluded(new)) => {
+                new.total_cmp(current) != Ordering::Greater
+            }
+            (_, Bound::Unbounded) => false,
+        };
+        if tighter {
+            self.high = bound;
+        }
+    }
 
-            // 混合数值比较
-            (Value::Int(a), Value::Float(b)) => {
-                (*a as f64).partial_cmp(b).unwrap_or(Ordering::Equal)
+    /// 代价估算用的谓词，开区间的端点按闭区间估算
+    fn predicate(&self) -> Predicate {
+        let value = |bound: &Bound<Value>| match bound {
+            Bound::Included(value) | Bound::Excluded(value) => Some(value.clone()),
+            Bound::Unbounded => None,
+        };
+        match (&self.low, &self.high) {
+            (Bound::Included(low), Bound::Included(high)) if low.total_cmp(high).is_eq() => {
+                Predicate::Equal(low.clone())
             }
-            (Value::Float(a), Value::Int(b)) => {
-                a.partial_cmp(&(*b as f64)).unwrap_or(Ordering::Equal)
+            (low, high) => Predicate::Range {
+                low: value(low),
+                high: value(high),
+            },
+        }
+    }
+}
+
+impl fmt::Display for KeyRange {
+    /// 与 WHERE 中的写法相同，如 `id = 2`、`id > 1 AND id <= 5`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let literal = |value: &Value| Expression::Value(value.clone());
+        if let Predicate::Equal(value) = self.predicate() {
+            return write!(f, "{} = {}", self.column, literal(&value));
+        }
+        let mut parts = Vec::new();
+        match &self.low {
+            Bound::Included(value) => parts.push(format!("{} >= {}", self.column, literal(value))),
+            Bound::Excluded(value) => parts.push(format!("{} > {}", self.column, literal(value))),
+            Bound::Unbounded => {}
+        }
+        match &self.high {
+            Bound::Included(value) => parts.push(format!("{} <= {}", self.column, literal(value))),
+            Bound::Excluded(value) => parts.push(format!("{} < {}", self.column, literal(value))),
+            Bound::Unbounded => {}
+        }
+        write!(f, "{}", parts.join(" AND "))
+    }
+}
+
+/// 单表 SELECT 的主键范围及其代价估算，见 [`Executor::key_access`]
+#[derive(Debug, Clone, PartialEq)]
+struct KeyAccess {
+    range: KeyRange,
+    estimate: AccessEstimate,
+}
+
+/// WHERE 中主键列的取值范围，没有可用于主键索引的条件时返回 None
+///
+/// 只使用 `=`、`<`、`<=`、`>`、`>=` 且常量与主键同属数值、字符串或布尔值的项，
+/// 这时 WHERE 的比较结果与主键索引的排序（[`Value::total_cmp`]）一致；其余的项只在过滤时求值。
+fn primary_key_lookup(condition: &Condition, columns: &[ColumnDef]) -> Option<KeyRange> {
+    let primary = columns.iter().find(|column| column.is_primary)?;
+    let mut range = KeyRange {
+        column: primary.name.clone(),
+        low: Bound::Unbounded,
+        high: Bound::Unbounded,
+    };
+    collect_key_bounds(condition, columns, &mut range).then_some(range)
+}
+
+/// 把条件中限定主键的项收紧到 `range`，有任何一项时返回 true
+fn collect_key_bounds(condition: &Condition, columns: &[ColumnDef], range: &mut KeyRange) -> bool {
+    let (left, operator, right) = match condition {
+        Condition::And(left, right) => {
+            let left = collect_key_bounds(left, columns, range);
+            return collect_key_bounds(right, columns, range) || left;
+        }
+        Condition::Expression(Expression::Binary {
+            left,
+            operator,
+            right,
+        }) => (&**left, operator.clone(), &**right),
+        _ => return false,
+    };
+    // 常量在左侧时把比较方向反过来
+    let (name, operator, value) = match (left, right) {
+        (Expression::Column(name), Expression::Value(value)) => (name, operator, value),
+        (Expression::Value(value), Expression::Column(name)) => {
+            let operator = match operator {
+                BinaryOperator::LessThan => BinaryOperator::GreaterThan,
+                BinaryOperator::LessThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+                BinaryOperator::GreaterThan => BinaryOperator::LessThan,
+                BinaryOperator::GreaterThanOrEqual => BinaryOperator::LessThanOrEqual,
+                operator => operator,
+            };
+            (name, operator, value)
+        }
+        _ => return false,
+    };
+    let Ok(index) = resolve_column(columns, name) else {
+        return false;
+    };
+    let comparable = matches!(
+        (&columns[index].data_type, value),
+        (
+            DataType::Int(_) | DataType::Float,
+            Value::Int(_) | Value::Float(_)
+        ) | (DataType::Varchar(_), Value::String(_))
+            | (DataType::Boolean, Value::Boolean(_))
+    );
+    if !columns[index].is_primary || !comparable {
+        return false;
+    }
+    let value = value.clone();
+    match operator {
+        BinaryOperator::Equal => {
+            range.raise_low(Bound::Included(value.clone()));
+            range.lower_high(Bound::Included(value));
+        }
+        BinaryOperator::GreaterThan => range.raise_low(Bound::Excluded(value)),
+        BinaryOperator::GreaterThanOrEqual => range.raise_low(Bound::Included(value)),
+        BinaryOperator::LessThan => range.lower_high(Bound::Excluded(value)),
+        BinaryOperator::LessThanOrEqual => range.lower_high(Bound::Included(value)),
+        _ => return false,
+    }
+    true
+}
+
+/// `SHOW TABLES` 结果的列名，与 MySQL 一样带上数据库名
+pub fn tables_column(database: &str) -> String {
+    format!("Tables_in_{}", database)
+}
+
+/// 生成与表定义等价的 CREATE TABLE 语句（SHOW CREATE TABLE 的输出）
+fn show_create_table(
+    name: &str,
+    columns: &[ColumnDef],
+    indexes: &[IndexDef],
+    checks: &[CheckConstraint],
+) -> String {
+    let mut lines = Vec::new();
+    for column in columns {
+        let mut line = format!("  `{}` {}", column.name, column.data_type);
+        if column.not_null {
+            line.push_str(" NOT NULL");
+        }
+        match &column.default {
+            None => {}
+            Some(Value::Null) => line.push_str(" DEFAULT NULL"),
+            Some(Value::String(s)) => {
+                line.push_str(&format!(" DEFAULT '{}'", s.replace('\'', "''")))
             }
+            Some(default) => line.push_str(&format!(" DEFAULT {}", default)),
+        }
+        if column.auto_increment {
+            line.push_str(" AUTO_INCREMENT");
+        }
+        lines.push(line);
+    }
+
+    if let Some(primary) = columns.iter().find(|col| col.is_primary) {
+        lines.push(format!("  PRIMARY KEY (`{}`)", primary.name));
+    }
+    // 列级 UNIQUE 没有对应的索引定义，按 MySQL 的习惯以列名作为索引名输出
+    for column in columns.iter().filter(|col| col.unique && !col.is_primary) {
+        let covered = indexes
+            .iter()
+            .any(|index| index.unique && index.columns == [column.name.clone()]);
+        if !covered {
+            lines.push(format!("  UNIQUE KEY `{0}` (`{0}`)", column.name));
+        }
+    }
+    for index in indexes {
+        let key_columns = index
+            .columns
+            .iter()
+            .map(|col| format!("`{}`", col))
+            .collect::<Vec<_>>()
+            .join(",");
+        let kind = if index.unique { "UNIQUE KEY" } else { "KEY" };
+        lines.push(format!("  {} `{}` ({})", kind, index.name, key_columns));
+    }
+    // 外键没有名字，按 MySQL 的习惯命名为 `表名_ibfk_序号`
+    let foreign_keys = columns
+        .iter()
+        .filter_map(|col| Some((&col.name, col.references.as_ref()?)));
+    for (number, (column, foreign_key)) in foreign_keys.enumerate() {
+        lines.push(format!(
+            "  CONSTRAINT `{}_ibfk_{}` FOREIGN KEY (`{}`) REFERENCES `{}` (`{}`)",
+            name,
+            number + 1,
+            column,
+            foreign_key.table,
+            foreign_key.column
+        ));
+    }
+    for check in checks {
+        lines.push(format!(
+            "  CONSTRAINT `{}` CHECK ({})",
+            check.name, check.expr
+        ));
+    }
 
-            // 字符串比较
-            (Value::String(a), Value::String(b)) => a.cmp(b),
+    format!("CREATE TABLE `{}` (\n{}\n)", name, lines.join(",\n"))
+}
+
+/// 根据结果集中的值推断列定义（用于 SELECT ... INTO）
+fn infer_column_defs(result_set: &ResultSet) -> Result<Vec<ColumnDef>> {
+    let mut columns = Vec::with_capacity(result_set.columns.len());
 
-            // 布尔值比较
-            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+    for (col_idx, name) in result_set.columns.iter().enumerate() {
+        let mut data_type = None;
+        let mut max_len = 0u64;
 
-            // 不同类型之间的比较（可以根据需要调整规则）
-            _ => Ordering::Equal,
+        for row in &result_set.rows {
+            match row.get(col_idx) {
+                Some(Value::Int(_)) => match data_type {
+                    None | Some(DataType::Int(_)) => data_type = Some(DataType::Int(None)),
+                    _ => return Err(mixed_column_error(name)),
+                },
+                Some(Value::Float(_)) => match data_type {
+                    None | Some(DataType::Float) => data_type = Some(DataType::Float),
+                    _ => return Err(mixed_column_error(name)),
+                },
+                Some(Value::Boolean(_)) => match data_type {
+                    None | Some(DataType::Boolean) => data_type = Some(DataType::Boolean),
+                    _ => return Err(mixed_column_error(name)),
+                },
+                Some(Value::String(s)) => {
+                    match data_type {
+                        None | Some(DataType::Varchar(_)) => {}
+                        _ => return Err(mixed_column_error(name)),
+                    }
+                    max_len = max_len.max(s.chars().count() as u64);
+                    data_type = Some(DataType::Varchar(Some(max_len.max(1))));
+                }
+                Some(Value::Null) | None => {}
+            }
         }
+
+        columns.push(ColumnDef {
+            name: name.clone(),
+            // 全为 NULL 的列按不限长度的 VARCHAR 处理
+            data_type: data_type.unwrap_or(DataType::Varchar(None)),
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        });
     }
+
+    Ok(columns)
+}
+
+fn mixed_column_error(name: &str) -> DBError {
+    DBError::Schema(format!("无法为列 '{}' 推断类型: 值的类型不一致", name))
 }
 
 /// 格式化select表头：运算符前后有字母时去空格，前后都是数字时保留空格
@@ -624,3 +2382,2470 @@ fn format_column_header(name: &str) -> String {
             .join(" ")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::Planner;
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+    use tempfile::TempDir;
+
+    fn create_test_storage() -> (StorageEngine, TempDir) {
+        let temp_dir = TempDir::new().expect("无法创建临时目录");
+        let storage =
+            StorageEngine::new(Some(temp_dir.path()), Some("test_db")).expect("无法创建存储引擎");
+        (storage, temp_dir)
+    }
+
+    /// 依次执行多条 SQL，返回最后一条语句的结果
+    fn run_sql(storage: &mut StorageEngine, sql: &str) -> Result<QueryResult> {
+        let statements = Parser::parse_sql(&MySqlDialect {}, sql)?;
+        let planner = Planner::new();
+        let mut session = Session::new();
+        let mut executor = Executor::new(storage, &mut session);
+
+        let mut last = Ok(QueryResult::Success);
+        for stmt in &statements {
+            last = executor.execute(planner.plan(stmt)?);
+            if last.is_err() {
+                break;
+            }
+        }
+        last
+    }
+
+    fn query_rows(storage: &mut StorageEngine, sql: &str) -> Vec<Vec<Value>> {
+        match run_sql(storage, sql).unwrap() {
+            QueryResult::ResultSet(rs) => rs.rows,
+            other => panic!("预期结果集，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_select_into_creates_table() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE books (id INT PRIMARY KEY, name VARCHAR(100), price INT);
+             INSERT INTO books VALUES (1, 'SETI', 32), (2, 'Rust', 66), (3, 'Go', NULL);",
+        )
+        .unwrap();
+
+        let result = run_sql(
+            &mut storage,
+            "SELECT id, name AS title, price * 2 AS doubled INTO cheap FROM books WHERE id < 3",
+        )
+        .unwrap();
+        assert_eq!(result.to_string(), "2 rows written to cheap\n");
+
+        let columns = storage.get_table_columns("cheap").unwrap();
+        let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "title", "doubled"]);
+        assert_eq!(columns[0].data_type, DataType::Int(None));
+        assert_eq!(columns[1].data_type, DataType::Varchar(Some(4)));
+
+        let rows = query_rows(&mut storage, "SELECT * FROM cheap");
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Value::Int(1),
+                    Value::String("SETI".to_string()),
+                    Value::Int(64)
+                ],
+                vec![
+                    Value::Int(2),
+                    Value::String("Rust".to_string()),
+                    Value::Int(132)
+                ],
+            ]
+        );
+
+        // 源表不受影响
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM books").len(), 3);
+    }
+
+    #[test]
+    fn test_select_into_existing_table_fails() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE src (id INT);
+             CREATE TABLE dst (id INT);
+             INSERT INTO src VALUES (1);",
+        )
+        .unwrap();
+
+        assert!(run_sql(&mut storage, "SELECT id INTO dst FROM src").is_err());
+        assert!(query_rows(&mut storage, "SELECT * FROM dst").is_empty());
+    }
+
+    #[test]
+    fn test_select_into_table_never_replaces_existing() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE src (id INT, body VARCHAR(20000));
+             CREATE TABLE dst (id INT, name VARCHAR(10));
+             INSERT INTO src VALUES (1, 'a'), (2, 'b');
+             INSERT INTO dst VALUES (9, 'old');",
+        )
+        .unwrap();
+
+        // INTO TABLE 只是 INTO 的另一种写法：目标表已存在时报错，原表不变
+        let err = run_sql(&mut storage, "SELECT id INTO TABLE dst FROM src").unwrap_err();
+        assert!(err.to_string().contains("已存在"), "{}", err);
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM dst"),
+            vec![vec![Value::Int(9), Value::String("old".to_string())]]
+        );
+        // CREATE TABLE ... AS SELECT 同样只创建新表
+        assert!(run_sql(&mut storage, "CREATE TABLE dst AS SELECT id FROM src").is_err());
+        run_sql(&mut storage, "CREATE TABLE copy AS SELECT body FROM src").unwrap();
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM copy").len(), 2);
+        assert!(run_sql(&mut storage, "SELECT id INTO TEMPORARY tmp FROM src").is_err());
+    }
+
+    #[test]
+    fn test_create_or_replace_table_as_select() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE src (id INT, body VARCHAR(20000));
+             CREATE TABLE dst (id INT, name VARCHAR(10));
+             INSERT INTO src VALUES (1, 'a'), (2, 'b');
+             INSERT INTO dst VALUES (9, 'old');",
+        )
+        .unwrap();
+
+        // 覆盖已存在的表：按结果重新推断列
+        let result = run_sql(
+            &mut storage,
+            "CREATE OR REPLACE TABLE dst AS SELECT id FROM src",
+        )
+        .unwrap();
+        assert_eq!(result.to_string(), "2 rows written to dst\n");
+        assert_eq!(storage.get_table_columns("dst").unwrap().len(), 1);
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM dst"),
+            vec![vec![Value::Int(1)], vec![Value::Int(2)]]
+        );
+        assert!(storage.get_table("dst#replaced").is_err());
+        // 不存在时直接创建
+        run_sql(&mut storage, "CREATE OR REPLACE TABLE fresh AS SELECT body FROM src").unwrap();
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM fresh").len(), 2);
+
+        // 写入中途失败（第三行超过单页容量）时原表不变，也不留下临时表
+        let big = "x".repeat(20000);
+        run_sql(&mut storage, &format!("INSERT INTO src VALUES (3, '{}')", big)).unwrap();
+        assert!(
+            run_sql(
+                &mut storage,
+                "CREATE OR REPLACE TABLE dst AS SELECT id, CONCAT(body, body) AS doubled FROM src"
+            )
+            .is_err()
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM dst"),
+            vec![vec![Value::Int(1)], vec![Value::Int(2)]]
+        );
+        assert!(storage.get_table("dst#overwrite").is_err());
+
+        // 改名失败（让开用的名字被占用）时原表改回原名，临时表被删除
+        run_sql(&mut storage, "CREATE TABLE `dst#replaced` (x INT)").unwrap();
+        assert!(
+            run_sql(
+                &mut storage,
+                "CREATE OR REPLACE TABLE dst AS SELECT body FROM src WHERE id = 1"
+            )
+            .is_err()
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM dst"),
+            vec![vec![Value::Int(1)], vec![Value::Int(2)]]
+        );
+        assert!(storage.get_table("dst#overwrite").is_err());
+        run_sql(&mut storage, "DROP TABLE `dst#replaced`").unwrap();
+
+        // 撤销记录无法跟随改名，事务中不能覆盖已存在的表
+        let err = run_sql(
+            &mut storage,
+            "BEGIN; CREATE OR REPLACE TABLE dst AS SELECT id FROM src WHERE id = 1",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("事务"), "{}", err);
+        run_sql(&mut storage, "ROLLBACK").unwrap();
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM dst").len(), 2);
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM src").len(), 3);
+    }
+
+    #[test]
+    fn test_select_into_infers_float_and_boolean_columns() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(&mut storage, "CREATE TABLE src (id INT, note VARCHAR(10))").unwrap();
+        run_sql(&mut storage, "INSERT INTO src VALUES (1, 'a'), (2, 'b')").unwrap();
+
+        run_sql(&mut storage, "SELECT id * 1.5 AS half, id > 1 AS big INTO floats FROM src")
+            .unwrap();
+        let columns = storage.get_table_columns("floats").unwrap();
+        assert_eq!(columns[0].data_type, DataType::Float);
+        assert_eq!(columns[1].data_type, DataType::Boolean);
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM floats"),
+            vec![
+                vec![Value::Float(1.5), Value::Boolean(false)],
+                vec![Value::Float(3.0), Value::Boolean(true)],
+            ]
+        );
+
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM src").len(), 2);
+    }
+
+    #[test]
+    fn test_boolean_int_coercion() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE flags (id INT, active INT);
+             INSERT INTO flags VALUES (1, TRUE), (2, FALSE);
+             INSERT INTO flags (id, active) VALUES (3, 1);",
+        )
+        .unwrap();
+
+        // 宽松模式下 TRUE/FALSE 写入 INT 列时存为 1/0，严格模式见 tests/sql_mode_test.rs
+        let rows = query_rows(&mut storage, "SELECT active FROM flags");
+        assert_eq!(
+            rows,
+            vec![vec![Value::Int(1)], vec![Value::Int(0)], vec![Value::Int(1)]]
+        );
+
+        // 宽松模式下布尔值与整数在两个方向上都可以比较
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id FROM flags WHERE active = TRUE").len(),
+            2
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id FROM flags WHERE FALSE = active"),
+            vec![vec![Value::Int(2)]]
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id FROM flags WHERE active < TRUE"),
+            vec![vec![Value::Int(2)]]
+        );
+
+        run_sql(&mut storage, "UPDATE flags SET active = FALSE WHERE id = 1").unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT active FROM flags WHERE id = 1"),
+            vec![vec![Value::Int(0)]]
+        );
+
+        // 布尔值不会被隐式转换为字符串
+        run_sql(&mut storage, "CREATE TABLE notes (body VARCHAR(10))").unwrap();
+        assert!(run_sql(&mut storage, "INSERT INTO notes VALUES (TRUE)").is_err());
+    }
+
+    #[test]
+    fn test_insert_returning() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT PRIMARY KEY AUTO_INCREMENT, name VARCHAR(20))",
+        )
+        .unwrap();
+
+        let result = run_sql(
+            &mut storage,
+            "INSERT INTO users (name) VALUES ('alice'), ('bob') RETURNING id, id * 10 AS tens, name",
+        )
+        .unwrap();
+        let QueryResult::ResultSet(rs) = result else {
+            panic!("RETURNING 应返回结果集");
+        };
+        assert_eq!(rs.columns, vec!["id", "tens", "name"]);
+        assert_eq!(
+            rs.rows,
+            vec![
+                vec![
+                    Value::Int(1),
+                    Value::Int(10),
+                    Value::String("alice".to_string())
+                ],
+                vec![
+                    Value::Int(2),
+                    Value::Int(20),
+                    Value::String("bob".to_string())
+                ],
+            ]
+        );
+
+        // 显式给出的值会推高计数器
+        run_sql(&mut storage, "INSERT INTO users VALUES (10, 'carol')").unwrap();
+        let result = run_sql(&mut storage, "INSERT INTO users VALUES (NULL, 'dave') RETURNING *")
+            .unwrap();
+        let QueryResult::ResultSet(rs) = result else {
+            panic!("RETURNING 应返回结果集");
+        };
+        assert_eq!(
+            rs.rows,
+            vec![vec![Value::Int(11), Value::String("dave".to_string())]]
+        );
+    }
+
+    #[test]
+    fn test_insert_ignore_returning_skips_rows() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(5));
+             INSERT INTO users VALUES (1, 'a');",
+        )
+        .unwrap();
+
+        // 没有 IGNORE 时重复主键直接报错
+        assert!(run_sql(&mut storage, "INSERT INTO users VALUES (1, 'b')").is_err());
+
+        let result = run_sql(
+            &mut storage,
+            "INSERT IGNORE INTO users VALUES (1, 'dup'), (2, 'b'), (3, 'too long'), (4, 'd') RETURNING id",
+        )
+        .unwrap();
+        let QueryResult::ResultSet(rs) = result else {
+            panic!("RETURNING 应返回结果集");
+        };
+        assert_eq!(rs.rows, vec![vec![Value::Int(2)], vec![Value::Int(4)]]);
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM users").len(), 3);
+    }
+
+    #[test]
+    fn test_insert_select() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(10), age INT);
+             CREATE TABLE archive (id INT PRIMARY KEY, name VARCHAR(10), age INT);
+             CREATE TABLE names (name VARCHAR(10), note VARCHAR(10));
+             INSERT INTO users VALUES (1, 'ann', 70), (2, 'bob', 30), (3, 'cat', 65);",
+        )
+        .unwrap();
+        let row = |id: i32, name: &str, age: i32| {
+            vec![
+                Value::Int(id),
+                Value::String(name.to_string()),
+                Value::Int(age),
+            ]
+        };
+
+        let result = run_sql(
+            &mut storage,
+            "INSERT INTO archive SELECT * FROM users WHERE age > 60",
+        )
+        .unwrap();
+        assert!(matches!(
+            result,
+            QueryResult::Modified(LastInsert {
+                rows_affected: 2,
+                ..
+            })
+        ));
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM archive ORDER BY id"),
+            vec![row(1, "ann", 70), row(3, "cat", 65)]
+        );
+
+        // 指定列时按列名写入，其余列为 NULL；查询可以是 UNION
+        run_sql(
+            &mut storage,
+            "INSERT INTO names (note, name) SELECT 'old', name FROM archive \
+             UNION ALL SELECT 'young', name FROM users WHERE age < 60",
+        )
+        .unwrap();
+        let names: Vec<Vec<Value>> = [("ann", "old"), ("bob", "young"), ("cat", "old")]
+            .iter()
+            .map(|&(name, note)| vec![Value::String(name.into()), Value::String(note.into())])
+            .collect();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT name, note FROM names ORDER BY name"),
+            names
+        );
+
+        // 向源表自身插入时只复制插入前已有的行
+        run_sql(&mut storage, "INSERT INTO names SELECT * FROM names").unwrap();
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM names").len(), 6);
+
+        // 列数不符、类型不符、违反约束都整体失败
+        for sql in [
+            "INSERT INTO archive SELECT id, name FROM users",
+            "INSERT INTO archive (id) SELECT name FROM users",
+            "INSERT INTO archive SELECT * FROM users",
+        ] {
+            assert!(run_sql(&mut storage, sql).is_err(), "{}", sql);
+        }
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM archive").len(), 2);
+
+        // INSERT IGNORE 跳过主键重复的行
+        run_sql(
+            &mut storage,
+            "INSERT IGNORE INTO archive SELECT * FROM users",
+        )
+        .unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM archive ORDER BY id"),
+            vec![row(1, "ann", 70), row(2, "bob", 30), row(3, "cat", 65)]
+        );
+    }
+
+    #[test]
+    fn test_primary_key_and_unique_constraints() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT PRIMARY KEY, email VARCHAR(10) UNIQUE, name VARCHAR(10));
+             INSERT INTO users VALUES (1, 'a@x', 'ann'), (2, NULL, 'bob'), (3, NULL, 'cat');",
+        )
+        .unwrap();
+        let ids = |storage: &mut StorageEngine| {
+            query_rows(storage, "SELECT id FROM users ORDER BY id")
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let duplicate = |result: Result<QueryResult>, entry: &str, key: &str| {
+            let err = result.expect_err("应违反唯一约束");
+            assert!(matches!(err, DBError::Schema(_)), "{}", err);
+            let expected = format!("Duplicate entry '{}' for key '{}'", entry, key);
+            assert!(err.to_string().contains(&expected), "{}", err);
+        };
+
+        // 与已有记录重复；主键不能为 NULL，UNIQUE 列的多个 NULL 不算重复
+        duplicate(
+            run_sql(&mut storage, "INSERT INTO users VALUES (1, 'b@x', 'dup')"),
+            "1",
+            "PRIMARY",
+        );
+        duplicate(
+            run_sql(&mut storage, "INSERT INTO users VALUES (4, 'a@x', 'dup')"),
+            "a@x",
+            "email",
+        );
+        assert!(run_sql(&mut storage, "INSERT INTO users VALUES (NULL, 'n@x', 'nil')").is_err());
+
+        // 多行 INSERT 内部重复或与已有记录重复时整体不写入
+        duplicate(
+            run_sql(
+                &mut storage,
+                "INSERT INTO users VALUES (4, 'd@x', 'dan'), (5, 'e@x', 'eve'), (4, 'f@x', 'fay')",
+            ),
+            "4",
+            "PRIMARY",
+        );
+        duplicate(
+            run_sql(
+                &mut storage,
+                "INSERT INTO users VALUES (4, 'd@x', 'dan'), (5, 'a@x', 'eve')",
+            ),
+            "a@x",
+            "email",
+        );
+        assert_eq!(ids(&mut storage), vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
+
+        // UPDATE 按更新后的整批值检查，失败时一行都不改
+        duplicate(
+            run_sql(&mut storage, "UPDATE users SET id = 1 WHERE id = 2"),
+            "1",
+            "PRIMARY",
+        );
+        duplicate(
+            run_sql(&mut storage, "UPDATE users SET email = 'z@x'"),
+            "z@x",
+            "email",
+        );
+        assert!(run_sql(&mut storage, "UPDATE users SET id = NULL WHERE id = 3").is_err());
+        assert_eq!(
+            query_rows(&mut storage, "SELECT email FROM users WHERE id = 1"),
+            vec![vec![Value::String("a@x".to_string())]]
+        );
+        // 记录与自己的旧值不冲突
+        run_sql(&mut storage, "UPDATE users SET email = 'a@x', name = 'anna' WHERE id = 1").unwrap();
+        run_sql(&mut storage, "UPDATE users SET email = NULL").unwrap();
+
+        // INSERT IGNORE 仍然逐行跳过重复的行
+        run_sql(
+            &mut storage,
+            "INSERT IGNORE INTO users VALUES (4, 'd@x', 'dan'), (4, 'e@x', 'eve'), (1, 'f@x', 'fay')",
+        )
+        .unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT name FROM users WHERE id = 4"),
+            vec![vec![Value::String("dan".to_string())]]
+        );
+    }
+
+    #[test]
+    fn test_not_null_enforced_for_explicit_values() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT AUTO_INCREMENT PRIMARY KEY, name VARCHAR(10) NOT NULL, age INT)",
+        )
+        .unwrap();
+        let message = |result: Result<QueryResult>| {
+            let err = result.expect_err("应违反 NOT NULL 约束");
+            assert!(matches!(err, DBError::Schema(_)), "{}", err);
+            err.to_string()
+        };
+
+        // 无列名与有列名两种写法都检查显式的 NULL
+        assert_eq!(
+            message(run_sql(&mut storage, "INSERT INTO users VALUES (1, NULL, 25)")),
+            "Column 'name' cannot be null"
+        );
+        assert_eq!(
+            message(run_sql(
+                &mut storage,
+                "INSERT INTO users (name, age) VALUES (NULL, 25)"
+            )),
+            "Column 'name' cannot be null"
+        );
+        // 多行写入时指出出错的行，且整条语句不写入
+        assert_eq!(
+            message(run_sql(
+                &mut storage,
+                "INSERT INTO users VALUES (1, 'ann', 25), (2, 'bob', NULL), (3, NULL, 40)"
+            )),
+            "Column 'name' cannot be null (第 3 行)"
+        );
+        assert!(query_rows(&mut storage, "SELECT * FROM users").is_empty());
+
+        // 自增列的 NULL 仍由存储层分配
+        run_sql(&mut storage, "INSERT INTO users VALUES (NULL, 'ann', NULL)").unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id, name FROM users"),
+            vec![vec![Value::Int(1), Value::String("ann".to_string())]]
+        );
+
+        assert_eq!(
+            message(run_sql(&mut storage, "UPDATE users SET name = NULL")),
+            "Column 'name' cannot be null"
+        );
+        // 没有匹配行时不报错
+        run_sql(&mut storage, "UPDATE users SET name = NULL WHERE id = 9").unwrap();
+        run_sql(&mut storage, "UPDATE users SET age = NULL").unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT name FROM users"),
+            vec![vec![Value::String("ann".to_string())]]
+        );
+    }
+
+    #[test]
+    fn test_insert_reports_every_invalid_row() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT, name VARCHAR(5) NOT NULL, age INT)",
+        )
+        .unwrap();
+
+        // 值数量、类型、NOT NULL 和长度的问题一起报告，指出每个出错的行，且一行都不写入
+        let err = run_sql(
+            &mut storage,
+            "INSERT INTO users VALUES (1, 'ann', 30), (2, 'bob'), (3, 'cat', 'old'), \
+             (4, NULL, 1), (5, 'eleanor', 2), (6, 'fay', 3, 4)",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "5 行数据有误: 第 2 行的值数量(2)与表的列数(3)不匹配; \
+             字符串 'old' 不会隐式转换为 INT (第 3 行); \
+             Column 'name' cannot be null (第 4 行); \
+             Data too long for column 'name' at row 5; \
+             第 6 行的值数量(4)与表的列数(3)不匹配"
+        );
+        assert!(query_rows(&mut storage, "SELECT * FROM users").is_empty());
+
+        // 只有一行出错时保持原来的错误信息
+        let err = run_sql(&mut storage, "INSERT INTO users VALUES (1, 'ann')").unwrap_err();
+        assert_eq!(err.to_string(), "第 1 行的值数量(2)与表的列数(3)不匹配");
+
+        // INSERT IGNORE 跳过类型错误的行，值数量不符仍然报错
+        run_sql(
+            &mut storage,
+            "INSERT IGNORE INTO users VALUES (1, 'ann', 30), (3, 'cat', 'old')",
+        )
+        .unwrap();
+        assert!(run_sql(&mut storage, "INSERT IGNORE INTO users VALUES (2, 'bob')").is_err());
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id FROM users"),
+            vec![vec![Value::Int(1)]]
+        );
+
+        // 出错的行很多时只列出前几行
+        let values: Vec<String> = (1..=12).map(|i| format!("({}, NULL, 0)", i)).collect();
+        let err = run_sql(
+            &mut storage,
+            &format!("INSERT INTO users VALUES {}", values.join(", ")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, DBError::Schema(_)), "{}", err);
+        let message = err.to_string();
+        assert!(message.starts_with("12 行数据有误: "), "{}", message);
+        assert!(message.contains("(第 10 行)"), "{}", message);
+        assert!(!message.contains("(第 11 行)"), "{}", message);
+        assert!(message.ends_with("; 其余 2 行未列出"), "{}", message);
+    }
+
+    #[test]
+    fn test_describe_and_show_columns() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT PRIMARY KEY, email VARCHAR(100) NOT NULL UNIQUE, age INT(3))",
+        )
+        .unwrap();
+
+        let QueryResult::ResultSet(rs) = run_sql(&mut storage, "DESCRIBE users").unwrap() else {
+            panic!("DESCRIBE 应返回结果集");
+        };
+        assert_eq!(rs.columns, ["Field", "Type", "Null", "Key", "Default"]);
+        let text = |row: &[&str]| {
+            let mut row: Vec<_> = row.iter().map(|s| Value::String(s.to_string())).collect();
+            row.push(Value::Null);
+            row
+        };
+        assert_eq!(
+            rs.rows,
+            vec![
+                text(&["id", "INT", "NO", "PRI"]),
+                text(&["email", "VARCHAR(100)", "NO", "UNI"]),
+                text(&["age", "INT(3)", "YES", ""]),
+            ]
+        );
+
+        // SHOW COLUMNS 与 DESC 是同一计划
+        for sql in ["SHOW COLUMNS FROM users", "SHOW FULL COLUMNS IN users", "DESC users"] {
+            assert_eq!(query_rows(&mut storage, sql), rs.rows, "{}", sql);
+        }
+        assert!(run_sql(&mut storage, "SHOW COLUMNS FROM users LIKE 'e%'").is_err());
+        assert!(matches!(
+            run_sql(&mut storage, "DESCRIBE missing"),
+            Err(DBError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_show_tables_sorted_with_database_column() {
+        let (mut storage, _temp_dir) = create_test_storage();
+
+        // 空数据库返回空结果集而不是报错
+        let QueryResult::ResultSet(rs) = run_sql(&mut storage, "SHOW TABLES").unwrap() else {
+            panic!("SHOW TABLES 应返回结果集");
+        };
+        assert_eq!(rs.columns, ["Tables_in_test_db"]);
+        assert!(rs.rows.is_empty());
+
+        run_sql(
+            &mut storage,
+            "CREATE TABLE zoo (id INT); CREATE TABLE apple (id INT); CREATE TABLE mango (id INT);",
+        )
+        .unwrap();
+        let names: Vec<Value> = ["apple", "mango", "zoo"]
+            .iter()
+            .map(|name| Value::String(name.to_string()))
+            .collect();
+        assert_eq!(
+            query_rows(&mut storage, "SHOW TABLES"),
+            names.into_iter().map(|name| vec![name]).collect::<Vec<_>>()
+        );
+
+        run_sql(&mut storage, "CREATE DATABASE other; USE other").unwrap();
+        let QueryResult::ResultSet(rs) = run_sql(&mut storage, "SHOW TABLES").unwrap() else {
+            panic!("SHOW TABLES 应返回结果集");
+        };
+        assert_eq!(rs.columns, ["Tables_in_other"]);
+        assert!(rs.rows.is_empty());
+    }
+
+    #[test]
+    fn test_alter_table_add_and_drop_column() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(10), KEY idx_name (name));
+             INSERT INTO users VALUES (1, 'ann'), (2, 'bob');",
+        )
+        .unwrap();
+        let columns = |storage: &StorageEngine| -> Vec<String> {
+            storage
+                .get_table_columns("users")
+                .unwrap()
+                .into_iter()
+                .map(|column| column.name)
+                .collect()
+        };
+
+        // 新列在已有记录中为 NULL，可以指定位置
+        run_sql(
+            &mut storage,
+            "ALTER TABLE users ADD COLUMN age INT, ADD COLUMN tag VARCHAR(5) FIRST, ADD email VARCHAR(20) AFTER id",
+        )
+        .unwrap();
+        assert_eq!(columns(&storage), ["tag", "id", "email", "name", "age"]);
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM users WHERE id = 2"),
+            vec![vec![
+                Value::Null,
+                Value::Int(2),
+                Value::Null,
+                Value::String("bob".to_string()),
+                Value::Null
+            ]]
+        );
+        run_sql(&mut storage, "INSERT INTO users (id, name, age) VALUES (3, 'cat', 30)").unwrap();
+        run_sql(&mut storage, "UPDATE users SET age = 20 WHERE id = 1").unwrap();
+
+        // 删除列同时删除只含该列的索引
+        run_sql(&mut storage, "ALTER TABLE users DROP COLUMN name, DROP tag").unwrap();
+        assert_eq!(columns(&storage), ["id", "email", "age"]);
+        assert!(storage.get_table_indexes("users").unwrap().is_empty());
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id, age FROM users ORDER BY id"),
+            vec![
+                vec![Value::Int(1), Value::Int(20)],
+                vec![Value::Int(2), Value::Null],
+                vec![Value::Int(3), Value::Int(30)],
+            ]
+        );
+
+        // 任何一个操作失败时整条语句不生效
+        for (sql, message) in [
+            ("ALTER TABLE users ADD COLUMN age INT", "Duplicate column name 'age'"),
+            ("ALTER TABLE users ADD COLUMN code INT NOT NULL", "不能添加没有默认值的列 'code'"),
+            ("ALTER TABLE users ADD COLUMN x INT, DROP COLUMN missing", "Can't DROP 'missing'"),
+            ("ALTER TABLE users ADD COLUMN x INT AFTER missing", "Unknown column 'missing'"),
+            ("ALTER TABLE users DROP id, DROP email, DROP age", "use DROP TABLE instead"),
+            ("ALTER TABLE users ADD COLUMN k INT PRIMARY KEY", "Multiple primary key"),
+        ] {
+            let err = run_sql(&mut storage, sql).unwrap_err();
+            assert!(err.to_string().contains(message), "{}: {}", sql, err);
+        }
+        assert_eq!(columns(&storage), ["id", "email", "age"]);
+        assert!(run_sql(&mut storage, "ALTER TABLE users RENAME TO people").is_err());
+
+        // 空表可以添加 NOT NULL 列
+        run_sql(&mut storage, "CREATE TABLE empty (a INT)").unwrap();
+        run_sql(&mut storage, "ALTER TABLE empty ADD COLUMN b INT NOT NULL").unwrap();
+        assert!(run_sql(&mut storage, "INSERT INTO empty (a) VALUES (1)").is_err());
+    }
+
+    #[test]
+    fn test_float_and_boolean_columns() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE products (id INT, price FLOAT, weight DOUBLE, active BOOLEAN);
+             INSERT INTO products VALUES (1, 9.5, 2, TRUE), (2, 3, 0.25, 0), (3, NULL, 1.0, NULL);",
+        )
+        .unwrap();
+
+        // 整数写入 FLOAT 列转为浮点数，0/1 写入 BOOLEAN 列转为布尔值
+        assert_eq!(
+            query_rows(&mut storage, "SELECT price, weight, active FROM products WHERE id < 3"),
+            vec![
+                vec![Value::Float(9.5), Value::Float(2.0), Value::Boolean(true)],
+                vec![Value::Float(3.0), Value::Float(0.25), Value::Boolean(false)],
+            ]
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT id FROM products WHERE price > 5"),
+            vec![vec![Value::Int(1)]]
+        );
+
+        let types: Vec<Value> = query_rows(&mut storage, "DESCRIBE products")
+            .into_iter()
+            .map(|row| row[1].clone())
+            .collect();
+        assert_eq!(
+            types,
+            ["INT", "FLOAT", "FLOAT", "BOOLEAN"].map(|t| Value::String(t.to_string()))
+        );
+
+        for sql in [
+            "INSERT INTO products VALUES (4, 'cheap', 1.0, TRUE)",
+            "INSERT INTO products VALUES (4, 1.0, 1.0, 2)",
+            "UPDATE products SET active = 'yes'",
+        ] {
+            assert!(
+                matches!(run_sql(&mut storage, sql), Err(DBError::Schema(_))),
+                "{}",
+                sql
+            );
+        }
+
+        // 整数值的浮点数显示为 3.0，表格按格式化后的文本计算列宽
+        let sql = "SELECT price, weight + 0.1 FROM products WHERE id = 2";
+        let text = run_sql(&mut storage, sql).unwrap().to_string();
+        assert!(text.contains("| 3.0   | 0.35       |"), "{}", text);
+
+        // 溢出为无穷大的运算报错，不会写入表中
+        for sql in [
+            "SELECT 1.0e308 * 10",
+            "UPDATE products SET weight = weight * 1.0e308 * 1.0e308",
+        ] {
+            match run_sql(&mut storage, sql) {
+                Err(DBError::Execution(e)) => assert!(e.contains("超出范围"), "{}: {}", sql, e),
+                other => panic!("{} 预期溢出错误，实际为 {:?}", sql, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_coerces_values_to_column_types() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE m (n INT, f FLOAT, b BOOLEAN, s VARCHAR(10));
+             INSERT INTO m VALUES (2.0, 3, 1, 'x'), (7.0, 1.5, FALSE, 'y');",
+        )
+        .unwrap();
+
+        // 存入的是转换后的值，之后的比较与列类型一致
+        assert_eq!(
+            query_rows(&mut storage, "SELECT n, f, b FROM m"),
+            vec![
+                vec![Value::Int(2), Value::Float(3.0), Value::Boolean(true)],
+                vec![Value::Int(7), Value::Float(1.5), Value::Boolean(false)],
+            ]
+        );
+        assert_eq!(query_rows(&mut storage, "SELECT n FROM m WHERE n = 2").len(), 1);
+        run_sql(&mut storage, "UPDATE m SET n = 4.0 WHERE n = 2").unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT n FROM m WHERE f = 3"),
+            vec![vec![Value::Int(4)]]
+        );
+
+        for (sql, message) in [
+            ("INSERT INTO m (n) VALUES (2.5)", "浮点数 2.5 有小数部分"),
+            ("INSERT INTO m (n) VALUES (3000000000.0)", "超出 INT 范围"),
+            ("INSERT INTO m (f) VALUES ('1.5')", "字符串 '1.5' 不会隐式转换为 FLOAT"),
+            ("INSERT INTO m (n) VALUES ('12')", "字符串 '12' 不会隐式转换为 INT"),
+            ("INSERT INTO m (b) VALUES (2)", "整数 2 不能写入 BOOLEAN 列"),
+            ("INSERT INTO m (b) VALUES (0.0)", "FLOAT 类型的值 0.0 不能写入 BOOLEAN 列"),
+            ("INSERT INTO m (s) VALUES (TRUE)", "BOOLEAN 类型的值 true 不能写入 VARCHAR(10) 列"),
+            ("UPDATE m SET n = 0.5", "浮点数 0.5"),
+        ] {
+            match run_sql(&mut storage, sql) {
+                Err(DBError::Schema(e)) => assert!(e.contains(message), "{}: {}", sql, e),
+                other => panic!("{} 预期类型错误，实际为 {:?}", sql, other),
+            }
+        }
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM m").len(), 2);
+    }
+
+    #[test]
+    fn test_constant_false_dml_skips_table_access() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE t (id INT, status VARCHAR(10));
+             INSERT INTO t VALUES (1, 'active'), (2, 'idle'), (3, 'active');",
+        )
+        .unwrap();
+        let pages_read = |storage: &StorageEngine| {
+            let database = storage.current_database().unwrap();
+            database.get_buffer_manager().pages_read()
+        };
+        let run_optimized = |storage: &mut StorageEngine, sql: &str| {
+            let stmt = &Parser::parse_sql(&MySqlDialect {}, sql).unwrap()[0];
+            let plan = crate::planner::optimizer::optimize(Planner::new().plan(stmt).unwrap());
+            let mut session = Session::new();
+            Executor::new(storage, &mut session).execute(plan)
+        };
+
+        let before = pages_read(&storage);
+        match run_optimized(&mut storage, "UPDATE t SET status = 'x' WHERE 1 = 0").unwrap() {
+            QueryResult::Updated(summary) => {
+                assert_eq!((summary.matched, summary.changed), (0, 0))
+            }
+            other => panic!("预期 Updated，实际为 {:?}", other),
+        }
+        run_optimized(&mut storage, "DELETE FROM t WHERE 1 = 0 AND id = 3").unwrap();
+        run_optimized(&mut storage, "DELETE FROM t WHERE NOT (1 = 1 OR id = 2)").unwrap();
+        assert_eq!(pages_read(&storage), before);
+
+        // 恒假条件下仍然检查 SET 的列
+        assert!(run_optimized(&mut storage, "UPDATE t SET missing = 1 WHERE 1 = 0").is_err());
+
+        // 恒真的条件去掉后按无条件执行
+        match run_optimized(&mut storage, "UPDATE t SET status = 'idle' WHERE 1 = 1").unwrap() {
+            QueryResult::Updated(summary) => {
+                assert_eq!((summary.matched, summary.changed), (3, 2))
+            }
+            other => panic!("预期 Updated，实际为 {:?}", other),
+        }
+        assert!(pages_read(&storage) > before);
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM t").len(), 3);
+    }
+
+    #[test]
+    fn test_insert_reports_last_insert_id() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE events (id INT AUTO_INCREMENT, kind VARCHAR(10));
+             CREATE TABLE plain (v INT);",
+        )
+        .unwrap();
+
+        let result = run_sql(&mut storage, "INSERT INTO events (kind) VALUES ('a'), ('b'), ('c')")
+            .unwrap();
+        let QueryResult::Modified(last) = result else {
+            panic!("INSERT 应返回 Modified");
+        };
+        assert_eq!(
+            last,
+            LastInsert {
+                rows_affected: 3,
+                last_insert_id: Some(3),
+                elapsed: None,
+            }
+        );
+        // 摘要不影响命令行输出
+        assert_eq!(QueryResult::Modified(last).to_string(), "");
+
+        let result = run_sql(&mut storage, "INSERT INTO plain VALUES (7), (8)").unwrap();
+        let QueryResult::Modified(last) = result else {
+            panic!("INSERT 应返回 Modified");
+        };
+        assert_eq!(last.rows_affected, 2);
+        assert_eq!(last.last_insert_id, None);
+
+        assert!(run_sql(&mut storage, "CREATE TABLE bad (name VARCHAR(5) AUTO_INCREMENT)").is_err());
+    }
+
+    #[test]
+    fn test_elapsed_footer() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE t (id INT); INSERT INTO t VALUES (1), (2)",
+        )
+        .unwrap();
+        let timed = |storage: &mut StorageEngine, sql: &str| {
+            let mut result = run_sql(storage, sql).unwrap();
+            result.set_elapsed(Some(Duration::from_micros(12_345)));
+            result.to_string()
+        };
+
+        let text = timed(&mut storage, "SELECT id FROM t");
+        assert_eq!(text.lines().last(), Some("2 rows in set (0.012 sec)"));
+        let text = timed(&mut storage, "SELECT id FROM t WHERE id = 1");
+        assert_eq!(text.lines().last(), Some("1 row in set (0.012 sec)"));
+        assert_eq!(
+            timed(&mut storage, "SELECT id FROM t WHERE id > 5"),
+            "Empty set (0.012 sec)\n"
+        );
+        assert_eq!(
+            timed(&mut storage, "INSERT INTO t VALUES (3)"),
+            "Query OK, 1 row affected (0.012 sec)\n"
+        );
+        assert_eq!(
+            timed(&mut storage, "UPDATE t SET id = 4 WHERE id >= 2"),
+            "Query OK, 2 rows affected (0.012 sec)\nRows matched: 2  Changed: 2\n"
+        );
+        assert_eq!(
+            timed(&mut storage, "DELETE FROM t WHERE id >= 3"),
+            "Query OK, 2 rows affected (0.012 sec)\n"
+        );
+        assert_eq!(
+            timed(&mut storage, "DELETE FROM t WHERE 1 = 0"),
+            "Query OK, 0 rows affected (0.012 sec)\n"
+        );
+        // 没有影响行数的语句不输出耗时
+        assert_eq!(timed(&mut storage, "CREATE TABLE u (id INT)"), "");
+    }
+
+    #[test]
+    fn test_truncate_cell() {
+        let cell = |text: &str, max_width| truncate_cell(text.to_string(), max_width);
+        assert_eq!(cell("hello", Some(5)), "hello");
+        assert_eq!(cell("hello world", Some(8)), "hello...");
+        assert_eq!(cell("hello world", None), "hello world");
+        // 按字符截断，不会切开多字节字符
+        assert_eq!(cell("数据库引擎测试", Some(5)), "数据...");
+        assert_eq!(cell("abcdef", Some(DisplayLimits::MIN_WIDTH)), "a...");
+    }
+
+    #[test]
+    fn test_display_limits() {
+        let rows = (0..5)
+            .map(|i| vec![Value::Int(i), Value::String("x".repeat(100))])
+            .collect();
+        let mut rs = ResultSet::new(vec!["id".to_string(), "note".to_string()], rows);
+
+        // 新建的结果集不限制，完整输出
+        assert_eq!(rs.limits, DisplayLimits::UNLIMITED);
+        assert!(rs.to_string().contains(&"x".repeat(100)));
+
+        // 交互模式的默认限制：长值截断到 64 个字符，表格对齐到截断后的宽度
+        rs.limits = DisplayLimits::default();
+        let text = rs.to_string();
+        let expected_cell = format!("{}...", "x".repeat(DEFAULT_MAX_WIDTH - 3));
+        let width = text.lines().next().unwrap().len();
+        assert!(text.lines().all(|line| line.len() == width));
+        assert!(text.contains(&format!("| {} |", expected_cell)), "{}", text);
+
+        // 超过最大行数的行不输出，以一行提示代替；耗时行仍按全部行数计算
+        rs.limits = DisplayLimits {
+            max_rows: Some(2),
+            max_width: Some(10),
+        };
+        rs.elapsed = Some(Duration::from_millis(1));
+        let lines: Vec<String> = rs.to_string().lines().map(str::to_string).collect();
+        assert_eq!(lines.len(), 2 + 2 + 2);
+        assert_eq!(lines[2], "| 0   | xxxxxxx... |");
+        assert_eq!(lines[4], "... 3 more rows");
+        assert_eq!(lines[5], "5 rows in set (0.001 sec)");
+        rs.limits.max_rows = Some(4);
+        assert!(rs.to_string().contains("\n... 1 more row\n"));
+
+        // 完整的数据仍在结果集中
+        assert_eq!(rs.rows.len(), 5);
+        assert_eq!(rs.rows[4][1], Value::String("x".repeat(100)));
+        rs.limits = DisplayLimits::UNLIMITED;
+        let text = rs.to_string();
+        assert!(text.contains(&"x".repeat(100)) && !text.contains("more row"));
+    }
+
+    #[test]
+    fn test_display_widths_count_characters() {
+        let rows = vec![
+            vec![Value::Int(1), Value::String("数据库".to_string())],
+            vec![Value::Int(2), Value::String("数据库引擎测试".to_string())],
+        ];
+        let mut rs = ResultSet::new(vec!["编号".to_string(), "名称".to_string()], rows);
+
+        // 多字节字符按字符数对齐，不按字节数
+        let text = rs.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "| 编号  | 名称      |");
+        assert_eq!(lines[2], "| 1   | 数据库     |");
+        assert_eq!(lines[3], "| 2   | 数据库引擎测试 |");
+
+        // 截断后的宽度同样按字符数计算
+        rs.limits = DisplayLimits {
+            max_rows: None,
+            max_width: Some(5),
+        };
+        let text = rs.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[3], "| 2   | 数据... |");
+        let width = lines[0].chars().count();
+        assert!(lines.iter().all(|line| line.chars().count() == width), "{}", text);
+    }
+
+    #[test]
+    fn test_oversized_row_warns_in_lenient_mode() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        let mut session = Session::new();
+        let mut executor = Executor::new(&mut storage, &mut session);
+        let planner = Planner::new();
+        let mut run = |sql: &str| {
+            let stmt = &Parser::parse_sql(&MySqlDialect {}, sql).unwrap()[0];
+            executor.execute(planner.plan(stmt)?)
+        };
+
+        // 每个字符按最多 4 字节估算，两列各占 20000 字节
+        run("CREATE TABLE wide (a VARCHAR(5000), b VARCHAR(5000))").unwrap();
+        let QueryResult::ResultSet(rs) = run("SHOW WARNINGS").unwrap() else {
+            panic!("SHOW WARNINGS 应返回结果集");
+        };
+        assert_eq!(rs.rows.len(), 1);
+        assert_eq!(rs.rows[0][0], Value::String("Warning".to_string()));
+        assert_eq!(rs.rows[0][1], Value::Int(1118));
+
+        // DESCRIBE 在表格后附带估算结果
+        let QueryResult::ResultSet(rs) = run("DESCRIBE wide").unwrap() else {
+            panic!("DESCRIBE 应返回结果集");
+        };
+        assert!(rs.comment.unwrap().contains("40009 字节"));
+
+        // 下一条语句清空警告
+        let QueryResult::ResultSet(rs) = run("SHOW WARNINGS").unwrap() else {
+            panic!("SHOW WARNINGS 应返回结果集");
+        };
+        assert!(rs.rows.is_empty());
+
+        // 可以放进单页的表不产生警告
+        run("CREATE TABLE narrow (a VARCHAR(100), b INT)").unwrap();
+        let QueryResult::ResultSet(rs) = run("SHOW WARNINGS").unwrap() else {
+            panic!("SHOW WARNINGS 应返回结果集");
+        };
+        assert!(rs.rows.is_empty());
+    }
+
+    #[test]
+    fn test_if_exists_and_if_not_exists() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        let mut session = Session::new();
+        let mut executor = Executor::new(&mut storage, &mut session);
+        let planner = Planner::new();
+        let mut run = |sql: &str| {
+            let stmt = &Parser::parse_sql(&MySqlDialect {}, sql).unwrap()[0];
+            executor.execute(planner.plan(stmt)?)
+        };
+        let warning_codes = |run: &mut dyn FnMut(&str) -> Result<QueryResult>| {
+            let QueryResult::ResultSet(rs) = run("SHOW WARNINGS").unwrap() else {
+                panic!("SHOW WARNINGS 应返回结果集");
+            };
+            rs.rows.into_iter().map(|row| row[1].clone()).collect::<Vec<_>>()
+        };
+
+        // 表不存在：IF NOT EXISTS 正常建表，IF EXISTS 删除时只留下 Note
+        run("CREATE TABLE IF NOT EXISTS t (id INT)").unwrap();
+        assert!(warning_codes(&mut run).is_empty());
+        run("INSERT INTO t VALUES (1)").unwrap();
+
+        // 表已存在：IF NOT EXISTS 不报错也不改动原表，不带时仍然报错
+        run("CREATE TABLE IF NOT EXISTS t (name VARCHAR(10))").unwrap();
+        assert_eq!(warning_codes(&mut run), vec![Value::Int(1050)]);
+        let QueryResult::ResultSet(rs) = run("SELECT * FROM t").unwrap() else {
+            panic!("预期结果集");
+        };
+        assert_eq!(rs.columns, vec!["id".to_string()]);
+        assert_eq!(rs.rows, vec![vec![Value::Int(1)]]);
+        assert!(run("CREATE TABLE t (id INT)").is_err());
+
+        // 表存在时 IF EXISTS 正常删除；不存在时不报错，只有不带 IF EXISTS 才报错
+        run("DROP TABLE IF EXISTS t").unwrap();
+        assert!(warning_codes(&mut run).is_empty());
+        run("DROP TABLE IF EXISTS t").unwrap();
+        assert_eq!(warning_codes(&mut run), vec![Value::Int(1051)]);
+        assert!(matches!(run("DROP TABLE t"), Err(DBError::Schema(_))));
+
+        // 多张表时只跳过不存在的那些
+        run("CREATE TABLE a (id INT)").unwrap();
+        run("DROP TABLE IF EXISTS missing, a").unwrap();
+        assert!(run("SELECT * FROM a").is_err());
+    }
+
+    #[test]
+    fn test_oversized_row_rejected_in_strict_mode() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        let mut session = Session::new();
+        session.set_sql_mode(crate::session::SqlMode::Strict);
+        let mut executor = Executor::new(&mut storage, &mut session);
+        let planner = Planner::new();
+
+        let sql = "CREATE TABLE wide (a VARCHAR(20000), b VARCHAR(20000))";
+        let stmt = &Parser::parse_sql(&MySqlDialect {}, sql).unwrap()[0];
+        assert!(executor.execute(planner.plan(stmt).unwrap()).is_err());
+        assert!(storage.get_table("wide").is_err());
+    }
+
+    #[test]
+    fn test_order_by_nulls_placement() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE scores (id INT, grp INT, score INT);
+             INSERT INTO scores VALUES (1, 1, 30), (2, 1, NULL), (3, 1, 10), (4, 2, NULL), (5, 2, 20);",
+        )
+        .unwrap();
+
+        let ids = |storage: &mut StorageEngine, order_by: &str| -> Vec<i32> {
+            query_rows(storage, &format!("SELECT id FROM scores ORDER BY {}", order_by))
+                .into_iter()
+                .map(|row| match row[0] {
+                    Value::Int(id) => id,
+                    ref other => panic!("预期整数，实际为 {:?}", other),
+                })
+                .collect()
+        };
+
+        // 未指定时保持原有行为：NULL 视为最小值
+        assert_eq!(ids(&mut storage, "score"), vec![2, 4, 3, 5, 1]);
+        assert_eq!(ids(&mut storage, "score DESC"), vec![1, 5, 3, 2, 4]);
+
+        assert_eq!(ids(&mut storage, "score ASC NULLS FIRST"), vec![2, 4, 3, 5, 1]);
+        assert_eq!(ids(&mut storage, "score ASC NULLS LAST"), vec![3, 5, 1, 2, 4]);
+        assert_eq!(ids(&mut storage, "score DESC NULLS FIRST"), vec![2, 4, 1, 5, 3]);
+        assert_eq!(ids(&mut storage, "score DESC NULLS LAST"), vec![1, 5, 3, 2, 4]);
+
+        // 作为第二排序键
+        assert_eq!(
+            ids(&mut storage, "grp DESC, score ASC NULLS LAST"),
+            vec![5, 4, 3, 1, 2]
+        );
+        assert_eq!(
+            ids(&mut storage, "grp, score DESC NULLS FIRST"),
+            vec![2, 1, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_order_by_expression_alias_and_position() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE items (id INT, price INT, quantity INT);
+             INSERT INTO items VALUES (1, 5, 2), (2, 3, 10), (3, 8, 0), (4, 1, 4);",
+        )
+        .unwrap();
+
+        let ids = |storage: &mut StorageEngine, sql: &str| -> Vec<Value> {
+            query_rows(storage, sql)
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect()
+        };
+        let ints = |ids: &[i32]| ids.iter().map(|&id| Value::Int(id)).collect::<Vec<_>>();
+
+        // 按表达式排序
+        assert_eq!(
+            ids(
+                &mut storage,
+                "SELECT id FROM items ORDER BY price * quantity DESC"
+            ),
+            ints(&[2, 1, 4, 3])
+        );
+        assert_eq!(
+            ids(
+                &mut storage,
+                "SELECT id FROM items ORDER BY price * quantity"
+            ),
+            ints(&[3, 4, 1, 2])
+        );
+
+        // 按 SELECT 中的别名和列位置排序
+        let rows = query_rows(
+            &mut storage,
+            "SELECT id, price * quantity AS total FROM items ORDER BY total DESC LIMIT 2",
+        );
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Int(2), Value::Int(30)],
+                vec![Value::Int(1), Value::Int(10)]
+            ]
+        );
+        assert_eq!(
+            ids(&mut storage, "SELECT id, price FROM items ORDER BY 2"),
+            ints(&[4, 2, 1, 3])
+        );
+        assert_eq!(
+            ids(&mut storage, "SELECT * FROM items ORDER BY 2 DESC"),
+            ints(&[3, 1, 2, 4])
+        );
+
+        // 按列位置排序时 NULL 同样视为最小值
+        run_sql(
+            &mut storage,
+            "UPDATE items SET quantity = NULL WHERE id = 3;",
+        )
+        .unwrap();
+        assert_eq!(
+            ids(&mut storage, "SELECT id, quantity FROM items ORDER BY 2"),
+            ints(&[3, 1, 4, 2])
+        );
+        assert!(run_sql(&mut storage, "SELECT id FROM items ORDER BY 2").is_err());
+        assert!(run_sql(&mut storage, "SELECT id FROM items ORDER BY 0").is_err());
+
+        // 聚合查询和 UNION 的结果同样可以按表达式和列位置排序
+        run_sql(
+            &mut storage,
+            "INSERT INTO items VALUES (5, 5, 1), (6, 5, 3);",
+        )
+        .unwrap();
+        assert_eq!(
+            ids(
+                &mut storage,
+                "SELECT price, COUNT(*) FROM items GROUP BY price ORDER BY COUNT(*) DESC, price LIMIT 1"
+            ),
+            ints(&[5])
+        );
+        assert_eq!(
+            ids(
+                &mut storage,
+                "SELECT id FROM items WHERE id < 3 UNION SELECT price FROM items WHERE id = 3 ORDER BY 1 DESC"
+            ),
+            ints(&[8, 2, 1])
+        );
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE people (id INT, name VARCHAR(20), city VARCHAR(20));
+             INSERT INTO people VALUES (1, ' Alice ', 'Paris'), (2, 'bob', NULL), (3, 'Émile', 'Lyon');",
+        )
+        .unwrap();
+
+        let QueryResult::ResultSet(rs) = run_sql(
+            &mut storage,
+            "SELECT UPPER(TRIM(name)), LENGTH(name), CONCAT(id, '-', city) AS tag, \
+             SUBSTR(city, 2, 3) FROM people ORDER BY id",
+        )
+        .unwrap() else {
+            panic!("SELECT 应返回结果集");
+        };
+        assert_eq!(
+            rs.columns,
+            [
+                "UPPER(TRIM(name))",
+                "LENGTH(name)",
+                "tag",
+                "SUBSTR(city, 2, 3)"
+            ]
+        );
+        let string = |s: &str| Value::String(s.to_string());
+        assert_eq!(
+            rs.rows,
+            vec![
+                vec![
+                    string("ALICE"),
+                    Value::Int(7),
+                    string("1-Paris"),
+                    string("ari")
+                ],
+                vec![string("BOB"), Value::Int(3), Value::Null, Value::Null],
+                vec![
+                    string("ÉMILE"),
+                    Value::Int(5),
+                    string("3-Lyon"),
+                    string("yon")
+                ],
+            ]
+        );
+
+        // WHERE 和 GROUP BY 中的函数
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT id FROM people WHERE LOWER(SUBSTR(name, 1, 1)) = 'é'"
+            ),
+            vec![vec![Value::Int(3)]]
+        );
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT LENGTH(city), COUNT(*) FROM people GROUP BY LENGTH(city) ORDER BY 1"
+            ),
+            vec![
+                vec![Value::Null, Value::Int(1)],
+                vec![Value::Int(4), Value::Int(1)],
+                vec![Value::Int(5), Value::Int(1)],
+            ]
+        );
+
+        // UPPER / LOWER 不接受非字符串参数
+        let err = run_sql(&mut storage, "SELECT UPPER(id) FROM people").unwrap_err();
+        assert_eq!(err.to_string(), "UPPER 只能用于字符串，不能用于 INT");
+        assert!(run_sql(&mut storage, "SELECT CONCAT() FROM people").is_err());
+    }
+
+    #[test]
+    fn test_create_table_with_inline_indexes() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        // mysqldump 风格的建表语句
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (
+               `id` INT NOT NULL AUTO_INCREMENT,
+               `email` VARCHAR(100) NOT NULL,
+               `name` VARCHAR(50),
+               `city` VARCHAR(50),
+               PRIMARY KEY (`id`),
+               UNIQUE KEY `uk_email` (`email`),
+               KEY `idx_name` (`name`),
+               INDEX (`city`, `name`)
+             );",
+        )
+        .unwrap();
+
+        let rows = query_rows(&mut storage, "SHOW CREATE TABLE users");
+        assert_eq!(
+            rows[0][1],
+            Value::String(
+                "CREATE TABLE `users` (
+  `id` INT NOT NULL AUTO_INCREMENT,
+  `email` VARCHAR(100) NOT NULL,
+  `name` VARCHAR(50),
+  `city` VARCHAR(50),
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `uk_email` (`email`),
+  KEY `idx_name` (`name`),
+  KEY `city` (`city`,`name`)
+)"
+                .to_string()
+            )
+        );
+
+        // UNIQUE KEY 登记了唯一约束
+        run_sql(
+            &mut storage,
+            "INSERT INTO users (email, name) VALUES ('a@x.com', 'a')",
+        )
+        .unwrap();
+        let err = run_sql(
+            &mut storage,
+            "INSERT INTO users (email, name) VALUES ('a@x.com', 'b')",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Duplicate entry"));
+    }
+
+    #[test]
+    fn test_create_table_rolls_back_on_bad_index() {
+        let (mut storage, _temp_dir) = create_test_storage();
+
+        let err = run_sql(
+            &mut storage,
+            "CREATE TABLE t (id INT, name VARCHAR(10), KEY idx_name (name), KEY idx_x (missing))",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing"));
+        assert!(storage.get_table("t").is_err());
+
+        assert!(
+            run_sql(
+                &mut storage,
+                "CREATE TABLE t (a INT, b INT, UNIQUE KEY uk_ab (a, b))"
+            )
+            .is_err()
+        );
+        assert!(storage.get_table("t").is_err());
+
+        // 失败的建表不影响随后同名表的创建
+        run_sql(&mut storage, "CREATE TABLE t (id INT, KEY (id))").unwrap();
+        assert_eq!(storage.get_table_indexes("t").unwrap()[0].name, "id");
+    }
+
+    #[test]
+    fn test_int_display_width_is_cosmetic() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        let statements =
+            Parser::parse_sql(&MySqlDialect {}, "CREATE TABLE t (a INT(3), b INT)").unwrap();
+        let mut session = Session::new();
+        Executor::new(&mut storage, &mut session)
+            .execute(Planner::new().plan(&statements[0]).unwrap())
+            .unwrap();
+
+        // 只有声明了宽度的列产生提示
+        let warnings = session.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].level, WarningLevel::Note);
+        assert_eq!(warnings[0].code, 1681);
+
+        // 显示宽度不限制取值
+        run_sql(&mut storage, "INSERT INTO t VALUES (123456, 1)").unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT a FROM t"),
+            vec![vec![Value::Int(123456)]]
+        );
+
+        let types: Vec<_> = query_rows(&mut storage, "DESCRIBE t")
+            .into_iter()
+            .map(|row| row[1].clone())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                Value::String("INT(3)".to_string()),
+                Value::String("INT".to_string())
+            ]
+        );
+        let rows = query_rows(&mut storage, "SHOW CREATE TABLE t");
+        assert_eq!(
+            rows[0][1],
+            Value::String("CREATE TABLE `t` (\n  `a` INT(3),\n  `b` INT\n)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_duplicate_result_column_warning() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(&mut storage, "CREATE TABLE t (id INT, name VARCHAR(10))").unwrap();
+        run_sql(&mut storage, "INSERT INTO t VALUES (1, 'a')").unwrap();
+
+        // 重复的列名照常返回，同时产生警告
+        let mut session = Session::new();
+        for (sql, expected) in [
+            ("SELECT id, name AS ID FROM t", 1),
+            ("SELECT id, name FROM t", 0),
+        ] {
+            let statements = Parser::parse_sql(&MySqlDialect {}, sql).unwrap();
+            Executor::new(&mut storage, &mut session)
+                .execute(Planner::new().plan(&statements[0]).unwrap())
+                .unwrap();
+            let warnings = session.warnings();
+            assert_eq!(warnings.len(), expected, "{}", sql);
+            if let Some(warning) = warnings.first() {
+                assert_eq!(warning.level, WarningLevel::Warning);
+                assert_eq!(warning.code, 1060);
+                assert!(warning.message.contains("'ID'"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_columns_reported_before_reading() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT, name VARCHAR(10))",
+        )
+        .unwrap();
+
+        let cases = [
+            ("SELECT nosuchcol FROM users", "列 'nosuchcol' 不存在"),
+            ("SELECT id FROM users WHERE zz = 1", "列 'zz' 不存在"),
+            ("SELECT id FROM users ORDER BY qq", "排序列 'qq' 不存在"),
+            (
+                "SELECT a, UPPER(b) FROM users WHERE c = 1 AND a > 0 ORDER BY d",
+                "列 'a', 'b', 'c', 'd' 不存在",
+            ),
+            (
+                "UPDATE users SET zz = 1, name = yy WHERE xx = 1",
+                "列 'zz', 'yy', 'xx' 不存在",
+            ),
+            ("DELETE FROM users WHERE zz = 1", "列 'zz' 不存在"),
+        ];
+        // 空表和非空表报告同样的错误
+        for populated in [false, true] {
+            if populated {
+                run_sql(&mut storage, "INSERT INTO users VALUES (1, 'a'), (2, 'b')").unwrap();
+            }
+            for (sql, expected) in cases {
+                let err = run_sql(&mut storage, sql).unwrap_err();
+                assert_eq!(err.to_string(), expected, "{}", sql);
+            }
+        }
+        assert_eq!(query_rows(&mut storage, "SELECT id FROM users").len(), 2);
+
+        // ORDER BY 可以引用别名（不区分大小写）
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT id AS Num FROM users ORDER BY num DESC"
+            ),
+            vec![vec![Value::Int(2)], vec![Value::Int(1)]]
+        );
+    }
+
+    #[test]
+    fn test_where_alias_and_aggregate_errors() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE items (id INT, price INT);
+             INSERT INTO items VALUES (1, 3), (2, 8);",
+        )
+        .unwrap();
+
+        let err = run_sql(
+            &mut storage,
+            "SELECT price * 2 AS double FROM items WHERE double > 10",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("别名 'double'"), "{}", err);
+        assert!(err.to_string().contains("price * 2"), "{}", err);
+
+        for sql in [
+            "SELECT id FROM items WHERE COUNT(*) > 1",
+            "UPDATE items SET price = 0 WHERE SUM(price) > 1",
+            "DELETE FROM items WHERE id = MAX(id)",
+        ] {
+            let err = run_sql(&mut storage, sql).unwrap_err();
+            assert!(err.to_string().contains("HAVING"), "{}: {}", sql, err);
+        }
+        assert_eq!(query_rows(&mut storage, "SELECT id FROM items").len(), 2);
+
+        // 别名与表中的列同名时 WHERE 引用的是表中的列
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT id AS price FROM items WHERE price > 5"
+            ),
+            vec![vec![Value::Int(2)]]
+        );
+    }
+
+    #[test]
+    fn test_boolean_conditions_with_null() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE flags (id INT, active BOOLEAN);
+             INSERT INTO flags VALUES (1, TRUE), (2, FALSE), (3, NULL), (4, TRUE);",
+        )
+        .unwrap();
+
+        // 比较和 NOT 遵循三值逻辑，NULL 行不匹配；IS [NOT] TRUE/FALSE 把 NULL 当作既非真也非假
+        let cases = [
+            ("active", vec![1, 4]),
+            ("NOT active", vec![2]),
+            ("active = TRUE", vec![1, 4]),
+            ("active <> TRUE", vec![2]),
+            ("active IS TRUE", vec![1, 4]),
+            ("active IS FALSE", vec![2]),
+            ("active IS NOT TRUE", vec![2, 3]),
+            ("active IS NOT FALSE", vec![1, 3, 4]),
+            ("NOT active IS TRUE", vec![2, 3]),
+            ("(active = FALSE) IS NOT TRUE", vec![1, 3, 4]),
+            ("(active IS NULL) IS TRUE", vec![3]),
+            ("active IS TRUE OR id = 2", vec![1, 2, 4]),
+            ("active AND id > 1", vec![4]),
+        ];
+        for (condition, expected) in cases {
+            let ids: Vec<Value> = expected.into_iter().map(Value::Int).collect();
+            let rows = query_rows(
+                &mut storage,
+                &format!("SELECT id FROM flags WHERE {} ORDER BY id", condition),
+            );
+            let found: Vec<Value> = rows.into_iter().map(|row| row[0].clone()).collect();
+            assert_eq!(found, ids, "WHERE {}", condition);
+        }
+
+        // HAVING 中同样可用
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT active, COUNT(*) FROM flags GROUP BY active HAVING active IS NOT TRUE \
+                 ORDER BY active",
+            ),
+            vec![
+                vec![Value::Null, Value::Int(1)],
+                vec![Value::Boolean(false), Value::Int(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_like_escape_and_ilike() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE promos (id INT, label VARCHAR(20));
+             INSERT INTO promos VALUES (1, '50% off'), (2, '500 off'), (3, 'Sale_A'),
+                 (4, 'SALE-b'), (5, 'sale_c'), (6, NULL);",
+        )
+        .unwrap();
+
+        let ids = |storage: &mut StorageEngine, condition: &str| -> Vec<i32> {
+            query_rows(storage, &format!("SELECT id FROM promos WHERE {}", condition))
+                .into_iter()
+                .map(|row| match row[0] {
+                    Value::Int(id) => id,
+                    ref other => panic!("预期整数，实际为 {:?}", other),
+                })
+                .collect()
+        };
+
+        assert_eq!(ids(&mut storage, "label LIKE '50%'"), vec![1, 2]);
+        // 转义后的通配符只匹配字面字符
+        assert_eq!(ids(&mut storage, "label LIKE '50\\%%'"), vec![1]);
+        assert_eq!(ids(&mut storage, "label LIKE '50\\%%' ESCAPE '\\\\'"), vec![1]);
+        assert_eq!(ids(&mut storage, "label LIKE '50#%%' ESCAPE '#'"), vec![1]);
+        assert_eq!(ids(&mut storage, "label LIKE '%#_%' ESCAPE '#'"), vec![3, 5]);
+
+        // LIKE 区分大小写，ILIKE 不区分
+        assert_eq!(ids(&mut storage, "label LIKE 'sale%'"), vec![5]);
+        assert_eq!(ids(&mut storage, "label ILIKE 'sale%'"), vec![3, 4, 5]);
+        assert_eq!(ids(&mut storage, "label ILIKE 'SALE#_%' ESCAPE '#'"), vec![3, 5]);
+
+        // NOT 形式对 NULL 同样不成立
+        assert_eq!(ids(&mut storage, "label NOT ILIKE 'sale%'"), vec![1, 2]);
+        assert_eq!(ids(&mut storage, "label NOT LIKE 'sale%'"), vec![1, 2, 3, 4]);
+        assert_eq!(ids(&mut storage, "NOT label LIKE '5%' AND id < 5"), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_like_wildcard_positions() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT, name VARCHAR(20));
+             INSERT INTO users VALUES (1, 'Alice'), (2, 'Alan'), (3, 'Bob'), (4, 'Malia'),
+                 (5, 'Al'), (6, NULL);",
+        )
+        .unwrap();
+        let ids = |storage: &mut StorageEngine, condition: &str| -> Vec<Value> {
+            query_rows(storage, &format!("SELECT id FROM users WHERE {}", condition))
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect()
+        };
+        let expect = |list: &[i32]| list.iter().map(|&id| Value::Int(id)).collect::<Vec<_>>();
+
+        assert_eq!(ids(&mut storage, "name LIKE 'Al%'"), expect(&[1, 2, 5]));
+        assert_eq!(ids(&mut storage, "name LIKE '%li%'"), expect(&[1, 4]));
+        assert_eq!(ids(&mut storage, "name LIKE '%ce'"), expect(&[1]));
+        assert_eq!(ids(&mut storage, "name LIKE 'A_a_'"), expect(&[2]));
+        assert_eq!(ids(&mut storage, "name LIKE 'Al_%'"), expect(&[1, 2]));
+        assert_eq!(ids(&mut storage, "name NOT LIKE '%a%'"), expect(&[1, 3, 5]));
+
+        // 任一侧为 NULL 时 LIKE 和 NOT LIKE 都不成立
+        assert!(ids(&mut storage, "name LIKE NULL").is_empty());
+        assert!(ids(&mut storage, "name NOT LIKE NULL").is_empty());
+    }
+
+    #[test]
+    fn test_where_null_three_valued_logic() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE t (id INT, v INT);
+             INSERT INTO t VALUES (1, 5), (2, 7), (3, NULL);",
+        )
+        .unwrap();
+        let ids = |storage: &mut StorageEngine, condition: &str| -> Vec<Value> {
+            query_rows(storage, &format!("SELECT id FROM t WHERE {}", condition))
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect()
+        };
+        let expect = |list: &[i32]| list.iter().map(|&id| Value::Int(id)).collect::<Vec<_>>();
+
+        // v 为 NULL 的行在 v = 5 和 NOT (v = 5) 中都不匹配
+        assert_eq!(ids(&mut storage, "v = 5"), expect(&[1]));
+        assert_eq!(ids(&mut storage, "NOT (v = 5)"), expect(&[2]));
+        assert_eq!(ids(&mut storage, "v <> 5"), expect(&[2]));
+        assert_eq!(ids(&mut storage, "NOT (v = 5 AND id = 3)"), expect(&[1, 2]));
+        assert_eq!(ids(&mut storage, "v = 5 OR id = 3"), expect(&[1, 3]));
+        assert_eq!(ids(&mut storage, "NOT (v = 5 OR id = 1)"), expect(&[2]));
+        assert_eq!(ids(&mut storage, "NOT (v > 100)"), expect(&[1, 2]));
+        assert_eq!(ids(&mut storage, "v IS NULL OR NOT (v = 5)"), expect(&[2, 3]));
+
+        run_sql(&mut storage, "DELETE FROM t WHERE NOT (v = 5)").unwrap();
+        assert_eq!(ids(&mut storage, "id > 0"), expect(&[1, 3]));
+    }
+
+    #[test]
+    fn test_where_is_null_and_constant_conditions() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT, email VARCHAR(30));
+             INSERT INTO users VALUES (1, 'a@x.com'), (2, NULL), (3, 'c@x.com'), (4, NULL);",
+        )
+        .unwrap();
+        let ids = |storage: &mut StorageEngine, condition: &str| -> Vec<Value> {
+            query_rows(storage, &format!("SELECT id FROM users WHERE {}", condition))
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect()
+        };
+        let expect = |list: &[i32]| list.iter().map(|&id| Value::Int(id)).collect::<Vec<_>>();
+
+        assert_eq!(ids(&mut storage, "email IS NULL"), expect(&[2, 4]));
+        assert_eq!(ids(&mut storage, "email IS NOT NULL"), expect(&[1, 3]));
+        assert_eq!(ids(&mut storage, "NOT (email IS NULL)"), expect(&[1, 3]));
+        assert_eq!(ids(&mut storage, "email IS NULL AND id > 2"), expect(&[4]));
+        assert_eq!(ids(&mut storage, "true"), expect(&[1, 2, 3, 4]));
+        assert!(ids(&mut storage, "false").is_empty());
+        assert_eq!(ids(&mut storage, "true AND email IS NOT NULL"), expect(&[1, 3]));
+        assert_eq!(ids(&mut storage, "false OR email IS NULL"), expect(&[2, 4]));
+
+        // 同样的条件用于 UPDATE / DELETE
+        run_sql(&mut storage, "UPDATE users SET email = 'none' WHERE email IS NULL").unwrap();
+        assert!(ids(&mut storage, "email IS NULL").is_empty());
+        run_sql(&mut storage, "DELETE FROM users WHERE false").unwrap();
+        assert_eq!(ids(&mut storage, "true").len(), 4);
+        run_sql(&mut storage, "DELETE FROM users WHERE true").unwrap();
+        assert!(ids(&mut storage, "true").is_empty());
+    }
+
+    #[test]
+    fn test_update_set_expressions_use_row_values() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE scores (id INT, score INT, bonus INT, name VARCHAR(10), nick VARCHAR(3));
+             INSERT INTO scores VALUES (5, 10, 1, 'alice', NULL), (6, 20, NULL, 'bob', NULL);",
+        )
+        .unwrap();
+        let rows = |storage: &mut StorageEngine| {
+            query_rows(storage, "SELECT id, score, bonus, name, nick FROM scores ORDER BY id")
+        };
+
+        match run_sql(&mut storage, "UPDATE scores SET score = score + 1 WHERE id = 5").unwrap() {
+            QueryResult::Updated(summary) => assert_eq!((summary.matched, summary.changed), (1, 1)),
+            other => panic!("预期 Updated，实际为 {:?}", other),
+        }
+        // 右侧都按更新前的值求值：score 与 bonus 交换；NULL 参与运算得到 NULL
+        run_sql(&mut storage, "UPDATE scores SET score = bonus, bonus = score").unwrap();
+        run_sql(&mut storage, "UPDATE scores SET name = nick WHERE id = 6").unwrap();
+        assert_eq!(
+            rows(&mut storage),
+            vec![
+                vec![
+                    Value::Int(5),
+                    Value::Int(1),
+                    Value::Int(11),
+                    Value::String("alice".to_string()),
+                    Value::Null
+                ],
+                vec![Value::Int(6), Value::Null, Value::Int(20), Value::Null, Value::Null],
+            ]
+        );
+
+        // 类型和长度仍按目标列检查，出错时不修改任何行
+        let before = rows(&mut storage);
+        assert!(run_sql(&mut storage, "UPDATE scores SET score = name").is_err());
+        assert!(run_sql(&mut storage, "UPDATE scores SET nick = name WHERE id = 5").is_err());
+        assert!(run_sql(&mut storage, "UPDATE scores SET score = missing + 1").is_err());
+        assert_eq!(rows(&mut storage), before);
+    }
+
+    #[test]
+    fn test_update_skips_unchanged_rows() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(&mut storage, "CREATE TABLE t (id INT, status VARCHAR(10))").unwrap();
+        run_sql(
+            &mut storage,
+            "INSERT INTO t VALUES (1, 'active'), (2, 'active'), (3, 'idle')",
+        )
+        .unwrap();
+        storage.save().unwrap();
+        let pages_written = |storage: &StorageEngine| {
+            let database = storage.current_database().unwrap();
+            database.get_buffer_manager().pages_written()
+        };
+        let before = pages_written(&storage);
+
+        // 所有匹配行的值都不变：不写回，也没有脏页需要刷盘
+        let touch = "UPDATE t SET status = 'active' WHERE status = 'active'";
+        match run_sql(&mut storage, touch).unwrap() {
+            QueryResult::Updated(summary) => {
+                assert_eq!((summary.matched, summary.changed), (2, 0))
+            }
+            other => panic!("预期 Updated，实际为 {:?}", other),
+        }
+        storage.save().unwrap();
+        assert_eq!(pages_written(&storage), before);
+
+        // 只有真正变化的行计入 changed
+        let update = "UPDATE t SET status = 'idle' WHERE id >= 2";
+        match run_sql(&mut storage, update).unwrap() {
+            QueryResult::Updated(summary) => {
+                assert_eq!((summary.matched, summary.changed), (2, 1))
+            }
+            other => panic!("预期 Updated，实际为 {:?}", other),
+        }
+        storage.save().unwrap();
+        assert!(pages_written(&storage) > before);
+
+        match run_sql(&mut storage, "SELECT status FROM t ORDER BY id").unwrap() {
+            QueryResult::ResultSet(rs) => assert_eq!(
+                rs.rows,
+                vec![
+                    vec![Value::String("active".to_string())],
+                    vec![Value::String("idle".to_string())],
+                    vec![Value::String("idle".to_string())],
+                ]
+            ),
+            other => panic!("预期结果集，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_select_limit_offset() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(&mut storage, "CREATE TABLE t (id INT, name VARCHAR(10))").unwrap();
+        run_sql(
+            &mut storage,
+            "INSERT INTO t VALUES (3, 'c'), (1, 'a'), (5, 'e'), (2, 'b'), (4, 'd')",
+        )
+        .unwrap();
+
+        let select = |storage: &mut StorageEngine, sql: &str| match run_sql(storage, sql).unwrap() {
+            QueryResult::ResultSet(rs) => rs,
+            other => panic!("预期结果集，实际为 {:?}", other),
+        };
+        let ids = |rs: &ResultSet| -> Vec<Value> { rs.rows.iter().map(|row| row[0].clone()).collect() };
+
+        // 先排序再截取
+        let rs = select(&mut storage, "SELECT id FROM t ORDER BY id DESC LIMIT 2 OFFSET 1");
+        assert_eq!(ids(&rs), vec![Value::Int(4), Value::Int(3)]);
+        let rs = select(&mut storage, "SELECT id FROM t WHERE id > 1 ORDER BY id LIMIT 1, 2");
+        assert_eq!(ids(&rs), vec![Value::Int(3), Value::Int(4)]);
+
+        // 没有 ORDER BY 时按存储顺序截取
+        let rs = select(&mut storage, "SELECT id FROM t LIMIT 2");
+        assert_eq!(ids(&rs), vec![Value::Int(3), Value::Int(1)]);
+
+        // OFFSET 超出行数时返回空结果
+        let rs = select(&mut storage, "SELECT id FROM t ORDER BY id LIMIT 10 OFFSET 5");
+        assert!(rs.rows.is_empty());
+        let rs = select(&mut storage, "SELECT id FROM t LIMIT 10 OFFSET 100");
+        assert!(rs.rows.is_empty());
+
+        // LIMIT 0 返回空结果但保留列名
+        let rs = select(&mut storage, "SELECT id, name FROM t LIMIT 0");
+        assert!(rs.rows.is_empty());
+        assert_eq!(rs.columns, vec!["id".to_string(), "name".to_string()]);
+        let rs = select(&mut storage, "SELECT 1 LIMIT 0");
+        assert!(rs.rows.is_empty());
+        assert_eq!(rs.columns.len(), 1);
+    }
+
+    #[test]
+    fn test_union_limit_push_down_preserves_results() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE a (id INT, score INT, tag VARCHAR(8)); \
+             CREATE TABLE b (id INT, points INT, tag VARCHAR(8))",
+        )
+        .unwrap();
+
+        // 简单的线性同余生成器，分数取值范围小，保证有大量重复值和 NULL
+        let mut seed: u32 = 12345;
+        let mut next = move |bound: u32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % bound
+        };
+        for (table, rows) in [("a", 40), ("b", 25)] {
+            let values: Vec<String> = (0..rows)
+                .map(|id| {
+                    let score = match next(6) {
+                        0 => "NULL".to_string(),
+                        n => n.to_string(),
+                    };
+                    format!("({}, {}, 't{}')", id, score, next(3))
+                })
+                .collect();
+            let sql = format!("INSERT INTO {} VALUES {}", table, values.join(", "));
+            run_sql(&mut storage, &sql).unwrap();
+        }
+
+        let queries = [
+            "SELECT id, score FROM a UNION ALL SELECT id, points FROM b \
+             ORDER BY score DESC, id LIMIT 7",
+            "SELECT id, score FROM a UNION ALL SELECT id, points FROM b \
+             ORDER BY score LIMIT 5 OFFSET 30",
+            "SELECT tag, score FROM a WHERE id > 10 UNION ALL SELECT tag, points FROM b \
+             ORDER BY tag, score DESC LIMIT 4",
+            "SELECT score FROM a UNION SELECT points FROM b ORDER BY score LIMIT 3",
+            "SELECT tag FROM a UNION ALL SELECT tag FROM b UNION ALL SELECT tag FROM a \
+             ORDER BY tag DESC LIMIT 10 OFFSET 50",
+            "SELECT id, score FROM a UNION SELECT id, points FROM b UNION ALL \
+             SELECT id, score FROM a ORDER BY score, id LIMIT 12",
+        ];
+        let planner = Planner::new();
+        for sql in queries {
+            let stmt = &Parser::parse_sql(&MySqlDialect {}, sql).unwrap()[0];
+            let plan = planner.plan(stmt).unwrap();
+            let optimized = crate::planner::optimizer::optimize(planner.plan(stmt).unwrap());
+            if sql.contains("UNION ALL") {
+                assert_ne!(optimized.explain(), plan.explain(), "{}", sql);
+            }
+
+            let mut session = Session::new();
+            let mut executor = Executor::new(&mut storage, &mut session);
+            let expected = executor.execute(plan).unwrap();
+            let actual = executor.execute(optimized).unwrap();
+            match (expected, actual) {
+                (QueryResult::ResultSet(expected), QueryResult::ResultSet(actual)) => {
+                    assert_eq!(actual.columns, expected.columns, "{}", sql);
+                    assert_eq!(actual.rows, expected.rows, "{}", sql);
+                }
+                other => panic!("预期结果集，实际为 {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_union_and_explain() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE a (id INT, name VARCHAR(8)); CREATE TABLE b (id INT); \
+             INSERT INTO a VALUES (1, 'x'), (2, 'y'), (2, 'y'); INSERT INTO b VALUES (2), (3)",
+        )
+        .unwrap();
+
+        // UNION 去重，UNION ALL 保留全部行
+        let rows = query_rows(&mut storage, "SELECT id FROM a UNION SELECT id FROM b");
+        assert_eq!(rows, vec![vec![Value::Int(1)], vec![Value::Int(2)], vec![Value::Int(3)]]);
+        let rows = query_rows(&mut storage, "SELECT id FROM a UNION ALL SELECT id FROM b");
+        assert_eq!(rows.len(), 5);
+
+        // 列数不同、排序列不在结果中都是错误
+        assert!(run_sql(&mut storage, "SELECT id, name FROM a UNION SELECT id FROM b").is_err());
+        assert!(run_sql(&mut storage, "SELECT id FROM a UNION SELECT id FROM b ORDER BY name").is_err());
+
+        let rows = query_rows(
+            &mut storage,
+            "EXPLAIN SELECT id FROM a UNION ALL SELECT id FROM b ORDER BY id LIMIT 2",
+        );
+        let lines: Vec<Value> = rows.into_iter().map(|mut row| row.remove(0)).collect();
+        assert_eq!(lines[0], Value::String("Union ALL".to_string()));
+        assert_eq!(lines[3], Value::String("  Select FROM a".to_string()));
+    }
+
+    #[test]
+    fn test_explain_access_method() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        let values: Vec<String> = (1..=100).map(|id| format!("({}, 'u{}')", id, id)).collect();
+        run_sql(
+            &mut storage,
+            &format!(
+                "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20)); \
+                 INSERT INTO users VALUES {}",
+                values.join(", ")
+            ),
+        )
+        .unwrap();
+        let explain = |storage: &mut StorageEngine, sql: &str| -> Vec<String> {
+            query_rows(storage, sql)
+                .into_iter()
+                .map(|row| row[0].to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            explain(
+                &mut storage,
+                "EXPLAIN SELECT name FROM users WHERE name <> 'x' AND id = 2"
+            ),
+            vec![
+                "Select FROM users",
+                "  COLUMNS name",
+                "  WHERE",
+                "    AND",
+                "      name <> 'x'",
+                "      id = 2",
+                "  ACCESS primary key lookup (id = 2), estimated 1.0% of rows",
+            ]
+        );
+
+        // 范围取各项的交集，命中比例在 [min, max] 之间插值
+        let last_line = |storage: &mut StorageEngine, sql: &str| -> String {
+            explain(storage, sql).pop().unwrap()
+        };
+        assert_eq!(
+            last_line(
+                &mut storage,
+                "EXPLAIN SELECT * FROM users WHERE id > 10 AND 5 < id AND id <= 20"
+            ),
+            "  ACCESS primary key lookup (id > 10 AND id <= 20), estimated 10.1% of rows"
+        );
+        assert_eq!(
+            last_line(&mut storage, "EXPLAIN SELECT * FROM users WHERE id >= 50"),
+            "  ACCESS full scan, primary key range (id >= 50) estimated 50.5% of rows"
+        );
+
+        // 会话变量调整阈值或强制取行方式
+        assert_eq!(
+            last_line(
+                &mut storage,
+                "SET index_selectivity_threshold = 60; \
+                 EXPLAIN SELECT * FROM users WHERE id >= 50"
+            ),
+            "  ACCESS primary key lookup (id >= 50), estimated 50.5% of rows"
+        );
+        assert_eq!(
+            last_line(
+                &mut storage,
+                "SET access_path = SCAN; EXPLAIN SELECT * FROM users WHERE id = 2"
+            ),
+            "  ACCESS full scan, primary key range (id = 2) estimated 1.0% of rows"
+        );
+        assert_eq!(
+            last_line(
+                &mut storage,
+                "SET access_path = INDEX; EXPLAIN SELECT * FROM users WHERE id < 'x'"
+            ),
+            "  ACCESS full scan"
+        );
+        assert_eq!(
+            explain(
+                &mut storage,
+                "EXPLAIN SELECT * FROM users WHERE id = 1 OR id = 2"
+            )
+            .last()
+            .unwrap(),
+            "  ACCESS full scan"
+        );
+        assert_eq!(
+            explain(&mut storage, "EXPLAIN DELETE FROM users WHERE id > 1"),
+            vec!["Delete FROM users", "  WHERE id > 1", "  ACCESS full scan"]
+        );
+        assert_eq!(
+            explain(
+                &mut storage,
+                "EXPLAIN UPDATE users SET name = 'c' WHERE id = 1"
+            ),
+            vec![
+                "Update users",
+                "  SET name = 'c'",
+                "  WHERE id = 1",
+                "  ACCESS full scan",
+            ]
+        );
+
+        // EXPLAIN 不执行语句
+        assert_eq!(query_rows(&mut storage, "SELECT * FROM users").len(), 100);
+    }
+    #[test]
+    fn test_select_distinct() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE perf (id INT, score INT, grade FLOAT); \
+             INSERT INTO perf VALUES (1, 90, 1.5), (2, 80, 2.0), (3, 90, 1.5), (4, NULL, NULL), \
+             (5, 70, 2.0), (6, NULL, NULL), (1, 90, 1.5)",
+        )
+        .unwrap();
+        let ints = |values: &[Option<i32>]| -> Vec<Vec<Value>> {
+            values
+                .iter()
+                .map(|value| vec![value.map_or(Value::Null, Value::Int)])
+                .collect()
+        };
+
+        // 保留每组重复行中第一次出现的位置，NULL 之间视为重复
+        assert_eq!(
+            query_rows(&mut storage, "SELECT DISTINCT score FROM perf"),
+            ints(&[Some(90), Some(80), None, Some(70)])
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT DISTINCT grade FROM perf").len(),
+            3
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT DISTINCT * FROM perf").len(),
+            6
+        );
+
+        // 与 ORDER BY 一起使用时保持排序，LIMIT / OFFSET 按去重后的行计算
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT DISTINCT score FROM perf ORDER BY score DESC"
+            ),
+            ints(&[Some(90), Some(80), Some(70), None])
+        );
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT DISTINCT score FROM perf ORDER BY score LIMIT 2 OFFSET 1"
+            ),
+            ints(&[Some(70), Some(80)])
+        );
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT DISTINCT score FROM perf LIMIT 2 OFFSET 1"
+            ),
+            ints(&[Some(80), None])
+        );
+
+        // 聚合结果同样去重
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT DISTINCT COUNT(*) FROM perf GROUP BY score ORDER BY COUNT(*)"
+            ),
+            ints(&[Some(1), Some(2), Some(3)])
+        );
+    }
+
+    #[test]
+    fn test_order_by_spills_to_disk_with_tiny_sort_buffer() {
+        let (mut storage, temp_dir) = create_test_storage();
+        run_sql(&mut storage, "CREATE TABLE t (id INT, score INT)").unwrap();
+        let values: Vec<String> = (0..300)
+            .map(|id| match id % 5 {
+                0 => format!("({}, NULL)", id),
+                _ => format!("({}, {})", id, (id * 13) % 17),
+            })
+            .collect();
+        run_sql(&mut storage, &format!("INSERT INTO t VALUES {}", values.join(", "))).unwrap();
+
+        let sql = "SELECT id, score FROM t ORDER BY score DESC NULLS FIRST, id";
+        let select = |storage: &mut StorageEngine, sort_buffer_size: usize| {
+            let ast = Parser::parse_sql(&MySqlDialect {}, sql).unwrap();
+            let mut session = Session::new();
+            session.set_sort_buffer_size(sort_buffer_size);
+            match Executor::new(storage, &mut session)
+                .execute(Planner::new().plan(&ast[0]).unwrap())
+                .unwrap()
+            {
+                QueryResult::ResultSet(rs) => rs.rows,
+                other => panic!("预期结果集，实际为 {:?}", other),
+            }
+        };
+
+        let in_memory = select(&mut storage, crate::session::DEFAULT_SORT_BUFFER_SIZE);
+        let spilled = select(&mut storage, 64);
+        assert_eq!(spilled.len(), 300);
+        assert_eq!(spilled, in_memory);
+
+        // 溢出文件全部清理
+        let leftovers: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().starts_with(sort::SPILL_FILE_PREFIX))
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+    }
+
+    #[test]
+    fn test_select_group_by_having() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE perf (id INT, score INT, name VARCHAR(20)); \
+             INSERT INTO perf VALUES (1, 90, 'a'), (2, 80, 'b'), (3, 90, 'c'), (4, NULL, 'd'), \
+             (5, 80, 'e'), (6, 90, 'f'), (7, NULL, 'g')",
+        )
+        .unwrap();
+
+        // 非聚合列必须出现在 GROUP BY 中
+        let err = run_sql(&mut storage, "SELECT name, COUNT(*) FROM perf").unwrap_err();
+        assert!(err.to_string().contains("'name'"), "{}", err);
+        let err = run_sql(&mut storage, "SELECT name, COUNT(*) FROM perf GROUP BY score").unwrap_err();
+        assert!(err.to_string().contains("'name'"), "{}", err);
+
+        // NULL 归入同一组，组按首次出现的顺序排列
+        let rows = query_rows(&mut storage, "SELECT score, COUNT(*), SUM(id) FROM perf GROUP BY score");
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Int(90), Value::Int(3), Value::Int(10)],
+                vec![Value::Int(80), Value::Int(2), Value::Int(7)],
+                vec![Value::Null, Value::Int(2), Value::Int(11)],
+            ]
+        );
+
+        // HAVING 过滤分组，可以使用聚合函数或别名，结果可按别名排序
+        let rows = query_rows(
+            &mut storage,
+            "SELECT score, COUNT(*) FROM perf GROUP BY score HAVING COUNT(*) > 2",
+        );
+        assert_eq!(rows, vec![vec![Value::Int(90), Value::Int(3)]]);
+        let rows = query_rows(
+            &mut storage,
+            "SELECT score, MAX(id) AS top FROM perf WHERE id > 1 GROUP BY score \
+             HAVING top < 7 ORDER BY top DESC",
+        );
+        assert_eq!(
+            rows,
+            vec![vec![Value::Int(90), Value::Int(6)], vec![Value::Int(80), Value::Int(5)]]
+        );
+
+        // 没有 GROUP BY 时整表是一组，空表也返回一行；聚合忽略 NULL
+        let rows = query_rows(
+            &mut storage,
+            "SELECT COUNT(*), COUNT(score), MIN(score), AVG(score) FROM perf",
+        );
+        assert_eq!(
+            rows,
+            vec![vec![Value::Int(7), Value::Int(5), Value::Int(80), Value::Float(86.0)]]
+        );
+        let rows = query_rows(&mut storage, "SELECT COUNT(*), SUM(id) FROM perf WHERE id > 100");
+        assert_eq!(rows, vec![vec![Value::Int(0), Value::Null]]);
+        let rows = query_rows(&mut storage, "SELECT score FROM perf WHERE id > 100 GROUP BY score");
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn test_inner_join_column_resolution() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE users (id INT, name VARCHAR(20));
+             CREATE TABLE orders (id INT, user_id INT);
+             INSERT INTO users VALUES (1, 'a'), (2, 'b');
+             INSERT INTO orders VALUES (10, 1), (11, 1), (12, 3);",
+        )
+        .unwrap();
+
+        // 未限定但唯一的列名可以直接使用，表名也可以作为限定符
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT orders.id, name FROM orders JOIN users ON user_id = users.id \
+                 WHERE orders.id > 10"
+            ),
+            vec![vec![Value::Int(11), Value::String("a".to_string())]]
+        );
+
+        // 自连接必须用别名区分
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT x.id, y.id FROM users x JOIN users y ON x.id < y.id"
+            ),
+            vec![vec![Value::Int(1), Value::Int(2)]]
+        );
+
+        match run_sql(&mut storage, "SELECT * FROM orders o JOIN users u ON o.user_id = u.id")
+            .unwrap()
+        {
+            QueryResult::ResultSet(rs) => {
+                assert_eq!(rs.columns, vec!["o.id", "o.user_id", "u.id", "u.name"]);
+                assert_eq!(rs.rows.len(), 2);
+            }
+            other => panic!("预期结果集，实际为 {:?}", other),
+        }
+
+        let err = run_sql(&mut storage, "SELECT id FROM orders JOIN users ON user_id = users.id")
+            .unwrap_err();
+        assert!(err.to_string().contains("ambiguous"), "{}", err);
+        assert!(run_sql(&mut storage, "SELECT * FROM users JOIN users ON 1 = 1").is_err());
+        assert!(
+            run_sql(&mut storage, "SELECT * FROM users LEFT JOIN orders ON users.id = user_id")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_single_table_qualified_columns() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE t (a INT PRIMARY KEY, c INT);
+             INSERT INTO t VALUES (1, 10), (2, 20), (3, 30);",
+        )
+        .unwrap();
+
+        // 单表查询中列名可以用表名限定，有别名时用别名限定
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT t.a FROM t WHERE T.c > 15 ORDER BY t.a DESC"
+            ),
+            vec![vec![Value::Int(3)], vec![Value::Int(2)]]
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT x.a, x.c * 2 FROM t x WHERE x.a > 2"),
+            vec![vec![Value::Int(3), Value::Int(60)]]
+        );
+        assert_eq!(
+            query_rows(
+                &mut storage,
+                "SELECT x.c, COUNT(*) FROM t x GROUP BY x.c HAVING x.c < 20"
+            ),
+            vec![vec![Value::Int(10), Value::Int(1)]]
+        );
+        assert_eq!(
+            query_rows(&mut storage, "SELECT x.*, x.a FROM t x WHERE x.a = 1"),
+            vec![vec![Value::Int(1), Value::Int(10), Value::Int(1)]]
+        );
+
+        run_sql(&mut storage, "UPDATE t SET c = 1 WHERE t.a = 1").unwrap();
+        run_sql(&mut storage, "UPDATE t u SET u.c = u.c + 1 WHERE u.a = 2").unwrap();
+        run_sql(&mut storage, "DELETE FROM t WHERE t.a = 3").unwrap();
+        assert_eq!(
+            query_rows(&mut storage, "SELECT * FROM t"),
+            vec![
+                vec![Value::Int(1), Value::Int(1)],
+                vec![Value::Int(2), Value::Int(21)],
+            ]
+        );
+
+        // 限定的不是查询的表时报告列不存在；有别名后不能再用表名限定
+        for sql in [
+            "SELECT other.a FROM t",
+            "SELECT t.a FROM t x",
+            "SELECT a FROM t WHERE other.a = 1",
+            "UPDATE t SET c = 1 WHERE other.a = 1",
+        ] {
+            let err = run_sql(&mut storage, sql).unwrap_err();
+            assert!(err.to_string().contains("不存在"), "{}: {}", sql, err);
+        }
+    }
+
+    #[test]
+    fn test_read_only_executor_shares_storage() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE t (id INT PRIMARY KEY, v INT); \
+             INSERT INTO t VALUES (1, 10), (2, 20), (3, 30)",
+        )
+        .unwrap();
+
+        let read = |storage: &StorageEngine, sql: &str| {
+            let stmt = &Parser::parse_sql(&MySqlDialect {}, sql).unwrap()[0];
+            let mut session = Session::new();
+            Executor::read_only(storage, &mut session).execute(Planner::new().plan(stmt)?)
+        };
+
+        // 多个线程共享同一个存储引擎同时查询
+        let storage = &storage;
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (1..=4)
+                .map(|id| {
+                    scope.spawn(move || {
+                        let sql = format!("SELECT v FROM t WHERE id = {}", id % 3 + 1);
+                        match read(storage, &sql).unwrap() {
+                            QueryResult::ResultSet(rs) => rs.rows,
+                            other => panic!("预期结果集，实际为 {:?}", other),
+                        }
+                    })
+                })
+                .collect();
+            for (id, worker) in (1..=4).zip(workers) {
+                let expected = Value::Int((id % 3 + 1) * 10);
+                assert_eq!(worker.join().unwrap(), vec![vec![expected]]);
+            }
+        });
+
+        // 修改数据和改动存储引擎状态的语句都被拒绝，数据不变
+        for sql in [
+            "INSERT INTO t VALUES (4, 40)",
+            "SELECT * INTO copy FROM t",
+            "USE test_db",
+            "BEGIN",
+        ] {
+            let err = read(storage, sql).unwrap_err();
+            assert!(matches!(err, DBError::Forbidden(_)), "{}: {}", sql, err);
+        }
+        assert_eq!(storage.get_all_records("t").unwrap().len(), 3);
+        assert!(storage.get_table("copy").is_err());
+    }
+
+    #[test]
+    fn test_column_pruning_matches_full_rows() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        run_sql(
+            &mut storage,
+            "CREATE TABLE wide (id INT PRIMARY KEY, Name VARCHAR(10), age INT, city VARCHAR(10), \
+             score FLOAT, active BOOLEAN); \
+             INSERT INTO wide VALUES (1, 'ann', 30, 'rome', 1.5, TRUE), \
+             (2, 'bob', NULL, 'oslo', 2.5, FALSE), (3, 'cy', 25, 'rome', NULL, TRUE), \
+             (4, NULL, 41, NULL, 0.5, NULL), (5, 'dee', 30, 'oslo', 3.0, TRUE)",
+        )
+        .unwrap();
+
+        // 附加一个引用全部列、恒为真的条件，同一查询就会按完整的记录执行；
+        // 两种执行方式的输出必须逐字节相同
+        let all_columns = ["id", "name", "age", "city", "score", "active"]
+            .map(|col| format!("AND ({0} IS NULL OR {0} IS NOT NULL)", col))
+            .join(" ");
+        for query in [
+            "SELECT name FROM wide WHERE age > 20 {} ORDER BY city, id",
+            "SELECT UPPER(NAME) AS n, age FROM wide WHERE id > 0 {} ORDER BY n DESC LIMIT 3",
+            "SELECT DISTINCT city FROM wide WHERE 1 = 1 {}",
+            "SELECT city, COUNT(*) AS c, AVG(score) FROM wide WHERE id < 10 {} \
+             GROUP BY city HAVING c > 1 ORDER BY c",
+            "SELECT COUNT(*) FROM wide WHERE id <> 3 {}",
+            "SELECT id * 2, score, active FROM wide WHERE id = 3 {}",
+            "SELECT age FROM wide WHERE name LIKE '%o%' {} ORDER BY 1 LIMIT 1 OFFSET 0",
+        ] {
+            let pruned = run_sql(&mut storage, &query.replace("{}", "")).unwrap();
+            let full = run_sql(&mut storage, &query.replace("{}", &all_columns)).unwrap();
+            assert_eq!(pruned.to_string(), full.to_string(), "{}", query);
+        }
+        assert_eq!(
+            query_rows(&mut storage, "SELECT city FROM wide WHERE id = 2"),
+            vec![vec![Value::String("oslo".to_string())]]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::error::{DBError, Result};
+use crate::planner::{AggregateFunction, Condition, Expression, SelectItem};
+use crate::session::SqlMode;
+use crate::storage::table::{ColumnDef, Record, Value, ValueKey};
+use std::collections::BTreeMap;
+
+/// 一个分组：GROUP BY 表达式的取值和属于该组的记录
+struct Group {
+    key: Vec<Value>,
+    records: Vec<Record>,
+}
+
+/// 分组聚合：按 GROUP BY 分组，每组计算一行选择列，再用 HAVING 过滤
+///
+/// 结果行按各组第一次出现的顺序排列。调用前应已通过 `validate_select`，
+/// 因此选择列在聚合函数之外只会引用分组列。
+pub fn aggregate(
+    records: Vec<Record>,
+    columns: &[ColumnDef],
+    items: &[SelectItem],
+    group_by: &[Expression],
+    having: Option<&Condition>,
+    mode: SqlMode,
+) -> Result<Vec<Vec<Value>>> {
+    let mut rows = Vec::new();
+    for group in group_records(records, columns, group_by, mode)? {
+        let mut binder = Binder {
+            group: &group,
+            columns,
+            group_by,
+            aliases: Vec::new(),
+            mode,
+        };
+        let row = items
+            .iter()
+            .map(|item| binder.evaluate(&item.expr))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(having) = having {
+            // HAVING 可以引用选择列的别名
+            binder.aliases = items
+                .iter()
+                .zip(&row)
+                .filter_map(|(item, value)| Some((item.alias.as_deref()?, value.clone())))
+                .collect();
+            if !binder
+                .bind_condition(having)?
+                .evaluate(&Record::new(vec![]), &[], mode)?
+            {
+                continue;
+            }
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// 按 GROUP BY 表达式的取值把记录分组
+///
+/// 取值按 `Value::total_cmp` 判等，NULL 与 NULL 归入同一组。没有 GROUP BY 时全部记录
+/// （包括零条记录）构成一个组，所以对空表 `SELECT COUNT(*)` 仍返回一行。
+fn group_records(
+    records: Vec<Record>,
+    columns: &[ColumnDef],
+    group_by: &[Expression],
+    mode: SqlMode,
+) -> Result<Vec<Group>> {
+    if group_by.is_empty() {
+        return Ok(vec![Group {
+            key: Vec::new(),
+            records,
+        }]);
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut index: BTreeMap<Vec<ValueKey>, usize> = BTreeMap::new();
+    for record in records {
+        let key = group_by
+            .iter()
+            .map(|expr| expr.evaluate(&record, columns, mode))
+            .collect::<Result<Vec<_>>>()?;
+        let slot = *index
+            .entry(key.iter().cloned().map(ValueKey).collect())
+            .or_insert_with(|| {
+                groups.push(Group {
+                    key,
+                    records: Vec::new(),
+                });
+                groups.len() - 1
+            });
+        groups[slot].records.push(record);
+    }
+    Ok(groups)
+}
+
+/// 把表达式中的聚合函数、分组列和别名替换为当前组的取值
+struct Binder<'a> {
+    group: &'a Group,
+    columns: &'a [ColumnDef],
+    group_by: &'a [Expression],
+    aliases: Vec<(&'a str, Value)>,
+    mode: SqlMode,
+}
+
+impl Binder<'_> {
+    fn evaluate(&self, expr: &Expression) -> Result<Value> {
+        self.bind(expr)?
+            .evaluate(&Record::new(vec![]), &[], self.mode)
+    }
+
+    fn bind(&self, expr: &Expression) -> Result<Expression> {
+        if let Some(position) = self.group_by.iter().position(|group| group == expr) {
+            return Ok(Expression::Value(self.group.key[position].clone()));
+        }
+        Ok(match expr {
+            Expression::Aggregate { func, arg } => {
+                Expression::Value(self.compute(*func, arg.as_deref())?)
+            }
+            Expression::Column(name) => {
+                match self
+                    .aliases
+                    .iter()
+                    .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+                {
+                    Some((_, value)) => Expression::Value(value.clone()),
+                    // 不在 GROUP BY 中的列已被语义检查拒绝，这里只会是空组
+                    None => Expression::Value(match self.group.records.first() {
+                        Some(record) => expr.evaluate(record, self.columns, self.mode)?,
+                        None => Value::Null,
+                    }),
+                }
+            }
+            Expression::Value(value) => Expression::Value(value.clone()),
+            // 求值时报告子查询不能出现在 WHERE 之外
+            Expression::Subquery(plan) => Expression::Subquery(plan.clone()),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => Expression::Binary {
+                left: Box::new(self.bind(left)?),
+                operator: operator.clone(),
+                right: Box::new(self.bind(right)?),
+            },
+            Expression::Unary { operator, operand } => Expression::Unary {
+                operator: operator.clone(),
+                operand: Box::new(self.bind(operand)?),
+            },
+            Expression::Function { name, args } => Expression::Function {
+                name: *name,
+                args: args
+                    .iter()
+                    .map(|arg| self.bind(arg))
+                    .collect::<Result<_>>()?,
+            },
+        })
+    }
+
+    fn bind_condition(&self, condition: &Condition) -> Result<Condition> {
+        Ok(match condition {
+            Condition::Expression(expr) => Condition::Expression(self.bind(expr)?),
+            Condition::IsNull(expr) => Condition::IsNull(self.bind(expr)?),
+            Condition::IsNotNull(expr) => Condition::IsNotNull(self.bind(expr)?),
+            Condition::Constant(value) => Condition::Constant(*value),
+            Condition::Like {
+                expr,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => Condition::Like {
+                expr: self.bind(expr)?,
+                pattern: self.bind(pattern)?,
+                escape: *escape,
+                case_insensitive: *case_insensitive,
+                negated: *negated,
+            },
+            Condition::In { expr, set, negated } => Condition::In {
+                expr: self.bind(expr)?,
+                set: set.clone(),
+                negated: *negated,
+            },
+            Condition::And(left, right) => Condition::And(
+                Box::new(self.bind_condition(left)?),
+                Box::new(self.bind_condition(right)?),
+            ),
+            Condition::Or(left, right) => Condition::Or(
+                Box::new(self.bind_condition(left)?),
+                Box::new(self.bind_condition(right)?),
+            ),
+            Condition::Not(inner) => Condition::Not(Box::new(self.bind_condition(inner)?)),
+            Condition::IsTruth {
+                operand,
+                value,
+                negated,
+            } => Condition::IsTruth {
+                operand: Box::new(self.bind_condition(operand)?),
+                value: *value,
+                negated: *negated,
+            },
+        })
+    }
+
+    /// 在当前组上计算聚合函数，除 `COUNT(*)` 外都忽略 NULL
+    fn compute(&self, func: AggregateFunction, arg: Option<&Expression>) -> Result<Value> {
+        let Some(arg) = arg else {
+            return count_value(self.group.records.len());
+        };
+        let mut values = Vec::new();
+        for record in &self.group.records {
+            let value = arg.evaluate(record, self.columns, self.mode)?;
+            if !value.is_null() {
+                values.push(value);
+            }
+        }
+
+        match func {
+            AggregateFunction::Count => count_value(values.len()),
+            AggregateFunction::Sum => sum(func, &values),
+            AggregateFunction::Avg if values.is_empty() => Ok(Value::Null),
+            AggregateFunction::Avg => {
+                let mut total = 0.0;
+                for value in &values {
+                    total += match value {
+                        Value::Int(n) => f64::from(*n),
+                        Value::Float(f) => *f,
+                        other => return Err(not_numeric(func, other)),
+                    };
+                }
+                float_result(func, total / values.len() as f64)
+            }
+            AggregateFunction::Min => Ok(values
+                .into_iter()
+                .min_by(Value::total_cmp)
+                .unwrap_or(Value::Null)),
+            AggregateFunction::Max => Ok(values
+                .into_iter()
+                .max_by(Value::total_cmp)
+                .unwrap_or(Value::Null)),
+        }
+    }
+}
+
+fn count_value(count: usize) -> Result<Value> {
+    i32::try_from(count)
+        .map(Value::Int)
+        .map_err(|_| DBError::Execution("COUNT 结果超出 INT 范围".to_string()))
+}
+
+/// 求和，全部为 NULL（或没有行）时结果为 NULL；整数求和溢出 INT 时报错
+fn sum(func: AggregateFunction, values: &[Value]) -> Result<Value> {
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    let mut int_total: i64 = 0;
+    let mut float_total: Option<f64> = None;
+    for value in values {
+        match value {
+            Value::Int(n) => int_total += i64::from(*n),
+            Value::Float(f) => *float_total.get_or_insert(0.0) += f,
+            other => return Err(not_numeric(func, other)),
+        }
+    }
+    match float_total {
+        Some(total) => float_result(func, total + int_total as f64),
+        None => i32::try_from(int_total)
+            .map(Value::Int)
+            .map_err(|_| DBError::Execution(format!("{} 结果超出 INT 范围", func))),
+    }
+}
+
+/// 浮点数求和的结果，溢出为无穷大时报错
+fn float_result(func: AggregateFunction, total: f64) -> Result<Value> {
+    if total.is_finite() {
+        Ok(Value::Float(total))
+    } else {
+        Err(DBError::Execution(format!("{} 结果超出 FLOAT 范围", func)))
+    }
+}
+
+fn not_numeric(func: AggregateFunction, value: &Value) -> DBError {
+    DBError::Execution(format!("{} 只能用于数值，不能用于 '{}'", func, value))
+}
@@ -0,0 +1,184 @@
+use crate::error::{DBError, Result};
+use crate::session::ExecutionLimits;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// 一条语句已经用掉的执行时间和扫描行数
+///
+/// 执行器在逐行处理记录之间检查，超过 [`ExecutionLimits`] 或取消标志被置位时中止语句。
+/// 存储层一次读出整张表，读取本身不会被打断。
+#[derive(Debug)]
+pub struct ExecutionBudget {
+    limits: ExecutionLimits,
+    /// 会话的取消标志，见 [`Session::set_cancel_flag`](crate::session::Session::set_cancel_flag)
+    cancel_flag: Option<Arc<AtomicBool>>,
+    started: Instant,
+    /// 已扫描的行数
+    examined: usize,
+    /// 已写入存储的行数，中止时报告给用户
+    applied: usize,
+    /// 语句是否在事务中执行：事务中已写入的行在 ROLLBACK 时撤销，否则不会回滚
+    in_transaction: bool,
+}
+
+impl ExecutionBudget {
+    pub fn new(
+        limits: ExecutionLimits,
+        cancel_flag: Option<Arc<AtomicBool>>,
+        in_transaction: bool,
+    ) -> Self {
+        Self {
+            limits,
+            cancel_flag,
+            started: Instant::now(),
+            examined: 0,
+            applied: 0,
+            in_transaction,
+        }
+    }
+
+    /// 扫描一行之前调用：计入扫描行数并检查限制
+    pub fn examine(&mut self) -> Result<()> {
+        // 先检查取消，报告的扫描行数不包括这一行
+        self.check_cancelled()?;
+        self.examined += 1;
+        self.check()
+    }
+
+    /// 记录一行已写入存储
+    pub fn record_write(&mut self) {
+        self.applied += 1;
+    }
+
+    /// 检查语句是否被取消，执行时间和扫描行数是否超过限制
+    pub fn check(&self) -> Result<()> {
+        self.check_cancelled()?;
+        let reason = if let Some(max_rows) = self.limits.max_rows
+            && self.examined > max_rows
+        {
+            format!("扫描的行数超过限制（{} 行）", max_rows)
+        } else if let Some(max_duration) = self.limits.max_duration
+            && self.started.elapsed() > max_duration
+        {
+            format!("执行时间超过限制（{} 毫秒）", max_duration.as_millis())
+        } else {
+            return Ok(());
+        };
+
+        Err(DBError::Execution(format!(
+            "查询已中止，{}{}",
+            reason,
+            self.applied_note()
+        )))
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        if !self
+            .cancel_flag
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+        {
+            return Ok(());
+        }
+        let mut message = "查询已取消".to_string();
+        if self.examined > 0 {
+            message.push_str(&format!("，已扫描 {} 行", self.examined));
+        }
+        Err(DBError::Cancelled(message + &self.applied_note()))
+    }
+
+    /// 中止前已经写入的行数说明，没有写入时为空
+    fn applied_note(&self) -> String {
+        if self.applied == 0 {
+            String::new()
+        } else if self.in_transaction {
+            format!("；中止前已修改 {} 行，ROLLBACK 时撤销", self.applied)
+        } else {
+            format!("；中止前已修改 {} 行，这些修改不会回滚", self.applied)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_budget_limits() {
+        let mut budget = ExecutionBudget::new(ExecutionLimits::default(), None, false);
+        for _ in 0..10_000 {
+            budget.examine().unwrap();
+        }
+
+        let mut budget = ExecutionBudget::new(
+            ExecutionLimits {
+                max_duration: None,
+                max_rows: Some(2),
+            },
+            None,
+            false,
+        );
+        budget.examine().unwrap();
+        budget.record_write();
+        budget.examine().unwrap();
+        budget.record_write();
+        let message = budget.examine().unwrap_err().to_string();
+        assert!(message.starts_with("查询已中止，扫描的行数超过限制（2 行）"), "{}", message);
+        assert!(message.contains("已修改 2 行"), "{}", message);
+
+        // 把开始时间提前，不需要真的等待
+        let mut budget = ExecutionBudget::new(
+            ExecutionLimits {
+                max_duration: Some(Duration::from_millis(5)),
+                max_rows: None,
+            },
+            None,
+            false,
+        );
+        budget.check().unwrap();
+        budget.started -= Duration::from_secs(1);
+        let message = budget.check().unwrap_err().to_string();
+        assert_eq!(message, "查询已中止，执行时间超过限制（5 毫秒）");
+        assert!(!message.contains("已修改"), "{}", message);
+    }
+
+    #[test]
+    fn test_budget_cancel_flag() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut budget =
+            ExecutionBudget::new(ExecutionLimits::default(), Some(Arc::clone(&flag)), false);
+        budget.check().unwrap();
+        budget.examine().unwrap();
+        budget.examine().unwrap();
+        budget.record_write();
+
+        flag.store(true, Ordering::Relaxed);
+        let err = budget.check().unwrap_err();
+        assert!(matches!(err, DBError::Cancelled(_)), "{:?}", err);
+        assert_eq!(
+            err.to_string(),
+            "查询已取消，已扫描 2 行；中止前已修改 1 行，这些修改不会回滚"
+        );
+
+        // 取消优先于执行限制
+        let budget = ExecutionBudget::new(
+            ExecutionLimits {
+                max_duration: None,
+                max_rows: Some(0),
+            },
+            Some(Arc::clone(&flag)),
+            false,
+        );
+        assert_eq!(budget.check().unwrap_err().to_string(), "查询已取消");
+
+        // 事务中已写入的行还可以 ROLLBACK
+        let mut budget = ExecutionBudget::new(ExecutionLimits::default(), Some(flag), true);
+        budget.record_write();
+        assert_eq!(
+            budget.check().unwrap_err().to_string(),
+            "查询已取消；中止前已修改 1 行，ROLLBACK 时撤销"
+        );
+    }
+}
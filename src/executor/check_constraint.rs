@@ -0,0 +1,77 @@
+use crate::error::{DBError, Result};
+use crate::planner::{Condition, Planner};
+use crate::session::SqlMode;
+use crate::storage::StorageEngine;
+use crate::storage::table::{CheckConstraint, ColumnDef, Record, Value};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::parser::Parser;
+
+/// 生成了计划的 CHECK 约束
+pub struct PlannedCheck {
+    constraint: CheckConstraint,
+    condition: Condition,
+}
+
+/// 表的全部 CHECK 约束，按定义顺序；目录中保存的条件文本在这里重新生成计划
+pub fn plan_checks(storage: &StorageEngine, table_name: &str) -> Result<Vec<PlannedCheck>> {
+    storage
+        .get_table_checks(table_name)?
+        .into_iter()
+        .map(|constraint| {
+            let condition = plan_condition(&constraint.expr)?;
+            Ok(PlannedCheck {
+                constraint,
+                condition,
+            })
+        })
+        .collect()
+}
+
+fn plan_condition(expr: &str) -> Result<Condition> {
+    let expr = Parser::new(&MySqlDialect {})
+        .try_with_sql(expr)?
+        .parse_expr()?;
+    Planner::new().analyze_condition(&expr)
+}
+
+/// 检查一行待写入的值（按表定义的列顺序）是否满足所有 CHECK 约束
+///
+/// 与 SQL 标准一样只有条件为假时才算违反，条件为 NULL（如比较的列为 NULL）时视为满足。
+pub fn check_row(
+    checks: &[PlannedCheck],
+    row: &[Value],
+    columns: &[ColumnDef],
+    mode: SqlMode,
+) -> Result<()> {
+    if checks.is_empty() {
+        return Ok(());
+    }
+    let record = Record::new(row.to_vec());
+    for check in checks {
+        if check.condition.truth(&record, columns, mode)? == Some(false) {
+            return Err(DBError::Execution(format!(
+                "Check constraint '{}' is violated: {}",
+                check.constraint.name, check.constraint.expr
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 删除列之前检查它是否被 CHECK 约束引用
+pub fn check_drop_column(storage: &StorageEngine, table_name: &str, column: &str) -> Result<()> {
+    for check in plan_checks(storage, table_name)? {
+        if check
+            .condition
+            .column_references()
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(column))
+        {
+            return Err(DBError::Schema(format!(
+                "Check constraint '{}' uses column '{}', hence column cannot be dropped or renamed.",
+                check.constraint.name, column
+            )));
+        }
+    }
+    Ok(())
+}
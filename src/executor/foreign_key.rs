@@ -0,0 +1,297 @@
+use crate::error::{DBError, Result};
+use crate::storage::StorageEngine;
+use crate::storage::table::{
+    ColumnDef, ForeignKey, IndexDef, Record, RecordId, Value, ValueKey, column_position,
+};
+use std::collections::BTreeSet;
+
+/// 引用某张表的一个外键列
+struct Referencing {
+    /// 引用表的表名，带与被引用表相同的数据库限定
+    table: String,
+    /// 引用列在引用表中的位置
+    index: usize,
+    column: ColumnDef,
+}
+
+impl Referencing {
+    fn foreign_key(&self) -> &ForeignKey {
+        self.column.references.as_ref().expect("引用列总是带有外键")
+    }
+}
+
+/// 与 `table_name` 在同一数据库中的另一张表，带与它相同的数据库限定
+fn sibling_table(table_name: &str, other: &str) -> String {
+    match table_name.split_once('.') {
+        Some((database, _)) => format!("{}.{}", database, other),
+        None => other.to_string(),
+    }
+}
+
+/// 错误信息中外键的写法，与 MySQL 的外键定义相同
+fn describe(table: &str, column: &str, foreign_key: &ForeignKey) -> String {
+    format!(
+        "`{}`, FOREIGN KEY (`{}`) REFERENCES `{}` (`{}`)",
+        table, column, foreign_key.table, foreign_key.column
+    )
+}
+
+/// 检查建表语句中的外键，返回被引用表名去掉数据库限定、大小写与已有的表一致的列定义
+///
+/// 被引用表必须与新表在同一个数据库中，也可以是新表自身；被引用列必须是主键或
+/// 单列唯一索引，类型与引用列相同（长度和显示宽度可以不同）。
+pub fn resolve_definitions(
+    storage: &StorageEngine,
+    name: &str,
+    columns: &[ColumnDef],
+    indexes: &[IndexDef],
+) -> Result<Vec<ColumnDef>> {
+    let (database, table) = storage.table_database(name)?;
+    let mut resolved = columns.to_vec();
+    for column in &mut resolved {
+        let Some(foreign_key) = &mut column.references else {
+            continue;
+        };
+        if let Some((referenced_database, referenced_table)) = foreign_key.table.split_once('.') {
+            if !referenced_database.eq_ignore_ascii_case(database.get_name()) {
+                return Err(DBError::Schema("外键只能引用同一数据库中的表".to_string()));
+            }
+            foreign_key.table = referenced_table.to_string();
+        }
+
+        let (referenced_columns, referenced_indexes) =
+            if foreign_key.table.eq_ignore_ascii_case(&table) {
+                foreign_key.table = table.clone();
+                (columns, indexes)
+            } else {
+                let referenced = database
+                    .get_table(database.resolve_table_name(&foreign_key.table))
+                    .map_err(|_| {
+                        DBError::Schema(format!(
+                            "添加外键失败：被引用的表 '{}' 不存在",
+                            foreign_key.table
+                        ))
+                    })?;
+                foreign_key.table = referenced.name().to_string();
+                (referenced.columns(), referenced.indexes())
+            };
+
+        let referenced = column_position(referenced_columns, &foreign_key.column)
+            .map(|index| &referenced_columns[index])
+            .ok_or_else(|| {
+                DBError::Schema(format!(
+                    "添加外键失败：被引用的表 '{}' 中没有列 '{}'",
+                    foreign_key.table, foreign_key.column
+                ))
+            })?;
+        let unique = referenced.unique
+            || referenced_indexes.iter().any(|index| {
+                index.unique && index.columns.len() == 1 && index.columns[0] == referenced.name
+            });
+        if !unique {
+            return Err(DBError::Schema(format!(
+                "添加外键失败：被引用的表 '{}' 中的列 '{}' 不是主键，也没有单列唯一索引",
+                foreign_key.table, referenced.name
+            )));
+        }
+        if std::mem::discriminant(&referenced.data_type)
+            != std::mem::discriminant(&column.data_type)
+        {
+            return Err(DBError::Schema(format!(
+                "外键的引用列 '{}' 与被引用列 '{}' 类型不兼容",
+                column.name, referenced.name
+            )));
+        }
+        foreign_key.column = referenced.name.clone();
+    }
+    Ok(resolved)
+}
+
+/// 找出外键值在被引用表中不存在的待写入行，返回行的下标和错误
+///
+/// NULL 不受外键约束。引用自身的表按写入后的内容检查：`replaced` 中被替换的旧行不算，
+/// 同一批写入的行可以互相引用。
+pub fn find_missing_references(
+    storage: &StorageEngine,
+    table_name: &str,
+    columns: &[ColumnDef],
+    rows: &[Vec<Value>],
+    replaced: &[RecordId],
+) -> Result<Vec<(usize, DBError)>> {
+    let mut missing: Vec<(usize, DBError)> = Vec::new();
+    for (index, column) in columns.iter().enumerate() {
+        let Some(foreign_key) = &column.references else {
+            continue;
+        };
+        let referenced_name = sibling_table(table_name, &foreign_key.table);
+        let referenced = storage.get_table(&referenced_name)?;
+        let self_reference = std::ptr::eq(referenced, storage.get_table(table_name)?);
+        let position =
+            column_position(referenced.columns(), &foreign_key.column).ok_or_else(|| {
+                DBError::Schema(format!("被引用的列 '{}' 不存在", foreign_key.column))
+            })?;
+
+        let kept = |record: &Record| {
+            !(self_reference && record.id().is_some_and(|id| replaced.contains(&id)))
+        };
+
+        // 被引用列是主键时逐个值按主键查找，只有普通的唯一列才需要扫描整张表
+        let by_primary_key = referenced.get_primary_key_index() == Some(position);
+        let mut existing: BTreeSet<ValueKey> = BTreeSet::new();
+        if !by_primary_key {
+            existing.extend(
+                storage
+                    .get_all_records(&referenced_name)?
+                    .iter()
+                    .filter(|record| kept(record))
+                    .map(|record| ValueKey(record.values()[position].clone())),
+            );
+        }
+        if self_reference {
+            existing.extend(rows.iter().map(|row| ValueKey(row[position].clone())));
+        }
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let value = &row[index];
+            let reported = missing.iter().any(|(reported, _)| *reported == row_index);
+            if value.is_null() || reported || existing.contains(&ValueKey(value.clone())) {
+                continue;
+            }
+            if by_primary_key
+                && storage
+                    .find_by_primary_key(&referenced_name, value)?
+                    .is_some_and(|record| kept(&record))
+            {
+                continue;
+            }
+            missing.push((
+                row_index,
+                DBError::Schema(format!(
+                    "不能插入或修改子表的行，违反外键约束 ({})",
+                    describe(table_name, &column.name, foreign_key)
+                )),
+            ));
+        }
+    }
+    missing.sort_by_key(|(row_index, _)| *row_index);
+    Ok(missing)
+}
+
+/// 删除或修改一批行之前检查它们原来的值是否仍被引用（RESTRICT）
+///
+/// `removed` 是被删除或修改的行，`replacements` 是修改后的值（删除时为空）。
+/// 修改后仍然存在的值不算被移除；引用自身的表按修改后的内容检查，一起删除的行不算。
+pub fn check_not_referenced(
+    storage: &StorageEngine,
+    table_name: &str,
+    removed: &[Record],
+    replacements: &[Vec<Value>],
+) -> Result<()> {
+    let referencing = referencing_columns(storage, table_name)?;
+    if referencing.is_empty() || removed.is_empty() {
+        return Ok(());
+    }
+    let table = storage.get_table(table_name)?;
+    let removed_ids: Vec<RecordId> = removed.iter().filter_map(Record::id).collect();
+
+    for child in &referencing {
+        let foreign_key = child.foreign_key();
+        let position = column_position(table.columns(), &foreign_key.column).ok_or_else(|| {
+            DBError::Schema(format!("被引用的列 '{}' 不存在", foreign_key.column))
+        })?;
+        let kept: BTreeSet<ValueKey> = replacements
+            .iter()
+            .map(|row| ValueKey(row[position].clone()))
+            .collect();
+        let gone: BTreeSet<ValueKey> = removed
+            .iter()
+            .map(|record| ValueKey(record.values()[position].clone()))
+            .filter(|value| !value.0.is_null() && !kept.contains(value))
+            .collect();
+        if gone.is_empty() {
+            continue;
+        }
+
+        let self_reference = std::ptr::eq(storage.get_table(&child.table)?, table);
+        let mut child_rows: Vec<Vec<Value>> = storage
+            .get_all_records(&child.table)?
+            .into_iter()
+            .filter(|record| {
+                !(self_reference && record.id().is_some_and(|id| removed_ids.contains(&id)))
+            })
+            .map(|record| record.values().to_vec())
+            .collect();
+        if self_reference {
+            child_rows.extend(replacements.iter().cloned());
+        }
+        if child_rows
+            .iter()
+            .any(|row| gone.contains(&ValueKey(row[child.index].clone())))
+        {
+            return Err(DBError::Schema(format!(
+                "不能删除或修改父表的行，违反外键约束 ({})",
+                describe(&child.table, &child.column.name, foreign_key)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 删除表之前检查是否有其它表引用它；一起删除的表和引用自身的外键不算
+pub fn check_drop_table(
+    storage: &StorageEngine,
+    table_name: &str,
+    dropped: &[String],
+) -> Result<()> {
+    let table = storage.get_table(table_name)?;
+    for child in referencing_columns(storage, table_name)? {
+        let child_table = storage.get_table(&child.table)?;
+        let also_dropped = dropped.iter().any(|name| {
+            storage
+                .get_table(name)
+                .is_ok_and(|dropped| std::ptr::eq(dropped, child_table))
+        });
+        if !std::ptr::eq(child_table, table) && !also_dropped {
+            return Err(DBError::Schema(format!(
+                "不能删除表 '{}'，表 '{}' 的外键引用了它",
+                table.name(),
+                child_table.name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 删除列之前检查它是否被外键引用
+pub fn check_drop_column(storage: &StorageEngine, table_name: &str, column: &str) -> Result<()> {
+    for child in referencing_columns(storage, table_name)? {
+        if child.foreign_key().column.eq_ignore_ascii_case(column) {
+            return Err(DBError::Schema(format!(
+                "不能删除列 '{}'，表 '{}' 的外键引用了它",
+                column, child.table
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 同一数据库中引用 `table_name` 的所有外键列，包括表自身引用自己的列
+fn referencing_columns(storage: &StorageEngine, table_name: &str) -> Result<Vec<Referencing>> {
+    let (database, table) = storage.table_database(table_name)?;
+    let mut referencing = Vec::new();
+    for name in database.get_table_names() {
+        for (index, column) in database.get_table(&name)?.columns().iter().enumerate() {
+            let references_table = column.references.as_ref().is_some_and(|foreign_key| {
+                database.resolve_table_name(&foreign_key.table) == table
+            });
+            if references_table {
+                referencing.push(Referencing {
+                    table: sibling_table(table_name, &name),
+                    index,
+                    column: column.clone(),
+                });
+            }
+        }
+    }
+    Ok(referencing)
+}
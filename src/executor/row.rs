@@ -0,0 +1,266 @@
+use super::ResultSet;
+use crate::error::{DBError, Result};
+use crate::storage::table::Value;
+
+/// 可以从结果中的 [`Value`] 取出的 Rust 类型，供 [`Row::get`] 使用
+///
+/// `Option<T>` 把 NULL 取成 `None`；其余类型遇到 NULL 报错。
+pub trait FromValue: Sized {
+    /// 转换失败时返回期望的 SQL 类型名
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str>;
+}
+
+impl FromValue for i32 {
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str> {
+        match value {
+            Value::Int(i) => Ok(*i),
+            _ => Err("INT"),
+        }
+    }
+}
+
+impl FromValue for f64 {
+    /// INT 按数值转换为浮点数
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str> {
+        match value {
+            Value::Float(f) => Ok(*f),
+            Value::Int(i) => Ok(*i as f64),
+            _ => Err("FLOAT"),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            _ => Err("VARCHAR"),
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str> {
+        match value {
+            Value::Boolean(b) => Ok(*b),
+            _ => Err("BOOLEAN"),
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str> {
+        Ok(value.clone())
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> std::result::Result<Self, &'static str> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+/// 结果集中的一行，可以按列名取值
+#[derive(Debug, Clone, Copy)]
+pub struct Row<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+}
+
+impl<'a> Row<'a> {
+    /// 按列名取出一个值并转换为 `T`
+    ///
+    /// 列不存在返回 [`DBError::NotFound`]，类型不符或非 `Option` 类型遇到 NULL
+    /// 返回 [`DBError::Execution`]。
+    pub fn get<T: FromValue>(&self, column: &str) -> Result<T> {
+        let value = self
+            .value(column)
+            .ok_or_else(|| DBError::NotFound(format!("结果中没有列 '{}'", column)))?;
+        T::from_value(value).map_err(|expected| {
+            DBError::Execution(format!(
+                "列 '{}' 的值 {} 是 {} 类型，不能按 {} 读取",
+                column,
+                value,
+                value.type_name(),
+                expected
+            ))
+        })
+    }
+
+    /// 按列名取出原始值，列不存在时返回 None
+    pub fn value(&self, column: &str) -> Option<&'a Value> {
+        column_index(self.columns, column).and_then(|index| self.values.get(index))
+    }
+
+    /// 按列的顺序排列的所有值
+    pub fn values(&self) -> &'a [Value] {
+        self.values
+    }
+}
+
+/// 列名的位置：先找完全相同的列名，再忽略大小写查找
+fn column_index(columns: &[String], name: &str) -> Option<usize> {
+    columns
+        .iter()
+        .position(|column| column == name)
+        .or_else(|| {
+            columns
+                .iter()
+                .position(|column| column.eq_ignore_ascii_case(name))
+        })
+}
+
+impl ResultSet {
+    /// 列名的位置：先找完全相同的列名，再忽略大小写查找
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        column_index(&self.columns, name)
+    }
+
+    /// 第 `index` 行（从 0 开始）
+    pub fn row(&self, index: usize) -> Option<Row<'_>> {
+        self.rows.get(index).map(|values| Row {
+            columns: &self.columns,
+            values,
+        })
+    }
+
+    /// 按顺序遍历所有行
+    ///
+    /// ```
+    /// # use simple_db::{DBConfig, SimpleDB};
+    /// use simple_db::executor::QueryResult;
+    ///
+    /// # let temp_dir = tempfile::TempDir::new().unwrap();
+    /// # let config = DBConfig {
+    /// #     base_dir: Some(temp_dir.path().to_string_lossy().to_string()),
+    /// #     db_name: Some("doc_db".to_string()),
+    /// #     no_query_history: true,
+    /// #     ..DBConfig::default()
+    /// # };
+    /// let mut db = SimpleDB::with_config(config)?;
+    /// db.execute_single_sql("CREATE TABLE users (id INT, name VARCHAR(20), score FLOAT)")?;
+    /// db.execute_single_sql("INSERT INTO users VALUES (1, 'alice', 9.5), (2, 'bob', NULL)")?;
+    ///
+    /// let QueryResult::ResultSet(rs) = db.execute_single_sql("SELECT * FROM users ORDER BY id")?
+    /// else {
+    ///     panic!("SELECT 应返回结果集");
+    /// };
+    /// let mut users = Vec::new();
+    /// for row in rs.iter() {
+    ///     let id: i32 = row.get("id")?;
+    ///     let name: String = row.get("name")?;
+    ///     let score: Option<f64> = row.get("score")?;
+    ///     users.push((id, name, score));
+    /// }
+    /// assert_eq!(
+    ///     users,
+    ///     vec![(1, "alice".to_string(), Some(9.5)), (2, "bob".to_string(), None)]
+    /// );
+    ///
+    /// // 类型不符时报错，而不是返回默认值
+    /// assert!(rs.row(0).unwrap().get::<i32>("name").is_err());
+    /// # Ok::<(), simple_db::error::DBError>(())
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Row<'_>> {
+        self.rows.iter().map(|values| Row {
+            columns: &self.columns,
+            values,
+        })
+    }
+
+    /// 取第 `row` 行 `column` 列的 INT 值，NULL 为 `None`
+    ///
+    /// ```
+    /// use simple_db::executor::ResultSet;
+    /// use simple_db::storage::table::Value;
+    ///
+    /// let rs = ResultSet::new(
+    ///     vec!["id".to_string(), "name".to_string()],
+    ///     vec![vec![Value::Int(7), Value::Null]],
+    /// );
+    /// assert_eq!(rs.get_int(0, "id")?, Some(7));
+    /// assert_eq!(rs.get_string(0, "name")?, None);
+    /// assert!(rs.get_string(0, "id").is_err());
+    /// assert!(rs.get_int(1, "id").is_err());
+    /// # Ok::<(), simple_db::error::DBError>(())
+    /// ```
+    pub fn get_int(&self, row: usize, column: &str) -> Result<Option<i32>> {
+        self.get(row, column)
+    }
+
+    /// 取第 `row` 行 `column` 列的字符串，NULL 为 `None`
+    pub fn get_string(&self, row: usize, column: &str) -> Result<Option<String>> {
+        self.get(row, column)
+    }
+
+    /// 取第 `row` 行 `column` 列的浮点数（INT 按数值转换），NULL 为 `None`
+    pub fn get_float(&self, row: usize, column: &str) -> Result<Option<f64>> {
+        self.get(row, column)
+    }
+
+    /// 取第 `row` 行 `column` 列的布尔值，NULL 为 `None`
+    pub fn get_bool(&self, row: usize, column: &str) -> Result<Option<bool>> {
+        self.get(row, column)
+    }
+
+    fn get<T: FromValue>(&self, row: usize, column: &str) -> Result<T> {
+        self.row(row)
+            .ok_or_else(|| {
+                DBError::NotFound(format!(
+                    "结果只有 {} 行，没有第 {} 行",
+                    self.rows.len(),
+                    row
+                ))
+            })?
+            .get(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_typed_access() {
+        let rs = ResultSet::new(
+            vec!["id".to_string(), "Price".to_string(), "ok".to_string()],
+            vec![
+                vec![Value::Int(1), Value::Float(2.5), Value::Boolean(true)],
+                vec![Value::Int(2), Value::Int(3), Value::Null],
+            ],
+        );
+        assert_eq!(rs.column_index("price"), Some(1));
+        assert_eq!(rs.column_index("missing"), None);
+
+        let rows: Vec<Row> = rs.iter().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get::<f64>("Price").unwrap(), 2.5);
+        assert_eq!(rows[1].get::<f64>("price").unwrap(), 3.0);
+        assert!(rows[0].get::<bool>("ok").unwrap());
+        assert_eq!(rows[1].get::<Option<bool>>("ok").unwrap(), None);
+        assert_eq!(rows[1].get::<Value>("ok").unwrap(), Value::Null);
+        assert_eq!(rows[1].values()[0], Value::Int(2));
+
+        // NULL 只能取成 Option，类型不符、列不存在都报错
+        assert!(matches!(
+            rows[1].get::<bool>("ok"),
+            Err(DBError::Execution(_))
+        ));
+        let err = rows[0].get::<i32>("Price").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "列 'Price' 的值 2.5 是 FLOAT 类型，不能按 INT 读取"
+        );
+        assert!(matches!(
+            rows[0].get::<i32>("nope"),
+            Err(DBError::NotFound(_))
+        ));
+
+        assert_eq!(rs.get_bool(0, "ok").unwrap(), Some(true));
+        assert_eq!(rs.get_float(1, "ok").unwrap(), None);
+        assert!(rs.get_int(2, "id").is_err());
+    }
+}
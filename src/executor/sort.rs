@@ -0,0 +1,617 @@
+use crate::error::{DBError, Result};
+use crate::planner::{
+    Expression, NullsOrder, OrderByItem, SelectColumns, SortDirection, SortTarget, resolve_column,
+    validate,
+};
+use crate::session::SqlMode;
+use crate::storage::io::page::Page;
+use crate::storage::table::record::RawRecord;
+use crate::storage::table::{ColumnDef, DataType, Record, Value};
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicU64};
+
+/// 排序溢出文件的文件名前缀，文件位于数据目录下
+pub const SPILL_FILE_PREFIX: &str = "sort-spill-";
+
+/// 溢出文件中每批记录的条数
+const SPILL_BATCH_ROWS: usize = 1024;
+
+/// 一次归并最多同时打开的有序段数，超过时先分组归并成更长的段
+const MERGE_FAN_IN: usize = 64;
+
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 解析后的排序键
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey {
+    column: usize,
+    direction: SortDirection,
+    nulls: NullsOrder,
+}
+
+/// 解析后的 ORDER BY
+///
+/// 不能直接对应某一列的排序项（表达式、以表达式为值的别名）在排序前求值，
+/// 依次追加在每条记录的末尾，排序键指向这些追加的位置；排序后再去掉。
+#[derive(Debug, Clone, Default)]
+pub struct SortSpec {
+    pub keys: Vec<SortKey>,
+    /// 记录的列定义，计算排序值时使用
+    columns: Vec<ColumnDef>,
+    /// 需要计算的排序值
+    computed: Vec<Expression>,
+}
+
+impl SortSpec {
+    /// 记录末尾追加计算出的排序值，没有需要计算的排序项时原样返回
+    pub fn prepare(&self, record: Record, mode: SqlMode) -> Result<Record> {
+        if self.computed.is_empty() {
+            return Ok(record);
+        }
+        let mut values = Vec::with_capacity(record.values().len() + self.computed.len());
+        for expr in &self.computed {
+            values.push(expr.evaluate(&record, &self.columns, mode)?);
+        }
+        let mut row = record.into_raw_data();
+        row.extend(values);
+        Ok(Record::new(row))
+    }
+
+    /// 去掉 [`prepare`](Self::prepare) 追加的排序值
+    pub fn strip(&self, record: Record) -> Record {
+        if self.computed.is_empty() {
+            return record;
+        }
+        let mut row = record.into_raw_data();
+        row.truncate(row.len() - self.computed.len());
+        Record::new(row)
+    }
+
+    fn push_key(&mut self, item: &OrderByItem, column: usize) {
+        self.keys.push(SortKey {
+            column,
+            direction: item.direction,
+            nulls: item.nulls,
+        });
+    }
+
+    /// 按表达式排序：列引用直接取该列，其余表达式追加为计算值
+    fn push_expression(&mut self, item: &OrderByItem, expr: &Expression) {
+        if let Expression::Column(name) = expr
+            && let Ok(column) = resolve_column(&self.columns, name)
+        {
+            return self.push_key(item, column);
+        }
+        self.computed.push(expr.clone());
+        let column = self.columns.len() + self.computed.len() - 1;
+        self.push_key(item, column);
+    }
+}
+
+/// 按表中的列解析非聚合查询的 ORDER BY
+///
+/// 列名先按 SELECT 列表中的别名查找（与 MySQL 一致），再按表中的列查找，都找不到时报错；
+/// 列位置指 SELECT 列表中的第几项，超出范围时报错。
+pub fn resolve_sort_keys(
+    order_items: &[OrderByItem],
+    columns: &[ColumnDef],
+    select_columns: &SelectColumns,
+) -> Result<SortSpec> {
+    let mut spec = SortSpec {
+        columns: columns.to_vec(),
+        ..SortSpec::default()
+    };
+    for item in order_items {
+        match (&item.target, select_columns) {
+            (SortTarget::Column(name), select_columns) => {
+                let aliased = match select_columns {
+                    SelectColumns::Columns(items) => items.iter().find(|select| {
+                        select
+                            .alias
+                            .as_deref()
+                            .is_some_and(|alias| alias.eq_ignore_ascii_case(name))
+                    }),
+                    SelectColumns::Wildcard => None,
+                    SelectColumns::Mixed(_) => unreachable!("通配符应该在前面已经展开"),
+                };
+                match aliased {
+                    Some(select) => spec.push_expression(item, &select.expr),
+                    None => spec.push_key(item, resolve_column(columns, name)?),
+                }
+            }
+            (SortTarget::Position(position), SelectColumns::Wildcard) => {
+                let column = validate::sort_position(*position, columns.len())?;
+                spec.push_key(item, column);
+            }
+            (SortTarget::Position(position), SelectColumns::Columns(items)) => {
+                let index = validate::sort_position(*position, items.len())?;
+                spec.push_expression(item, &items[index].expr);
+            }
+            (SortTarget::Expression { expr, .. }, _) => spec.push_expression(item, expr),
+            (SortTarget::Position(_), SelectColumns::Mixed(_)) => {
+                unreachable!("通配符应该在前面已经展开")
+            }
+        }
+    }
+    Ok(spec)
+}
+
+/// 按结果列解析 ORDER BY 项（聚合查询和 UNION 的排序作用于结果），找不到的列报错
+///
+/// 表达式中的列名指结果列名，见 [`validate::result_sort_position`]。
+pub fn resolve_result_sort_keys(order_items: &[OrderByItem], names: &[String]) -> Result<SortSpec> {
+    let mut spec = SortSpec {
+        columns: names
+            .iter()
+            .map(|name| ColumnDef {
+                name: name.clone(),
+                data_type: DataType::Varchar(Some(0)),
+                not_null: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                unique: false,
+                references: None,
+            })
+            .collect(),
+        ..SortSpec::default()
+    };
+    for item in order_items {
+        match validate::result_sort_position(names, &item.target)? {
+            Some(column) => spec.push_key(item, column),
+            None => {
+                if let SortTarget::Expression { expr, .. } = &item.target {
+                    spec.push_expression(item, expr);
+                }
+            }
+        }
+    }
+    Ok(spec)
+}
+
+/// 按排序键比较两条记录，内存排序和外部归并共用
+pub fn compare_records(a: &Record, b: &Record, keys: &[SortKey]) -> Ordering {
+    for key in keys {
+        let ordering = compare_values(
+            &a.values()[key.column],
+            &b.values()[key.column],
+            key.direction,
+            key.nulls,
+        );
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// 按排序方向比较两个值
+///
+/// 非 NULL 值按 [`Value::total_cmp`] 的全序比较；NULL 的位置完全由 `nulls` 决定，
+/// 不会随排序方向翻转。
+fn compare_values(a: &Value, b: &Value, direction: SortDirection, nulls: NullsOrder) -> Ordering {
+    let null_first = match nulls {
+        NullsOrder::First => Ordering::Less,
+        NullsOrder::Last => Ordering::Greater,
+    };
+
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => null_first,
+        (_, Value::Null) => null_first.reverse(),
+        _ => match direction {
+            SortDirection::Asc => a.total_cmp(b),
+            SortDirection::Desc => a.total_cmp(b).reverse(),
+        },
+    }
+}
+
+/// ORDER BY 的排序器
+///
+/// 记录先在内存中累积；估算大小超过 `budget` 字节时，把已累积的记录排好序写入
+/// 数据目录下的临时文件（一个有序段），最后对所有有序段做 k 路归并。
+/// 没有发生溢出时就是普通的内存排序。排序是稳定的：排序键相同的记录保持输入顺序。
+pub struct ExternalSorter {
+    keys: Vec<SortKey>,
+    budget: usize,
+    spill_dir: PathBuf,
+    buffer: Vec<Record>,
+    buffered_bytes: usize,
+    runs: Vec<SpillRun>,
+}
+
+impl ExternalSorter {
+    pub fn new(keys: Vec<SortKey>, budget: usize, spill_dir: &Path) -> Self {
+        Self {
+            keys,
+            budget,
+            spill_dir: spill_dir.to_path_buf(),
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    /// 加入一条记录，必要时溢出到磁盘
+    pub fn push(&mut self, record: Record) -> Result<()> {
+        self.buffered_bytes += Page::estimate_record_size(record.raw_data());
+        self.buffer.push(record);
+        if self.buffered_bytes > self.budget {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// 已写入磁盘的有序段数
+    #[cfg(test)]
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// 结束输入，按顺序返回全部记录
+    pub fn finish(mut self) -> Result<SortedRecords> {
+        if self.runs.is_empty() {
+            let keys = self.keys;
+            let mut records = self.buffer;
+            records.sort_by(|a, b| compare_records(a, b, &keys));
+            return Ok(SortedRecords::Memory(records.into_iter()));
+        }
+
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+        let mut runs = std::mem::take(&mut self.runs);
+        while runs.len() > MERGE_FAN_IN {
+            runs = self.merge_passes(runs)?;
+        }
+        Ok(SortedRecords::Merge(RunMerger::open(self.keys, runs)?))
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        let keys = &self.keys;
+        self.buffer.sort_by(|a, b| compare_records(a, b, keys));
+        let records = std::mem::take(&mut self.buffer);
+        self.buffered_bytes = 0;
+        let run = SpillRun::write(&self.spill_dir, records.into_iter().map(Ok))?;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    /// 相邻的有序段每 `MERGE_FAN_IN` 个归并为一段，保持段的先后顺序以维持稳定性
+    fn merge_passes(&self, runs: Vec<SpillRun>) -> Result<Vec<SpillRun>> {
+        let mut merged = Vec::new();
+        let mut runs = runs.into_iter();
+        loop {
+            let group: Vec<SpillRun> = runs.by_ref().take(MERGE_FAN_IN).collect();
+            match group.len() {
+                0 => break,
+                1 => merged.extend(group),
+                _ => {
+                    let merger = RunMerger::open(self.keys.clone(), group)?;
+                    merged.push(SpillRun::write(&self.spill_dir, merger)?);
+                }
+            }
+        }
+        Ok(merged)
+    }
+}
+
+/// 排序结果
+pub enum SortedRecords {
+    /// 未溢出，直接来自内存
+    Memory(std::vec::IntoIter<Record>),
+    /// 多个有序段的归并
+    Merge(RunMerger),
+}
+
+impl Iterator for SortedRecords {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            SortedRecords::Memory(records) => records.next().map(Ok),
+            SortedRecords::Merge(merger) => merger.next(),
+        }
+    }
+}
+
+/// 磁盘上的一个有序段，释放时删除文件
+struct SpillRun {
+    path: PathBuf,
+}
+
+impl SpillRun {
+    /// 把有序的记录按批写入新的临时文件
+    ///
+    /// 写入失败时返回错误，已创建的文件随 `SpillRun` 一起删除。
+    fn write<I>(dir: &Path, records: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = Result<Record>>,
+    {
+        let id = NEXT_SPILL_ID.fetch_add(1, atomic::Ordering::Relaxed);
+        let path = dir.join(format!(
+            "{}{}-{}.tmp",
+            SPILL_FILE_PREFIX,
+            std::process::id(),
+            id
+        ));
+        let file =
+            File::create(&path).map_err(|e| DBError::IO(format!("无法创建排序临时文件: {}", e)))?;
+        let run = Self { path };
+
+        let mut writer = BufWriter::new(file);
+        let mut batch: Vec<RawRecord> = Vec::with_capacity(SPILL_BATCH_ROWS);
+        for record in records {
+            batch.push(record?.into_raw_data());
+            if batch.len() == SPILL_BATCH_ROWS {
+                write_batch(&mut writer, &batch)?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            write_batch(&mut writer, &batch)?;
+        }
+        writer.flush()?;
+        Ok(run)
+    }
+}
+
+impl Drop for SpillRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn write_batch(writer: &mut BufWriter<File>, batch: &Vec<RawRecord>) -> Result<()> {
+    bincode::encode_into_std_write(batch, writer, bincode::config::standard())
+        .map_err(|e| DBError::IO(format!("无法写入排序临时文件: {}", e)))?;
+    Ok(())
+}
+
+/// 顺序读取一个有序段
+struct RunReader {
+    reader: BufReader<File>,
+    batch: std::vec::IntoIter<RawRecord>,
+    // 读完（或出错）后随读取器一起删除文件
+    _run: SpillRun,
+}
+
+impl RunReader {
+    fn open(run: SpillRun) -> Result<Self> {
+        let file = File::open(&run.path)
+            .map_err(|e| DBError::IO(format!("无法打开排序临时文件: {}", e)))?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            batch: Vec::new().into_iter(),
+            _run: run,
+        })
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        if let Some(values) = self.batch.next() {
+            return Ok(Some(Record::new(values)));
+        }
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        let batch: Vec<RawRecord> =
+            bincode::decode_from_std_read(&mut self.reader, bincode::config::standard())
+                .map_err(|e| DBError::IO(format!("无法读取排序临时文件: {}", e)))?;
+        self.batch = batch.into_iter();
+        Ok(self.batch.next().map(Record::new))
+    }
+}
+
+/// 有序段的 k 路归并
+///
+/// 每次取各段当前记录中最小的一条；相同时取靠前的段，与内存中的稳定排序结果一致。
+pub struct RunMerger {
+    keys: Vec<SortKey>,
+    readers: Vec<RunReader>,
+    heads: Vec<Option<Record>>,
+    failed: bool,
+}
+
+impl RunMerger {
+    fn open(keys: Vec<SortKey>, runs: Vec<SpillRun>) -> Result<Self> {
+        let mut readers = Vec::with_capacity(runs.len());
+        for run in runs {
+            readers.push(RunReader::open(run)?);
+        }
+        let mut heads = Vec::with_capacity(readers.len());
+        for reader in &mut readers {
+            heads.push(reader.next_record()?);
+        }
+        Ok(Self {
+            keys,
+            readers,
+            heads,
+            failed: false,
+        })
+    }
+}
+
+impl Iterator for RunMerger {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let mut smallest: Option<usize> = None;
+        for (index, head) in self.heads.iter().enumerate() {
+            let Some(record) = head else { continue };
+            let is_smaller = match smallest.and_then(|i| self.heads[i].as_ref()) {
+                Some(current) => compare_records(record, current, &self.keys) == Ordering::Less,
+                None => true,
+            };
+            if is_smaller {
+                smallest = Some(index);
+            }
+        }
+
+        let index = smallest?;
+        let record = self.heads[index].take();
+        match self.readers[index].next_record() {
+            Ok(next) => self.heads[index] = next,
+            Err(e) => {
+                self.failed = true;
+                return Some(Err(e));
+            }
+        }
+        record.map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::table::DataType;
+    use tempfile::TempDir;
+
+    fn columns() -> Vec<ColumnDef> {
+        ["id", "score", "name"]
+            .iter()
+            .zip([
+                DataType::Int(None),
+                DataType::Int(None),
+                DataType::Varchar(Some(32)),
+            ])
+            .map(|(name, data_type)| ColumnDef {
+                name: name.to_string(),
+                data_type,
+                not_null: false,
+                unique: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
+            })
+            .collect()
+    }
+
+    fn records(count: i32) -> Vec<Record> {
+        (0..count)
+            .map(|id| {
+                // 分数有大量重复和 NULL，用来检查稳定性和 NULL 的位置
+                let score = if id % 7 == 0 {
+                    Value::Null
+                } else {
+                    Value::Int((id * 37) % 11)
+                };
+                Record::new(vec![
+                    Value::Int(id),
+                    score,
+                    Value::String(format!("n{}", id)),
+                ])
+            })
+            .collect()
+    }
+
+    fn order_by(direction: SortDirection, nulls: NullsOrder) -> Vec<OrderByItem> {
+        vec![OrderByItem {
+            target: SortTarget::Column("score".to_string()),
+            direction,
+            nulls,
+        }]
+    }
+
+    fn spill_files(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with(SPILL_FILE_PREFIX))
+            .collect()
+    }
+
+    fn sort_with_budget(dir: &Path, keys: &[SortKey], budget: usize) -> (Vec<Record>, usize) {
+        let mut sorter = ExternalSorter::new(keys.to_vec(), budget, dir);
+        for record in records(500) {
+            sorter.push(record).unwrap();
+        }
+        let spilled = sorter.spilled_runs();
+        let sorted = sorter
+            .finish()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        (sorted, spilled)
+    }
+
+    #[test]
+    fn test_external_sort_matches_in_memory_sort() {
+        let temp_dir = TempDir::new().unwrap();
+        let columns = columns();
+
+        for (direction, nulls) in [
+            (SortDirection::Asc, NullsOrder::First),
+            (SortDirection::Desc, NullsOrder::Last),
+            (SortDirection::Desc, NullsOrder::First),
+        ] {
+            let keys = resolve_sort_keys(
+                &order_by(direction, nulls),
+                &columns,
+                &SelectColumns::Wildcard,
+            )
+            .unwrap()
+            .keys;
+            let (expected, spilled) = sort_with_budget(temp_dir.path(), &keys, usize::MAX);
+            assert_eq!(spilled, 0);
+
+            // 每条记录单独成段，段数超过一次归并的上限，会经过多轮归并
+            for budget in [1, 512] {
+                let (sorted, spilled) = sort_with_budget(temp_dir.path(), &keys, budget);
+                assert!(spilled > 1, "预算 {} 应发生溢出", budget);
+                let ids = |records: &[Record]| -> Vec<Value> {
+                    records.iter().map(|r| r.values()[0].clone()).collect()
+                };
+                assert_eq!(ids(&sorted), ids(&expected), "{:?} {:?}", direction, nulls);
+            }
+            assert!(spill_files(temp_dir.path()).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_spill_files_removed_when_dropped_early() {
+        let temp_dir = TempDir::new().unwrap();
+        let keys = resolve_sort_keys(
+            &order_by(SortDirection::Asc, NullsOrder::First),
+            &columns(),
+            &SelectColumns::Wildcard,
+        )
+        .unwrap()
+        .keys;
+
+        // 未调用 finish 就丢弃排序器
+        let mut sorter = ExternalSorter::new(keys.clone(), 1, temp_dir.path());
+        for record in records(10) {
+            sorter.push(record).unwrap();
+        }
+        assert!(!spill_files(temp_dir.path()).is_empty());
+        drop(sorter);
+        assert!(spill_files(temp_dir.path()).is_empty());
+
+        // 只读取了一部分结果
+        let mut sorter = ExternalSorter::new(keys, 1, temp_dir.path());
+        for record in records(10) {
+            sorter.push(record).unwrap();
+        }
+        let mut sorted = sorter.finish().unwrap();
+        sorted.next().unwrap().unwrap();
+        drop(sorted);
+        assert!(spill_files(temp_dir.path()).is_empty());
+
+        // 溢出目录不存在时报错，不留下文件
+        let missing = temp_dir.path().join("missing");
+        let mut sorter = ExternalSorter::new(Vec::new(), 1, &missing);
+        assert!(matches!(
+            sorter.push(records(1).remove(0)),
+            Err(DBError::IO(_))
+        ));
+        assert!(spill_files(temp_dir.path()).is_empty());
+    }
+}
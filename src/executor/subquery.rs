@@ -0,0 +1,85 @@
+use super::ResultSet;
+use crate::error::{DBError, Result};
+use crate::planner::{Condition, Expression, InSet, Plan};
+use crate::storage::table::Value;
+
+/// 执行一个子查询计划，返回它的结果集
+pub type RunSubquery<'a> = dyn FnMut(&Plan) -> Result<ResultSet> + 'a;
+
+/// 把条件中的子查询替换为它们的结果，每个子查询执行一次
+///
+/// 标量子查询必须恰好返回一行一列，替换为这个值；IN 的子查询必须返回一列，
+/// 替换为所有结果值（包括 NULL），逐行过滤时只做成员检查。
+pub fn resolve(condition: &mut Condition, run: &mut RunSubquery) -> Result<()> {
+    match condition {
+        Condition::Expression(expr) | Condition::IsNull(expr) | Condition::IsNotNull(expr) => {
+            resolve_expression(expr, run)
+        }
+        Condition::Like { expr, pattern, .. } => {
+            resolve_expression(expr, run)?;
+            resolve_expression(pattern, run)
+        }
+        Condition::In { expr, set, .. } => {
+            resolve_expression(expr, run)?;
+            if let InSet::Subquery(plan) = set {
+                let values = single_column(run(plan)?)?;
+                *set = InSet::Values(values);
+            }
+            Ok(())
+        }
+        Condition::And(left, right) | Condition::Or(left, right) => {
+            resolve(left, run)?;
+            resolve(right, run)
+        }
+        Condition::Not(inner) | Condition::IsTruth { operand: inner, .. } => resolve(inner, run),
+        Condition::Constant(_) => Ok(()),
+    }
+}
+
+fn resolve_expression(expr: &mut Expression, run: &mut RunSubquery) -> Result<()> {
+    match expr {
+        Expression::Subquery(plan) => {
+            let value = scalar_value(run(plan)?)?;
+            *expr = Expression::Value(value);
+            Ok(())
+        }
+        Expression::Binary { left, right, .. } => {
+            resolve_expression(left, run)?;
+            resolve_expression(right, run)
+        }
+        Expression::Unary { operand, .. } => resolve_expression(operand, run),
+        Expression::Aggregate { arg: Some(arg), .. } => resolve_expression(arg, run),
+        Expression::Function { args, .. } => args
+            .iter_mut()
+            .try_for_each(|arg| resolve_expression(arg, run)),
+        Expression::Column(_) | Expression::Value(_) | Expression::Aggregate { arg: None, .. } => {
+            Ok(())
+        }
+    }
+}
+
+/// 子查询结果中唯一一列的所有值
+fn single_column(result: ResultSet) -> Result<Vec<Value>> {
+    if result.columns.len() != 1 {
+        return Err(DBError::Execution(
+            "Operand should contain 1 column(s)".to_string(),
+        ));
+    }
+    Ok(result
+        .rows
+        .into_iter()
+        .map(|mut row| row.swap_remove(0))
+        .collect())
+}
+
+/// 标量子查询的值，结果必须恰好一行
+fn scalar_value(result: ResultSet) -> Result<Value> {
+    let mut values = single_column(result)?;
+    match values.len() {
+        1 => Ok(values.swap_remove(0)),
+        0 => Err(DBError::Execution("Subquery returns no rows".to_string())),
+        _ => Err(DBError::Execution(
+            "Subquery returns more than 1 row".to_string(),
+        )),
+    }
+}
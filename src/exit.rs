@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+/// 交互模式退出时对未保存更改的处理结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitDecision {
+    /// 退出（没有未保存的更改，或用户确认退出）
+    Exit,
+    /// 需要向用户确认，附带提示文本
+    Ask(String),
+    /// 取消退出，回到交互提示符
+    Stay,
+    /// 先保存再退出，保存失败时取消退出
+    SaveAndExit,
+}
+
+/// 决定退出前如何处理未保存的更改
+///
+/// `answer` 为 `None` 表示尚未询问：有未保存的更改时返回 [`ExitDecision::Ask`]。
+/// 回答 `y` 确认退出，`s` 保存后退出，其余（包括直接回车）都取消退出。
+pub fn decide_exit(dirty: bool, since_last_save: Duration, answer: Option<&str>) -> ExitDecision {
+    if !dirty {
+        return ExitDecision::Exit;
+    }
+    let Some(answer) = answer else {
+        return ExitDecision::Ask(format!(
+            "有未保存的更改（最后保存于 {} 分钟前），确认退出? (y/N/s=保存后退出) ",
+            since_last_save.as_secs() / 60
+        ));
+    };
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => ExitDecision::Exit,
+        "s" | "save" => ExitDecision::SaveAndExit,
+        _ => ExitDecision::Stay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide_exit() {
+        let minutes = |m: u64| Duration::from_secs(m * 60);
+
+        // 没有未保存的更改时不询问，回答也被忽略
+        assert_eq!(decide_exit(false, minutes(30), None), ExitDecision::Exit);
+        assert_eq!(
+            decide_exit(false, minutes(30), Some("n")),
+            ExitDecision::Exit
+        );
+
+        assert_eq!(
+            decide_exit(true, minutes(12) + Duration::from_secs(59), None),
+            ExitDecision::Ask(
+                "有未保存的更改（最后保存于 12 分钟前），确认退出? (y/N/s=保存后退出) ".to_string()
+            )
+        );
+        assert!(
+            matches!(decide_exit(true, Duration::from_secs(5), None), ExitDecision::Ask(p) if p.contains("0 分钟前"))
+        );
+
+        for (answer, expected) in [
+            ("y", ExitDecision::Exit),
+            (" Y\n", ExitDecision::Exit),
+            ("yes", ExitDecision::Exit),
+            ("s", ExitDecision::SaveAndExit),
+            ("S", ExitDecision::SaveAndExit),
+            ("n", ExitDecision::Stay),
+            ("", ExitDecision::Stay),
+            ("quit", ExitDecision::Stay),
+        ] {
+            assert_eq!(
+                decide_exit(true, minutes(1), Some(answer)),
+                expected,
+                "回答 {:?}",
+                answer
+            );
+        }
+    }
+}
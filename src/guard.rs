@@ -0,0 +1,33 @@
+use crate::planner::{Condition, Plan};
+
+/// 语句守卫：在计划生成（含常量折叠）之后、执行之前检查每条语句
+///
+/// 返回 `Err(message)` 时语句不执行，调用方得到 [`DBError::Forbidden`](crate::error::DBError::Forbidden)。
+/// 守卫需要是 `Sync` 的，多个线程通过 [`SimpleDB::execute_read_only`](crate::SimpleDB::execute_read_only)
+/// 同时查询时会共享它。
+pub type StatementGuard = Box<dyn Fn(&Plan) -> Result<(), String> + Send + Sync>;
+
+/// 只读守卫：拒绝所有修改数据或结构的语句，见 [`Plan::is_mutating`]
+pub fn read_only() -> StatementGuard {
+    Box::new(|plan| {
+        if plan.is_mutating() {
+            Err(format!("只读会话不允许执行 {}", plan.kind()))
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// 要求 WHERE 的守卫：拒绝没有条件的 UPDATE / DELETE
+///
+/// 守卫看到的是折叠后的计划，`WHERE 1=1` 这样恒真的条件同样被拒绝。
+pub fn require_where() -> StatementGuard {
+    Box::new(|plan| match plan {
+        Plan::Update { conditions, .. } | Plan::Delete { conditions, .. }
+            if matches!(conditions, None | Some(Condition::Constant(true))) =>
+        {
+            Err(format!("{} 必须带有限定行的 WHERE 条件", plan.kind()))
+        }
+        _ => Ok(()),
+    })
+}
@@ -6,6 +6,79 @@ use rustyline::hint::HistoryHinter;
 use rustyline::validate::MatchingBracketValidator;
 use rustyline_derive::{Completer, Helper, Hinter, Validator};
 use std::borrow::Cow::{self, Borrowed, Owned};
+use std::collections::BTreeMap;
+
+use crate::storage::StorageEngine;
+
+/// 补全用的库表名和列名快照
+///
+/// 交互模式在每条语句成功执行后重新生成（包括 USE 切换数据库），补全时不再访问存储引擎。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaSnapshot {
+    /// 当前数据库名
+    pub current: Option<String>,
+    /// 数据库名 -> 表名
+    pub databases: BTreeMap<String, Vec<String>>,
+    /// (数据库名, 表名) -> 按定义顺序排列的列名
+    pub columns: BTreeMap<(String, String), Vec<String>>,
+}
+
+impl SchemaSnapshot {
+    pub fn from_storage(storage: &StorageEngine) -> Self {
+        let mut databases = BTreeMap::new();
+        let mut columns = BTreeMap::new();
+        for name in storage.list_databases() {
+            let Ok(database) = storage.get_database(&name) else {
+                continue;
+            };
+            let mut tables = database.get_table_names();
+            tables.sort();
+            for table in &tables {
+                if let Ok(t) = database.get_table(table) {
+                    let names = t.columns().iter().map(|c| c.name.clone()).collect();
+                    columns.insert((name.clone(), table.clone()), names);
+                }
+            }
+            databases.insert(name, tables);
+        }
+        Self {
+            current: storage
+                .current_database()
+                .ok()
+                .map(|db| db.get_name().to_string()),
+            databases,
+            columns,
+        }
+    }
+
+    /// 当前数据库中的表名
+    pub fn current_tables(&self) -> &[String] {
+        self.current
+            .as_ref()
+            .and_then(|name| self.databases.get(name))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// 表的列名，`table` 可以带库名前缀，表名先精确匹配再忽略大小写匹配
+    pub fn table_columns(&self, table: &str) -> &[String] {
+        let (database, table) = match table.split_once('.') {
+            Some((database, table)) => (database, table),
+            None => match &self.current {
+                Some(current) => (current.as_str(), table),
+                None => return &[],
+            },
+        };
+        let Some(tables) = self.databases.get(database) else {
+            return &[];
+        };
+        tables
+            .iter()
+            .find(|name| *name == table)
+            .or_else(|| tables.iter().find(|name| name.eq_ignore_ascii_case(table)))
+            .and_then(|name| self.columns.get(&(database.to_string(), name.clone())))
+            .map_or(&[], Vec::as_slice)
+    }
+}
 
 #[derive(Helper, Completer, Hinter, Validator)]
 pub struct SQLHelper {
@@ -47,6 +120,15 @@ impl SQLHelper {
         self.colored_prompt = prompt;
     }
 
+    /// 更新补全用的库表名快照
+    pub fn set_schema(&mut self, schema: SchemaSnapshot) {
+        self.completer.schema = schema;
+    }
+
+    pub fn schema(&self) -> &SchemaSnapshot {
+        &self.completer.schema
+    }
+
     fn highlight_sql_syntax(&self, line: &str) -> String {
         let mut result = line.to_string();
 
@@ -131,13 +213,91 @@ impl Highlighter for SQLHelper {
 
 pub struct SQLCompleter {
     file_completer: FilenameCompleter,
+    schema: SchemaSnapshot,
 }
 
 impl SQLCompleter {
     pub fn new() -> Self {
         Self {
             file_completer: FilenameCompleter::new(),
+            schema: SchemaSnapshot::default(),
+        }
+    }
+
+    /// 表名补全：`库名.` 之后补全该库的表（保留库名前缀），否则补全当前库的表
+    fn table_candidates(&self, word: &str) -> Vec<String> {
+        if let Some((database, prefix)) = word.split_once('.') {
+            return self
+                .schema
+                .databases
+                .get(database)
+                .into_iter()
+                .flatten()
+                .filter(|table| starts_with_ignore_case(table, prefix))
+                .map(|table| format!("{}.{}", database, table))
+                .collect();
+        }
+        self.schema
+            .current_tables()
+            .iter()
+            .filter(|table| starts_with_ignore_case(table, word))
+            .cloned()
+            .collect()
+    }
+
+    /// 列名补全：补全当前行中 FROM / JOIN / UPDATE / INTO 之后出现的表的列
+    ///
+    /// `表名.` 或 `别名.` 之后只补全该表的列（保留前缀）。
+    fn column_candidates(&self, line: &str, word: &str) -> Vec<String> {
+        let tables = referenced_tables(line);
+        if let Some((qualifier, prefix)) = word.rsplit_once('.') {
+            let Some((table, _)) = tables.iter().find(|(table, alias)| {
+                alias.is_some_and(|alias| alias.eq_ignore_ascii_case(qualifier))
+                    || table.eq_ignore_ascii_case(qualifier)
+                    || table
+                        .rsplit_once('.')
+                        .is_some_and(|(_, name)| name.eq_ignore_ascii_case(qualifier))
+            }) else {
+                return Vec::new();
+            };
+            return self
+                .schema
+                .table_columns(table)
+                .iter()
+                .filter(|column| starts_with_ignore_case(column, prefix))
+                .map(|column| format!("{}.{}", qualifier, column))
+                .collect();
+        }
+
+        let mut candidates: Vec<String> = Vec::new();
+        for (table, _) in &tables {
+            for column in self.schema.table_columns(table) {
+                if starts_with_ignore_case(column, word) && !candidates.contains(column) {
+                    candidates.push(column.clone());
+                }
+            }
         }
+        candidates
+    }
+
+    /// 引出表名的关键字
+    const TABLE_CONTEXT: &'static [&'static str] = &["FROM", "JOIN", "INTO", "UPDATE", "TABLE"];
+
+    /// 之后跟列名的关键字
+    const COLUMN_CONTEXT: &'static [&'static str] =
+        &["SELECT", "WHERE", "SET", "AND", "OR", "ON", "BY", "HAVING"];
+
+    /// 光标前最近的子句关键字决定补全表名还是列名，都没有时补全表名
+    fn completes_columns(before_word: &str) -> bool {
+        tokens(before_word)
+            .into_iter()
+            .rev()
+            .map(str::to_ascii_uppercase)
+            .find(|token| {
+                Self::TABLE_CONTEXT.contains(&token.as_str())
+                    || Self::COLUMN_CONTEXT.contains(&token.as_str())
+            })
+            .is_some_and(|token| Self::COLUMN_CONTEXT.contains(&token.as_str()))
     }
 
     // SQL 关键字
@@ -232,7 +392,10 @@ impl SQLCompleter {
 
     // 元命令
     const META_COMMANDS: &'static [&'static str] =
-        &[".exit", ".quit", ".help", ".tables", ".schema", ".save"];
+        &[
+            ".exit", ".quit", ".help", ".tables", ".schema", ".save", ".watch", ".backup", ".restore",
+            ".format",
+        ];
 }
 
 impl Completer for SQLCompleter {
@@ -274,17 +437,97 @@ impl Completer for SQLCompleter {
             .map(|i| i + 1)
             .unwrap_or(0);
 
-        let prefix = &line_up_to_pos[word_start..].to_uppercase();
+        let word = &line_up_to_pos[word_start..];
+        let prefix = word.to_uppercase();
 
-        let matches: Vec<Pair> = Self::SQL_KEYWORDS
+        let keywords = Self::SQL_KEYWORDS
             .iter()
-            .filter(|&keyword| keyword.starts_with(prefix))
-            .map(|&keyword| Pair {
-                display: keyword.to_string(),
-                replacement: keyword.to_string(),
+            .filter(|&keyword| !word.contains('.') && keyword.starts_with(&prefix))
+            .map(|&keyword| keyword.to_string());
+        let names = if Self::completes_columns(&line_up_to_pos[..word_start]) {
+            self.column_candidates(line, word)
+        } else {
+            self.table_candidates(word)
+        };
+        let matches: Vec<Pair> = keywords
+            .chain(names)
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
             })
             .collect();
 
         Ok((word_start, matches))
     }
 }
+
+/// 按标识符切分，逗号单独作为一个词，忽略其它符号
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let is_name = c.is_alphanumeric() || c == '_' || c == '.';
+        match (is_name, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push(&text[s..i]);
+                start = None;
+            }
+            _ => {}
+        }
+        if c == ',' {
+            tokens.push(&text[i..i + 1]);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&text[s..]);
+    }
+    tokens
+}
+
+/// 行中 FROM / JOIN / UPDATE / INTO 之后引用的表及其别名，FROM 之后可以用逗号分隔多个表
+fn referenced_tables(line: &str) -> Vec<(&str, Option<&str>)> {
+    let tokens = tokens(line);
+    let is_keyword =
+        |token: &str| SQLCompleter::SQL_KEYWORDS.contains(&token.to_ascii_uppercase().as_str());
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let keyword = tokens[i].to_ascii_uppercase();
+        i += 1;
+        if !matches!(keyword.as_str(), "FROM" | "JOIN" | "UPDATE" | "INTO") {
+            continue;
+        }
+        while let Some(&table) = tokens.get(i) {
+            if table == "," || is_keyword(table) {
+                break;
+            }
+            i += 1;
+            if tokens
+                .get(i)
+                .is_some_and(|token| token.eq_ignore_ascii_case("AS"))
+            {
+                i += 1;
+            }
+            let alias = tokens
+                .get(i)
+                .copied()
+                .filter(|&token| token != "," && !is_keyword(token));
+            if alias.is_some() {
+                i += 1;
+            }
+            tables.push((table, alias));
+            if keyword != "FROM" || tokens.get(i) != Some(&",") {
+                break;
+            }
+            i += 1;
+        }
+    }
+    tables
+}
+
+fn starts_with_ignore_case(name: &str, prefix: &str) -> bool {
+    name.len() >= prefix.len()
+        && name.is_char_boundary(prefix.len())
+        && name[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
@@ -1,19 +1,45 @@
 use clap::Parser;
-use executor::QueryResult;
+use executor::{DisplayLimits, QueryResult};
 use sqlparser::dialect::MySqlDialect;
 use sqlparser::parser::Parser as SqlParser;
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant, SystemTime};
 
+pub mod client;
 pub mod error;
 pub mod executor;
+pub mod exit;
+pub mod guard;
 pub mod helper;
+pub mod observer;
+pub mod output;
 pub mod planner;
+pub mod script;
+pub mod server;
+pub mod session;
+pub mod settings;
 pub mod storage;
+#[doc(hidden)]
+pub mod testing;
+pub mod watch;
 
-use error::Result;
-use storage::StorageEngine;
+use error::{DBError, Result};
+use guard::StatementGuard;
+use helper::SchemaSnapshot;
+use observer::{StatementEvent, StatementObserver};
+use output::OutputFormat;
+use planner::Plan;
+use planner::check::{Checker, PlanSummary};
+use script::ScriptResult;
+use session::{ExecutionLimits, HistoryEntry, QUERY_HISTORY_TABLE, Session, SqlMode};
+use settings::ReplSettings;
+use storage::backup::{BackupSummary, OnlineBackup};
+use storage::table::CompactionSummary;
+use storage::{DataDirEntry, StorageEngine};
 
 /// Simple DB - 一个简单的数据库引擎
 #[derive(Parser)]
@@ -41,9 +67,78 @@ pub struct DBConfig {
     #[arg(short = 'i', long = "interactive")]
     pub interactive: bool,
 
-    /// 详细输出
+    /// 详细输出：把执行的语句、耗时和 DDL 等日志输出到标准错误
     #[arg(short = 'v', long = "verbose")]
     pub verbose: bool,
+
+    /// 不记录会话查询历史（_query_history）
+    #[arg(long = "no-query-history")]
+    pub no_query_history: bool,
+
+    /// 以 TCP 服务方式运行，监听指定地址（如 127.0.0.1:7878）
+    #[arg(long = "serve", value_name = "ADDR")]
+    pub serve: Option<String>,
+
+    /// .watch 的最大执行次数
+    #[arg(long = "watch-max-iterations", default_value_t = watch::DEFAULT_WATCH_MAX_ITERATIONS)]
+    pub watch_max_iterations: usize,
+
+    /// 查询结果的输出格式
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// 执行 SQL 文件时遇到第一条失败的语句就停止，并以非零状态退出
+    #[arg(long = "stop-on-error")]
+    pub stop_on_error: bool,
+
+    /// 单条语句的最长执行时间（毫秒），超过时中止语句，0 表示不限制
+    #[arg(long = "max-exec-millis", default_value_t = 0)]
+    pub max_exec_millis: u64,
+
+    /// 单条 SELECT / UPDATE / DELETE 最多扫描的行数，超过时中止语句，0 表示不限制
+    #[arg(long = "max-result-rows", default_value_t = 0)]
+    pub max_result_rows: usize,
+
+    /// 非交互模式下把查询结果写入指定文件，错误和其它提示仍输出到终端
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub output: Option<String>,
+
+    /// 安静模式：只输出查询结果和错误，不输出欢迎信息、保存提示等
+    #[arg(short = 'q', long = "quiet", conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// 交互模式的历史记录文件，默认为数据目录下的 simple_db_history.txt
+    #[arg(long = "history-file", value_name = "FILE")]
+    pub history_file: Option<String>,
+
+    /// 完整检查所有表的记录与表结构是否一致，输出每张表的结果后退出；发现问题时以非零状态退出
+    #[arg(long = "verify")]
+    pub verify: bool,
+}
+
+/// 与命令行不带任何参数时相同的配置
+impl Default for DBConfig {
+    fn default() -> Self {
+        Self {
+            sql_file: None,
+            base_dir: None,
+            db_name: None,
+            execute: None,
+            interactive: false,
+            verbose: false,
+            no_query_history: false,
+            serve: None,
+            watch_max_iterations: watch::DEFAULT_WATCH_MAX_ITERATIONS,
+            format: OutputFormat::Table,
+            stop_on_error: false,
+            max_exec_millis: 0,
+            max_result_rows: 0,
+            output: None,
+            quiet: false,
+            history_file: None,
+            verify: false,
+        }
+    }
 }
 
 impl DBConfig {
@@ -52,7 +147,11 @@ impl DBConfig {
     }
 
     pub fn get_run_mode(&self) -> RunMode {
-        if let Some(sql) = &self.execute {
+        if self.verify {
+            RunMode::Verify
+        } else if let Some(addr) = &self.serve {
+            RunMode::Serve(addr.clone())
+        } else if let Some(sql) = &self.execute {
             RunMode::SingleCommand(sql.clone())
         } else if self.interactive || self.sql_file.is_none() {
             RunMode::Interactive
@@ -69,11 +168,20 @@ pub enum RunMode {
     File(String),
     Interactive,
     SingleCommand(String),
+    Serve(String),
+    Verify,
 }
 
 pub struct SimpleDB {
     storage_engine: StorageEngine,
+    session: Session,
     config: DBConfig,
+    /// 嵌入方安装的语句守卫，见 [`SimpleDB::set_statement_guard`]
+    statement_guard: Option<StatementGuard>,
+    /// 嵌入方安装的语句观察者，见 [`SimpleDB::set_statement_observer`]
+    statement_observer: Option<StatementObserver>,
+    /// 交互模式下表格输出的行数和列宽限制（`.set maxrows`、`.set maxwidth`）
+    display_limits: DisplayLimits,
 }
 
 impl SimpleDB {
@@ -82,49 +190,247 @@ impl SimpleDB {
     }
 
     pub fn with_config(config: DBConfig) -> Result<Self> {
+        let mut session = Session::new();
+        session.set_query_history_enabled(!config.no_query_history);
+        session.set_execution_limits(ExecutionLimits {
+            max_duration: (config.max_exec_millis > 0)
+                .then(|| Duration::from_millis(config.max_exec_millis)),
+            max_rows: (config.max_result_rows > 0).then_some(config.max_result_rows),
+        });
+
+        let storage_engine = StorageEngine::new(
+            config.base_dir.as_deref().map(Path::new),
+            config.db_name.as_deref(),
+        )?;
+        for entry in storage_engine.databases() {
+            if let DataDirEntry::Skipped(skipped) = entry {
+                log::warn!("跳过数据目录中的 '{}'（{}）", skipped.name, skipped.reason);
+            }
+        }
+
         Ok(Self {
-            storage_engine: StorageEngine::new(
-                config.base_dir.as_deref().map(Path::new),
-                config.db_name.as_deref(),
-            )?,
+            storage_engine,
+            session,
             config,
+            statement_guard: None,
+            statement_observer: None,
+            display_limits: DisplayLimits::default(),
         })
     }
 
     pub fn from_args() -> Result<Self> {
         let config = DBConfig::from_args();
+        init_logging(config.verbose);
         Self::with_config(config)
     }
 
-    pub fn execute_sql_file(&mut self, file_path: &str) -> Result<Vec<Result<QueryResult>>> {
-        if self.config.verbose {
-            println!("正在读取文件: {}", file_path);
-        }
+    /// 逐条执行 SQL 文件中的语句，每条结果带有语句开始的行号
+    ///
+    /// 一条语句失败（包括语法错误）不影响后续语句；配置了 `stop_on_error` 时在第一条
+    /// 失败的语句之后停止。只有文件无法读取时整体返回错误。
+    pub fn execute_sql_file(&mut self, file_path: &str) -> Result<Vec<ScriptResult>> {
+        log::info!("正在读取文件: {}", file_path);
         let sql_content = fs::read_to_string(file_path)?;
-        self.execute_sql(&sql_content)
+
+        let mut results = Vec::new();
+        for statement in script::split_statements(&sql_content) {
+            let statement_results = match self.execute_sql(&statement.sql) {
+                Ok(statement_results) => statement_results,
+                Err(e) => vec![Err(e)],
+            };
+            let failed = statement_results.iter().any(|result| result.is_err());
+            results.extend(statement_results.into_iter().map(|result| ScriptResult {
+                line: statement.line,
+                result,
+            }));
+            if failed && self.config.stop_on_error {
+                break;
+            }
+        }
+        Ok(results)
     }
 
     pub fn execute_sql(&mut self, sql: &str) -> Result<Vec<Result<QueryResult>>> {
         let dialect = MySqlDialect {};
         let ast_statements = SqlParser::parse_sql(&dialect, sql)?;
 
-        let mut executor = executor::Executor::new(&mut self.storage_engine);
         let planner = planner::Planner::new();
 
         let mut results = Vec::new();
 
         for stmt in ast_statements {
-            if self.config.verbose {
-                println!("执行语句: {:?}", stmt);
+            let sql = stmt.to_string();
+            log::debug!("执行语句: {}", sql);
+            self.observe(&StatementEvent::Started { sql: &sql });
+            let planning_timer = Instant::now();
+            let plan = match planner.plan(&stmt) {
+                Ok(plan) => planner::optimizer::optimize(plan),
+                Err(e) => {
+                    self.observe(&StatementEvent::Finished {
+                        sql: &sql,
+                        elapsed: planning_timer.elapsed(),
+                        result: Err(&e),
+                    });
+                    return Err(e);
+                }
+            };
+            let planning = planning_timer.elapsed();
+
+            // 查询历史伪表本身的查询不记录，避免自我递归
+            let record = self.session.query_history_enabled()
+                && !matches!(&plan, Plan::Select { table_name: Some(name), .. } if name == QUERY_HISTORY_TABLE);
+            let started_at = SystemTime::now();
+            let timer = Instant::now();
+
+            let mut result = match self.authorize(&plan) {
+                Ok(()) => {
+                    // 失败的语句也可能已写入部分数据，一律视为有未保存的更改
+                    if plan.is_mutating() {
+                        self.storage_engine.mark_unsaved();
+                    }
+                    executor::Executor::new(&mut self.storage_engine, &mut self.session)
+                        .execute(plan)
+                }
+                Err(e) => Err(e),
+            };
+            let execution = timer.elapsed();
+            if let Ok(result) = &mut result {
+                result.set_elapsed(Some(planning + execution));
+            }
+            log::debug!(
+                "计划耗时 {:.3} 秒，执行耗时 {:.3} 秒",
+                planning.as_secs_f64(),
+                execution.as_secs_f64()
+            );
+            self.observe(&StatementEvent::Finished {
+                sql: &sql,
+                elapsed: planning + execution,
+                result: result.as_ref(),
+            });
+
+            if record {
+                self.session.record_query(HistoryEntry {
+                    statement: sql,
+                    started_at,
+                    duration: execution,
+                    rows: result.as_ref().map_or(0, QueryResult::row_count),
+                    success: result.is_ok(),
+                    forbidden: matches!(result, Err(DBError::Forbidden(_))),
+                });
             }
-            let plan = planner.plan(&stmt)?;
-            let result = executor.execute(plan);
             results.push(result);
         }
 
         Ok(results)
     }
 
+    /// 只读执行一段 SQL，只需要共享引用，多个线程可以各自带着自己的会话同时查询
+    ///
+    /// 语句使用传入的会话（会话变量、警告），不记入查询历史；修改数据或结构的语句
+    /// 以及 USE、BEGIN 等语句返回 [`DBError::Forbidden`]，见 [`executor::Executor::read_only`]。
+    /// 与写入并发时由调用方加读写锁，例如 `Arc<RwLock<SimpleDB>>`：查询持读锁，
+    /// [`execute_sql`](Self::execute_sql) 持写锁，写入会等到进行中的查询全部结束。
+    pub fn execute_read_only(
+        &self,
+        sql: &str,
+        session: &mut Session,
+    ) -> Result<Vec<Result<QueryResult>>> {
+        let dialect = MySqlDialect {};
+        let ast_statements = SqlParser::parse_sql(&dialect, sql)?;
+
+        let planner = planner::Planner::new();
+
+        let mut results = Vec::new();
+        for stmt in ast_statements {
+            let sql = stmt.to_string();
+            log::debug!("只读执行语句: {}", sql);
+            self.observe(&StatementEvent::Started { sql: &sql });
+            let timer = Instant::now();
+            let plan = match planner.plan(&stmt) {
+                Ok(plan) => planner::optimizer::optimize(plan),
+                Err(e) => {
+                    self.observe(&StatementEvent::Finished {
+                        sql: &sql,
+                        elapsed: timer.elapsed(),
+                        result: Err(&e),
+                    });
+                    return Err(e);
+                }
+            };
+            let mut result = self.authorize(&plan).and_then(|()| {
+                executor::Executor::read_only(&self.storage_engine, session).execute(plan)
+            });
+            let elapsed = timer.elapsed();
+            if let Ok(result) = &mut result {
+                result.set_elapsed(Some(elapsed));
+            }
+            self.observe(&StatementEvent::Finished {
+                sql: &sql,
+                elapsed,
+                result: result.as_ref(),
+            });
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    /// 安装语句守卫，之后每条语句在执行前都要经过它，替换已安装的守卫
+    ///
+    /// 被拒绝的语句不执行，返回 [`DBError::Forbidden`]，在查询历史中记为失败并标记 `forbidden`。
+    /// [`guard`] 模块提供了只读和要求 WHERE 两个现成的守卫。
+    pub fn set_statement_guard(&mut self, guard: StatementGuard) {
+        self.statement_guard = Some(guard);
+    }
+
+    /// 移除语句守卫
+    pub fn clear_statement_guard(&mut self) {
+        self.statement_guard = None;
+    }
+
+    /// 安装语句观察者，替换已安装的观察者
+    ///
+    /// [`execute_sql`](Self::execute_sql) 和 [`execute_read_only`](Self::execute_read_only)
+    /// 在每条语句开始和结束时调用它，事件中带有 SQL 文本、耗时和结果，见 [`StatementEvent`]。
+    pub fn set_statement_observer(&mut self, observer: StatementObserver) {
+        self.statement_observer = Some(observer);
+    }
+
+    /// 移除语句观察者
+    pub fn clear_statement_observer(&mut self) {
+        self.statement_observer = None;
+    }
+
+    fn observe(&self, event: &StatementEvent) {
+        if let Some(observer) = &self.statement_observer {
+            observer(event);
+        }
+    }
+
+    fn authorize(&self, plan: &Plan) -> Result<()> {
+        match &self.statement_guard {
+            Some(guard) => guard(plan).map_err(DBError::Forbidden),
+            None => Ok(()),
+        }
+    }
+
+    /// 只解析、生成计划并做语义检查，不执行任何语句，也不修改数据
+    ///
+    /// 每条语句得到一个 [`PlanSummary`]，计划或检查失败记录在该语句的 `error` 中，
+    /// 不影响后续语句；只有 SQL 无法解析时整体返回错误。
+    pub fn check_sql(&self, sql: &str) -> Result<Vec<PlanSummary>> {
+        let dialect = MySqlDialect {};
+        let ast_statements = SqlParser::parse_sql(&dialect, sql)?;
+
+        let planner = planner::Planner::new();
+        let mut checker = Checker::new(&self.storage_engine, self.session.is_strict())?
+            .with_truncate_long_strings(self.session.truncate_long_strings());
+        Ok(ast_statements
+            .iter()
+            .map(|stmt| checker.check(&planner, stmt))
+            .collect())
+    }
+
     pub fn execute_single_sql(&mut self, sql: &str) -> Result<QueryResult> {
         let results = self.execute_sql(sql)?;
         if let Some(result) = results.into_iter().next() {
@@ -134,76 +440,390 @@ impl SimpleDB {
         }
     }
 
+    /// 列出数据库中的表（按字母顺序），结果列与 `SHOW TABLES` 相同；`database` 为空时使用当前数据库
+    ///
+    /// 指定其他数据库时只读取其目录，不切换当前数据库。
+    pub fn show_tables(&self, database: Option<&str>) -> Result<QueryResult> {
+        let database = match database {
+            Some(name) => self.storage_engine.get_database(name)?,
+            None => self.storage_engine.current_database()?,
+        };
+        let mut names = database.get_table_names();
+        names.sort();
+        let rows = names
+            .into_iter()
+            .map(|name| vec![storage::table::Value::String(name)])
+            .collect();
+        Ok(QueryResult::ResultSet(executor::ResultSet::new(
+            vec![executor::tables_column(database.get_name())],
+            rows,
+        )))
+    }
+
+    /// 表的存储统计：行数、页数、字节数、平均页面使用率和已删除的槽位数
+    ///
+    /// `table_name` 为空时列出当前数据库的所有表（按字母顺序）。
+    pub fn table_stats(&self, table_name: Option<&str>) -> Result<QueryResult> {
+        let names = match table_name {
+            Some(name) => vec![name.to_string()],
+            None => {
+                let mut names = self.storage_engine.get_table_names()?;
+                names.sort();
+                names
+            }
+        };
+        let count = |n: usize| storage::table::Value::Int(i32::try_from(n).unwrap_or(i32::MAX));
+        let mut rows = Vec::with_capacity(names.len());
+        for name in names {
+            let stats = self.storage_engine.table_stats(&name)?;
+            rows.push(vec![
+                storage::table::Value::String(name),
+                count(stats.row_count),
+                count(stats.page_count),
+                count(stats.total_bytes),
+                storage::table::Value::String(format!(
+                    "{:.1}%",
+                    stats.avg_page_utilization * 100.0
+                )),
+                count(stats.deleted_slots),
+            ]);
+        }
+        let columns = [
+            "Table",
+            "Rows",
+            "Pages",
+            "Bytes",
+            "Page_utilization",
+            "Deleted_slots",
+        ];
+        Ok(QueryResult::ResultSet(executor::ResultSet::new(
+            columns.iter().map(|column| column.to_string()).collect(),
+            rows,
+        )))
+    }
+
+    /// 完整检查所有数据库中每张表的记录与表结构是否一致，返回每张表的结果和有问题的表数
+    ///
+    /// 检查记录的列数、每个值的类型和数据页能否读取，元数据与数据文件不同步
+    /// （保存中途崩溃、手工复制文件）时可以据此找出受影响的表。
+    pub fn verify(&self) -> (QueryResult, usize) {
+        let count = |n: usize| storage::table::Value::Int(i32::try_from(n).unwrap_or(i32::MAX));
+        let mut problems = 0;
+        let mut rows = Vec::new();
+        for (db_name, table, result) in self.storage_engine.verify_databases() {
+            let status = if result.is_consistent() {
+                "OK".to_string()
+            } else {
+                problems += 1;
+                result.first_problem.clone().unwrap_or_default()
+            };
+            rows.push(vec![
+                storage::table::Value::String(db_name),
+                storage::table::Value::String(table),
+                count(result.records_checked),
+                count(result.arity_mismatches),
+                count(result.type_mismatches),
+                count(result.unreadable_pages),
+                storage::table::Value::String(status),
+            ]);
+        }
+        let columns = [
+            "Database",
+            "Table",
+            "Records",
+            "Bad_arity",
+            "Bad_types",
+            "Unreadable_pages",
+            "Status",
+        ];
+        let result = QueryResult::ResultSet(executor::ResultSet::new(
+            columns.iter().map(|column| column.to_string()).collect(),
+            rows,
+        ));
+        (result, problems)
+    }
+
+    /// 不经过 SQL 直接向当前数据库的表批量写入行，值按表定义的列顺序给出，返回写入的行数
+    ///
+    /// 超过 VARCHAR 长度的字符串按会话的 `truncate_long_strings` 处理：默认整批拒绝，
+    /// 打开时截断并记录警告（可用 `SHOW WARNINGS` 查看）。
+    pub fn bulk_insert(
+        &mut self,
+        table_name: &str,
+        rows: Vec<Vec<storage::table::Value>>,
+    ) -> Result<usize> {
+        self.session.clear_warnings();
+        self.storage_engine.mark_unsaved();
+        let truncate = self.session.truncate_long_strings();
+        let (record_ids, truncations) =
+            self.storage_engine.batch_insert_records(table_name, rows, truncate)?;
+        for truncation in truncations {
+            self.session.add_warning(
+                session::WarningLevel::Warning,
+                1265,
+                format!(
+                    "Data truncated for column '{}' at row {}",
+                    truncation.column, truncation.row
+                ),
+            );
+        }
+        Ok(record_ids.len())
+    }
+
+    /// 交互补全使用的库表名和列名快照
+    pub fn schema_snapshot(&self) -> SchemaSnapshot {
+        SchemaSnapshot::from_storage(&self.storage_engine)
+    }
+
+    /// 当前会话（SQL 模式、警告等）
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    /// 保存所有数据库，有活动的事务时报错
     pub fn save(&mut self) -> Result<()> {
         self.storage_engine.save()
     }
 
+    /// 结束会话：与 MySQL 断开连接时一样回滚未提交的事务，然后保存
+    pub fn end_session(&mut self) -> Result<()> {
+        if self.storage_engine.in_transaction() {
+            log::warn!("会话结束时有未提交的事务，已回滚");
+            self.storage_engine.rollback_transaction()?;
+        }
+        self.save()
+    }
+
+    /// 上次成功保存之后是否执行过修改数据或结构的操作
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.storage_engine.has_unsaved_changes()
+    }
+
+    /// 交互模式退出前确认未保存的更改，返回是否退出
+    ///
+    /// 没有未保存的更改时直接退出；否则用 `ask` 显示提示并读取回答，规则见
+    /// [`exit::decide_exit`]。`ask` 返回 `None` 表示输入已结束（EOF），无法再回答，
+    /// 此时直接退出，否则非交互的输入会一直停在提示上。选择保存后退出但保存失败时输出错误并取消退出，
+    /// 用户可以处理磁盘问题后重试；确认直接退出时仍按原样在退出时尝试保存。
+    pub fn confirm_exit<F>(&mut self, mut ask: F) -> bool
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let dirty = self.storage_engine.has_unsaved_changes();
+        let since_last_save = self
+            .storage_engine
+            .last_saved()
+            .elapsed()
+            .unwrap_or_default();
+        let prompt = match exit::decide_exit(dirty, since_last_save, None) {
+            exit::ExitDecision::Ask(prompt) => prompt,
+            decision => return decision == exit::ExitDecision::Exit,
+        };
+        let Some(answer) = ask(&prompt) else {
+            return true;
+        };
+        match exit::decide_exit(dirty, since_last_save, Some(&answer)) {
+            exit::ExitDecision::Exit => true,
+            exit::ExitDecision::SaveAndExit => match self.save() {
+                Ok(()) => {
+                    self.notice("数据库已保存");
+                    true
+                }
+                Err(e) => {
+                    eprintln!("保存失败: {}，已取消退出", e);
+                    false
+                }
+            },
+            exit::ExitDecision::Ask(_) | exit::ExitDecision::Stay => false,
+        }
+    }
+
+    /// 对当前数据库做在线备份，写入 `path`
+    ///
+    /// 按 [`DEFAULT_BACKUP_STEP_PAGES`](storage::backup::DEFAULT_BACKUP_STEP_PAGES)
+    /// 分步复制页面，备份内容是开始时的状态。
+    pub fn backup_online<P: AsRef<Path>>(&mut self, path: P) -> Result<BackupSummary> {
+        let backup = OnlineBackup::begin(&mut self.storage_engine, path)?;
+        backup.finish(&mut self.storage_engine)
+    }
+
+    /// 把指定数据库导出为快照文件 `path`，包含全部表的数据
+    pub fn export_database<P: AsRef<Path>>(
+        &mut self,
+        db_name: &str,
+        path: P,
+    ) -> Result<BackupSummary> {
+        self.storage_engine.export_database(db_name, path)
+    }
+
+    /// 整理当前数据库中一张表的数据页（VACUUM），返回整理前后的页数
+    pub fn compact_table(&mut self, table_name: &str) -> Result<CompactionSummary> {
+        self.storage_engine.mark_unsaved();
+        self.storage_engine.compact_table(table_name)
+    }
+
+    /// 把备份文件导入为新数据库，返回数据库名
+    pub fn restore_backup<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        db_name: Option<&str>,
+    ) -> Result<String> {
+        self.storage_engine.mark_unsaved();
+        self.storage_engine.import_database(path, db_name)
+    }
+
     pub fn run(&mut self) -> Result<()> {
         match self.config.get_run_mode() {
             RunMode::File(file_path) => self.run_file_mode(&file_path),
             RunMode::Interactive => self.run_interactive_mode(),
             RunMode::SingleCommand(sql) => self.run_single_command_mode(&sql),
+            RunMode::Serve(addr) => self.run_serve_mode(&addr),
+            RunMode::Verify => self.run_verify_mode(),
         }
     }
 
     fn run_file_mode(&mut self, file_path: &str) -> Result<()> {
-        if self.config.verbose {
-            println!("执行 SQL 文件模式: {}", file_path);
-        }
+        let mut out = self.result_writer()?;
+        self.run_file(file_path, &mut out)
+    }
 
-        let results = self.execute_sql_file(file_path);
-        if let Err(e) = results {
-            println!("Error: {}", e);
-            return Ok(());
-        }
-        let results = results?;
+    /// 执行 SQL 文件，查询结果写入 `out`，错误信息和摘要输出到终端
+    ///
+    /// 文件无法读取或有语句执行失败时返回错误，使进程以非零状态退出；
+    /// 配置了 `stop_on_error` 时在第一条失败的语句之后停止。
+    pub fn run_file<W: Write>(&mut self, file_path: &str, out: &mut W) -> Result<()> {
+        log::info!("执行 SQL 文件模式: {}", file_path);
+
+        let format = self.config.format;
+        let mut results = self.execute_sql_file(file_path)?;
 
         let len = results.len();
         let mut has_output = false;
-        for (i, result) in results.iter().enumerate() {
+        for (i, ScriptResult { line, result }) in results.iter_mut().enumerate() {
             match result {
                 Ok(res) => {
-                    let output = format!("{}", res);
+                    // 与 mysql 的批处理模式一样不输出行数和耗时，输出可以与预期结果直接比较
+                    res.set_elapsed(None);
+                    let output = output::render(res, format);
                     if !output.trim().is_empty() {
-                        print!("{}", output);
+                        write!(out, "{}", output)?;
                         has_output = true;
-                        // 如果是结果集，且不是最后一个结果，输出一个空行
-                        if let QueryResult::ResultSet(_) = res {
-                            if i + 1 < len {
-                                println!();
-                            }
+                        // 如果是结果集，且不是最后一个结果，输出一个空行；JSON 每个结果占一行，不加空行
+                        if let QueryResult::ResultSet(_) = res
+                            && i + 1 < len
+                            && format != OutputFormat::Json
+                        {
+                            writeln!(out)?;
                         }
                     }
                 }
-                Err(e) => {
-                    println!("Error: {}", e);
-                    return Ok(());
-                }
+                Err(e) => self.report(&format!("Error at line {}: {}", line, e)),
             }
         }
+        out.flush()?;
 
-        if !has_output {
-            println!("There are no results to be displayed.");
+        if !has_output && format == OutputFormat::Table && !self.config.quiet {
+            self.report("There are no results to be displayed.");
         }
 
-        self.save()?;
-        Ok(())
+        let first_failure = results.iter().find(|result| result.result.is_err());
+        self.end_session()?;
+        match first_failure {
+            Some(failure) => {
+                self.report(&script::summary(&results));
+                if self.config.stop_on_error {
+                    Err(DBError::Other(format!(
+                        "第 {} 行的语句执行失败，已停止执行",
+                        failure.line
+                    )))
+                } else {
+                    let failed = results.iter().filter(|result| result.result.is_err());
+                    Err(DBError::Other(format!("{} 条语句执行失败", failed.count())))
+                }
+            }
+            None => Ok(()),
+        }
     }
 
     fn run_single_command_mode(&mut self, sql: &str) -> Result<()> {
-        if self.config.verbose {
-            println!("执行单条命令模式: {}", sql);
-        }
+        let mut out = self.result_writer()?;
+        self.run_command(sql, &mut out)
+    }
+
+    /// 执行一条命令行给出的 SQL，结果写入 `out`，错误输出到标准错误
+    pub fn run_command<W: Write>(&mut self, sql: &str, out: &mut W) -> Result<()> {
+        log::info!("执行单条命令模式: {}", sql);
 
         match self.execute_single_sql(sql) {
-            Ok(result) => println!("{}", result),
+            Ok(mut result) => {
+                result.set_elapsed(None);
+                match self.config.format {
+                    OutputFormat::Table => writeln!(out, "{}", result)?,
+                    format => write!(out, "{}", output::render(&result, format))?,
+                }
+                out.flush()?;
+            }
             Err(e) => eprintln!("Error: {}", e),
         }
 
-        self.save()?;
+        self.end_session()?;
         Ok(())
     }
 
+    fn run_verify_mode(&mut self) -> Result<()> {
+        let mut out = self.result_writer()?;
+        let (result, problems) = self.verify();
+        write!(out, "{}", output::render(&result, self.config.format))?;
+        out.flush()?;
+        if problems > 0 {
+            return Err(DBError::Other(format!(
+                "{} 张表的记录与表结构不一致",
+                problems
+            )));
+        }
+        Ok(())
+    }
+
+    /// 非交互模式下查询结果的去向：`--output` 指定的文件，否则为标准输出
+    fn result_writer(&self) -> Result<Box<dyn Write>> {
+        Ok(match &self.config.output {
+            Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+            None => Box::new(io::stdout().lock()),
+        })
+    }
+
+    /// 非交互模式下的错误信息和摘要
+    ///
+    /// 表格输出到标准输出时与结果交错，便于与预期输出比较；CSV、JSON 输出通常交给
+    /// 其它程序处理，结果写入文件时也一样，这些信息改写到标准错误。
+    fn report(&self, message: &str) {
+        if self.config.format == OutputFormat::Table && self.config.output.is_none() {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// 输出提示信息，安静模式下不输出
+    fn notice(&self, message: &str) {
+        if !self.config.quiet {
+            println!("{}", message);
+        }
+    }
+
+    fn run_serve_mode(&mut self, addr: &str) -> Result<()> {
+        let server = server::Server::bind(addr)?;
+        self.notice(&format!(
+            "Simple DB 服务已启动，监听 {}",
+            server.local_addr()?
+        ));
+        server.serve(self)
+    }
+
     fn run_interactive_mode(&mut self) -> Result<()> {
         use crate::helper::SQLHelper;
         use rustyline::error::ReadlineError;
@@ -222,25 +842,36 @@ impl SimpleDB {
         // 设置自定义助手
         let mut helper = SQLHelper::new();
         helper.with_colored_prompt("\x1b[1;32msimple_db>\x1b[0m ".to_owned());
+        helper.set_schema(self.schema_snapshot());
         rl.set_helper(Some(helper));
 
-        // 尝试加载历史记录
-        let history_file = "data/simple_db_history.txt";
-        if rl.load_history(history_file).is_err() && self.config.verbose {
-            println!("未找到历史记录文件，将创建新文件");
+        // 尝试加载历史记录和上次保存的设置
+        let history_file = self.history_path();
+        if rl.load_history(&history_file).is_err() {
+            log::info!("未找到历史记录文件，将创建新文件");
+        }
+        match ReplSettings::load(&self.settings_path()) {
+            Ok(settings) => self.apply_repl_settings(settings.unwrap_or_default()),
+            Err(e) => eprintln!(
+                "警告: 忽略设置文件 {}: {}",
+                self.settings_path().display(),
+                e
+            ),
         }
 
-        println!("Simple DB 交互模式");
-        println!("功能:");
-        println!("  • 使用上下箭头键浏览命令历史");
-        println!("  • 使用 Tab 键自动补全 SQL 关键字和元命令");
-        println!("  • 支持语法高亮和括号匹配");
-        println!("  • Ctrl+C 中断当前输入，Ctrl+D 退出");
-        println!("输入 .help 查看帮助信息");
-        if self.config.verbose {
-            println!("详细模式已启用");
+        if !self.config.quiet {
+            println!("Simple DB 交互模式");
+            println!("功能:");
+            println!("  • 使用上下箭头键浏览命令历史");
+            println!("  • 使用 Tab 键自动补全 SQL 关键字、表名（含 库名.表名）、列名和元命令");
+            println!("  • 支持语法高亮和括号匹配");
+            println!("  • Ctrl+C 中断当前输入、正在执行的语句或正在输出的结果，Ctrl+D 退出");
+            println!("输入 .help 查看帮助信息");
+            if self.config.verbose {
+                println!("详细模式已启用");
+            }
+            println!();
         }
-        println!();
 
         loop {
             let readline = rl.readline("simple_db> ");
@@ -256,18 +887,32 @@ impl SimpleDB {
 
                     if trimmed.starts_with('.') {
                         // 处理元命令
-                        if self.handle_meta_command(trimmed)? {
+                        if self.handle_meta_command(trimmed)?
+                            && self.confirm_exit(|prompt| read_exit_answer(&mut rl, prompt))
+                        {
                             break;
                         }
                     } else
                     // 执行 SQL 命令
                     {
-                        match self.execute_single_sql(trimmed) {
-                            Ok(result) => print!("{}", result),
+                        match self.execute_cancellable(trimmed) {
+                            Ok(result) => self.print_interruptible(result)?,
+                            Err(e @ DBError::Cancelled(_)) => {
+                                eprintln!("{}", e);
+                                continue;
+                            }
                             //Err(e) => eprintln!("错误: {}", e),
-                            Err(_) => eprintln!("Error: Syntax error"),
+                            Err(_) => {
+                                eprintln!("Error: Syntax error");
+                                continue;
+                            }
                         }
                     }
+
+                    // USE、建表删表、修改表结构或 .restore 之后刷新补全用的库表名和列名
+                    if let Some(helper) = rl.helper_mut() {
+                        helper.set_schema(self.schema_snapshot());
+                    }
                 }
                 Err(ReadlineError::Interrupted) => {
                     println!("^C");
@@ -275,7 +920,9 @@ impl SimpleDB {
                 }
                 Err(ReadlineError::Eof) => {
                     println!("^D");
-                    break;
+                    if self.confirm_exit(|prompt| read_exit_answer(&mut rl, prompt)) {
+                        break;
+                    }
                 }
                 Err(err) => {
                     eprintln!("读取输入错误: {:?}", err);
@@ -284,18 +931,18 @@ impl SimpleDB {
             }
         }
 
-        // 保存历史记录
-        if let Err(e) = rl.save_history(history_file) {
-            if self.config.verbose {
-                eprintln!("保存历史记录失败: {}", e);
-            }
-        } else if self.config.verbose {
-            println!("历史记录已保存到 {}", history_file);
+        // 保存历史记录和设置
+        match rl.save_history(&history_file) {
+            Ok(()) => log::info!("历史记录已保存到 {}", history_file.display()),
+            Err(e) => log::warn!("保存历史记录失败: {}", e),
+        }
+        if let Err(e) = self.repl_settings().save(&self.settings_path()) {
+            eprintln!("保存设置失败: {}", e);
         }
 
-        println!("正在保存数据库...");
-        self.save()?;
-        println!("再见!");
+        self.notice("正在保存数据库...");
+        self.end_session()?;
+        self.notice("再见!");
         Ok(())
     }
 
@@ -310,10 +957,13 @@ impl SimpleDB {
                 self.print_interactive_help();
             }
 
-            ".tables" => match self.execute_single_sql("SHOW TABLES") {
-                Ok(result) => println!("{}", result),
-                Err(e) => eprintln!("获取表列表失败: {}", e),
-            },
+            cmd if cmd.split_whitespace().next() == Some(".tables") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                match self.show_tables(parts.get(1).copied()) {
+                    Ok(result) => println!("{}", result),
+                    Err(e) => eprintln!("获取表列表失败: {}", e),
+                }
+            }
 
             ".save" => match self.save() {
                 Ok(()) => println!("数据库已保存"),
@@ -334,15 +984,38 @@ impl SimpleDB {
                 println!("数据库状态:");
                 let db_name = self.storage_engine.current_database()?.get_name();
                 println!("  当前数据库: {}", db_name);
+                let databases: Vec<String> = self
+                    .storage_engine
+                    .list_databases()
+                    .into_iter()
+                    .map(|name| if name == db_name { format!("{}*", name) } else { name })
+                    .collect();
+                println!("  全部数据库: {}", databases.join(", "));
+                let skipped: Vec<String> = self
+                    .storage_engine
+                    .databases()
+                    .into_iter()
+                    .filter_map(|entry| match entry {
+                        DataDirEntry::Skipped(skipped) => {
+                            Some(format!("{}（{}）", skipped.name, skipped.reason))
+                        }
+                        DataDirEntry::Database(_) => None,
+                    })
+                    .collect();
+                if !skipped.is_empty() {
+                    println!("  跳过的目录: {}", skipped.join(", "));
+                }
 
                 let data_dir = &self.storage_engine.get_base_dir();
                 println!("  数据目录: {:?}", data_dir);
 
                 println!("  详细模式: {}", self.config.verbose);
+                println!("  严格模式: {}", self.session.is_strict());
             }
 
             ".v" | ".verbose" => {
                 self.config.verbose = !self.config.verbose;
+                set_verbose_logging(self.config.verbose);
                 if self.config.verbose {
                     println!("详细模式已启用");
                 } else {
@@ -350,6 +1023,67 @@ impl SimpleDB {
                 }
             }
 
+            ".strict" => {
+                if self.session.is_strict() {
+                    self.session.set_sql_mode(SqlMode::Lenient);
+                    println!("严格模式已禁用");
+                } else {
+                    self.session.set_sql_mode(SqlMode::Strict);
+                    println!("严格模式已启用");
+                }
+            }
+
+            ".history clear" => {
+                self.session.clear_query_history();
+                println!("查询历史已清空");
+            }
+
+            cmd if cmd.split_whitespace().next() == Some(".format") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                match parts.as_slice() {
+                    [_] => println!("当前输出格式: {}", self.config.format.name()),
+                    [_, name] => match OutputFormat::parse(name) {
+                        Some(format) => {
+                            self.config.format = format;
+                            println!("输出格式已设置为 {}", format.name());
+                        }
+                        None => eprintln!("未知的输出格式 '{}'，可选: table, csv, json", name),
+                    },
+                    _ => eprintln!("用法: .format [table|csv|json]"),
+                }
+            }
+            cmd if cmd.split_whitespace().next() == Some(".set") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                match parts.as_slice() {
+                    [_] => {
+                        let show = |limit: Option<usize>| {
+                            limit.map_or("不限制".to_string(), |n| n.to_string())
+                        };
+                        let limits = self.session.execution_limits();
+                        let millis = limits.max_duration.map(|d| d.as_millis() as usize);
+                        println!("maxrows: {}", show(self.display_limits.max_rows));
+                        println!("maxwidth: {}", show(self.display_limits.max_width));
+                        println!("maxexecmillis: {}", show(millis));
+                        println!("maxresultrows: {}", show(limits.max_rows));
+                    }
+                    [_, option, value] => match self.set_option(option, value) {
+                        Ok(()) => println!("{} 已设置为 {}", option.to_lowercase(), value),
+                        Err(e) => eprintln!("Error: {}", e),
+                    },
+                    _ => eprintln!("用法: .set [maxrows|maxwidth|maxexecmillis|maxresultrows] <N>"),
+                }
+            }
+            ".history" => {
+                let sql = format!("SELECT * FROM {}", QUERY_HISTORY_TABLE);
+                match self.execute_single_sql(&sql) {
+                    Ok(mut result) => {
+                        result.set_limits(self.display_limits);
+                        print!("{}", result)
+                    }
+                    Err(e) => eprintln!("获取查询历史失败: {}", e),
+                }
+            }
+
             cmd if cmd.starts_with(".schema") => {
                 let parts: Vec<&str> = cmd.split_whitespace().collect();
                 if parts.len() == 2 {
@@ -364,23 +1098,117 @@ impl SimpleDB {
                 }
             }
 
-            cmd if cmd.starts_with(".read") => {
-                let parts: Vec<&str> = cmd.split_whitespace().collect();
-                if parts.len() == 2 {
-                    let file_path = parts[1];
-                    match self.execute_sql_file(file_path) {
-                        Ok(results) => {
-                            for result in &results {
-                                match result {
-                                    Ok(res) => print!("{}", res),
-                                    Err(e) => eprint!("Error: {}", e),
+            cmd if cmd.split_whitespace().next() == Some(".read") => {
+                // .read 之后的全部内容都是路径，可以包含空格，也可以用引号括起来
+                let file_path = unquote_path(cmd[".read".len()..].trim());
+                if file_path.is_empty() {
+                    eprintln!("用法: .read <file_path>");
+                    return Ok(false);
+                }
+                match self.execute_sql_file(file_path) {
+                    Ok(mut results) => {
+                        for ScriptResult { line, result } in &mut results {
+                            match result {
+                                Ok(res) => {
+                                    res.set_limits(self.display_limits);
+                                    print!("{}", output::render(res, self.config.format))
                                 }
+                                Err(e) => eprintln!("Error at line {}: {}", line, e),
                             }
                         }
-                        Err(e) => eprintln!("读取文件失败: {}", e),
+                        println!("{}", script::summary(&results));
                     }
-                } else {
-                    //eprintln!("用法: .read <file_path>");
+                    Err(e) => eprintln!("读取文件失败: {}", e),
+                }
+            }
+
+            cmd if cmd.starts_with(".backup") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                let (result, path) = match parts.as_slice() {
+                    [_, "--online", path] => (self.backup_online(path), path),
+                    [_, db_name, path] => (self.export_database(db_name, path), path),
+                    _ => {
+                        eprintln!("用法: .backup --online <文件> 或 .backup <数据库名> <文件>");
+                        return Ok(false);
+                    }
+                };
+                match result {
+                    Ok(summary) => println!(
+                        "备份完成: {} 张表，{} 个页面 -> {}",
+                        summary.tables, summary.pages, path
+                    ),
+                    Err(e) => eprintln!("备份失败: {}", e),
+                }
+            }
+
+            cmd if cmd.starts_with(".vacuum") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                match parts.as_slice() {
+                    [_, table_name] => match self.compact_table(table_name) {
+                        Ok(summary) => println!(
+                            "整理完成: {} 条记录，页数 {} -> {}（按原顺序紧密排列需要 {} 页）",
+                            summary.records,
+                            summary.pages_before,
+                            summary.pages_after,
+                            summary.naive_pages
+                        ),
+                        Err(e) => eprintln!("整理失败: {}", e),
+                    },
+                    _ => eprintln!("用法: .vacuum <表名>"),
+                }
+            }
+
+            cmd if cmd.split_whitespace().next() == Some(".stats") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                let table_name = match parts.as_slice() {
+                    [_] => None,
+                    [_, table_name] => Some(*table_name),
+                    _ => {
+                        eprintln!("用法: .stats [表名]");
+                        return Ok(false);
+                    }
+                };
+                match self.table_stats(table_name) {
+                    Ok(mut result) => {
+                        result.set_limits(self.display_limits);
+                        print!("{}", result)
+                    }
+                    Err(e) => eprintln!("获取表统计失败: {}", e),
+                }
+            }
+
+            ".verify" => {
+                let (mut result, problems) = self.verify();
+                result.set_limits(self.display_limits);
+                print!("{}", result);
+                if problems > 0 {
+                    eprintln!("{} 张表的记录与表结构不一致", problems);
+                }
+            }
+
+            cmd if cmd.starts_with(".restore") => {
+                let parts: Vec<&str> = cmd.split_whitespace().collect();
+                let result = match parts.as_slice() {
+                    [_, path] => self.restore_backup(path, None),
+                    [_, path, db_name] => self.restore_backup(path, Some(db_name)),
+                    [_, path, keyword, db_name] if keyword.eq_ignore_ascii_case("as") => {
+                        self.restore_backup(path, Some(db_name))
+                    }
+                    _ => {
+                        eprintln!("用法: .restore <文件> [as <数据库名>]");
+                        return Ok(false);
+                    }
+                };
+                match result {
+                    Ok(db_name) => println!("已从备份导入数据库 '{}'", db_name),
+                    Err(e) => eprintln!("导入失败: {}", e),
+                }
+            }
+
+            cmd if cmd.starts_with(".watch") => {
+                match watch::WatchCommand::parse(&cmd[".watch".len()..]) {
+                    Ok(watch_cmd) => self.run_watch(&watch_cmd)?,
+                    Err(e) => eprintln!("Error: {}", e),
                 }
             }
 
@@ -392,24 +1220,191 @@ impl SimpleDB {
         Ok(false)
     }
 
+    /// 交互模式的历史记录文件：`--history-file` 指定的文件，否则在数据目录下
+    pub fn history_path(&self) -> PathBuf {
+        match &self.config.history_file {
+            Some(path) => PathBuf::from(path),
+            None => self
+                .storage_engine
+                .get_base_dir()
+                .join(settings::HISTORY_FILE_NAME),
+        }
+    }
+
+    /// 交互模式设置文件，在数据目录下
+    pub fn settings_path(&self) -> PathBuf {
+        self.storage_engine
+            .get_base_dir()
+            .join(settings::SETTINGS_FILE_NAME)
+    }
+
+    /// 当前的交互模式设置（输出格式、表格的行数和列宽限制）
+    pub fn repl_settings(&self) -> ReplSettings {
+        ReplSettings {
+            format: self.config.format,
+            max_rows: self.display_limits.max_rows,
+            max_width: self.display_limits.max_width,
+        }
+    }
+
+    /// 应用交互模式设置，交互模式启动时用上次退出时保存的设置调用
+    pub fn apply_repl_settings(&mut self, settings: ReplSettings) {
+        self.config.format = settings.format;
+        self.display_limits = settings.display_limits();
+    }
+
+    /// 修改表格输出或语句执行的一项限制，0 表示不限制
+    fn set_option(&mut self, option: &str, value: &str) -> Result<()> {
+        let limit = match value.parse::<usize>() {
+            Ok(0) => None,
+            Ok(n) => Some(n),
+            Err(_) => return Err(DBError::Other(format!("无效的数值 '{}'", value))),
+        };
+        match option.to_lowercase().as_str() {
+            "maxrows" => self.display_limits.max_rows = limit,
+            "maxwidth" => {
+                if limit.is_some_and(|width| width < DisplayLimits::MIN_WIDTH) {
+                    return Err(DBError::Other(format!(
+                        "maxwidth 不能小于 {}",
+                        DisplayLimits::MIN_WIDTH
+                    )));
+                }
+                self.display_limits.max_width = limit;
+            }
+            "maxexecmillis" => {
+                let mut limits = self.session.execution_limits();
+                limits.max_duration = limit.map(|millis| Duration::from_millis(millis as u64));
+                self.session.set_execution_limits(limits);
+            }
+            "maxresultrows" => {
+                let mut limits = self.session.execution_limits();
+                limits.max_rows = limit;
+                self.session.set_execution_limits(limits);
+            }
+            _ => {
+                return Err(DBError::Other(format!(
+                    "未知的选项 '{}'，可选: maxrows, maxwidth, maxexecmillis, maxresultrows",
+                    option
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 交互模式下执行一条语句，执行期间按 Ctrl+C 取消语句而不退出程序
+    fn execute_cancellable(&mut self, sql: &str) -> Result<QueryResult> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let sig_id =
+            signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&cancelled))?;
+        self.session.set_cancel_flag(Some(cancelled));
+
+        let result = self.execute_single_sql(sql);
+
+        self.session.set_cancel_flag(None);
+        signal_hook::low_level::unregister(sig_id);
+        result
+    }
+
+    /// 交互模式下输出结果，Ctrl+C 只打断输出而不退出程序
+    fn print_interruptible(&self, mut result: QueryResult) -> Result<()> {
+        result.set_limits(self.display_limits);
+        // CSV、JSON 一次输出整个结果，不按行检查中断
+        if self.config.format != OutputFormat::Table {
+            print!("{}", output::render(&result, self.config.format));
+            return Ok(());
+        }
+
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let sig_id =
+            signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&interrupted))?;
+
+        let outcome = {
+            let mut out = output::TerminalGuard::new(io::stdout().lock());
+            output::print_result(&mut out, &result, &interrupted)
+        };
+
+        signal_hook::low_level::unregister(sig_id);
+        outcome?;
+        Ok(())
+    }
+
+    /// 按间隔反复执行只读查询，直到 Ctrl+C 或达到最大执行次数
+    fn run_watch(&mut self, cmd: &watch::WatchCommand) -> Result<()> {
+        use watch::{SystemClock, WatchOutcome};
+
+        // 监视期间由我们接管 SIGINT，Ctrl+C 只结束监视而不退出程序
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let sig_id =
+            signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&interrupted))?;
+
+        let max_iterations = self.config.watch_max_iterations;
+        let outcome = watch::run_watch(
+            &mut SystemClock,
+            cmd.interval,
+            max_iterations,
+            &interrupted,
+            |iteration, now| {
+                print!("\x1B[2J\x1B[1;1H");
+                println!(
+                    "Every {:.1}s: {}    {} (#{})",
+                    cmd.interval.as_secs_f64(),
+                    cmd.sql,
+                    watch::format_timestamp(now),
+                    iteration
+                );
+                println!();
+                let mut result = self.execute_single_sql(&cmd.sql)?;
+                result.set_limits(self.display_limits);
+                // 输出中途按下 Ctrl+C 时停止输出，下一轮检查时结束监视
+                let mut out = output::TerminalGuard::new(io::stdout().lock());
+                output::print_result(&mut out, &result, &interrupted)?;
+                Ok(())
+            },
+        );
+
+        signal_hook::low_level::unregister(sig_id);
+
+        match outcome {
+            Ok(WatchOutcome::Interrupted) => println!("^C"),
+            Ok(WatchOutcome::MaxIterations) => {
+                println!("已达到最大执行次数 ({})，停止监视", max_iterations)
+            }
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        Ok(())
+    }
+
     fn print_interactive_help(&self) {
         println!("交互模式命令:");
         println!("  .exit, .quit, \\q              # 退出程序");
         println!("  .help, \\h                     # 显示帮助信息");
-        println!("  .tables                       # 显示所有表");
+        println!("  .tables [数据库]              # 显示当前（或指定）数据库的所有表");
         println!("  .schema <table_name>          # 显示表结构");
         println!("  .save                         # 手动保存数据库");
         println!("  .clear                        # 清屏");
         println!("  .version                      # 显示版本信息");
         println!("  .status                       # 显示数据库状态");
         println!("  .read <file_path>             # 执行SQL文件");
+        println!("  .watch <秒数> <SELECT 语句>   # 定期重新执行查询，Ctrl+C 停止");
         println!("  .v, .verbose                  # 切换详细模式");
+        println!("  .strict                       # 切换严格模式");
+        println!("  .format [table|csv|json]      # 查看或设置查询结果的输出格式");
+        println!("  .set [maxrows|maxwidth] <N>   # 表格的最大行数、列宽，0 为不限制");
+        println!("  .set maxexecmillis <N>        # 单条语句的最长执行时间（毫秒），0 为不限制");
+        println!("  .set maxresultrows <N>        # 单条语句最多扫描的行数，0 为不限制");
+        println!("  .history [clear]              # 查看或清空本会话的查询历史");
+        println!("  .backup --online <文件>       # 在线备份当前数据库，不阻塞写入");
+        println!("  .backup <数据库名> <文件>     # 把数据库导出为快照文件（目录和全部数据页）");
+        println!("  .restore <文件> [as <名称>]   # 把备份导入为新数据库，可用 as 指定新名称");
+        println!("  .vacuum <表名>                # 整理表的数据页，合并未填满的页");
+        println!("  .stats [表名]                 # 表的行数、页数、字节数和页面使用率");
+        println!("  .verify                       # 检查所有表的记录与表结构是否一致");
         println!();
 
         println!("增强功能 (rustyline):");
         println!("  ↑↓ 箭头键                     # 浏览命令历史");
         println!("  Tab 键                        # 自动补全");
-        println!("  Ctrl+C                        # 中断当前输入");
+        println!("  Ctrl+C                        # 中断当前输入或取消正在执行的语句");
         println!("  Ctrl+D                        # 退出程序");
         println!();
 
@@ -421,9 +1416,70 @@ impl SimpleDB {
     }
 }
 
+/// 安装命令行使用的日志输出，写到标准错误
+///
+/// 设置了 `RUST_LOG` 时按它过滤（如 `RUST_LOG=simple_db=trace` 可看到页面读写）；
+/// 否则只输出本库的日志，`--verbose` 时输出到 debug 级别，平时只输出错误。
+fn init_logging(verbose: bool) {
+    let mut builder = env_logger::Builder::new();
+    builder.format(|buf, record| writeln!(buf, "[{}] {}", record.level(), record.args()));
+    match std::env::var("RUST_LOG") {
+        Ok(filters) => {
+            builder.parse_filters(&filters);
+        }
+        Err(_) => {
+            builder.filter_module("simple_db", log::LevelFilter::Trace);
+        }
+    }
+    if builder.try_init().is_ok() {
+        set_verbose_logging(verbose);
+    }
+}
+
+/// `.verbose` 切换详细模式时调整日志级别，`RUST_LOG` 指定的过滤规则不受影响
+fn set_verbose_logging(verbose: bool) {
+    if std::env::var_os("RUST_LOG").is_none() {
+        log::set_max_level(if verbose {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Error
+        });
+    }
+}
+
+/// 读取退出确认的回答
+///
+/// 标准输入不是终端时不询问，按输入已结束处理；Ctrl+C 视为取消退出，
+/// Ctrl+D 或读取失败返回 `None`。
+fn read_exit_answer<H, I>(rl: &mut rustyline::Editor<H, I>, prompt: &str) -> Option<String>
+where
+    H: rustyline::Helper,
+    I: rustyline::history::History,
+{
+    use rustyline::error::ReadlineError;
+    use std::io::IsTerminal;
+
+    if !io::stdin().is_terminal() {
+        return None;
+    }
+    match rl.readline(prompt) {
+        Ok(answer) => Some(answer),
+        Err(ReadlineError::Interrupted) => Some(String::new()),
+        Err(_) => None,
+    }
+}
+
+/// 去掉路径两侧成对的引号
+fn unquote_path(path: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|&quote| path.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(path)
+}
+
 impl Drop for SimpleDB {
     fn drop(&mut self) {
-        if let Err(e) = self.save() {
+        if let Err(e) = self.end_session() {
             eprintln!("数据库保存失败: {}", e);
         }
     }
@@ -0,0 +1,52 @@
+//! 语句观察者：嵌入方借此观察引擎执行的每条语句，用于审计记录或调试测试脚本
+
+use crate::error::DBError;
+use crate::executor::QueryResult;
+use std::time::Duration;
+
+/// 语句观察者，见 [`SimpleDB::set_statement_observer`](crate::SimpleDB::set_statement_observer)
+///
+/// 与语句守卫一样需要是 `Sync` 的，只读查询可能在多个线程上同时发出事件。
+pub type StatementObserver = Box<dyn Fn(&StatementEvent) + Send + Sync>;
+
+/// 一条语句开始或结束时发出的事件
+///
+/// 同一条语句先收到 `Started`，再收到 `Finished`；生成计划失败时也会收到 `Finished`，
+/// 之后同一批中剩余的语句不再执行。
+#[derive(Debug)]
+pub enum StatementEvent<'a> {
+    /// 语句已解析，即将生成计划并执行
+    Started { sql: &'a str },
+    /// 语句执行结束，`elapsed` 包括生成计划和执行的时间
+    Finished {
+        sql: &'a str,
+        elapsed: Duration,
+        result: std::result::Result<&'a QueryResult, &'a DBError>,
+    },
+}
+
+impl StatementEvent<'_> {
+    /// 语句的 SQL 文本，由解析结果重新生成，与查询历史中的写法相同
+    pub fn sql(&self) -> &str {
+        match self {
+            StatementEvent::Started { sql } | StatementEvent::Finished { sql, .. } => sql,
+        }
+    }
+
+    /// 结束事件的一行结果摘要，如 `3 行`、`错误: ...`；开始事件没有摘要
+    pub fn summary(&self) -> Option<String> {
+        let StatementEvent::Finished { result, .. } = self else {
+            return None;
+        };
+        Some(match result {
+            Ok(QueryResult::ResultSet(rs)) => format!("{} 行", rs.rows.len()),
+            Ok(QueryResult::Modified(last)) => format!("影响 {} 行", last.rows_affected),
+            Ok(QueryResult::Updated(summary)) => {
+                format!("匹配 {} 行，变更 {} 行", summary.matched, summary.changed)
+            }
+            Ok(QueryResult::Success) => "成功".to_string(),
+            Ok(QueryResult::Message(message)) => message.clone(),
+            Err(e) => format!("错误: {}", e),
+        })
+    }
+}
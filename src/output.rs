@@ -0,0 +1,366 @@
+use crate::executor::QueryResult;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 清除颜色、粗体等所有 ANSI 属性
+pub const ANSI_RESET: &str = "\x1b[0m";
+
+/// 输出被 Ctrl+C 打断时追加的提示行
+pub const INTERRUPTED_MARKER: &str = "-- interrupted --";
+
+/// 查询结果的输出格式（`--format`、`.format`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// ASCII 表格
+    #[default]
+    Table,
+    /// CSV，首行为列名
+    Csv,
+    /// JSON，结果集输出为对象数组
+    Json,
+}
+
+impl OutputFormat {
+    /// 按名称解析，不区分大小写
+    pub fn parse(name: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(name, true).ok()
+    }
+
+    /// 格式名称，与命令行参数的写法相同
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+/// 按输出格式把一条语句的结果转成文本
+///
+/// 表格格式与 [`QueryResult`] 的 Display 相同；CSV 格式下没有结果集的语句不输出任何内容，
+/// JSON 格式下输出一个小对象，键与服务端协议的 JSON 结果相同。
+pub fn render(result: &QueryResult, format: OutputFormat) -> String {
+    let summary = match (format, result) {
+        (OutputFormat::Table, _) => return result.to_string(),
+        (OutputFormat::Csv, QueryResult::ResultSet(rs)) => return rs.to_csv(),
+        (OutputFormat::Csv, _) => return String::new(),
+        (OutputFormat::Json, QueryResult::ResultSet(rs)) => return format!("{}\n", rs.to_json()),
+        (OutputFormat::Json, QueryResult::Modified(last)) => json!({
+            "rows_affected": last.rows_affected,
+            "last_insert_id": last.last_insert_id,
+        }),
+        (OutputFormat::Json, QueryResult::Updated(summary)) => json!({
+            "rows_matched": summary.matched,
+            "rows_changed": summary.changed,
+        }),
+        (OutputFormat::Json, QueryResult::Message(message)) => json!({ "message": message }),
+        (OutputFormat::Json, QueryResult::Success) => json!({ "ok": true }),
+    };
+    format!("{}\n", summary)
+}
+
+/// 结果输出结束的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintOutcome {
+    /// 全部输出
+    Completed,
+    /// 中途被中断，剩余的行未输出
+    Interrupted,
+}
+
+/// 终端状态守卫：销毁时重置 ANSI 属性并刷新输出
+///
+/// 无论输出正常结束、被中断还是发生 panic，离开作用域时终端都会恢复默认样式，
+/// 提示符的颜色不会带到后续输出中。
+pub struct TerminalGuard<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TerminalGuard<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> Write for TerminalGuard<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for TerminalGuard<W> {
+    fn drop(&mut self) {
+        // Drop 中无法上报错误，终端已不可写时也没有别的补救办法
+        let _ = self.out.write_all(ANSI_RESET.as_bytes());
+        let _ = self.out.flush();
+    }
+}
+
+/// 输出查询结果，结果集逐行写出并在行与行之间检查 `interrupted`
+///
+/// 被中断时已写出的行保持完整，随后输出 [`INTERRUPTED_MARKER`] 并返回
+/// [`PrintOutcome::Interrupted`]；中断标志由调用方负责复位。
+pub fn print_result<W: Write>(
+    out: &mut W,
+    result: &QueryResult,
+    interrupted: &AtomicBool,
+) -> io::Result<PrintOutcome> {
+    let QueryResult::ResultSet(rs) = result else {
+        write!(out, "{}", result)?;
+        out.flush()?;
+        return Ok(PrintOutcome::Completed);
+    };
+    if rs.rows.is_empty() || rs.columns.is_empty() {
+        if let Some(footer) = rs.footer() {
+            writeln!(out, "{}", footer)?;
+            out.flush()?;
+        }
+        return Ok(PrintOutcome::Completed);
+    }
+
+    let column_widths = rs.column_widths();
+    out.write_all(rs.format_header(&column_widths).as_bytes())?;
+    for row in rs.visible_rows() {
+        if interrupted.load(Ordering::SeqCst) {
+            writeln!(out, "{}{}", ANSI_RESET, INTERRUPTED_MARKER)?;
+            out.flush()?;
+            return Ok(PrintOutcome::Interrupted);
+        }
+        out.write_all(rs.format_row(row, &column_widths).as_bytes())?;
+    }
+    if let Some(note) = rs.hidden_rows_note() {
+        writeln!(out, "{}", note)?;
+    }
+    if let Some(comment) = &rs.comment {
+        writeln!(out, "-- {}", comment)?;
+    }
+    if let Some(footer) = rs.footer() {
+        writeln!(out, "{}", footer)?;
+    }
+    out.flush()?;
+    Ok(PrintOutcome::Completed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{DisplayLimits, ResultSet};
+    use crate::storage::table::Value;
+    use std::time::Duration;
+
+    fn result_set(rows: usize) -> QueryResult {
+        let rows = (0..rows)
+            .map(|i| vec![Value::Int(i as i32), Value::String(format!("row{}", i))])
+            .collect();
+        QueryResult::ResultSet(ResultSet::new(
+            vec!["id".to_string(), "name".to_string()],
+            rows,
+        ))
+    }
+
+    /// 假输出：写满指定行数后置位中断标志，模拟用户在输出过程中按下 Ctrl+C
+    struct FakeTerminal<'a> {
+        written: Vec<u8>,
+        interrupt_after_lines: usize,
+        interrupted: &'a AtomicBool,
+    }
+
+    impl Write for FakeTerminal<'_> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            let lines = self.written.iter().filter(|&&b| b == b'\n').count();
+            if lines >= self.interrupt_after_lines {
+                self.interrupted.store(true, Ordering::SeqCst);
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_print_result_matches_display_when_not_interrupted() {
+        let interrupted = AtomicBool::new(false);
+        let timed = |mut result: QueryResult| {
+            result.set_elapsed(Some(Duration::from_millis(15)));
+            result
+        };
+        let limited = |mut result: QueryResult| {
+            result.set_limits(DisplayLimits {
+                max_rows: Some(2),
+                max_width: Some(4),
+            });
+            result
+        };
+        for result in [
+            result_set(3),
+            result_set(0),
+            QueryResult::Message("ok".to_string()),
+            timed(result_set(3)),
+            timed(result_set(0)),
+            limited(result_set(3)),
+        ] {
+            let mut out = Vec::new();
+            let outcome = print_result(&mut out, &result, &interrupted).unwrap();
+            assert_eq!(outcome, PrintOutcome::Completed);
+            assert_eq!(String::from_utf8(out).unwrap(), result.to_string());
+        }
+    }
+
+    #[test]
+    fn test_print_result_stops_between_rows() {
+        // 预先置位：只输出表头
+        let interrupted = AtomicBool::new(true);
+        let mut out = Vec::new();
+        let outcome = print_result(&mut out, &result_set(100), &interrupted).unwrap();
+        assert_eq!(outcome, PrintOutcome::Interrupted);
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("id"));
+        assert_eq!(lines[2], format!("{}{}", ANSI_RESET, INTERRUPTED_MARKER));
+
+        // 输出两行数据后中断：已输出的行完整，其余行不再输出
+        let interrupted = AtomicBool::new(false);
+        let mut terminal = FakeTerminal {
+            written: Vec::new(),
+            interrupt_after_lines: 4,
+            interrupted: &interrupted,
+        };
+        let outcome = print_result(&mut terminal, &result_set(100), &interrupted).unwrap();
+        assert_eq!(outcome, PrintOutcome::Interrupted);
+        let text = String::from_utf8(terminal.written).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[3].starts_with("| 1 ") && lines[3].ends_with('|'));
+        assert!(lines[4].ends_with(INTERRUPTED_MARKER));
+    }
+
+    #[test]
+    fn test_terminal_guard_resets_on_drop_and_panic() {
+        let mut out = Vec::new();
+        {
+            let mut guard = TerminalGuard::new(&mut out);
+            write!(guard, "\x1b[1;32mgreen").unwrap();
+        }
+        assert_eq!(out, format!("\x1b[1;32mgreen{}", ANSI_RESET).into_bytes());
+
+        let mut out = Vec::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut guard = TerminalGuard::new(&mut out);
+            let interrupted = AtomicBool::new(true);
+            print_result(&mut guard, &result_set(5), &interrupted).unwrap();
+            panic!("输出过程中出错");
+        }));
+        assert!(result.is_err());
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with(&format!("{}\n{}", INTERRUPTED_MARKER, ANSI_RESET)));
+    }
+
+    fn tricky_result() -> ResultSet {
+        ResultSet::new(
+            vec![
+                "name".to_string(),
+                "id".to_string(),
+                "score".to_string(),
+                "ok".to_string(),
+            ],
+            vec![
+                vec![
+                    Value::String("a,b \"c\"\nd\\e 中文".to_string()),
+                    Value::Int(1),
+                    Value::Float(1.5),
+                    Value::Boolean(true),
+                ],
+                vec![
+                    Value::String(String::new()),
+                    Value::Null,
+                    Value::Null,
+                    Value::Boolean(false),
+                ],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let result = QueryResult::ResultSet(tricky_result());
+        assert_eq!(
+            render(&result, OutputFormat::Csv),
+            "name,id,score,ok\n\"a,b \"\"c\"\"\nd\\e 中文\",1,1.5,true\n\"\",,,false\n"
+        );
+
+        // 没有结果集的语句在 CSV 中不输出任何内容
+        assert_eq!(render(&QueryResult::Success, OutputFormat::Csv), "");
+        assert_eq!(
+            render(
+                &QueryResult::ResultSet(ResultSet::new(vec!["id".to_string()], vec![])),
+                OutputFormat::Csv
+            ),
+            "id\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json_parses_back() {
+        let rs = tricky_result();
+        let text = render(&QueryResult::ResultSet(tricky_result()), OutputFormat::Json);
+        assert!(text.ends_with('\n'));
+        // 键按列的顺序输出
+        assert!(text.starts_with("[{\"name\":"), "{}", text);
+
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let rows: Vec<Vec<Value>> = parsed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|object| {
+                rs.columns
+                    .iter()
+                    .map(|column| Value::from_json(&object[column]).unwrap())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows, rs.rows);
+
+        // 没有结果集的语句输出一个小对象
+        let summary = |result: QueryResult| -> serde_json::Value {
+            serde_json::from_str(&render(&result, OutputFormat::Json)).unwrap()
+        };
+        assert_eq!(summary(QueryResult::Success), json!({ "ok": true }));
+        assert_eq!(
+            summary(QueryResult::Updated(crate::executor::UpdateSummary {
+                matched: 3,
+                changed: 2,
+                elapsed: None,
+            })),
+            json!({ "rows_matched": 3, "rows_changed": 2 })
+        );
+        assert_eq!(
+            summary(QueryResult::ResultSet(ResultSet::new(
+                vec!["id".to_string()],
+                vec![]
+            ))),
+            json!([])
+        );
+    }
+
+    #[test]
+    fn test_output_format_parse() {
+        assert_eq!(OutputFormat::parse("CSV"), Some(OutputFormat::Csv));
+        assert_eq!(OutputFormat::parse("json"), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::parse("xml"), None);
+        assert_eq!(OutputFormat::default().name(), "table");
+    }
+}
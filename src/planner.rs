@@ -1,6 +1,21 @@
+pub mod check;
+pub mod cost;
+pub mod function;
+pub mod like;
+pub mod optimizer;
+pub mod validate;
+
 use crate::error::{DBError, Result};
-use crate::storage::table::{ColumnDef, DataType, Record, Value};
+use crate::session::SqlMode;
+use crate::storage::table::value::MAX_INT_DISPLAY_WIDTH;
+use crate::storage::table::{
+    AlterColumn, CheckConstraint, ColumnDef, ColumnPosition, DataType, ForeignKey, IndexDef,
+    Record, Value, column_position,
+};
+use function::ScalarFunction;
+use like::{DEFAULT_LIKE_ESCAPE, LikePattern};
 use sqlparser::ast;
+use std::borrow::Cow;
 
 /// 表达式枚举
 #[derive(Clone, Debug, PartialEq)]
@@ -16,6 +31,57 @@ pub enum Expression {
         operator: UnaryOperator,
         operand: Box<Expression>,
     },
+    /// 聚合函数调用，`arg` 为 `None` 表示 `COUNT(*)`
+    Aggregate {
+        func: AggregateFunction,
+        arg: Option<Box<Expression>>,
+    },
+    /// 标量函数调用，如 `UPPER(name)`
+    Function {
+        name: ScalarFunction,
+        args: Vec<Expression>,
+    },
+    /// 标量子查询 `(SELECT ...)`，必须恰好返回一行一列
+    ///
+    /// 执行器在过滤之前执行 WHERE 中的子查询，替换为它的结果值，见 [`Condition::In`]。
+    Subquery(Box<Plan>),
+}
+
+/// 聚合函数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    /// 按函数名（不区分大小写）查找聚合函数
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "COUNT" => Some(AggregateFunction::Count),
+            "SUM" => Some(AggregateFunction::Sum),
+            "AVG" => Some(AggregateFunction::Avg),
+            "MIN" => Some(AggregateFunction::Min),
+            "MAX" => Some(AggregateFunction::Max),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AggregateFunction::Count => "COUNT",
+            AggregateFunction::Sum => "SUM",
+            AggregateFunction::Avg => "AVG",
+            AggregateFunction::Min => "MIN",
+            AggregateFunction::Max => "MAX",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 /// 二元操作符
@@ -38,6 +104,45 @@ pub enum BinaryOperator {
     Or,
 }
 
+impl BinaryOperator {
+    /// 结合的紧密程度，数值越大越先计算
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::LessThan
+            | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanOrEqual => 3,
+            BinaryOperator::Add | BinaryOperator::Subtract => 4,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Equal => "=",
+            BinaryOperator::NotEqual => "<>",
+            BinaryOperator::LessThan => "<",
+            BinaryOperator::LessThanOrEqual => "<=",
+            BinaryOperator::GreaterThan => ">",
+            BinaryOperator::GreaterThanOrEqual => ">=",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
 /// 一元操作符
 #[derive(Clone, Debug, PartialEq)]
 pub enum UnaryOperator {
@@ -46,6 +151,60 @@ pub enum UnaryOperator {
     Plus,
 }
 
+/// 按 SQL 写法输出，字符串字面量加单引号，嵌套的二元运算加括号
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Column(name) => write!(f, "{}", name),
+            Expression::Value(Value::String(s)) => write!(f, "'{}'", s.replace('\'', "''")),
+            Expression::Value(value) => write!(f, "{}", value),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let precedence = operator.precedence();
+                let left = Operand(left, precedence);
+                // 同级运算按左结合，右边的同级运算也要加括号
+                let right = Operand(right, precedence + 1);
+                write!(f, "{} {} {}", left, operator, right)
+            }
+            Expression::Unary { operator, operand } => {
+                let prefix = match operator {
+                    UnaryOperator::Not => "NOT ",
+                    UnaryOperator::Minus => "-",
+                    UnaryOperator::Plus => "+",
+                };
+                write!(f, "{}{}", prefix, Operand(operand, u8::MAX))
+            }
+            Expression::Aggregate { func, arg: None } => write!(f, "{}(*)", func),
+            Expression::Aggregate {
+                func,
+                arg: Some(arg),
+            } => write!(f, "{}({})", func, arg),
+            Expression::Function { name, args } => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", name, args.join(", "))
+            }
+            Expression::Subquery(_) => write!(f, "(subquery)"),
+        }
+    }
+}
+
+/// 作为运算数输出的表达式，优先级低于 `.1` 的二元运算加括号
+struct Operand<'a>(&'a Expression, u8);
+
+impl std::fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            expr @ Expression::Binary { operator, .. } if operator.precedence() < self.1 => {
+                write!(f, "({})", expr)
+            }
+            expr => write!(f, "{}", expr),
+        }
+    }
+}
+
 /// 条件枚举
 #[derive(Clone, Debug, PartialEq)]
 pub enum Condition {
@@ -53,22 +212,313 @@ pub enum Condition {
     IsNull(Expression),
     IsNotNull(Expression),
     Constant(bool),
+    /// `expr [NOT] LIKE/ILIKE pattern [ESCAPE 'c']`，`escape` 为 `None` 表示不转义
+    Like {
+        expr: Expression,
+        pattern: Expression,
+        escape: Option<char>,
+        case_insensitive: bool,
+        negated: bool,
+    },
+    /// `expr [NOT] IN (SELECT ...)`
+    In {
+        expr: Expression,
+        set: InSet,
+        negated: bool,
+    },
+    /// `cond IS [NOT] TRUE/FALSE`，结果总是确定的：`NULL IS TRUE` 为假，`NULL IS NOT TRUE` 为真
+    IsTruth {
+        operand: Box<Condition>,
+        value: bool,
+        negated: bool,
+    },
     And(Box<Condition>, Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
     Not(Box<Condition>),
 }
 
+/// IN 右侧的值集合
+///
+/// 计划中是子查询；执行器在过滤之前执行一次（不支持相关子查询），
+/// 替换为子查询返回的值，逐行求值时只做成员检查。
+#[derive(Clone, Debug, PartialEq)]
+pub enum InSet {
+    Subquery(Box<Plan>),
+    Values(Vec<Value>),
+}
+
+impl Condition {
+    /// AND / OR 以及包含它们的 NOT，EXPLAIN 中展开成多行
+    fn is_compound(&self) -> bool {
+        match self {
+            Condition::And(..) | Condition::Or(..) => true,
+            Condition::Not(inner) => inner.is_compound(),
+            _ => false,
+        }
+    }
+
+    /// 把条件树逐层缩进输出，连续的同一种 AND / OR 合并成一个节点
+    fn explain_into(&self, indent: &str, lines: &mut Vec<String>) {
+        let (keyword, operands) = match self {
+            Condition::And(..) => ("AND", self.flatten()),
+            Condition::Or(..) => ("OR", self.flatten()),
+            Condition::Not(inner) if inner.is_compound() => ("NOT", vec![&**inner]),
+            leaf => {
+                lines.push(format!("{}{}", indent, leaf));
+                return;
+            }
+        };
+        lines.push(format!("{}{}", indent, keyword));
+        let indent = format!("{}  ", indent);
+        for operand in operands {
+            operand.explain_into(&indent, lines);
+        }
+    }
+
+    /// 条件中的子查询，包括 IN 的子查询和表达式中的标量子查询
+    pub fn subqueries(&self) -> Vec<&Plan> {
+        let mut found = Vec::new();
+        self.collect_subqueries(&mut found);
+        found
+    }
+
+    fn collect_subqueries<'a>(&'a self, found: &mut Vec<&'a Plan>) {
+        match self {
+            Condition::In { expr, set, .. } => {
+                expr.collect_subqueries(found);
+                if let InSet::Subquery(plan) = set {
+                    found.push(plan);
+                }
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.collect_subqueries(found);
+                right.collect_subqueries(found);
+            }
+            Condition::Not(inner) | Condition::IsTruth { operand: inner, .. } => {
+                inner.collect_subqueries(found)
+            }
+            leaf => {
+                for expr in validate::condition_expressions(leaf) {
+                    expr.collect_subqueries(found);
+                }
+            }
+        }
+    }
+
+    /// 按先序访问条件中的所有表达式及其子表达式，不进入子查询
+    pub fn walk_expressions<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        for expr in validate::condition_expressions(self) {
+            expr.walk(visit);
+        }
+    }
+
+    /// 与 [`walk_expressions`](Self::walk_expressions) 相同，但可以修改访问到的表达式
+    pub fn walk_expressions_mut(&mut self, visit: &mut impl FnMut(&mut Expression)) {
+        match self {
+            Condition::Expression(expr)
+            | Condition::IsNull(expr)
+            | Condition::IsNotNull(expr)
+            | Condition::In { expr, .. } => expr.walk_mut(visit),
+            Condition::Like { expr, pattern, .. } => {
+                expr.walk_mut(visit);
+                pattern.walk_mut(visit);
+            }
+            Condition::Constant(_) => {}
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.walk_expressions_mut(visit);
+                right.walk_expressions_mut(visit);
+            }
+            Condition::Not(inner) | Condition::IsTruth { operand: inner, .. } => {
+                inner.walk_expressions_mut(visit)
+            }
+        }
+    }
+
+    /// 条件引用的所有列名，按出现顺序，不包括子查询中的列
+    pub fn column_references(&self) -> Vec<&str> {
+        validate::condition_expressions(self)
+            .flat_map(Expression::column_references)
+            .collect()
+    }
+
+    /// 与自身同类的 AND / OR 链上的所有运算数
+    fn flatten(&self) -> Vec<&Condition> {
+        match self {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let mut operands = Vec::new();
+                for side in [left, right] {
+                    if std::mem::discriminant(&**side) == std::mem::discriminant(self) {
+                        operands.extend(side.flatten());
+                    } else {
+                        operands.push(&**side);
+                    }
+                }
+                operands
+            }
+            other => vec![other],
+        }
+    }
+}
+
+/// 单行的 SQL 写法，AND 中的 OR 加括号
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Expression(expr) => write!(f, "{}", expr),
+            Condition::IsNull(expr) => write!(f, "{} IS NULL", Operand(expr, 4)),
+            Condition::IsNotNull(expr) => write!(f, "{} IS NOT NULL", Operand(expr, 4)),
+            Condition::Constant(value) => write!(f, "{}", if *value { "TRUE" } else { "FALSE" }),
+            Condition::IsTruth {
+                operand,
+                value,
+                negated,
+            } => {
+                match &**operand {
+                    Condition::Expression(expr) => write!(f, "{}", Operand(expr, 4))?,
+                    Condition::Constant(_) => write!(f, "{}", operand)?,
+                    compound => write!(f, "({})", compound)?,
+                }
+                let not = if *negated { " NOT" } else { "" };
+                let value = if *value { "TRUE" } else { "FALSE" };
+                write!(f, " IS{} {}", not, value)
+            }
+            Condition::Like {
+                expr,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                let like = if *case_insensitive { "ILIKE" } else { "LIKE" };
+                let (expr, pattern) = (Operand(expr, 4), Operand(pattern, 4));
+                write!(f, "{} {}{} {}", expr, not, like, pattern)?;
+                match escape {
+                    Some(DEFAULT_LIKE_ESCAPE) => Ok(()),
+                    Some(c) => write!(f, " ESCAPE '{}'", c),
+                    None => write!(f, " ESCAPE ''"),
+                }
+            }
+            Condition::And(left, right) => {
+                for (i, side) in [left, right].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " AND ")?;
+                    }
+                    match &**side {
+                        or @ Condition::Or(..) => write!(f, "({})", or)?,
+                        other => write!(f, "{}", other)?,
+                    }
+                }
+                Ok(())
+            }
+            Condition::In { expr, set, negated } => {
+                let not = if *negated { "NOT " } else { "" };
+                match set {
+                    InSet::Subquery(_) => write!(f, "{} {}IN (subquery)", Operand(expr, 4), not),
+                    InSet::Values(values) => {
+                        let values: Vec<String> = values
+                            .iter()
+                            .map(|value| Expression::Value(value.clone()).to_string())
+                            .collect();
+                        write!(f, "{} {}IN ({})", Operand(expr, 4), not, values.join(", "))
+                    }
+                }
+            }
+            Condition::Or(left, right) => write!(f, "{} OR {}", left, right),
+            Condition::Not(inner) => match &**inner {
+                inner @ (Condition::And(..) | Condition::Or(..)) => write!(f, "NOT ({})", inner),
+                inner => write!(f, "NOT {}", inner),
+            },
+        }
+    }
+}
+
+/// INSERT 写入的行的来源
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertSource {
+    /// VALUES 中的字面量行，值为 None 表示 DEFAULT
+    Values(Vec<Vec<Option<Value>>>),
+    /// INSERT ... SELECT 的查询（SELECT 或 UNION 计划）
+    Select(Box<Plan>),
+}
+
 /// 选择列枚举
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SelectColumns {
     /// 通配符 * - 选择所有列
     Wildcard,
     /// 具体的列列表
     Columns(Vec<SelectItem>),
+    /// 含有限定通配符或同时有通配符和其它项的列表，如 `t.*`、`*, id`、`users.*, 1 AS one`
+    ///
+    /// 执行时由 [`SelectColumns::expand`] 按位置把通配符展开成表的各列。
+    Mixed(Vec<Projection>),
+}
+
+/// 选择列表中的一项：通配符或表达式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Projection {
+    /// `*`，或以表名、别名限定的 `t.*`
+    Wildcard(Option<String>),
+    Item(SelectItem),
+}
+
+impl SelectColumns {
+    /// 把 `Mixed` 中的通配符展开成对应表的各列，其它形式原样返回
+    ///
+    /// `table_columns` 是查询看到的列：单表时为表的各列，多表连接时列名形如 `别名.列名`；
+    /// `qualifiers` 依次是 FROM 中各表的别名（没有别名时为表名）。限定名不是其中之一时报错。
+    pub fn expand(
+        &self,
+        table_columns: &[ColumnDef],
+        qualifiers: &[&str],
+    ) -> Result<Cow<'_, SelectColumns>> {
+        let SelectColumns::Mixed(projections) = self else {
+            return Ok(Cow::Borrowed(self));
+        };
+
+        let mut items = Vec::new();
+        for projection in projections {
+            let columns: Vec<&ColumnDef> = match projection {
+                Projection::Item(item) => {
+                    items.push(item.clone());
+                    continue;
+                }
+                Projection::Wildcard(None) => table_columns.iter().collect(),
+                Projection::Wildcard(Some(qualifier)) => {
+                    let matched = qualifiers
+                        .iter()
+                        .find(|name| qualifier_matches(name, qualifier));
+                    let Some(matched) = matched else {
+                        return Err(DBError::Planner(format!("Unknown table '{}'", qualifier)));
+                    };
+                    if qualifiers.len() == 1 {
+                        table_columns.iter().collect()
+                    } else {
+                        let prefix = format!("{}.", matched);
+                        table_columns
+                            .iter()
+                            .filter(|column| {
+                                column.name.len() > prefix.len()
+                                    && column.name.is_char_boundary(prefix.len())
+                                    && column.name[..prefix.len()].eq_ignore_ascii_case(&prefix)
+                            })
+                            .collect()
+                    }
+                }
+            };
+            items.extend(columns.into_iter().map(|column| SelectItem {
+                expr: Expression::Column(column.name.clone()),
+                alias: None,
+                original_text: column.name.clone(),
+            }));
+        }
+        Ok(Cow::Owned(SelectColumns::Columns(items)))
+    }
 }
 
 /// 选择项结构
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SelectItem {
     pub expr: Expression,
     pub alias: Option<String>,
@@ -77,45 +527,163 @@ pub struct SelectItem {
 }
 
 /// 排序方向
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortDirection {
     Asc,
     Desc,
 }
 
+/// NULL 在排序结果中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+impl NullsOrder {
+    /// 未指定 NULLS FIRST/LAST 时的默认位置：NULL 视为最小值，
+    /// 即升序时排在最前，降序时排在最后（与 MySQL 一致）
+    pub fn default_for(direction: &SortDirection) -> Self {
+        match direction {
+            SortDirection::Asc => NullsOrder::First,
+            SortDirection::Desc => NullsOrder::Last,
+        }
+    }
+}
+
+/// 排序依据
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortTarget {
+    /// 列名或 SELECT 列表中的别名
+    Column(String),
+    /// SELECT 列表中的位置（`ORDER BY 2`），从 1 开始
+    Position(usize),
+    /// 表达式，`text` 为原始 SQL 文本，与某个结果列名相同时直接按该列排序
+    Expression { expr: Expression, text: String },
+}
+
+impl std::fmt::Display for SortTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortTarget::Column(column) => write!(f, "{}", column),
+            SortTarget::Position(position) => write!(f, "{}", position),
+            SortTarget::Expression { text, .. } => write!(f, "{}", text),
+        }
+    }
+}
+
 /// 排序项
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct OrderByItem {
-    pub column: String,
+    pub target: SortTarget,
     pub direction: SortDirection,
+    /// NULL 的位置，不受排序方向影响
+    pub nulls: NullsOrder,
+}
+
+impl std::fmt::Display for OrderByItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        let nulls = match self.nulls {
+            NullsOrder::First => "FIRST",
+            NullsOrder::Last => "LAST",
+        };
+        write!(f, "{} {} NULLS {}", self.target, direction, nulls)
+    }
+}
+
+/// INNER JOIN 子句
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub table_name: String,
+    pub alias: Option<String>,
+    /// ON 条件，列名可以用表名或别名限定（如 `b.id`）
+    pub on: Condition,
+}
+
+/// SELECT ... INTO 或 CREATE TABLE ... AS SELECT 的目标表
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectInto {
+    pub table: String,
+    /// `CREATE OR REPLACE TABLE ... AS SELECT`：目标表已存在时用查询结果替换它，否则报错
+    pub overwrite: bool,
+}
+
+impl std::fmt::Display for SelectInto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.table)?;
+        if self.overwrite {
+            write!(f, " (REPLACE)")?;
+        }
+        Ok(())
+    }
 }
 
 /// 查询计划枚举
-#[derive(Debug)]
+///
+/// 每条语句只生成一个计划，Select 变体较大也无需装箱。
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Plan {
     CreateTable {
         name: String,
         columns: Vec<ColumnDef>,
+        /// 表定义中内联的 KEY / INDEX / UNIQUE KEY
+        indexes: Vec<IndexDef>,
+        /// 列级和表级的 CHECK 约束，按出现的顺序
+        checks: Vec<CheckConstraint>,
+        /// CREATE TABLE IF NOT EXISTS：表已存在时不报错
+        if_not_exists: bool,
     },
     DropTable {
         //name: String,
         name_vec: Vec<String>,
+        /// DROP TABLE IF EXISTS：表不存在时不报错
+        if_exists: bool,
+    },
+    /// ALTER TABLE ADD/DROP COLUMN，操作按顺序应用
+    AlterTable {
+        table_name: String,
+        operations: Vec<AlterColumn>,
     },
     Select {
         table_name: Option<String>,
         columns: SelectColumns,
+        /// SELECT DISTINCT：去掉重复的结果行
+        distinct: bool,
         conditions: Option<Condition>,
         order_by: Option<Vec<OrderByItem>>,
+        /// FROM 表的别名
+        table_alias: Option<String>,
+        /// 依次与 FROM 表连接的表，没有 JOIN 时为空
+        joins: Vec<Join>,
+        /// GROUP BY 表达式，没有 GROUP BY 时为空
+        group_by: Vec<Expression>,
+        having: Option<Condition>,
+        /// LIMIT 行数，没有 LIMIT 时不限制
+        limit: Option<u64>,
+        /// OFFSET 跳过的行数
+        offset: Option<u64>,
+        /// SELECT ... INTO 或 CREATE TABLE ... AS SELECT 的目标表
+        into: Option<SelectInto>,
     },
     Insert {
         table_name: String,
         /// 空时表示插入所有列， 非空时表示指定列
         columns: Vec<String>,
-        rows: Vec<Vec<Value>>,
+        source: InsertSource,
+        /// INSERT IGNORE：跳过违反约束的行而不是报错
+        ignore: bool,
+        /// RETURNING 子句的投影
+        returning: Option<SelectColumns>,
     },
     Update {
         table_name: String,
-        set_pairs: Vec<(String, Value)>,
+        /// SET 的目标列和新值表达式，表达式按每行更新前的值求值
+        set_pairs: Vec<(String, Expression)>,
         conditions: Option<Condition>,
     },
     Delete {
@@ -133,9 +701,339 @@ pub enum Plan {
     },
     ShowDatabases,
     ShowTables,
+    ShowWarnings,
+    /// SET var = value[, ...]，值为 None 表示 DEFAULT
+    SetVariable {
+        assignments: Vec<(String, Option<Value>)>,
+    },
+    /// SHOW VARIABLES [LIKE pattern]
+    ShowVariables {
+        pattern: Option<String>,
+    },
+    /// UNION [ALL]，连续的 UNION 按左结合嵌套
+    Union {
+        left: Box<Plan>,
+        right: Box<Plan>,
+        /// UNION ALL 保留重复行，UNION 去重
+        all: bool,
+        /// 作用于合并结果的 ORDER BY，列名指结果列名
+        order_by: Option<Vec<OrderByItem>>,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    },
+    /// EXPLAIN <statement>
+    Explain(Box<Plan>),
     DescribeTable {
         name: String,
     },
+    ShowCreateTable {
+        name: String,
+    },
+    /// BEGIN / START TRANSACTION
+    Begin,
+    Commit,
+    Rollback,
+}
+
+impl Plan {
+    /// 语句类型，如 `SELECT`、`CREATE TABLE`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Plan::CreateTable { .. } => "CREATE TABLE",
+            Plan::DropTable { .. } => "DROP TABLE",
+            Plan::AlterTable { .. } => "ALTER TABLE",
+            Plan::Select { .. } => "SELECT",
+            Plan::Insert { .. } => "INSERT",
+            Plan::Update { .. } => "UPDATE",
+            Plan::Delete { .. } => "DELETE",
+            Plan::CreateDatabase { .. } => "CREATE DATABASE",
+            Plan::DropDatabase { .. } => "DROP DATABASE",
+            Plan::UseDatabase { .. } => "USE",
+            Plan::ShowDatabases => "SHOW DATABASES",
+            Plan::ShowTables => "SHOW TABLES",
+            Plan::ShowWarnings => "SHOW WARNINGS",
+            Plan::SetVariable { .. } => "SET",
+            Plan::ShowVariables { .. } => "SHOW VARIABLES",
+            Plan::Union { .. } => "UNION",
+            Plan::Explain(_) => "EXPLAIN",
+            Plan::DescribeTable { .. } => "DESCRIBE",
+            Plan::ShowCreateTable { .. } => "SHOW CREATE TABLE",
+            Plan::Begin => "BEGIN",
+            Plan::Commit => "COMMIT",
+            Plan::Rollback => "ROLLBACK",
+        }
+    }
+
+    /// 是否会修改数据或结构；USE 和 SET 只改变会话状态，不算修改，EXPLAIN 不执行语句
+    ///
+    /// 事务控制语句本身不算修改：ROLLBACK 撤销的修改在执行时已经算过。
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            Plan::CreateTable { .. }
+            | Plan::DropTable { .. }
+            | Plan::AlterTable { .. }
+            | Plan::Insert { .. }
+            | Plan::Update { .. }
+            | Plan::Delete { .. }
+            | Plan::CreateDatabase { .. }
+            | Plan::DropDatabase { .. } => true,
+            Plan::Select { into, .. } => into.is_some(),
+            Plan::Union { left, right, .. } => left.is_mutating() || right.is_mutating(),
+            Plan::UseDatabase { .. }
+            | Plan::ShowDatabases
+            | Plan::ShowTables
+            | Plan::ShowWarnings
+            | Plan::SetVariable { .. }
+            | Plan::ShowVariables { .. }
+            | Plan::Explain(_)
+            | Plan::DescribeTable { .. }
+            | Plan::ShowCreateTable { .. }
+            | Plan::Begin
+            | Plan::Commit
+            | Plan::Rollback => false,
+        }
+    }
+
+    /// EXPLAIN 的输出，每行一个节点或属性，子节点缩进两格
+    pub fn explain(&self) -> Vec<String> {
+        self.explain_with(|_| None)
+    }
+
+    /// 同 [`Plan::explain`]，`access` 为 SELECT / UPDATE / DELETE 节点给出取行方式
+    /// （如主键查找或全表扫描），返回 `None` 时不输出这一行
+    pub fn explain_with(&self, access: impl Fn(&Plan) -> Option<String>) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.explain_into(0, &access, &mut lines);
+        lines
+    }
+
+    fn explain_into(
+        &self,
+        depth: usize,
+        access: &dyn Fn(&Plan) -> Option<String>,
+        lines: &mut Vec<String>,
+    ) {
+        let indent = "  ".repeat(depth);
+        match self {
+            Plan::Select {
+                table_name,
+                columns,
+                distinct,
+                table_alias,
+                joins,
+                conditions,
+                group_by,
+                having,
+                order_by,
+                limit,
+                offset,
+                into,
+            } => {
+                let mut line = format!("{}Select", indent);
+                if *distinct {
+                    line.push_str(" DISTINCT");
+                }
+                match table_name {
+                    Some(name) => line.push_str(&format!(" FROM {}", name)),
+                    None => line.push_str(" (no table)"),
+                }
+                if let Some(alias) = table_alias {
+                    line.push_str(&format!(" AS {}", alias));
+                }
+                lines.push(line);
+                lines.push(format!("{}  COLUMNS {}", indent, explain_columns(columns)));
+                for join in joins {
+                    let mut keyword = format!("JOIN {}", join.table_name);
+                    if let Some(alias) = &join.alias {
+                        keyword.push_str(&format!(" AS {}", alias));
+                    }
+                    keyword.push_str(" ON");
+                    explain_condition(&indent, &keyword, &join.on, lines);
+                }
+                if let Some(condition) = conditions {
+                    explain_condition(&indent, "WHERE", condition, lines);
+                }
+                explain_access(&indent, self, access, lines);
+                if !group_by.is_empty() {
+                    let exprs: Vec<String> = group_by.iter().map(|expr| expr.to_string()).collect();
+                    lines.push(format!("{}  GROUP BY {}", indent, exprs.join(", ")));
+                }
+                if let Some(condition) = having {
+                    explain_condition(&indent, "HAVING", condition, lines);
+                }
+                explain_order_limit(&indent, order_by, *limit, *offset, lines);
+                if let Some(target) = into {
+                    lines.push(format!("{}  INTO {}", indent, target));
+                }
+                explain_subqueries(depth, conditions, access, lines);
+            }
+            Plan::Union {
+                left,
+                right,
+                all,
+                order_by,
+                limit,
+                offset,
+            } => {
+                let kind = if *all { "ALL" } else { "DISTINCT" };
+                lines.push(format!("{}Union {}", indent, kind));
+                explain_order_limit(&indent, order_by, *limit, *offset, lines);
+                left.explain_into(depth + 1, access, lines);
+                right.explain_into(depth + 1, access, lines);
+            }
+            Plan::Insert {
+                table_name,
+                columns,
+                source,
+                ignore,
+                returning,
+            } => {
+                let mut line = format!("{}Insert", indent);
+                if *ignore {
+                    line.push_str(" IGNORE");
+                }
+                line.push_str(&format!(" INTO {}", table_name));
+                if !columns.is_empty() {
+                    line.push_str(&format!(" ({})", columns.join(", ")));
+                }
+                lines.push(line);
+                match source {
+                    InsertSource::Values(rows) => {
+                        lines.push(format!("{}  VALUES {} rows", indent, rows.len()))
+                    }
+                    InsertSource::Select(query) => query.explain_into(depth + 1, access, lines),
+                }
+                if let Some(columns) = returning {
+                    lines.push(format!(
+                        "{}  RETURNING {}",
+                        indent,
+                        explain_columns(columns)
+                    ));
+                }
+            }
+            Plan::Update {
+                table_name,
+                set_pairs,
+                conditions,
+            } => {
+                lines.push(format!("{}Update {}", indent, table_name));
+                let assignments: Vec<String> = set_pairs
+                    .iter()
+                    .map(|(column, expr)| format!("{} = {}", column, expr))
+                    .collect();
+                lines.push(format!("{}  SET {}", indent, assignments.join(", ")));
+                if let Some(condition) = conditions {
+                    explain_condition(&indent, "WHERE", condition, lines);
+                }
+                explain_access(&indent, self, access, lines);
+                explain_subqueries(depth, conditions, access, lines);
+            }
+            Plan::Delete {
+                table_name,
+                conditions,
+            } => {
+                lines.push(format!("{}Delete FROM {}", indent, table_name));
+                if let Some(condition) = conditions {
+                    explain_condition(&indent, "WHERE", condition, lines);
+                }
+                explain_access(&indent, self, access, lines);
+                explain_subqueries(depth, conditions, access, lines);
+            }
+            Plan::Explain(inner) => inner.explain_into(depth, access, lines),
+            other => {
+                // 其余语句只输出计划类型
+                let debug = format!("{:?}", other);
+                let name = debug.split([' ', '(', '{']).next().unwrap_or_default();
+                lines.push(format!("{}{}", indent, name));
+            }
+        }
+    }
+}
+
+/// 多行的 EXPLAIN 输出
+impl std::fmt::Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.explain().join("\n"))
+    }
+}
+
+fn explain_columns(columns: &SelectColumns) -> String {
+    let explain_item = |item: &SelectItem| match &item.alias {
+        Some(alias) => format!("{} AS {}", item.expr, alias),
+        None => item.expr.to_string(),
+    };
+    match columns {
+        SelectColumns::Wildcard => "*".to_string(),
+        SelectColumns::Columns(items) => items
+            .iter()
+            .map(explain_item)
+            .collect::<Vec<_>>()
+            .join(", "),
+        SelectColumns::Mixed(projections) => projections
+            .iter()
+            .map(|projection| match projection {
+                Projection::Wildcard(None) => "*".to_string(),
+                Projection::Wildcard(Some(qualifier)) => format!("{}.*", qualifier),
+                Projection::Item(item) => explain_item(item),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// 简单条件与关键字写在同一行，AND / OR 条件树在下一行起逐层缩进
+fn explain_condition(indent: &str, keyword: &str, condition: &Condition, lines: &mut Vec<String>) {
+    if condition.is_compound() {
+        lines.push(format!("{}  {}", indent, keyword));
+        condition.explain_into(&format!("{}    ", indent), lines);
+    } else {
+        lines.push(format!("{}  {} {}", indent, keyword, condition));
+    }
+}
+
+fn explain_access(
+    indent: &str,
+    plan: &Plan,
+    access: &dyn Fn(&Plan) -> Option<String>,
+    lines: &mut Vec<String>,
+) {
+    if let Some(method) = access(plan) {
+        lines.push(format!("{}  ACCESS {}", indent, method));
+    }
+}
+
+/// WHERE 中的子查询逐个在 SUBQUERY 下缩进输出
+fn explain_subqueries(
+    depth: usize,
+    conditions: &Option<Condition>,
+    access: &dyn Fn(&Plan) -> Option<String>,
+    lines: &mut Vec<String>,
+) {
+    for subquery in conditions.iter().flat_map(Condition::subqueries) {
+        lines.push(format!("{}  SUBQUERY", "  ".repeat(depth)));
+        subquery.explain_into(depth + 2, access, lines);
+    }
+}
+
+fn explain_order_limit(
+    indent: &str,
+    order_by: &Option<Vec<OrderByItem>>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    lines: &mut Vec<String>,
+) {
+    if let Some(items) = order_by {
+        let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+        lines.push(format!("{}  ORDER BY {}", indent, items.join(", ")));
+    }
+    match (limit, offset) {
+        (Some(limit), Some(offset)) => {
+            lines.push(format!("{}  LIMIT {} OFFSET {}", indent, limit, offset))
+        }
+        (Some(limit), None) => lines.push(format!("{}  LIMIT {}", indent, limit)),
+        (None, Some(offset)) => lines.push(format!("{}  OFFSET {}", indent, offset)),
+        (None, None) => {}
+    }
 }
 
 /// 统一的查询计划生成器
@@ -150,25 +1048,45 @@ impl Planner {
     /// 主要的计划生成方法
     pub fn plan(&self, stmt: &ast::Statement) -> Result<Plan> {
         match stmt {
-            ast::Statement::CreateTable(create_table) => Ok(Plan::CreateTable {
-                name: create_table.name.to_string(),
-                columns: self.analyze_column_definitions(&create_table.columns)?,
-            }),
+            ast::Statement::CreateTable(create_table) if create_table.query.is_some() => {
+                self.plan_create_table_as(create_table)
+            }
+            ast::Statement::CreateTable(create_table) => {
+                let name = self.object_name(&create_table.name)?;
+                let mut columns = self.analyze_column_definitions(&create_table.columns)?;
+                let indexes =
+                    self.analyze_table_constraints(&create_table.constraints, &mut columns)?;
+                let checks = self.analyze_check_constraints(&name, create_table, &columns)?;
+                Ok(Plan::CreateTable {
+                    name,
+                    columns,
+                    indexes,
+                    checks,
+                    if_not_exists: create_table.if_not_exists,
+                })
+            }
 
             ast::Statement::Drop {
-                object_type, names, ..
+                object_type,
+                names,
+                if_exists,
+                ..
             } => match object_type {
                 ast::ObjectType::Table => {
                     if !names.is_empty() {
                         Ok(Plan::DropTable {
-                            name_vec: names.iter().map(|n| n.to_string()).collect(),
+                            name_vec: names
+                                .iter()
+                                .map(|name| self.object_name(name))
+                                .collect::<Result<_>>()?,
+                            if_exists: *if_exists,
                         })
                     } else {
                         //Err(DBError::Parse("DROP TABLE缺少表名".to_string()))
                         Err(DBError::Parse("Error: Syntax error".to_string()))
                     }
                 }
-                ast::ObjectType::Database => {
+                ast::ObjectType::Database | ast::ObjectType::Schema => {
                     if let Some(name) = names.first() {
                         Ok(Plan::DropDatabase {
                             name: name.to_string(),
@@ -181,6 +1099,17 @@ impl Planner {
                 _ => Err(DBError::Parse("Error: Syntax error".to_string())),
             },
 
+            ast::Statement::AlterTable {
+                name,
+                if_exists: false,
+                only: false,
+                operations,
+                ..
+            } => Ok(Plan::AlterTable {
+                table_name: self.object_name(name)?,
+                operations: self.analyze_alter_operations(operations)?,
+            }),
+
             ast::Statement::Query(query) => self.analyze_select(query),
             ast::Statement::Insert(insert) => self.plan_insert(insert),
 
@@ -191,23 +1120,36 @@ impl Planner {
                 ..
             } => {
                 let sqlparser::ast::TableWithJoins { relation, .. } = table;
-                let table_name = match relation {
-                    ast::TableFactor::Table { name, .. } => name.to_string(),
-                    _ => return Err(DBError::Planner("仅支持简单表引用".to_string())),
-                };
+                let (table_name, alias) = self.analyze_table_factor(relation)?;
+                let qualifier = alias.as_deref().unwrap_or(&table_name);
                 let mut set_pairs = Vec::new();
 
                 for assignment in assignments {
-                    let column_name = assignment.target.to_string();
-                    let value = self.analyze_expr_to_value(&assignment.value)?;
+                    // `列名` 或 `表名.列名`，限定名由下面的 unqualify_column 去掉
+                    let mut column_name = match &assignment.target {
+                        ast::AssignmentTarget::ColumnName(name) if name.0.len() <= 2 => name
+                            .0
+                            .iter()
+                            .map(|part| part.as_ident().map(|ident| self.identifier(ident)))
+                            .collect::<Option<Vec<_>>>()
+                            .map(|parts| parts.join(".")),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| assignment.target.to_string());
+                    unqualify_column(&mut column_name, qualifier);
+                    let mut value = self.convert_expr(&assignment.value)?;
+                    unqualify_columns(&mut value, qualifier);
                     set_pairs.push((column_name, value));
                 }
 
-                let conditions = if let Some(expr) = selection {
+                let mut conditions = if let Some(expr) = selection {
                     Some(self.analyze_condition(expr)?)
                 } else {
                     None
                 };
+                if let Some(condition) = &mut conditions {
+                    condition.walk_expressions_mut(&mut |expr| unqualify_columns(expr, qualifier));
+                }
 
                 Ok(Plan::Update {
                     table_name,
@@ -217,39 +1159,54 @@ impl Planner {
             }
 
             ast::Statement::Delete(delete) => {
-                //have bug “仅支持单表删除”
-                if delete.tables.len() > 1 {
+                let from = match &delete.from {
+                    ast::FromTable::WithFromKeyword(from) | ast::FromTable::WithoutKeyword(from) => {
+                        from
+                    }
+                };
+                // 多表删除（DELETE t1, t2 FROM ... / USING / JOIN）不支持
+                if delete.tables.len() > 1
+                    || delete.using.is_some()
+                    || from.iter().any(|table| !table.joins.is_empty())
+                {
                     return Err(DBError::Parse("仅支持单表删除".to_string()));
                 }
-                //have bug delete.tables为空
-                //let table_name = delete.tables[0].to_string();
-                // 兼容不同SQL解析器的Delete结构
-                let table_name: String = if !delete.tables.is_empty() {
-                    delete.tables[0].to_string()
-                } else {
-                    let from = &delete.from;
-                    let from_str = from.to_string();
-                    //此时from的格式为“FROM table_name”，需要从中截取出table_name
-                    let parts: Vec<&str> = from_str.split_whitespace().collect();
-                    if parts.len() == 2 && parts[0].eq_ignore_ascii_case("from") {
-                        parts[1].to_string()
-                    } else {
-                        from_str
-                    }
+                let (table_name, alias) = match from.as_slice() {
+                    [table] => match &table.relation {
+                        relation @ ast::TableFactor::Table { .. } => {
+                            self.analyze_table_factor(relation)?
+                        }
+                        _ => {
+                            return Err(DBError::Planner(
+                                "DELETE 仅支持普通表，不支持子查询或表函数".to_string(),
+                            ));
+                        }
+                    },
+                    _ => return Err(DBError::Parse("DELETE 缺少表名".to_string())),
                 };
+                // MySQL 单表形式 DELETE t FROM t 中，目标表必须就是 FROM 的表
+                if let Some(target) = delete.tables.first()
+                    && self.object_name(target)? != table_name
+                {
+                    return Err(DBError::Parse("仅支持单表删除".to_string()));
+                }
+                // 忽略这些子句会删除比预期更多的行，因此直接拒绝
+                if delete.limit.is_some() || !delete.order_by.is_empty() || delete.returning.is_some()
+                {
+                    return Err(DBError::Planner(
+                        "DELETE 暂不支持 ORDER BY、LIMIT 和 RETURNING".to_string(),
+                    ));
+                }
 
-                // 输出表的名字
-                /*
-                return Err(DBError::Parse(
-                    format!("DELETE 语句的表名: {}", table_name),
-                ));
-                */
-
-                let conditions = if let Some(expr) = &delete.selection {
+                let mut conditions = if let Some(expr) = &delete.selection {
                     Some(self.analyze_condition(expr)?)
                 } else {
                     None
                 };
+                if let Some(condition) = &mut conditions {
+                    let qualifier = alias.as_deref().unwrap_or(&table_name);
+                    condition.walk_expressions_mut(&mut |expr| unqualify_columns(expr, qualifier));
+                }
 
                 Ok(Plan::Delete {
                     table_name,
@@ -257,12 +1214,69 @@ impl Planner {
                 })
             }
 
+            ast::Statement::StartTransaction {
+                modes, statements, ..
+            } => {
+                if !modes.is_empty() || !statements.is_empty() {
+                    return Err(DBError::Planner("仅支持不带选项的 BEGIN".to_string()));
+                }
+                Ok(Plan::Begin)
+            }
+            ast::Statement::Commit { chain, .. } => {
+                if *chain {
+                    return Err(DBError::Planner("不支持 COMMIT AND CHAIN".to_string()));
+                }
+                Ok(Plan::Commit)
+            }
+            ast::Statement::Rollback { chain, savepoint } => {
+                if *chain || savepoint.is_some() {
+                    return Err(DBError::Planner(
+                        "不支持 ROLLBACK AND CHAIN 和 ROLLBACK TO SAVEPOINT".to_string(),
+                    ));
+                }
+                Ok(Plan::Rollback)
+            }
+
             ast::Statement::ShowTables { .. } => Ok(Plan::ShowTables),
+            ast::Statement::ShowVariable { variable }
+                if matches!(variable.as_slice(), [ident] if ident.value.eq_ignore_ascii_case("WARNINGS")) =>
+            {
+                Ok(Plan::ShowWarnings)
+            }
             ast::Statement::ShowDatabases { .. } => Ok(Plan::ShowDatabases),
+            ast::Statement::ShowVariables { filter, global, .. } => {
+                if *global {
+                    return Err(DBError::Planner("不支持全局变量".to_string()));
+                }
+                let pattern = match filter {
+                    None => None,
+                    Some(ast::ShowStatementFilter::Like(pattern)) => Some(pattern.clone()),
+                    Some(other) => {
+                        return Err(DBError::Planner(format!(
+                            "SHOW VARIABLES 仅支持 LIKE 过滤: {}",
+                            other
+                        )));
+                    }
+                };
+                Ok(Plan::ShowVariables { pattern })
+            }
+            ast::Statement::Set(set) => self.analyze_set(set),
+            ast::Statement::Explain {
+                statement,
+                analyze: false,
+                ..
+            } => Ok(Plan::Explain(Box::new(self.plan(statement)?))),
 
             ast::Statement::CreateDatabase { db_name, .. } => Ok(Plan::CreateDatabase {
                 name: db_name.to_string(),
             }),
+            // MySQL 中 SCHEMA 是 DATABASE 的同义词
+            ast::Statement::CreateSchema {
+                schema_name: ast::SchemaName::Simple(name),
+                ..
+            } => Ok(Plan::CreateDatabase {
+                name: name.to_string(),
+            }),
 
             ast::Statement::Use(use_stmt) => match use_stmt {
                 ast::Use::Database(name) => Ok(Plan::UseDatabase {
@@ -278,18 +1292,172 @@ impl Planner {
             },
 
             ast::Statement::ExplainTable { table_name, .. } => Ok(Plan::DescribeTable {
-                name: table_name.to_string(),
+                name: self.object_name(table_name)?,
+            }),
+            // SHOW [FULL] COLUMNS FROM <表名> 与 DESCRIBE 相同
+            ast::Statement::ShowColumns { show_options, .. } => {
+                if show_options.filter_position.is_some() {
+                    return Err(DBError::Planner("SHOW COLUMNS 不支持 LIKE/WHERE 过滤".to_string()));
+                }
+                match &show_options.show_in {
+                    Some(ast::ShowStatementIn {
+                        parent_name: Some(table_name),
+                        ..
+                    }) => Ok(Plan::DescribeTable {
+                        name: self.object_name(table_name)?,
+                    }),
+                    _ => Err(DBError::Planner("SHOW COLUMNS 需要指定表名".to_string())),
+                }
+            }
+
+            ast::Statement::ShowCreate {
+                obj_type: ast::ShowCreateObject::Table,
+                obj_name,
+            } => Ok(Plan::ShowCreateTable {
+                name: self.object_name(obj_name)?,
             }),
 
             _ => Err(DBError::Parse("Error: Syntax error".to_string())),
         }
     }
 
-    /// 分析 SELECT 查询
+    /// 分析 SELECT 查询（包括 UNION）
     fn analyze_select(&self, query: &ast::Query) -> Result<Plan> {
-        let body = match &*query.body {
-            ast::SetExpr::Select(select) => &**select,
-            _ => return Err(DBError::Planner("仅支持SELECT查询".to_string())),
+        let (limit, offset) = self.analyze_limit(query.limit_clause.as_ref())?;
+        let order_by = if let Some(ref order_by_clause) = query.order_by {
+            match &order_by_clause.kind {
+                ast::OrderByKind::Expressions(exprs) => Some(self.analyze_order_by(exprs)?),
+                ast::OrderByKind::All(_) => {
+                    return Err(DBError::Planner("暂不支持 ORDER BY ALL 语法".to_string()));
+                }
+            }
+        } else {
+            None
+        };
+
+        match &*query.body {
+            ast::SetExpr::Select(select) => {
+                self.analyze_select_body(select, order_by, limit, offset)
+            }
+            ast::SetExpr::SetOperation { .. } => match self.analyze_set_expr(&query.body)? {
+                Plan::Union {
+                    left, right, all, ..
+                } => Ok(Plan::Union {
+                    left,
+                    right,
+                    all,
+                    order_by,
+                    limit,
+                    offset,
+                }),
+                other => Ok(other),
+            },
+            // 带括号的查询，如 (SELECT ...)
+            ast::SetExpr::Query(inner)
+                if order_by.is_none() && limit.is_none() && offset.is_none() =>
+            {
+                self.analyze_select(inner)
+            }
+            _ => Err(DBError::Planner("仅支持SELECT查询".to_string())),
+        }
+    }
+
+    /// 分析 UNION 的一侧
+    fn analyze_set_expr(&self, expr: &ast::SetExpr) -> Result<Plan> {
+        match expr {
+            ast::SetExpr::Select(select) => self.analyze_select_body(select, None, None, None),
+            ast::SetExpr::Query(query) => self.analyze_select(query),
+            ast::SetExpr::SetOperation {
+                op: ast::SetOperator::Union,
+                set_quantifier,
+                left,
+                right,
+            } => {
+                let all = match set_quantifier {
+                    ast::SetQuantifier::All => true,
+                    ast::SetQuantifier::Distinct | ast::SetQuantifier::None => false,
+                    other => {
+                        return Err(DBError::Planner(format!("不支持 UNION {}", other)));
+                    }
+                };
+                Ok(Plan::Union {
+                    left: Box::new(self.analyze_set_expr(left)?),
+                    right: Box::new(self.analyze_set_expr(right)?),
+                    all,
+                    order_by: None,
+                    limit: None,
+                    offset: None,
+                })
+            }
+            ast::SetExpr::SetOperation { op, .. } => {
+                Err(DBError::Planner(format!("暂不支持 {} 操作", op)))
+            }
+            _ => Err(DBError::Planner("仅支持SELECT查询".to_string())),
+        }
+    }
+
+    /// `CREATE [OR REPLACE] TABLE name AS SELECT ...`
+    ///
+    /// 与 `SELECT ... INTO name` 生成同样的计划，列定义由查询结果推断；
+    /// `OR REPLACE` 时目标表已存在也会被查询结果替换。
+    fn plan_create_table_as(&self, create_table: &ast::CreateTable) -> Result<Plan> {
+        if !create_table.columns.is_empty() || !create_table.constraints.is_empty() {
+            return Err(DBError::Planner(
+                "CREATE TABLE ... AS SELECT 不支持同时定义列或约束".to_string(),
+            ));
+        }
+        if create_table.if_not_exists || create_table.temporary {
+            return Err(DBError::Planner(
+                "CREATE TABLE ... AS SELECT 不支持 IF NOT EXISTS 或 TEMPORARY".to_string(),
+            ));
+        }
+        let table = self.object_name(&create_table.name)?;
+        let Some(query) = &create_table.query else {
+            return Err(DBError::Planner("CREATE TABLE ... AS 缺少查询".to_string()));
+        };
+        let mut plan = self.analyze_select(query)?;
+        match &mut plan {
+            Plan::Select { into: Some(_), .. } => Err(DBError::Planner(
+                "CREATE TABLE ... AS SELECT 中的查询不能再带 INTO".to_string(),
+            )),
+            Plan::Select { into, .. } => {
+                *into = Some(SelectInto {
+                    table,
+                    overwrite: create_table.or_replace,
+                });
+                Ok(plan)
+            }
+            _ => Err(DBError::Planner(
+                "CREATE TABLE ... AS 只支持单个 SELECT 查询".to_string(),
+            )),
+        }
+    }
+
+    /// 分析单个 SELECT，ORDER BY 和 LIMIT 来自外层查询
+    fn analyze_select_body(
+        &self,
+        body: &ast::Select,
+        mut order_by: Option<Vec<OrderByItem>>,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> Result<Plan> {
+        let into = match &body.into {
+            Some(into) if into.temporary || into.unlogged => {
+                return Err(DBError::Planner(format!("不支持 INTO {}", into)));
+            }
+            // `INTO TABLE name` 与 PostgreSQL 一样只是另一种写法，同样只创建新表
+            Some(into) => Some(SelectInto {
+                table: self.object_name(&into.name)?,
+                overwrite: false,
+            }),
+            None => None,
+        };
+        let distinct = match &body.distinct {
+            None => false,
+            Some(ast::Distinct::Distinct) => true,
+            Some(ast::Distinct::On(_)) => {
+                return Err(DBError::Planner("不支持 DISTINCT ON".to_string()));
+            }
         };
 
         if body.from.is_empty() {
@@ -298,94 +1466,214 @@ impl Planner {
             Ok(Plan::Select {
                 table_name: None,
                 columns,
+                distinct,
                 conditions: None,
                 order_by: None,
+                table_alias: None,
+                joins: Vec::new(),
+                group_by: Vec::new(),
+                having: None,
+                limit,
+                offset,
+                into,
             })
         } else {
             // 有表查询
-            let table_name = self.extract_table_name(&body.from)?;
-            let columns = self.analyze_select_columns(&body.projection)?;
+            let (table_name, table_alias, joins) = self.analyze_from(&body.from)?;
+            let mut columns = self.analyze_select_columns(&body.projection)?;
 
-            let conditions = if let Some(selection) = &body.selection {
+            let mut conditions = if let Some(selection) = &body.selection {
                 Some(self.analyze_condition(selection)?)
             } else {
                 None
             };
 
-            let order_by = if let Some(ref order_by_clause) = query.order_by {
-                match &order_by_clause.kind {
-                    ast::OrderByKind::Expressions(exprs) => Some(self.analyze_order_by(exprs)?),
-                    ast::OrderByKind::All(_) => {
-                        return Err(DBError::Planner("暂不支持 ORDER BY ALL 语法".to_string()));
-                    }
+            let mut group_by = match &body.group_by {
+                ast::GroupByExpr::Expressions(exprs, modifiers) if modifiers.is_empty() => exprs
+                    .iter()
+                    .map(|expr| self.convert_expr(expr))
+                    .collect::<Result<Vec<_>>>()?,
+                _ => {
+                    return Err(DBError::Planner(format!(
+                        "不支持的 GROUP BY 语法: {}",
+                        body.group_by
+                    )));
                 }
-            } else {
-                None
             };
 
+            let mut having = match &body.having {
+                Some(having) => Some(self.analyze_condition(having)?),
+                None => None,
+            };
+
+            if joins.is_empty() {
+                let qualifier = table_alias.as_deref().unwrap_or(&table_name);
+                let mut unqualify = |expr: &mut Expression| unqualify_columns(expr, qualifier);
+                match &mut columns {
+                    SelectColumns::Wildcard => {}
+                    SelectColumns::Columns(items) => {
+                        items.iter_mut().for_each(|item| unqualify(&mut item.expr))
+                    }
+                    SelectColumns::Mixed(projections) => {
+                        for projection in projections {
+                            if let Projection::Item(item) = projection {
+                                unqualify(&mut item.expr);
+                            }
+                        }
+                    }
+                }
+                group_by.iter_mut().for_each(&mut unqualify);
+                for condition in conditions.iter_mut().chain(having.iter_mut()) {
+                    condition.walk_expressions_mut(&mut unqualify);
+                }
+                for item in order_by.iter_mut().flatten() {
+                    match &mut item.target {
+                        SortTarget::Column(name) => unqualify_column(name, qualifier),
+                        SortTarget::Expression { expr, .. } => unqualify(expr),
+                        SortTarget::Position(_) => {}
+                    }
+                }
+            }
+
             Ok(Plan::Select {
                 table_name: Some(table_name),
                 columns,
+                distinct,
                 conditions,
                 order_by,
+                table_alias,
+                joins,
+                group_by,
+                having,
+                limit,
+                offset,
+                into,
             })
         }
     }
 
+    /// 分析 LIMIT / OFFSET，返回 (limit, offset)
+    ///
+    /// 同时支持 `LIMIT n OFFSET m` 和 MySQL 的 `LIMIT m, n`。
+    fn analyze_limit(
+        &self,
+        limit_clause: Option<&ast::LimitClause>,
+    ) -> Result<(Option<u64>, Option<u64>)> {
+        match limit_clause {
+            None => Ok((None, None)),
+            Some(ast::LimitClause::LimitOffset {
+                limit,
+                offset,
+                limit_by,
+            }) => {
+                if !limit_by.is_empty() {
+                    return Err(DBError::Planner("不支持 LIMIT BY 语法".to_string()));
+                }
+                let limit = limit.as_ref().map(|expr| self.row_count(expr, "LIMIT")).transpose()?;
+                let offset = offset
+                    .as_ref()
+                    .map(|offset| self.row_count(&offset.value, "OFFSET"))
+                    .transpose()?;
+                Ok((limit, offset))
+            }
+            Some(ast::LimitClause::OffsetCommaLimit { offset, limit }) => Ok((
+                Some(self.row_count(limit, "LIMIT")?),
+                Some(self.row_count(offset, "OFFSET")?),
+            )),
+        }
+    }
+
+    /// LIMIT / OFFSET 的行数必须是非负整数常量
+    fn row_count(&self, expr: &ast::Expr, clause: &str) -> Result<u64> {
+        if let ast::Expr::Value(value) = expr
+            && let ast::Value::Number(n, _) = &value.value
+            && let Ok(count) = n.parse::<u64>()
+        {
+            return Ok(count);
+        }
+        Err(DBError::Planner(format!(
+            "{} 的值必须是非负整数: {}",
+            clause, expr
+        )))
+    }
+
     /// 分析选择列
+    ///
+    /// 只有一个 `*` 时为 [`SelectColumns::Wildcard`]；有限定通配符，或通配符与其它项混用时
+    /// 为 [`SelectColumns::Mixed`]，按原来的顺序保留各项。`*, id`、`*, *` 合法，
+    /// `id, *` 与 MySQL 一样是语法错误。
     fn analyze_select_columns(&self, projection: &[ast::SelectItem]) -> Result<SelectColumns> {
-        let has_wildcard = projection.iter().any(|item| {
-            matches!(
-                item,
-                ast::SelectItem::Wildcard(_) | ast::SelectItem::QualifiedWildcard(_, _)
-            )
-        });
-
-        if has_wildcard {
-            if projection.len() > 1 {
-                return Err(DBError::Parse("Error: Syntax error".to_string()));
-            }
+        if let [ast::SelectItem::Wildcard(_)] = projection {
             return Ok(SelectColumns::Wildcard);
         }
 
-        let mut columns = Vec::new();
+        let mut projections = Vec::new();
         for item in projection {
-            match item {
-                ast::SelectItem::UnnamedExpr(expr) => {
-                    let expression = self.convert_expr(expr)?;
-                    let original_text = format!("{}", expr);
-
-                    columns.push(SelectItem {
-                        expr: expression,
-                        alias: None,
-                        original_text,
-                    });
-                }
+            projections.push(match item {
+                ast::SelectItem::UnnamedExpr(expr) => Projection::Item(SelectItem {
+                    expr: self.convert_expr(expr)?,
+                    alias: None,
+                    original_text: format!("{}", expr),
+                }),
 
-                ast::SelectItem::ExprWithAlias { expr, alias } => {
-                    let expression = self.convert_expr(expr)?;
-                    let original_text = format!("{}", expr);
+                ast::SelectItem::ExprWithAlias { expr, alias } => Projection::Item(SelectItem {
+                    expr: self.convert_expr(expr)?,
+                    alias: Some(alias.to_string()),
+                    original_text: format!("{}", expr),
+                }),
 
-                    columns.push(SelectItem {
-                        expr: expression,
-                        alias: Some(alias.to_string()),
-                        original_text,
-                    });
+                // 与 MySQL 一样，不带限定的 * 不能跟在表达式后面（`SELECT id, *` 是语法错误）
+                ast::SelectItem::Wildcard(_)
+                    if projections
+                        .iter()
+                        .any(|projection| matches!(projection, Projection::Item(_))) =>
+                {
+                    return Err(DBError::Parse("Error: Syntax error".to_string()));
                 }
+                ast::SelectItem::Wildcard(_) => Projection::Wildcard(None),
 
-                ast::SelectItem::Wildcard(_) | ast::SelectItem::QualifiedWildcard(_, _) => {
-                    unreachable!("通配符应该在前面已经处理");
+                ast::SelectItem::QualifiedWildcard(kind, _) => {
+                    let ast::SelectItemQualifiedWildcardKind::ObjectName(name) = kind else {
+                        return Err(DBError::Parse("Error: Syntax error".to_string()));
+                    };
+                    Projection::Wildcard(Some(self.object_name(name)?))
                 }
-            }
+            });
         }
 
-        Ok(SelectColumns::Columns(columns))
+        if projections
+            .iter()
+            .all(|projection| matches!(projection, Projection::Item(_)))
+        {
+            let items = projections
+                .into_iter()
+                .filter_map(|projection| match projection {
+                    Projection::Item(item) => Some(item),
+                    Projection::Wildcard(_) => None,
+                })
+                .collect();
+            return Ok(SelectColumns::Columns(items));
+        }
+        Ok(SelectColumns::Mixed(projections))
     }
 
     /// 转换表达式
     pub fn convert_expr(&self, expr: &ast::Expr) -> Result<Expression> {
         match expr {
-            ast::Expr::Identifier(ident) => Ok(Expression::Column(ident.value.clone())),
+            ast::Expr::Identifier(ident) => Ok(Expression::Column(self.identifier(ident))),
+
+            // 限定列名 `表名.列名` / `别名.列名`
+            ast::Expr::CompoundIdentifier(parts) => match parts.as_slice() {
+                [table, column] => Ok(Expression::Column(format!(
+                    "{}.{}",
+                    self.identifier(table),
+                    self.identifier(column)
+                ))),
+                _ => Err(DBError::Planner(format!(
+                    "不支持的限定列名: {}",
+                    expr
+                ))),
+            },
 
             ast::Expr::Value(value_with_span) => {
                 let value = self.convert_ast_value(&value_with_span.value)?;
@@ -405,6 +1693,13 @@ impl Planner {
             }
 
             ast::Expr::UnaryOp { op, expr } => {
+                // `-2147483648` 整体作为一个字面量，否则 2147483648 本身就超出 INT 范围
+                if let (ast::UnaryOperator::Minus, ast::Expr::Value(value)) = (op, &**expr)
+                    && let ast::Value::Number(n, long) = &value.value
+                {
+                    let negative = ast::Value::Number(format!("-{}", n), *long);
+                    return self.convert_ast_value(&negative).map(Expression::Value);
+                }
                 let operand = Box::new(self.convert_expr(expr)?);
                 let operator = self.convert_unary_operator(op)?;
                 Ok(Expression::Unary { operator, operand })
@@ -423,10 +1718,131 @@ impl Planner {
                 Err(DBError::Planner("IS NOT NULL 应在条件层处理".to_string()))
             }
 
+            ast::Expr::Function(function) => self.convert_function(function),
+            ast::Expr::Nested(inner) => self.convert_expr(inner),
+            ast::Expr::Subquery(query) => Ok(Expression::Subquery(
+                self.subquery_plan(self.analyze_select(query)?)?,
+            )),
+
+            // SUBSTR(str, start, len) / SUBSTRING(str FROM start FOR len)
+            ast::Expr::Substring {
+                expr: inner,
+                substring_from: Some(from),
+                substring_for,
+                ..
+            } => {
+                let mut args = vec![self.convert_expr(inner)?, self.convert_expr(from)?];
+                if let Some(len) = substring_for {
+                    args.push(self.convert_expr(len)?);
+                }
+                Ok(Expression::Function {
+                    name: ScalarFunction::Substr,
+                    args,
+                })
+            }
+
+            ast::Expr::Trim {
+                expr: inner,
+                trim_where: None,
+                trim_what: None,
+                trim_characters: None,
+            } => Ok(Expression::Function {
+                name: ScalarFunction::Trim,
+                args: vec![self.convert_expr(inner)?],
+            }),
+
             _ => Err(DBError::Planner(format!("不支持的表达式: {:?}", expr))),
         }
     }
 
+    /// 转换函数调用：聚合函数或标量函数
+    fn convert_function(&self, function: &ast::Function) -> Result<Expression> {
+        let name = function.name.to_string();
+        if let Some(scalar) = ScalarFunction::from_name(&name) {
+            return self.convert_scalar_function(scalar, function);
+        }
+        let func = AggregateFunction::from_name(&name)
+            .ok_or_else(|| DBError::Planner(format!("不支持的函数: {}", name)))?;
+
+        let list = match &function.args {
+            ast::FunctionArguments::List(list)
+                if list.duplicate_treatment.is_none()
+                    && list.clauses.is_empty()
+                    && function.filter.is_none()
+                    && function.over.is_none()
+                    && function.within_group.is_empty() =>
+            {
+                list
+            }
+            _ => {
+                return Err(DBError::Planner(format!(
+                    "不支持的聚合函数用法: {}",
+                    function
+                )));
+            }
+        };
+
+        let arg = match list.args.as_slice() {
+            [ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Wildcard)]
+                if func == AggregateFunction::Count =>
+            {
+                None
+            }
+            [ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(expr))] => {
+                Some(Box::new(self.convert_expr(expr)?))
+            }
+            _ => {
+                return Err(DBError::Planner(format!(
+                    "聚合函数 {} 需要且只接受一个参数: {}",
+                    func, function
+                )));
+            }
+        };
+
+        Ok(Expression::Aggregate { func, arg })
+    }
+
+    /// 转换标量函数调用，参数只能是普通表达式
+    fn convert_scalar_function(
+        &self,
+        name: ScalarFunction,
+        function: &ast::Function,
+    ) -> Result<Expression> {
+        let list = match &function.args {
+            ast::FunctionArguments::List(list)
+                if list.duplicate_treatment.is_none()
+                    && list.clauses.is_empty()
+                    && function.filter.is_none()
+                    && function.over.is_none()
+                    && function.within_group.is_empty() =>
+            {
+                list
+            }
+            _ => {
+                return Err(DBError::Planner(format!(
+                    "不支持的函数用法: {}",
+                    function
+                )));
+            }
+        };
+
+        let args = list
+            .args
+            .iter()
+            .map(|arg| match arg {
+                ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(expr)) => {
+                    self.convert_expr(expr)
+                }
+                _ => Err(DBError::Planner(format!(
+                    "函数 {} 的参数必须是表达式: {}",
+                    name, function
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        name.check_arity(args.len())?;
+        Ok(Expression::Function { name, args })
+    }
+
     /// 分析条件
     pub fn analyze_condition(&self, expr: &ast::Expr) -> Result<Condition> {
         use sqlparser::ast::{BinaryOperator, Expr};
@@ -440,6 +1856,20 @@ impl Planner {
                 let expr = self.convert_expr(inner_expr)?;
                 Ok(Condition::IsNotNull(expr))
             }
+            Expr::Like {
+                negated,
+                any,
+                expr,
+                pattern,
+                escape_char,
+            } => self.analyze_like(*negated, *any, expr, pattern, escape_char, false),
+            Expr::ILike {
+                negated,
+                any,
+                expr,
+                pattern,
+                escape_char,
+            } => self.analyze_like(*negated, *any, expr, pattern, escape_char, true),
             Expr::BinaryOp { left, op, right } => {
                 match op {
                     BinaryOperator::And => Ok(Condition::And(
@@ -469,6 +1899,20 @@ impl Planner {
                     }
                 }
             }
+            Expr::IsTrue(inner) => self.analyze_truth_test(inner, true, false),
+            Expr::IsNotTrue(inner) => self.analyze_truth_test(inner, true, true),
+            Expr::IsFalse(inner) => self.analyze_truth_test(inner, false, false),
+            Expr::IsNotFalse(inner) => self.analyze_truth_test(inner, false, true),
+            Expr::Nested(inner) => self.analyze_condition(inner),
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => Ok(Condition::In {
+                expr: self.convert_expr(expr)?,
+                set: InSet::Subquery(self.subquery_plan(self.analyze_set_expr(subquery)?)?),
+                negated: *negated,
+            }),
             Expr::Value(value) => {
                 if let sqlparser::ast::Value::Boolean(b) = &value.value {
                     Ok(Condition::Constant(*b))
@@ -483,6 +1927,72 @@ impl Planner {
             }
         }
     }
+
+    /// 分析 `expr IS [NOT] TRUE/FALSE`，被检验的可以是任意条件
+    fn analyze_truth_test(
+        &self,
+        inner: &ast::Expr,
+        value: bool,
+        negated: bool,
+    ) -> Result<Condition> {
+        Ok(Condition::IsTruth {
+            operand: Box::new(self.analyze_condition(inner)?),
+            value,
+            negated,
+        })
+    }
+
+    /// 子查询的计划：只能是不带 INTO 的查询
+    fn subquery_plan(&self, plan: Plan) -> Result<Box<Plan>> {
+        match plan {
+            Plan::Select { into: Some(_), .. } => {
+                Err(DBError::Planner("子查询中不能使用 INTO".to_string()))
+            }
+            plan => Ok(Box::new(plan)),
+        }
+    }
+
+    /// 分析 LIKE / ILIKE 条件
+    fn analyze_like(
+        &self,
+        negated: bool,
+        any: bool,
+        expr: &ast::Expr,
+        pattern: &ast::Expr,
+        escape_char: &Option<String>,
+        case_insensitive: bool,
+    ) -> Result<Condition> {
+        if any {
+            return Err(DBError::Planner("不支持 LIKE ANY".to_string()));
+        }
+
+        // 未指定 ESCAPE 时默认使用反斜杠，ESCAPE '' 表示不转义
+        let escape = match escape_char.as_deref() {
+            None => Some(DEFAULT_LIKE_ESCAPE),
+            Some(escape) => {
+                let mut chars = escape.chars();
+                match (chars.next(), chars.next()) {
+                    (None, _) => None,
+                    (Some(c), None) => Some(c),
+                    _ => {
+                        return Err(DBError::Planner(format!(
+                            "ESCAPE 子句必须是单个字符: '{}'",
+                            escape
+                        )));
+                    }
+                }
+            }
+        };
+
+        Ok(Condition::Like {
+            expr: self.convert_expr(expr)?,
+            pattern: self.convert_expr(pattern)?,
+            escape,
+            case_insensitive,
+            negated,
+        })
+    }
+
     // ====== 辅助方法 ======
 
     fn convert_binary_operator(&self, op: &ast::BinaryOperator) -> Result<BinaryOperator> {
@@ -516,10 +2026,15 @@ impl Planner {
     fn convert_ast_value(&self, value: &ast::Value) -> Result<Value> {
         match value {
             ast::Value::Number(n, _) => {
-                if n.contains('.') {
-                    Ok(Value::Float(n.parse().map_err(|e| {
-                        DBError::Planner(format!("无法解析浮点数: {}", e))
-                    })?))
+                // 带小数点或指数的是浮点数，浮点数的显示形式（如 1e-6、1.5e300）可以原样读回
+                if n.contains(['.', 'e', 'E']) {
+                    let parsed: f64 = n
+                        .parse()
+                        .map_err(|e| DBError::Planner(format!("无法解析浮点数: {}", e)))?;
+                    if !parsed.is_finite() {
+                        return Err(DBError::Planner(format!("浮点数超出范围: {}", n)));
+                    }
+                    Ok(Value::Float(parsed))
                 } else {
                     let parsed_int: i64 = n
                         .parse()
@@ -541,31 +2056,123 @@ impl Planner {
         }
     }
 
+    /// 把 INSERT 的值或列默认值求值为常量
+    ///
+    /// 与执行时一样先转换为 [`Expression`] 再求值，算术及其溢出检查都由 `Value` 的运算完成。
+    /// 引用列、聚合函数或子查询时报语法错误。计划阶段不知道会话的 SQL 模式，比较按宽松模式
+    /// 求值；写入列时的类型转换在执行时按会话的模式进行。
     pub fn analyze_expr_to_value(&self, expr: &ast::Expr) -> Result<Value> {
-        // 这个方法可以简化为直接转换表达式然后求值
-        match expr {
-            ast::Expr::Value(value) => self.convert_ast_value(&value.value),
-            ast::Expr::BinaryOp { left, op, right } => {
-                let left_value = self.analyze_expr_to_value(left)?;
-                let right_value = self.analyze_expr_to_value(right)?;
+        let expression = self.convert_expr(expr)?;
+        let mut constant = true;
+        expression.walk(&mut |expr| {
+            constant &= !matches!(
+                expr,
+                Expression::Column(_) | Expression::Aggregate { .. } | Expression::Subquery(_)
+            );
+        });
+        if !constant {
+            return Err(DBError::Parse("Error: Syntax error".to_string()));
+        }
+        expression.evaluate(&Record::new(Vec::new()), &[], SqlMode::Lenient)
+    }
 
-                // 这里可以直接进行计算，或者构建表达式然后求值
-                match op {
-                    ast::BinaryOperator::Plus => left_value.add(&right_value),
-                    ast::BinaryOperator::Minus => left_value.subtract(&right_value),
-                    ast::BinaryOperator::Multiply => left_value.multiply(&right_value),
-                    ast::BinaryOperator::Divide => left_value.divide(&right_value),
-                    ast::BinaryOperator::Modulo => left_value.modulo(&right_value),
-                    _ => Err(DBError::Parse("Error: Syntax error".to_string())),
+    /// 分析 SET 语句，支持 `SET var = value`、`SET @@var = value` 和 `SET @@session.var = value`
+    fn analyze_set(&self, set: &ast::Set) -> Result<Plan> {
+        let pairs: Vec<(Option<&ast::ContextModifier>, &ast::ObjectName, &ast::Expr)> = match set {
+            ast::Set::SingleAssignment {
+                scope,
+                hivevar: false,
+                variable,
+                values,
+            } if values.len() == 1 => vec![(scope.as_ref(), variable, &values[0])],
+            ast::Set::MultipleAssignments { assignments } => assignments
+                .iter()
+                .map(|assignment| (assignment.scope.as_ref(), &assignment.name, &assignment.value))
+                .collect(),
+            _ => return Err(DBError::Planner(format!("不支持的 SET 语句: {}", set))),
+        };
+
+        let mut assignments = Vec::new();
+        for (scope, variable, value) in pairs {
+            if let Some(scope) = scope
+                && !matches!(scope, ast::ContextModifier::Session | ast::ContextModifier::Local)
+            {
+                return Err(DBError::Planner(format!("不支持的变量作用域: {}", scope)));
+            }
+            let name = self.session_variable_name(variable)?;
+            let value = match value {
+                ast::Expr::Identifier(ident) if ident.value.eq_ignore_ascii_case("DEFAULT") => {
+                    None
                 }
+                // ON / OFF / STRICT 等不加引号的取值按字符串处理
+                ast::Expr::Identifier(ident) => Some(Value::String(ident.value.clone())),
+                expr => Some(self.convert_expr(expr)?.evaluate(
+                    &Record::new(Vec::new()),
+                    &[],
+                    SqlMode::Lenient,
+                )?),
+            };
+            assignments.push((name, value));
+        }
+        Ok(Plan::SetVariable { assignments })
+    }
+
+    /// 对象名各部分去掉引号后用 `.` 连接，如 `` `db1`.Users `` 得到 `db1.users`
+    ///
+    /// 最后一部分是表名，按 [`identifier`](Self::identifier) 规范化；数据库名对应
+    /// 数据目录，保持原样。
+    fn object_name(&self, name: &ast::ObjectName) -> Result<String> {
+        let parts: Vec<&ast::Ident> = name
+            .0
+            .iter()
+            .map(|part| part.as_ident())
+            .collect::<Option<_>>()
+            .ok_or_else(|| DBError::Planner(format!("无效的表名: {}", name)))?;
+        let Some((table, database)) = parts.split_last() else {
+            return Err(DBError::Planner(format!("无效的表名: {}", name)));
+        };
+        let mut parts: Vec<String> = database.iter().map(|ident| ident.value.clone()).collect();
+        parts.push(self.identifier(table));
+        Ok(parts.join("."))
+    }
+
+    /// 标识符的规范形式：不带引号的转为小写，带引号（`` `Name` ``、`"Name"`）的保持原样
+    ///
+    /// 表名和列名因此不区分大小写，`SELECT ID FROM Users` 可以访问按小写创建的 `users`。
+    fn identifier(&self, ident: &ast::Ident) -> String {
+        match ident.quote_style {
+            None => ident.value.to_lowercase(),
+            Some(_) => ident.value.clone(),
+        }
+    }
+
+    /// 去掉 `@@` 和 `session.` 前缀，得到会话变量名
+    fn session_variable_name(&self, variable: &ast::ObjectName) -> Result<String> {
+        let parts: Vec<&str> = variable
+            .0
+            .iter()
+            .map(|part| part.as_ident().map(|ident| ident.value.as_str()))
+            .collect::<Option<_>>()
+            .ok_or_else(|| DBError::Planner(format!("无效的变量名: {}", variable)))?;
+
+        let name = match parts.as_slice() {
+            [name] => name.strip_prefix("@@").unwrap_or(name),
+            [scope, name]
+                if scope.eq_ignore_ascii_case("@@session") || scope.eq_ignore_ascii_case("@@local") =>
+            {
+                name
             }
-            _ => Err(DBError::Parse("Error: Syntax error".to_string())),
+            _ => return Err(DBError::Planner(format!("不支持的变量: {}", variable))),
+        };
+        if name.starts_with('@') {
+            return Err(DBError::Planner(format!("不支持用户变量: {}", name)));
         }
+        Ok(name.to_lowercase())
     }
 
     fn plan_insert(&self, insert: &ast::Insert) -> Result<Plan> {
         let table_name = match &insert.table {
-            ast::TableObject::TableName(name) => name.to_string(),
+            ast::TableObject::TableName(name) => self.object_name(name)?,
             _ => return Err(DBError::Parse("仅支持简单表引用".to_string())),
         };
 
@@ -573,16 +2180,31 @@ impl Planner {
         let columns: Vec<String> = if insert.columns.is_empty() {
             Vec::new()
         } else {
-            insert.columns.iter().map(|col| col.to_string()).collect()
+            insert
+                .columns
+                .iter()
+                .map(|col| self.identifier(col))
+                .collect()
         };
 
-        // 解析行数据
+        // 解析行数据：VALUES 中的字面量，或者一个查询
+        let Some(query) = &insert.source else {
+            return Err(DBError::Parse("Error: Syntax error".to_string()));
+        };
         let mut rows = Vec::new();
-        if let Some(ast::SetExpr::Values(values_list)) = &insert.source.as_ref().map(|s| &*s.body) {
+        if let ast::SetExpr::Values(values_list) = &*query.body {
             for row in &values_list.rows {
                 let mut row_values = Vec::new();
                 for expr in row {
-                    let value = self.analyze_expr_to_value(expr)?;
+                    let value = match expr {
+                        ast::Expr::Identifier(ident)
+                            if ident.quote_style.is_none()
+                                && ident.value.eq_ignore_ascii_case("DEFAULT") =>
+                        {
+                            None
+                        }
+                        _ => Some(self.analyze_expr_to_value(expr)?),
+                    };
                     row_values.push(value);
                 }
 
@@ -594,40 +2216,82 @@ impl Planner {
 
                 rows.push(row_values);
             }
-        } else {
-            return Err(DBError::Parse("Error: Syntax error".to_string()));
         }
+        let source = match &*query.body {
+            ast::SetExpr::Values(_) => InsertSource::Values(rows),
+            _ => {
+                let select = self.analyze_select(query)?;
+                if select.is_mutating() {
+                    return Err(DBError::Planner(
+                        "INSERT ... SELECT 的查询中不能使用 INTO".to_string(),
+                    ));
+                }
+                InsertSource::Select(Box::new(select))
+            }
+        };
+
+        let returning = insert
+            .returning
+            .as_ref()
+            .map(|items| self.analyze_select_columns(items))
+            .transpose()?;
 
         Ok(Plan::Insert {
             table_name,
             columns,
-            rows,
+            source,
+            ignore: insert.ignore,
+            returning,
         })
     }
 
     /// 解析列定义
     pub fn analyze_column_definitions(&self, cols: &[ast::ColumnDef]) -> Result<Vec<ColumnDef>> {
-        let mut columns = Vec::with_capacity(cols.len());
+        let mut columns: Vec<ColumnDef> = Vec::with_capacity(cols.len());
 
         for col in cols {
-            let name = col.name.to_string();
+            let name = self.identifier(&col.name);
+            if columns
+                .iter()
+                .any(|column| column.name.eq_ignore_ascii_case(&name))
+            {
+                return Err(DBError::Schema(format!("Duplicate column name '{}'", name)));
+            }
 
             let data_type = match col.data_type {
-                ast::DataType::Int(size) | ast::DataType::Integer(size) => {
-                    DataType::Int(size.unwrap_or(64))
-                }
+                ast::DataType::Int(width) | ast::DataType::Integer(width) => match width {
+                    Some(width) if width > MAX_INT_DISPLAY_WIDTH as u64 => {
+                        return Err(DBError::Schema(format!(
+                            "列 '{}' 的显示宽度超出范围（最大 {}）",
+                            name, MAX_INT_DISPLAY_WIDTH
+                        )));
+                    }
+                    width => DataType::Int(width.map(|width| width as u32)),
+                },
                 ast::DataType::Varchar(lenth) => match lenth {
                     Some(ast::CharacterLength::IntegerLength { length, .. }) => {
-                        DataType::Varchar(length)
+                        DataType::Varchar(Some(length))
                     }
-                    None | Some(ast::CharacterLength::Max) => DataType::Varchar(u64::MAX),
+                    None | Some(ast::CharacterLength::Max) => DataType::Varchar(None),
                 },
+                ast::DataType::Float(_)
+                | ast::DataType::Float4
+                | ast::DataType::Float8
+                | ast::DataType::Float32
+                | ast::DataType::Float64
+                | ast::DataType::Real
+                | ast::DataType::Double(_)
+                | ast::DataType::DoublePrecision => DataType::Float,
+                ast::DataType::Bool | ast::DataType::Boolean => DataType::Boolean,
                 _ => return Err(DBError::Parse("Error: Syntax error".to_string())),
             };
 
             let mut not_null = false;
             let mut unique = false;
             let mut my_is_primaty = false;
+            let mut auto_increment = false;
+            let mut default = None;
+            let mut references = None;
 
             for constraint in &col.options {
                 match constraint.option {
@@ -637,32 +2301,410 @@ impl Planner {
                     ast::ColumnOption::Unique { is_primary, .. } => {
                         unique = true;
                         my_is_primaty = is_primary;
-                        not_null = is_primary;
+                        not_null |= is_primary;
                     }
+                    ast::ColumnOption::DialectSpecific(ref tokens)
+                        if matches!(tokens.as_slice(), [token] if token.to_string().eq_ignore_ascii_case("AUTO_INCREMENT")) =>
+                    {
+                        if !matches!(data_type, DataType::Int(_)) {
+                            return Err(DBError::Schema(format!(
+                                "AUTO_INCREMENT 列 '{}' 必须是整数类型",
+                                name
+                            )));
+                        }
+                        auto_increment = true;
+                    }
+                    ast::ColumnOption::Default(ref expr) => {
+                        default = Some(self.analyze_expr_to_value(expr)?);
+                    }
+                    ast::ColumnOption::ForeignKey {
+                        ref foreign_table,
+                        ref referred_columns,
+                        ref on_delete,
+                        ref on_update,
+                        ..
+                    } => {
+                        references = Some(self.analyze_foreign_key(
+                            foreign_table,
+                            referred_columns,
+                            [on_delete, on_update],
+                        )?);
+                    }
+                    // 作用于整行，与表级约束一起由 analyze_check_constraints 处理
+                    ast::ColumnOption::Check(_) => {}
                     _ => {
                         return Err(DBError::Parse("Error: Syntax error".to_string()));
                     }
                 }
             }
+
+            // 默认值在建表时就转换为列的类型；NOT NULL 列不能默认为 NULL，自增列不能有默认值
+            let default = match default {
+                Some(value) => {
+                    let value = value.coerce_to(&data_type, SqlMode::Lenient);
+                    let valid = !(auto_increment || (not_null && value.is_null()))
+                        && validate::check_value_kind(&value, &data_type).is_ok()
+                        && validate::check_value_length(&value, &data_type).is_ok();
+                    if !valid {
+                        return Err(DBError::Schema(format!(
+                            "Invalid default value for '{}'",
+                            name
+                        )));
+                    }
+                    Some(value)
+                }
+                None => None,
+            };
+
             columns.push(ColumnDef {
                 name,
                 data_type,
                 not_null,
                 unique,
                 is_primary: my_is_primaty,
+                auto_increment,
+                default,
+                references,
+            });
+        }
+
+        if columns.iter().filter(|col| col.auto_increment).count() > 1 {
+            return Err(DBError::Schema(
+                "一张表只能有一个 AUTO_INCREMENT 列".to_string(),
+            ));
+        }
+
+        Ok(columns)
+    }
+
+    /// 解析外键的被引用表和列，只支持单列外键和 RESTRICT（NO ACTION）动作
+    fn analyze_foreign_key(
+        &self,
+        foreign_table: &ast::ObjectName,
+        referred_columns: &[ast::Ident],
+        actions: [&Option<ast::ReferentialAction>; 2],
+    ) -> Result<ForeignKey> {
+        let [column] = referred_columns else {
+            return Err(DBError::Schema("暂不支持多列外键".to_string()));
+        };
+        for action in actions.into_iter().flatten() {
+            if !matches!(
+                action,
+                ast::ReferentialAction::Restrict | ast::ReferentialAction::NoAction
+            ) {
+                return Err(DBError::Planner(format!("暂不支持外键动作 {}", action)));
+            }
+        }
+        Ok(ForeignKey {
+            table: self.object_name(foreign_table)?,
+            column: self.identifier(column),
+        })
+    }
+
+    /// 解析 ALTER TABLE 的操作，目前只支持 ADD COLUMN 和 DROP COLUMN
+    fn analyze_alter_operations(
+        &self,
+        operations: &[ast::AlterTableOperation],
+    ) -> Result<Vec<AlterColumn>> {
+        operations
+            .iter()
+            .map(|operation| match operation {
+                ast::AlterTableOperation::AddColumn {
+                    if_not_exists: false,
+                    column_def,
+                    column_position,
+                    ..
+                } => {
+                    let column = self
+                        .analyze_column_definitions(std::slice::from_ref(column_def))?
+                        .remove(0);
+                    if column.references.is_some() {
+                        return Err(DBError::Planner(
+                            "暂不支持在 ALTER TABLE 中添加外键".to_string(),
+                        ));
+                    }
+                    if column_def
+                        .options
+                        .iter()
+                        .any(|option| matches!(option.option, ast::ColumnOption::Check(_)))
+                    {
+                        return Err(DBError::Planner(
+                            "暂不支持在 ALTER TABLE 中添加 CHECK 约束".to_string(),
+                        ));
+                    }
+                    let position = match column_position {
+                        None => ColumnPosition::Last,
+                        Some(ast::MySQLColumnPosition::First) => ColumnPosition::First,
+                        Some(ast::MySQLColumnPosition::After(name)) => {
+                            ColumnPosition::After(self.identifier(name))
+                        }
+                    };
+                    Ok(AlterColumn::Add { column, position })
+                }
+                ast::AlterTableOperation::DropColumn {
+                    column_name,
+                    if_exists: false,
+                    ..
+                } => Ok(AlterColumn::Drop {
+                    name: self.identifier(column_name),
+                }),
+                other => Err(DBError::Planner(format!(
+                    "暂不支持的 ALTER TABLE 操作: {}",
+                    other
+                ))),
+            })
+            .collect()
+    }
+
+    /// 解析 CREATE TABLE 中的表级约束
+    ///
+    /// PRIMARY KEY 和 FOREIGN KEY 直接标记到列上；KEY / INDEX / UNIQUE KEY 转换为索引定义，
+    /// 列是否存在等检查在执行器创建索引时进行。未命名的索引与 MySQL 一样
+    /// 以第一列命名，重名时追加 `_2`、`_3` 后缀。
+    fn analyze_table_constraints(
+        &self,
+        constraints: &[ast::TableConstraint],
+        columns: &mut [ColumnDef],
+    ) -> Result<Vec<IndexDef>> {
+        let mut indexes: Vec<IndexDef> = Vec::new();
+
+        for constraint in constraints {
+            let (name, key_columns, unique) = match constraint {
+                ast::TableConstraint::PrimaryKey {
+                    columns: key_columns,
+                    ..
+                } => {
+                    let [key_column] = key_columns.as_slice() else {
+                        return Err(DBError::Schema("暂不支持多列主键".to_string()));
+                    };
+                    if columns.iter().any(|col| col.is_primary) {
+                        return Err(DBError::Schema(
+                            "Multiple primary key defined".to_string(),
+                        ));
+                    }
+                    let key_column = self.identifier(key_column);
+                    let column = columns
+                        .iter_mut()
+                        .find(|col| col.name == key_column)
+                        .ok_or_else(|| {
+                            DBError::Schema(format!(
+                                "Key column '{}' doesn't exist in table",
+                                key_column
+                            ))
+                        })?;
+                    column.is_primary = true;
+                    column.unique = true;
+                    column.not_null = true;
+                    continue;
+                }
+                ast::TableConstraint::Unique {
+                    name,
+                    index_name,
+                    columns: key_columns,
+                    ..
+                } => (index_name.as_ref().or(name.as_ref()), key_columns, true),
+                ast::TableConstraint::Index {
+                    name,
+                    columns: key_columns,
+                    ..
+                } => (name.as_ref(), key_columns, false),
+                ast::TableConstraint::ForeignKey {
+                    columns: key_columns,
+                    foreign_table,
+                    referred_columns,
+                    on_delete,
+                    on_update,
+                    ..
+                } => {
+                    let [key_column] = key_columns.as_slice() else {
+                        return Err(DBError::Schema("暂不支持多列外键".to_string()));
+                    };
+                    let references = self.analyze_foreign_key(
+                        foreign_table,
+                        referred_columns,
+                        [on_delete, on_update],
+                    )?;
+                    let key_column = self.identifier(key_column);
+                    let column = columns
+                        .iter_mut()
+                        .find(|col| col.name == key_column)
+                        .ok_or_else(|| {
+                            DBError::Schema(format!(
+                                "Key column '{}' doesn't exist in table",
+                                key_column
+                            ))
+                        })?;
+                    if column.references.is_some() {
+                        return Err(DBError::Schema(format!(
+                            "列 '{}' 已经声明了外键",
+                            key_column
+                        )));
+                    }
+                    column.references = Some(references);
+                    continue;
+                }
+                ast::TableConstraint::Check { .. } => continue,
+                _ => {
+                    return Err(DBError::Planner(format!(
+                        "暂不支持的表约束: {}",
+                        constraint
+                    )));
+                }
+            };
+
+            let key_columns: Vec<String> =
+                key_columns.iter().map(|ident| self.identifier(ident)).collect();
+            let name = match name {
+                Some(name) => {
+                    if indexes.iter().any(|index| index.name.eq_ignore_ascii_case(&name.value)) {
+                        return Err(DBError::Schema(format!(
+                            "Duplicate key name '{}'",
+                            name.value
+                        )));
+                    }
+                    name.value.clone()
+                }
+                None => {
+                    let base = key_columns
+                        .first()
+                        .cloned()
+                        .ok_or_else(|| DBError::Parse("Error: Syntax error".to_string()))?;
+                    let taken = |candidate: &str| {
+                        indexes
+                            .iter()
+                            .any(|index| index.name.eq_ignore_ascii_case(candidate))
+                    };
+                    let mut candidate = base.clone();
+                    let mut suffix = 2;
+                    while taken(&candidate) {
+                        candidate = format!("{}_{}", base, suffix);
+                        suffix += 1;
+                    }
+                    candidate
+                }
+            };
+
+            indexes.push(IndexDef {
+                name,
+                columns: key_columns,
+                unique,
+            });
+        }
+
+        Ok(indexes)
+    }
+
+    /// 收集 CREATE TABLE 中的列级和表级 CHECK 约束
+    ///
+    /// 列级约束与 MySQL 一样作用于整行，也可以引用其他列。未命名的约束按出现的顺序
+    /// 命名为 `表名_chk_1`、`表名_chk_2`……条件在这里生成一次计划，不能生成计划或
+    /// 不满足 [`validate::check_constraint_condition`] 时建表失败。
+    fn analyze_check_constraints(
+        &self,
+        table_name: &str,
+        create_table: &ast::CreateTable,
+        columns: &[ColumnDef],
+    ) -> Result<Vec<CheckConstraint>> {
+        let column_checks = create_table
+            .columns
+            .iter()
+            .flat_map(|col| &col.options)
+            .filter_map(|option| match &option.option {
+                ast::ColumnOption::Check(expr) => Some((option.name.as_ref(), expr)),
+                _ => None,
+            });
+        let table_checks = create_table.constraints.iter().filter_map(|constraint| {
+            let ast::TableConstraint::Check { name, expr } = constraint else {
+                return None;
+            };
+            Some((name.as_ref(), &**expr))
+        });
+
+        // 带数据库限定的表名只用表名部分生成约束名
+        let base = table_name.rsplit('.').next().unwrap_or(table_name);
+        let mut checks: Vec<CheckConstraint> = Vec::new();
+        let mut unnamed = 0;
+        for (name, expr) in column_checks.chain(table_checks) {
+            let name = match name {
+                Some(name) => self.identifier(name),
+                None => {
+                    unnamed += 1;
+                    format!("{}_chk_{}", base, unnamed)
+                }
+            };
+            if checks
+                .iter()
+                .any(|check| check.name.eq_ignore_ascii_case(&name))
+            {
+                return Err(DBError::Schema(format!(
+                    "Duplicate check constraint name '{}'.",
+                    name
+                )));
+            }
+            let condition = self.analyze_condition(expr)?;
+            validate::check_constraint_condition(&name, &condition, columns)?;
+            checks.push(CheckConstraint {
+                name,
+                expr: expr.to_string(),
+            });
+        }
+        Ok(checks)
+    }
+
+    /// 解析 SELECT 的 FROM 子句：主表、别名以及 INNER JOIN 的表
+    fn analyze_from(
+        &self,
+        from: &[ast::TableWithJoins],
+    ) -> Result<(String, Option<String>, Vec<Join>)> {
+        if from.len() != 1 {
+            return Err(DBError::Planner(
+                "仅支持单表查询或 JOIN ... ON，不支持逗号连接".to_string(),
+            ));
+        }
+
+        let (table_name, alias) = self.analyze_table_factor(&from[0].relation)?;
+        let mut qualifiers = vec![alias.clone().unwrap_or_else(|| table_name.clone())];
+
+        let mut joins = Vec::new();
+        for join in &from[0].joins {
+            let on = match &join.join_operator {
+                ast::JoinOperator::Join(ast::JoinConstraint::On(on))
+                | ast::JoinOperator::Inner(ast::JoinConstraint::On(on)) => on,
+                other => {
+                    return Err(DBError::Planner(format!(
+                        "仅支持带 ON 条件的 INNER JOIN: {:?}",
+                        other
+                    )));
+                }
+            };
+
+            let (join_table, join_alias) = self.analyze_table_factor(&join.relation)?;
+            let qualifier = join_alias.clone().unwrap_or_else(|| join_table.clone());
+            if qualifiers.contains(&qualifier) {
+                return Err(DBError::Planner(format!(
+                    "Not unique table/alias: '{}'",
+                    qualifier
+                )));
+            }
+            qualifiers.push(qualifier);
+
+            joins.push(Join {
+                table_name: join_table,
+                alias: join_alias,
+                on: self.analyze_condition(on)?,
             });
         }
 
-        Ok(columns)
+        Ok((table_name, alias, joins))
     }
 
-    fn extract_table_name(&self, from: &[ast::TableWithJoins]) -> Result<String> {
-        if from.len() != 1 {
-            return Err(DBError::Planner("仅支持单表查询".to_string()));
-        }
-
-        match &from[0].relation {
-            ast::TableFactor::Table { name, .. } => Ok(name.to_string()),
+    /// 解析表引用，返回表名和别名
+    fn analyze_table_factor(&self, factor: &ast::TableFactor) -> Result<(String, Option<String>)> {
+        match factor {
+            ast::TableFactor::Table { name, alias, .. } => Ok((
+                self.object_name(name)?,
+                alias.as_ref().map(|alias| self.identifier(&alias.name)),
+            )),
             _ => Err(DBError::Planner("仅支持简单表引用".to_string())),
         }
     }
@@ -671,20 +2713,36 @@ impl Planner {
         let mut items = Vec::new();
 
         for order_expr in order_by {
-            let column = match &order_expr.expr {
-                ast::Expr::Identifier(ident) => ident.value.clone(),
-                ast::Expr::CompoundIdentifier(parts) => {
-                    if parts.len() == 1 {
-                        parts[0].value.clone()
-                    } else {
+            let target = match &order_expr.expr {
+                ast::Expr::Identifier(ident) => SortTarget::Column(self.identifier(ident)),
+                ast::Expr::CompoundIdentifier(parts) => match parts.as_slice() {
+                    [column] => SortTarget::Column(self.identifier(column)),
+                    [table, column] => SortTarget::Column(format!(
+                        "{}.{}",
+                        self.identifier(table),
+                        self.identifier(column)
+                    )),
+                    _ => {
                         return Err(DBError::Planner("ORDER BY 暂不支持复合标识符".to_string()));
                     }
+                },
+                // 整数常量表示 SELECT 列表中的位置
+                ast::Expr::Value(value) if matches!(value.value, ast::Value::Number(..)) => {
+                    let text = value.to_string();
+                    match text.parse::<usize>() {
+                        Ok(position) if position >= 1 => SortTarget::Position(position),
+                        _ => {
+                            return Err(DBError::Planner(format!(
+                                "ORDER BY 的列位置 {} 无效，应为从 1 开始的整数",
+                                text
+                            )));
+                        }
+                    }
                 }
-                _ => {
-                    return Err(DBError::Planner(
-                        "ORDER BY 暂不支持表达式，仅支持列名".to_string(),
-                    ));
-                }
+                expr => SortTarget::Expression {
+                    expr: self.convert_expr(expr)?,
+                    text: expr.to_string(),
+                },
             };
 
             // 在 sqlparser 0.56.0 中，使用 options.asc
@@ -693,7 +2751,17 @@ impl Planner {
                 Some(false) => SortDirection::Desc,
             };
 
-            items.push(OrderByItem { column, direction });
+            let nulls = match order_expr.options.nulls_first {
+                Some(true) => NullsOrder::First,
+                Some(false) => NullsOrder::Last,
+                None => NullsOrder::default_for(&direction),
+            };
+
+            items.push(OrderByItem {
+                target,
+                direction,
+                nulls,
+            });
         }
 
         Ok(items)
@@ -702,17 +2770,87 @@ impl Planner {
 
 // ====== 为 Expression 和 Condition 实现 evaluate 方法 ======
 
+/// 按名称查找列的位置
+///
+/// JOIN 查询中的列名都带有表名或别名限定（如 `a.id`），此时未限定的列名
+/// 只要在所有表中唯一，也能找到对应的列。单表语句中的限定名在生成计划时已经去掉，
+/// 见 `unqualify_column`。精确匹配找不到时再不区分大小写匹配，
+/// 见 [`column_position`]。
+pub fn resolve_column(columns: &[ColumnDef], name: &str) -> Result<usize> {
+    find_column(columns, name)?.ok_or_else(|| DBError::Planner(format!("列 '{}' 不存在", name)))
+}
+
+/// 与 [`resolve_column`] 相同，但找不到的列返回 `None`，只有列名有歧义时报错
+pub fn find_column(columns: &[ColumnDef], name: &str) -> Result<Option<usize>> {
+    if let Some(idx) = column_position(columns, name) {
+        return Ok(Some(idx));
+    }
+
+    let suffix = format!(".{}", name);
+    let candidates = |matches: &dyn Fn(&str) -> bool| -> Vec<usize> {
+        (0..columns.len())
+            .filter(|&idx| matches(&columns[idx].name))
+            .collect()
+    };
+    let mut found = candidates(&|column| column.ends_with(&suffix));
+    if found.is_empty() {
+        let suffix = suffix.to_lowercase();
+        found = candidates(&|column| column.to_lowercase().ends_with(&suffix));
+    }
+    match found.as_slice() {
+        [idx] => Ok(Some(*idx)),
+        [] => Ok(None),
+        _ => Err(DBError::Planner(format!(
+            "Column '{}' in field list is ambiguous",
+            name
+        ))),
+    }
+}
+
+/// 限定名 `qualifier` 是否指 `name` 这张表（或别名）；带库名的表也可以只用表名限定
+fn qualifier_matches(name: &str, qualifier: &str) -> bool {
+    name.eq_ignore_ascii_case(qualifier)
+        || name
+            .rsplit_once('.')
+            .is_some_and(|(_, table)| table.eq_ignore_ascii_case(qualifier))
+}
+
+/// 单表语句中去掉列名上的表名或别名限定：`t.a` 在 `FROM t` 中就是 `a`
+///
+/// `qualifier` 为表的别名，没有别名时为表名。限定的不是这张表时保持原样，
+/// 执行时报告列不存在。多表连接的列名本身带有限定，不经过这里。
+fn unqualify_column(name: &mut String, qualifier: &str) {
+    if let Some(column) = name
+        .split_once('.')
+        .filter(|(table, _)| qualifier_matches(qualifier, table))
+        .map(|(_, column)| column.to_string())
+    {
+        *name = column;
+    }
+}
+
+/// 对表达式中的每个列名调用 [`unqualify_column`]，不进入子查询
+fn unqualify_columns(expr: &mut Expression, qualifier: &str) {
+    expr.walk_mut(&mut |expr| {
+        if let Expression::Column(name) = expr {
+            unqualify_column(name, qualifier);
+        }
+    });
+}
+
 impl Expression {
-    /// 评估表达式的值
-    pub fn evaluate(&self, record: &Record, columns: &[ColumnDef]) -> Result<Value> {
+    /// 评估表达式的值，比较运算中布尔值与数值能否互相比较由 `mode` 决定
+    pub fn evaluate(&self, record: &Record, columns: &[ColumnDef], mode: SqlMode) -> Result<Value> {
         match self {
             Expression::Column(column_name) => {
-                let column_idx = columns
-                    .iter()
-                    .position(|col| &col.name == column_name)
-                    .ok_or_else(|| DBError::Planner(format!("列 '{}' 不存在", column_name)))?;
-
-                Ok(record.values()[column_idx].clone())
+                let column_idx = resolve_column(columns, column_name)?;
+                record.values().get(column_idx).cloned().ok_or_else(|| {
+                    DBError::Execution(format!(
+                        "记录只有 {} 列，取不到列 '{}'：数据文件与表结构不一致，可用 .verify 检查",
+                        record.values().len(),
+                        column_name
+                    ))
+                })
             }
 
             Expression::Value(value) => Ok(value.clone()),
@@ -722,8 +2860,8 @@ impl Expression {
                 operator,
                 right,
             } => {
-                let left_val = left.evaluate(record, columns)?;
-                let right_val = right.evaluate(record, columns)?;
+                let left_val = left.evaluate(record, columns, mode)?;
+                let right_val = right.evaluate(record, columns, mode)?;
 
                 match operator {
                     // 算术操作
@@ -733,45 +2871,132 @@ impl Expression {
                     BinaryOperator::Divide => left_val.divide(&right_val),
                     BinaryOperator::Modulo => left_val.modulo(&right_val),
 
-                    // 比较操作（返回布尔值）
-                    BinaryOperator::Equal => Ok(Value::Boolean(left_val.eq(&right_val)?)),
-                    BinaryOperator::NotEqual => Ok(Value::Boolean(left_val.ne(&right_val)?)),
-                    BinaryOperator::LessThan => Ok(Value::Boolean(left_val.lt(&right_val)?)),
-                    BinaryOperator::LessThanOrEqual => Ok(Value::Boolean(left_val.le(&right_val)?)),
-                    BinaryOperator::GreaterThan => Ok(Value::Boolean(left_val.gt(&right_val)?)),
+                    // 比较操作（返回布尔值，任一侧为 NULL 时为 NULL）
+                    BinaryOperator::Equal => compare(&left_val, &right_val, Value::eq, mode),
+                    BinaryOperator::NotEqual => compare(&left_val, &right_val, Value::ne, mode),
+                    BinaryOperator::LessThan => compare(&left_val, &right_val, Value::lt, mode),
+                    BinaryOperator::LessThanOrEqual => {
+                        compare(&left_val, &right_val, Value::le, mode)
+                    }
+                    BinaryOperator::GreaterThan => compare(&left_val, &right_val, Value::gt, mode),
                     BinaryOperator::GreaterThanOrEqual => {
-                        Ok(Value::Boolean(left_val.ge(&right_val)?))
+                        compare(&left_val, &right_val, Value::ge, mode)
                     }
 
-                    // 逻辑操作
-                    BinaryOperator::And => match (left_val, right_val) {
-                        (Value::Boolean(l), Value::Boolean(r)) => Ok(Value::Boolean(l && r)),
-                        _ => Err(DBError::Parse("Error: Syntax error".to_string())),
-                    },
-                    BinaryOperator::Or => match (left_val, right_val) {
-                        (Value::Boolean(l), Value::Boolean(r)) => Ok(Value::Boolean(l || r)),
-                        _ => Err(DBError::Parse("Error: Syntax error".to_string())),
-                    },
+                    // 逻辑操作（三值逻辑）
+                    BinaryOperator::And => Ok(truth_value(and3(
+                        truth_of(&left_val)?,
+                        truth_of(&right_val)?,
+                    ))),
+                    BinaryOperator::Or => Ok(truth_value(or3(
+                        truth_of(&left_val)?,
+                        truth_of(&right_val)?,
+                    ))),
                 }
             }
 
+            Expression::Aggregate { func, .. } => Err(DBError::Execution(format!(
+                "聚合函数 {} 不能在单行上求值",
+                func
+            ))),
+
+            // WHERE 中的子查询在过滤之前已被替换为结果值
+            Expression::Subquery(_) => Err(subquery_outside_where()),
+
+            Expression::Function { name, args } => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.evaluate(record, columns, mode))
+                    .collect::<Result<Vec<_>>>()?;
+                name.apply(&values)
+            }
+
             Expression::Unary { operator, operand } => {
-                let val = operand.evaluate(record, columns)?;
+                let val = operand.evaluate(record, columns, mode)?;
 
                 match operator {
-                    UnaryOperator::Not => {
-                        if let Value::Boolean(b) = val {
-                            Ok(Value::Boolean(!b))
-                        } else {
-                            Err(DBError::Parse("Error: Syntax error".to_string()))
-                        }
-                    }
+                    UnaryOperator::Not => Ok(truth_value(truth_of(&val)?.map(|b| !b))),
                     UnaryOperator::Minus => val.negate(),
                     UnaryOperator::Plus => Ok(val), // 正号不改变值
                 }
             }
         }
     }
+
+    /// 按先序访问表达式和它的所有子表达式，不进入子查询
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        visit(self);
+        match self {
+            Expression::Binary { left, right, .. } => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            Expression::Unary { operand, .. } => operand.walk(visit),
+            Expression::Aggregate { arg: Some(arg), .. } => arg.walk(visit),
+            Expression::Function { args, .. } => {
+                for arg in args {
+                    arg.walk(visit);
+                }
+            }
+            Expression::Column(_)
+            | Expression::Value(_)
+            | Expression::Aggregate { arg: None, .. }
+            | Expression::Subquery(_) => {}
+        }
+    }
+
+    /// 与 [`walk`](Self::walk) 相同，但可以修改访问到的表达式
+    pub fn walk_mut(&mut self, visit: &mut impl FnMut(&mut Expression)) {
+        visit(self);
+        match self {
+            Expression::Binary { left, right, .. } => {
+                left.walk_mut(visit);
+                right.walk_mut(visit);
+            }
+            Expression::Unary { operand, .. } => operand.walk_mut(visit),
+            Expression::Aggregate { arg: Some(arg), .. } => arg.walk_mut(visit),
+            Expression::Function { args, .. } => {
+                for arg in args {
+                    arg.walk_mut(visit);
+                }
+            }
+            Expression::Column(_)
+            | Expression::Value(_)
+            | Expression::Aggregate { arg: None, .. }
+            | Expression::Subquery(_) => {}
+        }
+    }
+
+    /// 表达式引用的所有列名，按出现顺序；子查询中的列属于子查询自己的表，不包括在内
+    pub fn column_references(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.walk(&mut |expr| {
+            if let Expression::Column(name) = expr {
+                names.push(name.as_str());
+            }
+        });
+        names
+    }
+
+    fn collect_subqueries<'a>(&'a self, found: &mut Vec<&'a Plan>) {
+        match self {
+            Expression::Subquery(plan) => found.push(plan),
+            Expression::Binary { left, right, .. } => {
+                left.collect_subqueries(found);
+                right.collect_subqueries(found);
+            }
+            Expression::Unary { operand, .. } => operand.collect_subqueries(found),
+            Expression::Aggregate { arg: Some(arg), .. } => arg.collect_subqueries(found),
+            Expression::Function { args, .. } => {
+                for arg in args {
+                    arg.collect_subqueries(found);
+                }
+            }
+            Expression::Column(_)
+            | Expression::Value(_)
+            | Expression::Aggregate { arg: None, .. } => {}
+        }
+    }
 }
 
 impl Condition {
@@ -785,35 +3010,141 @@ impl Condition {
         Condition::Constant(false)
     }
 
-    pub fn evaluate(&self, record: &Record, columns: &[ColumnDef]) -> Result<bool> {
+    /// 过滤时使用的求值：只有结果为真时保留该行，假和 NULL（未知）都不匹配
+    pub fn evaluate(&self, record: &Record, columns: &[ColumnDef], mode: SqlMode) -> Result<bool> {
+        Ok(self.truth(record, columns, mode)? == Some(true))
+    }
+
+    /// 按 SQL 三值逻辑求值，`None` 表示 NULL（未知）
+    ///
+    /// 与 NULL 比较的结果为 NULL；`NULL AND FALSE` 为假，`NULL OR TRUE` 为真，
+    /// `NOT NULL` 仍为 NULL。IS [NOT] NULL 和 IS [NOT] TRUE/FALSE 的结果总是确定的。
+    pub fn truth(
+        &self,
+        record: &Record,
+        columns: &[ColumnDef],
+        mode: SqlMode,
+    ) -> Result<Option<bool>> {
         match self {
-            Condition::Expression(expr) => {
-                let result = expr.evaluate(record, columns)?;
-                match result {
-                    Value::Boolean(b) => Ok(b),
-                    _ => Err(DBError::Parse("Error: Syntax error".to_string())),
-                }
-            }
+            Condition::Expression(expr) => truth_of(&expr.evaluate(record, columns, mode)?),
             Condition::IsNull(expr) => {
-                let value = expr.evaluate(record, columns)?;
-                Ok(matches!(value, Value::Null))
+                let value = expr.evaluate(record, columns, mode)?;
+                Ok(Some(matches!(value, Value::Null)))
             }
             Condition::IsNotNull(expr) => {
-                let value = expr.evaluate(record, columns)?;
-                Ok(!matches!(value, Value::Null))
+                let value = expr.evaluate(record, columns, mode)?;
+                Ok(Some(!matches!(value, Value::Null)))
             }
-            Condition::Constant(b) => Ok(*b),
-            Condition::And(left, right) => {
-                Ok(left.evaluate(record, columns)? && right.evaluate(record, columns)?)
+            Condition::Constant(b) => Ok(Some(*b)),
+            Condition::Like {
+                expr,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => {
+                let value = expr.evaluate(record, columns, mode)?;
+                let pattern = pattern.evaluate(record, columns, mode)?;
+                // 任一侧为 NULL 时 LIKE 和 NOT LIKE 的结果都是 NULL
+                if matches!(value, Value::Null) || matches!(pattern, Value::Null) {
+                    return Ok(None);
+                }
+                let matched = LikePattern::new(&pattern.to_string(), *escape, *case_insensitive)
+                    .matches(&value.to_string());
+                Ok(Some(matched != *negated))
             }
-            Condition::Or(left, right) => {
-                Ok(left.evaluate(record, columns)? || right.evaluate(record, columns)?)
+            Condition::In { expr, set, negated } => {
+                let InSet::Values(values) = set else {
+                    return Err(subquery_outside_where());
+                };
+                let value = expr.evaluate(record, columns, mode)?;
+                Ok(in_values(&value, values, mode)?.map(|found| found != *negated))
+            }
+            Condition::And(left, right) => Ok(and3(
+                left.truth(record, columns, mode)?,
+                right.truth(record, columns, mode)?,
+            )),
+            Condition::Or(left, right) => Ok(or3(
+                left.truth(record, columns, mode)?,
+                right.truth(record, columns, mode)?,
+            )),
+            Condition::Not(inner) => Ok(inner.truth(record, columns, mode)?.map(|b| !b)),
+            Condition::IsTruth {
+                operand,
+                value,
+                negated,
+            } => {
+                let truth = operand.truth(record, columns, mode)?;
+                Ok(Some((truth == Some(*value)) != *negated))
             }
-            Condition::Not(inner) => Ok(!inner.evaluate(record, columns)?),
         }
     }
 }
 
+/// 比较运算：任一侧为 NULL 时结果为 NULL
+fn compare(
+    left: &Value,
+    right: &Value,
+    op: fn(&Value, &Value, SqlMode) -> Result<bool>,
+    mode: SqlMode,
+) -> Result<Value> {
+    if left.is_null() || right.is_null() {
+        return Ok(Value::Null);
+    }
+    Ok(Value::Boolean(op(left, right, mode)?))
+}
+
+/// IN 的三值逻辑：集合为空时为假；左侧为 NULL，或找不到而集合中有 NULL 时为 NULL
+fn in_values(value: &Value, values: &[Value], mode: SqlMode) -> Result<Option<bool>> {
+    if values.is_empty() {
+        return Ok(Some(false));
+    }
+    let mut unknown = false;
+    for candidate in values {
+        match compare(value, candidate, Value::eq, mode)? {
+            Value::Boolean(true) => return Ok(Some(true)),
+            Value::Null => unknown = true,
+            _ => {}
+        }
+    }
+    Ok(if unknown { None } else { Some(false) })
+}
+
+fn subquery_outside_where() -> DBError {
+    DBError::Execution("子查询只能用在 WHERE 条件中".to_string())
+}
+
+/// 布尔值或 NULL 转为三值逻辑的真值，其他类型不能作为条件
+fn truth_of(value: &Value) -> Result<Option<bool>> {
+    match value {
+        Value::Boolean(b) => Ok(Some(*b)),
+        Value::Null => Ok(None),
+        _ => Err(DBError::Parse("Error: Syntax error".to_string())),
+    }
+}
+
+fn truth_value(truth: Option<bool>) -> Value {
+    truth.map_or(Value::Null, Value::Boolean)
+}
+
+/// 三值逻辑 AND：有一侧为假即为假，否则有 NULL 即为 NULL
+fn and3(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+/// 三值逻辑 OR：有一侧为真即为真，否则有 NULL 即为 NULL
+fn or3(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::storage::table::DataType;
@@ -835,27 +3166,27 @@ mod tests {
         let planner = Planner::new();
         let plan = planner.plan(&ast[0]).unwrap();
 
-        if let Plan::CreateTable { name, columns } = plan {
+        if let Plan::CreateTable { name, columns, .. } = plan {
             assert_eq!(name, "users");
             assert_eq!(columns.len(), 6);
 
             assert_eq!(columns[0].name, "id");
-            assert_eq!(columns[0].data_type, DataType::Int(32));
+            assert_eq!(columns[0].data_type, DataType::Int(Some(32)));
             assert!(columns[0].is_primary);
             assert!(columns[0].not_null);
             assert!(columns[0].unique);
 
             assert_eq!(columns[1].name, "name");
-            assert_eq!(columns[1].data_type, DataType::Varchar(100));
+            assert_eq!(columns[1].data_type, DataType::Varchar(Some(100)));
 
             assert_eq!(columns[2].name, "left_num");
-            assert_eq!(columns[2].data_type, DataType::Int(32));
+            assert_eq!(columns[2].data_type, DataType::Int(Some(32)));
 
             assert_eq!(columns[3].name, "discription");
-            assert_eq!(columns[3].data_type, DataType::Varchar(150));
+            assert_eq!(columns[3].data_type, DataType::Varchar(Some(150)));
 
             assert_eq!(columns[4].name, "price");
-            assert!(matches!(columns[4].data_type, DataType::Int(_)));
+            assert_eq!(columns[4].data_type, DataType::Int(None));
 
             assert_eq!(columns[5].name, "time");
             assert!(matches!(columns[5].data_type, DataType::Int(_)));
@@ -864,6 +3195,357 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_delete_plan_table_name() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let plan_sql = |sql: &str| {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            planner.plan(&ast[0])
+        };
+
+        for (sql, expected) in [
+            ("DELETE FROM users WHERE id = 1", "users"),
+            ("DELETE users FROM users WHERE id = 1", "users"),
+            ("DELETE FROM `users` WHERE id = 1", "users"),
+            ("DELETE `users` FROM users WHERE id = 1", "users"),
+            ("DELETE FROM db1.users WHERE id = 1", "db1.users"),
+            ("DELETE FROM `db1`.`users` WHERE id = 1", "db1.users"),
+        ] {
+            match plan_sql(sql).unwrap() {
+                Plan::Delete {
+                    table_name,
+                    conditions,
+                } => {
+                    assert_eq!(table_name, expected, "{}", sql);
+                    assert!(conditions.is_some());
+                }
+                _ => panic!("预期生成Delete查询计划"),
+            }
+        }
+        assert!(matches!(
+            plan_sql("DELETE FROM users").unwrap(),
+            Plan::Delete { conditions: None, .. }
+        ));
+
+        // 多表删除和会被忽略的子句都应报错，而不是删除错误的行
+        assert!(plan_sql("DELETE FROM a, b").is_err());
+        assert!(plan_sql("DELETE a FROM a JOIN b ON a.id = b.id").is_err());
+        assert!(plan_sql("DELETE b FROM a").is_err());
+        assert!(plan_sql("DELETE users FROM db1.users").is_err());
+        assert!(plan_sql("DELETE t FROM (SELECT * FROM users) AS t").is_err());
+        assert!(plan_sql("DELETE FROM users LIMIT 1").is_err());
+        assert!(plan_sql("DELETE FROM users ORDER BY id").is_err());
+    }
+
+    #[test]
+    fn test_transaction_plans() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let plan_sql = |sql: &str| {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            planner.plan(&ast[0])
+        };
+
+        for (sql, kind) in [
+            ("BEGIN", "BEGIN"),
+            ("START TRANSACTION", "BEGIN"),
+            ("COMMIT", "COMMIT"),
+            ("ROLLBACK", "ROLLBACK"),
+        ] {
+            let plan = plan_sql(sql).unwrap();
+            assert_eq!(plan.kind(), kind);
+            assert!(!plan.is_mutating());
+        }
+        assert!(plan_sql("START TRANSACTION READ ONLY").is_err());
+        assert!(plan_sql("ROLLBACK TO SAVEPOINT sp").is_err());
+        assert!(plan_sql("COMMIT AND CHAIN").is_err());
+    }
+
+    #[test]
+    fn test_select_distinct_plan() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let plan_sql = |sql: &str| {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            planner.plan(&ast[0])
+        };
+
+        for (sql, expected) in [
+            ("SELECT DISTINCT score FROM t", true),
+            ("SELECT DISTINCT * FROM t ORDER BY id", true),
+            ("SELECT DISTINCT 1", true),
+            ("SELECT score FROM t", false),
+        ] {
+            match plan_sql(sql).unwrap() {
+                Plan::Select { distinct, .. } => assert_eq!(distinct, expected, "{}", sql),
+                other => panic!("预期 SELECT 计划，实际为 {:?}", other),
+            }
+        }
+        assert_eq!(
+            plan_sql("SELECT DISTINCT score FROM t").unwrap().explain()[0],
+            "Select DISTINCT FROM t"
+        );
+        assert!(plan_sql("SELECT DISTINCT ON (score) id FROM t").is_err());
+    }
+
+    #[test]
+    fn test_limit_offset_plan() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let limit_offset = |sql: &str| -> Result<(Option<u64>, Option<u64>)> {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            match planner.plan(&ast[0])? {
+                Plan::Select { limit, offset, .. } => Ok((limit, offset)),
+                _ => panic!("预期生成Select查询计划"),
+            }
+        };
+
+        assert_eq!(limit_offset("SELECT * FROM t").unwrap(), (None, None));
+        assert_eq!(limit_offset("SELECT * FROM t LIMIT 10").unwrap(), (Some(10), None));
+        assert_eq!(
+            limit_offset("SELECT * FROM t ORDER BY id LIMIT 10 OFFSET 20").unwrap(),
+            (Some(10), Some(20))
+        );
+        // MySQL 的 LIMIT offset, count
+        assert_eq!(limit_offset("SELECT * FROM t LIMIT 20, 10").unwrap(), (Some(10), Some(20)));
+        assert_eq!(limit_offset("SELECT 1 LIMIT 0").unwrap(), (Some(0), None));
+
+        assert!(limit_offset("SELECT * FROM t LIMIT -1").is_err());
+        assert!(limit_offset("SELECT * FROM t LIMIT 1.5").is_err());
+        assert!(limit_offset("SELECT * FROM t LIMIT id").is_err());
+    }
+
+    #[test]
+    fn test_like_condition_plan() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let where_condition = |sql: &str| -> Result<Condition> {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            match planner.plan(&ast[0])? {
+                Plan::Select { conditions, .. } => Ok(conditions.unwrap()),
+                _ => panic!("预期生成Select查询计划"),
+            }
+        };
+
+        assert_eq!(
+            where_condition("SELECT * FROM t WHERE name NOT ILIKE 'a!%%' ESCAPE '!'").unwrap(),
+            Condition::Like {
+                expr: Expression::Column("name".to_string()),
+                pattern: Expression::Value(Value::String("a!%%".to_string())),
+                escape: Some('!'),
+                case_insensitive: true,
+                negated: true,
+            }
+        );
+        // 默认转义字符为反斜杠，且 MySQL 方言保留 \% 中的反斜杠
+        assert_eq!(
+            where_condition("SELECT * FROM t WHERE name LIKE '50\\%%'").unwrap(),
+            Condition::Like {
+                expr: Expression::Column("name".to_string()),
+                pattern: Expression::Value(Value::String("50\\%%".to_string())),
+                escape: Some('\\'),
+                case_insensitive: false,
+                negated: false,
+            }
+        );
+        match where_condition("SELECT * FROM t WHERE name LIKE 'a' ESCAPE ''").unwrap() {
+            Condition::Like { escape, .. } => assert_eq!(escape, None),
+            other => panic!("预期生成 LIKE 条件，实际为 {:?}", other),
+        }
+        assert!(where_condition("SELECT * FROM t WHERE name LIKE 'a' ESCAPE '!!'").is_err());
+    }
+
+    #[test]
+    fn test_explain_rendering() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let explain = |sql: &str| {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            planner.plan(&ast[0]).unwrap().explain()
+        };
+
+        assert_eq!(
+            explain(
+                "SELECT name, COUNT(*) AS n, UPPER(city) FROM users u \
+                 WHERE (age + 1) * 2 > 30 AND name LIKE 'a%' AND (city = 'it''s' OR city IS NULL) \
+                 GROUP BY name, city HAVING NOT (n > 1 AND n < 5) ORDER BY name"
+            ),
+            vec![
+                "Select FROM users AS u",
+                "  COLUMNS name, COUNT(*) AS n, UPPER(city)",
+                "  WHERE",
+                "    AND",
+                "      (age + 1) * 2 > 30",
+                "      name LIKE 'a%'",
+                "      OR",
+                "        city = 'it''s'",
+                "        city IS NULL",
+                "  GROUP BY name, city",
+                "  HAVING",
+                "    NOT",
+                "      AND",
+                "        n > 1",
+                "        n < 5",
+                "  ORDER BY name ASC NULLS FIRST",
+            ]
+        );
+
+        assert_eq!(
+            explain("UPDATE t SET a = -b, c = 'x' WHERE id = 1"),
+            vec!["Update t", "  SET a = -b, c = 'x'", "  WHERE id = 1"]
+        );
+        assert_eq!(explain("DELETE FROM t"), vec!["Delete FROM t"]);
+        assert_eq!(
+            explain(
+                "DELETE FROM t WHERE id NOT IN (SELECT t_id FROM s) \
+                 AND v > (SELECT AVG(v) FROM t)"
+            ),
+            vec![
+                "Delete FROM t",
+                "  WHERE",
+                "    AND",
+                "      id NOT IN (subquery)",
+                "      v > (subquery)",
+                "  SUBQUERY",
+                "    Select FROM s",
+                "      COLUMNS t_id",
+                "  SUBQUERY",
+                "    Select FROM t",
+                "      COLUMNS AVG(v)",
+            ]
+        );
+        assert_eq!(
+            explain("INSERT IGNORE INTO t (a, b) VALUES (1, 2), (3, 4)"),
+            vec!["Insert IGNORE INTO t (a, b)", "  VALUES 2 rows"]
+        );
+        assert_eq!(
+            explain("INSERT INTO t SELECT * FROM s JOIN r ON s.id = r.id"),
+            vec![
+                "Insert INTO t",
+                "  Select FROM s",
+                "    COLUMNS *",
+                "    JOIN r ON s.id = r.id",
+            ]
+        );
+
+        // 单行写法中 AND 里的 OR 加括号
+        let ast = sqlparser::parser::Parser::parse_sql(
+            &dialect,
+            "SELECT * FROM t WHERE a = 1 AND (b = 2 OR NOT c IS NULL)",
+        )
+        .unwrap();
+        let Plan::Select {
+            conditions: Some(condition),
+            ..
+        } = planner.plan(&ast[0]).unwrap()
+        else {
+            panic!("预期带条件的 SELECT 计划");
+        };
+        assert_eq!(condition.to_string(), "a = 1 AND (b = 2 OR NOT c IS NULL)");
+    }
+
+    #[test]
+    fn test_int_display_width_limit() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+
+        let sql = "CREATE TABLE t (a INT(255))";
+        let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+        assert!(planner.plan(&ast[0]).is_ok());
+
+        let sql = "CREATE TABLE t (a INT(256))";
+        let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+        assert!(planner.plan(&ast[0]).is_err());
+    }
+
+    #[test]
+    fn test_create_table_constraints_plan() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let sql = "CREATE TABLE t (
+            id INT,
+            a INT,
+            b INT,
+            PRIMARY KEY (id),
+            KEY (a),
+            KEY (a, b),
+            UNIQUE KEY uk_b (b)
+        )";
+        let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+        let plan = Planner::new().plan(&ast[0]).unwrap();
+
+        let Plan::CreateTable {
+            columns, indexes, ..
+        } = plan
+        else {
+            panic!("预期生成CreateTable查询计划");
+        };
+        assert!(columns[0].is_primary && columns[0].not_null && columns[0].unique);
+        let names: Vec<_> = indexes.iter().map(|index| index.name.as_str()).collect();
+        assert_eq!(names, ["a", "a_2", "uk_b"]);
+        assert_eq!(indexes[1].columns, ["a", "b"]);
+        assert!(indexes[2].unique);
+
+        let duplicate = "CREATE TABLE t (a INT, KEY k (a), KEY k (a))";
+        let ast = sqlparser::parser::Parser::parse_sql(&dialect, duplicate).unwrap();
+        assert!(Planner::new().plan(&ast[0]).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_column_names_rejected() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+
+        for sql in [
+            "CREATE TABLE t (id INT, id VARCHAR(10))",
+            "CREATE TABLE t (id INT, ID INT)",
+            "CREATE TABLE t (Id INT, `id` INT)",
+        ] {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            let err = planner.plan(&ast[0]).unwrap_err();
+            assert_eq!(err.to_string(), "Duplicate column name 'id'", "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_column_default_values() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+
+        let sql = "CREATE TABLE t (id INT DEFAULT -1, status VARCHAR(10) NOT NULL DEFAULT 'new', \
+                   score FLOAT DEFAULT 2, note VARCHAR(10) DEFAULT NULL, v INT)";
+        let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+        let Plan::CreateTable { columns, .. } = planner.plan(&ast[0]).unwrap() else {
+            panic!("预期生成CreateTable查询计划");
+        };
+        let defaults: Vec<_> = columns.into_iter().map(|col| col.default).collect();
+        assert_eq!(
+            defaults,
+            vec![
+                Some(Value::Int(-1)),
+                Some(Value::String("new".to_string())),
+                Some(Value::Float(2.0)),
+                Some(Value::Null),
+                None,
+            ]
+        );
+
+        for sql in [
+            "CREATE TABLE t (id INT NOT NULL DEFAULT NULL)",
+            "CREATE TABLE t (id INT DEFAULT 'abc')",
+            "CREATE TABLE t (name VARCHAR(2) DEFAULT 'abc')",
+            "CREATE TABLE t (id INT AUTO_INCREMENT DEFAULT 1)",
+        ] {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            let err = planner.plan(&ast[0]).unwrap_err();
+            assert!(
+                err.to_string().starts_with("Invalid default value for"),
+                "{}",
+                sql
+            );
+        }
+    }
+
     /*
     #[test]
     fn test_drop_table_plan() {
@@ -894,6 +3576,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             // 验证是无表查询
@@ -933,6 +3616,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert!(table_name.is_none());
@@ -965,6 +3649,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             // 有表查询
@@ -1009,6 +3694,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1037,10 +3723,12 @@ mod tests {
             // 测试 ORDER BY
             let order_by = order_by.unwrap();
             assert_eq!(order_by.len(), 2);
-            assert_eq!(order_by[0].column, "name");
+            assert!(matches!(&order_by[0].target, SortTarget::Column(c) if c == "name"));
             assert_eq!(order_by[0].direction, SortDirection::Asc);
-            assert_eq!(order_by[1].column, "id");
+            assert!(matches!(&order_by[1].target, SortTarget::Column(c) if c == "id"));
             assert_eq!(order_by[1].direction, SortDirection::Desc);
+            assert_eq!(order_by[0].nulls, NullsOrder::First);
+            assert_eq!(order_by[1].nulls, NullsOrder::Last);
         } else {
             panic!("预期生成Select查询计划");
         }
@@ -1059,6 +3747,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1121,6 +3810,11 @@ mod tests {
                     panic!("预期生成表达式条件，而不是 IS NOT NULL")
                 }
                 Condition::Constant(_) => panic!("预期生成表达式条件，而不是常量条件"),
+                Condition::Like { .. } => panic!("预期生成表达式条件，而不是 LIKE 条件"),
+                Condition::In { .. } => panic!("预期生成表达式条件，而不是 IN 条件"),
+                Condition::IsTruth { .. } => {
+                    panic!("预期生成表达式条件，而不是 IS TRUE/FALSE 条件")
+                }
                 Condition::And(_, _) | Condition::Or(_, _) | Condition::Not(_) => {
                     panic!("预期生成表达式条件，而不是逻辑组合条件")
                 }
@@ -1146,6 +3840,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1240,6 +3935,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1297,6 +3993,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1354,6 +4051,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1407,6 +4105,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1451,6 +4150,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1482,6 +4182,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "users"); // 修改：使用 Option<String>
@@ -1514,20 +4215,114 @@ mod tests {
     }
 
     #[test]
-    fn test_select_wildcard_with_other_columns_should_fail() {
+    fn test_select_wildcard_with_other_columns() {
         let dialect = sqlparser::dialect::MySqlDialect {};
-        let sql = "SELECT *, id FROM users;";
-        let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
         let planner = Planner::new();
+        let columns = |sql: &str| -> Result<SelectColumns> {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            match planner.plan(&ast[0])? {
+                Plan::Select { columns, .. } => Ok(columns),
+                other => panic!("预期生成Select查询计划: {:?}", other),
+            }
+        };
+        let item = |name: &str| {
+            Projection::Item(SelectItem {
+                expr: Expression::Column(name.to_string()),
+                alias: None,
+                original_text: name.to_string(),
+            })
+        };
 
-        // 这应该返回错误
-        let result = planner.plan(&ast[0]);
-        assert!(result.is_err());
+        // 通配符与其它项按原来的顺序保留
+        assert_eq!(
+            columns("SELECT *, id FROM users").unwrap(),
+            SelectColumns::Mixed(vec![Projection::Wildcard(None), item("id")])
+        );
+        assert_eq!(
+            columns("SELECT *, * FROM users").unwrap(),
+            SelectColumns::Mixed(vec![Projection::Wildcard(None), Projection::Wildcard(None)])
+        );
+        assert_eq!(
+            columns("SELECT id, Users.*, name FROM users").unwrap(),
+            SelectColumns::Mixed(vec![
+                item("id"),
+                Projection::Wildcard(Some("users".to_string())),
+                item("name"),
+            ])
+        );
+        assert_eq!(
+            columns("SELECT u.* FROM users u").unwrap(),
+            SelectColumns::Mixed(vec![Projection::Wildcard(Some("u".to_string()))])
+        );
+        assert_eq!(
+            columns("SELECT * FROM users").unwrap(),
+            SelectColumns::Wildcard
+        );
+
+        // 不带限定的 * 不能跟在表达式后面
+        assert!(matches!(
+            columns("SELECT id, * FROM users"),
+            Err(DBError::Parse(_))
+        ));
+    }
 
-        if let Err(DBError::Parse(_)) = result {
-        } else {
-            panic!("预期解析错误");
-        }
+    #[test]
+    fn test_expand_wildcards() {
+        let column = |name: &str| ColumnDef {
+            name: name.to_string(),
+            data_type: DataType::Int(None),
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        };
+        let names = |columns: &SelectColumns| match columns {
+            SelectColumns::Columns(items) => items
+                .iter()
+                .map(|item| item.original_text.clone())
+                .collect::<Vec<_>>(),
+            other => panic!("预期展开为具体列: {:?}", other),
+        };
+        let one = Projection::Item(SelectItem {
+            expr: Expression::Value(Value::Int(1)),
+            alias: Some("one".to_string()),
+            original_text: "1".to_string(),
+        });
+
+        // 单表：按表名或别名限定，带库名的表也可以只用表名
+        let single = [column("id"), column("name")];
+        let columns = SelectColumns::Mixed(vec![
+            Projection::Wildcard(Some("T".to_string())),
+            one.clone(),
+            Projection::Wildcard(None),
+        ]);
+        assert_eq!(
+            names(&columns.expand(&single, &["db1.t"]).unwrap()),
+            ["id", "name", "1", "id", "name"]
+        );
+        let err = columns.expand(&single, &["x"]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown table 'T'");
+
+        // 多表连接：只展开限定的那张表
+        let joined = [column("u.id"), column("u.name"), column("o.id")];
+        let columns = SelectColumns::Mixed(vec![
+            Projection::Wildcard(Some("o".to_string())),
+            one,
+            Projection::Wildcard(Some("u".to_string())),
+        ]);
+        assert_eq!(
+            names(&columns.expand(&joined, &["u", "o"]).unwrap()),
+            ["o.id", "1", "u.id", "u.name"]
+        );
+
+        // 不含通配符的列表原样返回
+        let plain = SelectColumns::Wildcard;
+        assert!(matches!(
+            plain.expand(&single, &["t"]).unwrap(),
+            Cow::Borrowed(_)
+        ));
     }
 
     #[test]
@@ -1543,6 +4338,7 @@ mod tests {
             columns,
             conditions,
             order_by,
+            ..
         } = plan
         {
             assert_eq!(table_name.as_ref().unwrap(), "books_test12"); // 修改：使用 Option<String>
@@ -1580,7 +4376,8 @@ mod tests {
         if let Plan::Insert {
             table_name,
             columns,
-            rows,
+            source: InsertSource::Values(rows),
+            ..
         } = plan
         {
             assert_eq!(table_name, "users");
@@ -1589,13 +4386,13 @@ mod tests {
 
             // 第一行
             assert_eq!(rows[0].len(), 2);
-            assert_eq!(rows[0][0], Value::Int(1));
-            assert_eq!(rows[0][1], Value::String("Alice".to_string()));
+            assert_eq!(rows[0][0], Some(Value::Int(1)));
+            assert_eq!(rows[0][1], Some(Value::String("Alice".to_string())));
 
             // 第二行
             assert_eq!(rows[1].len(), 2);
-            assert_eq!(rows[1][0], Value::Int(2));
-            assert_eq!(rows[1][1], Value::String("Bob".to_string()));
+            assert_eq!(rows[1][0], Some(Value::Int(2)));
+            assert_eq!(rows[1][1], Some(Value::String("Bob".to_string())));
         } else {
             panic!("预期生成Insert查询计划");
         }
@@ -1612,7 +4409,8 @@ mod tests {
         if let Plan::Insert {
             table_name,
             columns,
-            rows,
+            source: InsertSource::Values(rows),
+            ..
         } = plan
         {
             assert_eq!(table_name, "users");
@@ -1621,15 +4419,15 @@ mod tests {
 
             // 第一行
             assert_eq!(rows[0].len(), 3);
-            assert_eq!(rows[0][0], Value::Int(1));
-            assert_eq!(rows[0][1], Value::String("Alice".to_string()));
-            assert_eq!(rows[0][2], Value::Int(25));
+            assert_eq!(rows[0][0], Some(Value::Int(1)));
+            assert_eq!(rows[0][1], Some(Value::String("Alice".to_string())));
+            assert_eq!(rows[0][2], Some(Value::Int(25)));
 
             // 第二行
             assert_eq!(rows[1].len(), 3);
-            assert_eq!(rows[1][0], Value::Int(2));
-            assert_eq!(rows[1][1], Value::String("Bob".to_string()));
-            assert_eq!(rows[1][2], Value::Int(30));
+            assert_eq!(rows[1][0], Some(Value::Int(2)));
+            assert_eq!(rows[1][1], Some(Value::String("Bob".to_string())));
+            assert_eq!(rows[1][2], Some(Value::Int(30)));
         } else {
             panic!("预期生成Insert查询计划");
         }
@@ -1645,4 +4443,240 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_insert_select_plan() {
+        let dialect = sqlparser::dialect::MySqlDialect {};
+        let planner = Planner::new();
+        let plan_sql = |sql: &str| {
+            let ast = sqlparser::parser::Parser::parse_sql(&dialect, sql).unwrap();
+            planner.plan(&ast[0])
+        };
+
+        let plan =
+            plan_sql("INSERT INTO archive (id, name) SELECT id, name FROM users WHERE age > 60")
+                .unwrap();
+        let Plan::Insert {
+            columns,
+            source: InsertSource::Select(select),
+            ..
+        } = plan
+        else {
+            panic!("预期生成来源为查询的 Insert 计划");
+        };
+        assert_eq!(columns, vec!["id", "name"]);
+        assert!(matches!(
+            *select,
+            Plan::Select { ref table_name, conditions: Some(_), .. } if table_name.as_deref() == Some("users")
+        ));
+
+        let plan = plan_sql("INSERT INTO t SELECT a FROM x UNION SELECT a FROM y").unwrap();
+        assert!(matches!(
+            plan,
+            Plan::Insert {
+                source: InsertSource::Select(ref select),
+                ..
+            } if matches!(**select, Plan::Union { .. })
+        ));
+        assert!(plan_sql("INSERT INTO t SELECT a INTO u FROM x").is_err());
+    }
+
+    #[test]
+    fn test_constant_values_at_int_boundaries() {
+        let planner = Planner::new();
+        let value_of = |sql: &str| -> Result<Value> {
+            let ast =
+                sqlparser::parser::Parser::parse_sql(&sqlparser::dialect::MySqlDialect {}, sql)
+                    .unwrap();
+            match planner.plan(&ast[0])? {
+                Plan::Insert {
+                    source: InsertSource::Values(rows),
+                    ..
+                } => Ok(rows[0][0].clone().unwrap()),
+                other => panic!("预期生成Insert查询计划，实际为 {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            value_of("INSERT INTO t VALUES (-2147483648)").unwrap(),
+            Value::Int(i32::MIN)
+        );
+        assert_eq!(
+            value_of("INSERT INTO t VALUES (2147483646 + 1)").unwrap(),
+            Value::Int(i32::MAX)
+        );
+        assert_eq!(
+            value_of("INSERT INTO t VALUES (UPPER('a'))").unwrap(),
+            Value::String("A".to_string())
+        );
+        for sql in [
+            "INSERT INTO t VALUES (2147483648)",
+            "INSERT INTO t VALUES (2147483647 + 1)",
+            "INSERT INTO t VALUES (-(-2147483648))",
+            "INSERT INTO t VALUES (-2147483648 / -1)",
+        ] {
+            assert!(value_of(sql).is_err(), "应报错: {}", sql);
+        }
+        // 常量只能由字面量组成
+        assert!(value_of("INSERT INTO t VALUES (id + 1)").is_err());
+    }
+
+    #[test]
+    fn test_column_references() {
+        let ast = sqlparser::parser::Parser::parse_sql(
+            &sqlparser::dialect::MySqlDialect {},
+            "SELECT * FROM t WHERE UPPER(a) = b + COUNT(c) AND d IS NULL \
+             AND e IN (SELECT f FROM u) AND g > (SELECT MAX(h) FROM u)",
+        )
+        .unwrap();
+        let Plan::Select {
+            conditions: Some(condition),
+            ..
+        } = Planner::new().plan(&ast[0]).unwrap()
+        else {
+            panic!("预期生成带条件的Select查询计划");
+        };
+        // 子查询中的列不算在内
+        assert_eq!(
+            condition.column_references(),
+            vec!["a", "b", "c", "d", "e", "g"]
+        );
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        let record = Record::new(Vec::new());
+        let literal = |truth: Option<bool>| Expression::Value(truth_value(truth));
+        let condition = |truth: Option<bool>| Condition::Expression(literal(truth));
+        let binary = |l, operator, r| Expression::Binary {
+            left: Box::new(literal(l)),
+            operator,
+            right: Box::new(literal(r)),
+        };
+
+        let (t, f, n) = (Some(true), Some(false), None);
+        // (左, 右, AND, OR)
+        let table = [
+            (t, t, t, t),
+            (t, f, f, t),
+            (t, n, n, t),
+            (f, t, f, t),
+            (f, f, f, f),
+            (f, n, f, n),
+            (n, t, n, t),
+            (n, f, f, n),
+            (n, n, n, n),
+        ];
+        for (l, r, and, or) in table {
+            let and_cond = Condition::And(Box::new(condition(l)), Box::new(condition(r)));
+            let or_cond = Condition::Or(Box::new(condition(l)), Box::new(condition(r)));
+            assert_eq!(
+                and_cond.truth(&record, &[], SqlMode::Lenient).unwrap(),
+                and,
+                "{:?} AND {:?}",
+                l,
+                r
+            );
+            assert_eq!(
+                or_cond.truth(&record, &[], SqlMode::Lenient).unwrap(),
+                or,
+                "{:?} OR {:?}",
+                l,
+                r
+            );
+            assert_eq!(
+                and_cond.evaluate(&record, &[], SqlMode::Lenient).unwrap(),
+                and == t
+            );
+
+            // 表达式中的 AND / OR 遵循同样的真值表
+            let and_expr = binary(l, BinaryOperator::And, r);
+            let or_expr = binary(l, BinaryOperator::Or, r);
+            assert_eq!(
+                and_expr.evaluate(&record, &[], SqlMode::Lenient).unwrap(),
+                truth_value(and)
+            );
+            assert_eq!(
+                or_expr.evaluate(&record, &[], SqlMode::Lenient).unwrap(),
+                truth_value(or)
+            );
+        }
+
+        for (operand, negated) in [(t, f), (f, t), (n, n)] {
+            let not_cond = Condition::Not(Box::new(condition(operand)));
+            assert_eq!(
+                not_cond.truth(&record, &[], SqlMode::Lenient).unwrap(),
+                negated
+            );
+            let not_expr = Expression::Unary {
+                operator: UnaryOperator::Not,
+                operand: Box::new(literal(operand)),
+            };
+            assert_eq!(
+                not_expr.evaluate(&record, &[], SqlMode::Lenient).unwrap(),
+                truth_value(negated)
+            );
+        }
+
+        // IS [NOT] TRUE/FALSE 的结果从不为 NULL
+        // (操作数, IS TRUE, IS FALSE)
+        let truth_table = [(t, true, false), (f, false, true), (n, false, false)];
+        for (operand, is_true, is_false) in truth_table {
+            for (value, expected) in [(true, is_true), (false, is_false)] {
+                for negated in [false, true] {
+                    let test = Condition::IsTruth {
+                        operand: Box::new(condition(operand)),
+                        value,
+                        negated,
+                    };
+                    assert_eq!(
+                        test.truth(&record, &[], SqlMode::Lenient).unwrap(),
+                        Some(expected != negated),
+                        "{}",
+                        test
+                    );
+                }
+            }
+        }
+
+        // 与 NULL 比较的结果为 NULL，NOT 之后仍不匹配
+        for operator in [
+            BinaryOperator::Equal,
+            BinaryOperator::NotEqual,
+            BinaryOperator::LessThan,
+            BinaryOperator::LessThanOrEqual,
+            BinaryOperator::GreaterThan,
+            BinaryOperator::GreaterThanOrEqual,
+        ] {
+            let comparison = Expression::Binary {
+                left: Box::new(Expression::Value(Value::Null)),
+                operator: operator.clone(),
+                right: Box::new(Expression::Value(Value::Int(5))),
+            };
+            assert_eq!(
+                comparison.evaluate(&record, &[], SqlMode::Lenient).unwrap(),
+                Value::Null
+            );
+            let negated = Condition::Not(Box::new(Condition::Expression(comparison)));
+            assert_eq!(
+                negated.truth(&record, &[], SqlMode::Lenient).unwrap(),
+                None,
+                "{:?}",
+                operator
+            );
+            assert!(!negated.evaluate(&record, &[], SqlMode::Lenient).unwrap());
+        }
+
+        // IS NULL 的结果总是确定的
+        let is_null = Condition::IsNull(Expression::Value(Value::Null));
+        assert_eq!(is_null.truth(&record, &[], SqlMode::Lenient).unwrap(), t);
+        let not_like = Condition::Like {
+            expr: Expression::Value(Value::Null),
+            pattern: Expression::Value(Value::String("%".to_string())),
+            escape: None,
+            case_insensitive: false,
+            negated: true,
+        };
+        assert_eq!(not_like.truth(&record, &[], SqlMode::Lenient).unwrap(), n);
+    }
 }
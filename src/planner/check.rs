@@ -0,0 +1,860 @@
+//! 只计划、不执行的 SQL 检查，供外部工具在真实的表结构上校验脚本
+//!
+//! 检查不读取也不修改任何数据。脚本中的 CREATE TABLE、DROP TABLE 和 USE 只作用于
+//! 检查器自己的目录副本，后续语句能看到它们的效果。
+
+use super::function::ScalarFunction;
+use super::optimizer::fold_expression;
+use super::validate::{self, describe};
+use super::{
+    AggregateFunction, BinaryOperator, Condition, Expression, InSet, InsertSource, Plan, Planner,
+    SelectColumns, SortTarget, UnaryOperator, resolve_column,
+};
+use crate::error::{DBError, Result};
+use crate::session::{QUERY_HISTORY_TABLE, SqlMode, Warning, WarningLevel, query_history_columns};
+use crate::storage::StorageEngine;
+use crate::storage::catalog::Catalog;
+use crate::storage::table::{ColumnDef, DataType, Table, Value, column_position};
+use sqlparser::ast;
+use std::fmt;
+
+/// 一条语句的检查结果
+#[derive(Debug)]
+pub struct PlanSummary {
+    /// 语句文本（由语法树重新生成）
+    pub statement: String,
+    /// 语句类型，见 [`Plan::kind`]；生成计划失败时为 `"UNKNOWN"`
+    pub kind: &'static str,
+    /// 引用的表，按第一次出现的顺序
+    pub tables: Vec<String>,
+    /// 引用的列，JOIN 查询中形如 `别名.列名`
+    pub columns: Vec<String>,
+    /// 执行这条语句时会产生的警告
+    pub warnings: Vec<Warning>,
+    /// 计划或语义检查失败的原因，`None` 表示通过检查
+    pub error: Option<DBError>,
+}
+
+impl PlanSummary {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn reference_table(&mut self, name: &str) {
+        if !self.tables.iter().any(|table| table == name) {
+            self.tables.push(name.to_string());
+        }
+    }
+
+    fn reference_column(&mut self, name: &str) {
+        if !self.columns.iter().any(|column| column == name) {
+            self.columns.push(name.to_string());
+        }
+    }
+
+    fn warn(&mut self, level: WarningLevel, code: u32, message: String) {
+        self.warnings.push(Warning {
+            level,
+            code,
+            message,
+        });
+    }
+}
+
+/// 表达式的静态类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    Int,
+    Float,
+    Boolean,
+    String,
+    Null,
+    /// 无法静态确定，如 HAVING 中引用的选择列别名
+    Unknown,
+}
+
+impl ValueType {
+    fn of_value(value: &Value) -> Self {
+        match value {
+            Value::Int(_) => ValueType::Int,
+            Value::Float(_) => ValueType::Float,
+            Value::Boolean(_) => ValueType::Boolean,
+            Value::String(_) => ValueType::String,
+            Value::Null => ValueType::Null,
+        }
+    }
+
+    fn of_column(column: &ColumnDef) -> Self {
+        match column.data_type {
+            DataType::Int(_) => ValueType::Int,
+            DataType::Varchar(_) => ValueType::String,
+            DataType::Float => ValueType::Float,
+            DataType::Boolean => ValueType::Boolean,
+        }
+    }
+
+    /// 能否参与算术运算（NULL 和未知类型不报错）
+    fn is_arithmetic(self) -> bool {
+        !matches!(self, ValueType::Boolean | ValueType::String)
+    }
+
+    /// 能否作为条件或 AND / OR / NOT 的操作数
+    fn is_logical(self) -> bool {
+        matches!(
+            self,
+            ValueType::Boolean | ValueType::Null | ValueType::Unknown
+        )
+    }
+
+    /// 能否写入该类型的列，规则与插入时的隐式转换（`Value::coerce_to`）一致
+    ///
+    /// 整数写入 BOOLEAN 列时只有 0/1 合法、浮点数写入 INT 列时只有整数值合法，
+    /// 静态检查无法确定，不报错。严格模式下布尔值与整数不互相转换。
+    fn fits(self, data_type: &DataType, mode: SqlMode) -> bool {
+        let lenient = mode == SqlMode::Lenient;
+        match (self, data_type) {
+            (ValueType::Null | ValueType::Unknown, _)
+            | (ValueType::Int, DataType::Int(_) | DataType::Float)
+            | (ValueType::Boolean, DataType::Boolean)
+            | (ValueType::Float, DataType::Float | DataType::Int(_))
+            | (ValueType::String, DataType::Varchar(_)) => true,
+            (ValueType::Int, DataType::Boolean) | (ValueType::Boolean, DataType::Int(_)) => lenient,
+            _ => false,
+        }
+    }
+
+    /// 比较时的类别，与 `Value` 的比较规则一致：数值之间、字符串之间可以比较，
+    /// 布尔值在宽松模式下归入数值，严格模式下只能与布尔值比较
+    fn comparison_class(self, mode: SqlMode) -> Option<u8> {
+        match self {
+            ValueType::Boolean if mode == SqlMode::Strict => Some(3),
+            ValueType::Int | ValueType::Float | ValueType::Boolean => Some(1),
+            ValueType::String => Some(2),
+            ValueType::Null | ValueType::Unknown => None,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::Int => "INT",
+            ValueType::Float => "FLOAT",
+            ValueType::Boolean => "BOOLEAN",
+            ValueType::String => "VARCHAR",
+            ValueType::Null => "NULL",
+            ValueType::Unknown => "未知类型",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 逐条检查语句，维护检查用的目录副本
+pub struct Checker<'a> {
+    storage: &'a StorageEngine,
+    catalog: Catalog,
+    /// 会话是否处于严格模式，决定超长的行定义是错误还是警告，以及布尔值与整数能否互相转换和比较
+    strict: bool,
+    /// 会话的 truncate_long_strings，决定超长的字符串是错误还是警告
+    truncate_long_strings: bool,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(storage: &'a StorageEngine, strict: bool) -> Result<Self> {
+        let catalog = storage.current_database()?.catalog().clone();
+        Ok(Self {
+            storage,
+            catalog,
+            strict,
+            truncate_long_strings: false,
+        })
+    }
+
+    fn sql_mode(&self) -> SqlMode {
+        if self.strict {
+            SqlMode::Strict
+        } else {
+            SqlMode::Lenient
+        }
+    }
+
+    /// 按会话的 truncate_long_strings 检查超长字符串
+    pub fn with_truncate_long_strings(mut self, truncate: bool) -> Self {
+        self.truncate_long_strings = truncate;
+        self
+    }
+
+    /// 超长的字符串：允许截断时记为警告，否则报错
+    fn check_lengths(
+        &self,
+        row: &[Value],
+        table_columns: &[ColumnDef],
+        truncate: bool,
+        summary: &mut PlanSummary,
+    ) -> Result<()> {
+        for (value, column) in row.iter().zip(table_columns) {
+            match validate::check_value_length(value, &column.data_type) {
+                Ok(()) => {}
+                Err(_) if truncate => summary.warn(
+                    WarningLevel::Warning,
+                    1265,
+                    format!("列 '{}' 的值将被截断", column.name),
+                ),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// 为一条语句生成计划并做语义检查，失败原因记录在结果的 `error` 中
+    pub fn check(&mut self, planner: &Planner, stmt: &ast::Statement) -> PlanSummary {
+        let mut summary = PlanSummary {
+            statement: stmt.to_string(),
+            kind: "UNKNOWN",
+            tables: Vec::new(),
+            columns: Vec::new(),
+            warnings: Vec::new(),
+            error: None,
+        };
+        let result = planner.plan(stmt).and_then(|plan| {
+            summary.kind = plan.kind();
+            self.check_plan(&plan, &mut summary)
+        });
+        summary.error = result.err();
+        summary
+    }
+
+    fn check_plan(&mut self, plan: &Plan, summary: &mut PlanSummary) -> Result<()> {
+        match plan {
+            Plan::CreateTable {
+                name,
+                columns,
+                if_not_exists,
+                ..
+            } => {
+                validate::ensure_writable(name)?;
+                summary.reference_table(name);
+                if self.catalog.has_table(name) {
+                    if *if_not_exists {
+                        summary.warn(WarningLevel::Note, 1050, format!("表 '{}' 已存在", name));
+                        return Ok(());
+                    }
+                    return Err(DBError::Schema(format!("表 '{}' 已存在", name)));
+                }
+                if let Some(message) = validate::oversized_row_message(name, columns) {
+                    if self.strict {
+                        return Err(DBError::Schema(message));
+                    }
+                    summary.warn(WarningLevel::Warning, 1118, message);
+                }
+                for message in columns.iter().filter_map(validate::display_width_note) {
+                    summary.warn(WarningLevel::Note, 1681, message);
+                }
+                self.catalog
+                    .add_table_metadata(name.clone(), columns.clone())
+            }
+            Plan::AlterTable {
+                table_name,
+                operations,
+            } => {
+                validate::ensure_writable(table_name)?;
+                let columns = self.table_columns(table_name, summary)?;
+                let indexes = self.catalog.get_table_indexes(table_name)?;
+                let altered = Table::with_indexes(table_name.clone(), columns, indexes)
+                    .plan_alter(operations)?;
+                if let Some(message) = validate::oversized_row_message(table_name, &altered.columns)
+                {
+                    if self.strict {
+                        return Err(DBError::Schema(message));
+                    }
+                    summary.warn(WarningLevel::Warning, 1118, message);
+                }
+                self.catalog
+                    .update_table_schema(table_name, altered.columns, altered.indexes)
+            }
+            Plan::DropTable {
+                name_vec,
+                if_exists,
+            } => {
+                let mut last_err = None;
+                for name in name_vec {
+                    summary.reference_table(name);
+                    if self.catalog.has_table(name) {
+                        self.catalog.remove_table_metadata(name)?;
+                    } else if *if_exists {
+                        summary.warn(WarningLevel::Note, 1051, format!("未知的表 '{}'", name));
+                    } else {
+                        last_err = Some(DBError::Schema(format!("表 '{}' 不存在", name)));
+                    }
+                }
+                last_err.map_or(Ok(()), Err)
+            }
+            Plan::Select { .. } => self.check_select(plan, summary).map(drop),
+            Plan::Union { .. } => self.check_union(plan, summary).map(drop),
+            Plan::Insert {
+                table_name,
+                columns,
+                source,
+                ignore,
+                returning,
+            } => {
+                validate::ensure_writable(table_name)?;
+                let table_columns = self.table_columns(table_name, summary)?;
+                if columns.is_empty() {
+                    for column in &table_columns {
+                        summary.reference_column(&column.name);
+                    }
+                }
+                for name in columns {
+                    if column_position(&table_columns, name).is_none() {
+                        return Err(DBError::Schema(format!(
+                            "表 '{}' 中不存在列 '{}'",
+                            table_name, name
+                        )));
+                    }
+                    summary.reference_column(name);
+                }
+
+                let expected = if columns.is_empty() {
+                    table_columns.len()
+                } else {
+                    columns.len()
+                };
+                // 查询的结果在执行时才知道，这里只检查查询本身和列数
+                let rows: &[Vec<Option<Value>>] = match source {
+                    InsertSource::Values(rows) => rows,
+                    InsertSource::Select(select) => {
+                        let names = match **select {
+                            Plan::Union { .. } => self.check_union(select, summary)?,
+                            _ => self.check_select(select, summary)?,
+                        };
+                        validate::check_insert_select_arity(names.len(), expected)?;
+                        &[]
+                    }
+                };
+                // 与执行时一样检查所有行，出错的行合并到一个错误中
+                let mut errors = Vec::new();
+                for (row_index, row) in rows.iter().enumerate() {
+                    if row.len() != expected {
+                        errors.push(DBError::Execution(format!(
+                            "第 {} 行的值数量({})与列数({})不匹配",
+                            row_index + 1,
+                            row.len(),
+                            expected
+                        )));
+                        continue;
+                    }
+                    let row_number = (rows.len() > 1).then_some(row_index + 1);
+                    let checked =
+                        validate::build_insert_row(&table_columns, columns, row, self.sql_mode())
+                            .and_then(|full_row| {
+                                validate::check_not_null(&full_row, &table_columns)?;
+                                self.check_lengths(
+                                    &full_row,
+                                    &table_columns,
+                                    self.truncate_long_strings,
+                                    summary,
+                                )
+                            });
+                    match checked {
+                        Ok(()) => {}
+                        // INSERT IGNORE 跳过这一行而不是报错
+                        Err(e) if *ignore => summary.warn(
+                            WarningLevel::Warning,
+                            1366,
+                            format!("第 {} 行将被跳过: {}", row_index + 1, e),
+                        ),
+                        Err(e) => errors.push(validate::at_row(e, row_number)),
+                    }
+                }
+                if let Some(e) = validate::combine_row_errors(errors) {
+                    return Err(e);
+                }
+
+                if let Some(returning) = returning
+                    && let SelectColumns::Columns(items) =
+                        &*returning.expand(&table_columns, &[table_name.as_str()])?
+                {
+                    for item in items {
+                        self.infer(&item.expr, &table_columns, &[], summary)?;
+                    }
+                }
+                Ok(())
+            }
+            Plan::Update {
+                table_name,
+                set_pairs,
+                conditions,
+            } => {
+                validate::ensure_writable(table_name)?;
+                let table_columns = self.table_columns(table_name, summary)?;
+                let mut unknown = validate::UnknownColumns::default();
+                for (name, expr) in set_pairs {
+                    if column_position(&table_columns, name).is_none() {
+                        unknown.add(name);
+                    }
+                    unknown.check_expression(expr, &table_columns)?;
+                }
+                if let Some(condition) = conditions {
+                    validate::check_where(condition, &[], &table_columns)?;
+                    unknown.check_condition(condition, &table_columns)?;
+                }
+                unknown.into_result()?;
+                for (name, expr) in set_pairs {
+                    let Some(index) = column_position(&table_columns, name) else {
+                        continue;
+                    };
+                    let column = &table_columns[index];
+                    summary.reference_column(name);
+                    // 字面量按执行时的规则检查；引用列的表达式只能检查推断出的类型
+                    let Expression::Value(value) = fold_expression(expr.clone()) else {
+                        let value_type = self.infer(expr, &table_columns, &[], summary)?;
+                        if !value_type.fits(&column.data_type, self.sql_mode()) {
+                            return Err(DBError::Schema(format!(
+                                "不能把 {} 类型的值写入 {} 列 '{}'",
+                                value_type, column.data_type, name
+                            )));
+                        }
+                        continue;
+                    };
+                    let value = value.coerce_to(&column.data_type, self.sql_mode());
+                    validate::check_value_kind(&value, &column.data_type)?;
+                    self.check_lengths(
+                        std::slice::from_ref(&value),
+                        std::slice::from_ref(column),
+                        self.truncate_long_strings,
+                        summary,
+                    )?;
+                }
+                match conditions {
+                    Some(condition) => {
+                        self.check_condition(condition, &table_columns, &[], summary)
+                    }
+                    None => Ok(()),
+                }
+            }
+            Plan::Delete {
+                table_name,
+                conditions,
+            } => {
+                validate::ensure_writable(table_name)?;
+                let table_columns = self.table_columns(table_name, summary)?;
+                match conditions {
+                    Some(condition) => {
+                        validate::check_where(condition, &[], &table_columns)?;
+                        let mut unknown = validate::UnknownColumns::default();
+                        unknown.check_condition(condition, &table_columns)?;
+                        unknown.into_result()?;
+                        self.check_condition(condition, &table_columns, &[], summary)
+                    }
+                    None => Ok(()),
+                }
+            }
+            Plan::UseDatabase { name } => {
+                self.catalog = self.storage.get_database(name)?.catalog().clone();
+                Ok(())
+            }
+            Plan::DescribeTable { name } | Plan::ShowCreateTable { name } => {
+                self.table_columns(name, summary).map(drop)
+            }
+            Plan::Explain(inner) => self.check_plan(inner, summary),
+            Plan::CreateDatabase { .. }
+            | Plan::DropDatabase { .. }
+            | Plan::ShowDatabases
+            | Plan::ShowTables
+            | Plan::ShowWarnings
+            | Plan::SetVariable { .. }
+            | Plan::ShowVariables { .. }
+            | Plan::Begin
+            | Plan::Commit
+            | Plan::Rollback => Ok(()),
+        }
+    }
+
+    /// 检查单个 SELECT，返回结果列名
+    fn check_select(&self, plan: &Plan, summary: &mut PlanSummary) -> Result<Vec<String>> {
+        let Plan::Select {
+            table_name,
+            columns,
+            conditions,
+            order_by,
+            table_alias,
+            joins,
+            group_by,
+            having,
+            into,
+            ..
+        } = plan
+        else {
+            unreachable!("check_select 只处理 SELECT 计划");
+        };
+        let table_columns = match table_name {
+            None if matches!(columns, SelectColumns::Mixed(_)) => {
+                return Err(DBError::Execution("无表查询不支持通配符 *".to_string()));
+            }
+            None => Vec::new(),
+            Some(name) if joins.is_empty() => self.table_columns(name, summary)?,
+            Some(name) => {
+                // 与执行时一样，每个 ON 条件只能引用它之前已连接的表
+                let mut all = self.qualified_columns(name, table_alias.as_deref(), summary)?;
+                for join in joins {
+                    all.extend(self.qualified_columns(
+                        &join.table_name,
+                        join.alias.as_deref(),
+                        summary,
+                    )?);
+                    self.check_condition(&join.on, &all, &[], summary)?;
+                }
+                all
+            }
+        };
+        let qualifiers: Vec<&str> = table_name
+            .iter()
+            .map(|name| table_alias.as_deref().unwrap_or(name))
+            .chain(
+                joins
+                    .iter()
+                    .map(|join| join.alias.as_deref().unwrap_or(&join.table_name)),
+            )
+            .collect();
+        let columns = &*columns.expand(&table_columns, &qualifiers)?;
+        validate::validate_select(columns, group_by, having.as_ref())?;
+
+        // 与执行时一样，先把找不到的列一起报告
+        let aggregated = validate::is_aggregate_query(columns, group_by, having.as_ref());
+        validate::check_select_columns(
+            columns,
+            conditions.as_ref(),
+            order_by.as_deref().filter(|_| !aggregated),
+            &table_columns,
+        )?;
+
+        let (names, aliases): (Vec<String>, Vec<&str>) = match columns {
+            SelectColumns::Wildcard => {
+                for column in &table_columns {
+                    summary.reference_column(&column.name);
+                }
+                let names = table_columns.iter().map(|column| column.name.clone());
+                (names.collect(), Vec::new())
+            }
+            SelectColumns::Columns(items) => {
+                for item in items {
+                    self.infer(&item.expr, &table_columns, &[], summary)?;
+                }
+                let names = items
+                    .iter()
+                    .map(|item| item.alias.clone().unwrap_or(item.original_text.clone()))
+                    .collect();
+                let aliases = items.iter().filter_map(|item| item.alias.as_deref());
+                (names, aliases.collect())
+            }
+            SelectColumns::Mixed(_) => unreachable!("通配符应该在前面已经展开"),
+        };
+
+        if let Some(condition) = conditions {
+            self.check_condition(condition, &table_columns, &[], summary)?;
+        }
+        for expr in group_by {
+            self.infer(expr, &table_columns, &[], summary)?;
+        }
+        if let Some(having) = having {
+            self.check_condition(having, &table_columns, &aliases, summary)?;
+        }
+
+        if let Some(order_items) = order_by {
+            for item in order_items {
+                if aggregated {
+                    validate::result_sort_position(&names, &item.target)?;
+                    continue;
+                }
+                match &item.target {
+                    SortTarget::Column(column) => {
+                        if aliases
+                            .iter()
+                            .any(|alias| alias.eq_ignore_ascii_case(column))
+                        {
+                            continue;
+                        }
+                        // 非聚合查询按表中的列排序
+                        let index = resolve_column(&table_columns, column)?;
+                        summary.reference_column(&table_columns[index].name);
+                    }
+                    SortTarget::Position(position) => {
+                        validate::sort_position(*position, names.len())?;
+                    }
+                    SortTarget::Expression { expr, .. } => {
+                        self.infer(expr, &table_columns, &[], summary)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(target) = into {
+            validate::ensure_writable(&target.table)?;
+            summary.reference_table(&target.table);
+            if !target.overwrite && self.catalog.has_table(&target.table) {
+                return Err(DBError::Schema(format!("表 '{}' 已存在", target.table)));
+            }
+        }
+        Ok(names)
+    }
+
+    /// 检查 UNION 的两侧，返回结果列名（取左侧）
+    fn check_union(&self, plan: &Plan, summary: &mut PlanSummary) -> Result<Vec<String>> {
+        let Plan::Union {
+            left,
+            right,
+            order_by,
+            ..
+        } = plan
+        else {
+            unreachable!("check_union 只处理 UNION 计划");
+        };
+
+        let left = self.check_union_branch(left, summary)?;
+        let right = self.check_union_branch(right, summary)?;
+        if left.len() != right.len() {
+            return Err(DBError::Execution(format!(
+                "UNION 两侧的列数不同（{} 和 {}）",
+                left.len(),
+                right.len()
+            )));
+        }
+        for item in order_by.iter().flatten() {
+            validate::result_sort_position(&left, &item.target)?;
+        }
+        Ok(left)
+    }
+
+    fn check_union_branch(&self, plan: &Plan, summary: &mut PlanSummary) -> Result<Vec<String>> {
+        match plan {
+            Plan::Select { into: Some(_), .. } => Err(DBError::Execution(
+                "UNION 的分支中不能使用 INTO".to_string(),
+            )),
+            Plan::Select { .. } => self.check_select(plan, summary),
+            Plan::Union { .. } => self.check_union(plan, summary),
+            _ => Err(DBError::Execution("UNION 的分支必须是查询".to_string())),
+        }
+    }
+
+    /// 检查子查询本身，子查询只能返回一列
+    fn check_subquery(&self, plan: &Plan, summary: &mut PlanSummary) -> Result<()> {
+        if self.check_union_branch(plan, summary)?.len() != 1 {
+            return Err(DBError::Execution(
+                "Operand should contain 1 column(s)".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 表的列定义，表不存在时报错
+    fn table_columns(&self, name: &str, summary: &mut PlanSummary) -> Result<Vec<ColumnDef>> {
+        summary.reference_table(name);
+        if name == QUERY_HISTORY_TABLE {
+            return Ok(query_history_columns());
+        }
+        // 带数据库限定的表读取该数据库的目录
+        let (catalog, table) = match name.split_once('.') {
+            Some((database, table)) => (self.storage.get_database(database)?.catalog(), table),
+            None => (&self.catalog, name),
+        };
+        if !catalog.has_table(table) {
+            return Err(DBError::NotFound(format!("表 '{}' 不存在", name)));
+        }
+        catalog.get_table_columns(table)
+    }
+
+    /// 参与 JOIN 的表的列定义，列名加上表名或别名限定
+    fn qualified_columns(
+        &self,
+        name: &str,
+        alias: Option<&str>,
+        summary: &mut PlanSummary,
+    ) -> Result<Vec<ColumnDef>> {
+        let qualifier = alias.unwrap_or(name);
+        let mut columns = self.table_columns(name, summary)?;
+        for column in &mut columns {
+            column.name = format!("{}.{}", qualifier, column.name);
+        }
+        Ok(columns)
+    }
+
+    fn check_condition(
+        &self,
+        condition: &Condition,
+        columns: &[ColumnDef],
+        aliases: &[&str],
+        summary: &mut PlanSummary,
+    ) -> Result<()> {
+        match condition {
+            Condition::Expression(expr) => {
+                let value_type = self.infer(expr, columns, aliases, summary)?;
+                if !value_type.is_logical() {
+                    return Err(DBError::Planner(format!(
+                        "条件 '{}' 的类型为 {}，不是布尔值",
+                        describe(expr),
+                        value_type
+                    )));
+                }
+                Ok(())
+            }
+            Condition::IsNull(expr) | Condition::IsNotNull(expr) => {
+                self.infer(expr, columns, aliases, summary).map(drop)
+            }
+            Condition::Like { expr, pattern, .. } => {
+                self.infer(expr, columns, aliases, summary)?;
+                self.infer(pattern, columns, aliases, summary).map(drop)
+            }
+            Condition::In { expr, set, .. } => {
+                self.infer(expr, columns, aliases, summary)?;
+                match set {
+                    InSet::Subquery(plan) => self.check_subquery(plan, summary),
+                    InSet::Values(_) => Ok(()),
+                }
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                self.check_condition(left, columns, aliases, summary)?;
+                self.check_condition(right, columns, aliases, summary)
+            }
+            Condition::Not(inner) | Condition::IsTruth { operand: inner, .. } => {
+                self.check_condition(inner, columns, aliases, summary)
+            }
+            Condition::Constant(_) => Ok(()),
+        }
+    }
+
+    /// 推断表达式的类型，同时检查列是否存在、操作数类型是否兼容
+    ///
+    /// `aliases` 是允许引用的选择列别名（只用于 HAVING），其类型视为未知。
+    fn infer(
+        &self,
+        expr: &Expression,
+        columns: &[ColumnDef],
+        aliases: &[&str],
+        summary: &mut PlanSummary,
+    ) -> Result<ValueType> {
+        match expr {
+            Expression::Column(name) => {
+                if aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name)) {
+                    return Ok(ValueType::Unknown);
+                }
+                let index = resolve_column(columns, name)?;
+                summary.reference_column(&columns[index].name);
+                Ok(ValueType::of_column(&columns[index]))
+            }
+            Expression::Value(value) => Ok(ValueType::of_value(value)),
+            Expression::Unary { operator, operand } => {
+                let operand = self.infer(operand, columns, aliases, summary)?;
+                match operator {
+                    UnaryOperator::Not if operand.is_logical() => Ok(ValueType::Boolean),
+                    UnaryOperator::Not => Err(DBError::Planner(format!(
+                        "NOT 的操作数必须是布尔值，实际为 {}",
+                        operand
+                    ))),
+                    UnaryOperator::Minus | UnaryOperator::Plus if operand.is_arithmetic() => {
+                        Ok(operand)
+                    }
+                    UnaryOperator::Minus | UnaryOperator::Plus => Err(DBError::Planner(format!(
+                        "正负号只能用于数值，不能用于 {}",
+                        operand
+                    ))),
+                }
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.infer(left, columns, aliases, summary)?;
+                let right = self.infer(right, columns, aliases, summary)?;
+                match operator {
+                    BinaryOperator::Add
+                    | BinaryOperator::Subtract
+                    | BinaryOperator::Multiply
+                    | BinaryOperator::Divide
+                    | BinaryOperator::Modulo => {
+                        if !left.is_arithmetic() || !right.is_arithmetic() {
+                            return Err(DBError::Planner(format!(
+                                "类型不兼容：不能对 {} 和 {} 做 {} 运算",
+                                left, right, operator
+                            )));
+                        }
+                        Ok(match (left, right) {
+                            (ValueType::Int, ValueType::Int) => ValueType::Int,
+                            (ValueType::Float, _) | (_, ValueType::Float) => ValueType::Float,
+                            _ => ValueType::Unknown,
+                        })
+                    }
+                    BinaryOperator::And | BinaryOperator::Or => {
+                        if !left.is_logical() || !right.is_logical() {
+                            return Err(DBError::Planner(format!(
+                                "{} 的操作数必须是布尔值，实际为 {} 和 {}",
+                                operator, left, right
+                            )));
+                        }
+                        Ok(ValueType::Boolean)
+                    }
+                    _ => {
+                        if let (Some(a), Some(b)) = (
+                            left.comparison_class(self.sql_mode()),
+                            right.comparison_class(self.sql_mode()),
+                        ) && a != b
+                        {
+                            return Err(DBError::Planner(format!(
+                                "类型不匹配：不能用 {} 比较 {} 和 {}",
+                                operator, left, right
+                            )));
+                        }
+                        Ok(ValueType::Boolean)
+                    }
+                }
+            }
+            Expression::Aggregate { func, arg } => {
+                let Some(arg) = arg else {
+                    return Ok(ValueType::Int);
+                };
+                let arg = self.infer(arg, columns, aliases, summary)?;
+                match func {
+                    AggregateFunction::Count => Ok(ValueType::Int),
+                    AggregateFunction::Min | AggregateFunction::Max => Ok(arg),
+                    AggregateFunction::Sum | AggregateFunction::Avg if !arg.is_arithmetic() => Err(
+                        DBError::Planner(format!("{} 只能用于数值，不能用于 {}", func, arg)),
+                    ),
+                    AggregateFunction::Sum if arg == ValueType::Int => Ok(ValueType::Int),
+                    AggregateFunction::Sum | AggregateFunction::Avg => Ok(ValueType::Float),
+                }
+            }
+            Expression::Function { name, args } => {
+                for (position, arg) in args.iter().enumerate() {
+                    let arg = self.infer(arg, columns, aliases, summary)?;
+                    let known = !matches!(arg, ValueType::Null | ValueType::Unknown);
+                    if name.requires_string() && known && arg != ValueType::String {
+                        return Err(DBError::Planner(format!(
+                            "{} 只能用于字符串，不能用于 {}",
+                            name, arg
+                        )));
+                    }
+                    if *name == ScalarFunction::Substr
+                        && position > 0
+                        && known
+                        && arg != ValueType::Int
+                    {
+                        return Err(DBError::Planner(format!(
+                            "{} 的位置和长度必须是整数，实际为 {}",
+                            name, arg
+                        )));
+                    }
+                }
+                Ok(match name {
+                    ScalarFunction::Length => ValueType::Int,
+                    _ => ValueType::String,
+                })
+            }
+            // 子查询结果的类型要执行后才知道
+            Expression::Subquery(plan) => {
+                self.check_subquery(plan, summary)?;
+                Ok(ValueType::Unknown)
+            }
+        }
+    }
+}
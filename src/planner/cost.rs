@@ -0,0 +1,342 @@
+use crate::session::SqlMode;
+use crate::storage::table::{PrimaryKeyStats, Value};
+
+/// 默认的索引选择阈值：估计命中比例低于该值时才走索引
+pub const DEFAULT_INDEX_SELECTIVITY_THRESHOLD: f64 = 0.2;
+
+/// 无法插值时（如字符串范围）使用的默认范围选择率
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// 列统计信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    /// 表中总行数
+    pub row_count: usize,
+    /// 不同非 NULL 值的数量
+    pub distinct_count: usize,
+    /// NULL 值的数量
+    pub null_count: usize,
+    /// 最小非 NULL 值
+    pub min: Option<Value>,
+    /// 最大非 NULL 值
+    pub max: Option<Value>,
+}
+
+impl From<PrimaryKeyStats> for ColumnStatistics {
+    /// 主键唯一且不为 NULL，不同值的数量等于行数
+    fn from(stats: PrimaryKeyStats) -> Self {
+        Self {
+            row_count: stats.key_count,
+            distinct_count: stats.key_count,
+            null_count: 0,
+            min: stats.min,
+            max: stats.max,
+        }
+    }
+}
+
+/// 索引列上的谓词形式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// col = value
+    Equal(Value),
+    /// low <= col <= high，任一端可缺省
+    Range {
+        low: Option<Value>,
+        high: Option<Value>,
+    },
+}
+
+/// 访问路径
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPath {
+    IndexLookup,
+    FullScan,
+}
+
+/// 访问路径提示（用于调试时强制某一路径）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccessPathHint {
+    #[default]
+    Auto,
+    ForceIndex,
+    ForceScan,
+}
+
+/// 代价估算结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessEstimate {
+    /// 估计的命中比例（0.0 ~ 1.0）
+    pub selectivity: f64,
+    /// 选择的访问路径
+    pub path: AccessPath,
+}
+
+/// 基于列统计信息的简单代价估算器
+#[derive(Debug, Clone)]
+pub struct CostEstimator {
+    threshold: f64,
+    hint: AccessPathHint,
+}
+
+impl Default for CostEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_INDEX_SELECTIVITY_THRESHOLD)
+    }
+}
+
+impl CostEstimator {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold: threshold.clamp(0.0, 1.0),
+            hint: AccessPathHint::Auto,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: AccessPathHint) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// 估算谓词的命中比例
+    ///
+    /// 等值谓词使用 1/ndv，范围谓词在 [min, max] 之间线性插值；
+    /// NULL 永远不满足比较谓词，因此结果会乘以非 NULL 行所占的比例。
+    pub fn estimate_selectivity(&self, stats: &ColumnStatistics, predicate: &Predicate) -> f64 {
+        if stats.row_count == 0 {
+            return 0.0;
+        }
+
+        let non_null_fraction =
+            stats.row_count.saturating_sub(stats.null_count) as f64 / stats.row_count as f64;
+
+        let fraction = match predicate {
+            Predicate::Equal(Value::Null) => 0.0,
+            Predicate::Equal(value) => {
+                if Self::outside_bounds(stats, value) || stats.distinct_count == 0 {
+                    0.0
+                } else {
+                    1.0 / stats.distinct_count as f64
+                }
+            }
+            Predicate::Range { low, high } => Self::range_fraction(stats, low, high),
+        };
+
+        (fraction * non_null_fraction).clamp(0.0, 1.0)
+    }
+
+    /// 根据估算结果和阈值选择访问路径
+    pub fn choose(&self, stats: &ColumnStatistics, predicate: &Predicate) -> AccessEstimate {
+        let selectivity = self.estimate_selectivity(stats, predicate);
+        let path = match self.hint {
+            AccessPathHint::ForceIndex => AccessPath::IndexLookup,
+            AccessPathHint::ForceScan => AccessPath::FullScan,
+            AccessPathHint::Auto if selectivity < self.threshold => AccessPath::IndexLookup,
+            AccessPathHint::Auto => AccessPath::FullScan,
+        };
+
+        AccessEstimate { selectivity, path }
+    }
+
+    fn outside_bounds(stats: &ColumnStatistics, value: &Value) -> bool {
+        let below = stats
+            .min
+            .as_ref()
+            .is_some_and(|min| value.lt(min, SqlMode::Lenient).unwrap_or(false));
+        let above = stats
+            .max
+            .as_ref()
+            .is_some_and(|max| value.gt(max, SqlMode::Lenient).unwrap_or(false));
+        below || above
+    }
+
+    fn range_fraction(stats: &ColumnStatistics, low: &Option<Value>, high: &Option<Value>) -> f64 {
+        let (Some(min), Some(max)) = (
+            stats.min.as_ref().and_then(as_f64),
+            stats.max.as_ref().and_then(as_f64),
+        ) else {
+            return DEFAULT_RANGE_SELECTIVITY;
+        };
+
+        let low = match low {
+            Some(value) => match as_f64(value) {
+                Some(v) => v.max(min),
+                None => return DEFAULT_RANGE_SELECTIVITY,
+            },
+            None => min,
+        };
+        let high = match high {
+            Some(value) => match as_f64(value) {
+                Some(v) => v.min(max),
+                None => return DEFAULT_RANGE_SELECTIVITY,
+            },
+            None => max,
+        };
+
+        if low > high {
+            return 0.0;
+        }
+        if max == min {
+            return 1.0;
+        }
+
+        (high - low) / (max - min)
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int_stats(row_count: usize, distinct_count: usize, min: i32, max: i32) -> ColumnStatistics {
+        ColumnStatistics {
+            row_count,
+            distinct_count,
+            null_count: 0,
+            min: Some(Value::Int(min)),
+            max: Some(Value::Int(max)),
+        }
+    }
+
+    #[test]
+    fn test_equality_uses_distinct_count() {
+        let estimator = CostEstimator::default();
+
+        // 主键：每个值唯一，选择率极低
+        let unique = int_stats(1000, 1000, 1, 1000);
+        let estimate = estimator.choose(&unique, &Predicate::Equal(Value::Int(42)));
+        assert!((estimate.selectivity - 0.001).abs() < 1e-9);
+        assert_eq!(estimate.path, AccessPath::IndexLookup);
+
+        // 只有两个取值的状态列：命中一半，应该走全表扫描
+        let skewed = ColumnStatistics {
+            row_count: 1000,
+            distinct_count: 2,
+            null_count: 0,
+            min: Some(Value::String("active".to_string())),
+            max: Some(Value::String("inactive".to_string())),
+        };
+        let estimate = estimator.choose(
+            &skewed,
+            &Predicate::Equal(Value::String("active".to_string())),
+        );
+        assert!((estimate.selectivity - 0.5).abs() < 1e-9);
+        assert_eq!(estimate.path, AccessPath::FullScan);
+    }
+
+    #[test]
+    fn test_equality_outside_bounds_matches_nothing() {
+        let estimator = CostEstimator::default();
+        let stats = int_stats(100, 10, 10, 20);
+
+        assert_eq!(
+            estimator.estimate_selectivity(&stats, &Predicate::Equal(Value::Int(5))),
+            0.0
+        );
+        assert_eq!(
+            estimator.estimate_selectivity(&stats, &Predicate::Equal(Value::Null)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_range_interpolation() {
+        let estimator = CostEstimator::default();
+        let stats = int_stats(1000, 1000, 0, 100);
+
+        let narrow = Predicate::Range {
+            low: Some(Value::Int(10)),
+            high: Some(Value::Int(20)),
+        };
+        let estimate = estimator.choose(&stats, &narrow);
+        assert!((estimate.selectivity - 0.1).abs() < 1e-9);
+        assert_eq!(estimate.path, AccessPath::IndexLookup);
+
+        let open_ended = Predicate::Range {
+            low: Some(Value::Int(50)),
+            high: None,
+        };
+        let estimate = estimator.choose(&stats, &open_ended);
+        assert!((estimate.selectivity - 0.5).abs() < 1e-9);
+        assert_eq!(estimate.path, AccessPath::FullScan);
+
+        let empty = Predicate::Range {
+            low: Some(Value::Int(200)),
+            high: None,
+        };
+        assert_eq!(estimator.estimate_selectivity(&stats, &empty), 0.0);
+    }
+
+    #[test]
+    fn test_nulls_reduce_selectivity() {
+        let estimator = CostEstimator::default();
+        let stats = ColumnStatistics {
+            row_count: 100,
+            distinct_count: 1,
+            null_count: 50,
+            min: Some(Value::Int(1)),
+            max: Some(Value::Int(1)),
+        };
+
+        let selectivity = estimator.estimate_selectivity(&stats, &Predicate::Equal(Value::Int(1)));
+        assert!((selectivity - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_threshold_and_hint() {
+        let stats = int_stats(1000, 1000, 0, 100);
+        let predicate = Predicate::Range {
+            low: Some(Value::Int(0)),
+            high: Some(Value::Int(30)),
+        };
+
+        assert_eq!(
+            CostEstimator::default().choose(&stats, &predicate).path,
+            AccessPath::FullScan
+        );
+        assert_eq!(
+            CostEstimator::new(0.5).choose(&stats, &predicate).path,
+            AccessPath::IndexLookup
+        );
+        assert_eq!(
+            CostEstimator::default()
+                .with_hint(AccessPathHint::ForceIndex)
+                .choose(&stats, &predicate)
+                .path,
+            AccessPath::IndexLookup
+        );
+        assert_eq!(
+            CostEstimator::new(0.5)
+                .with_hint(AccessPathHint::ForceScan)
+                .choose(&stats, &predicate)
+                .path,
+            AccessPath::FullScan
+        );
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let stats = ColumnStatistics {
+            row_count: 0,
+            distinct_count: 0,
+            null_count: 0,
+            min: None,
+            max: None,
+        };
+        let estimate = CostEstimator::default().choose(&stats, &Predicate::Equal(Value::Int(1)));
+        assert_eq!(estimate.selectivity, 0.0);
+        assert_eq!(estimate.path, AccessPath::IndexLookup);
+    }
+}
@@ -0,0 +1,215 @@
+//! 标量字符串函数：UPPER、LOWER、LENGTH、CONCAT、SUBSTR、TRIM
+
+use crate::error::{DBError, Result};
+use crate::storage::table::Value;
+
+/// 标量函数，逐行求值
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarFunction {
+    Upper,
+    Lower,
+    /// 字符个数（不是字节数）
+    Length,
+    /// 任一参数为 NULL 时结果为 NULL，非字符串参数按文本拼接
+    Concat,
+    /// `SUBSTR(str, start[, len])`，位置从 1 开始，负数从末尾倒数
+    Substr,
+    /// 去掉两端的空格
+    Trim,
+}
+
+impl ScalarFunction {
+    /// 按函数名（不区分大小写）查找，SUBSTR 和 TRIM 由专门的语法产生，不在这里查找
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "UPPER" | "UCASE" => Some(ScalarFunction::Upper),
+            "LOWER" | "LCASE" => Some(ScalarFunction::Lower),
+            "LENGTH" | "CHAR_LENGTH" => Some(ScalarFunction::Length),
+            "CONCAT" => Some(ScalarFunction::Concat),
+            _ => None,
+        }
+    }
+
+    /// 检查参数个数
+    pub fn check_arity(self, count: usize) -> Result<()> {
+        let ok = match self {
+            ScalarFunction::Concat => count >= 1,
+            ScalarFunction::Substr => (2..=3).contains(&count),
+            _ => count == 1,
+        };
+        if ok {
+            return Ok(());
+        }
+        let expected = match self {
+            ScalarFunction::Concat => "至少 1 个",
+            ScalarFunction::Substr => "2 或 3 个",
+            _ => "1 个",
+        };
+        Err(DBError::Planner(format!(
+            "函数 {} 需要{}参数，实际为 {} 个",
+            self, expected, count
+        )))
+    }
+
+    /// 参数是否必须是字符串，其余函数把非字符串参数按文本处理
+    pub fn requires_string(self) -> bool {
+        matches!(
+            self,
+            ScalarFunction::Upper | ScalarFunction::Lower | ScalarFunction::Trim
+        )
+    }
+
+    /// 对已求值的参数调用函数
+    pub fn apply(self, args: &[Value]) -> Result<Value> {
+        if args.iter().any(|arg| matches!(arg, Value::Null)) {
+            return Ok(Value::Null);
+        }
+        match self {
+            ScalarFunction::Upper => Ok(Value::String(self.string_arg(&args[0])?.to_uppercase())),
+            ScalarFunction::Lower => Ok(Value::String(self.string_arg(&args[0])?.to_lowercase())),
+            ScalarFunction::Trim => Ok(Value::String(
+                self.string_arg(&args[0])?.trim_matches(' ').to_string(),
+            )),
+            ScalarFunction::Length => Ok(Value::Int(args[0].to_string().chars().count() as i32)),
+            ScalarFunction::Concat => Ok(Value::String(
+                args.iter().map(|arg| arg.to_string()).collect(),
+            )),
+            ScalarFunction::Substr => {
+                let text = args[0].to_string();
+                let start = self.int_arg(&args[1])?;
+                let len = args.get(2).map(|len| self.int_arg(len)).transpose()?;
+                Ok(Value::String(substr(&text, start, len)))
+            }
+        }
+    }
+
+    fn string_arg(self, value: &Value) -> Result<&str> {
+        match value {
+            Value::String(s) => Ok(s),
+            other => Err(DBError::Execution(format!(
+                "{} 只能用于字符串，不能用于 {}",
+                self,
+                other.type_name()
+            ))),
+        }
+    }
+
+    fn int_arg(self, value: &Value) -> Result<i32> {
+        match value {
+            Value::Int(i) => Ok(*i),
+            other => Err(DBError::Execution(format!(
+                "{} 的位置和长度必须是整数，实际为 {}",
+                self,
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for ScalarFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ScalarFunction::Upper => "UPPER",
+            ScalarFunction::Lower => "LOWER",
+            ScalarFunction::Length => "LENGTH",
+            ScalarFunction::Concat => "CONCAT",
+            ScalarFunction::Substr => "SUBSTR",
+            ScalarFunction::Trim => "TRIM",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 按字符截取子串；位置为 0、超出范围或长度不是正数时得到空串
+fn substr(text: &str, start: i32, len: Option<i32>) -> String {
+    let count = text.chars().count() as i64;
+    let start = match start as i64 {
+        0 => return String::new(),
+        s if s > 0 => s - 1,
+        s => count + s,
+    };
+    if start < 0 || start >= count {
+        return String::new();
+    }
+    let take = match len {
+        Some(len) if len <= 0 => return String::new(),
+        Some(len) => len as usize,
+        None => usize::MAX,
+    };
+    text.chars().skip(start as usize).take(take).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn test_string_functions() {
+        let apply = |func: ScalarFunction, args: &[Value]| func.apply(args).unwrap();
+
+        assert_eq!(
+            apply(ScalarFunction::Upper, &[string("Straße")]),
+            string("STRASSE")
+        );
+        assert_eq!(
+            apply(ScalarFunction::Lower, &[string("AbC")]),
+            string("abc")
+        );
+        assert_eq!(
+            apply(ScalarFunction::Length, &[string("数据库")]),
+            Value::Int(3)
+        );
+        assert_eq!(
+            apply(ScalarFunction::Length, &[Value::Int(-12)]),
+            Value::Int(3)
+        );
+        assert_eq!(
+            apply(ScalarFunction::Trim, &[string("  a b ")]),
+            string("a b")
+        );
+        assert_eq!(
+            apply(
+                ScalarFunction::Concat,
+                &[string("id-"), Value::Int(7), Value::Float(1.5)]
+            ),
+            string("id-71.5")
+        );
+        assert_eq!(
+            apply(ScalarFunction::Concat, &[string("a"), Value::Null]),
+            Value::Null
+        );
+
+        // NULL 不报类型错误，其它非字符串参数报错
+        assert_eq!(apply(ScalarFunction::Upper, &[Value::Null]), Value::Null);
+        let err = ScalarFunction::Upper.apply(&[Value::Int(1)]).unwrap_err();
+        assert_eq!(err.to_string(), "UPPER 只能用于字符串，不能用于 INT");
+
+        assert!(ScalarFunction::Concat.check_arity(0).is_err());
+        assert!(ScalarFunction::Substr.check_arity(1).is_err());
+        assert!(ScalarFunction::Substr.check_arity(3).is_ok());
+        assert!(ScalarFunction::Lower.check_arity(2).is_err());
+    }
+
+    #[test]
+    fn test_substr_bounds() {
+        let cases = [
+            (1, None, "héllo"),
+            (2, Some(3), "éll"),
+            (4, Some(10), "lo"),
+            (-3, Some(2), "ll"),
+            (-5, None, "héllo"),
+            (0, None, ""),
+            (6, None, ""),
+            (-6, None, ""),
+            (2, Some(0), ""),
+            (2, Some(-1), ""),
+        ];
+        for (start, len, expected) in cases {
+            assert_eq!(substr("héllo", start, len), expected, "{} {:?}", start, len);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::cost::Predicate;
+use crate::storage::table::Value;
+
+/// LIKE 的默认转义字符
+pub const DEFAULT_LIKE_ESCAPE: char = '\\';
+
+/// 编译后的模式片段
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 普通字符（包括被转义的通配符）
+    Literal(char),
+    /// `_`：任意单个字符
+    AnyChar,
+    /// `%`：任意长度（可为空）的字符序列
+    AnyString,
+}
+
+/// LIKE / ILIKE 模式
+///
+/// `escape` 为 `None` 时不支持转义；转义字符位于模式末尾时按普通字符处理。
+/// ILIKE 目前只做 ASCII 大小写折叠。
+#[derive(Debug, Clone, PartialEq)]
+pub struct LikePattern {
+    tokens: Vec<Token>,
+    case_insensitive: bool,
+}
+
+impl LikePattern {
+    pub fn new(pattern: &str, escape: Option<char>, case_insensitive: bool) -> Self {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                c if Some(c) == escape => Token::Literal(chars.next().unwrap_or(c)),
+                '%' => Token::AnyString,
+                '_' => Token::AnyChar,
+                c => Token::Literal(c),
+            };
+            // 连续的 % 等价于一个
+            if token == Token::AnyString && tokens.last() == Some(&Token::AnyString) {
+                continue;
+            }
+            tokens.push(token);
+        }
+
+        Self {
+            tokens,
+            case_insensitive,
+        }
+    }
+
+    /// 判断文本是否匹配模式
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let (mut t, mut p) = (0, 0);
+        // 最近一次 % 的位置及其当时对应的文本位置，用于回溯
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while t < text.len() {
+            match self.tokens.get(p) {
+                Some(Token::AnyString) => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                Some(Token::AnyChar) => {
+                    t += 1;
+                    p += 1;
+                    continue;
+                }
+                Some(Token::Literal(c)) if self.char_eq(*c, text[t]) => {
+                    t += 1;
+                    p += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, t));
+                }
+                None => return false,
+            }
+        }
+
+        self.tokens[p..]
+            .iter()
+            .all(|token| *token == Token::AnyString)
+    }
+
+    /// 模式开头不含通配符的字面量前缀
+    pub fn literal_prefix(&self) -> String {
+        self.tokens
+            .iter()
+            .map_while(|token| match token {
+                Token::Literal(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 前缀索引快速路径可用的范围谓词
+    ///
+    /// 只有区分大小写的 LIKE 才能转成 `prefix <= col <= prefix + U+10FFFF` 形式的范围；
+    /// ILIKE 需要大小写不敏感的列排序规则，在此之前一律返回 `None`。
+    pub fn prefix_range(&self) -> Option<Predicate> {
+        if self.case_insensitive {
+            return None;
+        }
+        let prefix = self.literal_prefix();
+        if prefix.is_empty() {
+            return None;
+        }
+        let high = format!("{}{}", prefix, char::MAX);
+        Some(Predicate::Range {
+            low: Some(Value::String(prefix)),
+            high: Some(Value::String(high)),
+        })
+    }
+
+    fn char_eq(&self, pattern: char, text: char) -> bool {
+        if self.case_insensitive {
+            pattern.eq_ignore_ascii_case(&text)
+        } else {
+            pattern == text
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn like(pattern: &str, text: &str) -> bool {
+        LikePattern::new(pattern, Some(DEFAULT_LIKE_ESCAPE), false).matches(text)
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(like("a%", "abc"));
+        assert!(like("%c", "abc"));
+        assert!(like("%b%", "abc"));
+        assert!(like("a_c", "abc"));
+        assert!(like("%", ""));
+        assert!(like("%%a%%", "banana"));
+        assert!(like("%an_", "banana"));
+        assert!(!like("a_c", "abbc"));
+        assert!(!like("abc", "ABC"));
+        assert!(!like("_", ""));
+        assert!(like("数据_", "数据库"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert!(like("50\\%%", "50% off"));
+        assert!(!like("50\\%%", "500 off"));
+        assert!(like("a\\_b", "a_b"));
+        assert!(!like("a\\_b", "axb"));
+        // 末尾的转义字符按普通字符处理
+        assert!(like("a\\", "a\\"));
+
+        let custom = LikePattern::new("10!%", Some('!'), false);
+        assert!(custom.matches("10%"));
+        assert!(!custom.matches("100"));
+        // 自定义转义字符后，反斜杠就是普通字符
+        assert!(LikePattern::new("a\\%", Some('!'), false).matches("a\\bc"));
+        // 没有转义字符
+        assert!(LikePattern::new("a\\%", None, false).matches("a\\x"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let pattern = LikePattern::new("ab%", Some(DEFAULT_LIKE_ESCAPE), true);
+        assert!(pattern.matches("ABC"));
+        assert!(pattern.matches("aBxyz"));
+        assert!(!pattern.matches("xab"));
+    }
+
+    #[test]
+    fn test_prefix_range() {
+        let pattern = LikePattern::new("ab\\%c%d", Some(DEFAULT_LIKE_ESCAPE), false);
+        assert_eq!(pattern.literal_prefix(), "ab%c");
+        assert_eq!(
+            pattern.prefix_range(),
+            Some(Predicate::Range {
+                low: Some(Value::String("ab%c".to_string())),
+                high: Some(Value::String(format!("ab%c{}", char::MAX))),
+            })
+        );
+        assert_eq!(LikePattern::new("%ab", None, false).prefix_range(), None);
+        // ILIKE 不能走前缀索引
+        assert_eq!(LikePattern::new("ab%", None, true).prefix_range(), None);
+    }
+}
@@ -0,0 +1,612 @@
+use super::{
+    Condition, Expression, InSet, InsertSource, OrderByItem, Plan, SelectColumns, SortTarget,
+    find_column, validate,
+};
+use crate::session::SqlMode;
+use crate::storage::table::{ColumnDef, Record, Value};
+use std::collections::BTreeSet;
+
+/// 执行前对查询计划做基于规则的改写
+pub fn optimize(plan: Plan) -> Plan {
+    match plan {
+        Plan::Explain(inner) => Plan::Explain(Box::new(optimize(*inner))),
+        plan @ Plan::Union { .. } => push_down_union_limit(plan),
+        Plan::Insert {
+            table_name,
+            columns,
+            source: InsertSource::Select(select),
+            ignore,
+            returning,
+        } => Plan::Insert {
+            table_name,
+            columns,
+            source: InsertSource::Select(Box::new(optimize(*select))),
+            ignore,
+            returning,
+        },
+        Plan::Update {
+            table_name,
+            set_pairs,
+            conditions,
+        } => Plan::Update {
+            table_name,
+            set_pairs: set_pairs
+                .into_iter()
+                .map(|(column, value)| (column, fold_expression(value)))
+                .collect(),
+            conditions: fold_where(conditions),
+        },
+        Plan::Delete {
+            table_name,
+            conditions,
+        } => Plan::Delete {
+            table_name,
+            conditions: fold_where(conditions),
+        },
+        other => other,
+    }
+}
+
+/// 化简 WHERE 条件：恒真的条件去掉，走无条件的路径；恒假的条件化简为
+/// `Constant(false)`，执行器据此不访问表直接返回
+fn fold_where(conditions: Option<Condition>) -> Option<Condition> {
+    match conditions.map(simplify_condition) {
+        Some(Condition::Constant(true)) => None,
+        other => other,
+    }
+}
+
+/// 化简条件树中只含字面量的部分
+///
+/// 字面量子表达式按逐行求值的规则预先求值；`FALSE AND x` 化简为假，`TRUE OR x` 化简为真，
+/// `TRUE AND x`、`FALSE OR x` 化简为 `x`。引用列的部分保持原样。
+pub fn simplify_condition(condition: Condition) -> Condition {
+    match condition {
+        Condition::Expression(expr) => match fold_expression(expr) {
+            Expression::Value(Value::Boolean(b)) => Condition::Constant(b),
+            expr => Condition::Expression(expr),
+        },
+        Condition::IsNull(expr) => match fold_expression(expr) {
+            Expression::Value(value) => Condition::Constant(value.is_null()),
+            expr => Condition::IsNull(expr),
+        },
+        Condition::IsNotNull(expr) => match fold_expression(expr) {
+            Expression::Value(value) => Condition::Constant(!value.is_null()),
+            expr => Condition::IsNotNull(expr),
+        },
+        Condition::Like {
+            expr,
+            pattern,
+            escape,
+            case_insensitive,
+            negated,
+        } => Condition::Like {
+            expr: fold_expression(expr),
+            pattern: fold_expression(pattern),
+            escape,
+            case_insensitive,
+            negated,
+        },
+        Condition::In { expr, set, negated } => Condition::In {
+            expr: fold_expression(expr),
+            set: match set {
+                InSet::Subquery(plan) => InSet::Subquery(Box::new(optimize(*plan))),
+                values => values,
+            },
+            negated,
+        },
+        Condition::And(left, right) => {
+            match (simplify_condition(*left), simplify_condition(*right)) {
+                (Condition::Constant(false), _) | (_, Condition::Constant(false)) => {
+                    Condition::Constant(false)
+                }
+                (Condition::Constant(true), other) | (other, Condition::Constant(true)) => other,
+                (left, right) => Condition::And(Box::new(left), Box::new(right)),
+            }
+        }
+        Condition::Or(left, right) => {
+            match (simplify_condition(*left), simplify_condition(*right)) {
+                (Condition::Constant(true), _) | (_, Condition::Constant(true)) => {
+                    Condition::Constant(true)
+                }
+                (Condition::Constant(false), other) | (other, Condition::Constant(false)) => other,
+                (left, right) => Condition::Or(Box::new(left), Box::new(right)),
+            }
+        }
+        Condition::Not(inner) => match simplify_condition(*inner) {
+            Condition::Constant(b) => Condition::Constant(!b),
+            inner => Condition::Not(Box::new(inner)),
+        },
+        Condition::IsTruth {
+            operand,
+            value,
+            negated,
+        } => match simplify_condition(*operand) {
+            Condition::Constant(b) => Condition::Constant((b == value) != negated),
+            operand => Condition::IsTruth {
+                operand: Box::new(operand),
+                value,
+                negated,
+            },
+        },
+        constant @ Condition::Constant(_) => constant,
+    }
+}
+
+/// 自底向上把只含字面量的运算替换为它的值
+pub fn fold_expression(expr: Expression) -> Expression {
+    match expr {
+        Expression::Binary {
+            left,
+            operator,
+            right,
+        } => evaluate_literal(Expression::Binary {
+            left: Box::new(fold_expression(*left)),
+            operator,
+            right: Box::new(fold_expression(*right)),
+        }),
+        Expression::Unary { operator, operand } => evaluate_literal(Expression::Unary {
+            operator,
+            operand: Box::new(fold_expression(*operand)),
+        }),
+        Expression::Function { name, args } => evaluate_literal(Expression::Function {
+            name,
+            args: args.into_iter().map(fold_expression).collect(),
+        }),
+        Expression::Subquery(plan) => Expression::Subquery(Box::new(optimize(*plan))),
+        other => other,
+    }
+}
+
+/// 操作数都已是字面量时求值；求值出错（如除以零）时保持原样，错误留到执行时报告
+///
+/// 按严格模式求值：布尔值与数值的比较在宽松模式下才成立，留到执行时按会话的模式求值。
+fn evaluate_literal(expr: Expression) -> Expression {
+    let literal = match &expr {
+        Expression::Binary { left, right, .. } => {
+            matches!((&**left, &**right), (Expression::Value(_), Expression::Value(_)))
+        }
+        Expression::Unary { operand, .. } => matches!(**operand, Expression::Value(_)),
+        Expression::Function { args, .. } => {
+            args.iter().all(|arg| matches!(arg, Expression::Value(_)))
+        }
+        _ => false,
+    };
+    if !literal {
+        return expr;
+    }
+    match expr.evaluate(&Record::new(Vec::new()), &[], SqlMode::Strict) {
+        Ok(value) => Expression::Value(value),
+        Err(_) => expr,
+    }
+}
+
+/// 把 UNION ALL 外层的 ORDER BY + LIMIT 复制到各个分支
+///
+/// 合并结果按同样顺序的前 `limit + offset` 行一定分别来自各分支自己的前
+/// `limit + offset` 行，所以每个分支只需取这么多行，外层仍然重新排序和截取。
+/// UNION 要先去重再截取，不能下推。排序列在分支中不是简单列引用，
+/// 或分支自带 ORDER BY / LIMIT 时，该分支保持原样。
+pub fn push_down_union_limit(plan: Plan) -> Plan {
+    let Plan::Union {
+        left,
+        right,
+        all,
+        order_by,
+        limit,
+        offset,
+    } = plan
+    else {
+        return plan;
+    };
+
+    let (mut left, mut right) = (*left, *right);
+    if all
+        && let (Some(items), Some(limit)) = (&order_by, limit)
+        && let Some(positions) = output_names(&left).and_then(|names| positions(items, &names))
+    {
+        let branch_limit = limit.saturating_add(offset.unwrap_or(0));
+        left = push_into_branch(left, items, &positions, branch_limit);
+        right = push_into_branch(right, items, &positions, branch_limit);
+    }
+
+    // 分支本身（或刚下推到的）UNION ALL 可以继续向下推
+    Plan::Union {
+        left: Box::new(push_down_union_limit(left)),
+        right: Box::new(push_down_union_limit(right)),
+        all,
+        order_by,
+        limit,
+        offset,
+    }
+}
+
+/// 查询结果的列名，`SELECT *` 在执行前无法确定，返回 None
+fn output_names(plan: &Plan) -> Option<Vec<String>> {
+    match plan {
+        Plan::Select {
+            columns: SelectColumns::Columns(items),
+            ..
+        } => Some(
+            items
+                .iter()
+                .map(|item| item.alias.clone().unwrap_or_else(|| item.original_text.clone()))
+                .collect(),
+        ),
+        Plan::Union { left, .. } => output_names(left),
+        _ => None,
+    }
+}
+
+/// 每个排序项对应的结果列位置
+fn positions(items: &[OrderByItem], names: &[String]) -> Option<Vec<usize>> {
+    items
+        .iter()
+        .map(|item| validate::result_sort_position(names, &item.target).ok().flatten())
+        .collect()
+}
+
+/// 按结果列位置把排序项改写为分支自己的列名，再设置分支的排序和行数
+fn push_into_branch(
+    branch: Plan,
+    items: &[OrderByItem],
+    positions: &[usize],
+    branch_limit: u64,
+) -> Plan {
+    match branch {
+        Plan::Select {
+            table_name: Some(table_name),
+            columns: SelectColumns::Columns(select_items),
+            distinct,
+            conditions,
+            order_by: None,
+            table_alias,
+            joins,
+            group_by,
+            having,
+            limit: None,
+            offset: None,
+            into: None,
+        } if group_by.is_empty() && having.is_none() => {
+            let rewritten: Option<Vec<OrderByItem>> = items
+                .iter()
+                .zip(positions)
+                .map(|(item, &position)| match &select_items.get(position)?.expr {
+                    Expression::Column(column) => Some(OrderByItem {
+                        target: SortTarget::Column(column.clone()),
+                        ..item.clone()
+                    }),
+                    _ => None,
+                })
+                .collect();
+            let limit = rewritten.is_some().then_some(branch_limit);
+            Plan::Select {
+                table_name: Some(table_name),
+                columns: SelectColumns::Columns(select_items),
+                distinct,
+                conditions,
+                order_by: rewritten,
+                table_alias,
+                joins,
+                group_by,
+                having,
+                limit,
+                offset: None,
+                into: None,
+            }
+        }
+        Plan::Union {
+            left,
+            right,
+            all,
+            order_by: None,
+            limit: None,
+            offset: None,
+        } => {
+            let rewritten: Option<Vec<OrderByItem>> = output_names(&left).and_then(|names| {
+                items
+                    .iter()
+                    .zip(positions)
+                    .map(|(item, &position)| {
+                        Some(OrderByItem {
+                            target: SortTarget::Column(names.get(position)?.clone()),
+                            ..item.clone()
+                        })
+                    })
+                    .collect()
+            });
+            let limit = rewritten.is_some().then_some(branch_limit);
+            Plan::Union {
+                left,
+                right,
+                all,
+                order_by: rewritten,
+                limit,
+                offset: None,
+            }
+        }
+        other => other,
+    }
+}
+
+/// 单表 SELECT 实际用到的列在表中的位置（升序），执行器只读取这些列
+///
+/// 选择列、WHERE、GROUP BY、HAVING 和 ORDER BY 引用的列都算在内，HAVING 和 ORDER BY
+/// 中的选择列别名由选择列本身覆盖。`SELECT *`、带 JOIN 的查询、找不到或有歧义的列名
+/// 都返回 None，按完整的记录执行，结果和报错与不裁剪时相同；用到全部列时也返回 None。
+pub fn required_columns(plan: &Plan, table_columns: &[ColumnDef]) -> Option<Vec<usize>> {
+    let Plan::Select {
+        columns: SelectColumns::Columns(items),
+        conditions,
+        order_by,
+        joins,
+        group_by,
+        having,
+        ..
+    } = plan
+    else {
+        return None;
+    };
+    if !joins.is_empty() {
+        return None;
+    }
+
+    let mut names = Vec::new();
+    for item in items {
+        names.extend(item.expr.column_references());
+    }
+    for expr in group_by {
+        names.extend(expr.column_references());
+    }
+    for condition in [conditions, having].into_iter().flatten() {
+        names.extend(condition.column_references());
+    }
+    for item in order_by.iter().flatten() {
+        match &item.target {
+            SortTarget::Column(name) => names.push(name),
+            SortTarget::Expression { expr, .. } => names.extend(expr.column_references()),
+            SortTarget::Position(_) => {}
+        }
+    }
+
+    let mut positions = BTreeSet::new();
+    for name in names {
+        match find_column(table_columns, name) {
+            Ok(Some(position)) => {
+                positions.insert(position);
+            }
+            Ok(None)
+                if items
+                    .iter()
+                    .filter_map(|item| item.alias.as_deref())
+                    .any(|alias| alias.eq_ignore_ascii_case(name)) => {}
+            _ => return None,
+        }
+    }
+    (positions.len() < table_columns.len()).then(|| positions.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::Planner;
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+
+    fn plan(sql: &str) -> Plan {
+        let ast = Parser::parse_sql(&MySqlDialect {}, sql).unwrap();
+        Planner::new().plan(&ast[0]).unwrap()
+    }
+
+    fn explain(sql: &str) -> Vec<String> {
+        optimize(plan(sql)).explain()
+    }
+
+    #[test]
+    fn test_push_down_into_union_all_branches() {
+        assert_eq!(
+            explain(
+                "SELECT id, created_at FROM a UNION ALL \
+                 SELECT id, ts AS created_at FROM b WHERE id > 1 \
+                 ORDER BY created_at DESC LIMIT 50 OFFSET 10"
+            ),
+            vec![
+                "Union ALL",
+                "  ORDER BY created_at DESC NULLS LAST",
+                "  LIMIT 50 OFFSET 10",
+                "  Select FROM a",
+                "    COLUMNS id, created_at",
+                "    ORDER BY created_at DESC NULLS LAST",
+                "    LIMIT 60",
+                "  Select FROM b",
+                "    COLUMNS id, ts AS created_at",
+                "    WHERE id > 1",
+                // 按位置改写为分支自己的列名
+                "    ORDER BY ts DESC NULLS LAST",
+                "    LIMIT 60",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_push_down_through_nested_unions() {
+        // (a UNION ALL b) UNION ALL c：内层 UNION ALL 及其分支都被限制
+        let lines = explain(
+            "SELECT x FROM a UNION ALL SELECT x FROM b UNION ALL SELECT x FROM c \
+             ORDER BY x LIMIT 5",
+        );
+        assert_eq!(lines.iter().filter(|l| l.trim() == "LIMIT 5").count(), 5);
+
+        // 内层是 UNION（去重）：内层整体可以限制，但不能再推到它的分支
+        let lines = explain(
+            "SELECT x FROM a UNION SELECT x FROM b UNION ALL SELECT x FROM c \
+             ORDER BY x LIMIT 5",
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "Union ALL",
+                "  ORDER BY x ASC NULLS FIRST",
+                "  LIMIT 5",
+                "  Union DISTINCT",
+                "    ORDER BY x ASC NULLS FIRST",
+                "    LIMIT 5",
+                "    Select FROM a",
+                "      COLUMNS x",
+                "    Select FROM b",
+                "      COLUMNS x",
+                "  Select FROM c",
+                "    COLUMNS x",
+                "    ORDER BY x ASC NULLS FIRST",
+                "    LIMIT 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_push_down_when_unsafe() {
+        let unchanged = [
+            // UNION 去重
+            "SELECT x FROM a UNION SELECT x FROM b ORDER BY x LIMIT 5",
+            // 没有 LIMIT 或没有 ORDER BY
+            "SELECT x FROM a UNION ALL SELECT x FROM b ORDER BY x",
+            "SELECT x FROM a UNION ALL SELECT x FROM b LIMIT 5",
+            // 结果列名未知
+            "SELECT * FROM a UNION ALL SELECT * FROM b ORDER BY x LIMIT 5",
+        ];
+        for sql in unchanged {
+            assert_eq!(explain(sql), plan(sql).explain(), "{}", sql);
+        }
+
+        // 分支的排序列是表达式，或分支自带 LIMIT：只下推到其余分支
+        let lines = explain(
+            "SELECT x FROM a UNION ALL SELECT y + 1 AS x FROM b UNION ALL \
+             (SELECT x FROM c LIMIT 2) ORDER BY x LIMIT 5",
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "Union ALL",
+                "  ORDER BY x ASC NULLS FIRST",
+                "  LIMIT 5",
+                "  Union ALL",
+                "    ORDER BY x ASC NULLS FIRST",
+                "    LIMIT 5",
+                "    Select FROM a",
+                "      COLUMNS x",
+                "      ORDER BY x ASC NULLS FIRST",
+                "      LIMIT 5",
+                "    Select FROM b",
+                "      COLUMNS y + 1 AS x",
+                "  Select FROM c",
+                "    COLUMNS x",
+                "    LIMIT 2",
+            ]
+        );
+    }
+
+    /// DELETE 计划中未经改写的 WHERE 条件
+    fn where_condition(condition: &str) -> Condition {
+        match plan(&format!("DELETE FROM t WHERE {}", condition)) {
+            Plan::Delete {
+                conditions: Some(condition),
+                ..
+            } => condition,
+            other => panic!("预期带条件的 DELETE，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_simplify_condition() {
+        let cases = [
+            ("1 = 0", "FALSE"),
+            ("1 + 1 = 2", "TRUE"),
+            ("NULL IS NULL", "TRUE"),
+            ("1 = 0 AND id = 3", "FALSE"),
+            ("id = 3 AND 1 = 0", "FALSE"),
+            ("1 = 1 AND id = 3", "id = 3"),
+            ("id = 3 OR 2 > 1", "TRUE"),
+            ("1 = 0 OR id = 3", "id = 3"),
+            ("NOT 1 = 0 AND (id > 1 + 1 OR 1 = 0)", "id > 2"),
+            ("(id = 1 OR 1 = 1) AND (name IS NULL AND 1 + 1 = 3)", "FALSE"),
+            ("id = 1 OR (id = 2 AND NOT TRUE)", "id = 1"),
+            ("name LIKE 'a%' AND TRUE", "name LIKE 'a%'"),
+            ("id = -(2 * 3) + 10", "id = 4"),
+            // 求值出错的字面量保持原样，错误留到执行时
+            ("1 / 0 = 1", "1 / 0 = 1"),
+            ("id = 1 AND name IS NOT NULL", "id = 1 AND name IS NOT NULL"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                simplify_condition(where_condition(input)),
+                where_condition(expected),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_fold_where_in_update_and_delete() {
+        let conditions = |sql: &str| match optimize(plan(sql)) {
+            Plan::Update { conditions, .. } | Plan::Delete { conditions, .. } => conditions,
+            other => panic!("预期 UPDATE 或 DELETE，实际为 {:?}", other),
+        };
+        assert_eq!(conditions("UPDATE t SET x = 1 WHERE 1 = 1"), None);
+        assert_eq!(conditions("DELETE FROM t WHERE 1 = 1 OR id = 2"), None);
+        assert_eq!(
+            conditions("DELETE FROM t WHERE 1 = 0 AND id = 3"),
+            Some(Condition::Constant(false))
+        );
+        assert_eq!(
+            conditions("UPDATE t SET x = 1 WHERE id = 3 AND 2 > 1"),
+            Some(where_condition("id = 3"))
+        );
+    }
+
+    #[test]
+    fn test_required_columns() {
+        let table_columns: Vec<ColumnDef> = ["id", "name", "age", "city", "score"]
+            .into_iter()
+            .map(|name| ColumnDef {
+                name: name.to_string(),
+                data_type: crate::storage::table::DataType::Int(None),
+                not_null: false,
+                unique: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
+            })
+            .collect();
+        let cases: [(&str, Option<Vec<usize>>); 9] = [
+            (
+                "SELECT name FROM t WHERE age > 1 ORDER BY city",
+                Some(vec![1, 2, 3]),
+            ),
+            ("SELECT COUNT(*) FROM t", Some(vec![])),
+            // HAVING 和 ORDER BY 中的别名由选择列覆盖
+            (
+                "SELECT city, COUNT(*) AS n FROM t GROUP BY city HAVING n > 1 ORDER BY n",
+                Some(vec![3]),
+            ),
+            (
+                "SELECT UPPER(NAME) AS n FROM t ORDER BY n, 1, score + 1",
+                Some(vec![1, 4]),
+            ),
+            ("SELECT * FROM t", None),
+            ("SELECT id, name, age, city, score FROM t", None),
+            ("SELECT missing FROM t", None),
+            ("SELECT name FROM t ORDER BY missing", None),
+            ("SELECT t.name FROM t JOIN u ON t.id = u.id", None),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(
+                required_columns(&plan(sql), &table_columns),
+                expected,
+                "{}",
+                sql
+            );
+        }
+    }
+}
@@ -0,0 +1,773 @@
+//! 语义检查：不读取数据，只依赖计划本身和表的列定义
+//!
+//! 执行器在执行前调用这些检查，`check` 模块也用它们在不执行的情况下检查整段脚本。
+
+use crate::error::{DBError, Result};
+use crate::planner::{
+    Condition, Expression, OrderByItem, SelectColumns, SelectItem, SortTarget, find_column,
+};
+use crate::session::{QUERY_HISTORY_TABLE, SqlMode};
+use crate::storage::io::page::MAX_RECORD_SIZE;
+use crate::storage::table::{ColumnDef, DataType, Table, Value, column_position, varchar_len};
+
+/// 选择列在聚合语境下的分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionKind {
+    /// 包含聚合函数，且聚合函数之外只引用分组列，如 `COUNT(*)`、`grp + SUM(x)`
+    Aggregate,
+    /// 只引用分组列（或与某个 GROUP BY 表达式完全相同），包括常量
+    Grouped,
+    /// 在聚合函数之外引用了不在 GROUP BY 中的列
+    Plain,
+}
+
+/// 对选择列分类
+pub fn classify(expr: &Expression, group_by: &[Expression]) -> ProjectionKind {
+    if first_ungrouped_column(expr, group_by).is_some() {
+        ProjectionKind::Plain
+    } else if contains_aggregate(expr) {
+        ProjectionKind::Aggregate
+    } else {
+        ProjectionKind::Grouped
+    }
+}
+
+/// SELECT 的语义检查，在执行前运行
+///
+/// 规则：
+/// 1. 聚合函数不能嵌套，GROUP BY 表达式中不能出现聚合函数；
+/// 2. 只要存在聚合函数或 GROUP BY，聚合函数之外引用的列都必须出现在 GROUP BY 中，
+///    此时也不能使用 `SELECT *`；
+/// 3. HAVING 只能引用聚合函数、分组列或选择列的别名；
+/// 4. HAVING 只能用于聚合查询。
+pub fn validate_select(
+    columns: &SelectColumns,
+    group_by: &[Expression],
+    having: Option<&Condition>,
+) -> Result<()> {
+    let items: &[SelectItem] = match columns {
+        SelectColumns::Wildcard => &[],
+        SelectColumns::Columns(items) => items,
+        SelectColumns::Mixed(_) => unreachable!("通配符应该在前面已经展开"),
+    };
+
+    // 规则 1
+    for expr in items.iter().map(|item| &item.expr) {
+        check_nested_aggregate(expr, false)?;
+    }
+    for expr in group_by {
+        if contains_aggregate(expr) {
+            return Err(DBError::Planner(format!(
+                "GROUP BY 中不能使用聚合函数: {}",
+                describe(expr)
+            )));
+        }
+    }
+    if let Some(having) = having {
+        for expr in condition_expressions(having) {
+            check_nested_aggregate(expr, false)?;
+        }
+    }
+
+    // 规则 2
+    if is_aggregate_query(columns, group_by, having) {
+        if matches!(columns, SelectColumns::Wildcard) {
+            return Err(DBError::Planner(
+                "存在聚合函数或 GROUP BY 时不能使用 SELECT *，请列出分组列".to_string(),
+            ));
+        }
+        for item in items {
+            if classify(&item.expr, group_by) == ProjectionKind::Plain
+                && let Some(column) = first_ungrouped_column(&item.expr, group_by)
+            {
+                return Err(DBError::Planner(format!(
+                    "选择列 '{}' 中的列 '{}' 既不在 GROUP BY 中也不在聚合函数内：\
+                     存在聚合函数或 GROUP BY 时，非聚合列必须出现在 GROUP BY 中",
+                    item.original_text, column
+                )));
+            }
+        }
+    }
+
+    // 规则 3、4
+    if let Some(having) = having {
+        if !is_aggregate_query(columns, group_by, Some(having)) {
+            return Err(DBError::Planner(
+                "HAVING 需要与 GROUP BY 或聚合函数一起使用".to_string(),
+            ));
+        }
+        let aliases: Vec<&str> = items
+            .iter()
+            .filter_map(|item| item.alias.as_deref())
+            .collect();
+        for expr in condition_expressions(having) {
+            if let Some(column) = first_ungrouped_column(expr, group_by)
+                && !aliases
+                    .iter()
+                    .any(|alias| alias.eq_ignore_ascii_case(column))
+            {
+                return Err(DBError::Planner(format!(
+                    "HAVING 中的列 '{}' 不是分组列、聚合函数或选择列的别名：\
+                     HAVING 只能引用这三者",
+                    column
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// WHERE 条件的语义检查，在逐行求值之前运行
+///
+/// WHERE 在分组和计算选择列之前逐行求值，因此：
+/// 1. 不能使用聚合函数，对聚合结果的过滤应写在 HAVING 中；
+/// 2. 不能引用选择列的别名（与表中的列同名时取表中的列），应重复别名对应的表达式。
+///
+/// `items` 为 SELECT 的选择列，UPDATE 和 DELETE 传入空切片。其余找不到的列由
+/// [`UnknownColumns`] 一并报告。
+pub fn check_where(
+    condition: &Condition,
+    items: &[SelectItem],
+    table_columns: &[ColumnDef],
+) -> Result<()> {
+    for expr in condition_expressions(condition) {
+        if let Some(aggregate) = first_aggregate(expr) {
+            return Err(DBError::Planner(format!(
+                "WHERE 中不能使用聚合函数 {}：WHERE 在分组之前逐行过滤，\
+                 过滤聚合结果请使用 GROUP BY ... HAVING",
+                aggregate
+            )));
+        }
+    }
+    for name in condition.column_references() {
+        if find_column(table_columns, name)?.is_some() {
+            continue;
+        }
+        let aliased = items.iter().find(|item| {
+            item.alias
+                .as_deref()
+                .is_some_and(|alias| alias.eq_ignore_ascii_case(name))
+        });
+        if let Some(item) = aliased {
+            return Err(DBError::Planner(format!(
+                "WHERE 中不能引用选择列的别名 '{}'：WHERE 在计算选择列之前求值，\
+                 请在 WHERE 中重复它对应的表达式 {}",
+                name, item.original_text
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 语句引用的、在表中找不到的列，按出现顺序记录，同名只记一次
+///
+/// 执行器在读取记录之前收集全部引用再一起报告，空表和非空表得到同样的错误。
+#[derive(Debug, Default)]
+pub struct UnknownColumns(Vec<String>);
+
+impl UnknownColumns {
+    /// 记录一个找不到的列名
+    pub fn add(&mut self, name: &str) {
+        if !self.0.iter().any(|unknown| unknown == name) {
+            self.0.push(name.to_string());
+        }
+    }
+
+    /// 检查表达式中引用的列；列名有歧义时直接报错
+    pub fn check_expression(&mut self, expr: &Expression, columns: &[ColumnDef]) -> Result<()> {
+        for name in expr.column_references() {
+            if find_column(columns, name)?.is_none() {
+                self.add(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查条件中引用的列
+    pub fn check_condition(&mut self, condition: &Condition, columns: &[ColumnDef]) -> Result<()> {
+        condition_expressions(condition).try_for_each(|expr| self.check_expression(expr, columns))
+    }
+
+    /// 已记录的找不到的列名
+    pub fn names(&self) -> &[String] {
+        &self.0
+    }
+
+    /// 有找不到的列时报一个错误，列出全部未知的列
+    pub fn into_result(self) -> Result<()> {
+        self.report("列")
+    }
+
+    fn report(self, kind: &str) -> Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let listed: Vec<String> = self.0.iter().map(|name| format!("'{}'", name)).collect();
+        Err(DBError::Planner(format!(
+            "{} {} 不存在",
+            kind,
+            listed.join(", ")
+        )))
+    }
+}
+
+/// SELECT 读取记录之前的列检查：选择列、WHERE 条件和 ORDER BY 引用的列都必须在表中
+///
+/// WHERE 先经过 [`check_where`]，引用别名或使用聚合函数时给出针对性的错误。
+/// ORDER BY 中的列名也可以是选择列的别名。聚合查询的 ORDER BY 作用于结果列，
+/// 由调用方传入 `None`，见 [`result_sort_position`]。
+/// 只有 ORDER BY 引用了未知列时报“排序列 ... 不存在”，否则一并列出所有未知列。
+pub fn check_select_columns(
+    columns: &SelectColumns,
+    conditions: Option<&Condition>,
+    order_by: Option<&[OrderByItem]>,
+    table_columns: &[ColumnDef],
+) -> Result<()> {
+    let items: &[SelectItem] = match columns {
+        SelectColumns::Wildcard => &[],
+        SelectColumns::Columns(items) => items,
+        SelectColumns::Mixed(_) => unreachable!("通配符应该在前面已经展开"),
+    };
+    if let Some(condition) = conditions {
+        check_where(condition, items, table_columns)?;
+    }
+    let mut unknown = UnknownColumns::default();
+    for item in items {
+        unknown.check_expression(&item.expr, table_columns)?;
+    }
+    if let Some(condition) = conditions {
+        unknown.check_condition(condition, table_columns)?;
+    }
+    let only_sort = unknown.0.is_empty();
+    for item in order_by.unwrap_or_default() {
+        match &item.target {
+            SortTarget::Column(name) => {
+                let aliased = items
+                    .iter()
+                    .filter_map(|item| item.alias.as_deref())
+                    .any(|alias| alias.eq_ignore_ascii_case(name));
+                if !aliased && find_column(table_columns, name)?.is_none() {
+                    unknown.add(name);
+                }
+            }
+            SortTarget::Expression { expr, .. } => unknown.check_expression(expr, table_columns)?,
+            SortTarget::Position(_) => {}
+        }
+    }
+    if only_sort {
+        return unknown.report("排序列");
+    }
+    unknown.into_result()
+}
+
+/// 按结果列名（不区分大小写）查找 ORDER BY 项的位置，用于作用在结果上的排序
+pub fn result_column_position(names: &[String], column: &str) -> Result<usize> {
+    names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(column))
+        .ok_or_else(|| DBError::Execution(format!("ORDER BY 中的列 '{}' 不在查询结果中", column)))
+}
+
+/// ORDER BY 项在查询结果中的位置
+///
+/// 列名按结果列名查找，找不到时报错；列位置超出结果列数时报错；
+/// 表达式的原始文本与某个结果列名相同时取该列，否则返回 None，需要按结果行求值。
+pub fn result_sort_position(names: &[String], target: &SortTarget) -> Result<Option<usize>> {
+    match target {
+        SortTarget::Column(column) => result_column_position(names, column).map(Some),
+        SortTarget::Position(position) => sort_position(*position, names.len()).map(Some),
+        SortTarget::Expression { text, .. } => Ok(names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(text))),
+    }
+}
+
+/// `ORDER BY n` 对应的下标，超出结果列数时报错
+pub fn sort_position(position: usize, column_count: usize) -> Result<usize> {
+    if (1..=column_count).contains(&position) {
+        Ok(position - 1)
+    } else {
+        Err(DBError::Execution(format!(
+            "ORDER BY 的列位置 {} 超出结果列数 {}",
+            position, column_count
+        )))
+    }
+}
+
+/// 拒绝对只读伪表的写操作
+pub fn ensure_writable(table_name: &str) -> Result<()> {
+    if table_name == QUERY_HISTORY_TABLE {
+        return Err(DBError::Execution(format!("表 '{}' 是只读的", table_name)));
+    }
+    Ok(())
+}
+
+/// 按表定义的列顺序组装待插入的一行，并完成类型转换与类型校验
+///
+/// `columns` 为空时值已按表定义顺序给出；否则未指定的列取默认值。值为 None（DEFAULT）
+/// 时同样取默认值，见 [`column_default`]。VARCHAR 长度不在这里检查，见 [`check_value_kind`]。
+pub fn build_insert_row(
+    table_columns: &[ColumnDef],
+    columns: &[String],
+    row: &[Option<Value>],
+    mode: SqlMode,
+) -> Result<Vec<Value>> {
+    let mut full_row = Vec::with_capacity(table_columns.len());
+    for (position, table_col) in table_columns.iter().enumerate() {
+        let column_index = if columns.is_empty() {
+            Some(position)
+        } else {
+            columns
+                .iter()
+                .position(|col| col == &table_col.name)
+                .or_else(|| {
+                    columns
+                        .iter()
+                        .position(|col| col.eq_ignore_ascii_case(&table_col.name))
+                })
+        };
+        match column_index.and_then(|index| row.get(index)) {
+            Some(Some(value)) => {
+                let value = value.clone().coerce_to(&table_col.data_type, mode);
+                check_value_kind(&value, &table_col.data_type)?;
+                full_row.push(value);
+            }
+            _ => full_row.push(column_default(table_col)?),
+        }
+    }
+    Ok(full_row)
+}
+
+/// INSERT 未指定或写 DEFAULT 的列取的值
+///
+/// 没有声明默认值时取 NULL（自增列的 NULL 由存储层分配），NOT NULL 且非自增的列必须指定。
+pub fn column_default(column: &ColumnDef) -> Result<Value> {
+    if let Some(default) = &column.default {
+        return Ok(default.clone());
+    }
+    if column.not_null && !column.auto_increment {
+        return Err(DBError::Execution(format!(
+            "Field '{}' doesn't have a default value",
+            column.name
+        )));
+    }
+    Ok(Value::Null)
+}
+
+/// INSERT ... SELECT 的结果列数必须等于要写入的列数
+pub fn check_insert_select_arity(selected: usize, expected: usize) -> Result<()> {
+    if selected == expected {
+        Ok(())
+    } else {
+        Err(DBError::Execution(format!(
+            "INSERT ... SELECT 的结果列数({})与要写入的列数({})不匹配",
+            selected, expected
+        )))
+    }
+}
+
+/// 检查一行中 NOT NULL 列是否被显式写入 NULL，自增列的 NULL 由存储层分配，不算违反
+pub fn check_not_null(row: &[Value], table_columns: &[ColumnDef]) -> Result<()> {
+    for (value, column) in row.iter().zip(table_columns) {
+        if value.is_null() && column.not_null && !column.auto_increment {
+            return Err(DBError::Schema(format!(
+                "Column '{}' cannot be null",
+                column.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 无列名的 INSERT 中一行的值数量必须等于表的列数，`row_number` 从 1 开始
+pub fn check_row_arity<T>(row: &[T], column_count: usize, row_number: usize) -> Result<()> {
+    if row.len() == column_count {
+        return Ok(());
+    }
+    Err(DBError::Execution(format!(
+        "第 {} 行的值数量({})与表的列数({})不匹配",
+        row_number,
+        row.len(),
+        column_count
+    )))
+}
+
+/// 检查一行中的字符串是否超过 VARCHAR 长度，错误信息与存储层拒绝超长字符串时相同
+pub fn check_row_lengths(
+    row: &[Value],
+    table_columns: &[ColumnDef],
+    row_number: usize,
+) -> Result<()> {
+    for (value, column) in row.iter().zip(table_columns) {
+        if check_value_length(value, &column.data_type).is_err() {
+            return Err(DBError::Schema(format!(
+                "Data too long for column '{}' at row {}",
+                column.name, row_number
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 多行写入时在错误信息后注明从 1 开始的行号，错误类别不变
+pub fn at_row(error: DBError, row_number: Option<usize>) -> DBError {
+    match row_number {
+        Some(row_number) => {
+            DBError::from_code(error.code(), format!("{} (第 {} 行)", error, row_number))
+        }
+        None => error,
+    }
+}
+
+/// 合并后的错误信息中最多列出的出错行数
+const MAX_REPORTED_ROW_ERRORS: usize = 10;
+
+/// 把多行写入中各行的错误合并为一个，只有一个错误时原样返回，没有错误时返回 `None`
+///
+/// 合并后的错误沿用第一个错误的类别。
+pub fn combine_row_errors(mut errors: Vec<DBError>) -> Option<DBError> {
+    if errors.len() <= 1 {
+        return errors.pop();
+    }
+    let listed: Vec<String> = errors
+        .iter()
+        .take(MAX_REPORTED_ROW_ERRORS)
+        .map(ToString::to_string)
+        .collect();
+    let mut message = format!("{} 行数据有误: {}", errors.len(), listed.join("; "));
+    if errors.len() > MAX_REPORTED_ROW_ERRORS {
+        message.push_str(&format!(
+            "; 其余 {} 行未列出",
+            errors.len() - MAX_REPORTED_ROW_ERRORS
+        ));
+    }
+    Some(DBError::from_code(errors[0].code(), message))
+}
+
+/// 验证值类型是否与列定义匹配，NULL 总是通过（NOT NULL 约束另行检查）
+pub fn check_value_type(value: &Value, data_type: &DataType) -> Result<()> {
+    check_value_kind(value, data_type)?;
+    check_value_length(value, data_type)
+}
+
+/// 只检查值的种类与列类型是否相符，不检查 VARCHAR 长度
+///
+/// `value` 应已经过 [`Value::coerce_to`] 转换。超长字符串由存储层按会话的
+/// `truncate_long_strings` 拒绝或截断。
+pub fn check_value_kind(value: &Value, data_type: &DataType) -> Result<()> {
+    let message = match (value, data_type) {
+        (Value::Int(_), DataType::Int(_))
+        | (Value::String(_), DataType::Varchar(_))
+        | (Value::Float(_), DataType::Float)
+        | (Value::Boolean(_), DataType::Boolean)
+        | (Value::Null, _) => return Ok(()),
+        (Value::Float(f), DataType::Int(_)) => {
+            format!("浮点数 {} 有小数部分或超出 INT 范围，不能写入 INT 列", f)
+        }
+        // 宽松模式下 0/1 和布尔值已在 coerce_to 中转换，到这里说明是严格模式
+        (Value::Int(n @ (0 | 1)), DataType::Boolean) => {
+            format!(
+                "严格模式下整数 {} 不能写入 BOOLEAN 列，应写 TRUE 或 FALSE",
+                n
+            )
+        }
+        (Value::Int(n), DataType::Boolean) => {
+            format!("整数 {} 不能写入 BOOLEAN 列，只接受 0 和 1", n)
+        }
+        (Value::Boolean(_), DataType::Int(_)) => {
+            format!("严格模式下布尔值 {} 不能写入 {} 列", value, data_type)
+        }
+        (Value::String(s), _) => format!("字符串 '{}' 不会隐式转换为 {}", s, data_type),
+        _ => format!(
+            "{} 类型的值 {} 不能写入 {} 列",
+            value.type_name(),
+            value,
+            data_type
+        ),
+    };
+    Err(DBError::Schema(message))
+}
+
+/// 检查字符串是否超过 VARCHAR 声明的长度
+pub fn check_value_length(value: &Value, data_type: &DataType) -> Result<()> {
+    match (value, data_type.varchar_limit()) {
+        (Value::String(s), Some(limit)) if varchar_len(s) > limit => {
+            Err(DBError::Schema(format!(
+                "字符串长度({})超过了VARCHAR({})的限制",
+                varchar_len(s),
+                limit
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 表的最坏情况行大小超过单页容量时的提示信息
+pub fn oversized_row_message(name: &str, columns: &[ColumnDef]) -> Option<String> {
+    let row_size = Table::estimate_max_row_size(columns)?;
+    (row_size > MAX_RECORD_SIZE).then(|| {
+        format!(
+            "表 '{}' 的最坏情况行大小为 {} 字节，超过单页可容纳的 {} 字节，过长的行将无法插入",
+            name, row_size, MAX_RECORD_SIZE
+        )
+    })
+}
+
+/// INT 的显示宽度不限制取值范围（MySQL 8 已弃用），建表时给出提示
+pub fn display_width_note(column: &ColumnDef) -> Option<String> {
+    match column.data_type {
+        DataType::Int(Some(width)) => Some(format!(
+            "列 '{}' 的显示宽度 INT({}) 不限制取值范围，且已被弃用",
+            column.name, width
+        )),
+        _ => None,
+    }
+}
+
+/// 查询是否需要分组聚合：有 GROUP BY，或选择列、HAVING 中出现聚合函数
+pub fn is_aggregate_query(
+    columns: &SelectColumns,
+    group_by: &[Expression],
+    having: Option<&Condition>,
+) -> bool {
+    !group_by.is_empty()
+        || matches!(columns, SelectColumns::Columns(items)
+            if items.iter().any(|item| contains_aggregate(&item.expr)))
+        || having.is_some_and(|having| condition_expressions(having).any(contains_aggregate))
+}
+
+/// 检查 CHECK 约束的条件
+///
+/// 条件只能引用表中已有的非自增列（自增值在写入时才生成），不能包含子查询和聚合函数。
+pub fn check_constraint_condition(
+    name: &str,
+    condition: &Condition,
+    columns: &[ColumnDef],
+) -> Result<()> {
+    if !condition.subqueries().is_empty()
+        || condition_expressions(condition).any(contains_aggregate)
+    {
+        return Err(DBError::Schema(format!(
+            "An expression of check constraint '{}' contains disallowed function.",
+            name
+        )));
+    }
+    for column in condition.column_references() {
+        match column_position(columns, column) {
+            None => {
+                return Err(DBError::Schema(format!(
+                    "Check constraint '{}' refers to non-existing column '{}'.",
+                    name, column
+                )));
+            }
+            Some(index) if columns[index].auto_increment => {
+                return Err(DBError::Schema(format!(
+                    "Check constraint '{}' cannot refer to an auto-increment column.",
+                    name
+                )));
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// 表达式中是否包含聚合函数
+pub fn contains_aggregate(expr: &Expression) -> bool {
+    first_aggregate(expr).is_some()
+}
+
+/// 表达式中（按先序）第一个聚合函数调用
+fn first_aggregate(expr: &Expression) -> Option<&Expression> {
+    let mut found = None;
+    expr.walk(&mut |expr| {
+        if found.is_none() && matches!(expr, Expression::Aggregate { .. }) {
+            found = Some(expr);
+        }
+    });
+    found
+}
+
+fn check_nested_aggregate(expr: &Expression, inside_aggregate: bool) -> Result<()> {
+    match expr {
+        Expression::Aggregate { func, arg } => {
+            if inside_aggregate {
+                return Err(DBError::Planner(format!(
+                    "聚合函数 {} 不能嵌套在另一个聚合函数中",
+                    func
+                )));
+            }
+            match arg {
+                Some(arg) => check_nested_aggregate(arg, true),
+                None => Ok(()),
+            }
+        }
+        Expression::Binary { left, right, .. } => {
+            check_nested_aggregate(left, inside_aggregate)?;
+            check_nested_aggregate(right, inside_aggregate)
+        }
+        Expression::Unary { operand, .. } => check_nested_aggregate(operand, inside_aggregate),
+        Expression::Function { args, .. } => args
+            .iter()
+            .try_for_each(|arg| check_nested_aggregate(arg, inside_aggregate)),
+        Expression::Column(_) | Expression::Value(_) | Expression::Subquery(_) => Ok(()),
+    }
+}
+
+/// 聚合函数之外第一个不在 GROUP BY 中的列
+///
+/// 与某个 GROUP BY 表达式完全相同的子表达式视为分组列，如 `GROUP BY a + 1` 时的 `a + 1`。
+fn first_ungrouped_column<'a>(expr: &'a Expression, group_by: &[Expression]) -> Option<&'a str> {
+    if group_by.contains(expr) {
+        return None;
+    }
+    match expr {
+        Expression::Column(name) => Some(name),
+        Expression::Binary { left, right, .. } => first_ungrouped_column(left, group_by)
+            .or_else(|| first_ungrouped_column(right, group_by)),
+        Expression::Unary { operand, .. } => first_ungrouped_column(operand, group_by),
+        Expression::Function { args, .. } => args
+            .iter()
+            .find_map(|arg| first_ungrouped_column(arg, group_by)),
+        Expression::Aggregate { .. } | Expression::Value(_) | Expression::Subquery(_) => None,
+    }
+}
+
+/// 条件中出现的所有表达式，不包括 IN 右侧的子查询
+pub fn condition_expressions(condition: &Condition) -> Box<dyn Iterator<Item = &Expression> + '_> {
+    match condition {
+        Condition::Expression(expr)
+        | Condition::IsNull(expr)
+        | Condition::IsNotNull(expr)
+        | Condition::In { expr, .. } => Box::new(std::iter::once(expr)),
+        Condition::Like { expr, pattern, .. } => Box::new([expr, pattern].into_iter()),
+        Condition::Constant(_) => Box::new(std::iter::empty()),
+        Condition::And(left, right) | Condition::Or(left, right) => {
+            Box::new(condition_expressions(left).chain(condition_expressions(right)))
+        }
+        Condition::Not(inner) | Condition::IsTruth { operand: inner, .. } => {
+            condition_expressions(inner)
+        }
+    }
+}
+
+/// 错误信息中表达式的简短写法
+pub fn describe(expr: &Expression) -> String {
+    match expr {
+        Expression::Column(name) => name.clone(),
+        Expression::Aggregate { func, .. } => format!("{}(...)", func),
+        Expression::Function { name, .. } => format!("{}(...)", name),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::{Plan, Planner};
+    use sqlparser::dialect::MySqlDialect;
+    use sqlparser::parser::Parser;
+
+    fn validate_sql(sql: &str) -> Result<()> {
+        let ast = Parser::parse_sql(&MySqlDialect {}, sql).unwrap();
+        match Planner::new().plan(&ast[0])? {
+            Plan::Select {
+                columns,
+                group_by,
+                having,
+                ..
+            } => validate_select(&columns, &group_by, having.as_ref()),
+            other => panic!("预期生成Select查询计划，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_classify_projection() {
+        let ast = Parser::parse_sql(
+            &MySqlDialect {},
+            "SELECT COUNT(*), grp + SUM(x), grp, 1, name, name + MIN(x) FROM t GROUP BY grp",
+        )
+        .unwrap();
+        let Plan::Select {
+            columns: SelectColumns::Columns(items),
+            group_by,
+            ..
+        } = Planner::new().plan(&ast[0]).unwrap()
+        else {
+            panic!("预期生成Select查询计划");
+        };
+        let kinds: Vec<ProjectionKind> = items
+            .iter()
+            .map(|item| classify(&item.expr, &group_by))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ProjectionKind::Aggregate,
+                ProjectionKind::Aggregate,
+                ProjectionKind::Grouped,
+                ProjectionKind::Grouped,
+                ProjectionKind::Plain,
+                ProjectionKind::Plain,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compatibility_matrix() {
+        let valid = [
+            "SELECT name FROM users",
+            "SELECT name, id + 1 FROM users WHERE id > 1",
+            "SELECT * FROM users",
+            "SELECT COUNT(*) FROM users",
+            "SELECT COUNT(*), MAX(id) FROM users",
+            "SELECT grp, COUNT(*) FROM users GROUP BY grp",
+            "SELECT grp FROM users GROUP BY grp",
+            "SELECT grp + 1, SUM(score) * 2 FROM users GROUP BY grp",
+            "SELECT id + 1 FROM users GROUP BY id + 1",
+            "SELECT grp, COUNT(*) FROM users GROUP BY grp HAVING COUNT(*) > 5",
+            "SELECT grp, COUNT(*) AS n FROM users GROUP BY grp HAVING n > 5",
+            "SELECT grp FROM users GROUP BY grp HAVING grp > 1",
+            "SELECT COUNT(*) FROM users HAVING MAX(id) > 1",
+        ];
+        for sql in valid {
+            assert!(validate_sql(sql).is_ok(), "应通过: {}", sql);
+        }
+
+        // (SQL, 错误信息中必须出现的内容)
+        let invalid = [
+            ("SELECT name, COUNT(*) FROM users", "'name'"),
+            ("SELECT name, COUNT(*) FROM users", "GROUP BY"),
+            ("SELECT name FROM users GROUP BY grp", "'name'"),
+            ("SELECT grp, name + 1 FROM users GROUP BY grp", "'name'"),
+            ("SELECT * FROM users GROUP BY grp", "SELECT *"),
+            ("SELECT COUNT(MAX(id)) FROM users", "嵌套"),
+            (
+                "SELECT grp FROM users GROUP BY COUNT(*)",
+                "GROUP BY 中不能使用聚合函数",
+            ),
+            (
+                "SELECT grp FROM users GROUP BY grp HAVING name = 'a'",
+                "HAVING 中的列 'name'",
+            ),
+            ("SELECT name FROM users HAVING MAX(id) > 1", "'name'"),
+            (
+                "SELECT COUNT(*) FROM users HAVING id > 1",
+                "HAVING 中的列 'id'",
+            ),
+        ];
+        for (sql, expected) in invalid {
+            match validate_sql(sql) {
+                Err(e) => assert!(
+                    e.to_string().contains(expected),
+                    "{}: 错误信息 '{}' 中缺少 '{}'",
+                    sql,
+                    e,
+                    expected
+                ),
+                Ok(()) => panic!("应报错: {}", sql),
+            }
+        }
+    }
+}
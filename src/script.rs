@@ -0,0 +1,200 @@
+use crate::error::Result;
+use crate::executor::QueryResult;
+
+/// SQL 脚本中的一条语句
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptStatement {
+    /// 语句第一个字符所在的行号，从 1 开始
+    pub line: usize,
+    /// 语句文本，不含结尾的分号
+    pub sql: String,
+}
+
+/// 脚本中一条语句的执行结果
+#[derive(Debug)]
+pub struct ScriptResult {
+    /// 语句开始的行号
+    pub line: usize,
+    pub result: Result<QueryResult>,
+}
+
+/// 按分号把脚本切分成语句，记录每条语句开始的行号
+///
+/// 引号（`'`、`"`、反引号）和注释（`--`、`#`、`/* */`）中的分号不作为分隔符；
+/// 只有空白和注释的片段被丢弃。切分只为定位和逐条执行，语法由解析器检查，
+/// 未闭合的引号或注释一直延续到脚本末尾。
+pub fn split_statements(script: &str) -> Vec<ScriptStatement> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut start_line = None;
+    let mut line = 1;
+    let mut chars = script.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                if let Some(line) = start_line.take() {
+                    statements.push(ScriptStatement {
+                        line,
+                        sql: current.trim().to_string(),
+                    });
+                }
+                current.clear();
+                continue;
+            }
+            '\'' | '"' | '`' => {
+                start_line.get_or_insert(line);
+                current.push(c);
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == '\n' {
+                        line += 1;
+                    } else if next == '\\' && c != '`' {
+                        // 反斜杠转义下一个字符（MySQL 字符串的写法）
+                        if let Some(escaped) = chars.next() {
+                            if escaped == '\n' {
+                                line += 1;
+                            }
+                            current.push(escaped);
+                        }
+                    } else if next == c {
+                        // 连写两个引号表示引号本身
+                        if chars.peek() == Some(&c) {
+                            current.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                continue;
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                skip_line_comment(&mut chars, &mut current);
+                continue;
+            }
+            '#' => {
+                skip_line_comment(&mut chars, &mut current);
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                current.push(' ');
+                continue;
+            }
+            _ => {}
+        }
+
+        if c == '\n' {
+            line += 1;
+        } else if !c.is_whitespace() {
+            start_line.get_or_insert(line);
+        }
+        current.push(c);
+    }
+
+    if let Some(line) = start_line {
+        statements.push(ScriptStatement {
+            line,
+            sql: current.trim().to_string(),
+        });
+    }
+    statements
+}
+
+/// 跳过行注释，保留结尾的换行交给调用方计数
+fn skip_line_comment(chars: &mut std::iter::Peekable<std::str::Chars>, current: &mut String) {
+    while chars.next_if(|&next| next != '\n').is_some() {}
+    current.push(' ');
+}
+
+/// 执行结果的摘要，如 `8 ok, 2 failed (lines 12, 30)`
+pub fn summary(results: &[ScriptResult]) -> String {
+    let failed: Vec<String> = results
+        .iter()
+        .filter(|result| result.result.is_err())
+        .map(|result| result.line.to_string())
+        .collect();
+    let ok = results.len() - failed.len();
+    match failed.len() {
+        0 => format!("{} ok", ok),
+        1 => format!("{} ok, 1 failed (line {})", ok, failed[0]),
+        count => format!("{} ok, {} failed (lines {})", ok, count, failed.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DBError;
+
+    fn lines_and_sql(script: &str) -> Vec<(usize, String)> {
+        split_statements(script)
+            .into_iter()
+            .map(|statement| (statement.line, statement.sql))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_statements_tracks_lines() {
+        let script = "-- 建表\nCREATE TABLE t (id INT);\n\n\
+                      INSERT INTO t VALUES (1);  INSERT INTO t\n  VALUES (2);\n\
+                      /* 多行\n注释; */ SELECT * FROM t";
+        assert_eq!(
+            lines_and_sql(script),
+            [
+                (2, "CREATE TABLE t (id INT)".to_string()),
+                (4, "INSERT INTO t VALUES (1)".to_string()),
+                (4, "INSERT INTO t\n  VALUES (2)".to_string()),
+                (7, "SELECT * FROM t".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_statements_ignores_quoted_semicolons() {
+        let script = "INSERT INTO t VALUES ('a;b', 'it''s;', 'x\\';y');\n\
+                      SELECT `odd;name` FROM t # 注释;\n;\n\
+                      SELECT \"multi\nline;\"";
+        assert_eq!(
+            lines_and_sql(script),
+            [
+                (
+                    1,
+                    "INSERT INTO t VALUES ('a;b', 'it''s;', 'x\\';y')".to_string()
+                ),
+                (2, "SELECT `odd;name` FROM t".to_string()),
+                (4, "SELECT \"multi\nline;\"".to_string()),
+            ]
+        );
+        assert!(split_statements("  -- 只有注释\n/* ; */ ;; \n").is_empty());
+    }
+
+    #[test]
+    fn test_summary() {
+        let ok = |line| ScriptResult {
+            line,
+            result: Ok(QueryResult::Success),
+        };
+        let failed = |line| ScriptResult {
+            line,
+            result: Err(DBError::Other("失败".to_string())),
+        };
+        assert_eq!(summary(&[ok(1), ok(2)]), "2 ok");
+        assert_eq!(summary(&[ok(1), failed(3)]), "1 ok, 1 failed (line 3)");
+        assert_eq!(
+            summary(&[failed(12), ok(20), failed(30)]),
+            "1 ok, 2 failed (lines 12, 30)"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::SimpleDB;
+use crate::error::Result;
+use crate::executor::QueryResult;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+pub mod protocol;
+
+use protocol::{DEFAULT_ROW_CHUNK, ResultFormat};
+
+/// 基于 TCP 的 SQL 服务
+///
+/// 客户端每发送一行 SQL，服务端返回一个结果。连接建立后的第一行可以是
+/// `FORMAT BINARY` / `FORMAT JSON` 前导行，用于选择结果格式，缺省为 JSON：
+///
+/// - JSON：每个结果一行 JSON，见 [`protocol::result_to_json`]
+/// - 二进制：长度前缀的 bincode 帧，见 [`protocol::Frame`]
+///
+/// 一行中包含多条语句时依次执行，返回第一个错误或最后一条语句的结果。
+/// 连接按顺序逐个处理，同一时间只服务一个客户端。
+pub struct Server {
+    listener: TcpListener,
+    chunk_rows: usize,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            chunk_rows: DEFAULT_ROW_CHUNK,
+        })
+    }
+
+    /// 设置二进制格式下每个数据帧的行数
+    pub fn with_chunk_rows(mut self, chunk_rows: usize) -> Self {
+        self.chunk_rows = chunk_rows.max(1);
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// 持续接受连接并执行请求
+    ///
+    /// 单个连接出错只会断开该连接，不会停止服务。每个连接断开后回滚它没有提交的事务，再保存数据库。
+    pub fn serve(&self, db: &mut SimpleDB) -> Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::error!("接受连接失败: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.handle_connection(db, stream) {
+                log::error!("连接处理失败: {}", e);
+            }
+            // 客户端断开时没有提交的事务随之回滚，不会被保存
+            db.end_session()?;
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, db: &mut SimpleDB, stream: TcpStream) -> Result<()> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+        let mut format = ResultFormat::default();
+        let mut first_line = true;
+
+        for line in reader.lines() {
+            let line = line?;
+            let sql = line.trim();
+            if sql.is_empty() {
+                continue;
+            }
+            let is_first = std::mem::replace(&mut first_line, false);
+            if is_first && let Some(selected) = ResultFormat::from_preamble(sql) {
+                format = selected;
+                continue;
+            }
+
+            let result = execute_request(db, sql);
+            match format {
+                ResultFormat::Json => {
+                    writeln!(writer, "{}", protocol::result_to_json(&result))?;
+                }
+                ResultFormat::Binary => {
+                    protocol::write_binary_result(&mut writer, &result, self.chunk_rows)?;
+                }
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// 执行一行请求中的全部语句
+fn execute_request(db: &mut SimpleDB, sql: &str) -> Result<QueryResult> {
+    let mut last = QueryResult::Success;
+    for result in db.execute_sql(sql)? {
+        last = result?;
+    }
+    Ok(last)
+}
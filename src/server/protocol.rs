@@ -0,0 +1,430 @@
+use crate::error::{DBError, Result};
+use crate::executor::{LastInsert, QueryResult, ResultSet, UpdateSummary};
+use crate::storage::table::Value;
+use bincode::{Decode, Encode};
+use serde_json::json;
+use std::io::{Read, Write};
+
+/// 切换到二进制结果格式的前导行
+pub const FORMAT_BINARY_PREAMBLE: &str = "FORMAT BINARY";
+
+/// 显式选择 JSON 结果格式的前导行（默认即为 JSON）
+pub const FORMAT_JSON_PREAMBLE: &str = "FORMAT JSON";
+
+/// 二进制格式下每个数据帧包含的行数
+pub const DEFAULT_ROW_CHUNK: usize = 1024;
+
+/// 单个二进制帧的最大长度，防止损坏的长度前缀导致巨量分配
+const MAX_FRAME_SIZE: usize = 256 * 1024 * 1024;
+
+/// 按结果头预分配的最大行数，行数来自对端，不能完全信任
+const MAX_PREALLOCATED_ROWS: u64 = 64 * 1024;
+
+/// 结果格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultFormat {
+    /// 每个结果一行 JSON
+    #[default]
+    Json,
+    /// 长度前缀的 bincode 帧
+    Binary,
+}
+
+impl ResultFormat {
+    /// 连接建立后客户端发送的前导行
+    pub fn preamble(&self) -> &'static str {
+        match self {
+            ResultFormat::Json => FORMAT_JSON_PREAMBLE,
+            ResultFormat::Binary => FORMAT_BINARY_PREAMBLE,
+        }
+    }
+
+    /// 解析前导行，不是前导行时返回 `None`
+    pub fn from_preamble(line: &str) -> Option<Self> {
+        let line = line.trim().trim_end_matches(';');
+        if line.eq_ignore_ascii_case(FORMAT_BINARY_PREAMBLE) {
+            Some(ResultFormat::Binary)
+        } else if line.eq_ignore_ascii_case(FORMAT_JSON_PREAMBLE) {
+            Some(ResultFormat::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// 二进制格式的帧
+///
+/// 结果集先发送一个 `ResultHeader`，随后是若干个 `Rows` 帧，行数之和等于 `row_count`。
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub enum Frame {
+    ResultHeader {
+        columns: Vec<String>,
+        column_types: Vec<String>,
+        row_count: u64,
+        comment: Option<String>,
+    },
+    Rows(Vec<Vec<Value>>),
+    Modified {
+        rows_affected: u64,
+        last_insert_id: Option<i64>,
+    },
+    Success,
+    Message(String),
+    Error {
+        code: u32,
+        message: String,
+    },
+    Updated {
+        matched: u64,
+        changed: u64,
+    },
+}
+
+/// 写出一个帧：4 字节小端长度 + bincode 内容
+pub fn write_frame<W: Write>(writer: &mut W, frame: &Frame) -> Result<()> {
+    let body = bincode::encode_to_vec(frame, bincode::config::standard())
+        .map_err(|e| DBError::IO(format!("无法序列化结果帧: {}", e)))?;
+    let len = u32::try_from(body.len())
+        .map_err(|_| DBError::IO(format!("结果帧过大: {} 字节", body.len())))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&body)?;
+    Ok(())
+}
+
+/// 读取一个帧
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Frame> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(DBError::IO(format!("结果帧过大: {} 字节", len)));
+    }
+
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body)?;
+    let (frame, _) = bincode::decode_from_slice(&body, bincode::config::standard())
+        .map_err(|e| DBError::IO(format!("结果帧损坏或格式不正确: {}", e)))?;
+    Ok(frame)
+}
+
+/// 以二进制格式写出一条语句的结果，结果集的行按 `chunk_rows` 分帧发送
+pub fn write_binary_result<W: Write>(
+    writer: &mut W,
+    result: &Result<QueryResult>,
+    chunk_rows: usize,
+) -> Result<()> {
+    match result {
+        Ok(QueryResult::ResultSet(rs)) => {
+            write_frame(
+                writer,
+                &Frame::ResultHeader {
+                    columns: rs.columns.clone(),
+                    column_types: column_types(rs),
+                    row_count: rs.rows.len() as u64,
+                    comment: rs.comment.clone(),
+                },
+            )?;
+            for chunk in rs.rows.chunks(chunk_rows.max(1)) {
+                write_frame(writer, &Frame::Rows(chunk.to_vec()))?;
+            }
+            Ok(())
+        }
+        Ok(QueryResult::Modified(last)) => write_frame(
+            writer,
+            &Frame::Modified {
+                rows_affected: last.rows_affected as u64,
+                last_insert_id: last.last_insert_id,
+            },
+        ),
+        Ok(QueryResult::Updated(summary)) => write_frame(
+            writer,
+            &Frame::Updated {
+                matched: summary.matched as u64,
+                changed: summary.changed as u64,
+            },
+        ),
+        Ok(QueryResult::Success) => write_frame(writer, &Frame::Success),
+        Ok(QueryResult::Message(msg)) => write_frame(writer, &Frame::Message(msg.clone())),
+        Err(e) => write_frame(
+            writer,
+            &Frame::Error {
+                code: e.code(),
+                message: e.to_string(),
+            },
+        ),
+    }
+}
+
+/// 读取一条语句的二进制结果
+pub fn read_binary_result<R: Read>(reader: &mut R) -> Result<QueryResult> {
+    match read_frame(reader)? {
+        Frame::ResultHeader {
+            columns,
+            row_count,
+            comment,
+            ..
+        } => {
+            let mut rows = Vec::with_capacity(row_count.min(MAX_PREALLOCATED_ROWS) as usize);
+            while (rows.len() as u64) < row_count {
+                match read_frame(reader)? {
+                    Frame::Rows(chunk) => rows.extend(chunk),
+                    other => {
+                        return Err(DBError::IO(format!("预期数据行帧，实际为 {:?}", other)));
+                    }
+                }
+            }
+            let mut rs = ResultSet::new(columns, rows);
+            rs.comment = comment;
+            Ok(QueryResult::ResultSet(rs))
+        }
+        Frame::Modified {
+            rows_affected,
+            last_insert_id,
+        } => Ok(QueryResult::Modified(LastInsert {
+            rows_affected: rows_affected as usize,
+            last_insert_id,
+            elapsed: None,
+        })),
+        Frame::Updated { matched, changed } => Ok(QueryResult::Updated(UpdateSummary {
+            matched: matched as usize,
+            changed: changed as usize,
+            elapsed: None,
+        })),
+        Frame::Success => Ok(QueryResult::Success),
+        Frame::Message(msg) => Ok(QueryResult::Message(msg)),
+        Frame::Error { code, message } => Err(DBError::from_code(code, message)),
+        Frame::Rows(_) => Err(DBError::IO("数据行帧之前缺少结果头".to_string())),
+    }
+}
+
+/// 以 JSON 格式编码一条语句的结果
+pub fn result_to_json(result: &Result<QueryResult>) -> serde_json::Value {
+    match result {
+        Ok(QueryResult::ResultSet(rs)) => json!({
+            "columns": rs.columns,
+            "rows": rs
+                .rows
+                .iter()
+                .map(|row| row.iter().map(Value::to_json).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            "comment": rs.comment,
+        }),
+        Ok(QueryResult::Modified(last)) => json!({
+            "rows_affected": last.rows_affected,
+            "last_insert_id": last.last_insert_id,
+        }),
+        Ok(QueryResult::Updated(summary)) => json!({
+            "rows_matched": summary.matched,
+            "rows_changed": summary.changed,
+        }),
+        Ok(QueryResult::Success) => json!({ "ok": true }),
+        Ok(QueryResult::Message(msg)) => json!({ "message": msg }),
+        Err(e) => json!({ "error": { "code": e.code(), "message": e.to_string() } }),
+    }
+}
+
+/// 解码 JSON 格式的结果
+pub fn result_from_json(json: &serde_json::Value) -> Result<QueryResult> {
+    let invalid = || DBError::IO(format!("无法识别的 JSON 结果: {}", json));
+    let object = json.as_object().ok_or_else(invalid)?;
+
+    if let Some(error) = object.get("error") {
+        let code = error.get("code").and_then(|c| c.as_u64()).unwrap_or(1);
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default();
+        return Err(DBError::from_code(code as u32, message.to_string()));
+    }
+
+    if let Some(columns) = object.get("columns") {
+        let columns = columns
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|c| c.as_str().map(str::to_string).ok_or_else(invalid))
+            .collect::<Result<Vec<_>>>()?;
+        let rows = object
+            .get("rows")
+            .and_then(|rows| rows.as_array())
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|row| {
+                row.as_array()
+                    .ok_or_else(invalid)?
+                    .iter()
+                    .map(|v| Value::from_json(v).ok_or_else(invalid))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut rs = ResultSet::new(columns, rows);
+        rs.comment = object
+            .get("comment")
+            .and_then(|c| c.as_str())
+            .map(str::to_string);
+        return Ok(QueryResult::ResultSet(rs));
+    }
+
+    if let Some(rows_affected) = object.get("rows_affected") {
+        return Ok(QueryResult::Modified(LastInsert {
+            rows_affected: rows_affected.as_u64().ok_or_else(invalid)? as usize,
+            last_insert_id: object.get("last_insert_id").and_then(|id| id.as_i64()),
+            elapsed: None,
+        }));
+    }
+
+    if let (Some(matched), Some(changed)) = (object.get("rows_matched"), object.get("rows_changed"))
+    {
+        return Ok(QueryResult::Updated(UpdateSummary {
+            matched: matched.as_u64().ok_or_else(invalid)? as usize,
+            changed: changed.as_u64().ok_or_else(invalid)? as usize,
+            elapsed: None,
+        }));
+    }
+
+    if let Some(message) = object.get("message") {
+        return Ok(QueryResult::Message(
+            message.as_str().ok_or_else(invalid)?.to_string(),
+        ));
+    }
+
+    if object.get("ok").is_some() {
+        return Ok(QueryResult::Success);
+    }
+
+    Err(invalid())
+}
+
+/// 按每列第一个非 NULL 值推断列类型，全为 NULL 的列记为 NULL
+fn column_types(rs: &ResultSet) -> Vec<String> {
+    (0..rs.columns.len())
+        .map(|idx| {
+            let value = rs
+                .rows
+                .iter()
+                .filter_map(|row| row.get(idx))
+                .find(|value| !matches!(value, Value::Null));
+            match value {
+                Some(Value::Int(_)) => "INT",
+                Some(Value::Float(_)) => "FLOAT",
+                Some(Value::String(_)) => "VARCHAR",
+                Some(Value::Boolean(_)) => "BOOLEAN",
+                Some(Value::Null) | None => "NULL",
+            }
+            .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_value_result() -> QueryResult {
+        let mut rs = ResultSet::new(
+            vec![
+                "i".to_string(),
+                "f".to_string(),
+                "s".to_string(),
+                "b".to_string(),
+            ],
+            vec![
+                vec![
+                    Value::Int(i32::MIN),
+                    Value::Float(-1.5),
+                    Value::String("你好, \"world\"\n".to_string()),
+                    Value::Boolean(true),
+                ],
+                vec![
+                    Value::Null,
+                    Value::Float(2.0),
+                    Value::Null,
+                    Value::Boolean(false),
+                ],
+                vec![
+                    Value::Int(7),
+                    Value::Null,
+                    Value::String(String::new()),
+                    Value::Null,
+                ],
+            ],
+        );
+        rs.comment = Some("3 rows".to_string());
+        QueryResult::ResultSet(rs)
+    }
+
+    fn rows(result: QueryResult) -> (Vec<String>, Vec<Vec<Value>>, Option<String>) {
+        match result {
+            QueryResult::ResultSet(rs) => (rs.columns, rs.rows, rs.comment),
+            other => panic!("预期结果集，实际为 {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let result = Ok(every_value_result());
+        let mut buf = Vec::new();
+        write_binary_result(&mut buf, &result, 2).unwrap();
+
+        // 3 行按每帧 2 行发送：结果头 + 2 个数据帧
+        let mut reader = buf.as_slice();
+        assert!(matches!(
+            read_frame(&mut reader).unwrap(),
+            Frame::ResultHeader { ref column_types, row_count: 3, .. }
+                if column_types == &["INT", "FLOAT", "VARCHAR", "BOOLEAN"]
+        ));
+        assert!(matches!(read_frame(&mut reader).unwrap(), Frame::Rows(r) if r.len() == 2));
+        assert!(matches!(read_frame(&mut reader).unwrap(), Frame::Rows(r) if r.len() == 1));
+        assert!(reader.is_empty());
+
+        let decoded = read_binary_result(&mut buf.as_slice()).unwrap();
+        assert_eq!(rows(decoded), rows(every_value_result()));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = result_to_json(&Ok(every_value_result()));
+        let decoded = result_from_json(&json).unwrap();
+        assert_eq!(rows(decoded), rows(every_value_result()));
+
+        let modified = result_from_json(&result_to_json(&Ok(QueryResult::Modified(LastInsert {
+            rows_affected: 2,
+            last_insert_id: Some(9),
+            elapsed: None,
+        }))))
+        .unwrap();
+        assert!(matches!(
+            modified,
+            QueryResult::Modified(LastInsert {
+                rows_affected: 2,
+                last_insert_id: Some(9),
+                ..
+            })
+        ));
+
+        let summary = UpdateSummary {
+            matched: 3,
+            changed: 1,
+            elapsed: None,
+        };
+        let updated = result_from_json(&result_to_json(&Ok(QueryResult::Updated(summary)))).unwrap();
+        assert!(matches!(updated, QueryResult::Updated(s) if s == summary));
+    }
+
+    #[test]
+    fn test_errors_keep_code() {
+        let error: Result<QueryResult> = Err(DBError::NotFound("表 't' 不存在".to_string()));
+
+        let mut buf = Vec::new();
+        write_binary_result(&mut buf, &error, DEFAULT_ROW_CHUNK).unwrap();
+        match read_binary_result(&mut buf.as_slice()) {
+            Err(DBError::NotFound(msg)) => assert_eq!(msg, "表 't' 不存在"),
+            other => panic!("预期 NotFound 错误，实际为 {:?}", other),
+        }
+
+        match result_from_json(&result_to_json(&error)) {
+            Err(DBError::NotFound(msg)) => assert_eq!(msg, "表 't' 不存在"),
+            other => panic!("预期 NotFound 错误，实际为 {:?}", other),
+        }
+    }
+}
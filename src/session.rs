@@ -0,0 +1,529 @@
+use crate::error::{DBError, Result};
+use crate::planner::cost::{AccessPathHint, DEFAULT_INDEX_SELECTIVITY_THRESHOLD};
+use crate::storage::table::{ColumnDef, DataType, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, SystemTime};
+
+/// 会话查询历史伪表的表名
+pub const QUERY_HISTORY_TABLE: &str = "_query_history";
+
+/// 查询历史伪表的列定义
+pub fn query_history_columns() -> Vec<ColumnDef> {
+    let column = |name: &str, data_type: DataType| ColumnDef {
+        name: name.to_string(),
+        data_type,
+        not_null: true,
+        unique: false,
+        is_primary: false,
+        auto_increment: false,
+        default: None,
+        references: None,
+    };
+    vec![
+        column("statement", DataType::Varchar(Some(1024))),
+        column("started_at", DataType::Int(None)),
+        column("duration_ms", DataType::Int(None)),
+        column("rows", DataType::Int(None)),
+        column("success", DataType::Int(None)),
+        column("forbidden", DataType::Int(None)),
+    ]
+}
+
+/// 查询历史最多保留的语句条数
+pub const QUERY_HISTORY_CAPACITY: usize = 1000;
+
+/// ORDER BY 排序缓冲区的默认大小（字节），超过后排序数据溢出到磁盘
+pub const DEFAULT_SORT_BUFFER_SIZE: usize = 256 * 1024 * 1024;
+
+/// 会话变量名
+pub const ACCESS_PATH_VARIABLE: &str = "access_path";
+pub const INDEX_SELECTIVITY_THRESHOLD_VARIABLE: &str = "index_selectivity_threshold";
+pub const SQL_MODE_VARIABLE: &str = "sql_mode";
+pub const QUERY_HISTORY_VARIABLE: &str = "query_history";
+pub const SORT_BUFFER_SIZE_VARIABLE: &str = "sort_buffer_size";
+pub const MAX_RESULT_ROWS_VARIABLE: &str = "max_result_rows";
+pub const TRUNCATE_LONG_STRINGS_VARIABLE: &str = "truncate_long_strings";
+
+/// 全部会话变量，按名称排序
+pub const SESSION_VARIABLES: &[VariableSpec] = &[
+    VariableSpec {
+        name: ACCESS_PATH_VARIABLE,
+        kind: VariableKind::Enum(&["AUTO", "INDEX", "SCAN"]),
+        default: VariableValue::Enum("AUTO"),
+    },
+    VariableSpec {
+        name: INDEX_SELECTIVITY_THRESHOLD_VARIABLE,
+        kind: VariableKind::Integer { min: 0, max: 100 },
+        default: VariableValue::Integer((DEFAULT_INDEX_SELECTIVITY_THRESHOLD * 100.0) as i64),
+    },
+    VariableSpec {
+        name: MAX_RESULT_ROWS_VARIABLE,
+        kind: VariableKind::Integer {
+            min: 0,
+            max: i32::MAX as i64,
+        },
+        default: VariableValue::Integer(0),
+    },
+    VariableSpec {
+        name: QUERY_HISTORY_VARIABLE,
+        kind: VariableKind::Bool,
+        default: VariableValue::Bool(true),
+    },
+    VariableSpec {
+        name: SORT_BUFFER_SIZE_VARIABLE,
+        kind: VariableKind::Integer {
+            min: 1,
+            max: i32::MAX as i64,
+        },
+        default: VariableValue::Integer(DEFAULT_SORT_BUFFER_SIZE as i64),
+    },
+    VariableSpec {
+        name: SQL_MODE_VARIABLE,
+        kind: VariableKind::Enum(&["LENIENT", "STRICT"]),
+        default: VariableValue::Enum("LENIENT"),
+    },
+    VariableSpec {
+        name: TRUNCATE_LONG_STRINGS_VARIABLE,
+        kind: VariableKind::Bool,
+        default: VariableValue::Bool(false),
+    },
+];
+
+/// 查询历史中语句文本的最大长度（字节）
+const MAX_HISTORY_STATEMENT_LEN: usize = 1024;
+
+/// SQL 模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SqlMode {
+    /// 宽松模式：可恢复的问题记录为警告
+    #[default]
+    Lenient,
+    /// 严格模式：可恢复的问题直接报错
+    Strict,
+}
+
+/// 警告级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningLevel {
+    Note,
+    Warning,
+}
+
+impl fmt::Display for WarningLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningLevel::Note => write!(f, "Note"),
+            WarningLevel::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+/// 一条警告，对应 SHOW WARNINGS 的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub level: WarningLevel,
+    /// 与 MySQL 对应的错误码
+    pub code: u32,
+    pub message: String,
+}
+
+/// 会话变量的取值类型及约束
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableKind {
+    /// ON / OFF
+    Bool,
+    /// 闭区间内的整数
+    Integer { min: i64, max: i64 },
+    /// 给定取值之一，不区分大小写
+    Enum(&'static [&'static str]),
+}
+
+/// 会话变量的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableValue {
+    Bool(bool),
+    Integer(i64),
+    Enum(&'static str),
+}
+
+impl fmt::Display for VariableValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableValue::Bool(true) => write!(f, "ON"),
+            VariableValue::Bool(false) => write!(f, "OFF"),
+            VariableValue::Integer(n) => write!(f, "{}", n),
+            VariableValue::Enum(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// 会话变量的定义
+#[derive(Debug)]
+pub struct VariableSpec {
+    pub name: &'static str,
+    pub kind: VariableKind,
+    pub default: VariableValue,
+}
+
+impl VariableSpec {
+    /// 按名称查找会话变量，不区分大小写
+    pub fn lookup(name: &str) -> Option<&'static VariableSpec> {
+        SESSION_VARIABLES
+            .iter()
+            .find(|spec| spec.name.eq_ignore_ascii_case(name))
+    }
+
+    /// 把 SET 语句中的值转换为变量的取值，`None` 表示 DEFAULT
+    pub fn parse(&self, value: Option<&Value>) -> Result<VariableValue> {
+        let Some(value) = value else {
+            return Ok(self.default);
+        };
+        let invalid = |allowed: String| {
+            DBError::Execution(format!(
+                "变量 '{}' 不能设置为 '{}'，{}",
+                self.name, value, allowed
+            ))
+        };
+
+        match self.kind {
+            VariableKind::Bool => match value {
+                Value::Boolean(b) => Ok(VariableValue::Bool(*b)),
+                Value::Int(0) => Ok(VariableValue::Bool(false)),
+                Value::Int(1) => Ok(VariableValue::Bool(true)),
+                Value::String(s) if s.eq_ignore_ascii_case("ON") => Ok(VariableValue::Bool(true)),
+                Value::String(s) if s.eq_ignore_ascii_case("OFF") => {
+                    Ok(VariableValue::Bool(false))
+                }
+                _ => Err(invalid("取值只能是 ON 或 OFF".to_string())),
+            },
+            VariableKind::Integer { min, max } => match value {
+                Value::Int(n) if (min..=max).contains(&(*n as i64)) => {
+                    Ok(VariableValue::Integer(*n as i64))
+                }
+                _ => Err(invalid(format!("取值范围为 [{}, {}]", min, max))),
+            },
+            VariableKind::Enum(names) => match value {
+                Value::String(s) => names
+                    .iter()
+                    .find(|name| name.eq_ignore_ascii_case(s))
+                    .map(|name| VariableValue::Enum(name))
+                    .ok_or_else(|| invalid(format!("取值只能是 {}", names.join(", ")))),
+                _ => Err(invalid(format!("取值只能是 {}", names.join(", ")))),
+            },
+        }
+    }
+}
+
+/// 查询历史中的一条记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// 语句文本（超过 1KB 时截断）
+    pub statement: String,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    /// 返回或影响的行数
+    pub rows: usize,
+    pub success: bool,
+    /// 被语句守卫拒绝（未执行），此时 `success` 为 false
+    pub forbidden: bool,
+}
+
+/// 单条语句的执行限制（`--max-exec-millis`、`--max-result-rows`、`.set`），None 表示不限制
+///
+/// 与会话变量 `max_result_rows` 只截断返回的结果不同，超过这里的限制时语句中止并报错。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExecutionLimits {
+    /// 语句的最长执行时间
+    pub max_duration: Option<Duration>,
+    /// SELECT / UPDATE / DELETE 最多扫描的行数
+    pub max_rows: Option<usize>,
+}
+
+/// 会话状态：跨语句保留的设置和诊断信息
+#[derive(Debug)]
+pub struct Session {
+    /// 会话变量的当前值，包含 [`SESSION_VARIABLES`] 中的全部变量
+    variables: BTreeMap<&'static str, VariableValue>,
+    /// 上一条语句产生的警告
+    warnings: Vec<Warning>,
+    /// 最近执行的语句，最旧的在前
+    query_history: VecDeque<HistoryEntry>,
+    /// 每条语句的执行时间和扫描行数限制
+    execution_limits: ExecutionLimits,
+    /// 取消标志，置位后正在执行的语句在处理下一行之前中止
+    cancel_flag: Option<Arc<AtomicBool>>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            variables: SESSION_VARIABLES
+                .iter()
+                .map(|spec| (spec.name, spec.default))
+                .collect(),
+            warnings: Vec::new(),
+            query_history: VecDeque::new(),
+            execution_limits: ExecutionLimits::default(),
+            cancel_flag: None,
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 会话变量的当前值
+    pub fn variable(&self, name: &str) -> Option<VariableValue> {
+        let spec = VariableSpec::lookup(name)?;
+        self.variables.get(spec.name).copied()
+    }
+
+    /// 全部会话变量，按名称排序
+    pub fn variables(&self) -> impl Iterator<Item = (&'static str, VariableValue)> + '_ {
+        self.variables.iter().map(|(name, value)| (*name, *value))
+    }
+
+    /// 设置会话变量，取值应来自 [`VariableSpec::parse`]
+    pub fn set_variable(&mut self, spec: &VariableSpec, value: VariableValue) {
+        self.variables.insert(spec.name, value);
+        // 关闭查询历史时同时清空已有记录
+        if spec.name == QUERY_HISTORY_VARIABLE && value == VariableValue::Bool(false) {
+            self.query_history.clear();
+        }
+    }
+
+    fn set_named(&mut self, name: &str, value: VariableValue) {
+        let spec = VariableSpec::lookup(name).expect("会话变量未注册");
+        self.set_variable(spec, value);
+    }
+
+    fn integer(&self, name: &str) -> i64 {
+        match self.variable(name) {
+            Some(VariableValue::Integer(n)) => n,
+            other => unreachable!("会话变量 {} 不是整数: {:?}", name, other),
+        }
+    }
+
+    pub fn sql_mode(&self) -> SqlMode {
+        match self.variable(SQL_MODE_VARIABLE) {
+            Some(VariableValue::Enum("STRICT")) => SqlMode::Strict,
+            _ => SqlMode::Lenient,
+        }
+    }
+
+    pub fn set_sql_mode(&mut self, sql_mode: SqlMode) {
+        let name = match sql_mode {
+            SqlMode::Lenient => "LENIENT",
+            SqlMode::Strict => "STRICT",
+        };
+        self.set_named(SQL_MODE_VARIABLE, VariableValue::Enum(name));
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.sql_mode() == SqlMode::Strict
+    }
+
+    /// 记录一条警告
+    pub fn add_warning(&mut self, level: WarningLevel, code: u32, message: String) {
+        self.warnings.push(Warning {
+            level,
+            code,
+            message,
+        });
+    }
+
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// 清空警告（每条新语句执行前调用）
+    pub fn clear_warnings(&mut self) {
+        self.warnings.clear();
+    }
+
+    pub fn query_history_enabled(&self) -> bool {
+        self.variable(QUERY_HISTORY_VARIABLE) == Some(VariableValue::Bool(true))
+    }
+
+    /// 开关查询历史；关闭时同时清空已有记录
+    pub fn set_query_history_enabled(&mut self, enabled: bool) {
+        self.set_named(QUERY_HISTORY_VARIABLE, VariableValue::Bool(enabled));
+    }
+
+    pub fn sort_buffer_size(&self) -> usize {
+        self.integer(SORT_BUFFER_SIZE_VARIABLE) as usize
+    }
+
+    /// 设置排序缓冲区大小，至少为 1 字节
+    pub fn set_sort_buffer_size(&mut self, size: usize) {
+        let size = size.clamp(1, i32::MAX as usize) as i64;
+        self.set_named(SORT_BUFFER_SIZE_VARIABLE, VariableValue::Integer(size));
+    }
+
+    /// SELECT 最多返回的行数，0（默认）表示不限制
+    pub fn max_result_rows(&self) -> Option<usize> {
+        match self.integer(MAX_RESULT_ROWS_VARIABLE) {
+            0 => None,
+            n => Some(n as usize),
+        }
+    }
+
+    /// 单表 SELECT 按主键取行还是全表扫描：AUTO 按估算的命中比例选择，INDEX / SCAN 强制其一
+    pub fn access_path_hint(&self) -> AccessPathHint {
+        match self.variable(ACCESS_PATH_VARIABLE) {
+            Some(VariableValue::Enum("INDEX")) => AccessPathHint::ForceIndex,
+            Some(VariableValue::Enum("SCAN")) => AccessPathHint::ForceScan,
+            _ => AccessPathHint::Auto,
+        }
+    }
+
+    /// 估算的命中比例低于该值（0.0 ~ 1.0）时才按主键取行
+    pub fn index_selectivity_threshold(&self) -> f64 {
+        self.integer(INDEX_SELECTIVITY_THRESHOLD_VARIABLE) as f64 / 100.0
+    }
+
+    pub fn execution_limits(&self) -> ExecutionLimits {
+        self.execution_limits
+    }
+
+    pub fn set_execution_limits(&mut self, limits: ExecutionLimits) {
+        self.execution_limits = limits;
+    }
+
+    pub fn cancel_flag(&self) -> Option<&Arc<AtomicBool>> {
+        self.cancel_flag.as_ref()
+    }
+
+    /// 安装取消标志：其它线程或信号处理函数置位后，语句以 [`DBError::Cancelled`] 中止
+    ///
+    /// 标志不会被自动清除，置位期间之后的语句也会被取消，由调用方在下一条语句前复位。
+    pub fn set_cancel_flag(&mut self, flag: Option<Arc<AtomicBool>>) {
+        self.cancel_flag = flag;
+    }
+
+    /// 超过 VARCHAR 长度的字符串是否截断并警告（默认报错）
+    pub fn truncate_long_strings(&self) -> bool {
+        self.variable(TRUNCATE_LONG_STRINGS_VARIABLE) == Some(VariableValue::Bool(true))
+    }
+
+    /// 记录一条已执行的语句，超过容量时淘汰最旧的记录
+    pub fn record_query(&mut self, mut entry: HistoryEntry) {
+        if !self.query_history_enabled() {
+            return;
+        }
+
+        if entry.statement.len() > MAX_HISTORY_STATEMENT_LEN {
+            let mut end = MAX_HISTORY_STATEMENT_LEN;
+            while !entry.statement.is_char_boundary(end) {
+                end -= 1;
+            }
+            entry.statement.truncate(end);
+        }
+
+        if self.query_history.len() >= QUERY_HISTORY_CAPACITY {
+            self.query_history.pop_front();
+        }
+        self.query_history.push_back(entry);
+    }
+
+    pub fn query_history(&self) -> &VecDeque<HistoryEntry> {
+        &self.query_history
+    }
+
+    pub fn clear_query_history(&mut self) {
+        self.query_history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(statement: &str) -> HistoryEntry {
+        HistoryEntry {
+            statement: statement.to_string(),
+            started_at: SystemTime::UNIX_EPOCH,
+            duration: Duration::ZERO,
+            rows: 0,
+            success: true,
+            forbidden: false,
+        }
+    }
+
+    #[test]
+    fn test_query_history_capacity() {
+        let mut session = Session::new();
+        for i in 0..QUERY_HISTORY_CAPACITY + 5 {
+            session.record_query(entry(&format!("SELECT {}", i)));
+        }
+
+        let history = session.query_history();
+        assert_eq!(history.len(), QUERY_HISTORY_CAPACITY);
+        assert_eq!(history.front().unwrap().statement, "SELECT 5");
+        assert_eq!(
+            history.back().unwrap().statement,
+            format!("SELECT {}", QUERY_HISTORY_CAPACITY + 4)
+        );
+
+        session.clear_query_history();
+        assert!(session.query_history().is_empty());
+    }
+
+    #[test]
+    fn test_query_history_truncates_on_char_boundary() {
+        let mut session = Session::new();
+        // 每个汉字 3 字节，1024 不是 3 的倍数
+        session.record_query(entry(&"值".repeat(400)));
+
+        let statement = &session.query_history()[0].statement;
+        assert_eq!(statement.len(), 1023);
+        assert!(statement.chars().all(|c| c == '值'));
+    }
+
+    #[test]
+    fn test_session_variable_validation() {
+        let spec = |name: &str| VariableSpec::lookup(name).unwrap();
+        let parse = |name: &str, value: Value| spec(name).parse(Some(&value));
+
+        assert_eq!(
+            parse("SQL_MODE", Value::String("strict".to_string())).unwrap(),
+            VariableValue::Enum("STRICT")
+        );
+        assert_eq!(
+            parse("query_history", Value::String("off".to_string())).unwrap(),
+            VariableValue::Bool(false)
+        );
+        assert_eq!(
+            parse("query_history", Value::Int(1)).unwrap(),
+            VariableValue::Bool(true)
+        );
+        assert_eq!(
+            spec("sort_buffer_size").parse(None).unwrap(),
+            VariableValue::Integer(DEFAULT_SORT_BUFFER_SIZE as i64)
+        );
+
+        // 错误信息中给出允许的取值
+        let err = parse("max_result_rows", Value::Int(-1)).unwrap_err();
+        assert!(err.to_string().contains("[0, 2147483647]"), "{}", err);
+        let err = parse("sql_mode", Value::String("ANSI".to_string())).unwrap_err();
+        assert!(err.to_string().contains("LENIENT, STRICT"), "{}", err);
+        let err = parse("query_history", Value::Int(2)).unwrap_err();
+        assert!(err.to_string().contains("ON 或 OFF"), "{}", err);
+
+        assert!(VariableSpec::lookup("no_such_variable").is_none());
+    }
+
+    #[test]
+    fn test_query_history_disabled() {
+        let mut session = Session::new();
+        session.record_query(entry("SELECT 1"));
+        session.set_query_history_enabled(false);
+        session.record_query(entry("SELECT 2"));
+        assert!(session.query_history().is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::error::{DBError, Result};
+use crate::executor::DisplayLimits;
+use crate::output::OutputFormat;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// 交互模式的历史记录文件名，默认放在数据目录下
+pub const HISTORY_FILE_NAME: &str = "simple_db_history.txt";
+
+/// 交互模式设置文件名，放在数据目录下
+pub const SETTINGS_FILE_NAME: &str = "simple_db_settings.json";
+
+/// 交互模式中用 `.format`、`.set` 修改、退出时保存的设置
+///
+/// 文件中缺少的项取默认值，`null` 表示不限制。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplSettings {
+    pub format: OutputFormat,
+    pub max_rows: Option<usize>,
+    pub max_width: Option<usize>,
+}
+
+impl Default for ReplSettings {
+    fn default() -> Self {
+        let limits = DisplayLimits::default();
+        Self {
+            format: OutputFormat::default(),
+            max_rows: limits.max_rows,
+            max_width: limits.max_width,
+        }
+    }
+}
+
+impl ReplSettings {
+    /// 读取设置文件，文件不存在时返回 None，内容无法解析时报错
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(DBError::IO(format!("无法读取设置文件: {}", e))),
+        };
+        let settings: Self = serde_json::from_str(&text)
+            .map_err(|e| DBError::Other(format!("无法解析设置文件: {}", e)))?;
+        if settings
+            .max_width
+            .is_some_and(|width| width < DisplayLimits::MIN_WIDTH)
+        {
+            return Err(DBError::Other(format!(
+                "设置文件中的 max_width 不能小于 {}",
+                DisplayLimits::MIN_WIDTH
+            )));
+        }
+        Ok(Some(settings))
+    }
+
+    /// 写入设置文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| DBError::Other(format!("无法序列化设置: {}", e)))?;
+        fs::write(path, text).map_err(|e| DBError::IO(format!("无法写入设置文件: {}", e)))
+    }
+
+    /// 表格输出的行数和列宽限制
+    pub fn display_limits(&self) -> DisplayLimits {
+        DisplayLimits {
+            max_rows: self.max_rows,
+            max_width: self.max_width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(SETTINGS_FILE_NAME);
+        assert_eq!(ReplSettings::load(&path).unwrap(), None);
+
+        let settings = ReplSettings {
+            format: OutputFormat::Json,
+            max_rows: None,
+            max_width: Some(12),
+        };
+        settings.save(&path).unwrap();
+        assert_eq!(ReplSettings::load(&path).unwrap(), Some(settings));
+
+        // 缺少的项取默认值
+        fs::write(&path, r#"{"format": "csv"}"#).unwrap();
+        let loaded = ReplSettings::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.format, OutputFormat::Csv);
+        assert_eq!(loaded.display_limits(), DisplayLimits::default());
+    }
+
+    #[test]
+    fn test_corrupt_settings_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(SETTINGS_FILE_NAME);
+        for text in [
+            "not json",
+            r#"{"format": "xml"}"#,
+            r#"{"max_rows": -1}"#,
+            r#"{"max_width": 2}"#,
+        ] {
+            fs::write(&path, text).unwrap();
+            assert!(ReplSettings::load(&path).is_err(), "{}", text);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod backup;
 pub mod catalog;
 mod database;
 pub mod io;
@@ -7,19 +8,55 @@ pub mod table;
 pub mod transaction;
 
 use crate::error::{DBError, Result};
+use backup::{BackupSummary, OnlineBackup};
 use database::Database;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
 use std::path::{Path, PathBuf};
-use table::{ColumnDef, Record, RecordId, Table, Value};
+use std::time::SystemTime;
+use table::{
+    AlterColumn, CheckConstraint, ColumnDef, CompactionSummary, IndexDef, PrimaryKeyStats, Record,
+    RecordId, Table, TableStats, TableVerification, Truncation, Value,
+};
+use transaction::{Transaction, UndoEntry};
 
 /// 存储引擎 - 负责数据存储和访问
+///
+/// 读取记录的方法只需要共享引用，可以在多个线程中同时调用，各数据库的缓冲池有自己的锁，
+/// 读取页面时在这里逐个访问；修改数据需要独占引用。与写入并发时在外层加读写锁，
+/// 加锁顺序固定为先存储引擎（表）后缓冲池（页面）：缓冲池的锁只在单次表操作内部持有，
+/// 持有期间不会再去获取外层的锁，因此不会死锁。
 pub struct StorageEngine {
     /// 多个数据库
-    databases: HashMap<String, Database>,
+    databases: BTreeMap<String, Database>,
     /// 当前选中的数据库
     current_database: Option<String>,
     /// 基础数据目录
     base_dir: PathBuf,
+    /// 上次成功保存之后是否有过修改
+    unsaved_changes: bool,
+    /// 上次成功保存（或加载）的时间
+    last_saved: SystemTime,
+    /// BEGIN 开启、尚未 COMMIT 或 ROLLBACK 的事务
+    transaction: Option<Transaction>,
+    /// 加载时跳过的、不是数据库的子目录
+    skipped: Vec<SkippedDirectory>,
+}
+
+/// 数据目录中没有作为数据库加载的子目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedDirectory {
+    pub name: String,
+    pub reason: String,
+}
+
+/// 数据目录中的一项，见 [`StorageEngine::databases`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataDirEntry {
+    /// 已加载（或本次运行中创建）的数据库
+    Database(String),
+    /// 加载时跳过的子目录
+    Skipped(SkippedDirectory),
 }
 
 impl StorageEngine {
@@ -36,9 +73,13 @@ impl StorageEngine {
         let db_name = db_name.unwrap_or("default");
 
         let mut storage_engine = Self {
-            databases: HashMap::new(),
+            databases: BTreeMap::new(),
             current_database: None,
             base_dir,
+            unsaved_changes: false,
+            last_saved: SystemTime::now(),
+            transaction: None,
+            skipped: Vec::new(),
         };
 
         storage_engine.load()?;
@@ -64,47 +105,300 @@ impl StorageEngine {
     }
 
     /// 加载所有数据库
+    ///
+    /// 数据目录不存在时创建；存在时必须是可写的目录。只有含数据文件或元数据文件的子目录
+    /// 作为数据库加载，其余子目录跳过并记录下来，见 [`databases`](Self::databases)。
     fn load(&mut self) -> Result<()> {
-        if !self.base_dir.exists() {
-            std::fs::create_dir_all(&self.base_dir)
-                .map_err(|e| DBError::IO(format!("无法创建数据库目录: {}", e)))?;
-        }
-
-        // 读取基础目录中的所有子目录
-        let entries = std::fs::read_dir(&self.base_dir)
-            .map_err(|e| DBError::IO(format!("无法读取数据库目录: {}", e)))?;
-
+        self.prepare_base_dir()?;
+
+        let entries = std::fs::read_dir(&self.base_dir).map_err(|e| {
+            DBError::Config(format!(
+                "无法读取数据目录 '{}': {}",
+                self.base_dir.display(),
+                e
+            ))
+        })?;
+
+        // 先找出全部数据库目录再逐个加载，不会加载到一半才发现无关的目录
+        let mut found = Vec::new();
         for entry in entries {
             let entry = entry.map_err(|e| DBError::IO(format!("无法读取数据库目录项: {}", e)))?;
             let path = entry.path();
-
-            if path.is_dir() {
-                if let Some(db_name) = path.file_name().and_then(|n| n.to_str()) {
-                    // 加载数据库
-                    let mut database =
-                        Database::new(db_name.to_string(), self.get_db_path(db_name))?;
-                    database.load()?;
-                    self.databases.insert(db_name.to_string(), database);
+            if !path.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            match path.file_name().and_then(|n| n.to_str()) {
+                Some(db_name) if Self::is_database_dir(&path, db_name) => {
+                    found.push(db_name.to_string())
                 }
+                Some(_) => self.skipped.push(SkippedDirectory {
+                    name,
+                    reason: "没有数据文件或元数据文件".to_string(),
+                }),
+                None => self.skipped.push(SkippedDirectory {
+                    name,
+                    reason: "目录名不是有效的 UTF-8".to_string(),
+                }),
             }
         }
+        self.skipped.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for db_name in found {
+            let mut database = Database::new(db_name.clone(), self.get_db_path(&db_name))?;
+            database.load()?;
+            self.databases.insert(db_name, database);
+        }
 
         Ok(())
     }
 
+    /// 确保数据目录是可写的目录，不存在时创建
+    fn prepare_base_dir(&self) -> Result<()> {
+        let dir = self.base_dir.display();
+        match std::fs::metadata(&self.base_dir) {
+            Ok(metadata) if !metadata.is_dir() => {
+                return Err(DBError::Config(format!(
+                    "--data-dir 指向的 '{}' 是一个文件，请指定一个目录",
+                    dir
+                )));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return std::fs::create_dir_all(&self.base_dir).map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::PermissionDenied {
+                        DBError::Config(format!("没有权限创建数据目录 '{}'", dir))
+                    } else {
+                        DBError::Config(format!("无法创建数据目录 '{}': {}", dir, e))
+                    }
+                });
+            }
+            Err(e) => {
+                return Err(DBError::Config(format!(
+                    "无法访问数据目录 '{}': {}",
+                    dir, e
+                )));
+            }
+        }
+
+        // 用一个临时文件检查是否可写，之后创建数据库和保存时才不会失败
+        let probe = self.base_dir.join(".simple_db_write_check");
+        std::fs::write(&probe, b"")
+            .and_then(|()| std::fs::remove_file(&probe))
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    DBError::Config(format!("没有权限写入数据目录 '{}'", dir))
+                } else {
+                    DBError::Config(format!("无法写入数据目录 '{}': {}", dir, e))
+                }
+            })
+    }
+
+    /// 子目录中有数据文件或以目录名命名的元数据文件时视为数据库目录
+    fn is_database_dir(path: &Path, db_name: &str) -> bool {
+        path.join("data.db").is_file() || path.join(format!("{}.meta", db_name)).is_file()
+    }
+
     /// 保存所有数据库
+    ///
+    /// 各数据库的脏页在各自的线程中序列化并写入磁盘；全部写完后再依次写入元数据，
+    /// 保存中途崩溃时元数据不会记下还没有写入的页面。没有修改的数据库整个跳过。
+    /// 有活动的事务时报错：页面中有未提交的修改，保存后崩溃会把它们留在磁盘上。
     pub fn save(&mut self) -> Result<()> {
-        // 保存每个数据库
+        if self.transaction.is_some() {
+            return Err(DBError::Execution(
+                "事务中不能保存，请先 COMMIT 或 ROLLBACK".to_string(),
+            ));
+        }
+        let flushed: Vec<Result<()>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .databases
+                .values_mut()
+                .filter(|database| database.is_dirty())
+                .map(|database| scope.spawn(|| database.flush_pages()))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| {
+                    worker.join().unwrap_or_else(|_| {
+                        Err(DBError::IO("写入数据页的线程异常退出".to_string()))
+                    })
+                })
+                .collect()
+        });
+        flushed.into_iter().collect::<Result<()>>()?;
+
+        for database in self.databases.values_mut() {
+            database.save_metadata()?;
+        }
+
+        self.unsaved_changes = false;
+        self.last_saved = SystemTime::now();
+        Ok(())
+    }
+
+    /// 记录有尚未保存的修改，下次成功 [`save`](Self::save) 后清除
+    pub fn mark_unsaved(&mut self) {
+        self.unsaved_changes = true;
+    }
+
+    /// 上次成功保存之后是否有过修改
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.unsaved_changes
+    }
+
+    /// 上次成功保存的时间，从未保存时为加载数据的时间
+    pub fn last_saved(&self) -> SystemTime {
+        self.last_saved
+    }
+
+    /// 开启事务，已有活动的事务时报错
+    pub fn begin_transaction(&mut self) -> Result<()> {
+        if self.transaction.is_some() {
+            return Err(DBError::Execution(
+                "已有活动的事务，请先 COMMIT 或 ROLLBACK".to_string(),
+            ));
+        }
+        self.transaction = Some(Transaction::new());
+        self.hold_transaction_pages(true);
+        Ok(())
+    }
+
+    /// 提交事务：丢弃撤销日志，没有活动的事务时什么也不做
+    pub fn commit_transaction(&mut self) {
+        self.transaction = None;
+        self.hold_transaction_pages(false);
+    }
+
+    /// 事务期间让各数据库的缓冲池扣留被修改的页面，未提交的修改不会因置换写入磁盘
+    fn hold_transaction_pages(&mut self, hold: bool) {
         for database in self.databases.values_mut() {
-            database.save()?;
+            let buffer_manager = database.get_buffer_manager_mut();
+            if hold {
+                buffer_manager.begin_transaction();
+            } else {
+                buffer_manager.end_transaction();
+            }
+        }
+    }
+
+    /// 回滚事务：按相反顺序执行撤销日志中的逆操作，没有活动的事务时什么也不做
+    ///
+    /// 某一项撤销失败时继续撤销其余各项，最后返回第一个错误。
+    pub fn rollback_transaction(&mut self) -> Result<()> {
+        let Some(transaction) = self.transaction.take() else {
+            return Ok(());
+        };
+
+        // 重新插入的行会得到新的记录ID，更早的日志项按新ID找到它
+        let mut moved: HashMap<(String, String, RecordId), RecordId> = HashMap::new();
+        let mut first_error = None;
+        for entry in transaction.into_undo_log() {
+            let (database, table) = entry.table();
+            let key = |record_id: &RecordId| (database.to_string(), table.to_string(), *record_id);
+            let result = match &entry {
+                UndoEntry::Insert { record_id, .. } => {
+                    let record_id = moved.get(&key(record_id)).unwrap_or(record_id);
+                    self.get_database_mut(database)
+                        .and_then(|db| db.delete_record(table, *record_id))
+                }
+                UndoEntry::Delete {
+                    record_id, values, ..
+                } => self
+                    .get_database_mut(database)
+                    .and_then(|db| db.insert_record(table, values.clone()))
+                    .map(|new_id| {
+                        moved.insert(key(record_id), new_id);
+                    }),
+                UndoEntry::Update {
+                    record_id,
+                    updated_id,
+                    values,
+                    ..
+                } => {
+                    let current_id = moved.get(&key(updated_id)).unwrap_or(updated_id);
+                    self.get_database_mut(database)
+                        .and_then(|db| {
+                            let set_pairs = db
+                                .get_table(table)?
+                                .columns()
+                                .iter()
+                                .map(|column| column.name.clone())
+                                .zip(values.iter().cloned())
+                                .collect();
+                            db.update_record(table, *current_id, &set_pairs)
+                        })
+                        .map(|restored_id| {
+                            if restored_id != *record_id {
+                                moved.insert(key(record_id), restored_id);
+                            }
+                        })
+                }
+                UndoEntry::CreateTable { .. } => self
+                    .get_database_mut(database)
+                    .and_then(|db| db.drop_table(table)),
+            };
+            if let Err(e) = result {
+                first_error.get_or_insert(e);
+            }
+        }
+        self.hold_transaction_pages(false);
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// 是否有活动的事务
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    /// 事务中记录对表 `table_name` 执行的操作，`entry` 接收表所在的数据库名和不带限定的表名
+    fn log_undo(&mut self, table_name: &str, entry: impl FnOnce(String, String) -> UndoEntry) {
+        if self.transaction.is_none() {
+            return;
+        }
+        let Ok((database, table)) = self.table_database(table_name) else {
+            return;
+        };
+        let database = database.get_name().to_string();
+        if let Some(transaction) = &mut self.transaction {
+            transaction.record(entry(database, table));
         }
+    }
 
+    /// 事务中只允许修改本事务创建的表的结构，其他表的结构变更无法撤销
+    fn ensure_schema_change_allowed(&self, table_name: &str, statement: &str) -> Result<()> {
+        let Some(transaction) = &self.transaction else {
+            return Ok(());
+        };
+        let (database, table) = self.table_database(table_name)?;
+        if transaction.created_table(database.get_name(), &table) {
+            Ok(())
+        } else {
+            Err(DBError::Execution(format!(
+                "事务中不能执行 {}，请先 COMMIT 或 ROLLBACK",
+                statement
+            )))
+        }
+    }
+
+    /// 事务中不允许创建或删除数据库
+    fn ensure_no_transaction(&self, statement: &str) -> Result<()> {
+        if self.transaction.is_some() {
+            return Err(DBError::Execution(format!(
+                "事务中不能执行 {}，请先 COMMIT 或 ROLLBACK",
+                statement
+            )));
+        }
         Ok(())
     }
 
     // 以下是数据库管理方法
     /// 创建数据库
     pub fn create_database(&mut self, name: String) -> Result<()> {
+        self.ensure_no_transaction("CREATE DATABASE")?;
         if self.databases.contains_key(&name) {
             return Err(DBError::Schema(format!("数据库 '{}' 已存在", name)));
         }
@@ -114,6 +408,7 @@ impl StorageEngine {
         let database = Database::new(name.clone(), &db_path)?;
 
         self.databases.insert(name.clone(), database);
+        log::info!("创建数据库 '{}'", name);
 
         // 如果是第一个创建的数据库，自动设为当前数据库
         if self.current_database.is_none() {
@@ -125,11 +420,19 @@ impl StorageEngine {
 
     /// 删除数据库
     pub fn drop_database(&mut self, name: &str) -> Result<()> {
+        self.ensure_no_transaction("DROP DATABASE")?;
         if !self.databases.contains_key(name) {
             return Err(DBError::NotFound(format!("数据库 '{}' 不存在", name)));
         }
 
-        self.databases.remove(name);
+        // 先移出内存，脏页在 Database 析构时写回，随后再删除整个目录
+        drop(self.databases.remove(name));
+        let db_path = self.get_db_path(name);
+        if db_path.exists() {
+            std::fs::remove_dir_all(&db_path)
+                .map_err(|e| DBError::IO(format!("无法删除数据库目录: {}", e)))?;
+        }
+        log::info!("删除数据库 '{}'", name);
 
         // 如果删除的是当前数据库，重置当前数据库选择
         if self.current_database.as_deref() == Some(name) {
@@ -139,6 +442,30 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// 把一个数据库导出为独立的快照文件，包含目录和全部数据页，也包含尚未保存的修改
+    ///
+    /// 文件带有魔数和格式版本，用 [`import_database`](Self::import_database) 导入。
+    pub fn export_database<P: AsRef<Path>>(
+        &mut self,
+        name: &str,
+        path: P,
+    ) -> Result<BackupSummary> {
+        log::info!("导出数据库 '{}' 到 {}", name, path.as_ref().display());
+        OnlineBackup::begin_database(self, name, path)?.finish(self)
+    }
+
+    /// 从快照文件重建数据库，返回数据库名
+    ///
+    /// `new_name` 为 None 时沿用快照中的数据库名，同名数据库已存在时报错。
+    /// 记录重新插入，页ID由新数据库重新分配。
+    pub fn import_database<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        new_name: Option<&str>,
+    ) -> Result<String> {
+        backup::import_backup(self, path, new_name)
+    }
+
     /// 更改当前数据库为
     pub fn use_database(&mut self, name: &str) -> Result<()> {
         if !self.databases.contains_key(name) {
@@ -207,85 +534,343 @@ impl StorageEngine {
             .ok_or_else(|| DBError::NotFound(format!("当前数据库 '{}' 不存在", name)))
     }
 
-    // 以下是一些代理方法 - 转发到当前数据库
+    /// 解析可带数据库限定的表名，返回表所在的数据库和它在其中的表名
+    ///
+    /// `db.table` 指向数据库 `db`（不存在时报错），不带限定的表名指向当前数据库；
+    /// 表名大小写不同时取数据库中已有的写法，见 [`Database::resolve_table_name`]。
+    pub fn table_database(&self, name: &str) -> Result<(&Database, String)> {
+        let (database, table) = match name.split_once('.') {
+            Some((database, table)) => (self.get_database(database)?, table),
+            None => (self.current_database()?, name),
+        };
+        Ok((database, database.resolve_table_name(table).to_string()))
+    }
+
+    /// 同 [`table_database`](Self::table_database)，返回可变数据库
+    fn table_database_mut(&mut self, name: &str) -> Result<(&mut Database, String)> {
+        let (database, table) = match name.split_once('.') {
+            Some((database, table)) => (self.get_database_mut(database)?, table),
+            None => (self.current_database_mut()?, name),
+        };
+        let table = database.resolve_table_name(table).to_string();
+        Ok((database, table))
+    }
+
+    // 以下是一些代理方法 - 转发到表名限定的数据库，不带限定时转发到当前数据库
     /// 创建表
     pub fn create_table(&mut self, name: String, columns: Vec<ColumnDef>) -> Result<()> {
-        let database = self.current_database_mut()?;
-        database.create_table(name, columns)
+        let (database, table) = self.table_database_mut(&name)?;
+        log::info!("在数据库 '{}' 中创建表 '{}'", database.get_name(), table);
+        database.create_table(table, columns)?;
+        self.log_undo(&name, |database, table| UndoEntry::CreateTable {
+            database,
+            table,
+        });
+        Ok(())
+    }
+
+    /// 在表上添加索引定义
+    pub fn create_index(&mut self, table_name: &str, index: IndexDef) -> Result<()> {
+        self.ensure_schema_change_allowed(table_name, "CREATE INDEX")?;
+        let (database, table) = self.table_database_mut(table_name)?;
+        log::info!("在表 '{}' 上创建索引 {:?}", table, index);
+        database.create_index(&table, index)
+    }
+
+    /// 在表上添加 CHECK 约束
+    pub fn create_check(&mut self, table_name: &str, check: CheckConstraint) -> Result<()> {
+        self.ensure_schema_change_allowed(table_name, "CREATE TABLE")?;
+        let (database, table) = self.table_database_mut(table_name)?;
+        log::info!("在表 '{}' 上添加 CHECK 约束 {:?}", table, check);
+        database.create_check(&table, check)
     }
 
     /// 删除表
+    ///
+    /// 事务中只能删除本事务创建的表，删除后回滚时不再需要撤销它。
     pub fn drop_table(&mut self, name: &str) -> Result<()> {
-        let database = self.current_database_mut()?;
-        database.drop_table(name)
+        self.ensure_schema_change_allowed(name, "DROP TABLE")?;
+        let (database, table) = self.table_database_mut(name)?;
+        database.drop_table(&table)?;
+        log::info!("从数据库 '{}' 中删除表 '{}'", database.get_name(), table);
+        let database = database.get_name().to_string();
+        if let Some(transaction) = &mut self.transaction {
+            transaction.forget_table(&database, &table);
+        }
+        Ok(())
+    }
+
+    /// 修改表名，`new_name` 是同一数据库中的新表名（不带数据库限定）
+    pub fn rename_table(&mut self, name: &str, new_name: &str) -> Result<()> {
+        self.ensure_schema_change_allowed(name, "RENAME TABLE")?;
+        let (database, table) = self.table_database_mut(name)?;
+        database.rename_table(&table, new_name.to_string())?;
+        log::info!("将表 '{}' 改名为 '{}'", table, new_name);
+        Ok(())
+    }
+
+    /// 修改一张表的列（ALTER TABLE ADD/DROP COLUMN）
+    pub fn alter_table(&mut self, name: &str, operations: &[AlterColumn]) -> Result<()> {
+        self.ensure_schema_change_allowed(name, "ALTER TABLE")?;
+        let (database, table) = self.table_database_mut(name)?;
+        log::info!("修改表 '{}' 的列: {:?}", table, operations);
+        database.alter_table(&table, operations)
+    }
+
+    /// 整理一张表的数据页（VACUUM）
+    pub fn compact_table(&mut self, name: &str) -> Result<CompactionSummary> {
+        self.ensure_schema_change_allowed(name, "VACUUM")?;
+        let (database, table) = self.table_database_mut(name)?;
+        log::info!("整理表 '{}' 的数据页", table);
+        database.compact_table(&table)
+    }
+
+    /// 表的行数、页数、字节数、页面使用率和已删除的槽位数
+    pub fn table_stats(&self, name: &str) -> Result<TableStats> {
+        let (database, table) = self.table_database(name)?;
+        database.table_stats(&table)
+    }
+
+    /// 完整检查所有数据库中记录与表结构是否一致，按 (数据库, 表) 排列
+    pub fn verify_databases(&self) -> Vec<(String, String, TableVerification)> {
+        self.databases
+            .iter()
+            .flat_map(|(db_name, database)| {
+                database
+                    .verify(None)
+                    .into_iter()
+                    .map(move |(table, result)| (db_name.clone(), table, result))
+            })
+            .collect()
     }
 
     /// 获取表
     pub fn get_table(&self, name: &str) -> Result<&Table> {
-        let database = self.current_database()?;
-        database.get_table(name)
+        let (database, table) = self.table_database(name)?;
+        database.get_table(&table)
     }
 
     /// 获取可变表
     pub fn get_table_mut(&mut self, name: &str) -> Result<&mut Table> {
-        let database = self.current_database_mut()?;
-        database.get_table_mut(name)
+        let (database, table) = self.table_database_mut(name)?;
+        database.get_table_mut(&table)
+    }
+
+    /// 获取表的索引定义
+    pub fn get_table_indexes(&self, name: &str) -> Result<Vec<IndexDef>> {
+        Ok(self.get_table(name)?.indexes().to_vec())
+    }
+
+    /// 获取表的 CHECK 约束
+    pub fn get_table_checks(&self, name: &str) -> Result<Vec<CheckConstraint>> {
+        let (database, table) = self.table_database(name)?;
+        database.get_table_checks(&table)
     }
 
     /// 获取表的列定义
     pub fn get_table_columns(&self, name: &str) -> Result<Vec<ColumnDef>> {
-        let database = self.current_database()?;
-        let table = database.get_table(name)?;
-        Ok(table.columns().to_vec())
+        Ok(self.get_table(name)?.columns().to_vec())
     }
 
     // 以下是一些对表记录的操作
     /// 增加一行
     pub fn insert_record(&mut self, table_name: &str, values: Vec<Value>) -> Result<RecordId> {
-        let database = self.current_database_mut()?;
-        database.insert_record(table_name, values)
+        let (database, table) = self.table_database_mut(table_name)?;
+        let record_id = database.insert_record(&table, values)?;
+        self.log_undo(table_name, |database, table| UndoEntry::Insert {
+            database,
+            table,
+            record_id,
+        });
+        Ok(record_id)
+    }
+
+    /// 批量增加多行，整批要么全部写入、要么全部不写入，返回各行的记录ID
+    ///
+    /// 超长字符串整批拒绝，需要截断时使用 [`batch_insert_records`](Self::batch_insert_records)。
+    pub fn insert_records(
+        &mut self,
+        table_name: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> Result<Vec<RecordId>> {
+        self.batch_insert_records(table_name, rows, false)
+            .map(|(record_ids, _)| record_ids)
+    }
+
+    /// 批量增加多行，`truncate` 决定超长字符串是截断还是整批拒绝
+    pub fn batch_insert_records(
+        &mut self,
+        table_name: &str,
+        rows: Vec<Vec<Value>>,
+        truncate: bool,
+    ) -> Result<(Vec<RecordId>, Vec<Truncation>)> {
+        let (database, table) = self.table_database_mut(table_name)?;
+        let (record_ids, truncations) = database.batch_insert_records(&table, rows, truncate)?;
+        for &record_id in &record_ids {
+            self.log_undo(table_name, |database, table| UndoEntry::Insert {
+                database,
+                table,
+                record_id,
+            });
+        }
+        Ok((record_ids, truncations))
     }
 
     /// 删除一行
     pub fn delete_record(&mut self, table_name: &str, record_id: RecordId) -> Result<()> {
-        let database = self.current_database_mut()?;
-        database.delete_record(table_name, record_id)
+        let in_transaction = self.transaction.is_some();
+        let (database, table) = self.table_database_mut(table_name)?;
+        if !in_transaction {
+            return database.delete_record(&table, record_id);
+        }
+        let values = database.get_record(&table, record_id)?.values().to_vec();
+        database.delete_record(&table, record_id)?;
+        self.log_undo(table_name, |database, table| UndoEntry::Delete {
+            database,
+            table,
+            record_id,
+            values,
+        });
+        Ok(())
     }
 
-    /// 更新一行
+    /// 更新一行，返回这一行更新后的记录ID（变大的行可能被搬到其它页面）
     pub fn update_record(
         &mut self,
         table_name: &str,
         record_id: RecordId,
         set_pairs: &Vec<(String, Value)>,
+    ) -> Result<RecordId> {
+        let in_transaction = self.transaction.is_some();
+        let (database, table) = self.table_database_mut(table_name)?;
+        if !in_transaction {
+            return database.update_record(&table, record_id, set_pairs);
+        }
+        let values = database.get_record(&table, record_id)?.values().to_vec();
+        let updated_id = database.update_record(&table, record_id, set_pairs)?;
+        self.log_undo(table_name, |database, table| UndoEntry::Update {
+            database,
+            table,
+            record_id,
+            updated_id,
+            values,
+        });
+        Ok(updated_id)
+    }
+
+    /// 检查一批待写入的行是否满足表的 NOT NULL 和唯一约束，见 [`Table::check_constraints`]
+    pub fn check_constraints(
+        &self,
+        table_name: &str,
+        rows: &[Vec<Value>],
+        replaced: &[RecordId],
     ) -> Result<()> {
-        let database = self.current_database_mut()?;
-        database.update_record(table_name, record_id, set_pairs)
+        let (database, table) = self.table_database(table_name)?;
+        database.check_constraints(&table, rows, replaced)
+    }
+
+    /// 让一批待写入的行符合表的 VARCHAR 长度，见 [`Table::fit_strings`]
+    pub fn fit_strings(
+        &self,
+        table_name: &str,
+        rows: &mut [Vec<Value>],
+        truncate: bool,
+    ) -> Result<Vec<Truncation>> {
+        let (database, table) = self.table_database(table_name)?;
+        database.fit_strings(&table, rows, truncate)
+    }
+
+    /// 按记录ID获取一行
+    pub fn get_record(&self, table_name: &str, record_id: RecordId) -> Result<Record> {
+        let (database, table) = self.table_database(table_name)?;
+        database.get_record(&table, record_id)
+    }
+
+    /// 按主键值查找一行，不扫描数据页；没有主键或找不到时返回 None
+    pub fn find_by_primary_key(&self, table_name: &str, key: &Value) -> Result<Option<Record>> {
+        let (database, table) = self.table_database(table_name)?;
+        database.find_by_primary_key(&table, key)
+    }
+
+    /// 按主键范围查找记录，按主键顺序返回；没有主键或范围为空时返回空列表
+    pub fn find_by_primary_key_range(
+        &self,
+        table_name: &str,
+        low: Bound<&Value>,
+        high: Bound<&Value>,
+    ) -> Result<Vec<Record>> {
+        let (database, table) = self.table_database(table_name)?;
+        database.find_by_primary_key_range(&table, low, high)
+    }
+
+    /// 主键索引的键数和最小、最大键，表没有主键时返回 None
+    pub fn primary_key_stats(&self, table_name: &str) -> Result<Option<PrimaryKeyStats>> {
+        let (database, table) = self.table_database(table_name)?;
+        database.primary_key_stats(&table)
     }
 
     /// 获取表中所有记录
-    pub fn get_all_records(&mut self, table_name: &str) -> Result<Vec<Record>> {
-        let database = self.current_database_mut()?;
-        database.get_all_records(table_name)
+    pub fn get_all_records(&self, table_name: &str) -> Result<Vec<Record>> {
+        let (database, table) = self.table_database(table_name)?;
+        database.get_all_records(&table)
     }
 
-    /// 获取当前数据库中所有表的名称
+    /// 获取表中所有记录的指定列，记录中的值按 `positions` 的顺序排列
+    pub fn get_records_projected(
+        &self,
+        table_name: &str,
+        positions: &[usize],
+    ) -> Result<Vec<Record>> {
+        let (database, table) = self.table_database(table_name)?;
+        database.get_records_projected(&table, positions)
+    }
+
+    /// 按存储顺序逐条读取表中的记录交给 `visit`，不先把整张表读入内存
+    ///
+    /// `positions` 不为空时记录只包含这些列，与 [`get_records_projected`](Self::get_records_projected) 相同。
+    /// `visit` 返回错误时停止扫描并返回该错误。
+    pub fn scan_records<F>(
+        &self,
+        table_name: &str,
+        positions: Option<&[usize]>,
+        visit: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Record) -> Result<()>,
+    {
+        let (database, table) = self.table_database(table_name)?;
+        database.scan_records(&table, positions, visit)
+    }
+
+    /// 获取当前数据库中所有表的名称，按字母顺序排列
     pub fn get_table_names(&self) -> Result<Vec<String>> {
-        let database = self.current_database()?;
-        Ok(database.get_table_names())
+        Ok(self.current_database()?.get_table_names())
     }
 
-    /// 获取所有数据库的名称
-    pub fn get_database_names(&self) -> Vec<String> {
+    /// 获取所有数据库的名称，按字母顺序排列
+    pub fn list_databases(&self) -> Vec<String> {
         self.databases.keys().cloned().collect()
     }
+
+    /// 已加载的数据库和加载时跳过的子目录，各自按名称排列，数据库在前
+    pub fn databases(&self) -> Vec<DataDirEntry> {
+        self.databases
+            .keys()
+            .cloned()
+            .map(DataDirEntry::Database)
+            .chain(self.skipped.iter().cloned().map(DataDirEntry::Skipped))
+            .collect()
+    }
 }
 
-// 实现 Drop trait 以在存储引擎被销毁时自动保存数据
+// 实现 Drop trait 以在存储引擎被销毁时自动保存数据，未提交的事务先回滚
 impl Drop for StorageEngine {
     fn drop(&mut self) {
+        if let Err(e) = self.rollback_transaction() {
+            log::error!("回滚未提交的事务时出错: {}", e);
+        }
         if let Err(e) = self.save() {
-            eprintln!("保存存储引擎时出错: {}", e);
+            log::error!("保存存储引擎时出错: {}", e);
         }
     }
 }
@@ -293,6 +878,7 @@ impl Drop for StorageEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::io::page::PAGE_SIZE;
     use crate::storage::table::{ColumnDef, DataType, Value};
     use tempfile::TempDir;
 
@@ -307,24 +893,33 @@ mod tests {
         vec![
             ColumnDef {
                 name: "id".to_string(),
-                data_type: DataType::Int(32),
+                data_type: DataType::Int(None),
                 not_null: true,
                 unique: true,
                 is_primary: true,
+                auto_increment: false,
+                default: None,
+                references: None,
             },
             ColumnDef {
                 name: "name".to_string(),
-                data_type: DataType::Varchar(100),
+                data_type: DataType::Varchar(Some(100)),
                 not_null: true,
                 is_primary: false,
+                auto_increment: false,
+                default: None,
                 unique: false,
+                references: None,
             },
             ColumnDef {
                 name: "age".to_string(),
-                data_type: DataType::Int(32),
+                data_type: DataType::Int(None),
                 not_null: false,
                 is_primary: false,
+                auto_increment: false,
+                default: None,
                 unique: false,
+                references: None,
             },
         ]
     }
@@ -430,6 +1025,20 @@ mod tests {
         assert_eq!(records[0].values(), &values1);
         assert_eq!(records[1].values(), &values2);
 
+        // 只取部分列时与整行中对应位置的值相同，记录ID不变
+        let projected = storage.get_records_projected("users", &[2, 0]).unwrap();
+        assert_eq!(projected[0].values(), &[Value::Int(25), Value::Int(1)]);
+        assert_eq!(projected[1].id(), Some(record_id2));
+
+        // 逐条扫描时 visit 返回错误会停止扫描
+        let mut visited = Vec::new();
+        let result = storage.scan_records("users", Some(&[1]), |record| {
+            visited.push(record.values()[0].clone());
+            Err(DBError::Other("stop".to_string()))
+        });
+        assert!(matches!(result, Err(DBError::Other(_))));
+        assert_eq!(visited, [Value::String("Alice".to_string())]);
+
         // 测试更新记录
         let update_pairs = vec![
             ("name".to_string(), Value::String("Alice Smith".to_string())),
@@ -526,6 +1135,75 @@ mod tests {
         assert!(storage.get_table("table2").is_err());
     }
 
+    #[test]
+    fn test_parallel_save_matches_sequential_save() {
+        // 几个数据库各有许多脏页，并行保存与逐个保存写出的文件完全相同
+        let build = |temp_dir: &TempDir| {
+            let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("db0")).unwrap();
+            for name in ["db1", "db2", "db3"] {
+                storage.create_database(name.to_string()).unwrap();
+            }
+            for name in ["db0", "db1", "db2", "db3"] {
+                storage.use_database(name).unwrap();
+                storage
+                    .create_table("t".to_string(), create_test_columns())
+                    .unwrap();
+                let rows = (0..2000)
+                    .map(|i| {
+                        vec![
+                            Value::Int(i),
+                            Value::String(format!("{}-{:0>90}", name, i)),
+                            Value::Int(i % 7),
+                        ]
+                    })
+                    .collect();
+                storage.insert_records("t", rows).unwrap();
+            }
+            storage
+        };
+        let files = |temp_dir: &TempDir| {
+            let mut files = BTreeMap::new();
+            for database in std::fs::read_dir(temp_dir.path()).unwrap() {
+                for file in std::fs::read_dir(database.unwrap().path()).unwrap() {
+                    let path = file.unwrap().path();
+                    let relative = path.strip_prefix(temp_dir.path()).unwrap().to_path_buf();
+                    files.insert(relative, std::fs::read(&path).unwrap());
+                }
+            }
+            files
+        };
+
+        let parallel_dir = TempDir::new().unwrap();
+        let mut parallel = build(&parallel_dir);
+        let sequential_dir = TempDir::new().unwrap();
+        let mut sequential = build(&sequential_dir);
+
+        parallel.save().unwrap();
+        for name in sequential.list_databases() {
+            sequential.get_database_mut(&name).unwrap().save().unwrap();
+        }
+        let written = files(&parallel_dir);
+        let data_files: Vec<_> = written
+            .iter()
+            .filter(|(path, _)| path.ends_with("data.db"))
+            .collect();
+        assert_eq!(data_files.len(), 4);
+        assert!(
+            data_files
+                .iter()
+                .all(|(_, bytes)| bytes.len() >= 5 * PAGE_SIZE)
+        );
+        assert_eq!(written, files(&sequential_dir));
+
+        // 重新打开后数据完整
+        drop(parallel);
+        let mut reopened = StorageEngine::new(Some(parallel_dir.path()), Some("db0")).unwrap();
+        for name in ["db0", "db1", "db2", "db3"] {
+            reopened.use_database(name).unwrap();
+            assert_eq!(reopened.get_all_records("t").unwrap().len(), 2000);
+        }
+    }
+
     #[test]
     fn test_edge_cases() {
         let (mut storage, _temp_dir) = create_test_storage();
@@ -618,4 +1296,390 @@ mod tests {
         let records = storage.get_all_records("concurrent_table").unwrap();
         assert_eq!(records.len(), 10);
     }
+
+    #[test]
+    fn test_compact_table_packs_mixed_sizes() {
+        use crate::storage::io::page::{PACKED_PAGE_CAPACITY, PAGE_HEADER_SIZE, Page};
+
+        let (mut storage, _temp_dir) = create_test_storage();
+        let columns = vec![
+            ColumnDef {
+                name: "id".to_string(),
+                data_type: DataType::Int(None),
+                not_null: true,
+                unique: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
+            },
+            ColumnDef {
+                name: "body".to_string(),
+                data_type: DataType::Varchar(Some(20000)),
+                not_null: true,
+                unique: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
+            },
+        ];
+        storage
+            .create_table("docs".to_string(), columns)
+            .unwrap();
+
+        // 每页一条大记录加三条小记录；前一半页面删掉小记录，后一半删掉大记录，
+        // 按扫描顺序就成了五条大记录后跟十五条小记录，逐页顺序排列放不紧
+        let mut expected = Vec::new();
+        let mut deleted = Vec::new();
+        for group in 0..10 {
+            for k in 0..4 {
+                let len = if k == 0 { 17000 } else { 4000 };
+                let values = vec![Value::Int(group * 4 + k), Value::String("x".repeat(len))];
+                let record_id = storage.insert_record("docs", values.clone()).unwrap();
+                if (group < 5) == (k == 0) {
+                    expected.push(values);
+                } else {
+                    deleted.push(record_id);
+                }
+            }
+        }
+        assert_eq!(storage.get_table("docs").unwrap().page_ids().len(), 10);
+        for record_id in deleted {
+            storage.delete_record("docs", record_id).unwrap();
+        }
+
+        let summary = storage.compact_table("docs").unwrap();
+        assert_eq!(summary.records, expected.len());
+
+        let rows = |storage: &mut StorageEngine| {
+            let mut rows: Vec<Vec<Value>> = storage
+                .get_all_records("docs")
+                .unwrap()
+                .into_iter()
+                .map(|record| record.values().to_vec())
+                .collect();
+            rows.sort_by(|a, b| a[0].total_cmp(&b[0]));
+            rows
+        };
+        assert_eq!(rows(&mut storage), expected);
+
+        // 与理论下界最多差一页，并且比按原顺序排列省页
+        let total: usize = storage
+            .get_all_records("docs")
+            .unwrap()
+            .into_iter()
+            .map(|record| Page::packed_record_size(&record.into_raw_data()))
+            .sum();
+        let lower_bound = total.div_ceil(PACKED_PAGE_CAPACITY - PAGE_HEADER_SIZE);
+        assert!(summary.pages_after <= lower_bound + 1, "{:?}", summary);
+        assert!(summary.pages_after < summary.naive_pages, "{:?}", summary);
+        assert!(summary.pages_after < summary.pages_before, "{:?}", summary);
+        assert_eq!(
+            storage.get_table("docs").unwrap().page_ids().len(),
+            summary.pages_after
+        );
+
+        // 整理后的每个页面都不超过页大小，能正常写回磁盘
+        storage.save().unwrap();
+    }
+
+    #[test]
+    fn test_insert_records_fills_tail_page_and_is_atomic() {
+        let (mut storage, _temp_dir) = create_test_storage();
+        let columns = vec![
+            ColumnDef {
+                name: "id".to_string(),
+                data_type: DataType::Int(None),
+                not_null: true,
+                unique: false,
+                is_primary: true,
+                auto_increment: true,
+                default: None,
+                references: None,
+            },
+            ColumnDef {
+                name: "body".to_string(),
+                data_type: DataType::Varchar(Some(40000)),
+                not_null: true,
+                unique: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
+            },
+        ];
+        storage.create_table("docs".to_string(), columns).unwrap();
+        let row = |len: usize| vec![Value::Null, Value::String("x".repeat(len))];
+
+        // 末尾页还有空间时先装满它，之后才分配新页
+        storage.insert_record("docs", row(4000)).unwrap();
+        let record_ids = storage
+            .insert_records("docs", (0..10).map(|_| row(4000)).collect())
+            .unwrap();
+        let page_ids = storage.get_table("docs").unwrap().page_ids().to_vec();
+        assert_eq!(page_ids.len(), 2);
+        assert_eq!(record_ids[0].page_id, page_ids[0]);
+        assert_eq!(record_ids[9].page_id, page_ids[1]);
+
+        // 第7行放不进任何页面：整批不写入，自增值和页面都不变
+        let mut rows: Vec<Vec<Value>> = (0..10).map(|_| row(4000)).collect();
+        rows[6] = row(36000);
+        assert!(storage.insert_records("docs", rows).is_err());
+        assert_eq!(storage.get_all_records("docs").unwrap().len(), 11);
+        assert_eq!(storage.get_table("docs").unwrap().page_ids(), page_ids);
+
+        // 第7行主键与已有行重复：约束检查在写入前整体失败
+        let mut rows: Vec<Vec<Value>> = (0..10).map(|_| row(10)).collect();
+        rows[6][0] = Value::Int(3);
+        assert!(storage.insert_records("docs", rows).is_err());
+        assert_eq!(storage.get_all_records("docs").unwrap().len(), 11);
+
+        let record_id = storage.insert_record("docs", row(10)).unwrap();
+        let record = storage.get_record("docs", record_id).unwrap();
+        assert_eq!(record.values()[0], Value::Int(12));
+        storage.save().unwrap();
+    }
+
+    #[test]
+    fn test_table_stats_reflect_deletes() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("test_db")).unwrap();
+        storage
+            .create_table("logs".to_string(), create_test_columns())
+            .unwrap();
+        let empty = storage.table_stats("logs").unwrap();
+        assert_eq!((empty.row_count, empty.page_count), (0, 0));
+        assert_eq!(empty.avg_page_utilization, 0.0);
+
+        let row = |id: i32| vec![Value::Int(id), Value::String("x".repeat(100)), Value::Null];
+        let record_ids = storage
+            .insert_records("logs", (0..1000).map(row).collect())
+            .unwrap();
+        let stats = storage.table_stats("logs").unwrap();
+        let page_count = storage.get_table("logs").unwrap().page_ids().len();
+        assert_eq!(stats.row_count, 1000);
+        assert_eq!(stats.page_count, page_count);
+        assert_eq!(stats.deleted_slots, 0);
+        assert!(page_count > 1, "{:?}", stats);
+        assert!(stats.total_bytes > 1000 * 100, "{:?}", stats);
+        let utilization = stats.total_bytes as f64 / (page_count * PAGE_SIZE) as f64;
+        assert_eq!(stats.avg_page_utilization, utilization);
+
+        // 删除的行留下空槽位，页数不变、字节数减少；新插入的行先复用空槽位
+        for record_id in record_ids.iter().step_by(4) {
+            storage.delete_record("logs", *record_id).unwrap();
+        }
+        let deleted = storage.table_stats("logs").unwrap();
+        assert_eq!(deleted.row_count, 750);
+        assert_eq!(deleted.deleted_slots, 250);
+        assert_eq!(deleted.page_count, page_count);
+        assert!(deleted.total_bytes < stats.total_bytes);
+        assert!(deleted.avg_page_utilization < stats.avg_page_utilization);
+
+        storage.insert_record("logs", row(1000)).unwrap();
+        let reused = storage.table_stats("logs").unwrap();
+        assert_eq!((reused.row_count, reused.deleted_slots), (751, 249));
+
+        // 整理后不再有空槽位
+        storage.compact_table("logs").unwrap();
+        let compacted = storage.table_stats("logs").unwrap();
+        assert_eq!((compacted.row_count, compacted.deleted_slots), (751, 0));
+        assert!(compacted.page_count <= page_count);
+        assert!(storage.table_stats("missing").is_err());
+    }
+
+    #[test]
+    fn test_compact_frees_pages_for_reuse() {
+        let temp_dir = TempDir::new().unwrap();
+        let free_pages = |storage: &mut StorageEngine| {
+            storage
+                .current_database_mut()
+                .unwrap()
+                .get_buffer_manager()
+                .free_page_count()
+        };
+        let row = |id: i32| vec![Value::Int(id), Value::String("x".repeat(100)), Value::Null];
+
+        let (kept, pages_before, pages_after) = {
+            let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("test_db")).unwrap();
+            storage
+                .create_table("logs".to_string(), create_test_columns())
+                .unwrap();
+            let record_ids = storage
+                .insert_records("logs", (0..5000).map(row).collect())
+                .unwrap();
+            let pages_before = storage.get_table("logs").unwrap().page_ids().len();
+
+            // 删除 90% 的行后整理
+            for (id, record_id) in record_ids.into_iter().enumerate() {
+                if id % 10 != 0 {
+                    storage.delete_record("logs", record_id).unwrap();
+                }
+            }
+            let summary = storage.compact_table("logs").unwrap();
+            let pages_after = storage.get_table("logs").unwrap().page_ids().len();
+            assert_eq!(summary.pages_after, pages_after);
+            assert!(pages_after * 5 < pages_before, "{:?}", summary);
+            assert_eq!(free_pages(&mut storage), pages_before - pages_after);
+
+            let kept: Vec<Vec<Value>> = (0..5000).step_by(10).map(row).collect();
+            let mut rows: Vec<Vec<Value>> = storage
+                .get_all_records("logs")
+                .unwrap()
+                .into_iter()
+                .map(|record| record.values().to_vec())
+                .collect();
+            rows.sort_by(|a, b| a[0].total_cmp(&b[0]));
+            assert_eq!(rows, kept);
+
+            // 整理出来的页面被之后新建的页面复用，数据文件不再增长
+            storage
+                .create_table("more".to_string(), create_test_columns())
+                .unwrap();
+            storage
+                .insert_records("more", (0..1000).map(row).collect())
+                .unwrap();
+            let reused = storage.get_table("more").unwrap().page_ids().to_vec();
+            assert!(
+                reused
+                    .iter()
+                    .all(|&page_id| (page_id as usize) < pages_before)
+            );
+            assert_eq!(
+                free_pages(&mut storage),
+                pages_before - pages_after - reused.len()
+            );
+
+            storage.drop_table("more").unwrap();
+            storage.save().unwrap();
+            (kept, pages_before, pages_after)
+        };
+
+        // 重新打开后，没有被任何表使用的页面仍然可以复用
+        let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("test_db")).unwrap();
+        assert_eq!(free_pages(&mut storage), pages_before - pages_after);
+        assert_eq!(storage.get_all_records("logs").unwrap().len(), kept.len());
+    }
+
+    #[test]
+    fn test_update_relocates_grown_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let columns = vec![
+            ColumnDef {
+                name: "id".to_string(),
+                data_type: DataType::Int(None),
+                not_null: true,
+                unique: false,
+                is_primary: true,
+                auto_increment: false,
+                default: None,
+                references: None,
+            },
+            ColumnDef {
+                name: "body".to_string(),
+                data_type: DataType::Varchar(Some(40000)),
+                not_null: false,
+                unique: false,
+                is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
+            },
+        ];
+        let body = |len: usize| Value::String("x".repeat(len));
+        let set_body = |len: usize| vec![("body".to_string(), body(len))];
+        let bodies = |storage: &mut StorageEngine| {
+            let mut rows: Vec<(Value, usize)> = storage
+                .get_all_records("docs")
+                .unwrap()
+                .into_iter()
+                .map(|record| match record.values() {
+                    [id, Value::String(s)] => (id.clone(), s.len()),
+                    other => panic!("意外的行 {:?}", other),
+                })
+                .collect();
+            rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+            rows
+        };
+
+        {
+            let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("test_db")).unwrap();
+            storage.create_table("docs".to_string(), columns).unwrap();
+            let record_ids = storage
+                .insert_records(
+                    "docs",
+                    (1..=8).map(|id| vec![Value::Int(id), body(3500)]).collect(),
+                )
+                .unwrap();
+            let first_page = record_ids[0].page_id;
+            assert!(record_ids.iter().all(|id| id.page_id == first_page));
+
+            // 原页面放不下变大的行：搬到新页面，返回新的记录ID，主键索引指向新位置
+            let moved = storage
+                .update_record("docs", record_ids[0], &set_body(20000))
+                .unwrap();
+            assert_ne!(moved.page_id, first_page);
+            assert!(storage.get_record("docs", record_ids[0]).is_err());
+            assert_eq!(
+                storage.get_record("docs", moved).unwrap().values()[1],
+                body(20000)
+            );
+            let found = storage
+                .find_by_primary_key("docs", &Value::Int(1))
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.id(), Some(moved));
+
+            // 放得下时仍然原地修改
+            assert_eq!(
+                storage
+                    .update_record("docs", record_ids[1], &set_body(10))
+                    .unwrap(),
+                record_ids[1]
+            );
+
+            // 事务中搬动的行回滚后恢复原值，之后按主键仍能找到
+            storage.begin_transaction().unwrap();
+            storage
+                .update_record("docs", record_ids[2], &set_body(25000))
+                .unwrap();
+            storage.rollback_transaction().unwrap();
+            let found = storage
+                .find_by_primary_key("docs", &Value::Int(3))
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.values()[1], body(3500));
+
+            // 太大、任何页面都放不下的行报错，原记录不变
+            assert!(
+                storage
+                    .update_record("docs", record_ids[3], &set_body(36000))
+                    .is_err()
+            );
+            assert_eq!(
+                storage.get_record("docs", record_ids[3]).unwrap().values()[1],
+                body(3500)
+            );
+
+            let mut expected: Vec<(Value, usize)> =
+                (1..=8).map(|id| (Value::Int(id), 3500)).collect();
+            expected[0].1 = 20000;
+            expected[1].1 = 10;
+            assert_eq!(bodies(&mut storage), expected);
+            storage.save().unwrap();
+        }
+
+        // 保存并重新打开后整张表仍能正常扫描
+        let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("test_db")).unwrap();
+        let rows = bodies(&mut storage);
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[0], (Value::Int(1), 20000));
+        assert_eq!(rows[1], (Value::Int(2), 10));
+        let found = storage
+            .find_by_primary_key("docs", &Value::Int(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.values()[1], body(20000));
+    }
 }
@@ -0,0 +1,290 @@
+use super::StorageEngine;
+use super::catalog::Catalog;
+use super::io::page::{Page, PageId};
+use crate::error::{DBError, Result};
+use bincode::{Decode, Encode};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 备份文件的魔数
+const BACKUP_MAGIC: &[u8; 4] = b"SDBK";
+
+/// 当前的备份文件格式版本
+const BACKUP_VERSION: u32 = 1;
+
+/// 每一步默认复制的页面数
+pub const DEFAULT_BACKUP_STEP_PAGES: usize = 16;
+
+/// 备份文件内容
+#[derive(Encode, Decode)]
+struct BackupArchive {
+    /// 源数据库名
+    database: String,
+    /// 备份开始时的目录（`Catalog::serialize` 的输出）
+    catalog: Vec<u8>,
+    /// 备份开始时各数据页的内容
+    pages: Vec<(PageId, Vec<u8>)>,
+}
+
+/// 备份结果统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupSummary {
+    pub tables: usize,
+    pub pages: usize,
+}
+
+/// 在线备份：开始时固定元数据和页面列表，之后分步复制页面
+///
+/// 备份期间写入者第一次修改某个页面前，缓冲池会保留该页面的前像，
+/// 因此每一步读到的都是备份开始时的内容。调用方只需在每一步期间独占存储引擎，
+/// 步与步之间写入可以继续进行。备份必须以 [`finish`](Self::finish) 或
+/// [`abort`](Self::abort) 结束，否则缓冲池会一直保留前像。
+pub struct OnlineBackup {
+    database: String,
+    path: PathBuf,
+    catalog: Catalog,
+    /// 尚未复制的页面
+    pending: VecDeque<PageId>,
+    /// 已复制的页面
+    pages: Vec<(PageId, Vec<u8>)>,
+}
+
+impl OnlineBackup {
+    /// 对当前数据库开始在线备份，备份完成后写入 `path`
+    pub fn begin<P: AsRef<Path>>(storage: &mut StorageEngine, path: P) -> Result<Self> {
+        let name = storage.current_database()?.get_name().to_string();
+        Self::begin_database(storage, &name, path)
+    }
+
+    /// 对指定的数据库开始在线备份，不要求它是当前数据库
+    pub fn begin_database<P: AsRef<Path>>(
+        storage: &mut StorageEngine,
+        name: &str,
+        path: P,
+    ) -> Result<Self> {
+        let database = storage.get_database_mut(name)?;
+        let catalog = database.snapshot_catalog()?;
+        database.get_buffer_manager_mut().begin_snapshot()?;
+
+        let mut pending = VecDeque::new();
+        for table_name in catalog.get_table_names() {
+            pending.extend(catalog.get_table_page_ids(&table_name)?);
+        }
+
+        Ok(Self {
+            database: database.get_name().to_string(),
+            path: path.as_ref().to_path_buf(),
+            catalog,
+            pending,
+            pages: Vec::new(),
+        })
+    }
+
+    /// 复制最多 `max_pages` 个页面，全部复制完成时返回 true
+    pub fn step(&mut self, storage: &mut StorageEngine, max_pages: usize) -> Result<bool> {
+        let buffer_manager = storage
+            .get_database_mut(&self.database)?
+            .get_buffer_manager_mut();
+
+        for _ in 0..max_pages {
+            let Some(page_id) = self.pending.pop_front() else {
+                break;
+            };
+            self.pages
+                .push((page_id, buffer_manager.snapshot_page(page_id)?));
+        }
+
+        Ok(self.pending.is_empty())
+    }
+
+    /// 复制剩余页面并写出备份文件
+    pub fn finish(mut self, storage: &mut StorageEngine) -> Result<BackupSummary> {
+        let result = self.complete(storage);
+        if let Ok(database) = storage.get_database_mut(&self.database) {
+            database.get_buffer_manager_mut().end_snapshot();
+        }
+        result
+    }
+
+    /// 放弃备份，释放保留的页面前像
+    pub fn abort(self, storage: &mut StorageEngine) {
+        if let Ok(database) = storage.get_database_mut(&self.database) {
+            database.get_buffer_manager_mut().end_snapshot();
+        }
+    }
+
+    fn complete(&mut self, storage: &mut StorageEngine) -> Result<BackupSummary> {
+        while !self.step(storage, DEFAULT_BACKUP_STEP_PAGES)? {}
+
+        let summary = BackupSummary {
+            tables: self.catalog.table_count(),
+            pages: self.pages.len(),
+        };
+        let archive = BackupArchive {
+            database: self.database.clone(),
+            catalog: self.catalog.serialize(),
+            pages: std::mem::take(&mut self.pages),
+        };
+
+        let body = bincode::encode_to_vec(&archive, bincode::config::standard())
+            .map_err(|e| DBError::IO(format!("无法序列化备份: {}", e)))?;
+        let mut data = Vec::with_capacity(BACKUP_MAGIC.len() + 4 + body.len());
+        data.extend_from_slice(BACKUP_MAGIC);
+        data.extend_from_slice(&BACKUP_VERSION.to_le_bytes());
+        data.extend_from_slice(&body);
+
+        fs::write(&self.path, data).map_err(|e| DBError::IO(format!("无法写入备份文件: {}", e)))?;
+        Ok(summary)
+    }
+}
+
+/// 把备份文件导入为新数据库，返回数据库名
+///
+/// 未指定 `db_name` 时使用备份中的源数据库名；同名数据库已存在时报错。
+/// 表的列、索引和 CHECK 约束按备份中的目录重建，
+/// 记录按备份中的页面顺序重新插入，不依赖原来的页ID。
+pub fn import_backup<P: AsRef<Path>>(
+    storage: &mut StorageEngine,
+    path: P,
+    db_name: Option<&str>,
+) -> Result<String> {
+    let data =
+        fs::read(path.as_ref()).map_err(|e| DBError::IO(format!("无法读取备份文件: {}", e)))?;
+    let body = data
+        .strip_prefix(BACKUP_MAGIC)
+        .and_then(|rest| rest.split_first_chunk::<4>())
+        .ok_or_else(|| DBError::IO("不是有效的备份文件".to_string()))
+        .and_then(|(version, body)| match u32::from_le_bytes(*version) {
+            BACKUP_VERSION => Ok(body),
+            version => Err(DBError::IO(format!("不支持的备份文件版本 {}", version))),
+        })?;
+    let (archive, _): (BackupArchive, _) =
+        bincode::decode_from_slice(body, bincode::config::standard())
+            .map_err(|e| DBError::IO(format!("备份文件损坏或格式不正确: {}", e)))?;
+
+    let catalog = Catalog::deserialize(&archive.catalog)?;
+    let pages: HashMap<PageId, Vec<u8>> = archive.pages.into_iter().collect();
+    let name = db_name.unwrap_or(&archive.database).to_string();
+    if storage.has_database(&name) {
+        return Err(DBError::Schema(format!(
+            "数据库 '{}' 已存在，请指定另一个数据库名导入",
+            name
+        )));
+    }
+
+    storage.create_database(name.clone())?;
+    let database = storage.get_database_mut(&name)?;
+    for table_name in catalog.get_table_names() {
+        database.create_table(table_name.clone(), catalog.get_table_columns(&table_name)?)?;
+        for index in catalog.get_table_indexes(&table_name)? {
+            database.create_index(&table_name, index)?;
+        }
+        for check in catalog.get_table_checks(&table_name)? {
+            database.create_check(&table_name, check)?;
+        }
+
+        for page_id in catalog.get_table_page_ids(&table_name)? {
+            let data = pages
+                .get(&page_id)
+                .ok_or_else(|| DBError::IO(format!("备份文件缺少页面 {}", page_id)))?;
+            let page = Page::from_data(page_id, data)?;
+            for (_, record) in page.iter_records() {
+                database.insert_record(&table_name, record.values().to_vec())?;
+            }
+        }
+    }
+    database.save()?;
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::table::{ColumnDef, DataType, Value};
+    use tempfile::TempDir;
+
+    fn column(name: &str, data_type: DataType) -> ColumnDef {
+        ColumnDef {
+            name: name.to_string(),
+            data_type,
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        }
+    }
+
+    fn sorted_rows(storage: &mut StorageEngine, table_name: &str) -> Vec<Vec<Value>> {
+        let mut rows: Vec<Vec<Value>> = storage
+            .get_all_records(table_name)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.values().to_vec())
+            .collect();
+        rows.sort_by_key(|row| match row[0] {
+            Value::Int(id) => id,
+            _ => i32::MAX,
+        });
+        rows
+    }
+
+    #[test]
+    fn test_online_backup_sees_state_at_start() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("shop")).unwrap();
+        storage
+            .create_table(
+                "items".to_string(),
+                vec![
+                    column("id", DataType::Int(None)),
+                    column("name", DataType::Varchar(Some(20))),
+                ],
+            )
+            .unwrap();
+        let first = storage
+            .insert_record("items", vec![Value::Int(1), Value::String("a".to_string())])
+            .unwrap();
+        storage
+            .insert_record("items", vec![Value::Int(2), Value::String("b".to_string())])
+            .unwrap();
+        let before = sorted_rows(&mut storage, "items");
+
+        let backup_path = temp_dir.path().join("shop.bak");
+        let mut backup = OnlineBackup::begin(&mut storage, &backup_path).unwrap();
+        // 同一时间只允许一个在线备份
+        assert!(OnlineBackup::begin(&mut storage, temp_dir.path().join("other.bak")).is_err());
+
+        // 备份开始后的修改不应出现在备份中
+        storage
+            .update_record(
+                "items",
+                first,
+                &vec![("name".to_string(), Value::String("changed".to_string()))],
+            )
+            .unwrap();
+        storage
+            .insert_record("items", vec![Value::Int(3), Value::String("c".to_string())])
+            .unwrap();
+        backup.step(&mut storage, 1).unwrap();
+        let summary = backup.finish(&mut storage).unwrap();
+        assert_eq!(
+            summary,
+            BackupSummary {
+                tables: 1,
+                pages: 1
+            }
+        );
+
+        let name = import_backup(&mut storage, &backup_path, Some("restored")).unwrap();
+        assert_eq!(name, "restored");
+        storage.use_database("restored").unwrap();
+        assert_eq!(sorted_rows(&mut storage, "items"), before);
+
+        // 同名数据库已存在时拒绝导入
+        assert!(import_backup(&mut storage, &backup_path, Some("restored")).is_err());
+    }
+}
@@ -1,14 +1,33 @@
 use super::io::page::PageId;
-use super::table::ColumnDef;
+use super::table::{CheckConstraint, ColumnDef, IndexDef};
 use crate::error::{DBError, Result};
 use bincode::{Decode, Encode};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+mod legacy;
+
+/// 元数据文件的魔数，用于区分带版本头的格式和旧版格式
+const METADATA_MAGIC: &[u8; 4] = b"SDBM";
+
+/// 当前的元数据格式版本
+///
+/// 版本 3 为列加入了默认值，版本 4 把 VARCHAR 的长度改为可选（未声明长度时为 None），
+/// 版本 5 为列加入了外键，版本 6 为表加入了 CHECK 约束，更早版本的目录在读取时迁移，
+/// 见 [`legacy::migrate_v5`]。
+const METADATA_VERSION: u32 = 6;
 
 /// 目录 - 存储数据库模式信息（表结构、列定义等元数据）
-#[derive(Debug, Clone, Encode, Decode)]
+///
+/// 序列化时只写入 `tables`，与以前整个结构体的编码相同。
+#[derive(Debug, Clone)]
 pub struct Catalog {
     /// 表元数据，存储表名与其对应的列定义
-    tables: HashMap<String, TableMetadata>,
+    ///
+    /// 按表名排序，序列化结果与插入顺序无关。bincode 对有序和无序映射的编码相同，
+    /// 以前按 HashMap 写入的元数据文件可以直接读取。
+    tables: BTreeMap<String, TableMetadata>,
+    /// 上次保存（或加载）之后是否有修改，见 [`is_dirty`](Self::is_dirty)
+    dirty: bool,
 }
 
 /// 表的元数据信息
@@ -18,66 +37,203 @@ struct TableMetadata {
     columns: Vec<ColumnDef>,
     /// 表的数据页ID列表
     page_ids: Vec<PageId>,
+    /// 索引定义
+    indexes: Vec<IndexDef>,
+    /// CHECK 约束，按定义顺序
+    checks: Vec<CheckConstraint>,
 }
 
 impl Catalog {
+    /// 新建的目录还没有写入过文件，视为有修改
     pub fn new() -> Self {
         Self {
-            tables: HashMap::new(),
+            tables: BTreeMap::new(),
+            dirty: true,
+        }
+    }
+
+    /// 由读取的表元数据构造，与文件中的内容一致
+    fn from_tables(tables: BTreeMap<String, TableMetadata>) -> Self {
+        Self {
+            tables,
+            dirty: false,
+        }
+    }
+
+    /// 上次保存之后目录是否被修改过（建表、删表、修改表结构或数据页列表）
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// 目录已写入文件后调用，清除修改标记
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// 目录中与 `name` 对应的表名，精确匹配优先，其次不区分大小写匹配；都没有时原样返回
+    ///
+    /// 新建的表名按小写保存；旧版本创建的大小写混合的表名仍然可以用任意大小写访问。
+    pub fn resolve_table_name<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.tables.contains_key(name) {
+            return name;
         }
+        self.tables
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case(name))
+            .map_or(name, String::as_str)
+    }
+
+    fn metadata(&self, table_name: &str) -> Option<&TableMetadata> {
+        self.tables.get(self.resolve_table_name(table_name))
+    }
+
+    fn metadata_mut(&mut self, table_name: &str) -> Option<&mut TableMetadata> {
+        let key = self.resolve_table_name(table_name).to_string();
+        self.tables.get_mut(&key)
     }
 
     /// 添加表元数据
     pub fn add_table_metadata(&mut self, name: String, columns: Vec<ColumnDef>) -> Result<()> {
-        if self.tables.contains_key(&name) {
+        if self.has_table(&name) {
             return Err(DBError::Schema(format!("表 '{}' 元数据已存在", name)));
         }
+        // 计划阶段已经拒绝重复的列名，这里再检查一次，避免写入有歧义的表结构
+        for (index, column) in columns.iter().enumerate() {
+            if columns[..index]
+                .iter()
+                .any(|other| other.name.eq_ignore_ascii_case(&column.name))
+            {
+                return Err(DBError::Schema(format!(
+                    "Duplicate column name '{}'",
+                    column.name
+                )));
+            }
+        }
 
         let metadata = TableMetadata {
             columns,
             page_ids: Vec::new(), // 新表没有数据页
+            indexes: Vec::new(),
+            checks: Vec::new(),
         };
 
         self.tables.insert(name, metadata);
+        self.dirty = true;
         Ok(())
     }
 
     /// 删除表元数据
     pub fn remove_table_metadata(&mut self, name: &str) -> Result<()> {
-        if !self.tables.contains_key(name) {
+        let key = self.resolve_table_name(name).to_string();
+        if self.tables.remove(&key).is_none() {
             return Err(DBError::NotFound(format!("表 '{}' 元数据不存在", name)));
         }
+        self.dirty = true;
+        Ok(())
+    }
 
-        self.tables.remove(name);
+    /// 修改表的元数据对应的表名
+    pub fn rename_table_metadata(&mut self, name: &str, new_name: String) -> Result<()> {
+        if self.has_table(&new_name) {
+            return Err(DBError::Schema(format!("表 '{}' 元数据已存在", new_name)));
+        }
+        let key = self.resolve_table_name(name).to_string();
+        let Some(metadata) = self.tables.remove(&key) else {
+            return Err(DBError::NotFound(format!("表 '{}' 元数据不存在", name)));
+        };
+        self.tables.insert(new_name, metadata);
+        self.dirty = true;
         Ok(())
     }
 
-    /// 获取所有表名
+    /// 获取所有表名，按字母顺序排列
     pub fn get_table_names(&self) -> Vec<String> {
         self.tables.keys().cloned().collect()
     }
 
     /// 获取表的列定义
     pub fn get_table_columns(&self, table_name: &str) -> Result<Vec<ColumnDef>> {
-        self.tables
-            .get(table_name)
+        self.metadata(table_name)
             .map(|metadata| metadata.columns.clone())
             .ok_or_else(|| DBError::NotFound(format!("表 '{}' 元数据不存在", table_name)))
     }
 
     /// 获取表的数据页ID列表
     pub fn get_table_page_ids(&self, table_name: &str) -> Result<Vec<PageId>> {
-        self.tables
-            .get(table_name)
+        self.metadata(table_name)
             .map(|metadata| metadata.page_ids.clone())
             .ok_or_else(|| DBError::NotFound(format!("表 '{}' 元数据不存在", table_name)))
     }
 
-    /// 更新表的数据页ID列表
+    /// 获取表的索引定义
+    pub fn get_table_indexes(&self, table_name: &str) -> Result<Vec<IndexDef>> {
+        self.metadata(table_name)
+            .map(|metadata| metadata.indexes.clone())
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 元数据不存在", table_name)))
+    }
+
+    /// 获取表的 CHECK 约束
+    pub fn get_table_checks(&self, table_name: &str) -> Result<Vec<CheckConstraint>> {
+        self.metadata(table_name)
+            .map(|metadata| metadata.checks.clone())
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 元数据不存在", table_name)))
+    }
+
+    /// 在表上添加 CHECK 约束；与 MySQL 一样，约束名在整个数据库中不能重复
+    pub fn add_table_check(&mut self, table_name: &str, check: CheckConstraint) -> Result<()> {
+        if self
+            .tables
+            .values()
+            .flat_map(|metadata| &metadata.checks)
+            .any(|other| other.name.eq_ignore_ascii_case(&check.name))
+        {
+            return Err(DBError::Schema(format!(
+                "Duplicate check constraint name '{}'.",
+                check.name
+            )));
+        }
+        match self.metadata_mut(table_name) {
+            Some(metadata) => {
+                metadata.checks.push(check);
+                self.dirty = true;
+                Ok(())
+            }
+            None => Err(DBError::NotFound(format!(
+                "表 '{}' 元数据不存在",
+                table_name
+            ))),
+        }
+    }
+
+    /// 更新表的列定义和索引定义（添加索引会改变列上的 UNIQUE 约束）
+    pub fn update_table_schema(
+        &mut self,
+        table_name: &str,
+        columns: Vec<ColumnDef>,
+        indexes: Vec<IndexDef>,
+    ) -> Result<()> {
+        match self.metadata_mut(table_name) {
+            Some(metadata) => {
+                metadata.columns = columns;
+                metadata.indexes = indexes;
+                self.dirty = true;
+                Ok(())
+            }
+            None => Err(DBError::NotFound(format!(
+                "表 '{}' 元数据不存在",
+                table_name
+            ))),
+        }
+    }
+
+    /// 更新表的数据页ID列表，列表没有变化时不算修改
     pub fn update_table_page_ids(&mut self, table_name: &str, page_ids: Vec<PageId>) -> Result<()> {
-        match self.tables.get_mut(table_name) {
+        match self.metadata_mut(table_name) {
             Some(metadata) => {
-                metadata.page_ids = page_ids;
+                if metadata.page_ids != page_ids {
+                    metadata.page_ids = page_ids;
+                    self.dirty = true;
+                }
                 Ok(())
             }
             None => Err(DBError::NotFound(format!(
@@ -89,9 +245,10 @@ impl Catalog {
 
     /// 添加表的数据页ID
     pub fn add_table_page_id(&mut self, table_name: &str, page_id: PageId) -> Result<()> {
-        match self.tables.get_mut(table_name) {
+        match self.metadata_mut(table_name) {
             Some(metadata) => {
                 metadata.page_ids.push(page_id);
+                self.dirty = true;
                 Ok(())
             }
             None => Err(DBError::NotFound(format!(
@@ -103,7 +260,7 @@ impl Catalog {
 
     /// 检查表是否存在
     pub fn has_table(&self, table_name: &str) -> bool {
-        self.tables.contains_key(table_name)
+        self.metadata(table_name).is_some()
     }
 
     /// 获取表元数据的数量
@@ -111,18 +268,44 @@ impl Catalog {
         self.tables.len()
     }
 
-    /// 使用 bincode 2.x 序列化目录
+    /// 使用 bincode 2.x 序列化目录，前面加上魔数和格式版本
     pub fn serialize(&self) -> Vec<u8> {
-        bincode::encode_to_vec(self, bincode::config::standard()).unwrap_or_else(|e| {
-            panic!("序列化Catalog失败: {}", e);
-        })
+        let body = bincode::encode_to_vec(&self.tables, bincode::config::standard())
+            .unwrap_or_else(|e| {
+                panic!("序列化Catalog失败: {}", e);
+            });
+
+        let mut buffer = Vec::with_capacity(METADATA_MAGIC.len() + 4 + body.len());
+        buffer.extend_from_slice(METADATA_MAGIC);
+        buffer.extend_from_slice(&METADATA_VERSION.to_le_bytes());
+        buffer.extend_from_slice(&body);
+        buffer
     }
 
-    /// 使用 bincode 2.x 反序列化目录
+    /// 反序列化目录；没有版本头的旧版格式会被迁移到当前格式
     pub fn deserialize(buffer: &[u8]) -> Result<Self> {
-        match bincode::decode_from_slice(buffer, bincode::config::standard()) {
-            Ok((catalog, _)) => Ok(catalog),
-            Err(e) => Err(DBError::IO(format!("反序列化Catalog失败: {}", e))),
+        let Some(rest) = buffer.strip_prefix(METADATA_MAGIC) else {
+            return legacy::migrate(buffer);
+        };
+        let (version, body) = rest
+            .split_first_chunk::<4>()
+            .ok_or_else(|| DBError::IO("元数据版本头不完整".to_string()))?;
+
+        match u32::from_le_bytes(*version) {
+            METADATA_VERSION => {
+                match bincode::decode_from_slice(body, bincode::config::standard()) {
+                    Ok((tables, _)) => Ok(Self::from_tables(tables)),
+                    Err(e) => Err(DBError::IO(format!("反序列化Catalog失败: {}", e))),
+                }
+            }
+            2 => legacy::migrate_v2(body),
+            3 => legacy::migrate_v3(body),
+            4 => legacy::migrate_v4(body),
+            5 => legacy::migrate_v5(body),
+            version => Err(DBError::IO(format!(
+                "不支持的元数据版本 {}（当前版本 {}）",
+                version, METADATA_VERSION
+            ))),
         }
     }
 
@@ -160,17 +343,23 @@ mod tests {
         let columns = vec![
             ColumnDef {
                 name: "id".to_string(),
-                data_type: DataType::Int(4),
+                data_type: DataType::Int(None),
                 not_null: true,
                 unique: true,
                 is_primary: true,
+                auto_increment: false,
+                default: None,
+                references: None,
             },
             ColumnDef {
                 name: "name".to_string(),
-                data_type: DataType::Varchar(255),
+                data_type: DataType::Varchar(Some(255)),
                 not_null: false,
                 unique: false,
                 is_primary: false,
+                auto_increment: false,
+                default: None,
+                references: None,
             },
         ];
 
@@ -200,15 +389,54 @@ mod tests {
         assert_eq!(page_ids, vec![1, 2]);
     }
 
+    #[test]
+    fn test_new_data_types_round_trip() {
+        // 已有类型的变体序号不变，旧的元数据文件才能继续加载
+        let config = bincode::config::standard();
+        assert_eq!(bincode::encode_to_vec(DataType::Int(None), config).unwrap()[0], 0);
+        assert_eq!(bincode::encode_to_vec(DataType::Varchar(Some(20)), config).unwrap()[0], 1);
+
+        let column = |name: &str, data_type| ColumnDef {
+            name: name.to_string(),
+            data_type,
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        };
+        let mut catalog = Catalog::new();
+        catalog
+            .add_table_metadata(
+                "products".to_string(),
+                vec![
+                    column("price", DataType::Float),
+                    column("active", DataType::Boolean),
+                ],
+            )
+            .unwrap();
+
+        let columns = Catalog::deserialize(&catalog.serialize())
+            .unwrap()
+            .get_table_columns("products")
+            .unwrap();
+        assert_eq!(columns[0].data_type, DataType::Float);
+        assert_eq!(columns[1].data_type, DataType::Boolean);
+    }
+
     #[test]
     fn test_catalog_file_operations() {
         let mut catalog = Catalog::new();
         let columns = vec![ColumnDef {
             name: "test_col".to_string(),
-            data_type: DataType::Int(4),
+            data_type: DataType::Int(None),
             not_null: true,
             unique: false,
             is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
         }];
 
         catalog
@@ -227,4 +455,174 @@ mod tests {
         // 清理测试文件
         std::fs::remove_file(temp_path).ok();
     }
+
+    #[test]
+    fn test_catalog_version_header() {
+        let serialized = Catalog::new().serialize();
+        assert!(serialized.starts_with(METADATA_MAGIC));
+
+        let mut future = serialized.clone();
+        future[METADATA_MAGIC.len()..METADATA_MAGIC.len() + 4]
+            .copy_from_slice(&(METADATA_VERSION + 1).to_le_bytes());
+        assert!(Catalog::deserialize(&future).is_err());
+        assert!(Catalog::deserialize(&serialized[..6]).is_err());
+    }
+
+    #[test]
+    fn test_migrate_legacy_catalogs() {
+        // 前三个样本都由旧版程序执行
+        // CREATE TABLE items (id INT PRIMARY KEY, qty INT(5) NOT NULL, label VARCHAR(20), ...) 生成
+        let v0 = Catalog::deserialize(include_bytes!("../../tests/fixtures/legacy_catalog_v0.meta"))
+            .unwrap();
+        let v1 = Catalog::deserialize(include_bytes!("../../tests/fixtures/legacy_catalog_v1.meta"))
+            .unwrap();
+        let v2 = Catalog::deserialize(include_bytes!("../../tests/fixtures/legacy_catalog_v2.meta"))
+            .unwrap();
+
+        for catalog in [&v0, &v1, &v2] {
+            let columns = catalog.get_table_columns("items").unwrap();
+            assert_eq!(columns[0].data_type, DataType::Int(None));
+            assert!(columns[0].is_primary);
+            assert_eq!(columns[1].data_type, DataType::Int(Some(5)));
+            assert!(columns[1].not_null);
+            assert_eq!(columns[2].data_type, DataType::Varchar(Some(20)));
+            assert!(columns.iter().all(|col| col.default.is_none()));
+        }
+        assert!(v0.get_table_indexes("items").unwrap().is_empty());
+        assert_eq!(v1.get_table_indexes("items").unwrap()[0].name, "idx_label");
+        assert_eq!(v2.get_table_indexes("items").unwrap()[0].name, "idx_label");
+
+        // 版本 3 的样本另有默认值和未声明长度的 VARCHAR（当时记为 u64::MAX）
+        let v3 = Catalog::deserialize(include_bytes!(
+            "../../tests/fixtures/legacy_catalog_v3.meta"
+        ))
+        .unwrap();
+        let columns = v3.get_table_columns("items").unwrap();
+        assert_eq!(
+            columns[1].default,
+            Some(crate::storage::table::Value::Int(1))
+        );
+        assert_eq!(columns[2].data_type, DataType::Varchar(Some(20)));
+        assert_eq!(columns[3].data_type, DataType::Varchar(None));
+        assert_eq!(columns[3].data_type.to_string(), "VARCHAR");
+        assert_eq!(v3.get_table_indexes("items").unwrap()[0].name, "idx_label");
+
+        // 版本 4 的样本与版本 3 的表相同，列还没有外键
+        let v4 = Catalog::deserialize(include_bytes!(
+            "../../tests/fixtures/legacy_catalog_v4.meta"
+        ))
+        .unwrap();
+        let columns = v4.get_table_columns("items").unwrap();
+        assert_eq!(columns[3].data_type, DataType::Varchar(None));
+        assert!(columns.iter().all(|col| col.references.is_none()));
+        assert!(v4.is_dirty());
+
+        // 版本 5 的样本另有一张带外键的表 lines，表还没有 CHECK 约束
+        let v5 = Catalog::deserialize(include_bytes!(
+            "../../tests/fixtures/legacy_catalog_v5.meta"
+        ))
+        .unwrap();
+        assert!(v5.get_table_checks("items").unwrap().is_empty());
+        let columns = v5.get_table_columns("lines").unwrap();
+        assert_eq!(
+            columns[1].references.as_ref().map(|r| r.table.as_str()),
+            Some("items")
+        );
+        assert!(v5.is_dirty());
+
+        // 迁移后按当前格式写回
+        let reloaded = Catalog::deserialize(&v1.serialize()).unwrap();
+        assert_eq!(reloaded.get_table_columns("items").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_table_order_is_deterministic() {
+        let column = ColumnDef {
+            name: "id".to_string(),
+            data_type: DataType::Int(None),
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        };
+        let names = ["orders", "accounts", "zones", "items"];
+        let build = |names: Vec<&str>| {
+            let mut catalog = Catalog::new();
+            for name in names {
+                catalog
+                    .add_table_metadata(name.to_string(), vec![column.clone()])
+                    .unwrap();
+            }
+            catalog
+        };
+        let forward = build(names.to_vec());
+        let backward = build(names.into_iter().rev().collect());
+        assert_eq!(
+            forward.get_table_names(),
+            vec!["accounts", "items", "orders", "zones"]
+        );
+        assert_eq!(forward.serialize(), backward.serialize());
+
+        // 改用有序映射之前，版本 2 的目录按 HashMap 编码，同样的字节仍能读取
+        #[derive(Encode)]
+        struct HashMapCatalog {
+            tables: std::collections::HashMap<String, TableMetadata>,
+        }
+        let old = HashMapCatalog {
+            tables: backward.tables.clone().into_iter().collect(),
+        };
+        let mut buffer = METADATA_MAGIC.to_vec();
+        buffer.extend_from_slice(&METADATA_VERSION.to_le_bytes());
+        buffer.extend(bincode::encode_to_vec(&old, bincode::config::standard()).unwrap());
+        let loaded = Catalog::deserialize(&buffer).unwrap();
+        assert_eq!(loaded.get_table_names(), forward.get_table_names());
+        assert_eq!(loaded.serialize(), forward.serialize());
+    }
+
+    #[test]
+    fn test_mixed_case_table_names_resolve() {
+        let column = ColumnDef {
+            name: "Id".to_string(),
+            data_type: DataType::Int(None),
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        };
+        let mut catalog = Catalog::new();
+        catalog
+            .add_table_metadata("Orders".to_string(), vec![column.clone()])
+            .unwrap();
+        catalog
+            .add_table_metadata("items".to_string(), vec![column.clone()])
+            .unwrap();
+
+        // 精确匹配优先，其次不区分大小写匹配
+        assert_eq!(catalog.resolve_table_name("Orders"), "Orders");
+        assert_eq!(catalog.resolve_table_name("orders"), "Orders");
+        assert_eq!(catalog.resolve_table_name("ITEMS"), "items");
+        assert_eq!(catalog.resolve_table_name("missing"), "missing");
+        assert!(catalog.has_table("ORDERS"));
+        assert_eq!(catalog.get_table_columns("orders").unwrap()[0].name, "Id");
+
+        // 只有大小写不同的表名不能重复添加，同一张表的列名也不能重复
+        assert!(
+            catalog
+                .add_table_metadata("ORDERS".to_string(), vec![column.clone()])
+                .is_err()
+        );
+        let mut duplicate = column.clone();
+        duplicate.name = "ID".to_string();
+        let err = catalog
+            .add_table_metadata("dup".to_string(), vec![column, duplicate])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Duplicate column name 'ID'");
+        assert!(!catalog.has_table("dup"));
+        catalog.remove_table_metadata("orders").unwrap();
+        assert_eq!(catalog.get_table_names(), vec!["items"]);
+    }
 }
@@ -0,0 +1,435 @@
+use super::{Catalog, TableMetadata};
+use crate::error::{DBError, Result};
+use crate::storage::io::page::PageId;
+use crate::storage::table::value::MAX_INT_DISPLAY_WIDTH;
+use crate::storage::table::{ColumnDef, DataType, IndexDef, Value};
+use bincode::Decode;
+use std::collections::HashMap;
+
+// 旧版元数据布局：没有版本头的最初两种，以及带版本头的旧版本。这些类型只用于读取，
+// 保持与当时的编码完全一致，不要随当前的 ColumnDef / IndexDef 一起修改。
+// 迁移得到的目录都标记为有修改，下次保存时按当前格式写回。
+
+/// 旧版数据类型：INT 的参数是声明的宽度，未声明时为 64
+#[derive(Decode)]
+enum LegacyDataType {
+    Int(u64),
+    Varchar(u64),
+}
+
+impl From<LegacyDataType> for DataType {
+    fn from(data_type: LegacyDataType) -> Self {
+        match data_type {
+            // 64 是旧版给裸 INT 填的默认值，无法与显式的 INT(64) 区分，统一视为未声明
+            LegacyDataType::Int(64) => DataType::Int(None),
+            LegacyDataType::Int(width) if width <= MAX_INT_DISPLAY_WIDTH as u64 => {
+                DataType::Int(Some(width as u32))
+            }
+            LegacyDataType::Int(_) => DataType::Int(None),
+            LegacyDataType::Varchar(len) => varchar(len),
+        }
+    }
+}
+
+/// 版本 4 之前的数据类型：未声明长度的 VARCHAR 记为 `Varchar(u64::MAX)`
+#[derive(Decode)]
+enum DataTypeV3 {
+    Int(Option<u32>),
+    Varchar(u64),
+    Float,
+    Boolean,
+}
+
+impl From<DataTypeV3> for DataType {
+    fn from(data_type: DataTypeV3) -> Self {
+        match data_type {
+            DataTypeV3::Int(width) => DataType::Int(width),
+            DataTypeV3::Varchar(len) => varchar(len),
+            DataTypeV3::Float => DataType::Float,
+            DataTypeV3::Boolean => DataType::Boolean,
+        }
+    }
+}
+
+/// 旧版 VARCHAR 的长度，`u64::MAX` 表示未声明长度
+fn varchar(len: u64) -> DataType {
+    DataType::Varchar(Some(len).filter(|&len| len != u64::MAX))
+}
+
+/// 最初的布局：列没有 AUTO_INCREMENT，表没有索引
+#[derive(Decode)]
+struct CatalogV0 {
+    tables: HashMap<String, TableMetadataV0>,
+}
+
+#[derive(Decode)]
+struct TableMetadataV0 {
+    columns: Vec<ColumnDefV0>,
+    page_ids: Vec<PageId>,
+}
+
+#[derive(Decode)]
+struct ColumnDefV0 {
+    name: String,
+    data_type: LegacyDataType,
+    not_null: bool,
+    unique: bool,
+    is_primary: bool,
+}
+
+/// 加入 AUTO_INCREMENT 和索引定义之后、引入版本头之前的布局
+#[derive(Decode)]
+struct CatalogV1 {
+    tables: HashMap<String, TableMetadataV1>,
+}
+
+#[derive(Decode)]
+struct TableMetadataV1 {
+    columns: Vec<ColumnDefV1>,
+    page_ids: Vec<PageId>,
+    indexes: Vec<IndexDefV1>,
+}
+
+#[derive(Decode)]
+struct ColumnDefV1 {
+    name: String,
+    data_type: LegacyDataType,
+    not_null: bool,
+    unique: bool,
+    is_primary: bool,
+    auto_increment: bool,
+}
+
+#[derive(Decode)]
+struct IndexDefV1 {
+    name: String,
+    columns: Vec<String>,
+    unique: bool,
+}
+
+/// 版本 2：带版本头，INT 的宽度改为可选，列还没有默认值
+#[derive(Decode)]
+struct CatalogV2 {
+    tables: HashMap<String, TableMetadataV2>,
+}
+
+#[derive(Decode)]
+struct TableMetadataV2 {
+    columns: Vec<ColumnDefV2>,
+    page_ids: Vec<PageId>,
+    indexes: Vec<IndexDefV1>,
+}
+
+#[derive(Decode)]
+struct ColumnDefV2 {
+    name: String,
+    data_type: DataTypeV3,
+    not_null: bool,
+    unique: bool,
+    is_primary: bool,
+    auto_increment: bool,
+}
+
+/// 版本 3：列加入了默认值，VARCHAR 的长度还不是可选的
+#[derive(Decode)]
+struct CatalogV3 {
+    tables: HashMap<String, TableMetadataV3>,
+}
+
+#[derive(Decode)]
+struct TableMetadataV3 {
+    columns: Vec<ColumnDefV3>,
+    page_ids: Vec<PageId>,
+    indexes: Vec<IndexDefV1>,
+}
+
+#[derive(Decode)]
+struct ColumnDefV3 {
+    name: String,
+    data_type: DataTypeV3,
+    not_null: bool,
+    unique: bool,
+    is_primary: bool,
+    auto_increment: bool,
+    default: Option<Value>,
+}
+
+impl From<ColumnDefV2> for ColumnDefV3 {
+    fn from(col: ColumnDefV2) -> Self {
+        ColumnDefV3 {
+            name: col.name,
+            data_type: col.data_type,
+            not_null: col.not_null,
+            unique: col.unique,
+            is_primary: col.is_primary,
+            auto_increment: col.auto_increment,
+            default: None,
+        }
+    }
+}
+
+/// 版本 4：VARCHAR 的长度改为可选，列还没有外键
+///
+/// 数据类型的编码与当前的 [`DataType`] 相同；以后修改 DataType 时要在这里换成当时的副本。
+#[derive(Decode)]
+struct CatalogV4 {
+    tables: HashMap<String, TableMetadataV4>,
+}
+
+#[derive(Decode)]
+struct TableMetadataV4 {
+    columns: Vec<ColumnDefV4>,
+    page_ids: Vec<PageId>,
+    indexes: Vec<IndexDefV1>,
+}
+
+#[derive(Decode)]
+struct ColumnDefV4 {
+    name: String,
+    data_type: DataType,
+    not_null: bool,
+    unique: bool,
+    is_primary: bool,
+    auto_increment: bool,
+    default: Option<Value>,
+}
+
+impl From<ColumnDefV3> for ColumnDefV4 {
+    fn from(col: ColumnDefV3) -> Self {
+        ColumnDefV4 {
+            name: col.name,
+            data_type: col.data_type.into(),
+            not_null: col.not_null,
+            unique: col.unique,
+            is_primary: col.is_primary,
+            auto_increment: col.auto_increment,
+            default: col.default,
+        }
+    }
+}
+
+/// 版本 5：列加入了外键，表还没有 CHECK 约束
+///
+/// 列和索引的编码与当前的 [`ColumnDef`] / [`IndexDef`] 相同；以后修改它们时要在这里换成
+/// 当时的副本。
+#[derive(Decode)]
+struct CatalogV5 {
+    tables: HashMap<String, TableMetadataV5>,
+}
+
+#[derive(Decode)]
+struct TableMetadataV5 {
+    columns: Vec<ColumnDef>,
+    page_ids: Vec<PageId>,
+    indexes: Vec<IndexDef>,
+}
+
+impl From<IndexDefV1> for IndexDef {
+    fn from(index: IndexDefV1) -> Self {
+        IndexDef {
+            name: index.name,
+            columns: index.columns,
+            unique: index.unique,
+        }
+    }
+}
+
+/// 按从新到旧的顺序尝试旧版布局，只接受恰好用完全部字节的解码结果
+pub(super) fn migrate(buffer: &[u8]) -> Result<Catalog> {
+    if let Some(catalog) = decode_exact::<CatalogV1>(buffer) {
+        return Ok(from_v1(catalog));
+    }
+    if let Some(catalog) = decode_exact::<CatalogV0>(buffer) {
+        return Ok(from_v0(catalog));
+    }
+    Err(DBError::IO("无法识别的元数据格式".to_string()))
+}
+
+/// 读取版本 2 的目录（版本头之后的部分）
+pub(super) fn migrate_v2(body: &[u8]) -> Result<Catalog> {
+    let catalog: CatalogV2 = decode_body(body)?;
+    let tables = catalog
+        .tables
+        .into_iter()
+        .map(|(name, metadata)| {
+            let metadata = TableMetadataV3 {
+                columns: metadata
+                    .columns
+                    .into_iter()
+                    .map(ColumnDefV3::from)
+                    .collect(),
+                page_ids: metadata.page_ids,
+                indexes: metadata.indexes,
+            };
+            (name, metadata)
+        })
+        .collect();
+    Ok(from_v3(CatalogV3 { tables }))
+}
+
+/// 读取版本 3 的目录（版本头之后的部分）
+pub(super) fn migrate_v3(body: &[u8]) -> Result<Catalog> {
+    Ok(from_v3(decode_body(body)?))
+}
+
+/// 读取版本 4 的目录（版本头之后的部分）
+pub(super) fn migrate_v4(body: &[u8]) -> Result<Catalog> {
+    Ok(from_v4(decode_body(body)?))
+}
+
+/// 读取版本 5 的目录（版本头之后的部分）
+pub(super) fn migrate_v5(body: &[u8]) -> Result<Catalog> {
+    let catalog: CatalogV5 = decode_body(body)?;
+    let tables = catalog
+        .tables
+        .into_iter()
+        .map(|(name, metadata)| {
+            let metadata = TableMetadata {
+                columns: metadata.columns,
+                page_ids: metadata.page_ids,
+                indexes: metadata.indexes,
+                checks: Vec::new(),
+            };
+            (name, metadata)
+        })
+        .collect();
+    Ok(Catalog {
+        tables,
+        dirty: true,
+    })
+}
+
+fn decode_body<T: Decode<()>>(body: &[u8]) -> Result<T> {
+    bincode::decode_from_slice(body, bincode::config::standard())
+        .map(|(value, _)| value)
+        .map_err(|e| DBError::IO(format!("反序列化Catalog失败: {}", e)))
+}
+
+fn from_v3(catalog: CatalogV3) -> Catalog {
+    let tables = catalog
+        .tables
+        .into_iter()
+        .map(|(name, metadata)| {
+            let metadata = TableMetadataV4 {
+                columns: metadata
+                    .columns
+                    .into_iter()
+                    .map(ColumnDefV4::from)
+                    .collect(),
+                page_ids: metadata.page_ids,
+                indexes: metadata.indexes,
+            };
+            (name, metadata)
+        })
+        .collect();
+    from_v4(CatalogV4 { tables })
+}
+
+fn from_v4(catalog: CatalogV4) -> Catalog {
+    let tables = catalog
+        .tables
+        .into_iter()
+        .map(|(name, metadata)| {
+            let columns = metadata
+                .columns
+                .into_iter()
+                .map(|col| ColumnDef {
+                    name: col.name,
+                    data_type: col.data_type,
+                    not_null: col.not_null,
+                    unique: col.unique,
+                    is_primary: col.is_primary,
+                    auto_increment: col.auto_increment,
+                    default: col.default,
+                    references: None,
+                })
+                .collect();
+            let metadata = TableMetadata {
+                columns,
+                page_ids: metadata.page_ids,
+                indexes: metadata.indexes.into_iter().map(IndexDef::from).collect(),
+                checks: Vec::new(),
+            };
+            (name, metadata)
+        })
+        .collect();
+    Catalog {
+        tables,
+        dirty: true,
+    }
+}
+
+fn decode_exact<T: Decode<()>>(buffer: &[u8]) -> Option<T> {
+    match bincode::decode_from_slice(buffer, bincode::config::standard()) {
+        Ok((value, read)) if read == buffer.len() => Some(value),
+        _ => None,
+    }
+}
+
+fn from_v1(catalog: CatalogV1) -> Catalog {
+    let tables = catalog
+        .tables
+        .into_iter()
+        .map(|(name, metadata)| {
+            let columns = metadata
+                .columns
+                .into_iter()
+                .map(|col| ColumnDef {
+                    name: col.name,
+                    data_type: col.data_type.into(),
+                    not_null: col.not_null,
+                    unique: col.unique,
+                    is_primary: col.is_primary,
+                    auto_increment: col.auto_increment,
+                    default: None,
+                    references: None,
+                })
+                .collect();
+            let metadata = TableMetadata {
+                columns,
+                page_ids: metadata.page_ids,
+                indexes: metadata.indexes.into_iter().map(IndexDef::from).collect(),
+                checks: Vec::new(),
+            };
+            (name, metadata)
+        })
+        .collect();
+    Catalog {
+        tables,
+        dirty: true,
+    }
+}
+
+fn from_v0(catalog: CatalogV0) -> Catalog {
+    let tables = catalog
+        .tables
+        .into_iter()
+        .map(|(name, metadata)| {
+            let columns = metadata
+                .columns
+                .into_iter()
+                .map(|col| ColumnDef {
+                    name: col.name,
+                    data_type: col.data_type.into(),
+                    not_null: col.not_null,
+                    unique: col.unique,
+                    is_primary: col.is_primary,
+                    auto_increment: false,
+                    default: None,
+                    references: None,
+                })
+                .collect();
+            let metadata = TableMetadata {
+                columns,
+                page_ids: metadata.page_ids,
+                indexes: Vec::new(),
+                checks: Vec::new(),
+            };
+            (name, metadata)
+        })
+        .collect();
+    Catalog {
+        tables,
+        dirty: true,
+    }
+}
@@ -1,16 +1,25 @@
 use super::catalog::Catalog;
 use super::io::PersistenceManager;
-use super::table::{Record, RecordId, Table};
+use super::io::page::PageId;
+use super::table::{
+    AlterColumn, CheckConstraint, CompactionSummary, IndexDef, PrimaryKeyStats, Record, RecordId,
+    Table, TableStats, TableVerification, Truncation,
+};
 use crate::error::{DBError, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound;
 use std::path::Path;
+use std::sync::MutexGuard;
+
+/// 加载数据库时每张表抽样检查的数据页数，完整检查见 [`Database::verify`]
+const LOAD_VERIFY_PAGES: usize = 1;
 
 /// 单个数据库的结构
 pub struct Database {
     /// 数据库名称
     name: String,
     /// 表集合
-    tables: HashMap<String, Table>,
+    tables: BTreeMap<String, Table>,
     /// 元数据目录
     catalog: Catalog,
     /// 持久化管理器
@@ -25,7 +34,7 @@ impl Database {
 
         Ok(Self {
             name,
-            tables: HashMap::new(),
+            tables: BTreeMap::new(),
             catalog,
             persistence,
         })
@@ -48,13 +57,81 @@ impl Database {
         Ok(())
     }
 
+    /// 在已有的表上添加索引定义，并同步到目录
+    pub fn create_index(&mut self, table_name: &str, index: IndexDef) -> Result<()> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+        table.add_index(index)?;
+
+        self.catalog.update_table_schema(
+            table_name,
+            table.columns().to_vec(),
+            table.indexes().to_vec(),
+        )
+    }
+
+    /// 在已有的表上添加 CHECK 约束，只记录在目录中
+    pub fn create_check(&mut self, table_name: &str, check: CheckConstraint) -> Result<()> {
+        if !self.tables.contains_key(table_name) {
+            return Err(DBError::NotFound(format!("表 '{}' 不存在", table_name)));
+        }
+        self.catalog.add_table_check(table_name, check)
+    }
+
+    /// 表的 CHECK 约束，按定义顺序
+    pub fn get_table_checks(&self, table_name: &str) -> Result<Vec<CheckConstraint>> {
+        self.catalog.get_table_checks(table_name)
+    }
+
+    /// 修改表的列并重写全部记录，同步目录中的列、索引和页ID列表
+    pub fn alter_table(&mut self, table_name: &str, operations: &[AlterColumn]) -> Result<()> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+        table.alter_columns(self.persistence.buffer_manager_mut(), operations)?;
+
+        self.catalog.update_table_schema(
+            table_name,
+            table.columns().to_vec(),
+            table.indexes().to_vec(),
+        )?;
+        self.catalog
+            .update_table_page_ids(table_name, table.page_ids().to_vec())
+    }
+
     pub fn drop_table(&mut self, name: &str) -> Result<()> {
         if !self.tables.contains_key(name) {
             return Err(DBError::NotFound(format!("表 '{}' 不存在", name)));
         }
 
-        self.tables.remove(name);
         self.catalog.remove_table_metadata(name)?;
+        if let Some(table) = self.tables.remove(name) {
+            let buffer_manager = self.persistence.buffer_manager_mut();
+            for &page_id in table.page_ids() {
+                buffer_manager.free_page(page_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 修改表名，数据页和索引定义保持不变
+    pub fn rename_table(&mut self, name: &str, new_name: String) -> Result<()> {
+        if self.catalog.has_table(&new_name) {
+            return Err(DBError::Schema(format!("表 '{}' 已存在", new_name)));
+        }
+        if !self.tables.contains_key(name) {
+            return Err(DBError::NotFound(format!("表 '{}' 不存在", name)));
+        }
+
+        self.catalog.rename_table_metadata(name, new_name.clone())?;
+        if let Some(mut table) = self.tables.remove(name) {
+            table.rename(new_name.clone());
+            self.tables.insert(new_name, table);
+        }
 
         Ok(())
     }
@@ -71,8 +148,13 @@ impl Database {
             .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", name)))
     }
 
+    /// 与 `name` 对应的表名，见 [`Catalog::resolve_table_name`]
+    pub fn resolve_table_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.catalog.resolve_table_name(name)
+    }
+
     // new code
-    pub fn get_buffer_manager(&self) -> &super::io::buffer_manager::BufferManager {
+    pub fn get_buffer_manager(&self) -> MutexGuard<'_, super::io::buffer_manager::BufferManager> {
         self.persistence.buffer_manager()
     }
 
@@ -87,9 +169,10 @@ impl Database {
         for table_name in self.catalog.get_table_names() {
             let columns = self.catalog.get_table_columns(&table_name)?;
             let page_ids = self.catalog.get_table_page_ids(&table_name)?;
+            let indexes = self.catalog.get_table_indexes(&table_name)?;
 
             // 创建表对象
-            let mut table = Table::new(table_name.clone(), columns);
+            let mut table = Table::with_indexes(table_name.clone(), columns, indexes);
 
             // 加载表的数据页
             table.load(self.persistence.buffer_manager_mut(), page_ids)?;
@@ -98,23 +181,89 @@ impl Database {
             self.tables.insert(table_name, table);
         }
 
+        // 没有被任何表使用的页面（删除的表、整理后多出的页）留给之后新建的页面复用
+        let used: HashSet<PageId> = self
+            .tables
+            .values()
+            .flat_map(|table| table.page_ids().iter().copied())
+            .collect();
+        self.persistence
+            .buffer_manager_mut()
+            .reclaim_unused_pages(&used);
+
+        // 元数据与数据文件不同步（保存中途崩溃、手工复制文件）时尽早提示
+        for (table_name, result) in self.verify(Some(LOAD_VERIFY_PAGES)) {
+            if !result.is_consistent() {
+                log::error!(
+                    "表 '{}.{}' 的记录与表结构不一致（{}），可用 .verify 完整检查",
+                    self.name,
+                    table_name,
+                    result.first_problem.unwrap_or_default()
+                );
+            }
+        }
+
         Ok(())
     }
 
-    /// 保存数据库
+    /// 逐表检查记录与表结构是否一致，按表名排列，见 [`Table::verify`]
+    pub fn verify(&self, max_pages: Option<usize>) -> Vec<(String, TableVerification)> {
+        let mut buffer_manager = self.persistence.buffer_manager();
+        self.tables
+            .iter()
+            .map(|(name, table)| (name.clone(), table.verify(&mut buffer_manager, max_pages)))
+            .collect()
+    }
+
+    /// 数据库的元数据目录
+    pub fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// 当前元数据的快照，页ID列表取自内存中的表而不是上次保存时的值
+    pub fn snapshot_catalog(&self) -> Result<Catalog> {
+        let mut catalog = self.catalog.clone();
+        for (table_name, table) in &self.tables {
+            catalog.update_table_page_ids(table_name, table.page_ids().to_vec())?;
+        }
+        Ok(catalog)
+    }
+
+    /// 保存数据库：先写数据页，再写元数据
+    ///
+    /// 元数据最后写入，保存中途崩溃时不会记下还没有写入的页面。
+    /// 只写入修改过的页面，目录没有修改时不重写元数据文件。
     pub fn save(&mut self) -> Result<()> {
-        // 更新目录中的页ID列表
+        self.flush_pages()?;
+        self.save_metadata()
+    }
+
+    /// 上次保存之后是否有需要写入的内容：脏页、修改过的目录或变化了的页ID列表
+    pub fn is_dirty(&self) -> bool {
+        self.catalog.is_dirty()
+            || self.persistence.buffer_manager().has_dirty_pages()
+            || self.tables.iter().any(|(table_name, table)| {
+                self.catalog
+                    .get_table_page_ids(table_name)
+                    .is_ok_and(|page_ids| page_ids != table.page_ids())
+            })
+    }
+
+    /// 把脏页写回磁盘，并更新目录中的页ID列表（目录本身不写入）
+    pub fn flush_pages(&mut self) -> Result<()> {
         for (table_name, table) in &self.tables {
             self.catalog
                 .update_table_page_ids(table_name, table.page_ids().to_vec())?;
         }
+        self.persistence.flush_dirty()
+    }
 
-        // 保存元数据
-        self.persistence.save_metadata(&self.name, &self.catalog)?;
-
-        // 刷新所有缓冲区页面到磁盘
-        self.persistence.buffer_manager_mut().flush_all_pages()?;
-
+    /// 目录有修改时写入元数据文件，应在 [`flush_pages`](Self::flush_pages) 成功之后调用
+    pub fn save_metadata(&mut self) -> Result<()> {
+        if self.catalog.is_dirty() {
+            self.persistence.save_metadata(&self.name, &self.catalog)?;
+            self.catalog.mark_clean();
+        }
         Ok(())
     }
 
@@ -135,6 +284,20 @@ impl Database {
         }
     }
 
+    /// 批量插入记录的代理方法，返回新记录的ID和被截断的值
+    pub fn batch_insert_records(
+        &mut self,
+        table_name: &str,
+        rows: Vec<Vec<super::table::Value>>,
+        truncate: bool,
+    ) -> Result<(Vec<RecordId>, Vec<Truncation>)> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+        table.batch_insert_records(self.persistence.buffer_manager_mut(), rows, truncate)
+    }
+
     /// 删除表中记录的代理方法
     pub fn delete_record(&mut self, table_name: &str, record_id: RecordId) -> Result<()> {
         // 检查表是否存在
@@ -148,40 +311,175 @@ impl Database {
         }
     }
 
-    /// 更新表中记录的代理方法
+    /// 更新表中记录的代理方法，返回记录更新后所在的位置
+    ///
+    /// 记录被搬到其它页面时同步目录中的页ID列表，见 [`Table::update_record`]。
     pub fn update_record(
         &mut self,
         table_name: &str,
         record_id: RecordId,
         set_pairs: &Vec<(String, super::table::Value)>,
-    ) -> Result<()> {
+    ) -> Result<RecordId> {
         // 检查表是否存在
         if let Some(table) = self.tables.get_mut(table_name) {
             // 获取可变的缓冲区管理器
             let buffer_manager = self.persistence.buffer_manager_mut();
             // 调用表的 update_record 方法更新记录
-            table.update_record(buffer_manager, record_id, set_pairs)
+            let new_id = table.update_record(buffer_manager, record_id, set_pairs)?;
+            if new_id != record_id {
+                self.catalog
+                    .update_table_page_ids(table_name, table.page_ids().to_vec())?;
+            }
+            Ok(new_id)
         } else {
             Err(DBError::NotFound(format!("表 '{}' 不存在", table_name)))
         }
     }
 
+    /// 按记录ID获取单条记录的代理方法
+    pub fn get_record(&self, table_name: &str, record_id: RecordId) -> Result<Record> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        table.get_record(&mut self.persistence.buffer_manager(), record_id)
+    }
+
+    /// 按主键值查找记录的代理方法，见 [`Table::find_by_primary_key`]
+    pub fn find_by_primary_key(
+        &self,
+        table_name: &str,
+        key: &super::table::Value,
+    ) -> Result<Option<Record>> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        table.find_by_primary_key(&mut self.persistence.buffer_manager(), key)
+    }
+
+    /// 按主键范围查找记录的代理方法，见 [`Table::find_by_primary_key_range`]
+    pub fn find_by_primary_key_range(
+        &self,
+        table_name: &str,
+        low: Bound<&super::table::Value>,
+        high: Bound<&super::table::Value>,
+    ) -> Result<Vec<Record>> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        table.find_by_primary_key_range(&mut self.persistence.buffer_manager(), low, high)
+    }
+
+    /// 主键索引的统计信息，见 [`Table::primary_key_stats`]
+    pub fn primary_key_stats(&self, table_name: &str) -> Result<Option<PrimaryKeyStats>> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        Ok(table.primary_key_stats())
+    }
+
     /// 获取表中全部记录的代理方法
-    pub fn get_all_records(&mut self, table_name: &str) -> Result<Vec<Record>> {
+    pub fn get_all_records(&self, table_name: &str) -> Result<Vec<Record>> {
         // 检查表是否存在
         let table = self
             .tables
             .get(table_name)
             .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
 
-        // 获取缓冲区管理器
-        let buffer_manager = self.persistence.buffer_manager_mut();
+        // 锁住缓冲区管理器，调用表的 get_all_records 方法获取所有记录
+        table.get_all_records(&mut self.persistence.buffer_manager())
+    }
+
+    /// 获取表中全部记录的指定列，见 [`Table::get_records_projected`]
+    pub fn get_records_projected(
+        &self,
+        table_name: &str,
+        positions: &[usize],
+    ) -> Result<Vec<Record>> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        table.get_records_projected(&mut self.persistence.buffer_manager(), positions)
+    }
+
+    /// 逐条读取表中的记录交给 `visit`，见 [`Table::scan`]；扫描期间一直持有缓冲池的锁
+    pub fn scan_records<F>(
+        &self,
+        table_name: &str,
+        positions: Option<&[usize]>,
+        mut visit: F,
+    ) -> Result<()>
+    where
+        F: FnMut(Record) -> Result<()>,
+    {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        let mut buffer_manager = self.persistence.buffer_manager();
+        table
+            .scan(&mut buffer_manager, positions)
+            .try_for_each(|record| visit(record?))
+    }
+
+    /// 检查一批待写入的行是否满足表的 NOT NULL 和唯一约束
+    pub fn check_constraints(
+        &self,
+        table_name: &str,
+        rows: &[Vec<super::table::Value>],
+        replaced: &[RecordId],
+    ) -> Result<()> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+        table.check_constraints(&mut self.persistence.buffer_manager(), rows, replaced)
+    }
+
+    /// 让一批待写入的行符合表的 VARCHAR 长度，见 [`Table::fit_strings`]
+    pub fn fit_strings(
+        &self,
+        table_name: &str,
+        rows: &mut [Vec<super::table::Value>],
+        truncate: bool,
+    ) -> Result<Vec<Truncation>> {
+        self.get_table(table_name)?.fit_strings(rows, truncate)
+    }
+
+    /// 表的存储统计，见 [`Table::stats`]
+    pub fn table_stats(&self, table_name: &str) -> Result<TableStats> {
+        let table = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
+
+        table.stats(&mut self.persistence.buffer_manager())
+    }
+
+    /// 整理表的数据页，并同步目录中的页ID列表
+    pub fn compact_table(&mut self, table_name: &str) -> Result<CompactionSummary> {
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| DBError::NotFound(format!("表 '{}' 不存在", table_name)))?;
 
-        // 调用表的 get_all_records 方法获取所有记录
-        table.get_all_records(buffer_manager)
+        let summary = table.compact(self.persistence.buffer_manager_mut())?;
+        self.catalog
+            .update_table_page_ids(table_name, table.page_ids().to_vec())?;
+        Ok(summary)
     }
 
-    /// 获取数据库中所有表的名称
+    /// 获取数据库中所有表的名称，按字母顺序排列
     pub fn get_table_names(&self) -> Vec<String> {
         self.catalog.get_table_names()
     }
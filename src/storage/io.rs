@@ -1,6 +1,7 @@
 pub mod buffer_manager;
 mod disk_manager;
 pub mod page;
+mod wal;
 
 use crate::error::{DBError, Result};
 use crate::storage::catalog::Catalog;
@@ -8,13 +9,16 @@ use buffer_manager::BufferManager;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 /// 持久化管理器 - 负责数据库元数据和记录的持久化
 pub struct PersistenceManager {
     /// 数据库目录
     db_dir: PathBuf,
     /// 缓冲池管理器
-    buffer_manager: BufferManager,
+    ///
+    /// 读取页面也会改动缓存和 LRU 顺序，共享访问时需要加锁，见 [`buffer_manager`](Self::buffer_manager)
+    buffer_manager: Mutex<BufferManager>,
 }
 
 impl PersistenceManager {
@@ -29,11 +33,11 @@ impl PersistenceManager {
         let data_file = db_dir.join("data.db");
 
         // 创建缓冲池管理器
-        let buffer_manager = BufferManager::new(data_file)?;
+        let buffer_manager = BufferManager::new(data_file, buffer_manager::DEFAULT_CAPACITY)?;
 
         Ok(Self {
             db_dir,
-            buffer_manager,
+            buffer_manager: Mutex::new(buffer_manager),
         })
     }
 
@@ -41,9 +45,8 @@ impl PersistenceManager {
     pub fn save_metadata(&self, database_name: &str, catalog: &Catalog) -> Result<()> {
         let metadata_file = self.db_dir.join(format!("{}.meta", database_name));
 
-        // 使用 bincode 2.x 序列化元数据
-        let catalog_data = bincode::encode_to_vec(catalog, bincode::config::standard())
-            .map_err(|e| DBError::IO(format!("无法序列化元数据: {}", e)))?;
+        // 序列化元数据（带格式版本头）
+        let catalog_data = catalog.serialize();
 
         // 写入文件
         let mut file = File::create(metadata_file)
@@ -75,11 +78,9 @@ impl PersistenceManager {
         file.read_to_end(&mut buffer)
             .map_err(|e| DBError::IO(format!("无法读取元数据: {}", e)))?;
 
-        // 使用 bincode 2.x 反序列化
-        let (catalog, _) = bincode::decode_from_slice(&buffer, bincode::config::standard())
-            .map_err(|e| DBError::IO(format!("无法解析元数据: {}", e)))?;
-
-        Ok(catalog)
+        // 反序列化，旧版格式在这里完成迁移
+        Catalog::deserialize(&buffer)
+            .map_err(|e| DBError::IO(format!("无法解析元数据: {}", e)))
     }
 
     /// 检查数据库是否存在
@@ -111,16 +112,11 @@ impl PersistenceManager {
             let entry = entry.map_err(|e| DBError::IO(format!("无法读取目录项: {}", e)))?;
 
             let path = entry.path();
-            if path.is_file() {
-                if let Some(extension) = path.extension() {
-                    if extension == "meta" {
-                        if let Some(stem) = path.file_stem() {
-                            if let Some(name) = stem.to_str() {
-                                databases.push(name.to_string());
-                            }
-                        }
-                    }
-                }
+            if path.is_file()
+                && path.extension().is_some_and(|ext| ext == "meta")
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                databases.push(name.to_string());
             }
         }
 
@@ -128,6 +124,8 @@ impl PersistenceManager {
     }
 
     /// 备份数据库元数据
+    ///
+    /// 只复制目录，不包含数据页；完整的快照见 `StorageEngine::export_database`。
     pub fn backup_metadata(&self, database_name: &str, backup_path: &str) -> Result<()> {
         let metadata_file = self.db_dir.join(format!("{}.meta", database_name));
 
@@ -158,9 +156,8 @@ impl PersistenceManager {
             fs::read(backup_path).map_err(|e| DBError::IO(format!("无法读取备份文件: {}", e)))?;
 
         // 尝试反序列化以验证数据完整性
-        let _: Catalog = bincode::decode_from_slice(&backup_data, bincode::config::standard())
-            .map_err(|e| DBError::IO(format!("备份文件损坏或格式不正确: {}", e)))?
-            .0;
+        Catalog::deserialize(&backup_data)
+            .map_err(|e| DBError::IO(format!("备份文件损坏或格式不正确: {}", e)))?;
 
         // 复制备份文件到元数据文件
         fs::copy(backup_path, metadata_file)
@@ -169,19 +166,30 @@ impl PersistenceManager {
         Ok(())
     }
 
-    /// 获取缓冲池管理器引用
-    pub fn buffer_manager(&self) -> &BufferManager {
-        &self.buffer_manager
+    /// 锁住缓冲池管理器，供只持有共享引用的读操作使用
+    ///
+    /// 多个线程同时读取同一个数据库时在这里逐个访问页面；持锁的读线程 panic 不会让缓冲池不可用。
+    pub fn buffer_manager(&self) -> MutexGuard<'_, BufferManager> {
+        self.buffer_manager
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
     }
 
-    /// 获取可变缓冲池管理器引用
+    /// 获取可变缓冲池管理器引用，独占访问时不需要加锁
     pub fn buffer_manager_mut(&mut self) -> &mut BufferManager {
-        &mut self.buffer_manager
+        self.buffer_manager
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
     }
 
     /// 刷新所有数据到磁盘
     pub fn flush_all(&mut self) -> Result<()> {
-        self.buffer_manager.flush_all_pages()
+        self.buffer_manager_mut().flush_all_pages()
+    }
+
+    /// 只把上次刷新之后修改过的脏页写回磁盘，见 [`BufferManager::flush_dirty_pages`]
+    pub fn flush_dirty(&mut self) -> Result<()> {
+        self.buffer_manager_mut().flush_dirty_pages()
     }
 
     /// 获取数据库目录路径
@@ -224,10 +232,13 @@ mod tests {
         let mut catalog = Catalog::new();
         let columns = vec![ColumnDef {
             name: "id".to_string(),
-            data_type: DataType::Int(4),
+            data_type: DataType::Int(None),
             not_null: true,
             unique: true,
             is_primary: true,
+            auto_increment: false,
+            default: None,
+            references: None,
         }];
         catalog
             .add_table_metadata("test_table".to_string(), columns)
@@ -282,10 +293,13 @@ mod tests {
         let mut catalog = Catalog::new();
         let columns = vec![ColumnDef {
             name: "test_col".to_string(),
-            data_type: DataType::Varchar(100),
+            data_type: DataType::Varchar(Some(100)),
             not_null: false,
             unique: false,
             is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
         }];
         catalog
             .add_table_metadata("backup_test".to_string(), columns)
@@ -314,4 +328,111 @@ mod tests {
         let restored_catalog = persistence.load_metadata("test_backup_db").unwrap();
         assert!(restored_catalog.has_table("backup_test"));
     }
+
+    #[test]
+    fn test_wal_recovers_interrupted_flush() {
+        use crate::storage::io::page::Page;
+        use crate::storage::table::Value;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut persistence = PersistenceManager::new(temp_dir.path()).unwrap();
+        let buffer_manager = persistence.buffer_manager_mut();
+        let page_id = buffer_manager.create_page().unwrap();
+        buffer_manager
+            .get_page_mut(page_id)
+            .unwrap()
+            .insert_record(vec![Value::Int(1)])
+            .unwrap();
+        persistence.flush_all().unwrap();
+
+        // 模拟刷盘中途崩溃：新页面已写入预写日志并落盘，数据文件还没来得及覆盖，
+        // 日志末尾还有一批只写了一半，缺少提交记录
+        let mut page = Page::new(page_id);
+        page.insert_record(vec![Value::Int(1)]).unwrap();
+        page.insert_record(vec![Value::Int(2)]).unwrap();
+        let wal_path = temp_dir.path().join("data.wal");
+        wal::WriteAheadLog::open(&wal_path)
+            .unwrap()
+            .append(&[(page_id, page.serialize().unwrap())])
+            .unwrap();
+        let mut torn = Vec::new();
+        wal::WriteAheadLog::open(temp_dir.path().join("torn.wal"))
+            .unwrap()
+            .append(&[(page_id, Page::new(page_id).serialize().unwrap())])
+            .unwrap();
+        torn.extend_from_slice(&fs::read(temp_dir.path().join("torn.wal")).unwrap());
+        torn.pop();
+        let mut file = fs::OpenOptions::new().append(true).open(&wal_path).unwrap();
+        file.write_all(&torn).unwrap();
+        drop(persistence);
+
+        // 重新打开时重做已提交的批次，忽略写了一半的批次并清空日志
+        let mut persistence = PersistenceManager::new(temp_dir.path()).unwrap();
+        let page = persistence.buffer_manager_mut().get_page(page_id).unwrap();
+        let values: Vec<Value> = page
+            .iter_records()
+            .map(|(_, record)| record.values()[0].clone())
+            .collect();
+        assert_eq!(values, vec![Value::Int(1), Value::Int(2)]);
+        assert_eq!(fs::metadata(&wal_path).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_wal_skips_partially_logged_batch() {
+        use crate::storage::io::page::Page;
+        use crate::storage::table::Value;
+
+        let first_values = |persistence: &mut PersistenceManager, page_id| {
+            let page = persistence.buffer_manager_mut().get_page(page_id).unwrap();
+            page.iter_records()
+                .map(|(_, record)| record.values()[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let temp_dir = TempDir::new().unwrap();
+        let mut persistence = PersistenceManager::new(temp_dir.path()).unwrap();
+        let buffer_manager = persistence.buffer_manager_mut();
+        let page_ids: Vec<_> = (1..=2)
+            .map(|n| {
+                let page_id = buffer_manager.create_page().unwrap();
+                buffer_manager
+                    .get_page_mut(page_id)
+                    .unwrap()
+                    .insert_record(vec![Value::Int(n)])
+                    .unwrap();
+                page_id
+            })
+            .collect();
+        persistence.flush_all().unwrap();
+
+        // 模拟追加一批两个页面的中途崩溃：第一个页面完整写入日志，第二个页面和提交记录缺失
+        let batch: Vec<_> = page_ids
+            .iter()
+            .map(|&page_id| {
+                let mut page = Page::new(page_id);
+                page.insert_record(vec![Value::Int(0)]).unwrap();
+                (page_id, page.serialize().unwrap())
+            })
+            .collect();
+        let wal_path = temp_dir.path().join("data.wal");
+        wal::WriteAheadLog::open(&wal_path)
+            .unwrap()
+            .append(&batch)
+            .unwrap();
+        // 两项一样长，截断到一半再多 20 字节正好落在第二项中间
+        let logged = fs::read(&wal_path).unwrap();
+        fs::write(&wal_path, &logged[..logged.len() / 2 + 20]).unwrap();
+        drop(persistence);
+
+        // 两个页面都保持旧内容，不会只重做其中一个
+        let mut persistence = PersistenceManager::new(temp_dir.path()).unwrap();
+        assert_eq!(
+            first_values(&mut persistence, page_ids[0]),
+            vec![Value::Int(1)]
+        );
+        assert_eq!(
+            first_values(&mut persistence, page_ids[1]),
+            vec![Value::Int(2)]
+        );
+        assert_eq!(fs::metadata(&wal_path).unwrap().len(), 0);
+    }
 }
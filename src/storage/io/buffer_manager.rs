@@ -1,64 +1,203 @@
 use super::disk_manager::DiskManager;
 use super::page::{Page, PageId};
 use crate::error::{DBError, Result};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::Path;
 
-/// 缓冲池大小（可以根据需要调整）
-const BUFFER_POOL_SIZE: usize = 1024;
+/// 默认的缓冲池容量（页数）
+pub const DEFAULT_CAPACITY: usize = 1024;
 
 /// 缓冲池管理器 - 负责页面的缓存和置换
+///
+/// 缓存的页面数超过容量时，置换最久未使用且没有被钉住的页面，脏页先写回磁盘。
+/// 事务期间修改过的页面在提交或回滚之前不会写回磁盘（no-steal），缓冲池放不下时暂时超出容量。
 pub struct BufferManager {
     /// 磁盘管理器
     disk_manager: DiskManager,
     /// 页面缓存
     pages: HashMap<PageId, Page>,
-    /// 最近使用的页面ID
-    lru_list: Vec<PageId>,
-    /// 被钉住的页面（不能被置换出去）
-    pinned_pages: HashSet<PageId>,
+    /// 最多缓存的页面数
+    capacity: usize,
+    /// 每个缓存页面最近一次被访问的时刻
+    last_used: HashMap<PageId, u64>,
+    /// 按访问时刻排列的缓存页面，最久未使用的在前
+    lru: BTreeMap<u64, PageId>,
+    /// 访问时刻计数器，每次访问加一
+    clock: u64,
+    /// 被钉住的页面及其钉住次数（不能被置换出去）
+    pin_counts: HashMap<PageId, usize>,
+    /// 已释放、可以被新页面复用的页ID
+    free_pages: BTreeSet<PageId>,
+    /// 上次刷新之后取得过可变引用的页面，只有它们可能是脏页
+    modified: HashSet<PageId>,
+    /// 事务期间取得过可变引用的页面，提交或回滚之前不能写回磁盘；没有事务时为 None
+    transaction_pages: Option<HashSet<PageId>>,
+    /// 在线备份期间保留的页面前像（页ID -> 备份开始时的序列化内容），
+    /// 没有备份进行时为 None
+    preserved_pages: Option<HashMap<PageId, Vec<u8>>>,
+    /// 打开以来访问页面的次数（含缓存命中）
+    pages_read: u64,
+    /// 请求的页面已在缓存中的次数
+    hits: u64,
+    /// 请求的页面需要从磁盘加载的次数
+    misses: u64,
 }
 
 impl BufferManager {
-    pub fn new<P: AsRef<Path>>(db_file_path: P) -> Result<Self> {
+    /// 打开数据文件，缓冲池最多缓存 `capacity` 个页面
+    pub fn new<P: AsRef<Path>>(db_file_path: P, capacity: usize) -> Result<Self> {
+        if capacity == 0 {
+            return Err(DBError::Other("缓冲池容量必须大于 0".to_string()));
+        }
         Ok(Self {
             disk_manager: DiskManager::new(db_file_path)?,
             pages: HashMap::new(),
-            lru_list: Vec::new(),
-            pinned_pages: HashSet::new(),
+            capacity,
+            last_used: HashMap::new(),
+            lru: BTreeMap::new(),
+            clock: 0,
+            pin_counts: HashMap::new(),
+            free_pages: BTreeSet::new(),
+            modified: HashSet::new(),
+            transaction_pages: None,
+            preserved_pages: None,
+            pages_read: 0,
+            hits: 0,
+            misses: 0,
         })
     }
 
     /// 获取页面，如果不在缓存中则从磁盘加载
     pub fn get_page(&mut self, page_id: PageId) -> Result<&Page> {
-        if !self.pages.contains_key(&page_id) {
-            // 页面不在缓存中，需要从磁盘加载
-            self.load_page(page_id)?;
+        self.pages_read += 1;
+        self.fetch(page_id)?;
+        Ok(&self.pages[&page_id])
+    }
+
+    /// 一次取得多个页面，不在缓存中的页面合并为尽量少的磁盘读取，见 [`prefetch`](Self::prefetch)
+    ///
+    /// 返回的页面与 `page_ids` 一一对应，页面数不能超过缓冲池容量。
+    pub fn read_pages(&mut self, page_ids: &[PageId]) -> Result<Vec<&Page>> {
+        if page_ids.len() > self.capacity {
+            return Err(DBError::Other(format!(
+                "一次读取的页面数 {} 超过了缓冲池容量 {}",
+                page_ids.len(),
+                self.capacity
+            )));
         }
+        self.prefetch(page_ids)?;
+        self.pages_read += page_ids.len() as u64;
+        // 钉住的页面占满缓冲池时，先读入的页面可能已被置换
+        page_ids
+            .iter()
+            .map(|page_id| {
+                self.pages.get(page_id).ok_or_else(|| {
+                    DBError::IO("缓冲池中被钉住的页面过多，无法同时容纳这批页面".to_string())
+                })
+            })
+            .collect()
+    }
 
-        // 更新LRU列表
-        self.update_lru(page_id);
+    /// 预读一批页面到缓冲池，不计入页面访问次数
+    ///
+    /// 已在缓存中的页面记为最近使用；其余页面按页ID排序后读取，数据文件中相邻的页面
+    /// 合并为一次读取。页面数超过缓冲池容量时，先读入的页面可能又被置换出去。
+    pub fn prefetch(&mut self, page_ids: &[PageId]) -> Result<()> {
+        let mut missing = Vec::new();
+        for &page_id in page_ids {
+            if self.pages.contains_key(&page_id) {
+                self.touch(page_id);
+            } else {
+                missing.push(page_id);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
 
-        // 返回页面
-        Ok(self.pages.get(&page_id).unwrap()) // Safe unwrap as we just ensured it exists
+        for (page_id, data) in self.disk_manager.read_pages(&missing)? {
+            let page = Page::from_data(page_id, &data)?;
+            self.misses += 1;
+            self.make_room()?;
+            self.pages.insert(page_id, page);
+            self.touch(page_id);
+        }
+        Ok(())
     }
 
     /// 获取可变页面引用
     pub fn get_page_mut(&mut self, page_id: PageId) -> Result<&mut Page> {
-        if !self.pages.contains_key(&page_id) {
-            // 页面不在缓存中，需要从磁盘加载
-            self.load_page(page_id)?;
+        self.pages_read += 1;
+        self.fetch(page_id)?;
+        self.modified.insert(page_id);
+        if let Some(transaction_pages) = &mut self.transaction_pages {
+            transaction_pages.insert(page_id);
         }
 
-        // 更新LRU列表
-        self.update_lru(page_id);
+        // 在线备份期间，页面第一次被修改前保留它的前像
+        if let Some(preserved) = &mut self.preserved_pages
+            && !preserved.contains_key(&page_id)
+        {
+            preserved.insert(page_id, self.pages[&page_id].serialize()?);
+        }
 
-        // 返回可变页面引用
-        Ok(self.pages.get_mut(&page_id).unwrap()) // Safe unwrap as we just ensured it exists
+        Ok(self.pages.get_mut(&page_id).unwrap()) // fetch 保证页面已在缓存中
+    }
+
+    /// 开始记录事务修改的页面（BEGIN 时调用），之后修改的页面在
+    /// [`end_transaction`](Self::end_transaction) 之前都不会写回磁盘
+    pub fn begin_transaction(&mut self) {
+        self.transaction_pages = Some(HashSet::new());
+    }
+
+    /// 事务提交或回滚完成后调用，被扣留的页面重新可以置换和刷新
+    pub fn end_transaction(&mut self) {
+        self.transaction_pages = None;
+    }
+
+    /// 页面是否被未结束的事务修改过，这样的页面不能写回磁盘
+    fn held_by_transaction(&self, page_id: PageId) -> bool {
+        self.transaction_pages
+            .as_ref()
+            .is_some_and(|transaction_pages| transaction_pages.contains(&page_id))
+    }
+
+    /// 开始保留页面前像（在线备份开始时调用）
+    pub fn begin_snapshot(&mut self) -> Result<()> {
+        if self.preserved_pages.is_some() {
+            return Err(DBError::Other("已有在线备份正在进行".to_string()));
+        }
+        self.preserved_pages = Some(HashMap::new());
+        Ok(())
+    }
+
+    /// 停止保留页面前像并释放已保留的内容
+    pub fn end_snapshot(&mut self) {
+        self.preserved_pages = None;
+    }
+
+    /// 读取页面在快照开始时的序列化内容
+    ///
+    /// 快照开始后被修改过的页面返回保留的前像，其余页面返回当前内容。
+    pub fn snapshot_page(&mut self, page_id: PageId) -> Result<Vec<u8>> {
+        if let Some(data) = self
+            .preserved_pages
+            .as_ref()
+            .and_then(|preserved| preserved.get(&page_id))
+        {
+            return Ok(data.clone());
+        }
+        self.get_page(page_id)?.serialize()
     }
 
-    /// 创建新页面
+    /// 创建新页面，优先复用已释放的页面（页ID最小的先用）
     pub fn create_page(&mut self) -> Result<PageId> {
+        // 复用的页面经过 get_page_mut 清空，在线备份期间先保留它的前像
+        if let Some(page_id) = self.free_pages.pop_first() {
+            self.get_page_mut(page_id)?.reset_records(Vec::new());
+            return Ok(page_id);
+        }
+
         // 分配新页面ID
         let page_id = self.disk_manager.allocate_page()?;
 
@@ -66,88 +205,205 @@ impl BufferManager {
         let page = Page::new(page_id);
 
         // 如果缓存已满，需要置换页面
-        if self.pages.len() >= BUFFER_POOL_SIZE {
-            self.evict_page()?;
-        }
+        self.make_room()?;
 
         // 将新页面加入缓存
         self.pages.insert(page_id, page);
-        self.update_lru(page_id);
+        self.touch(page_id);
 
         Ok(page_id)
     }
 
+    /// 释放不再被任何表使用的页面，之后创建页面时复用
+    pub fn free_page(&mut self, page_id: PageId) {
+        self.free_pages.insert(page_id);
+    }
+
+    /// 打开数据库时调用：数据文件中不在 `used` 里的页面都视为已释放
+    pub fn reclaim_unused_pages(&mut self, used: &HashSet<PageId>) {
+        self.free_pages = (0..self.disk_manager.page_count())
+            .filter(|page_id| !used.contains(page_id))
+            .collect();
+    }
+
+    /// 已释放、等待复用的页面数
+    pub fn free_page_count(&self) -> usize {
+        self.free_pages.len()
+    }
+
     /**
     将页面钉在缓冲池中（防止被置换出去）
+
+    同一页面可以被钉住多次，需要同样次数的 [`unpin_page`](Self::unpin_page) 才能再被置换。
     */
     pub fn pin_page(&mut self, page_id: PageId) -> Result<()> {
-        if !self.pages.contains_key(&page_id) {
-            self.load_page(page_id)?;
-        }
-
-        self.pinned_pages.insert(page_id);
+        self.fetch(page_id)?;
+        *self.pin_counts.entry(page_id).or_default() += 1;
         Ok(())
     }
 
-    /// 取消页面的钉住状态
+    /// 取消一次页面的钉住状态
     pub fn unpin_page(&mut self, page_id: PageId) {
-        self.pinned_pages.remove(&page_id);
+        if let Some(count) = self.pin_counts.get_mut(&page_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.pin_counts.remove(&page_id);
+            }
+        }
+    }
+
+    /// 缓冲池容量（页数）
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 当前缓存的页面数
+    pub fn cached_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// 打开以来请求的页面已在缓存中的次数
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// 打开以来请求的页面需要从磁盘加载的次数
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// 打开以来通过 [`get_page`](Self::get_page) / [`get_page_mut`](Self::get_page_mut)
+    /// 访问页面的次数，缓存命中也计入
+    pub fn pages_read(&self) -> u64 {
+        self.pages_read
+    }
+
+    /// 打开以来读取数据文件的次数，相邻页面的批量读取只算一次
+    pub fn file_reads(&self) -> u64 {
+        self.disk_manager.file_reads()
+    }
+
+    /// 打开以来写入磁盘的页面次数
+    pub fn pages_written(&self) -> u64 {
+        self.disk_manager.pages_written()
     }
 
     /// 刷新单个脏页面到磁盘
     pub fn flush_page(&mut self, page_id: PageId) -> Result<()> {
-        if let Some(page) = self.pages.get_mut(&page_id) {
-            if page.is_dirty() {
-                self.disk_manager.write_page(page_id, &page.serialize()?)?;
+        self.flush_pages(&[page_id])
+    }
+
+    /// 刷新所有脏页面到磁盘，整批经过一次预写日志
+    pub fn flush_all_pages(&mut self) -> Result<()> {
+        let page_ids: Vec<PageId> = self.pages.keys().copied().collect();
+        self.flush_pages(&page_ids)
+    }
+
+    /// 只检查上次刷新之后取得过可变引用的页面，把其中的脏页写回磁盘
+    ///
+    /// 与 [`flush_all_pages`](Self::flush_all_pages) 写入的内容相同，但不遍历整个缓冲池。
+    pub fn flush_dirty_pages(&mut self) -> Result<()> {
+        let mut page_ids: Vec<PageId> = self.modified.iter().copied().collect();
+        page_ids.sort_unstable();
+        self.flush_pages(&page_ids)
+    }
+
+    /// 缓冲池中是否有尚未写回磁盘的脏页
+    pub fn has_dirty_pages(&self) -> bool {
+        self.modified
+            .iter()
+            .any(|page_id| self.pages.get(page_id).is_some_and(Page::is_dirty))
+    }
+
+    /// 把给定页面中的脏页作为一批写入磁盘，跳过未结束的事务修改过的页面
+    fn flush_pages(&mut self, page_ids: &[PageId]) -> Result<()> {
+        let page_ids: Vec<PageId> = page_ids
+            .iter()
+            .copied()
+            .filter(|&page_id| !self.held_by_transaction(page_id))
+            .collect();
+        let mut dirty = Vec::new();
+        for page_id in &page_ids {
+            if let Some(page) = self.pages.get(page_id)
+                && page.is_dirty()
+            {
+                dirty.push((*page_id, page.serialize()?));
+            }
+        }
+        self.disk_manager.write_pages(&dirty)?;
+        for (page_id, _) in &dirty {
+            if let Some(page) = self.pages.get_mut(page_id) {
                 page.clear_dirty();
             }
         }
+        for page_id in &page_ids {
+            self.modified.remove(page_id);
+        }
         Ok(())
     }
 
-    /// 刷新所有脏页面到磁盘
-    pub fn flush_all_pages(&mut self) -> Result<()> {
-        for page_id in self.pages.keys().copied().collect::<Vec<_>>() {
-            self.flush_page(page_id)?;
+    /// 确保页面在缓存中（不在时从磁盘加载），并记为最近使用
+    fn fetch(&mut self, page_id: PageId) -> Result<()> {
+        if self.pages.contains_key(&page_id) {
+            self.hits += 1;
+        } else {
+            // 页面不在缓存中，需要从磁盘加载
+            self.misses += 1;
+            self.load_page(page_id)?;
         }
+        self.touch(page_id);
         Ok(())
     }
 
     /// 从磁盘加载页面到缓冲池
     fn load_page(&mut self, page_id: PageId) -> Result<()> {
-        // 如果缓冲池已满，需要置换页面
-        if self.pages.len() >= BUFFER_POOL_SIZE {
-            self.evict_page()?;
-        }
-
         // 从磁盘读取页面数据
         let data = self.disk_manager.read_page(page_id)?;
-
-        // 创建页面并加入缓冲池
         let page = Page::from_data(page_id, &data)?;
+
+        // 如果缓冲池已满，需要置换页面
+        self.make_room()?;
         self.pages.insert(page_id, page);
 
         Ok(())
     }
 
-    /// 置换页面（使用LRU策略）
-    fn evict_page(&mut self) -> Result<()> {
-        // 寻找可以置换的页面（最久未使用且未被钉住的页面）
-        let mut page_to_evict = None;
-
-        for page_id in &self.lru_list {
-            if !self.pinned_pages.contains(page_id) {
-                page_to_evict = Some(*page_id);
+    /// 缓冲池已满时置换页面，为新页面腾出位置
+    ///
+    /// 事务结束后，事务期间超出容量的部分在这里逐步置换出去。
+    fn make_room(&mut self) -> Result<()> {
+        while self.pages.len() >= self.capacity {
+            if !self.evict_page()? {
                 break;
             }
         }
+        Ok(())
+    }
+
+    /// 置换页面（使用LRU策略），返回是否置换出了页面
+    ///
+    /// 其余页面都被事务扣留时不置换，缓冲池暂时超出容量。
+    fn evict_page(&mut self) -> Result<bool> {
+        // 寻找可以置换的页面（最久未使用、未被钉住且没有被事务修改过的页面）
+        let page_to_evict = self.lru.values().copied().find(|&page_id| {
+            !self.pin_counts.contains_key(&page_id) && !self.held_by_transaction(page_id)
+        });
 
         // 如果找到可置换页面，先将其刷新到磁盘，然后从缓存移除
         if let Some(page_id) = page_to_evict {
             self.flush_page(page_id)?;
             self.pages.remove(&page_id);
-            self.lru_list.retain(|&id| id != page_id);
-            Ok(())
+            if let Some(time) = self.last_used.remove(&page_id) {
+                self.lru.remove(&time);
+            }
+            Ok(true)
+        } else if self
+            .pages
+            .keys()
+            .any(|&page_id| self.held_by_transaction(page_id))
+        {
+            // 未提交的修改不能写回磁盘，缓冲池暂时超出容量
+            Ok(false)
         } else {
             // 所有页面都被钉住，无法置换
             Err(DBError::IO(
@@ -156,18 +412,176 @@ impl BufferManager {
         }
     }
 
-    /// 更新LRU列表
-    fn update_lru(&mut self, page_id: PageId) {
-        self.lru_list.retain(|&id| id != page_id);
-        self.lru_list.push(page_id);
+    /// 把页面记为最近使用
+    fn touch(&mut self, page_id: PageId) {
+        self.clock += 1;
+        if let Some(time) = self.last_used.insert(page_id, self.clock) {
+            self.lru.remove(&time);
+        }
+        self.lru.insert(self.clock, page_id);
     }
 }
 
 impl Drop for BufferManager {
     fn drop(&mut self) {
-        // 确保所有脏页面都写回磁盘
+        // 确保所有脏页面都写回磁盘，未结束的事务修改过的页面除外
         if let Err(e) = self.flush_all_pages() {
-            eprintln!("关闭缓冲管理器时刷新页面失败: {}", e);
+            log::error!("关闭缓冲管理器时刷新页面失败: {}", e);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::table::Value;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lru_eviction_keeps_memory_bounded() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.db");
+        assert!(BufferManager::new(&path, 0).is_err());
+
+        let mut buffer_manager = BufferManager::new(&path, 4).unwrap();
+        let mut records = Vec::new();
+        for i in 0..20 {
+            let page_id = buffer_manager.create_page().unwrap();
+            let page = buffer_manager.get_page_mut(page_id).unwrap();
+            let id = page.insert_record(vec![Value::Int(i)]).unwrap();
+            records.push((id, i));
+            assert!(buffer_manager.cached_pages() <= 4);
+        }
+
+        // 被置换出去的脏页已经写回磁盘，重新读取时数据不变；
+        // 按顺序扫描比容量多的页面时每一页都不命中
+        let misses = buffer_manager.misses();
+        for &(id, i) in &records {
+            let page = buffer_manager.get_page(id.page_id).unwrap();
+            assert_eq!(page.get_record(id).unwrap().values(), &[Value::Int(i)]);
+        }
+        assert_eq!(buffer_manager.cached_pages(), 4);
+        assert_eq!(buffer_manager.misses() - misses, 20);
+
+        // 最近使用的页面命中缓存
+        let hits = buffer_manager.hits();
+        buffer_manager.get_page(records[19].0.page_id).unwrap();
+        assert_eq!(buffer_manager.hits(), hits + 1);
+
+        // 钉住的页面不会被置换，钉住几次就要取消几次
+        let pinned = records[0].0.page_id;
+        buffer_manager.pin_page(pinned).unwrap();
+        buffer_manager.pin_page(pinned).unwrap();
+        for &(id, _) in &records[1..] {
+            buffer_manager.get_page(id.page_id).unwrap();
+        }
+        buffer_manager.unpin_page(pinned);
+        let misses = buffer_manager.misses();
+        buffer_manager.get_page(pinned).unwrap();
+        assert_eq!(buffer_manager.misses(), misses);
+
+        buffer_manager.unpin_page(pinned);
+        for &(id, _) in &records[1..5] {
+            buffer_manager.get_page(id.page_id).unwrap();
+        }
+        buffer_manager.get_page(pinned).unwrap();
+        assert_eq!(buffer_manager.misses(), misses + 5);
+    }
+
+    #[test]
+    fn test_read_pages_coalesces_adjacent_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.db");
+        {
+            let mut buffer_manager = BufferManager::new(&path, 16).unwrap();
+            for i in 0..10 {
+                let page_id = buffer_manager.create_page().unwrap();
+                let page = buffer_manager.get_page_mut(page_id).unwrap();
+                page.insert_record(vec![Value::Int(i)]).unwrap();
+            }
+        }
+
+        // 页面 2..=5 和 8..=9 各合并为一次读取，返回顺序与请求顺序一致
+        let mut buffer_manager = BufferManager::new(&path, 16).unwrap();
+        let requested = [5, 2, 3, 9, 4, 8];
+        let pages = buffer_manager.read_pages(&requested).unwrap();
+        let ids: Vec<PageId> = pages.iter().map(|page| page.id()).collect();
+        assert_eq!(ids, requested);
+        let values: Vec<Value> = pages
+            .iter()
+            .flat_map(|page| page.iter_records())
+            .map(|(_, record)| record.values()[0].clone())
+            .collect();
+        assert_eq!(values, [5, 2, 3, 9, 4, 8].map(Value::Int));
+        assert_eq!(buffer_manager.file_reads(), 2);
+        assert_eq!(buffer_manager.misses(), 6);
+
+        // 已在缓存中的页面不再读取磁盘
+        buffer_manager.prefetch(&[1, 2, 3]).unwrap();
+        assert_eq!(buffer_manager.file_reads(), 3);
+        buffer_manager.get_page(2).unwrap();
+        assert_eq!(buffer_manager.file_reads(), 3);
+
+        assert!(buffer_manager.read_pages(&[0; 17]).is_err());
+        assert!(matches!(
+            buffer_manager.prefetch(&[10]),
+            Err(DBError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_transaction_pages_are_not_evicted() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.db");
+        let mut buffer_manager = BufferManager::new(&path, 2).unwrap();
+        let committed = buffer_manager.create_page().unwrap();
+        buffer_manager
+            .get_page_mut(committed)
+            .unwrap()
+            .insert_record(vec![Value::Int(0)])
+            .unwrap();
+        buffer_manager.flush_all_pages().unwrap();
+
+        // 事务修改过的页面超出容量时也留在缓冲池中，刷新时跳过
+        buffer_manager.begin_transaction();
+        buffer_manager
+            .get_page_mut(committed)
+            .unwrap()
+            .insert_record(vec![Value::Int(1)])
+            .unwrap();
+        for i in 0..4 {
+            let page_id = buffer_manager.create_page().unwrap();
+            buffer_manager
+                .get_page_mut(page_id)
+                .unwrap()
+                .insert_record(vec![Value::Int(i)])
+                .unwrap();
+        }
+        assert_eq!(buffer_manager.cached_pages(), 5);
+        let written = buffer_manager.pages_written();
+        buffer_manager.flush_all_pages().unwrap();
+        assert_eq!(buffer_manager.pages_written(), written);
+        assert!(buffer_manager.has_dirty_pages());
+
+        // 事务结束后页面重新可以置换，缓冲池逐步回到容量以内
+        buffer_manager.end_transaction();
+        buffer_manager.create_page().unwrap();
+        assert_eq!(buffer_manager.cached_pages(), 2);
+        assert!(buffer_manager.pages_written() > written);
+    }
+
+    #[test]
+    fn test_all_pages_pinned() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut buffer_manager = BufferManager::new(temp_dir.path().join("data.db"), 2).unwrap();
+        let pages: Vec<PageId> = (0..3)
+            .map(|_| buffer_manager.create_page().unwrap())
+            .collect();
+        buffer_manager.pin_page(pages[1]).unwrap();
+        buffer_manager.pin_page(pages[2]).unwrap();
+        assert!(buffer_manager.get_page(pages[0]).is_err());
+
+        buffer_manager.unpin_page(pages[1]);
+        assert!(buffer_manager.get_page(pages[0]).is_ok());
+    }
+}
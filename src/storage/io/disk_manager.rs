@@ -1,4 +1,5 @@
 use super::page::{PAGE_SIZE, PageId};
+use super::wal::{self, WriteAheadLog};
 use crate::error::{DBError, Result};
 use std::fs::{File, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
@@ -10,19 +11,42 @@ pub struct DiskManager {
     file: File,
     /// 下一个可分配的页面ID
     next_page_id: PageId,
+    /// 打开以来写入磁盘的页面次数
+    pages_written: u64,
+    /// 打开以来读取数据文件的次数，一次读取可以包含多个相邻的页面
+    file_reads: u64,
+    /// 与数据文件同名、扩展名为 `wal` 的预写日志
+    wal: WriteAheadLog,
 }
 
 impl DiskManager {
-    /// 创建或打开数据库文件
+    /// 创建或打开数据库文件，预写日志中有上次未写完的页面时先重做这些写入
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let wal_path = path.with_extension("wal");
+
         // 打开或创建数据库文件
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
             .read(true)
             .write(true)
-            .create(true).truncate(true)
+            .create(true)
+            // 打开已有的数据文件时保留其中的页面
+            .truncate(false)
             .open(path)
             .map_err(|e| DBError::IO(format!("无法打开数据库文件: {}", e)))?;
 
+        // 重做日志中已提交批次的页面写入，末尾缺少提交记录的批次整批忽略
+        let entries = wal::read_entries(&wal_path)?;
+        for (page_id, data) in &entries {
+            Self::write_to_file(&mut file, *page_id, data)?;
+        }
+        if !entries.is_empty() {
+            file.sync_data()
+                .map_err(|e| DBError::IO(format!("无法将恢复的页面写入磁盘: {}", e)))?;
+        }
+        let mut wal = WriteAheadLog::open(&wal_path)?;
+        wal.clear()?;
+
         // 计算当前文件大小以确定下一个可分配的页面ID
         let file_size = file
             .metadata()
@@ -31,7 +55,28 @@ impl DiskManager {
 
         let next_page_id = (file_size / PAGE_SIZE as u64) as PageId;
 
-        Ok(Self { file, next_page_id })
+        Ok(Self {
+            file,
+            next_page_id,
+            pages_written: 0,
+            file_reads: 0,
+            wal,
+        })
+    }
+
+    /// 打开以来写入磁盘的页面次数
+    pub fn pages_written(&self) -> u64 {
+        self.pages_written
+    }
+
+    /// 打开以来读取数据文件的次数
+    pub fn file_reads(&self) -> u64 {
+        self.file_reads
+    }
+
+    /// 数据文件中的页面数
+    pub fn page_count(&self) -> PageId {
+        self.next_page_id
     }
 
     /// 读取页面
@@ -64,26 +109,82 @@ impl DiskManager {
                 DBError::IO(format!("无法读取页面 {}: {}", page_id, e))
             }
         })?;
+        self.file_reads += 1;
+        log::trace!("读取页面 {}", page_id);
 
         Ok(buffer)
     }
 
-    /// 写入页面
-    pub fn write_page(&mut self, page_id: PageId, data: &[u8]) -> Result<()> {
-        if data.len() > PAGE_SIZE {
+    /// 读取一批页面，返回按页ID排序、去重后的 (页ID, 数据)
+    ///
+    /// 页ID先排序，数据文件中相邻的页面合并为一次读取。
+    pub fn read_pages(&mut self, page_ids: &[PageId]) -> Result<Vec<(PageId, Vec<u8>)>> {
+        let mut page_ids = page_ids.to_vec();
+        page_ids.sort_unstable();
+        page_ids.dedup();
+
+        let mut pages = Vec::with_capacity(page_ids.len());
+        for run in page_ids.chunk_by(|a, b| a + 1 == *b) {
+            let first = run[0];
+            if let Some(&missing) = run.iter().find(|&&page_id| page_id >= self.next_page_id) {
+                return Err(DBError::NotFound(format!("页面 {} 不存在", missing)));
+            }
+            self.file
+                .seek(SeekFrom::Start(self.page_offset(first)))
+                .map_err(|e| DBError::IO(format!("无法定位到页面 {}: {}", first, e)))?;
+            let mut buffer = vec![0; run.len() * PAGE_SIZE];
+            self.file.read_exact(&mut buffer).map_err(|e| {
+                DBError::IO(format!(
+                    "无法读取页面 {}..={}: {}",
+                    first,
+                    run[run.len() - 1],
+                    e
+                ))
+            })?;
+            self.file_reads += 1;
+            log::trace!("读取页面 {}..={}", first, run[run.len() - 1]);
+            pages.extend(
+                run.iter()
+                    .zip(buffer.chunks_exact(PAGE_SIZE))
+                    .map(|(&page_id, data)| (page_id, data.to_vec())),
+            );
+        }
+        Ok(pages)
+    }
+
+    /// 写入一批页面
+    ///
+    /// 先把新内容追加到预写日志并落盘，再覆盖数据文件中的页面，数据文件落盘后清空日志。
+    /// 中途崩溃时数据文件中可能新旧页面混杂，下次打开时由日志补全。
+    pub fn write_pages(&mut self, pages: &[(PageId, Vec<u8>)]) -> Result<()> {
+        if pages.is_empty() {
+            return Ok(());
+        }
+        if let Some((page_id, data)) = pages.iter().find(|(_, data)| data.len() > PAGE_SIZE) {
             return Err(DBError::IO(format!(
-                "页面数据过大: {} > {}",
+                "页面 {} 数据过大: {} > {}",
+                page_id,
                 data.len(),
                 PAGE_SIZE
             )));
         }
 
-        // 计算页面在文件中的偏移量
-        let offset = self.page_offset(page_id);
+        log::trace!("写入 {} 个页面", pages.len());
+        self.wal.append(pages)?;
+        for (page_id, data) in pages {
+            Self::write_to_file(&mut self.file, *page_id, data)?;
+            self.pages_written += 1;
+        }
+        self.file
+            .sync_data()
+            .map_err(|e| DBError::IO(format!("无法将页面写入磁盘: {}", e)))?;
+        self.wal.clear()
+    }
 
+    /// 把页面数据写到数据文件中的对应位置，不足一页的部分补零
+    fn write_to_file(file: &mut File, page_id: PageId, data: &[u8]) -> Result<()> {
         // 定位到页面位置
-        self.file
-            .seek(SeekFrom::Start(offset))
+        file.seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))
             .map_err(|e| DBError::IO(format!("无法定位到页面 {}: {}", page_id, e)))?;
 
         // 若数据小于页面大小，则创建完整大小的缓冲区
@@ -91,14 +192,10 @@ impl DiskManager {
         buffer[..data.len()].copy_from_slice(data);
 
         // 写入页面数据
-        self.file
-            .write_all(&buffer)
+        file.write_all(&buffer)
             .map_err(|e| DBError::IO(format!("无法写入页面 {}: {}", page_id, e)))?;
-        self.file
-            .flush()
-            .map_err(|e| DBError::IO(format!("无法刷新页面 {}: {}", page_id, e)))?;
-
-        Ok(())
+        file.flush()
+            .map_err(|e| DBError::IO(format!("无法刷新页面 {}: {}", page_id, e)))
     }
 
     /// 分配新页面
@@ -106,9 +203,10 @@ impl DiskManager {
         let page_id = self.next_page_id;
         self.next_page_id += 1;
 
-        // 写入空页面以扩展文件
-        let empty_page = vec![0; PAGE_SIZE];
-        self.write_page(page_id, &empty_page)?;
+        // 写入空页面以扩展文件；新页面还没有被任何表引用，不需要经过预写日志
+        Self::write_to_file(&mut self.file, page_id, &[])?;
+        self.pages_written += 1;
+        log::trace!("分配页面 {}", page_id);
 
         Ok(page_id)
     }
@@ -6,9 +6,38 @@ use crate::{
 /// 页面大小（增加到32KB以提供更多缓冲空间）
 pub const PAGE_SIZE: usize = 32768;
 
+/// 单条记录在页面中的额外开销估计（Option<T> 和 Vec 的开销）
+const RECORD_OVERHEAD: usize = 64;
+
+/// 页面保留的安全边距
+const SAFETY_MARGIN: usize = 2048;
+
+/// 空页面能容纳的单条记录的最大序列化大小（1 字节为空记录数组的长度前缀）
+pub const MAX_RECORD_SIZE: usize = PAGE_SIZE - SAFETY_MARGIN - RECORD_OVERHEAD - 1;
+
+/// 整理（VACUUM）后单个页面允许的序列化大小，与插入时一样预留安全边距，
+/// 给之后的原地更新留出空间
+pub const PACKED_PAGE_CAPACITY: usize = PAGE_SIZE - SAFETY_MARGIN;
+
+/// 原地替换记录后页面允许的序列化大小（保留 1KB 缓冲以避免边界情况）
+const REPLACE_SIZE_LIMIT: usize = PAGE_SIZE - 1024;
+
+/// 页面记录数组长度前缀的上限（变长整数，32KB 页面内的记录数最多占 3 字节）
+pub const PAGE_HEADER_SIZE: usize = 3;
+
 /// 页ID类型
 pub type PageId = u32;
 
+/// bincode 标准配置下长度前缀（变长整数）占用的字节数
+fn varint_size(n: usize) -> usize {
+    match n {
+        0..=250 => 1,
+        251..=0xFFFF => 3,
+        0x1_0000..=0xFFFF_FFFF => 5,
+        _ => 9,
+    }
+}
+
 /// 重新导入 Value 类型
 use crate::storage::table::Value;
 type RawRecord = Vec<Value>;
@@ -72,11 +101,9 @@ impl Page {
     /// 获取当前页面序列化后的大小（使用缓存优化）
     pub fn get_serialized_size(&mut self) -> Result<usize> {
         if let Some(size) = self.cached_size {
-            if !self.is_dirty {
-                return Ok(size);
-            }
+            return Ok(size);
         }
-        
+
         let serialized = self.serialize()?;
         let size = serialized.len();
         self.cached_size = Some(size);
@@ -121,16 +148,23 @@ impl Page {
             return Err(DBError::IO("页面空间不足，需要新页面".to_string()));
         }
 
+        let record_size = Self::packed_record_size(&raw_record);
         let slot = if let Some(slot) = self.records.iter().position(|r| r.is_none()) {
+            // 空槽位原来占 1 字节（None 标签）
+            self.cached_size = self.cached_size.map(|size| size - 1 + record_size);
             slot
         } else {
+            let old_prefix = varint_size(self.records.len());
             self.records.push(None);
+            let new_prefix = varint_size(self.records.len());
+            self.cached_size = self
+                .cached_size
+                .map(|size| size - old_prefix + new_prefix + record_size);
             self.records.len() - 1
         };
 
         self.records[slot] = Some(raw_record);
         self.is_dirty = true;
-        self.clear_cache(); // 清除缓存
 
         // 直接返回 RecordId
         Ok(RecordId::new(self.id, slot))
@@ -193,8 +227,18 @@ impl Page {
         self.records.iter().filter(|r| r.is_some()).count()
     }
 
-    /// 替换记录 - 使用 RecordId（带容量检查）
-    pub fn replace_record(&mut self, id: RecordId, new_raw_record: RawRecord) -> Result<()> {
+    /// 获取已删除记录留下的空槽位数量
+    pub fn get_deleted_slot_count(&self) -> usize {
+        self.records.iter().filter(|r| r.is_none()).count()
+    }
+
+    /// 替换后的页面大小是否仍在安全限制内，放不下时调用方应把记录搬到其它页面
+    pub fn can_replace_record(&self, id: RecordId, new_raw_record: &RawRecord) -> Result<bool> {
+        Ok(self.replaced_size(id, new_raw_record)? <= REPLACE_SIZE_LIMIT)
+    }
+
+    /// 替换指定记录后页面序列化的大小
+    fn replaced_size(&self, id: RecordId, new_raw_record: &RawRecord) -> Result<usize> {
         if id.page_id != self.id {
             return Err(DBError::IO("RecordId 的页面ID不匹配".to_string()));
         }
@@ -204,27 +248,31 @@ impl Page {
             return Err(DBError::NotFound(format!("记录槽位 {} 不存在", slot)));
         }
 
-        // 容量检查：计算替换后的页面大小
         let mut test_records = self.records.clone();
         test_records[slot] = Some(new_raw_record.clone());
 
-        let new_size = bincode::encode_to_vec(&test_records, bincode::config::standard())
-            .map_err(|e| DBError::IO(format!("估算页面大小失败: {}", e)))?
-            .len();
+        let encoded = bincode::encode_to_vec(&test_records, bincode::config::standard())
+            .map_err(|e| DBError::IO(format!("估算页面大小失败: {}", e)))?;
+        Ok(encoded.len())
+    }
 
-        // 增加一些缓冲空间以避免边界情况
-        let max_allowed_size = PAGE_SIZE - 1024; // 保留1KB的缓冲空间
-        
-        if new_size > max_allowed_size {
+    /// 替换记录 - 使用 RecordId（带容量检查）
+    ///
+    /// 替换后超出安全限制时返回错误，页面不变；见 [`Page::can_replace_record`]。
+    pub fn replace_record(&mut self, id: RecordId, new_raw_record: RawRecord) -> Result<()> {
+        let new_size = self.replaced_size(id, &new_raw_record)?;
+        if new_size > REPLACE_SIZE_LIMIT {
             return Err(DBError::IO(format!(
                 "替换记录后页面大小({} bytes)将超出安全限制({} bytes)，需要重新分配到新页面",
-                new_size, max_allowed_size
+                new_size, REPLACE_SIZE_LIMIT
             )));
         }
+        let slot = id.slot;
 
         // 执行替换
         self.records[slot] = Some(new_raw_record);
         self.is_dirty = true;
+        self.clear_cache();
         Ok(())
     }
 
@@ -276,6 +324,7 @@ impl Page {
             .ok_or_else(|| DBError::NotFound(format!("记录槽位 {} 已被删除", slot)))?;
         record[field_index] = new_value;
         self.is_dirty = true;
+        self.clear_cache();
         Ok(())
     }
 
@@ -327,6 +376,7 @@ impl Page {
         }
 
         self.is_dirty = true;
+        self.clear_cache();
         Ok(())
     }
 
@@ -408,6 +458,38 @@ impl Page {
             })
     }
 
+    /// 与 [`iter_records`](Self::iter_records) 相同，但每条记录只复制 `positions` 处的值
+    ///
+    /// 记录的列数少于 `positions` 要求时（数据文件与表结构不一致）返回错误。
+    pub fn iter_projected<'a>(
+        &'a self,
+        positions: &'a [usize],
+    ) -> impl Iterator<Item = Result<Record>> + 'a {
+        self.records
+            .iter()
+            .enumerate()
+            .filter_map(move |(slot, opt_record)| {
+                opt_record.as_ref().map(|raw_record| {
+                    let values = positions
+                        .iter()
+                        .map(|&index| {
+                            raw_record.get(index).cloned().ok_or_else(|| {
+                                DBError::Execution(format!(
+                                    "页 {} 槽位 {} 的记录只有 {} 列，取不到第 {} 列：\
+                                     数据文件与表结构不一致，可用 .verify 检查",
+                                    self.id,
+                                    slot,
+                                    raw_record.len(),
+                                    index + 1
+                                ))
+                            })
+                        })
+                        .collect::<Result<_>>()?;
+                    Ok(Record::with_id(RecordId::new(self.id, slot), values))
+                })
+            })
+    }
+
     /// 获取所有有效记录的ID
     pub fn get_all_record_ids(&self) -> Vec<RecordId> {
         self.records
@@ -430,28 +512,28 @@ impl Page {
             .unwrap_or(0)
     }
 
+    /// 记录存入页面后实际占用的字节数：`Option` 标签 1 字节加记录本身的编码
+    pub fn packed_record_size(record: &RawRecord) -> usize {
+        1 + Self::estimate_record_size(record)
+    }
+
+    /// 用给定记录整体替换页面内容，记录依次占用槽位 0, 1, 2...
+    ///
+    /// 调用方负责保证记录总大小不超过 `PACKED_PAGE_CAPACITY`。
+    pub fn reset_records(&mut self, records: Vec<RawRecord>) {
+        self.records = records.into_iter().map(Some).collect();
+        self.is_dirty = true;
+        self.clear_cache();
+    }
+
     /// 更精确且高效的容量检查
-    pub fn can_fit_record(&self, record: &RawRecord) -> Result<bool> {
-        // 快速估算，避免完整序列化
+    ///
+    /// 页面当前大小取自缓存，只有缓存失效后第一次检查才完整序列化一次；
+    /// 插入记录时缓存按记录大小增量更新。
+    pub fn can_fit_record(&mut self, record: &RawRecord) -> Result<bool> {
         let record_size = Self::estimate_record_size(record);
-        let estimated_overhead = 64; // Option<T> 和 Vec 的开销
-        let safety_margin = 2048; // 2KB安全边距
-        
-        // 使用当前记录数来估算页面使用情况
-        let active_records = self.records.iter().filter(|r| r.is_some()).count();
-        let estimated_current_size = active_records * 100 + 1024; // 粗略估算
-        
-        let estimated_new_size = estimated_current_size + record_size + estimated_overhead;
-        
-        // 如果快速检查失败，进行精确检查
-        if estimated_new_size > PAGE_SIZE - safety_margin {
-            // 只有在必要时才进行精确的序列化检查
-            let current_size = self.serialize()?.len();
-            let new_size = current_size + record_size + estimated_overhead;
-            Ok(new_size <= PAGE_SIZE - safety_margin)
-        } else {
-            Ok(true)
-        }
+        let current_size = self.get_serialized_size()?;
+        Ok(current_size + record_size + RECORD_OVERHEAD <= PAGE_SIZE - SAFETY_MARGIN)
     }
 
     // // 保留一些内部使用的 slot 方法（私有或仅供内部使用）
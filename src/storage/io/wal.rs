@@ -0,0 +1,203 @@
+use super::page::{PAGE_SIZE, PageId};
+use crate::error::{DBError, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 日志项头部：页ID(4) + 长度(4) + 校验和(8)
+const ENTRY_HEADER_SIZE: usize = 16;
+
+/// 提交记录的页ID位置上写的标记，不会是真实的页ID
+const COMMIT_MARKER: PageId = PageId::MAX;
+
+/// 预写日志（WAL）：覆盖数据文件中的页面之前，先把新的页面内容追加到日志并落盘
+///
+/// 每一项是 `页ID | 长度 | 校验和 | 页面内容`，整数均为小端。一批页面之后跟一条提交记录，
+/// 页ID位置为 [`COMMIT_MARKER`]、长度位置为这批的页面数，没有内容。页面全部写入数据文件并落盘后
+/// 清空日志；进程在两者之间被杀掉时，下次打开数据库会用日志中有提交记录的批次重做页面写入。
+pub struct WriteAheadLog {
+    /// 日志文件
+    file: File,
+    /// 日志文件路径
+    path: PathBuf,
+}
+
+impl WriteAheadLog {
+    /// 打开或创建日志文件，已有的内容保持不变
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| DBError::IO(format!("无法打开预写日志: {}", e)))?;
+        Ok(Self { file, path })
+    }
+
+    /// 把一批页面内容和这批的提交记录追加到日志并落盘
+    pub fn append(&mut self, pages: &[(PageId, Vec<u8>)]) -> Result<()> {
+        let mut buffer = Vec::new();
+        for (page_id, data) in pages {
+            encode_entry(&mut buffer, *page_id, data);
+        }
+        encode_commit(&mut buffer, pages.len());
+        self.file
+            .write_all(&buffer)
+            .map_err(|e| DBError::IO(format!("无法写入预写日志: {}", e)))?;
+        self.file
+            .sync_data()
+            .map_err(|e| DBError::IO(format!("无法将预写日志写入磁盘: {}", e)))
+    }
+
+    /// 清空日志，在日志中的页面全部写入数据文件并落盘后调用
+    pub fn clear(&mut self) -> Result<()> {
+        self.file
+            .set_len(0)
+            .and_then(|()| self.file.sync_data())
+            .map_err(|e| DBError::IO(format!("无法清空预写日志 {}: {}", self.path.display(), e)))
+    }
+}
+
+/// 读取日志中所有已提交批次的页面，遇到长度不足或校验和不符的项就停止
+///
+/// 追加一批页面的中途崩溃时，这批的前几项可能完整而提交记录缺失；这样的批次整批忽略，
+/// 数据文件不会只重做其中一部分页面。日志文件不存在时返回空列表。
+pub fn read_entries<P: AsRef<Path>>(path: P) -> Result<Vec<(PageId, Vec<u8>)>> {
+    let mut data = Vec::new();
+    match File::open(path) {
+        Ok(mut file) => file
+            .read_to_end(&mut data)
+            .map_err(|e| DBError::IO(format!("无法读取预写日志: {}", e)))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(DBError::IO(format!("无法打开预写日志: {}", e))),
+    };
+
+    let mut entries = Vec::new();
+    let mut batch = Vec::new();
+    let mut rest = data.as_slice();
+    while let Some((header, body)) = rest.split_first_chunk::<ENTRY_HEADER_SIZE>() {
+        let page_id = PageId::from_le_bytes(header[0..4].try_into().unwrap());
+        let len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        let checksum = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        if page_id == COMMIT_MARKER {
+            if len != batch.len() || commit_checksum(len) != checksum {
+                break;
+            }
+            entries.append(&mut batch);
+            rest = body;
+            continue;
+        }
+        if len > PAGE_SIZE || body.len() < len {
+            break;
+        }
+        let (page, next) = body.split_at(len);
+        if entry_checksum(page_id, page) != checksum {
+            break;
+        }
+        batch.push((page_id, page.to_vec()));
+        rest = next;
+    }
+    Ok(entries)
+}
+
+fn encode_entry(buffer: &mut Vec<u8>, page_id: PageId, data: &[u8]) {
+    buffer.extend_from_slice(&page_id.to_le_bytes());
+    buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&entry_checksum(page_id, data).to_le_bytes());
+    buffer.extend_from_slice(data);
+}
+
+fn encode_commit(buffer: &mut Vec<u8>, pages: usize) {
+    buffer.extend_from_slice(&COMMIT_MARKER.to_le_bytes());
+    buffer.extend_from_slice(&(pages as u32).to_le_bytes());
+    buffer.extend_from_slice(&commit_checksum(pages).to_le_bytes());
+}
+
+/// 提交记录的校验和，覆盖标记和页面数
+fn commit_checksum(pages: usize) -> u64 {
+    entry_checksum(COMMIT_MARKER, &(pages as u32).to_le_bytes())
+}
+
+/// 页ID和页面内容的 FNV-1a 校验和
+fn entry_checksum(page_id: PageId, data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    page_id
+        .to_le_bytes()
+        .iter()
+        .chain(data)
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(PRIME)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_torn_entries_are_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.wal");
+        assert!(read_entries(&path).unwrap().is_empty());
+
+        let pages = vec![(3, vec![1u8; 100]), (7, vec![2u8; PAGE_SIZE])];
+        let mut wal = WriteAheadLog::open(&path).unwrap();
+        wal.append(&pages).unwrap();
+        assert_eq!(read_entries(&path).unwrap(), pages);
+
+        // 最后一项只写了一半
+        let mut torn = Vec::new();
+        encode_entry(&mut torn, 9, &[3u8; 200]);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&torn[..torn.len() - 1]).unwrap();
+        assert_eq!(read_entries(&path).unwrap(), pages);
+
+        // 内容与校验和不符的项所在的整批及其后的项都被忽略
+        let mut data = fs::read(&path).unwrap();
+        data.truncate(data.len() - (torn.len() - 1));
+        let last_page_byte = data.len() - ENTRY_HEADER_SIZE - 1;
+        data[last_page_byte] ^= 0xff;
+        data.extend_from_slice(&torn);
+        fs::write(&path, &data).unwrap();
+        assert!(read_entries(&path).unwrap().is_empty());
+
+        wal.clear().unwrap();
+        assert!(read_entries(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_batch_without_commit_record_is_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.wal");
+        let first = vec![(1, vec![1u8; 100])];
+        let second = vec![(2, vec![2u8; 100]), (3, vec![3u8; 100])];
+        let mut wal = WriteAheadLog::open(&path).unwrap();
+        wal.append(&first).unwrap();
+        let first_len = fs::metadata(&path).unwrap().len() as usize;
+        wal.append(&second).unwrap();
+        let all: Vec<_> = first.iter().chain(&second).cloned().collect();
+        assert_eq!(read_entries(&path).unwrap(), all);
+
+        // 第二批在各个位置截断：只写完第一项、缺提交记录、提交记录写了一半，整批都不重做
+        let data = fs::read(&path).unwrap();
+        let entry_len = ENTRY_HEADER_SIZE + 100;
+        for len in [
+            first_len + entry_len,
+            first_len + 2 * entry_len,
+            data.len() - 1,
+        ] {
+            fs::write(&path, &data[..len]).unwrap();
+            assert_eq!(read_entries(&path).unwrap(), first, "截断到 {} 字节", len);
+        }
+
+        // 提交记录中的页面数与这批实际的项数不符时同样忽略
+        let mut forged = data[..first_len + entry_len].to_vec();
+        encode_commit(&mut forged, 2);
+        fs::write(&path, &forged).unwrap();
+        assert_eq!(read_entries(&path).unwrap(), first);
+    }
+}
@@ -1,13 +1,197 @@
 use super::io::buffer_manager::BufferManager;
-use super::io::page::PageId;
+use super::io::page::{
+    MAX_RECORD_SIZE, PACKED_PAGE_CAPACITY, PAGE_HEADER_SIZE, PAGE_SIZE, Page, PageId,
+};
 use crate::error::{DBError, Result};
+use crate::session::SqlMode;
+use record::RawRecord;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Bound;
 
+pub mod check;
+pub mod index;
 pub mod record;
 pub mod value;
 
 // 重新导出 record 模块的公共类型
+pub use check::CheckConstraint;
+pub use index::IndexDef;
 pub use record::{Record, RecordId};
-pub use value::{ColumnDef, DataType, Value};
+pub use value::{
+    ColumnDef, DataType, ForeignKey, Value, ValueKey, column_position, truncate_varchar,
+    varchar_len,
+};
+
+/// 一次表整理的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionSummary {
+    /// 存活的记录数
+    pub records: usize,
+    /// 整理前表占用的页数
+    pub pages_before: usize,
+    /// 按原顺序依次紧密排列需要的页数
+    pub naive_pages: usize,
+    /// 整理后实际占用的页数
+    pub pages_after: usize,
+}
+
+/// 一张表的存储统计，见 [`Table::stats`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableStats {
+    /// 存活的记录数
+    pub row_count: usize,
+    /// 表占用的数据页数
+    pub page_count: usize,
+    /// 所有数据页序列化后的总字节数
+    pub total_bytes: usize,
+    /// 数据页的平均使用率（序列化大小 / 页面大小），没有数据页时为 0
+    pub avg_page_utilization: f64,
+    /// 删除记录后留下的空槽位数，整理（VACUUM）后清零
+    pub deleted_slots: usize,
+}
+
+/// 主键索引的统计信息，见 [`Table::primary_key_stats`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrimaryKeyStats {
+    /// 索引中的键数；主键唯一且不为 NULL，所以也是表的行数和不同值的数量
+    pub key_count: usize,
+    /// 最小的主键，空表时为 None
+    pub min: Option<Value>,
+    /// 最大的主键，空表时为 None
+    pub max: Option<Value>,
+}
+
+/// 表中记录与表结构的一致性检查结果，见 [`Table::verify`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableVerification {
+    /// 检查过的数据页数
+    pub pages_checked: usize,
+    /// 检查过的记录数
+    pub records_checked: usize,
+    /// 无法读取的数据页数
+    pub unreadable_pages: usize,
+    /// 列数与表结构不符的记录数
+    pub arity_mismatches: usize,
+    /// 有值的类型与列类型不符的记录数
+    pub type_mismatches: usize,
+    /// 发现的第一处问题，如 `页 3 槽位 0: 有 2 列，表结构有 3 列`
+    pub first_problem: Option<String>,
+}
+
+impl TableVerification {
+    /// 没有发现任何问题
+    pub fn is_consistent(&self) -> bool {
+        self.unreadable_pages == 0 && self.arity_mismatches == 0 && self.type_mismatches == 0
+    }
+
+    fn note(&mut self, problem: impl FnOnce() -> String) {
+        if self.first_problem.is_none() {
+            self.first_problem = Some(problem());
+        }
+    }
+}
+
+/// ADD COLUMN 中新列的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnPosition {
+    /// 追加到最后（默认）
+    Last,
+    /// FIRST
+    First,
+    /// AFTER <列名>
+    After(String),
+}
+
+/// ALTER TABLE 中对列的一个操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlterColumn {
+    Add {
+        column: ColumnDef,
+        position: ColumnPosition,
+    },
+    Drop {
+        name: String,
+    },
+}
+
+/// 依次应用 ALTER TABLE 操作之后的表结构
+#[derive(Debug, Clone)]
+pub struct AlteredSchema {
+    pub columns: Vec<ColumnDef>,
+    pub indexes: Vec<IndexDef>,
+    /// 新的每一列在原记录中的位置，新增的列为 None
+    sources: Vec<Option<usize>>,
+}
+
+/// 写入时被截断到 VARCHAR 长度的一个值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truncation {
+    pub column: String,
+    /// 在本批行中从 1 开始的行号
+    pub row: usize,
+}
+
+/// 顺序扫描时每次预读的页面数
+pub const SCAN_PREFETCH_PAGES: usize = 64;
+
+/// 表的顺序扫描，见 [`Table::scan`]
+///
+/// 读取页面出错时返回一次错误，之后结束。
+pub struct TableScan<'a> {
+    buffer_manager: &'a mut BufferManager,
+    page_ids: &'a [PageId],
+    positions: Option<&'a [usize]>,
+    /// 每次预读的页面数
+    batch: usize,
+    /// 下一个要读取的页面在 `page_ids` 中的位置
+    next_page: usize,
+    /// 已预读到的位置（不含）
+    prefetched: usize,
+    /// 当前页面中还没有返回的记录
+    pending: std::vec::IntoIter<Record>,
+}
+
+impl TableScan<'_> {
+    /// 读出下一页的记录，没有更多页面时返回 None
+    fn read_next_page(&mut self) -> Option<Result<Vec<Record>>> {
+        let &page_id = self.page_ids.get(self.next_page)?;
+        if self.next_page == self.prefetched {
+            self.prefetched = (self.next_page + self.batch).min(self.page_ids.len());
+            let batch = &self.page_ids[self.next_page..self.prefetched];
+            if let Err(e) = self.buffer_manager.prefetch(batch) {
+                return Some(Err(e));
+            }
+        }
+        self.next_page += 1;
+        Some(
+            self.buffer_manager
+                .get_page(page_id)
+                .and_then(|page| match self.positions {
+                    Some(positions) => page.iter_projected(positions).collect(),
+                    None => Ok(page.iter_records().map(|(_, record)| record).collect()),
+                }),
+        )
+    }
+}
+
+impl Iterator for TableScan<'_> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.pending.next() {
+                return Some(Ok(record));
+            }
+            match self.read_next_page()? {
+                Ok(records) => self.pending = records.into_iter(),
+                Err(e) => {
+                    self.next_page = self.page_ids.len();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
 
 /// 表结构（优化版本）
 #[derive(Debug)]
@@ -22,6 +206,12 @@ pub struct Table {
     primary_key_index: Option<usize>,
     /// 记录数量缓存（用于快速统计）
     record_count: usize,
+    /// 自增列的下一个值（None 表示尚未根据现有数据计算），可能比 INT 的最大值大 1
+    next_auto_increment: Option<i64>,
+    /// 索引定义
+    indexes: Vec<IndexDef>,
+    /// 主键值到记录位置的内存索引，不持久化，加载和重新装页后重建
+    primary_index: BTreeMap<ValueKey, RecordId>,
 }
 
 impl Table {
@@ -35,9 +225,31 @@ impl Table {
             page_ids: Vec::new(),
             primary_key_index,
             record_count: 0,
+            next_auto_increment: None,
+            indexes: Vec::new(),
+            primary_index: BTreeMap::new(),
         }
     }
 
+    /// 使用已有的索引定义创建表（从目录加载时使用）
+    pub fn with_indexes(name: String, columns: Vec<ColumnDef>, indexes: Vec<IndexDef>) -> Self {
+        let mut table = Self::new(name, columns);
+        table.indexes = indexes;
+        table
+    }
+
+    /// 估算一行在最坏情况下的序列化大小（字节）
+    ///
+    /// 与 `Page::estimate_record_size` 的口径一致，可直接与 `MAX_RECORD_SIZE` 比较；
+    /// 存在未声明长度的 VARCHAR 时无法给出上界，返回 None。
+    pub fn estimate_max_row_size(columns: &[ColumnDef]) -> Option<usize> {
+        let mut size = value::varint_len(columns.len());
+        for column in columns {
+            size += column.data_type.max_encoded_size()?;
+        }
+        Some(size)
+    }
+
     pub fn get_primary_key_index(&self) -> Option<usize> {
         self.primary_key_index
     }
@@ -47,11 +259,215 @@ impl Table {
         &self.name
     }
 
+    /// 修改表名
+    pub fn rename(&mut self, name: String) {
+        self.name = name;
+    }
+
     /// 获取列定义
     pub fn columns(&self) -> &[ColumnDef] {
         &self.columns
     }
 
+    /// 获取索引定义
+    pub fn indexes(&self) -> &[IndexDef] {
+        &self.indexes
+    }
+
+    /// 添加索引定义
+    ///
+    /// 索引名不能重复，索引列必须存在。唯一索引会登记到对应列的 UNIQUE 约束上，
+    /// 由于唯一性检查按单列进行，暂不支持多列唯一索引；调用方需保证现有数据满足唯一性。
+    pub fn add_index(&mut self, index: IndexDef) -> Result<()> {
+        if index.name.eq_ignore_ascii_case("PRIMARY")
+            || self
+                .indexes
+                .iter()
+                .any(|existing| existing.name.eq_ignore_ascii_case(&index.name))
+        {
+            return Err(DBError::Schema(format!("Duplicate key name '{}'", index.name)));
+        }
+
+        let mut positions = Vec::with_capacity(index.columns.len());
+        for column in &index.columns {
+            match column_position(&self.columns, column) {
+                Some(position) => positions.push(position),
+                None => {
+                    return Err(DBError::Schema(format!(
+                        "Key column '{}' doesn't exist in table",
+                        column
+                    )));
+                }
+            }
+        }
+
+        if index.unique {
+            match positions.as_slice() {
+                [position] => self.columns[*position].unique = true,
+                _ => {
+                    return Err(DBError::Schema(format!(
+                        "暂不支持多列唯一索引 '{}'",
+                        index.name
+                    )));
+                }
+            }
+        }
+
+        self.indexes.push(index);
+        Ok(())
+    }
+
+    /// 依次应用 ALTER TABLE 操作，得到新的表结构，不修改表本身
+    ///
+    /// 删除列时同时把它从索引中移除，只剩这一列的索引随之删除。
+    pub fn plan_alter(&self, operations: &[AlterColumn]) -> Result<AlteredSchema> {
+        let mut columns = self.columns.clone();
+        let mut indexes = self.indexes.clone();
+        let mut sources: Vec<Option<usize>> = (0..columns.len()).map(Some).collect();
+
+        for operation in operations {
+            match operation {
+                AlterColumn::Add { column, position } => {
+                    if column_position(&columns, &column.name).is_some() {
+                        return Err(DBError::Schema(format!(
+                            "Duplicate column name '{}'",
+                            column.name
+                        )));
+                    }
+                    if column.is_primary && columns.iter().any(|col| col.is_primary) {
+                        return Err(DBError::Schema("Multiple primary key defined".to_string()));
+                    }
+                    if column.auto_increment && columns.iter().any(|col| col.auto_increment) {
+                        return Err(DBError::Schema(
+                            "一张表只能有一个 AUTO_INCREMENT 列".to_string(),
+                        ));
+                    }
+                    let at = match position {
+                        ColumnPosition::Last => columns.len(),
+                        ColumnPosition::First => 0,
+                        ColumnPosition::After(name) => {
+                            column_position(&columns, name).ok_or_else(|| {
+                                DBError::Schema(format!(
+                                    "Unknown column '{}' in '{}'",
+                                    name, self.name
+                                ))
+                            })? + 1
+                        }
+                    };
+                    columns.insert(at, column.clone());
+                    sources.insert(at, None);
+                }
+                AlterColumn::Drop { name } => {
+                    let position = column_position(&columns, name).ok_or_else(|| {
+                        DBError::Schema(format!(
+                            "Can't DROP '{}'; check that column/key exists",
+                            name
+                        ))
+                    })?;
+                    if columns.len() == 1 {
+                        return Err(DBError::Schema(
+                            "You can't delete all columns with ALTER TABLE; use DROP TABLE instead"
+                                .to_string(),
+                        ));
+                    }
+                    columns.remove(position);
+                    sources.remove(position);
+                    for index in &mut indexes {
+                        index.columns.retain(|column| column != name);
+                    }
+                    indexes.retain(|index| !index.columns.is_empty());
+                }
+            }
+        }
+
+        Ok(AlteredSchema {
+            columns,
+            indexes,
+            sources,
+        })
+    }
+
+    /// 修改表的列定义，并按新的列重写全部记录
+    ///
+    /// 新增列在已有记录中取默认值（没有默认值时取 NULL），所以表中有数据时不能添加
+    /// 没有默认值的 NOT NULL 列或自增列。
+    /// 记录重写后重新装页，记录ID会改变。
+    pub fn alter_columns(
+        &mut self,
+        buffer_manager: &mut BufferManager,
+        operations: &[AlterColumn],
+    ) -> Result<()> {
+        let altered = self.plan_alter(operations)?;
+        let records = self.get_all_records(buffer_manager)?;
+
+        if !records.is_empty()
+            && let Some(column) = altered
+                .columns
+                .iter()
+                .zip(&altered.sources)
+                .find(|(col, source)| {
+                    source.is_none()
+                        && ((col.not_null && col.default.is_none())
+                            || col.is_primary
+                            || col.auto_increment)
+                })
+                .map(|(col, _)| col)
+        {
+            return Err(DBError::Schema(format!(
+                "表 '{}' 中已有数据，不能添加没有默认值的列 '{}'",
+                self.name, column.name
+            )));
+        }
+        // 多条记录取同一个默认值会违反唯一约束
+        if records.len() > 1
+            && let Some(column) = altered
+                .columns
+                .iter()
+                .zip(&altered.sources)
+                .find(|(col, source)| {
+                    source.is_none()
+                        && col.unique
+                        && col.default.as_ref().is_some_and(|value| !value.is_null())
+                })
+                .map(|(col, _)| col)
+        {
+            return Err(DBError::Schema(format!(
+                "表 '{}' 中已有多条记录，唯一列 '{}' 不能都取默认值",
+                self.name, column.name
+            )));
+        }
+
+        let records: Vec<RawRecord> = records
+            .into_iter()
+            .map(|record| {
+                altered
+                    .sources
+                    .iter()
+                    .zip(&altered.columns)
+                    .map(|(source, column)| match source {
+                        Some(position) => record.values().get(*position).cloned().ok_or_else(|| {
+                            DBError::Execution(format!(
+                                "表 '{}' 的记录只有 {} 列：数据文件与表结构不一致，可用 .verify 检查",
+                                self.name,
+                                record.values().len()
+                            ))
+                        }),
+                        None => Ok(column.default.clone().unwrap_or(Value::Null)),
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+
+        if !altered.columns.iter().any(|col| col.auto_increment) {
+            self.next_auto_increment = None;
+        }
+        self.primary_key_index = altered.columns.iter().position(|col| col.is_primary);
+        self.columns = altered.columns;
+        self.indexes = altered.indexes;
+        self.pack_records(buffer_manager, records)?;
+        Ok(())
+    }
+
     /// 获取记录数量（快速）
     pub fn record_count(&self) -> usize {
         self.record_count
@@ -61,7 +477,7 @@ impl Table {
     pub fn insert_record(
         &mut self,
         buffer_manager: &mut BufferManager,
-        values: Vec<Value>,
+        mut values: Vec<Value>,
     ) -> Result<RecordId> {
         // 验证值的数量与列数是否匹配
         if values.len() != self.columns.len() {
@@ -72,37 +488,20 @@ impl Table {
             )));
         }
 
-        // 验证 NULL 约束
-        for (value, column) in values.iter().zip(&self.columns) {
-            if value == &Value::Null && column.not_null {
-                return Err(DBError::Schema(format!(
-                    "Field '{}' doesn't have a default value",
-                    column.name
-                )));
-            }
-        }
+        self.fit_strings(std::slice::from_mut(&mut values), false)?;
+        self.assign_auto_increment(buffer_manager, &mut values)?;
 
-        // 验证 UNIQUE 约束
-        for (i, (value, column)) in values.iter().zip(&self.columns).enumerate() {
-            if (column.unique || column.is_primary) && value != &Value::Null {
-                // 检查所有现有记录是否有重复值
-                for &page_id in &self.page_ids {
-                    let page = buffer_manager.get_page(page_id)?;
+        self.check_constraints(buffer_manager, std::slice::from_ref(&values), &[])?;
+        self.store_record(buffer_manager, values)
+    }
 
-                    // 遍历页面中的所有记录
-                    for (_, record) in page.iter_records() {
-                        let record_values = record.values();
-                        if i < record_values.len() && &record_values[i] == value {
-                            let constraint_name = if column.is_primary { "PRIMARY" } else { "UNIQUE" };
-                            return Err(DBError::Schema(format!(
-                                "Duplicate entry '{}' for key '{}'",
-                                value, constraint_name
-                            )));
-                        }
-                    }
-                }
-            }
-        }
+    /// 把已检查过的一行放进第一个有空间的页面，都放不下时分配新页面
+    fn store_record(
+        &mut self,
+        buffer_manager: &mut BufferManager,
+        values: Vec<Value>,
+    ) -> Result<RecordId> {
+        let key = self.primary_key_of(&values);
 
         // 尝试在现有页面中插入
         for &page_id in &self.page_ids {
@@ -113,6 +512,7 @@ impl Table {
                 match page.insert_record(values.clone()) {
                     Ok(record_id) => {
                         self.record_count += 1; // 增加记录计数
+                        self.index_record(key, record_id);
                         return Ok(record_id);
                     }
                     Err(_) => continue, // 虽然理论上能放下，但实际插入失败，尝试下一个页面
@@ -129,11 +529,13 @@ impl Table {
         match page.insert_record(values) {
             Ok(record_id) => {
                 self.record_count += 1; // 增加记录计数
+                self.index_record(key, record_id);
                 Ok(record_id)
             }
             Err(e) => {
                 // 如果新页面也无法容纳，说明单条记录太大
                 self.page_ids.pop(); // 移除刚创建的页面
+                buffer_manager.free_page(new_page_id);
                 Err(DBError::Schema(format!(
                     "记录太大，无法存储在单个页面中: {}",
                     e
@@ -143,13 +545,23 @@ impl Table {
     }
 
     /// 批量插入记录（性能优化版本）
+    ///
+    /// 超过 VARCHAR 长度的字符串在 `truncate` 为 true 时截断，否则整批拒绝，见 [`Table::fit_strings`]。
+    /// 自增列按行依次分配，约束对整批一起检查。写入从表的最后一页开始依次装页，
+    /// 整批要么全部写入，要么（任何一行失败时）一行都不留下。
     pub fn batch_insert_records(
         &mut self,
         buffer_manager: &mut BufferManager,
-        rows: Vec<Vec<Value>>,
-    ) -> Result<Vec<RecordId>> {
-        let mut inserted_ids = Vec::with_capacity(rows.len());
-        
+        mut rows: Vec<Vec<Value>>,
+        truncate: bool,
+    ) -> Result<(Vec<RecordId>, Vec<Truncation>)> {
+        // 只按最坏情况行大小预留页面 ID 列表的容量；页面本身仍在写满时逐个分配，
+        // 最坏情况估算往往偏大，按它提前分配页面会留下空页
+        if let Some(row_size) = Self::estimate_max_row_size(&self.columns) {
+            let rows_per_page = (MAX_RECORD_SIZE / row_size.max(1)).max(1);
+            self.page_ids.reserve(rows.len().div_ceil(rows_per_page));
+        }
+
         // 预先验证所有行
         for (row_idx, values) in rows.iter().enumerate() {
             if values.len() != self.columns.len() {
@@ -160,10 +572,93 @@ impl Table {
                     self.columns.len()
                 )));
             }
-            
-            // 验证NULL约束
+        }
+        let truncations = self.fit_strings(&mut rows, truncate)?;
+
+        let next_auto_increment = self.next_auto_increment;
+        let result = self.assign_batch(buffer_manager, &mut rows).and_then(|()| {
+            self.check_constraints(buffer_manager, &rows, &[])?;
+            self.append_records(buffer_manager, rows)
+        });
+        match result {
+            Ok(inserted_ids) => Ok((inserted_ids, truncations)),
+            Err(e) => {
+                self.next_auto_increment = next_auto_increment;
+                Err(e)
+            }
+        }
+    }
+
+    /// 依次为一批行分配自增值
+    fn assign_batch(
+        &mut self,
+        buffer_manager: &mut BufferManager,
+        rows: &mut [Vec<Value>],
+    ) -> Result<()> {
+        for values in rows {
+            self.assign_auto_increment(buffer_manager, values)?;
+        }
+        Ok(())
+    }
+
+    /// 让一批行中的字符串符合所在列的 VARCHAR 长度
+    ///
+    /// 所有写入路径都经过这里。`truncate` 为 false 时任何超长的值都使整批报错；
+    /// 为 true 时按字符边界截断，返回被截断的列和行号，由调用方转成警告。
+    pub fn fit_strings(&self, rows: &mut [Vec<Value>], truncate: bool) -> Result<Vec<Truncation>> {
+        let mut truncations = Vec::new();
+        for (row_index, values) in rows.iter_mut().enumerate() {
+            for (value, column) in values.iter_mut().zip(&self.columns) {
+                let (Value::String(s), Some(limit)) = (value, column.data_type.varchar_limit())
+                else {
+                    continue;
+                };
+                if varchar_len(s) <= limit {
+                    continue;
+                }
+                if !truncate {
+                    return Err(DBError::Schema(format!(
+                        "Data too long for column '{}' at row {}",
+                        column.name,
+                        row_index + 1
+                    )));
+                }
+                truncate_varchar(s, limit);
+                truncations.push(Truncation {
+                    column: column.name.clone(),
+                    row: row_index + 1,
+                });
+            }
+        }
+        Ok(truncations)
+    }
+    
+    /// 表上的唯一约束（主键和 UNIQUE 列，单列唯一索引已登记在列上），返回约束名和约束列的位置
+    fn unique_keys(&self) -> Vec<(&str, Vec<usize>)> {
+        let mut keys: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (position, column) in self.columns.iter().enumerate() {
+            if column.is_primary {
+                keys.push(("PRIMARY", vec![position]));
+            } else if column.unique {
+                keys.push((&column.name, vec![position]));
+            }
+        }
+        keys
+    }
+
+    /// 检查一批待写入的行是否满足 NOT NULL 和唯一约束，任何一行不满足都不写入
+    ///
+    /// `replaced` 是将被这批行替换的记录（UPDATE），不参与比较；批内的行之间也不能重复。
+    /// 唯一约束中 NULL 不与任何值重复，自增列的 NULL 在插入时才分配，不算违反 NOT NULL。
+    pub fn check_constraints(
+        &self,
+        buffer_manager: &mut BufferManager,
+        rows: &[Vec<Value>],
+        replaced: &[RecordId],
+    ) -> Result<()> {
+        for values in rows {
             for (value, column) in values.iter().zip(&self.columns) {
-                if value == &Value::Null && column.not_null {
+                if value.is_null() && column.not_null && !column.auto_increment {
                     return Err(DBError::Schema(format!(
                         "Field '{}' doesn't have a default value",
                         column.name
@@ -171,35 +666,143 @@ impl Table {
                 }
             }
         }
-        
-        // 批量插入（跳过重复的UNIQUE检查优化）
+
+        let keys = self.unique_keys();
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let key_of = |values: &[Value], positions: &[usize]| -> Option<Vec<ValueKey>> {
+            positions
+                .iter()
+                .map(|&position| match &values[position] {
+                    Value::Null => None,
+                    value => Some(ValueKey(value.clone())),
+                })
+                .collect()
+        };
+
+        let replaced: HashSet<RecordId> = replaced.iter().copied().collect();
+        let mut seen = vec![BTreeSet::new(); keys.len()];
+        for record in self.get_all_records(buffer_manager)? {
+            if record.id().is_some_and(|id| replaced.contains(&id)) {
+                continue;
+            }
+            for ((_, positions), seen) in keys.iter().zip(&mut seen) {
+                if let Some(key) = key_of(record.values(), positions) {
+                    seen.insert(key);
+                }
+            }
+        }
+        for values in rows {
+            for ((name, positions), seen) in keys.iter().zip(&mut seen) {
+                if let Some(key) = key_of(values, positions)
+                    && !seen.insert(key)
+                {
+                    let entry: Vec<String> = positions
+                        .iter()
+                        .map(|&position| values[position].to_string())
+                        .collect();
+                    return Err(DBError::Schema(format!(
+                        "Duplicate entry '{}' for key '{}'",
+                        entry.join("-"),
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 为自增列分配值
+    ///
+    /// 值为 NULL 时填入计数器的下一个值；显式给出的更大值会把计数器推到其后，
+    /// 与 MySQL 的行为一致。计数器在第一次使用时根据现有数据的最大值初始化。
+    fn assign_auto_increment(
+        &mut self,
+        buffer_manager: &mut BufferManager,
+        values: &mut [Value],
+    ) -> Result<()> {
+        let Some(index) = self.columns.iter().position(|col| col.auto_increment) else {
+            return Ok(());
+        };
+
+        let next = match self.next_auto_increment {
+            Some(next) => next,
+            None => {
+                let mut max = 0;
+                for record in self.get_all_records(buffer_manager)? {
+                    if let Some(Value::Int(v)) = record.values().get(index) {
+                        max = max.max(*v);
+                    }
+                }
+                i64::from(max) + 1
+            }
+        };
+
+        let next = match &values[index] {
+            Value::Null => {
+                let value = i32::try_from(next).map_err(|_| {
+                    DBError::Execution(format!(
+                        "自增列 '{}' 的值已达到 INT 上限",
+                        self.columns[index].name
+                    ))
+                })?;
+                values[index] = Value::Int(value);
+                next + 1
+            }
+            Value::Int(v) => next.max(i64::from(*v) + 1),
+            _ => next,
+        };
+        self.next_auto_increment = Some(next);
+        Ok(())
+    }
+
+    /// 把已检查过的一批行追加到表中（跳过约束检查，用于批量操作）
+    ///
+    /// 只定位一次末尾页，装满后分配下一页，不再为每一行从头查找有空间的页。
+    /// 某一行写入失败时撤销本批已写入的行和新分配的页。
+    fn append_records(
+        &mut self,
+        buffer_manager: &mut BufferManager,
+        rows: Vec<Vec<Value>>,
+    ) -> Result<Vec<RecordId>> {
+        let pages_before = self.page_ids.len();
+        let mut inserted_ids = Vec::with_capacity(rows.len());
         for values in rows {
-            // 对于批量插入，我们可以优化UNIQUE检查
-            // 这里简化处理，在生产环境中应该有更复杂的去重逻辑
-            let record_id = self.insert_record_fast(buffer_manager, values)?;
-            inserted_ids.push(record_id);
+            match self.append_record(buffer_manager, values) {
+                Ok(record_id) => inserted_ids.push(record_id),
+                Err(e) => {
+                    for &record_id in &inserted_ids {
+                        self.delete_record(buffer_manager, record_id)?;
+                        self.record_count -= 1;
+                    }
+                    for &page_id in &self.page_ids[pages_before..] {
+                        buffer_manager.get_page_mut(page_id)?.reset_records(Vec::new());
+                        buffer_manager.free_page(page_id);
+                    }
+                    self.page_ids.truncate(pages_before);
+                    return Err(e);
+                }
+            }
         }
-        
         Ok(inserted_ids)
     }
-    
-    /// 快速插入单条记录（跳过部分检查，用于批量操作）
-    fn insert_record_fast(
+
+    /// 把一行写入末尾页，放不下时分配新页
+    fn append_record(
         &mut self,
         buffer_manager: &mut BufferManager,
         values: Vec<Value>,
     ) -> Result<RecordId> {
-        // 尝试在现有页面中插入
-        for &page_id in &self.page_ids {
+        let key = self.primary_key_of(&values);
+        if let Some(&page_id) = self.page_ids.last() {
             let page = buffer_manager.get_page_mut(page_id)?;
-            if let Ok(true) = page.can_fit_record(&values) {
-                match page.insert_record(values.clone()) {
-                    Ok(record_id) => {
-                        self.record_count += 1;
-                        return Ok(record_id);
-                    }
-                    Err(_) => continue,
-                }
+            if let Ok(true) = page.can_fit_record(&values)
+                && let Ok(record_id) = page.insert_record(values.clone())
+            {
+                self.record_count += 1;
+                self.index_record(key, record_id);
+                return Ok(record_id);
             }
         }
 
@@ -210,14 +813,17 @@ impl Table {
         match page.insert_record(values) {
             Ok(record_id) => {
                 self.record_count += 1;
+                self.index_record(key, record_id);
                 Ok(record_id)
             }
             Err(e) => {
                 self.page_ids.pop();
+                buffer_manager.free_page(new_page_id);
                 Err(DBError::Schema(format!("记录太大: {}", e)))
             }
         }
     }
+
     pub fn delete_record(
         &mut self,
         buffer_manager: &mut BufferManager,
@@ -231,7 +837,12 @@ impl Table {
         }
 
         let page = buffer_manager.get_page_mut(id.page_id)?;
-        page.delete_record(id) // 直接传递 RecordId
+        let key = self.primary_key_of(page.get_record(id)?.values());
+        page.delete_record(id)?; // 直接传递 RecordId
+        if let Some(key) = key {
+            self.primary_index.remove(&key);
+        }
+        Ok(())
     }
 
     /// 获取记录
@@ -247,13 +858,18 @@ impl Table {
         page.get_record(id) // 直接传递 RecordId
     }
 
-    /// 修改记录
+    /// 修改记录，返回记录修改后所在的位置
+    ///
+    /// 不检查 NOT NULL 和唯一约束，调用方应先对整批新值调用 [`Table::check_constraints`]；
+    /// 超长的字符串直接报错，需要截断时调用方应先用 [`Table::fit_strings`] 处理新值。
+    /// 变大的记录在原页面放不下时搬到其它有空间的页面（或新页面），此时返回新的
+    /// RecordId，原来的 RecordId 失效。
     pub fn update_record(
         &mut self,
         buffer_manager: &mut BufferManager,
         id: RecordId,
         set_pairs: &Vec<(String, Value)>,
-    ) -> Result<()> {
+    ) -> Result<RecordId> {
         if !self.page_ids.contains(&id.page_id) {
             return Err(DBError::NotFound(format!(
                 "页面 {} 不属于表 {}",
@@ -269,9 +885,11 @@ impl Table {
 
         // 按照 set_pairs 更新记录值
         for (col_name, new_value) in set_pairs {
-            if let Some(col_index) = self.columns.iter().position(|col| &col.name == col_name) {
-                // ... 类型验证逻辑 ...
-                new_values[col_index] = new_value.clone();
+            if let Some(col_index) = column_position(&self.columns, col_name) {
+                // 执行器已按会话的 SQL 模式转换和校验过新值，直接调用存储层时按宽松模式转换
+                new_values[col_index] = new_value
+                    .clone()
+                    .coerce_to(&self.columns[col_index].data_type, SqlMode::Lenient);
             } else {
                 return Err(DBError::Schema(format!(
                     "表 '{}' 中不存在列 '{}'",
@@ -279,26 +897,321 @@ impl Table {
                 )));
             }
         }
+        self.fit_strings(std::slice::from_mut(&mut new_values), false)?;
+        let old_key = self.primary_key_of(original_record.values());
+        let new_key = self.primary_key_of(&new_values);
+
+        if !page.can_replace_record(id, &new_values)? {
+            // 先写入新位置再删除原记录，新位置也放不下时原记录保持不变
+            let new_id = self.store_record(buffer_manager, new_values)?;
+            buffer_manager.get_page_mut(id.page_id)?.delete_record(id)?;
+            self.record_count -= 1;
+            if old_key != new_key
+                && let Some(old_key) = old_key
+            {
+                self.primary_index.remove(&old_key);
+            }
+            return Ok(new_id);
+        }
 
         // 替换记录
         page.replace_record(id, new_values)?;
+        if old_key != new_key {
+            if let Some(old_key) = old_key {
+                self.primary_index.remove(&old_key);
+            }
+            self.index_record(new_key, id);
+        }
+        Ok(id)
+    }
+
+    /// 按主键值查找记录，走内存中的主键索引而不扫描数据页
+    ///
+    /// 表没有主键、键为 NULL 或不存在时返回 None。键按 [`Value::total_cmp`] 比较，
+    /// 所以 `5` 与 `5.0` 视为同一个键，与 WHERE 中 `=` 的结果一致。
+    pub fn find_by_primary_key(
+        &self,
+        buffer_manager: &mut BufferManager,
+        key: &Value,
+    ) -> Result<Option<Record>> {
+        if self.primary_key_index.is_none() || key.is_null() {
+            return Ok(None);
+        }
+        match self.primary_index.get(&ValueKey(key.clone())) {
+            Some(&id) => self.get_record(buffer_manager, id).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 按主键范围查找记录，按主键从小到大返回，只读取范围内的记录
+    ///
+    /// 表没有主键或范围为空时返回空列表。与 [`Table::find_by_primary_key`] 一样按
+    /// [`Value::total_cmp`] 比较，调用方需保证边界与主键列的类型可比。
+    pub fn find_by_primary_key_range(
+        &self,
+        buffer_manager: &mut BufferManager,
+        low: Bound<&Value>,
+        high: Bound<&Value>,
+    ) -> Result<Vec<Record>> {
+        if self.primary_key_index.is_none() {
+            return Ok(Vec::new());
+        }
+        let key = |bound: Bound<&Value>| bound.map(|value| ValueKey(value.clone()));
+        let (low, high) = (key(low), key(high));
+        // BTreeMap::range 在下界大于上界时会 panic，这里先排除空范围
+        if let (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) = (&low, &high)
+        {
+            let both_included = matches!((&low, &high), (Bound::Included(_), Bound::Included(_)));
+            if start > end || (start == end && !both_included) {
+                return Ok(Vec::new());
+            }
+        }
+        self.primary_index
+            .range((low, high))
+            .map(|(_, &id)| self.get_record(buffer_manager, id))
+            .collect()
+    }
+
+    /// 主键索引的键数和最小、最大键，表没有主键时返回 None
+    pub fn primary_key_stats(&self) -> Option<PrimaryKeyStats> {
+        self.primary_key_index?;
+        let first = |entry: Option<(&ValueKey, &RecordId)>| entry.map(|(key, _)| key.0.clone());
+        Some(PrimaryKeyStats {
+            key_count: self.primary_index.len(),
+            min: first(self.primary_index.first_key_value()),
+            max: first(self.primary_index.last_key_value()),
+        })
+    }
+
+    /// 一行的主键值，没有主键或主键为 NULL 时返回 None
+    fn primary_key_of(&self, values: &[Value]) -> Option<ValueKey> {
+        let value = values.get(self.primary_key_index?)?;
+        (!value.is_null()).then(|| ValueKey(value.clone()))
+    }
+
+    fn index_record(&mut self, key: Option<ValueKey>, id: RecordId) {
+        if let Some(key) = key {
+            self.primary_index.insert(key, id);
+        }
+    }
+
+    /// 扫描全部数据页重建主键索引
+    fn rebuild_primary_index(&mut self, buffer_manager: &mut BufferManager) -> Result<()> {
+        self.primary_index.clear();
+        if self.primary_key_index.is_none() {
+            return Ok(());
+        }
+        for record in self.get_all_records(buffer_manager)? {
+            if let Some(id) = record.id() {
+                let key = self.primary_key_of(record.values());
+                self.index_record(key, id);
+            }
+        }
         Ok(())
     }
 
     /// 获取表中所有记录
     pub fn get_all_records(&self, buffer_manager: &mut BufferManager) -> Result<Vec<Record>> {
-        let mut records = Vec::new();
+        self.scan(buffer_manager, None).collect()
+    }
 
+    /// 获取表中所有记录，每条记录只包含 `positions` 处的列（按给出的顺序）
+    ///
+    /// 只用到少数几列的查询不必复制整行，见 [`Page::iter_projected`]。
+    pub fn get_records_projected(
+        &self,
+        buffer_manager: &mut BufferManager,
+        positions: &[usize],
+    ) -> Result<Vec<Record>> {
+        self.scan(buffer_manager, Some(positions)).collect()
+    }
+
+    /// 按存储顺序逐页读出表中的记录，`positions` 不为空时只复制这些列
+    ///
+    /// 每次预读 [`SCAN_PREFETCH_PAGES`] 个页面（不超过缓冲池容量），之后逐页取出记录，
+    /// 任何时候只有一页的记录在迭代器中，调用方可以边读边过滤。
+    pub fn scan<'a>(
+        &'a self,
+        buffer_manager: &'a mut BufferManager,
+        positions: Option<&'a [usize]>,
+    ) -> TableScan<'a> {
+        let batch = SCAN_PREFETCH_PAGES.min(buffer_manager.capacity());
+        TableScan {
+            buffer_manager,
+            page_ids: &self.page_ids,
+            positions,
+            batch,
+            next_page: 0,
+            prefetched: 0,
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    /// 逐页统计记录数、已删除的槽位和序列化大小，不修改任何页面
+    pub fn stats(&self, buffer_manager: &mut BufferManager) -> Result<TableStats> {
+        let mut stats = TableStats {
+            row_count: 0,
+            page_count: self.page_ids.len(),
+            total_bytes: 0,
+            avg_page_utilization: 0.0,
+            deleted_slots: 0,
+        };
         for &page_id in &self.page_ids {
             let page = buffer_manager.get_page(page_id)?;
+            stats.row_count += page.get_record_count();
+            stats.deleted_slots += page.get_deleted_slot_count();
+            stats.total_bytes += page.serialize()?.len();
+        }
+        if stats.page_count > 0 {
+            stats.avg_page_utilization =
+                stats.total_bytes as f64 / (stats.page_count * PAGE_SIZE) as f64;
+        }
+        Ok(stats)
+    }
+
+    /// 检查表中记录的列数和值的类型是否与表结构一致，不修改任何页面
+    ///
+    /// `max_pages` 为 Some 时只检查前几个数据页（加载时抽样），None 时检查全部。
+    /// 无法读取的页面计入结果而不是返回错误，一次检查可以报告表中的全部问题。
+    pub fn verify(
+        &self,
+        buffer_manager: &mut BufferManager,
+        max_pages: Option<usize>,
+    ) -> TableVerification {
+        let mut result = TableVerification::default();
+        let pages = max_pages.unwrap_or(self.page_ids.len());
+        for &page_id in self.page_ids.iter().take(pages) {
+            result.pages_checked += 1;
+            let page = match buffer_manager.get_page(page_id) {
+                Ok(page) => page,
+                Err(e) => {
+                    result.unreadable_pages += 1;
+                    result.note(|| format!("页 {} 无法读取: {}", page_id, e));
+                    continue;
+                }
+            };
+            for (id, record) in page.iter_records() {
+                result.records_checked += 1;
+                let values = record.values();
+                if values.len() != self.columns.len() {
+                    result.arity_mismatches += 1;
+                    result.note(|| {
+                        format!(
+                            "页 {} 槽位 {}: 有 {} 列，表结构有 {} 列",
+                            page_id,
+                            id.slot,
+                            values.len(),
+                            self.columns.len()
+                        )
+                    });
+                } else if let Some((column, value)) = self
+                    .columns
+                    .iter()
+                    .zip(values)
+                    .find(|(column, value)| !column.data_type.holds(value))
+                {
+                    result.type_mismatches += 1;
+                    result.note(|| {
+                        format!(
+                            "页 {} 槽位 {}: 列 '{}' 是 {}，值 {} 是 {} 类型",
+                            page_id,
+                            id.slot,
+                            column.name,
+                            column.data_type,
+                            value,
+                            value.type_name()
+                        )
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// 整理表的数据页，把存活的记录重新装入尽量少的页面
+    ///
+    /// 记录按大小从大到小依次放入第一个放得下的页面（first-fit decreasing），
+    /// 记录较大且大小不一时比按原顺序紧密排列省页。整理后记录按新的
+    /// (PageId, slot) 顺序扫描，之前取得的 RecordId 全部失效。多出来的页面被清空
+    /// 并从表中移除，新布局需要的页面比原来多时才分配新页。
+    pub fn compact(&mut self, buffer_manager: &mut BufferManager) -> Result<CompactionSummary> {
+        let records: Vec<RawRecord> = self
+            .get_all_records(buffer_manager)?
+            .into_iter()
+            .map(Record::into_raw_data)
+            .collect();
+        let sizes: Vec<usize> = records.iter().map(Page::packed_record_size).collect();
+        let capacity = PACKED_PAGE_CAPACITY - PAGE_HEADER_SIZE;
 
-            // 直接使用迭代器获取所有记录
-            for (_, record) in page.iter_records() {
-                records.push(record);
+        // 按原顺序依次排列（next-fit）需要的页数，只用于和整理结果对比
+        let mut naive_pages = 0;
+        let mut used = capacity;
+        for &size in &sizes {
+            if used + size > capacity {
+                naive_pages += 1;
+                used = 0;
             }
+            used += size;
         }
 
-        Ok(records)
+        let pages_before = self.page_ids.len();
+        let record_total = records.len();
+        self.pack_records(buffer_manager, records)?;
+
+        Ok(CompactionSummary {
+            records: record_total,
+            pages_before,
+            naive_pages,
+            pages_after: self.page_ids.len(),
+        })
+    }
+
+    /// 把记录重新装入表的数据页（first-fit decreasing），复用已有的页，多余的页被清空
+    fn pack_records(
+        &mut self,
+        buffer_manager: &mut BufferManager,
+        records: Vec<RawRecord>,
+    ) -> Result<()> {
+        let sizes: Vec<usize> = records.iter().map(Page::packed_record_size).collect();
+        let capacity = PACKED_PAGE_CAPACITY - PAGE_HEADER_SIZE;
+
+        // 大小相同的记录保持原顺序，结果是确定的
+        let mut order: Vec<usize> = (0..records.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(sizes[i]));
+        let mut bins: Vec<(usize, Vec<usize>)> = Vec::new();
+        for i in order {
+            match bins.iter_mut().find(|(used, _)| used + sizes[i] <= capacity) {
+                Some((used, members)) => {
+                    *used += sizes[i];
+                    members.push(i);
+                }
+                None => bins.push((sizes[i], vec![i])),
+            }
+        }
+
+        let mut records: Vec<Option<RawRecord>> = records.into_iter().map(Some).collect();
+        let mut page_ids = Vec::with_capacity(bins.len());
+        for (position, (_, members)) in bins.into_iter().enumerate() {
+            let page_id = match self.page_ids.get(position) {
+                Some(&page_id) => page_id,
+                None => buffer_manager.create_page()?,
+            };
+            let page_records = members
+                .into_iter()
+                .filter_map(|i| records[i].take())
+                .collect();
+            buffer_manager.get_page_mut(page_id)?.reset_records(page_records);
+            page_ids.push(page_id);
+        }
+        for &page_id in self.page_ids.iter().skip(page_ids.len()) {
+            buffer_manager.get_page_mut(page_id)?.reset_records(Vec::new());
+            buffer_manager.free_page(page_id);
+        }
+        self.page_ids = page_ids;
+        self.rebuild_primary_index(buffer_manager)
     }
 
     /// 从磁盘加载表数据
@@ -307,9 +1220,8 @@ impl Table {
         buffer_manager: &mut BufferManager,
         page_ids: Vec<PageId>,
     ) -> Result<()> {
-        let _ = buffer_manager; // 可能需要在加载时使用 BufferManager
         self.page_ids = page_ids;
-        Ok(())
+        self.rebuild_primary_index(buffer_manager)
     }
 
     /// 获取表的页面ID列表
@@ -317,3 +1229,68 @@ impl Table {
         &self.page_ids
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::io::page::Page;
+    use super::*;
+
+    fn column(name: &str, data_type: DataType) -> ColumnDef {
+        ColumnDef {
+            name: name.to_string(),
+            data_type,
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_matches_serialized_size() {
+        for (varchar_len, int_count) in [(0, 1), (10, 2), (250, 0), (251, 3), (30000, 1)] {
+            let mut columns = vec![column("s", DataType::Varchar(Some(varchar_len)))];
+            // 最坏情况：每个字符都占 4 个 UTF-8 字节
+            let mut row = vec![Value::String("😀".repeat(varchar_len as usize))];
+            for i in 0..int_count {
+                columns.push(column(&format!("i{}", i), DataType::Int(None)));
+                row.push(Value::Int(i32::MIN));
+            }
+
+            let estimate = Table::estimate_max_row_size(&columns).unwrap();
+            let actual = Page::estimate_record_size(&row);
+            assert!(
+                estimate >= actual && estimate - actual <= 8,
+                "VARCHAR({}) + {} INT: 估算 {} 字节，实际 {} 字节",
+                varchar_len,
+                int_count,
+                estimate,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn test_estimate_unbounded_varchar() {
+        let columns = vec![
+            column("id", DataType::Int(None)),
+            column("body", DataType::Varchar(None)),
+        ];
+        assert_eq!(Table::estimate_max_row_size(&columns), None);
+    }
+
+    #[test]
+    fn test_truncate_varchar_counts_chars() {
+        // 按字符计数："中" 占 3 个字节，但只算一个字符
+        let mut s = "ab中文".to_string();
+        assert!(!truncate_varchar(&mut s, 4));
+        assert_eq!(s, "ab中文");
+        assert!(truncate_varchar(&mut s, 3));
+        assert_eq!(s, "ab中");
+        let mut s = "😀😀x".to_string();
+        assert!(truncate_varchar(&mut s, 1));
+        assert_eq!(s, "😀");
+    }
+}
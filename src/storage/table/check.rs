@@ -0,0 +1,13 @@
+use bincode::{Decode, Encode};
+
+/// 表上的 CHECK 约束
+///
+/// 条件保存为 SQL 文本，执行器在 INSERT 和 UPDATE 写入前重新生成计划，对每一行求值；
+/// 与 SQL 标准一样，条件为 NULL 时视为满足。
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct CheckConstraint {
+    /// 约束名，未命名的约束与 MySQL 一样命名为 `表名_chk_序号`
+    pub name: String,
+    /// 条件的 SQL 文本，不含外层的 `CHECK (...)`
+    pub expr: String,
+}
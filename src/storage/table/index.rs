@@ -0,0 +1,17 @@
+use bincode::{Decode, Encode};
+
+/// 表上的索引定义（KEY / INDEX / UNIQUE KEY）
+///
+/// 目前只保存在表的元数据中，用于约束登记和 SHOW CREATE TABLE，查询执行不使用这里的索引。
+/// 只有主键有内存索引（表中按主键排序的 BTreeMap）：单表 SELECT 的 WHERE 限定主键的等值或
+/// 范围时（见执行器的 `key_access` 和 `KeyRange`），用 `Table::find_by_primary_key` /
+/// `Table::find_by_primary_key_range` 取行；范围内的行占全表的比例估算值不低于会话变量
+/// `index_selectivity_threshold` 时仍然全表扫描，会话变量 `access_path` 可以强制其中一种。
+/// 其余查询都全表扫描。
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct IndexDef {
+    pub name: String,
+    /// 索引列，按定义顺序
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
@@ -1,5 +1,6 @@
 use super::super::io::page::PageId;
 use super::super::table::Value;
+use crate::error::{DBError, Result};
 use bincode::{Decode, Encode};
 
 /// 记录ID
@@ -67,4 +68,28 @@ impl Record {
     pub fn raw_data(&self) -> &RawRecord {
         &self.data
     }
+
+    /// 取出原始记录数据
+    pub fn into_raw_data(self) -> RawRecord {
+        self.data
+    }
+
+    /// 只保留 `positions` 处的值（按给出的顺序），记录ID不变
+    ///
+    /// 记录的列数少于 `positions` 要求时（数据文件与表结构不一致）返回错误。
+    pub fn project(&self, positions: &[usize]) -> Result<Record> {
+        let data = positions
+            .iter()
+            .map(|&index| {
+                self.data.get(index).cloned().ok_or_else(|| {
+                    DBError::Execution(format!(
+                        "记录只有 {} 列，取不到第 {} 列：数据文件与表结构不一致，可用 .verify 检查",
+                        self.data.len(),
+                        index + 1
+                    ))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Record { id: self.id, data })
+    }
 }
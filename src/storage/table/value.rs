@@ -1,5 +1,7 @@
 use crate::error::{DBError, Result};
+use crate::session::SqlMode;
 use bincode::{Decode, Encode};
+use std::cmp::Ordering;
 
 /// 表示值的枚举
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
@@ -26,73 +28,124 @@ impl Value {
         }
     }
 
-    // 保留现有的数学运算方法...
+    /// 值的 JSON 表示：非有限的浮点数无法用 JSON 表示，编码为 null
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Int(n) => serde_json::Value::from(*n),
+            Value::Float(f) => {
+                serde_json::Number::from_f64(*f).map_or(serde_json::Value::Null, Into::into)
+            }
+            Value::String(s) => serde_json::Value::from(s.as_str()),
+            Value::Boolean(b) => serde_json::Value::Bool(*b),
+            Value::Null => serde_json::Value::Null,
+        }
+    }
+
+    /// 从 JSON 解码值，能放进 INT 的整数解码为 Int；数组和对象返回 None
+    pub fn from_json(json: &serde_json::Value) -> Option<Value> {
+        match json {
+            serde_json::Value::Null => Some(Value::Null),
+            serde_json::Value::Bool(b) => Some(Value::Boolean(*b)),
+            serde_json::Value::String(s) => Some(Value::String(s.clone())),
+            serde_json::Value::Number(n) => match n.as_i64().map(i32::try_from) {
+                Some(Ok(n)) => Some(Value::Int(n)),
+                _ => n.as_f64().map(Value::Float),
+            },
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => None,
+        }
+    }
+
+    // 算术运算：任一操作数为 NULL 时结果为 NULL
     pub fn add(&self, other: &Value) -> Result<Value> {
         match (self, other) {
-            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
-            (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
-            (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 + b)),
-            (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a + *b as f64)),
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Int(a), Value::Int(b)) => {
+                checked(a.checked_add(*b), || format!("{} + {}", a, b))
+            }
+            (Value::Float(a), Value::Float(b)) => finite(a + b, || format!("{} + {}", self, other)),
+            (Value::Int(a), Value::Float(b)) => {
+                finite(*a as f64 + b, || format!("{} + {}", self, other))
+            }
+            (Value::Float(a), Value::Int(b)) => {
+                finite(a + *b as f64, || format!("{} + {}", self, other))
+            }
             _ => Err(DBError::Execution("类型不兼容，无法相加".to_string())),
         }
     }
 
     pub fn subtract(&self, other: &Value) -> Result<Value> {
         match (self, other) {
-            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a - b)),
-            (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
-            (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 - b)),
-            (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a - *b as f64)),
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Int(a), Value::Int(b)) => {
+                checked(a.checked_sub(*b), || format!("{} - {}", a, b))
+            }
+            (Value::Float(a), Value::Float(b)) => finite(a - b, || format!("{} - {}", self, other)),
+            (Value::Int(a), Value::Float(b)) => {
+                finite(*a as f64 - b, || format!("{} - {}", self, other))
+            }
+            (Value::Float(a), Value::Int(b)) => {
+                finite(a - *b as f64, || format!("{} - {}", self, other))
+            }
             _ => Err(DBError::Execution("类型不兼容，无法相减".to_string())),
         }
     }
 
     pub fn multiply(&self, other: &Value) -> Result<Value> {
         match (self, other) {
-            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a * b)),
-            (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a * b)),
-            (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 * b)),
-            (Value::Float(a), Value::Int(b)) => Ok(Value::Float(a * *b as f64)),
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Int(a), Value::Int(b)) => {
+                checked(a.checked_mul(*b), || format!("{} * {}", a, b))
+            }
+            (Value::Float(a), Value::Float(b)) => finite(a * b, || format!("{} * {}", self, other)),
+            (Value::Int(a), Value::Float(b)) => {
+                finite(*a as f64 * b, || format!("{} * {}", self, other))
+            }
+            (Value::Float(a), Value::Int(b)) => {
+                finite(a * *b as f64, || format!("{} * {}", self, other))
+            }
             _ => Err(DBError::Execution("类型不兼容，无法相乘".to_string())),
         }
     }
 
     pub fn divide(&self, other: &Value) -> Result<Value> {
         match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
             (Value::Int(a), Value::Int(b)) => {
                 if *b == 0 {
                     return Err(DBError::Execution("除数不能为零".to_string()));
                 }
-                Ok(Value::Int(a / b))
+                checked(a.checked_div(*b), || format!("{} / {}", a, b))
             }
             (Value::Float(a), Value::Float(b)) => {
                 if *b == 0.0 {
                     return Err(DBError::Execution("除数不能为零".to_string()));
                 }
-                Ok(Value::Float(a / b))
+                finite(a / b, || format!("{} / {}", self, other))
             }
             (Value::Int(a), Value::Float(b)) => {
                 if *b == 0.0 {
                     return Err(DBError::Execution("除数不能为零".to_string()));
                 }
-                Ok(Value::Float(*a as f64 / b))
+                finite(*a as f64 / b, || format!("{} / {}", self, other))
             }
             (Value::Float(a), Value::Int(b)) => {
                 if *b == 0 {
                     return Err(DBError::Execution("除数不能为零".to_string()));
                 }
-                Ok(Value::Float(a / *b as f64))
+                finite(a / *b as f64, || format!("{} / {}", self, other))
             }
             _ => Err(DBError::Execution("类型不兼容，无法相除".to_string())),
         }
     }
     pub fn modulo(&self, other: &Value) -> Result<Value> {
         match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
             (Value::Int(a), Value::Int(b)) => {
                 if *b == 0 {
                     return Err(DBError::Execution("模数不能为零".to_string()));
                 }
-                Ok(Value::Int(a % b))
+                // i32::MIN % -1 的数学结果是 0，不会溢出
+                Ok(Value::Int(a.wrapping_rem(*b)))
             }
             _ => Err(DBError::Execution("模运算仅支持整数".to_string())),
         }
@@ -100,74 +153,188 @@ impl Value {
 
     pub fn negate(&self) -> Result<Value> {
         match self {
-            Value::Int(n) => Ok(Value::Int(-n)),
+            Value::Null => Ok(Value::Null),
+            Value::Int(n) => checked(n.checked_neg(), || format!("-({})", n)),
             Value::Float(f) => Ok(Value::Float(-f)),
             _ => Err(DBError::Execution("只能对数值进行取负操作".to_string())),
         }
     }
 
-    // 保留现有的比较方法...
-    pub fn eq(&self, other: &Self) -> Result<bool> {
+    /// 值的全序比较，ORDER BY、MIN/MAX 和有序容器共用这一套排序规则
+    ///
+    /// - NULL 小于任何非 NULL 值（ORDER BY 再按 NULLS FIRST/LAST 决定 NULL 的位置）；
+    /// - 数值之间按大小比较，布尔值按 0/1 参与，整数与浮点数混合时按浮点数比较；
+    /// - 字符串之间按字节序比较，并排在所有数值之后。
+    ///
+    /// 过滤条件中的 `eq`、`lt` 等方法保持 SQL 语义：任一侧为 NULL 时结果为假，
+    /// 类型不兼容时报错；除此之外它们的结果与本方法一致。
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Value::Null, _) | (_, Value::Null) => Ok(false),
-            (Value::Int(a), Value::Int(b)) => Ok(a == b),
-            (Value::Float(a), Value::Float(b)) => Ok(a == b),
-            (Value::Int(a), Value::Float(b)) => Ok(*a as f64 == *b),
-            (Value::Float(a), Value::Int(b)) => Ok(*a == *b as f64),
-            (Value::String(a), Value::String(b)) => Ok(a == b),
-            (Value::Boolean(a), Value::Boolean(b)) => Ok(a == b),
-            _ => Err(DBError::Execution("类型不匹配，无法比较".to_string())),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            _ => match (self.as_f64(), other.as_f64()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or_else(|| a.total_cmp(&b)),
+                _ => self.type_rank().cmp(&other.type_rank()),
+            },
         }
     }
 
-    pub fn ne(&self, other: &Self) -> Result<bool> {
-        self.eq(other).map(|result| !result)
+    /// 全序中的类型分组：NULL < 数值（含布尔值） < 字符串
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Int(_) | Value::Float(_) | Value::Boolean(_) => 1,
+            Value::String(_) => 2,
+        }
     }
 
-    pub fn lt(&self, other: &Self) -> Result<bool> {
-        match (self, other) {
-            (Value::Null, _) | (_, Value::Null) => Ok(false),
-            (Value::Int(a), Value::Int(b)) => Ok(a < b),
-            (Value::Float(a), Value::Float(b)) => Ok(a < b),
-            (Value::Int(a), Value::Float(b)) => Ok((*a as f64) < *b),
-            (Value::Float(a), Value::Int(b)) => Ok(*a < *b as f64),
-            (Value::String(a), Value::String(b)) => Ok(a < b),
-            (Value::Boolean(a), Value::Boolean(b)) => Ok(!*a && *b),
-            _ => Err(DBError::Execution("类型不匹配，无法比较".to_string())),
+    /// 数值（含布尔值）转换为浮点数
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(f64::from(*n)),
+            Value::Float(f) => Some(*f),
+            Value::Boolean(b) => Some(f64::from(u8::from(*b))),
+            Value::String(_) | Value::Null => None,
         }
     }
 
-    pub fn le(&self, other: &Self) -> Result<bool> {
-        match (self, other) {
-            (Value::Null, _) | (_, Value::Null) => Ok(false),
-            (Value::Int(a), Value::Int(b)) => Ok(a <= b),
-            (Value::Float(a), Value::Float(b)) => Ok(a <= b),
-            (Value::Int(a), Value::Float(b)) => Ok(*a as f64 <= *b),
-            (Value::Float(a), Value::Int(b)) => Ok(*a <= *b as f64),
-            (Value::String(a), Value::String(b)) => Ok(a <= b),
-            (Value::Boolean(a), Value::Boolean(b)) => Ok(!*a || *b),
+    /// 过滤条件使用的比较：任一侧为 NULL 时返回 None，类型不兼容时报错
+    ///
+    /// 宽松模式下布尔值按 0/1 与数值比较，严格模式下布尔值只能与布尔值比较：
+    /// BOOLEAN 列上的 `WHERE b = 1` 报错，而不是当作没有匹配的行。
+    fn sql_cmp(&self, other: &Self, mode: SqlMode) -> Result<Option<Ordering>> {
+        if mode == SqlMode::Strict && self.mixes_boolean_with(other) {
+            return Err(DBError::Execution(format!(
+                "严格模式下 {} 与 {} 不能比较",
+                self.type_name(),
+                other.type_name()
+            )));
+        }
+        match (self.type_rank(), other.type_rank()) {
+            (0, _) | (_, 0) => Ok(None),
+            (a, b) if a == b => Ok(Some(self.total_cmp(other))),
             _ => Err(DBError::Execution("类型不匹配，无法比较".to_string())),
         }
     }
 
-    pub fn gt(&self, other: &Self) -> Result<bool> {
-        other.lt(self)
+    /// 一侧是布尔值、另一侧是数值
+    fn mixes_boolean_with(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Value::Boolean(_), Value::Int(_) | Value::Float(_))
+                | (Value::Int(_) | Value::Float(_), Value::Boolean(_))
+        )
     }
 
-    pub fn ge(&self, other: &Self) -> Result<bool> {
-        other.le(self)
+    pub fn eq(&self, other: &Self, mode: SqlMode) -> Result<bool> {
+        Ok(self.sql_cmp(other, mode)? == Some(Ordering::Equal))
+    }
+
+    pub fn ne(&self, other: &Self, mode: SqlMode) -> Result<bool> {
+        Ok(matches!(
+            self.sql_cmp(other, mode)?,
+            Some(Ordering::Less | Ordering::Greater)
+        ))
+    }
+
+    pub fn lt(&self, other: &Self, mode: SqlMode) -> Result<bool> {
+        Ok(self.sql_cmp(other, mode)? == Some(Ordering::Less))
+    }
+
+    pub fn le(&self, other: &Self, mode: SqlMode) -> Result<bool> {
+        Ok(matches!(
+            self.sql_cmp(other, mode)?,
+            Some(Ordering::Less | Ordering::Equal)
+        ))
+    }
+
+    pub fn gt(&self, other: &Self, mode: SqlMode) -> Result<bool> {
+        other.lt(self, mode)
+    }
+
+    pub fn ge(&self, other: &Self, mode: SqlMode) -> Result<bool> {
+        other.le(self, mode)
     }
 
     pub fn is_null(&self) -> bool {
         matches!(self, Value::Null)
     }
+
+    /// 值的类型名，用于错误信息
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "INT",
+            Value::Float(_) => "FLOAT",
+            Value::String(_) => "VARCHAR",
+            Value::Boolean(_) => "BOOLEAN",
+            Value::Null => "NULL",
+        }
+    }
+
+    /// 按列类型做写入前的隐式转换
+    ///
+    /// 整数写入 FLOAT 列时转为浮点数，小数部分为零且在 INT 范围内的浮点数写入 INT 列时
+    /// 转为整数，字符串不会转为数值。布尔值与整数之间的转换取决于 SQL 模式：
+    ///
+    /// - 宽松模式沿用 MySQL 把布尔值当作 tinyint 的做法，TRUE/FALSE 写入 INT 列时存为 1/0，
+    ///   0/1 写入 BOOLEAN 列时存为 FALSE/TRUE；
+    /// - 严格模式不做这两种转换：布尔值只能写入 BOOLEAN 列，BOOLEAN 列也只接受布尔值。
+    ///
+    /// 比较时同样如此，见 [`Value::eq`] 等方法的 `mode` 参数。
+    /// 其余值原样返回，由调用方继续做类型校验。
+    pub fn coerce_to(self, data_type: &DataType, mode: SqlMode) -> Value {
+        let lenient = mode == SqlMode::Lenient;
+        match (self, data_type) {
+            (Value::Boolean(b), DataType::Int(_)) if lenient => bool_as_int(b),
+            (Value::Int(n), DataType::Float) => Value::Float(f64::from(n)),
+            (Value::Float(f), DataType::Int(_))
+                if f.fract() == 0.0 && (f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&f) =>
+            {
+                Value::Int(f as i32)
+            }
+            (Value::Int(n @ (0 | 1)), DataType::Boolean) if lenient => Value::Boolean(n == 1),
+            (value, _) => value,
+        }
+    }
+}
+
+/// 按 [`Value::total_cmp`] 排序和判等的值，可用作 BTreeMap / BTreeSet 的键
+#[derive(Debug, Clone)]
+pub struct ValueKey(pub Value);
+
+impl PartialEq for ValueKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ValueKey {}
+
+impl PartialOrd for ValueKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ValueKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// 布尔值作为整数时的取值：TRUE 为 1，FALSE 为 0
+fn bool_as_int(b: bool) -> Value {
+    Value::Int(b as i32)
 }
 
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Int(n) => write!(f, "{}", n),
-            Value::Float(fl) => write!(f, "{}", fl),
+            Value::Float(fl) => {
+                f.write_str(&format_float(*fl, f.precision().unwrap_or(FLOAT_PRECISION)))
+            }
             Value::String(s) => write!(f, "{}", s),
             Value::Boolean(b) => write!(f, "{}", b),
             Value::Null => write!(f, "NULL"),
@@ -175,8 +342,55 @@ impl std::fmt::Display for Value {
     }
 }
 
+/// 浮点数显示的默认有效数字位数，`format!("{:.N}", value)` 可以指定其它位数
+pub const FLOAT_PRECISION: usize = 15;
+
+/// 按 `precision` 位有效数字格式化浮点数（与 C 的 `%g` 相同的规则）
+///
+/// 指数小于 -4 或不小于有效位数时用指数形式（如 `1.5e-7`、`1e20`），否则用小数形式；
+/// 去掉末尾多余的 0，但小数形式至少保留一位小数，整数值的浮点数显示为 `3.0` 以区别于 INT。
+pub fn format_float(value: f64, precision: usize) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let precision = precision.max(1);
+    // 先按有效位数舍入，再由舍入后的指数决定写法
+    let scientific = format!("{:.*e}", precision - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').expect("指数形式总是带有 e");
+    let exponent: i32 = exponent.parse().expect("指数总是整数");
+
+    if exponent < -4 || exponent >= precision as i32 {
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        return format!("{}e{}", mantissa, exponent);
+    }
+    let decimals = (precision as i32 - 1 - exponent).max(1) as usize;
+    let fixed = format!("{:.*}", decimals, value);
+    let trimmed = fixed.trim_end_matches('0');
+    match trimmed.strip_suffix('.') {
+        Some(_) => format!("{}0", trimmed),
+        None => trimmed.to_string(),
+    }
+}
+
+/// 浮点运算的结果，溢出为无穷大或得到 NaN 时报错，而不是把它们写进结果或表中
+fn finite(result: f64, expr: impl FnOnce() -> String) -> Result<Value> {
+    if result.is_finite() {
+        Ok(Value::Float(result))
+    } else {
+        let message = format!("FLOAT 运算结果超出范围: {}", expr());
+        Err(DBError::Execution(message))
+    }
+}
+
+/// 整数运算的结果，溢出 INT 时报错而不是回绕
+fn checked(result: Option<i32>, expr: impl FnOnce() -> String) -> Result<Value> {
+    result
+        .map(Value::Int)
+        .ok_or_else(|| DBError::Execution(format!("INT 运算结果超出范围: {}", expr())))
+}
+
 /// 表示列定义的结构
-#[derive(Debug, Clone, Encode, Decode)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub struct ColumnDef {
     pub name: String,
     pub data_type: DataType,
@@ -185,20 +399,310 @@ pub struct ColumnDef {
     pub not_null: bool,
     pub unique: bool,
     pub is_primary: bool, // is_primary => not_null && unique
+    /// AUTO_INCREMENT：插入 NULL 或省略时自动分配下一个整数
+    pub auto_increment: bool,
+    /// DEFAULT 子句给出的默认值，INSERT 省略该列或写 DEFAULT 时使用；None 表示没有声明
+    pub default: Option<Value>,
+    /// REFERENCES 子句或表级 FOREIGN KEY 声明的外键
+    pub references: Option<ForeignKey>,
+}
+
+/// 外键：列的非 NULL 值必须出现在被引用表的被引用列中
+///
+/// 被引用表与所在的表属于同一个数据库，表名不带数据库限定。删除或修改仍被引用的行
+/// 时报错（RESTRICT），不支持级联。
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct ForeignKey {
+    pub table: String,
+    pub column: String,
+}
+
+/// 按列名查找列的位置，精确匹配优先，其次不区分大小写匹配
+///
+/// 新建的列名按小写保存；旧版本创建的大小写混合的列名仍然可以用任意大小写访问。
+pub fn column_position(columns: &[ColumnDef], name: &str) -> Option<usize> {
+    columns.iter().position(|col| col.name == name).or_else(|| {
+        columns
+            .iter()
+            .position(|col| col.name.eq_ignore_ascii_case(name))
+    })
 }
 
+/// INT 显示宽度的上限（与 MySQL 一致）
+pub const MAX_INT_DISPLAY_WIDTH: u32 = 255;
+
 /// 表示数据类型的枚举
+///
+/// 目录按 bincode 的变体序号保存类型，新变体只能追加在末尾，已有元数据文件才能继续加载。
 #[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
 pub enum DataType {
-    Int(u64),
-    Varchar(u64),
+    /// 32 位整数；参数是用户声明的显示宽度（如 INT(3) 中的 3），
+    /// 只用于回显，不影响存储和取值范围
+    Int(Option<u32>),
+    /// 变长字符串；参数是声明的最大字符数，未声明长度（`VARCHAR`）时为 None，不限长度
+    Varchar(Option<u64>),
+    /// 64 位浮点数，FLOAT、DOUBLE 和 REAL 都映射到这里
+    Float,
+    /// BOOLEAN / BOOL
+    Boolean,
+}
+
+impl DataType {
+    /// 值的种类是否与列类型相符，NULL 与任何类型都相符；不检查 VARCHAR 长度
+    ///
+    /// 只用于检查已经存储的记录，写入前的校验和隐式转换见 [`Value::coerce_to`]。
+    pub fn holds(&self, value: &Value) -> bool {
+        matches!(
+            (value, self),
+            (Value::Null, _)
+                | (Value::Int(_), DataType::Int(_))
+                | (Value::String(_), DataType::Varchar(_))
+                | (Value::Float(_), DataType::Float)
+                | (Value::Boolean(_), DataType::Boolean)
+        )
+    }
+
+    /// VARCHAR 声明的长度上限；其他类型和未声明长度的 VARCHAR 为 None
+    pub fn varchar_limit(&self) -> Option<usize> {
+        match self {
+            DataType::Varchar(max_len) => max_len.and_then(|max_len| usize::try_from(max_len).ok()),
+            DataType::Int(_) | DataType::Float | DataType::Boolean => None,
+        }
+    }
+
+    /// 该类型的值在最坏情况下的 bincode 编码大小（字节），包含 Value 的枚举标签
+    ///
+    /// VARCHAR(n) 按每个字符最多 4 个 UTF-8 字节计算；未声明长度的 VARCHAR 没有上界，返回 None。
+    pub fn max_encoded_size(&self) -> Option<usize> {
+        match self {
+            DataType::Int(_) => Some(encoded_len(&Value::Int(i32::MIN))),
+            DataType::Float => Some(encoded_len(&Value::Float(f64::MAX))),
+            DataType::Boolean => Some(encoded_len(&Value::Boolean(true))),
+            DataType::Varchar(max_len) => {
+                let max_bytes = usize::try_from((*max_len)?).ok()?.checked_mul(4)?;
+                Some(encoded_len(&Value::Null) + varint_len(max_bytes) + max_bytes)
+            }
+        }
+    }
+}
+
+/// 字符串相对 VARCHAR(n) 的长度，长度校验和截断都按它计算
+///
+/// 与 MySQL 一样按字符（Unicode 标量值）计数，VARCHAR(10) 可以存 10 个汉字；
+/// 存储占用的字节数由 [`DataType::max_encoded_size`] 按每个字符最多 4 字节估算。
+pub fn varchar_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// 把字符串截断到 [`varchar_len`] 不超过 `limit`，只在字符边界处截断；返回是否截断
+pub fn truncate_varchar(s: &mut String, limit: usize) -> bool {
+    if varchar_len(s) <= limit {
+        return false;
+    }
+    let mut used = 0;
+    let mut end = 0;
+    let mut buf = [0; 4];
+    for (index, c) in s.char_indices() {
+        used += varchar_len(c.encode_utf8(&mut buf));
+        if used > limit {
+            break;
+        }
+        end = index + c.len_utf8();
+    }
+    s.truncate(end);
+    true
+}
+
+/// 值的实际 bincode 编码大小
+fn encoded_len(value: &Value) -> usize {
+    bincode::encode_to_vec(value, bincode::config::standard())
+        .map(|v| v.len())
+        .unwrap_or(0)
+}
+
+/// bincode 标准配置下长度前缀（变长整数）占用的字节数
+pub(crate) fn varint_len(n: usize) -> usize {
+    match n as u64 {
+        0..=250 => 1,
+        251..=0xFFFF => 3,
+        0x1_0000..=0xFFFF_FFFF => 5,
+        _ => 9,
+    }
 }
 
 impl std::fmt::Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            DataType::Int(size) => write!(f, "INT({})", size),
-            DataType::Varchar(size) => write!(f, "VARCHAR({})", size),
+            DataType::Int(Some(width)) => write!(f, "INT({})", width),
+            DataType::Int(None) => write!(f, "INT"),
+            DataType::Varchar(Some(size)) => write!(f, "VARCHAR({})", size),
+            DataType::Varchar(None) => write!(f, "VARCHAR"),
+            DataType::Float => write!(f, "FLOAT"),
+            DataType::Boolean => write!(f, "BOOLEAN"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_float() {
+        for (value, expected) in [
+            (0.1 + 0.2, "0.3"),
+            (3.0, "3.0"),
+            (-2.5, "-2.5"),
+            (0.0, "0.0"),
+            (1.0 / 3.0, "0.333333333333333"),
+            (123456.789, "123456.789"),
+            (0.0001, "0.0001"),
+            (0.00001234, "1.234e-5"),
+            (1e14, "100000000000000.0"),
+            (1e15, "1e15"),
+            (-1.5e300, "-1.5e300"),
+        ] {
+            assert_eq!(Value::Float(value).to_string(), expected, "{}", value);
+        }
+        // 精度按有效数字计算，可以用格式化参数指定
+        assert_eq!(format!("{:.3}", Value::Float(1.23456)), "1.23");
+        assert_eq!(format!("{:.3}", Value::Float(2.0)), "2.0");
+        assert_eq!(format!("{:.3}", Value::Float(98765.0)), "9.88e4");
+        assert_eq!(format_float(9.9999, 3), "10.0");
+    }
+
+    #[test]
+    fn test_boolean_int_by_sql_mode() {
+        let int = DataType::Int(None);
+        let (lenient, strict) = (SqlMode::Lenient, SqlMode::Strict);
+
+        // 宽松模式下布尔值与 0/1 互相转换，严格模式下原样返回，由类型校验拒绝
+        assert_eq!(Value::Boolean(true).coerce_to(&int, lenient), Value::Int(1));
+        assert_eq!(
+            Value::Int(0).coerce_to(&DataType::Boolean, lenient),
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            Value::Int(2).coerce_to(&DataType::Boolean, lenient),
+            Value::Int(2)
+        );
+        assert_eq!(
+            Value::Boolean(true).coerce_to(&int, strict),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            Value::Int(1).coerce_to(&DataType::Boolean, strict),
+            Value::Int(1)
+        );
+        // 数值之间的转换与模式无关
+        assert_eq!(
+            Value::Int(2).coerce_to(&DataType::Float, strict),
+            Value::Float(2.0)
+        );
+        assert_eq!(Value::Float(3.0).coerce_to(&int, strict), Value::Int(3));
+
+        for (left, right) in [
+            (Value::Boolean(true), Value::Int(1)),
+            (Value::Int(1), Value::Boolean(true)),
+            (Value::Boolean(false), Value::Float(0.0)),
+            (Value::Float(0.0), Value::Boolean(false)),
+        ] {
+            assert!(left.eq(&right, lenient).unwrap(), "{} = {}", left, right);
+            assert!(!left.lt(&right, lenient).unwrap(), "{} < {}", left, right);
+            assert!(left.eq(&right, strict).is_err(), "{} = {}", left, right);
+            assert!(left.ge(&right, strict).is_err(), "{} >= {}", left, right);
+        }
+        // 严格模式下同类型的比较和与 NULL 的比较不受影响
+        assert!(
+            Value::Boolean(false)
+                .lt(&Value::Boolean(true), strict)
+                .unwrap()
+        );
+        assert!(Value::Int(1).eq(&Value::Float(1.0), strict).unwrap());
+        assert!(!Value::Boolean(true).eq(&Value::Null, strict).unwrap());
+    }
+
+    #[test]
+    fn test_arithmetic_propagates_null() {
+        let null = Value::Null;
+        for result in [
+            null.add(&Value::Int(1)),
+            Value::Float(1.5).subtract(&null),
+            null.multiply(&null),
+            null.divide(&Value::Int(0)),
+            Value::Int(7).modulo(&null),
+            null.negate(),
+        ] {
+            assert_eq!(result.unwrap(), Value::Null);
+        }
+    }
+
+    #[test]
+    fn test_int_overflow_at_boundaries() {
+        let max = Value::Int(i32::MAX);
+        let min = Value::Int(i32::MIN);
+        let one = Value::Int(1);
+        let minus_one = Value::Int(-1);
+
+        // 恰好落在边界上的结果照常返回
+        assert_eq!(Value::Int(i32::MAX - 1).add(&one).unwrap(), max);
+        assert_eq!(Value::Int(i32::MIN + 1).subtract(&one).unwrap(), min);
+        assert_eq!(
+            Value::Int(-65536).multiply(&Value::Int(32768)).unwrap(),
+            min
+        );
+        assert_eq!(max.negate().unwrap(), Value::Int(-i32::MAX));
+        assert_eq!(min.divide(&one).unwrap(), min);
+        assert_eq!(min.modulo(&minus_one).unwrap(), Value::Int(0));
+
+        for (result, expr) in [
+            (max.add(&one), "2147483647 + 1"),
+            (min.subtract(&one), "-2147483648 - 1"),
+            (max.subtract(&minus_one), "2147483647 - -1"),
+            (
+                Value::Int(65536).multiply(&Value::Int(32768)),
+                "65536 * 32768",
+            ),
+            (min.multiply(&minus_one), "-2147483648 * -1"),
+            (min.negate(), "-(-2147483648)"),
+            (min.divide(&minus_one), "-2147483648 / -1"),
+        ] {
+            match result {
+                Err(DBError::Execution(message)) => {
+                    assert_eq!(message, format!("INT 运算结果超出范围: {}", expr))
+                }
+                other => panic!("{} 预期溢出错误，实际为 {:?}", expr, other),
+            }
+        }
+
+        // 与 FLOAT 混合运算时按浮点数计算，不会整数溢出
+        assert_eq!(
+            max.add(&Value::Float(1.0)).unwrap(),
+            Value::Float(2147483648.0)
+        );
+    }
+
+    #[test]
+    fn test_float_overflow_rejected() {
+        let huge = Value::Float(1e308);
+        for result in [
+            huge.multiply(&Value::Int(10)),
+            huge.add(&huge),
+            Value::Int(-1)
+                .subtract(&Value::Float(f64::MAX))
+                .and_then(|v| v.multiply(&huge)),
+            huge.divide(&Value::Float(1e-10)),
+        ] {
+            match result {
+                Err(DBError::Execution(message)) => {
+                    assert!(message.contains("FLOAT 运算结果超出范围"), "{}", message)
+                }
+                other => panic!("预期溢出错误，实际为 {:?}", other),
+            }
         }
+        assert_eq!(
+            huge.multiply(&Value::Float(0.5)).unwrap(),
+            Value::Float(5e307)
+        );
     }
 }
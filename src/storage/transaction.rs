@@ -1,12 +1,62 @@
-use crate::error::Result;
+use super::table::{RecordId, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 撤销日志中的一项：事务中执行过的一个操作，回滚时执行它的逆操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum UndoEntry {
+    /// 插入了一行，回滚时删除
+    Insert {
+        database: String,
+        table: String,
+        record_id: RecordId,
+    },
+    /// 删除了一行，回滚时按原值重新插入
+    Delete {
+        database: String,
+        table: String,
+        record_id: RecordId,
+        values: Vec<Value>,
+    },
+    /// 更新了一行，回滚时恢复更新前的值
+    ///
+    /// `record_id` 是更新前的位置，`updated_id` 是更新后的位置；变大的行被搬到其它页面时两者不同。
+    Update {
+        database: String,
+        table: String,
+        record_id: RecordId,
+        updated_id: RecordId,
+        values: Vec<Value>,
+    },
+    /// 创建了表，回滚时删除
+    CreateTable { database: String, table: String },
+}
+
+impl UndoEntry {
+    /// 操作所在的数据库和表
+    pub fn table(&self) -> (&str, &str) {
+        match self {
+            UndoEntry::Insert {
+                database, table, ..
+            }
+            | UndoEntry::Delete {
+                database, table, ..
+            }
+            | UndoEntry::Update {
+                database, table, ..
+            }
+            | UndoEntry::CreateTable { database, table } => (database, table),
+        }
+    }
+}
 
 /// 事务 - 管理数据库操作的原子性
+///
+/// 只保存撤销日志，不做隔离：事务中的修改直接写入表中，回滚时按相反顺序执行逆操作。
 pub struct Transaction {
     // 事务ID
     id: u64,
-    // 事务状态
-    active: bool,
-    // 可以添加事务日志、锁信息等
+    // 撤销日志，按执行顺序排列
+    undo_log: Vec<UndoEntry>,
 }
 
 impl Default for Transaction {
@@ -15,18 +65,14 @@ impl Default for Transaction {
     }
 }
 
-
 impl Transaction {
     pub fn new() -> Self {
-        static mut NEXT_ID: u64 = 0;
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
-        // 简单的事务ID生成
-        let id = unsafe {
-            NEXT_ID += 1;
-            NEXT_ID
-        };
-
-        Self { id, active: true }
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            undo_log: Vec::new(),
+        }
     }
 
     /// 获取事务ID
@@ -34,22 +80,74 @@ impl Transaction {
         self.id
     }
 
-    /// 提交事务
-    pub fn commit(&mut self) -> Result<()> {
-        self.active = false;
-        // 实际提交操作
-        Ok(())
+    /// 记录一个已执行的操作
+    pub fn record(&mut self, entry: UndoEntry) {
+        self.undo_log.push(entry);
+    }
+
+    /// 表是否由本事务创建
+    pub fn created_table(&self, database: &str, table: &str) -> bool {
+        self.undo_log.iter().any(|entry| {
+            matches!(entry, UndoEntry::CreateTable { .. }) && entry.table() == (database, table)
+        })
+    }
+
+    /// 本事务创建的表被删除后，与它有关的日志都不再需要撤销
+    pub fn forget_table(&mut self, database: &str, table: &str) {
+        self.undo_log
+            .retain(|entry| entry.table() != (database, table));
     }
 
-    /// 回滚事务
-    pub fn rollback(&mut self) -> Result<()> {
-        self.active = false;
-        // 实际回滚操作
-        Ok(())
+    /// 撤销日志，按需要撤销的顺序（最后执行的在前）排列
+    pub fn into_undo_log(self) -> Vec<UndoEntry> {
+        let mut undo_log = self.undo_log;
+        undo_log.reverse();
+        undo_log
     }
 
-    /// 检查事务是否处于活动状态
-    pub fn is_active(&self) -> bool {
-        self.active
+    /// 撤销日志的长度
+    pub fn len(&self) -> usize {
+        self.undo_log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo_log.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(table: &str, slot: usize) -> UndoEntry {
+        UndoEntry::Insert {
+            database: "db".to_string(),
+            table: table.to_string(),
+            record_id: RecordId::new(1, slot),
+        }
+    }
+
+    #[test]
+    fn test_undo_log_order_and_forget_table() {
+        let mut transaction = Transaction::new();
+        assert!(Transaction::new().id() > transaction.id());
+
+        transaction.record(UndoEntry::CreateTable {
+            database: "db".to_string(),
+            table: "t".to_string(),
+        });
+        transaction.record(insert("t", 0));
+        transaction.record(insert("u", 0));
+        transaction.record(insert("u", 1));
+        assert!(transaction.created_table("db", "t"));
+        assert!(!transaction.created_table("db", "u"));
+        assert!(!transaction.created_table("other", "t"));
+
+        transaction.forget_table("db", "t");
+        assert_eq!(transaction.len(), 2);
+        assert_eq!(
+            transaction.into_undo_log(),
+            vec![insert("u", 1), insert("u", 0)]
+        );
     }
 }
@@ -0,0 +1,305 @@
+use crate::error::{DBError, Result};
+use sqlparser::ast::{SetExpr, Statement};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::parser::Parser;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 允许的最小刷新间隔
+pub const MIN_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 默认的最大执行次数，防止忘记停止的 .watch 一直运行
+pub const DEFAULT_WATCH_MAX_ITERATIONS: usize = 1000;
+
+/// 等待期间检查中断标志的粒度
+const POLL_STEP: Duration = Duration::from_millis(100);
+
+/// 时钟抽象，便于在测试中注入假时钟
+pub trait Clock {
+    /// 当前时间（距 UNIX 纪元的时长）
+    fn now(&self) -> Duration;
+    /// 休眠指定时长
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// 使用系统时间的时钟
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// 解析后的 .watch 命令
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchCommand {
+    pub interval: Duration,
+    pub sql: String,
+}
+
+/// .watch 结束的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchOutcome {
+    /// 用户按下 Ctrl+C
+    Interrupted,
+    /// 达到最大执行次数
+    MaxIterations,
+}
+
+impl WatchCommand {
+    /// 解析 `.watch <秒数> <SQL>` 的参数部分
+    ///
+    /// 只允许只读查询，间隔不得小于 [`MIN_WATCH_INTERVAL`]。
+    pub fn parse(args: &str) -> Result<Self> {
+        let args = args.trim();
+        let (seconds, sql) = args
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| DBError::Parse("用法: .watch <秒数> <SELECT 语句>".to_string()))?;
+
+        let seconds: f64 = seconds
+            .parse()
+            .map_err(|_| DBError::Parse(format!("无效的刷新间隔: {}", seconds)))?;
+        if !seconds.is_finite() || seconds < MIN_WATCH_INTERVAL.as_secs_f64() {
+            return Err(DBError::Parse(format!(
+                "刷新间隔不能小于 {} 秒",
+                MIN_WATCH_INTERVAL.as_secs_f64()
+            )));
+        }
+
+        let sql = sql.trim();
+        let statements = Parser::parse_sql(&MySqlDialect {}, sql)?;
+        match statements.as_slice() {
+            [stmt] if is_read_only(stmt) => Ok(Self {
+                interval: Duration::from_secs_f64(seconds),
+                sql: sql.to_string(),
+            }),
+            [_] => Err(DBError::Execution(".watch 只能用于只读查询".to_string())),
+            _ => Err(DBError::Parse(".watch 只能监视一条语句".to_string())),
+        }
+    }
+}
+
+/// `SELECT ... INTO` 会新建目标表、写入查询结果，是写操作，不算只读查询
+fn is_read_only(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Query(query) => !writes_into_table(&query.body),
+        Statement::ShowTables { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::ExplainTable { .. } => true,
+        _ => false,
+    }
+}
+
+/// 查询中是否有带 INTO 子句的 SELECT，包括 UNION 的各个分支和括号中的查询
+fn writes_into_table(body: &SetExpr) -> bool {
+    match body {
+        SetExpr::Select(select) => select.into.is_some(),
+        SetExpr::Query(query) => writes_into_table(&query.body),
+        SetExpr::SetOperation { left, right, .. } => {
+            writes_into_table(left) || writes_into_table(right)
+        }
+        SetExpr::Values(_) | SetExpr::Table(_) => false,
+        _ => true,
+    }
+}
+
+/// 按固定间隔反复执行 `tick`，直到被中断或达到最大次数
+///
+/// `tick` 接收本次的序号（从 1 开始）和当前时间；它返回的错误会直接结束循环。
+/// 等待期间按 [`POLL_STEP`] 检查 `interrupted`，保证 Ctrl+C 能及时生效。
+pub fn run_watch<C, F>(
+    clock: &mut C,
+    interval: Duration,
+    max_iterations: usize,
+    interrupted: &AtomicBool,
+    mut tick: F,
+) -> Result<WatchOutcome>
+where
+    C: Clock,
+    F: FnMut(usize, Duration) -> Result<()>,
+{
+    for iteration in 1..=max_iterations {
+        if interrupted.load(Ordering::SeqCst) {
+            return Ok(WatchOutcome::Interrupted);
+        }
+        tick(iteration, clock.now())?;
+
+        if iteration == max_iterations {
+            break;
+        }
+
+        let deadline = clock.now() + interval;
+        loop {
+            if interrupted.load(Ordering::SeqCst) {
+                return Ok(WatchOutcome::Interrupted);
+            }
+            let now = clock.now();
+            if now >= deadline {
+                break;
+            }
+            clock.sleep((deadline - now).min(POLL_STEP));
+        }
+    }
+
+    Ok(WatchOutcome::MaxIterations)
+}
+
+/// 将时间格式化为 `HH:MM:SS`（UTC）
+pub fn format_timestamp(time: Duration) -> String {
+    let secs = time.as_secs() % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 假时钟：sleep 只推进内部时间，并在指定时刻触发中断
+    struct FakeClock<'a> {
+        now: Duration,
+        interrupt_at: Option<(Duration, &'a AtomicBool)>,
+    }
+
+    impl Clock for FakeClock<'_> {
+        fn now(&self) -> Duration {
+            self.now
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.now += duration;
+            if let Some((at, flag)) = self.interrupt_at
+                && self.now >= at
+            {
+                flag.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_watch_command() {
+        let cmd = WatchCommand::parse("2 SELECT COUNT(*) FROM events").unwrap();
+        assert_eq!(cmd.interval, Duration::from_secs(2));
+        assert_eq!(cmd.sql, "SELECT COUNT(*) FROM events");
+
+        let cmd = WatchCommand::parse("0.5 SHOW TABLES").unwrap();
+        assert_eq!(cmd.interval, Duration::from_millis(500));
+
+        assert!(WatchCommand::parse("0.1 SELECT 1").is_err());
+        assert!(WatchCommand::parse("abc SELECT 1").is_err());
+        assert!(WatchCommand::parse("2").is_err());
+        assert!(WatchCommand::parse("2 DELETE FROM events").is_err());
+        assert!(WatchCommand::parse("2 INSERT INTO events VALUES (1)").is_err());
+        assert!(WatchCommand::parse("2 DROP TABLE events").is_err());
+        assert!(WatchCommand::parse("2 SELECT 1; SELECT 2").is_err());
+        assert!(WatchCommand::parse("2 SELECT * INTO copy FROM events").is_err());
+        assert!(WatchCommand::parse("2 SELECT * INTO TABLE copy FROM events").is_err());
+        assert!(WatchCommand::parse("2 SELECT 1 UNION SELECT id INTO copy FROM events").is_err());
+    }
+
+    #[test]
+    fn test_watch_stops_at_max_iterations() {
+        let interrupted = AtomicBool::new(false);
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+            interrupt_at: None,
+        };
+        let mut ticks = Vec::new();
+
+        let outcome = run_watch(
+            &mut clock,
+            Duration::from_secs(2),
+            3,
+            &interrupted,
+            |i, now| {
+                ticks.push((i, now));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(outcome, WatchOutcome::MaxIterations);
+        assert_eq!(
+            ticks,
+            vec![
+                (1, Duration::ZERO),
+                (2, Duration::from_secs(2)),
+                (3, Duration::from_secs(4)),
+            ]
+        );
+        // 最后一次执行后不再等待
+        assert_eq!(clock.now, Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_watch_interrupt_during_wait() {
+        let interrupted = AtomicBool::new(false);
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+            interrupt_at: Some((Duration::from_millis(4500), &interrupted)),
+        };
+        let mut count = 0;
+
+        let outcome = run_watch(
+            &mut clock,
+            Duration::from_secs(2),
+            100,
+            &interrupted,
+            |_, _| {
+                count += 1;
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(outcome, WatchOutcome::Interrupted);
+        assert_eq!(count, 3);
+        // 中断在一个轮询步长内被发现
+        assert!(clock.now < Duration::from_millis(4500) + POLL_STEP);
+    }
+
+    #[test]
+    fn test_watch_propagates_errors() {
+        let interrupted = AtomicBool::new(false);
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+            interrupt_at: None,
+        };
+
+        let result = run_watch(
+            &mut clock,
+            Duration::from_secs(1),
+            10,
+            &interrupted,
+            |i, _| {
+                if i == 2 {
+                    Err(DBError::NotFound("表不存在".to_string()))
+                } else {
+                    Ok(())
+                }
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(Duration::from_secs(0)), "00:00:00");
+        assert_eq!(
+            format_timestamp(Duration::from_secs(86_400 + 3600 * 13 + 60 * 5 + 9)),
+            "13:05:09"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! 主键范围的取行方式：按估算的命中比例在主键索引和全表扫描之间选择。
+//!
+//! 表中主键分布很不均匀：大部分行挤在开头，少数行分散在很大的区间里，
+//! 估算按 [min, max] 线性插值，与实际命中的行数相差很远，但两种取行方式的结果必须相同。
+
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use tempfile::TempDir;
+
+/// 连续主键的行数
+const DENSE_ROWS: i32 = 990;
+
+/// 主键 1..=990 连续，另外 10 行的主键为 100000, 200000, ..., 1000000
+fn skewed_db(temp_dir: &TempDir) -> SimpleDB {
+    let mut db = testing::open_db(temp_dir.path(), "access_db").unwrap();
+    db.execute_single_sql("CREATE TABLE skewed (id INT PRIMARY KEY, grp INT)")
+        .unwrap();
+    let rows = (1..=DENSE_ROWS)
+        .chain((1..=10).map(|n| n * 100_000))
+        .map(|id| vec![Value::Int(id), Value::Int(id % 7)])
+        .collect();
+    db.bulk_insert("skewed", rows).unwrap();
+    db
+}
+
+/// EXPLAIN 输出中的取行方式
+fn access(db: &mut SimpleDB, condition: &str) -> String {
+    let lines = query_rows(
+        db,
+        &format!("EXPLAIN SELECT * FROM skewed WHERE {}", condition),
+    );
+    lines.last().unwrap()[0].to_string()
+}
+
+/// 分别强制走主键索引和全表扫描，两者的结果必须一致
+fn assert_paths_agree(db: &mut SimpleDB, condition: &str) -> Vec<Vec<Value>> {
+    let sql = format!("SELECT * FROM skewed WHERE {} ORDER BY id", condition);
+    db.execute_single_sql("SET access_path = INDEX").unwrap();
+    let indexed = query_rows(db, &sql);
+    db.execute_single_sql("SET access_path = SCAN").unwrap();
+    let scanned = query_rows(db, &sql);
+    db.execute_single_sql("SET access_path = AUTO").unwrap();
+    assert_eq!(indexed, scanned, "{}", condition);
+    assert_eq!(query_rows(db, &sql), indexed, "{}", condition);
+    indexed
+}
+
+#[test]
+fn test_both_paths_return_same_rows() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = skewed_db(&temp_dir);
+
+    let cases = [
+        ("id = 500", 1),
+        ("id <= 990", 990),
+        ("id > 990", 10),
+        ("id >= 1000 AND id < 500000", 4),
+        ("500000 <= id AND grp = 0", 1),
+        ("id >= 10 AND id <= 20", 11),
+        ("id > 10 AND id < 5", 0),
+        ("id > 5 AND id < 5", 0),
+        ("id = 5 AND id = 6", 0),
+        ("id >= 5.5 AND id < 8", 2),
+        ("id = NULL", 0),
+        ("id > 2000000", 0),
+    ];
+    for (condition, expected) in cases {
+        assert_eq!(
+            assert_paths_agree(&mut db, condition).len(),
+            expected,
+            "{}",
+            condition
+        );
+    }
+}
+
+#[test]
+fn test_plan_choice_follows_threshold() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = skewed_db(&temp_dir);
+
+    // 主键唯一，等值条件的估算为 1/1000
+    assert_eq!(
+        access(&mut db, "id = 500"),
+        "  ACCESS primary key lookup (id = 500), estimated 0.1% of rows"
+    );
+    // 实际命中 99% 的行，但插值估算只有约 0.1%，仍然走索引
+    assert_eq!(
+        access(&mut db, "id <= 990"),
+        "  ACCESS primary key lookup (id <= 990), estimated 0.1% of rows"
+    );
+    // 实际只命中 10 行，估算接近全部，走全表扫描
+    assert_eq!(
+        access(&mut db, "id > 990"),
+        "  ACCESS full scan, primary key range (id > 990) estimated 99.9% of rows"
+    );
+    // 估算约 30%，默认阈值 20% 下全表扫描，调高阈值后走索引
+    let condition = "id >= 700000";
+    assert!(access(&mut db, condition).starts_with("  ACCESS full scan"));
+    db.execute_single_sql("SET index_selectivity_threshold = 50")
+        .unwrap();
+    assert!(access(&mut db, condition).starts_with("  ACCESS primary key lookup"));
+    db.execute_single_sql("SET index_selectivity_threshold = DEFAULT")
+        .unwrap();
+
+    // 没有限定主键的条件不受 access_path 影响
+    db.execute_single_sql("SET access_path = INDEX").unwrap();
+    assert_eq!(access(&mut db, "grp = 1"), "  ACCESS full scan");
+    assert_eq!(access(&mut db, "id = 1 OR id = 2"), "  ACCESS full scan");
+    assert!(access(&mut db, "id > 990").starts_with("  ACCESS primary key lookup"));
+    db.execute_single_sql("SET access_path = SCAN").unwrap();
+    assert!(access(&mut db, "id = 500").starts_with("  ACCESS full scan"));
+}
@@ -0,0 +1,148 @@
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use tempfile::TempDir;
+
+#[test]
+fn test_altered_records_survive_reopen() {
+    let temp_dir = TempDir::new().unwrap();
+    let expected = {
+        let mut db = testing::open_db(temp_dir.path(), "alter_db").unwrap();
+        db.execute_single_sql("CREATE TABLE items (id INT, name VARCHAR(50), price INT)")
+            .unwrap();
+        for i in 0..200 {
+            db.execute_single_sql(&format!(
+                "INSERT INTO items VALUES ({}, 'item-{:03}', {})",
+                i,
+                i,
+                i * 10
+            ))
+            .unwrap();
+        }
+        db.execute_single_sql("ALTER TABLE items DROP COLUMN name, ADD COLUMN note VARCHAR(200)")
+            .unwrap();
+        db.execute_single_sql(&format!(
+            "UPDATE items SET note = '{}' WHERE id < 50",
+            "x".repeat(200)
+        ))
+        .unwrap();
+        db.save().unwrap();
+        query_rows(&mut db, "SELECT * FROM items")
+    };
+    assert_eq!(expected.len(), 200);
+    assert!(expected.iter().all(|row| row.len() == 3));
+
+    // 重新打开后列定义与每条记录一致
+    let mut db = testing::open_db(temp_dir.path(), "alter_db").unwrap();
+    let QueryResult::ResultSet(rs) = db.execute_single_sql("DESCRIBE items").unwrap() else {
+        panic!("DESCRIBE 应返回结果集");
+    };
+    let names: Vec<&Value> = rs.rows.iter().map(|row| &row[0]).collect();
+    assert_eq!(
+        names,
+        [
+            &Value::String("id".to_string()),
+            &Value::String("price".to_string()),
+            &Value::String("note".to_string())
+        ]
+    );
+    assert_eq!(query_rows(&mut db, "SELECT * FROM items"), expected);
+    assert_eq!(
+        query_rows(&mut db, "SELECT price, note FROM items WHERE id = 199"),
+        vec![vec![Value::Int(1990), Value::Null]]
+    );
+
+    // 重新打开后仍可继续写入
+    db.execute_single_sql("INSERT INTO items VALUES (200, 2000, 'new')")
+        .unwrap();
+    assert_eq!(query_rows(&mut db, "SELECT * FROM items").len(), 201);
+}
+
+/// 结果集的列名和行
+fn select_all(db: &mut SimpleDB, table: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match db
+        .execute_single_sql(&format!("SELECT * FROM {}", table))
+        .unwrap()
+    {
+        QueryResult::ResultSet(rs) => (rs.columns, rs.rows),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_column_order_survives_alter_backup_and_restore() {
+    let (source_dir, mut source) = testing::temp_db("alter_db").unwrap();
+    source
+        .execute_single_sql("CREATE TABLE events (b INT, d VARCHAR(10), a INT, c VARCHAR(10))")
+        .unwrap();
+    source
+        .execute_single_sql("INSERT INTO events VALUES (1, 'd1', 2, 'c1')")
+        .unwrap();
+    source
+        .execute_single_sql("ALTER TABLE events ADD COLUMN z INT FIRST, DROP COLUMN a")
+        .unwrap();
+    source
+        .execute_single_sql("ALTER TABLE events ADD COLUMN m VARCHAR(10) AFTER b, ADD COLUMN e INT")
+        .unwrap();
+    let expected_columns = ["z", "b", "m", "d", "c", "e"].map(String::from).to_vec();
+
+    let backup_path = source_dir.path().join("events.bak");
+    source.backup_online(&backup_path).unwrap();
+    let (_restore_dir, mut restored) = testing::temp_db("alter_db").unwrap();
+    restored
+        .restore_backup(&backup_path, Some("restored"))
+        .unwrap();
+    restored.execute_single_sql("USE restored").unwrap();
+
+    for db in [&mut source, &mut restored] {
+        let (columns, rows) = select_all(db, "events");
+        assert_eq!(columns, expected_columns);
+        assert_eq!(
+            rows,
+            vec![vec![
+                Value::Null,
+                Value::Int(1),
+                Value::Null,
+                Value::String("d1".to_string()),
+                Value::String("c1".to_string()),
+                Value::Null,
+            ]]
+        );
+
+        // DESCRIBE 与 SHOW CREATE TABLE 按同样的顺序列出
+        let described: Vec<Value> = query_rows(db, "DESCRIBE events")
+            .into_iter()
+            .map(|row| row[0].clone())
+            .collect();
+        assert_eq!(
+            described,
+            expected_columns
+                .iter()
+                .map(|name| Value::String(name.clone()))
+                .collect::<Vec<_>>()
+        );
+        let create = query_rows(db, "SHOW CREATE TABLE events")[0][1].to_string();
+        let positions: Vec<usize> = expected_columns
+            .iter()
+            .map(|name| create.find(&format!("`{}`", name)).unwrap())
+            .collect();
+        assert!(positions.is_sorted(), "{}", create);
+
+        // 按位置插入的值落在同样的列上
+        db.execute_single_sql("INSERT INTO events VALUES (9, 2, 'm2', 'd2', 'c2', 5)")
+            .unwrap();
+        let (_, rows) = select_all(db, "events");
+        assert_eq!(
+            rows[1],
+            vec![
+                Value::Int(9),
+                Value::Int(2),
+                Value::String("m2".to_string()),
+                Value::String("d2".to_string()),
+                Value::String("c2".to_string()),
+                Value::Int(5),
+            ]
+        );
+    }
+}
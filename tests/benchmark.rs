@@ -1,4 +1,8 @@
-use simple_db::{SimpleDB, DBConfig};
+use simple_db::storage::StorageEngine;
+use simple_db::storage::table::{ColumnDef, DataType, Value};
+use simple_db::SimpleDB;
+use simple_db::testing::{self, PERF_TABLE};
+use tempfile::TempDir;
 use std::time::{Duration, Instant};
 use std::fmt;
 
@@ -69,8 +73,6 @@ struct BenchmarkConfig {
     update_count: usize,
     /// 删除操作数量
     delete_count: usize,
-    /// 是否使用临时数据库
-    use_temp_db: bool,
 }
 
 impl Default for BenchmarkConfig {
@@ -80,7 +82,6 @@ impl Default for BenchmarkConfig {
             select_count: 50,
             update_count: 20,
             delete_count: 10,
-            use_temp_db: true,
         }
     }
 }
@@ -90,28 +91,28 @@ impl BenchmarkConfig {
     fn from_env() -> Self {
         let mut config = Self::default();
         
-        if let Ok(val) = std::env::var("BENCHMARK_INSERT_COUNT") {
-            if let Ok(count) = val.parse() {
-                config.insert_count = count;
-            }
+        if let Ok(val) = std::env::var("BENCHMARK_INSERT_COUNT")
+            && let Ok(count) = val.parse()
+        {
+            config.insert_count = count;
         }
         
-        if let Ok(val) = std::env::var("BENCHMARK_SELECT_COUNT") {
-            if let Ok(count) = val.parse() {
-                config.select_count = count;
-            }
+        if let Ok(val) = std::env::var("BENCHMARK_SELECT_COUNT")
+            && let Ok(count) = val.parse()
+        {
+            config.select_count = count;
         }
         
-        if let Ok(val) = std::env::var("BENCHMARK_UPDATE_COUNT") {
-            if let Ok(count) = val.parse() {
-                config.update_count = count;
-            }
+        if let Ok(val) = std::env::var("BENCHMARK_UPDATE_COUNT")
+            && let Ok(count) = val.parse()
+        {
+            config.update_count = count;
         }
         
-        if let Ok(val) = std::env::var("BENCHMARK_DELETE_COUNT") {
-            if let Ok(count) = val.parse() {
-                config.delete_count = count;
-            }
+        if let Ok(val) = std::env::var("BENCHMARK_DELETE_COUNT")
+            && let Ok(count) = val.parse()
+        {
+            config.delete_count = count;
         }
         
         config
@@ -122,33 +123,19 @@ impl BenchmarkConfig {
 struct DatabaseBenchmark {
     db: SimpleDB,
     config: BenchmarkConfig,
+    /// 数据库所在的临时目录，测试结束时删除
+    _temp_dir: TempDir,
 }
 
 impl DatabaseBenchmark {
     fn new(config: BenchmarkConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        let db_config = if config.use_temp_db {
-            DBConfig {
-                sql_file: None,
-                base_dir: Some("data/benchmark".to_string()),
-                db_name: Some("benchmark_test".to_string()),
-                execute: None,
-                interactive: false,
-                verbose: false,
-            }
-        } else {
-            DBConfig {
-                sql_file: None,
-                base_dir: None,
-                db_name: None,
-                execute: None,
-                interactive: false,
-                verbose: false,
-            }
-        };
+        let (temp_dir, db) = testing::temp_db("benchmark_test")?;
 
-        let db = SimpleDB::with_config(db_config)?;
-        
-        Ok(Self { db, config })
+        Ok(Self {
+            db,
+            config,
+            _temp_dir: temp_dir,
+        })
     }
 
     /// 运行完整的基准测试
@@ -180,20 +167,8 @@ impl DatabaseBenchmark {
     fn setup_test_environment(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         println!("正在设置测试环境...");
         
-        // 删除可能存在的测试表
-        let _ = self.db.execute_single_sql("DROP TABLE IF EXISTS benchmark_table");
-        
         // 创建测试表
-        let create_table_sql = "
-            CREATE TABLE benchmark_table (
-                id INT PRIMARY KEY,
-                name VARCHAR(50),
-                age INT,
-                email VARCHAR(100)
-            )
-        ";
-        
-        self.db.execute_single_sql(create_table_sql)?;
+        testing::create_perf_table(&mut self.db)?;
         println!("测试表创建完成");
         
         Ok(())
@@ -203,29 +178,17 @@ impl DatabaseBenchmark {
     fn benchmark_inserts(&mut self) -> Result<LatencyStats, Box<dyn std::error::Error>> {
         println!("开始插入基准测试...");
         let mut stats = LatencyStats::new();
-        
-        for i in 0..self.config.insert_count {
-            let sql = format!(
-                "INSERT INTO benchmark_table VALUES ({}, 'user{}', {}, 'user{}@example.com')",
-                i + 1,
-                i + 1,
-                20 + (i % 50),
-                i + 1
-            );
-            
-            let start = Instant::now();
-            self.db.execute_single_sql(&sql)?;
-            let latency = start.elapsed();
-            
-            stats.add(latency);
-            
-            if (i + 1) % 100 == 0 {
-                print!("\r插入进度: {}/{}", i + 1, self.config.insert_count);
-                std::io::Write::flush(&mut std::io::stdout()).unwrap();
-            }
-        }
-        
-        println!("\n插入测试完成");
+
+        // 所有行一次写入，只能得到每行的平均耗时
+        let start = Instant::now();
+        testing::populate_table(&mut self.db, self.config.insert_count)?;
+        let total = start.elapsed();
+        stats.count = self.config.insert_count;
+        stats.total = total;
+        stats.min = total / self.config.insert_count.max(1) as u32;
+        stats.max = stats.min;
+
+        println!("插入测试完成");
         Ok(stats)
     }
 
@@ -237,9 +200,9 @@ impl DatabaseBenchmark {
         for i in 0..self.config.select_count {
             // 随机查询不同类型的操作
             let sql = match i % 3 {
-                0 => format!("SELECT * FROM benchmark_table WHERE id = {}", (i % self.config.insert_count) + 1),
-                1 => "SELECT * FROM benchmark_table WHERE age > 30".to_string(),
-                _ => "SELECT name, email FROM benchmark_table WHERE age < 40".to_string(),
+                0 => format!("SELECT * FROM {} WHERE id = {}", PERF_TABLE, (i % self.config.insert_count) + 1),
+                1 => format!("SELECT * FROM {} WHERE score > 30", PERF_TABLE),
+                _ => format!("SELECT name, score FROM {} WHERE score < 40", PERF_TABLE),
             };
             
             let start = Instant::now();
@@ -266,7 +229,8 @@ impl DatabaseBenchmark {
         for i in 0..self.config.update_count {
             let id = (i % self.config.insert_count) + 1;
             let sql = format!(
-                "UPDATE benchmark_table SET age = {} WHERE id = {}",
+                "UPDATE {} SET score = {} WHERE id = {}",
+                PERF_TABLE,
                 30 + (i % 40),
                 id
             );
@@ -295,7 +259,7 @@ impl DatabaseBenchmark {
         for i in 0..self.config.delete_count {
             // 删除最后添加的记录
             let id = self.config.insert_count - i;
-            let sql = format!("DELETE FROM benchmark_table WHERE id = {}", id);
+            let sql = format!("DELETE FROM {} WHERE id = {}", PERF_TABLE, id);
             
             let start = Instant::now();
             self.db.execute_single_sql(&sql)?;
@@ -367,3 +331,227 @@ fn test_database_benchmark() -> Result<(), Box<dyn std::error::Error>> {
     
     Ok(())
 }
+
+/// 建立几个各有许多脏页的数据库，用于保存的基准测试
+fn build_dirty_databases(
+    base_dir: &std::path::Path,
+    databases: usize,
+    rows: i32,
+) -> Result<StorageEngine, Box<dyn std::error::Error>> {
+    let column = |name: &str, data_type: DataType| ColumnDef {
+        name: name.to_string(),
+        data_type,
+        not_null: false,
+        unique: false,
+        is_primary: false,
+        auto_increment: false,
+        default: None,
+        references: None,
+    };
+    let mut storage = StorageEngine::new(Some(base_dir), Some("save_bench_0"))?;
+    for i in 0..databases {
+        let name = format!("save_bench_{}", i);
+        if i > 0 {
+            storage.create_database(name.clone())?;
+        }
+        storage.use_database(&name)?;
+        storage.create_table(
+            "t".to_string(),
+            vec![
+                column("id", DataType::Int(None)),
+                column("body", DataType::Varchar(Some(200))),
+            ],
+        )?;
+        let rows = (0..rows)
+            .map(|id| vec![Value::Int(id), Value::String(format!("{:0>150}", id))])
+            .collect();
+        storage.insert_records("t", rows)?;
+    }
+    Ok(storage)
+}
+
+#[test]
+fn test_save_benchmark() -> Result<(), Box<dyn std::error::Error>> {
+    let databases = std::env::var("BENCHMARK_SAVE_DATABASES")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(4);
+    let rows = std::env::var("BENCHMARK_SAVE_ROWS")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(2000);
+
+    // 逐个数据库保存
+    let sequential_dir = tempfile::TempDir::new()?;
+    let mut storage = build_dirty_databases(sequential_dir.path(), databases, rows)?;
+    let start = Instant::now();
+    for name in storage.list_databases() {
+        storage.get_database_mut(&name)?.save()?;
+    }
+    let sequential = start.elapsed();
+
+    // StorageEngine::save 在多个线程中同时写入各数据库的数据页
+    let parallel_dir = tempfile::TempDir::new()?;
+    let mut storage = build_dirty_databases(parallel_dir.path(), databases, rows)?;
+    let start = Instant::now();
+    storage.save()?;
+    let parallel = start.elapsed();
+
+    println!(
+        "=== 保存基准测试（{} 个数据库，每个 {} 行）===",
+        databases, rows
+    );
+    println!("  逐个保存: {:.2}ms", sequential.as_secs_f64() * 1000.0);
+    println!("  并行保存: {:.2}ms", parallel.as_secs_f64() * 1000.0);
+    Ok(())
+}
+
+#[test]
+fn test_column_pruning_benchmark() -> Result<(), Box<dyn std::error::Error>> {
+    let rows = std::env::var("BENCHMARK_PRUNING_ROWS")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(5000);
+
+    // 30 列的宽表，一半整数一半字符串
+    let columns = (0..30)
+        .map(|i| ColumnDef {
+            name: format!("c{}", i),
+            data_type: if i % 2 == 0 {
+                DataType::Int(None)
+            } else {
+                DataType::Varchar(Some(40))
+            },
+            not_null: false,
+            unique: false,
+            is_primary: false,
+            auto_increment: false,
+            default: None,
+            references: None,
+        })
+        .collect();
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("pruning_bench"))?;
+    storage.create_table("wide".to_string(), columns)?;
+    let data = (0..rows)
+        .map(|id| {
+            (0..30)
+                .map(|i| match i % 2 {
+                    0 => Value::Int(id * 30 + i),
+                    _ => Value::String(format!("value {} of row {}", i, id)),
+                })
+                .collect()
+        })
+        .collect();
+    storage.insert_records("wide", data)?;
+
+    // 整行读取后再取出一列（裁剪之前的做法）与只读取需要的列
+    let start = Instant::now();
+    let full: Vec<Value> = storage
+        .get_all_records("wide")?
+        .iter()
+        .map(|record| record.values()[3].clone())
+        .collect();
+    let full_elapsed = start.elapsed();
+    let start = Instant::now();
+    let pruned: Vec<Value> = storage
+        .get_records_projected("wide", &[3])?
+        .into_iter()
+        .map(|record| record.values()[0].clone())
+        .collect();
+    let pruned_elapsed = start.elapsed();
+    assert_eq!(full, pruned);
+
+    println!("=== 列裁剪基准测试（30 列，{} 行，读取 1 列）===", rows);
+    println!("  读取整行: {:.2}ms", full_elapsed.as_secs_f64() * 1000.0);
+    println!(
+        "  只读取用到的列: {:.2}ms",
+        pruned_elapsed.as_secs_f64() * 1000.0
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sequential_scan_benchmark() -> Result<(), Box<dyn std::error::Error>> {
+    let rows = std::env::var("BENCHMARK_SCAN_ROWS")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(100_000);
+
+    let column = |name: &str, data_type: DataType| ColumnDef {
+        name: name.to_string(),
+        data_type,
+        not_null: false,
+        unique: false,
+        is_primary: false,
+        auto_increment: false,
+        default: None,
+        references: None,
+    };
+    let temp_dir = tempfile::TempDir::new()?;
+    let mut storage = StorageEngine::new(Some(temp_dir.path()), Some("scan_bench"))?;
+    storage.create_table(
+        "t".to_string(),
+        vec![
+            column("id", DataType::Int(None)),
+            column("name", DataType::Varchar(Some(40))),
+        ],
+    )?;
+    let data = (0..rows)
+        .map(|id| vec![Value::Int(id), Value::String(format!("name {}", id))])
+        .collect();
+    storage.insert_records("t", data)?;
+    storage.save()?;
+    drop(storage);
+
+    // 每次都重新打开数据库，从空的缓冲池开始读取
+    // 逐页读取（预读之前的做法）：每个页面一次磁盘读取
+    let storage = StorageEngine::new(Some(temp_dir.path()), Some("scan_bench"))?;
+    let start = Instant::now();
+    let page_ids = storage.get_table("t")?.page_ids().to_vec();
+    let mut by_page = Vec::new();
+    {
+        let database = storage.current_database()?;
+        let mut buffer_manager = database.get_buffer_manager();
+        for page_id in &page_ids {
+            let page = buffer_manager.get_page(*page_id)?;
+            by_page.extend(page.iter_records().map(|(_, record)| record));
+        }
+    }
+    let by_page_elapsed = start.elapsed();
+    drop(storage);
+
+    // 顺序扫描：成批预读相邻页面，逐条交给调用方
+    let storage = StorageEngine::new(Some(temp_dir.path()), Some("scan_bench"))?;
+    let start = Instant::now();
+    let mut scanned = 0;
+    storage.scan_records("t", None, |record| {
+        assert_eq!(record.values(), by_page[scanned].values());
+        scanned += 1;
+        Ok(())
+    })?;
+    let scan_elapsed = start.elapsed();
+    assert_eq!(scanned, rows as usize);
+    let file_reads = storage
+        .current_database()?
+        .get_buffer_manager()
+        .file_reads();
+
+    println!(
+        "=== 顺序扫描基准测试（{} 行，{} 个页面）===",
+        rows,
+        page_ids.len()
+    );
+    println!(
+        "  逐页读取: {:.2}ms，{:.0} 行/秒",
+        by_page_elapsed.as_secs_f64() * 1000.0,
+        rows as f64 / by_page_elapsed.as_secs_f64()
+    );
+    println!(
+        "  预读扫描: {:.2}ms，{:.0} 行/秒，读取数据文件 {} 次",
+        scan_elapsed.as_secs_f64() * 1000.0,
+        rows as f64 / scan_elapsed.as_secs_f64(),
+        file_reads
+    );
+    Ok(())
+}
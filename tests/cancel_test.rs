@@ -0,0 +1,104 @@
+use simple_db::SimpleDB;
+use simple_db::error::DBError;
+use simple_db::executor::QueryResult;
+use simple_db::observer::StatementEvent;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn create_numbers(db: &mut SimpleDB, count: i32) {
+    db.execute_single_sql("CREATE TABLE nums (id INT PRIMARY KEY, n INT)")
+        .unwrap();
+    let rows = (0..count)
+        .map(|id| vec![Value::Int(id), Value::Int(id % 10)])
+        .collect();
+    db.bulk_insert("nums", rows).unwrap();
+}
+
+fn count(db: &mut SimpleDB, sql: &str) -> Value {
+    match db.execute_single_sql(sql).unwrap() {
+        QueryResult::ResultSet(rs) => rs.rows[0][0].clone(),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_cancel_running_select_from_another_thread() {
+    let (_temp_dir, mut db) = testing::temp_db("cancel_db").unwrap();
+    create_numbers(&mut db, 500);
+
+    let flag = Arc::new(AtomicBool::new(false));
+    db.session_mut().set_cancel_flag(Some(Arc::clone(&flag)));
+
+    // 语句开始执行后由另一个线程置位取消标志
+    let (started_tx, started_rx) = mpsc::channel();
+    db.set_statement_observer(Box::new(move |event| {
+        if let StatementEvent::Started { .. } = event {
+            let _ = started_tx.send(());
+        }
+    }));
+    let canceller = {
+        let flag = Arc::clone(&flag);
+        thread::spawn(move || {
+            started_rx.recv().unwrap();
+            thread::sleep(Duration::from_millis(50));
+            flag.store(true, Ordering::Relaxed);
+        })
+    };
+
+    // 三张表的连接有一亿多种组合，不取消的话要执行很久
+    let started = Instant::now();
+    let err = db
+        .execute_single_sql(
+            "SELECT COUNT(*) FROM nums a JOIN nums b ON a.n = b.n JOIN nums c ON b.id <> c.id",
+        )
+        .unwrap_err();
+    canceller.join().unwrap();
+    assert!(matches!(err, DBError::Cancelled(_)), "{:?}", err);
+    assert!(err.to_string().starts_with("查询已取消"), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(30));
+
+    // 复位后可以继续执行
+    db.clear_statement_observer();
+    flag.store(false, Ordering::Relaxed);
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM nums"), Value::Int(500));
+}
+
+#[test]
+fn test_cancelled_dml_before_any_row() {
+    let (_temp_dir, mut db) = testing::temp_db("cancel_db").unwrap();
+    create_numbers(&mut db, 100);
+
+    let flag = Arc::new(AtomicBool::new(false));
+    db.session_mut().set_cancel_flag(Some(Arc::clone(&flag)));
+    {
+        let flag = Arc::clone(&flag);
+        thread::spawn(move || flag.store(true, Ordering::Relaxed))
+            .join()
+            .unwrap();
+    }
+
+    // 标志在语句开始前已经置位：一行也不处理
+    for sql in [
+        "UPDATE nums SET n = n + 1",
+        "DELETE FROM nums WHERE n = 3",
+        "INSERT INTO nums VALUES (100, 0), (101, 1)",
+        "INSERT IGNORE INTO nums SELECT id + 1000, n FROM nums",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err();
+        assert_eq!(err.to_string(), "查询已取消", "{}", sql);
+        assert!(!err.to_string().contains("已修改"), "{}", sql);
+    }
+
+    flag.store(false, Ordering::Relaxed);
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM nums"), Value::Int(100));
+    assert_eq!(count(&mut db, "SELECT SUM(n) FROM nums"), Value::Int(450));
+
+    // 卸下标志后不再检查
+    flag.store(true, Ordering::Relaxed);
+    db.session_mut().set_cancel_flag(None);
+    db.execute_single_sql("UPDATE nums SET n = 0").unwrap();
+}
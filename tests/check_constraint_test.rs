@@ -0,0 +1,175 @@
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+
+fn error_message(db: &mut SimpleDB, sql: &str) -> String {
+    match db.execute_single_sql(sql) {
+        Ok(result) => panic!("预期 {} 失败，实际为 {:?}", sql, result),
+        Err(e) => e.to_string(),
+    }
+}
+
+fn create_products(db: &mut SimpleDB) {
+    db.execute_single_sql(
+        "CREATE TABLE products (
+             id INT PRIMARY KEY,
+             price INT CHECK (price > 0),
+             discount INT,
+             CONSTRAINT discount_le_price CHECK (discount <= price)
+         )",
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_check_enforced_on_insert() {
+    let (_temp_dir, mut db) = testing::temp_db("check_db").unwrap();
+    create_products(&mut db);
+
+    db.execute_single_sql("INSERT INTO products VALUES (1, 10, 5)")
+        .unwrap();
+    let message = error_message(&mut db, "INSERT INTO products VALUES (2, 0, 0)");
+    assert_eq!(
+        message,
+        "Check constraint 'products_chk_1' is violated: price > 0"
+    );
+
+    // 多行插入中任何一行违反约束都不写入，错误中带有行号
+    let message = error_message(&mut db, "INSERT INTO products VALUES (3, 5, 1), (4, 5, 9)");
+    assert!(
+        message.contains("Check constraint 'discount_le_price' is violated: discount <= price"),
+        "{}",
+        message
+    );
+    assert!(message.contains("第 2 行"), "{}", message);
+
+    // IGNORE 跳过违反约束的行
+    db.execute_single_sql("INSERT IGNORE INTO products VALUES (5, -1, 0), (6, 3, 1)")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT id FROM products ORDER BY id"),
+        vec![vec![Value::Int(1)], vec![Value::Int(6)]]
+    );
+}
+
+#[test]
+fn test_check_enforced_on_update() {
+    let (_temp_dir, mut db) = testing::temp_db("check_db").unwrap();
+    create_products(&mut db);
+    db.execute_single_sql("INSERT INTO products VALUES (1, 10, 5), (2, 20, 0)")
+        .unwrap();
+
+    let message = error_message(&mut db, "UPDATE products SET price = -5 WHERE id = 1");
+    assert!(message.contains("products_chk_1"), "{}", message);
+    // 新值一起检查，任何一行违反约束都不修改
+    let message = error_message(&mut db, "UPDATE products SET discount = discount + 8");
+    assert!(message.contains("discount_le_price"), "{}", message);
+    assert_eq!(
+        query_rows(&mut db, "SELECT price, discount FROM products ORDER BY id"),
+        vec![
+            vec![Value::Int(10), Value::Int(5)],
+            vec![Value::Int(20), Value::Int(0)],
+        ]
+    );
+
+    db.execute_single_sql("UPDATE products SET price = 6 WHERE id = 1")
+        .unwrap();
+}
+
+#[test]
+fn test_null_satisfies_check() {
+    let (_temp_dir, mut db) = testing::temp_db("check_db").unwrap();
+    create_products(&mut db);
+
+    // 条件为 NULL 时视为满足
+    db.execute_single_sql("INSERT INTO products VALUES (1, NULL, NULL), (2, 4, NULL)")
+        .unwrap();
+    db.execute_single_sql("UPDATE products SET price = NULL, discount = 100 WHERE id = 2")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT COUNT(*) FROM products"),
+        vec![vec![Value::Int(2)]]
+    );
+
+    // IS NOT NULL 之类的条件可以要求非空
+    db.execute_single_sql("CREATE TABLE tags (name VARCHAR(10) CHECK (name IS NOT NULL))")
+        .unwrap();
+    let message = error_message(&mut db, "INSERT INTO tags VALUES (NULL)");
+    assert!(message.contains("tags_chk_1"), "{}", message);
+}
+
+#[test]
+fn test_check_definitions() {
+    let (_temp_dir, mut db) = testing::temp_db("check_db").unwrap();
+
+    let cases = [
+        (
+            "CREATE TABLE t (a INT CHECK (b > 0))",
+            "Check constraint 't_chk_1' refers to non-existing column 'b'.",
+        ),
+        (
+            "CREATE TABLE t (a INT AUTO_INCREMENT PRIMARY KEY, CHECK (a > 0))",
+            "Check constraint 't_chk_1' cannot refer to an auto-increment column.",
+        ),
+        (
+            "CREATE TABLE t (a INT CHECK (a IN (SELECT 1)))",
+            "An expression of check constraint 't_chk_1' contains disallowed function.",
+        ),
+        (
+            "CREATE TABLE t (a INT, CONSTRAINT c1 CHECK (a > 0), CONSTRAINT c1 CHECK (a < 9))",
+            "Duplicate check constraint name 'c1'.",
+        ),
+    ];
+    for (sql, expected) in cases {
+        assert_eq!(error_message(&mut db, sql), expected, "{}", sql);
+        assert!(db.execute_single_sql("SELECT * FROM t").is_err(), "{}", sql);
+    }
+
+    // 约束名在整个数据库中不能重复，失败时整张表不创建
+    create_products(&mut db);
+    let message = error_message(
+        &mut db,
+        "CREATE TABLE t (a INT, CONSTRAINT discount_le_price CHECK (a > 0))",
+    );
+    assert_eq!(
+        message,
+        "Duplicate check constraint name 'discount_le_price'."
+    );
+    assert!(db.execute_single_sql("SELECT * FROM t").is_err());
+
+    // 被约束引用的列不能删除
+    let message = error_message(&mut db, "ALTER TABLE products DROP COLUMN discount");
+    assert!(
+        message.starts_with("Check constraint 'discount_le_price' uses column 'discount'"),
+        "{}",
+        message
+    );
+    db.execute_single_sql("ALTER TABLE products ADD COLUMN note VARCHAR(10)")
+        .unwrap();
+    db.execute_single_sql("ALTER TABLE products DROP COLUMN note")
+        .unwrap();
+}
+
+#[test]
+fn test_checks_survive_reopen() {
+    let (temp_dir, mut db) = testing::temp_db("check_db").unwrap();
+    create_products(&mut db);
+    db.save().unwrap();
+    drop(db);
+
+    let mut db = testing::open_db(temp_dir.path(), "check_db").unwrap();
+    let message = error_message(&mut db, "INSERT INTO products VALUES (1, 5, 6)");
+    assert!(message.contains("discount_le_price"), "{}", message);
+    let rows = query_rows(&mut db, "SHOW CREATE TABLE products");
+    let statement = rows[0][1].to_string();
+    assert!(
+        statement.contains("CONSTRAINT `products_chk_1` CHECK (price > 0)"),
+        "{}",
+        statement
+    );
+    assert!(
+        statement.contains("CONSTRAINT `discount_le_price` CHECK (discount <= price)"),
+        "{}",
+        statement
+    );
+}
@@ -0,0 +1,182 @@
+use simple_db::SimpleDB;
+use simple_db::error::DBError;
+use simple_db::session::WarningLevel;
+use simple_db::testing::{self, query_rows};
+use tempfile::TempDir;
+
+fn setup() -> (SimpleDB, TempDir) {
+    let (temp_dir, mut db) = testing::temp_db("lint_db").unwrap();
+    db.execute_single_sql("CREATE TABLE users (id INT, name VARCHAR(20), age INT)")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO users VALUES (1, 'alice', 30), (2, 'bob', 17)")
+        .unwrap();
+    (db, temp_dir)
+}
+
+#[test]
+fn test_check_reports_each_statement_without_executing() {
+    let (mut db, _temp_dir) = setup();
+    let before = query_rows(&mut db, "SELECT * FROM users");
+
+    let summaries = db
+        .check_sql(
+            "SELECT name FROM users WHERE age > 18 ORDER BY id;
+             SELECT nickname FROM users;
+             INSERT INTO users VALUES (3, 'carol', 'old');
+             SELECT name FROM users WHERE name > 5;
+             CREATE TABLE logs (id INT, msg VARCHAR(100));
+             INSERT INTO logs (id, msg) VALUES (1, 'started');
+             DELETE FROM users WHERE age < 18;",
+        )
+        .unwrap();
+
+    let kinds: Vec<&str> = summaries.iter().map(|s| s.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            "SELECT",
+            "SELECT",
+            "INSERT",
+            "SELECT",
+            "CREATE TABLE",
+            "INSERT",
+            "DELETE"
+        ]
+    );
+    let failed: Vec<bool> = summaries.iter().map(|s| !s.is_ok()).collect();
+    assert_eq!(failed, [false, true, true, true, false, false, false]);
+
+    assert_eq!(summaries[0].tables, ["users"]);
+    assert_eq!(summaries[0].columns, ["name", "age", "id"]);
+    assert!(summaries[0].warnings.is_empty());
+
+    // 未知列
+    let error = summaries[1].error.as_ref().unwrap();
+    assert!(matches!(error, DBError::Planner(_)));
+    assert!(error.to_string().contains("'nickname'"), "{}", error);
+    // 插入值与列类型不符
+    let error = summaries[2].error.as_ref().unwrap();
+    assert!(matches!(error, DBError::Schema(_)), "{}", error);
+    // 字符串与数值比较
+    let error = summaries[3].error.as_ref().unwrap();
+    assert!(error.to_string().contains("VARCHAR 和 INT"), "{}", error);
+
+    // 脚本中新建的表对后续语句可见
+    assert_eq!(summaries[5].tables, ["logs"]);
+    assert_eq!(summaries[5].columns, ["id", "msg"]);
+
+    // 检查不改变任何数据
+    assert_eq!(query_rows(&mut db, "SELECT * FROM users"), before);
+    assert!(db.execute_single_sql("SELECT * FROM logs").is_err());
+}
+
+#[test]
+fn test_check_reports_warnings_and_planner_errors() {
+    let (db, _temp_dir) = setup();
+
+    let summaries = db
+        .check_sql(
+            "DROP TABLE IF EXISTS missing;
+             SELECT name FROM users ORDER BY nickname;
+             SELECT name, COUNT(*) FROM users;
+             SELECT SUM(name) FROM users;
+             INSERT IGNORE INTO users VALUES (3, 'carol', 'old'), (4, 'dave', 40);
+             SELECT age, COUNT(*) AS n FROM users GROUP BY age HAVING n > 1;",
+        )
+        .unwrap();
+    assert_eq!(summaries.len(), 6);
+
+    let warning = &summaries[0].warnings[..];
+    assert!(summaries[0].is_ok());
+    assert_eq!(warning.len(), 1);
+    assert_eq!(
+        (warning[0].level, warning[0].code),
+        (WarningLevel::Note, 1051)
+    );
+
+    // 找不到的 ORDER BY 列与执行时一样报错
+    let error = summaries[1].error.as_ref().unwrap();
+    assert_eq!(error.to_string(), "排序列 'nickname' 不存在");
+
+    // 聚合规则
+    let error = summaries[2].error.as_ref().unwrap();
+    assert!(error.to_string().contains("GROUP BY"), "{}", error);
+    let error = summaries[3].error.as_ref().unwrap();
+    assert!(error.to_string().contains("SUM"), "{}", error);
+
+    // INSERT IGNORE 跳过类型不符的行
+    assert!(summaries[4].is_ok());
+    assert_eq!(summaries[4].warnings.len(), 1);
+    assert!(summaries[4].warnings[0].message.contains("第 1 行"));
+
+    // HAVING 可以引用别名
+    assert!(summaries[5].is_ok(), "{:?}", summaries[5].error);
+
+    // 无法解析的脚本整体报错
+    assert!(db.check_sql("SELEC name FROM users").is_err());
+}
+
+#[test]
+fn test_check_update_set_expressions() {
+    let (db, _temp_dir) = setup();
+
+    let summaries = db
+        .check_sql(
+            "UPDATE users SET age = age + 1 WHERE id = 1;
+             UPDATE users SET name = age;
+             UPDATE users SET age = nickname;
+             UPDATE users SET age = 2 * 3;",
+        )
+        .unwrap();
+    let failed: Vec<bool> = summaries.iter().map(|s| !s.is_ok()).collect();
+    assert_eq!(failed, [false, true, true, false]);
+
+    assert_eq!(summaries[0].columns, ["age", "id"]);
+    let error = summaries[1].error.as_ref().unwrap();
+    assert!(error.to_string().contains("INT"), "{}", error);
+    let error = summaries[2].error.as_ref().unwrap();
+    assert!(error.to_string().contains("'nickname'"), "{}", error);
+}
+
+#[test]
+fn test_check_insert_select() {
+    let (db, _temp_dir) = setup();
+
+    let summaries = db
+        .check_sql(
+            "CREATE TABLE adults (id INT, name VARCHAR(20));
+             INSERT INTO adults SELECT id, name FROM users WHERE age >= 18;
+             INSERT INTO adults SELECT * FROM users;
+             INSERT INTO adults (name) SELECT nickname FROM users;",
+        )
+        .unwrap();
+    let failed: Vec<bool> = summaries.iter().map(|s| !s.is_ok()).collect();
+    assert_eq!(failed, [false, false, true, true]);
+
+    assert_eq!(summaries[1].tables, ["adults", "users"]);
+    let error = summaries[2].error.as_ref().unwrap();
+    assert!(error.to_string().contains("列数(3)"), "{}", error);
+    let error = summaries[3].error.as_ref().unwrap();
+    assert!(error.to_string().contains("'nickname'"), "{}", error);
+}
+
+#[test]
+fn test_check_string_functions() {
+    let (db, _temp_dir) = setup();
+
+    let summaries = db
+        .check_sql(
+            "SELECT UPPER(name), LENGTH(id) FROM users WHERE SUBSTR(name, 1, 1) = 'a';
+             SELECT LOWER(age) FROM users;
+             SELECT SUBSTR(name, 'x') FROM users;
+             SELECT UPPER(nickname) FROM users;",
+        )
+        .unwrap();
+    let failed: Vec<bool> = summaries.iter().map(|s| !s.is_ok()).collect();
+    assert_eq!(failed, [false, true, true, true]);
+
+    let error = summaries[1].error.as_ref().unwrap();
+    assert_eq!(error.to_string(), "LOWER 只能用于字符串，不能用于 INT");
+    let error = summaries[3].error.as_ref().unwrap();
+    assert!(error.to_string().contains("'nickname'"), "{}", error);
+}
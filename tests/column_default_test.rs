@@ -0,0 +1,149 @@
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+
+fn text(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn test_insert_uses_column_defaults() {
+    let (_temp_dir, mut db) = testing::temp_db("default_db").unwrap();
+    db.execute_single_sql(
+        "CREATE TABLE t (id INT, status VARCHAR(10) NOT NULL DEFAULT 'new', qty INT DEFAULT 1)",
+    )
+    .unwrap();
+
+    // 省略的列、DEFAULT 关键字都取默认值；NOT NULL 列有默认值时可以省略
+    db.execute_single_sql("INSERT INTO t (id) VALUES (1)")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO t VALUES (2, DEFAULT, 5), (3, 'done', default)")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO t (qty, id) VALUES (DEFAULT, DEFAULT)")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT id, status, qty FROM t"),
+        vec![
+            vec![Value::Int(1), text("new"), Value::Int(1)],
+            vec![Value::Int(2), text("new"), Value::Int(5)],
+            vec![Value::Int(3), text("done"), Value::Int(1)],
+            vec![Value::Null, text("new"), Value::Int(1)],
+        ]
+    );
+
+    // 没有默认值的 NOT NULL 列仍然必须指定
+    db.execute_single_sql("CREATE TABLE u (id INT NOT NULL, v INT)")
+        .unwrap();
+    for sql in [
+        "INSERT INTO u (v) VALUES (1)",
+        "INSERT INTO u VALUES (DEFAULT, 1)",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Field 'id' doesn't have a default value",
+            "{}",
+            sql
+        );
+    }
+}
+
+#[test]
+fn test_defaults_in_schema_output() {
+    let (temp_dir, mut db) = testing::temp_db("default_db").unwrap();
+    db.execute_single_sql(
+        "CREATE TABLE t (id INT PRIMARY KEY, status VARCHAR(10) DEFAULT 'it''s', qty INT DEFAULT 0)",
+    )
+    .unwrap();
+    db.execute_single_sql("INSERT INTO t (id) VALUES (1), (2)")
+        .unwrap();
+
+    // 已有记录中新增列取默认值，有默认值的 NOT NULL 列也可以添加
+    db.execute_single_sql("ALTER TABLE t ADD COLUMN flag INT NOT NULL DEFAULT 7")
+        .unwrap();
+    assert!(
+        db.execute_single_sql("ALTER TABLE t ADD COLUMN code INT UNIQUE DEFAULT 1")
+            .is_err()
+    );
+    db.save().unwrap();
+    drop(db);
+
+    // 默认值随目录保存，重新打开后仍然可见
+    let mut db = testing::open_db(temp_dir.path(), "default_db").unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT flag FROM t"),
+        vec![vec![Value::Int(7)], vec![Value::Int(7)]]
+    );
+    let defaults: Vec<Value> = query_rows(&mut db, "DESCRIBE t")
+        .into_iter()
+        .map(|row| row[4].clone())
+        .collect();
+    assert_eq!(defaults, [Value::Null, text("it's"), text("0"), text("7")]);
+
+    let rows = query_rows(&mut db, "SHOW CREATE TABLE t");
+    let Value::String(statement) = &rows[0][1] else {
+        panic!("SHOW CREATE TABLE 应返回字符串");
+    };
+    assert!(
+        statement.contains("`status` VARCHAR(10) DEFAULT 'it''s'"),
+        "{}",
+        statement
+    );
+    assert!(
+        statement.contains("`flag` INT NOT NULL DEFAULT 7"),
+        "{}",
+        statement
+    );
+
+    // SHOW CREATE TABLE 的输出可以原样重建出同样的默认值
+    db.execute_single_sql(&statement.replace("CREATE TABLE `t`", "CREATE TABLE `copy`"))
+        .unwrap();
+    db.execute_single_sql("INSERT INTO copy (id) VALUES (1)")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT * FROM copy"),
+        vec![vec![
+            Value::Int(1),
+            text("it's"),
+            Value::Int(0),
+            Value::Int(7)
+        ]]
+    );
+}
+
+#[test]
+fn test_float_defaults_round_trip_in_exponent_form() {
+    let (_temp_dir, mut db) = testing::temp_db("default_db").unwrap();
+
+    db.execute_single_sql(
+        "CREATE TABLE f (id INT, tiny FLOAT DEFAULT 0.000001, huge FLOAT DEFAULT 1e40, \
+         neg FLOAT DEFAULT -2.5E-7)",
+    )
+    .unwrap();
+    let rows = query_rows(&mut db, "SHOW CREATE TABLE f");
+    let Value::String(statement) = &rows[0][1] else {
+        panic!("SHOW CREATE TABLE 应返回字符串");
+    };
+    // 很小和很大的浮点数按指数形式显示
+    for default in ["DEFAULT 1e-6", "DEFAULT 1e40", "DEFAULT -2.5e-7"] {
+        assert!(statement.contains(default), "{}", statement);
+    }
+
+    // 指数形式的字面量按浮点数解析，SHOW CREATE TABLE 的输出可以原样重建
+    db.execute_single_sql(&statement.replace("CREATE TABLE `f`", "CREATE TABLE `copy`"))
+        .unwrap();
+    db.execute_sql("INSERT INTO f (id) VALUES (1); INSERT INTO copy (id) VALUES (1);")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT * FROM copy"),
+        query_rows(&mut db, "SELECT * FROM f")
+    );
+    assert_eq!(
+        query_rows(&mut db, "SELECT 1e20, 2E3, 1.5e-3"),
+        vec![vec![
+            Value::Float(1e20),
+            Value::Float(2000.0),
+            Value::Float(0.0015)
+        ]]
+    );
+    assert!(db.execute_single_sql("SELECT 1e400").is_err());
+}
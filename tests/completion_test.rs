@@ -0,0 +1,164 @@
+use rustyline::Context;
+use rustyline::completion::Completer;
+use rustyline::history::DefaultHistory;
+use simple_db::SimpleDB;
+use simple_db::error::DBError;
+use simple_db::executor::QueryResult;
+use simple_db::helper::SQLHelper;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use tempfile::TempDir;
+
+/// 两个数据库：shop(users, orders) 和 blog(posts, users_archive)，当前库为 shop
+fn setup() -> (SimpleDB, TempDir) {
+    let (temp_dir, mut db) = testing::temp_db("shop").unwrap();
+    for sql in [
+        "CREATE TABLE users (id INT)",
+        "CREATE TABLE orders (id INT)",
+        "CREATE DATABASE blog",
+        "USE blog",
+        "CREATE TABLE posts (id INT)",
+        "CREATE TABLE users_archive (id INT)",
+        "USE shop",
+    ] {
+        db.execute_single_sql(sql).unwrap();
+    }
+    (db, temp_dir)
+}
+
+fn complete(helper: &SQLHelper, line: &str) -> (usize, Vec<String>) {
+    let history = DefaultHistory::new();
+    let (start, pairs) = helper
+        .complete(line, line.len(), &Context::new(&history))
+        .unwrap();
+    (
+        start,
+        pairs.into_iter().map(|pair| pair.replacement).collect(),
+    )
+}
+
+fn table_names(result: QueryResult, database: &str) -> Vec<String> {
+    match result {
+        QueryResult::ResultSet(rs) => {
+            assert_eq!(rs.columns, [format!("Tables_in_{}", database)]);
+            rs.rows
+                .into_iter()
+                .map(|row| match row.as_slice() {
+                    [Value::String(name)] => name.clone(),
+                    other => panic!("预期一列表名，实际为 {:?}", other),
+                })
+                .collect()
+        }
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_completion_follows_use_and_database_prefix() {
+    let (mut db, _temp_dir) = setup();
+    let mut helper = SQLHelper::new();
+    helper.set_schema(db.schema_snapshot());
+    assert_eq!(helper.schema().current.as_deref(), Some("shop"));
+
+    // 关键字和当前库的表名一起补全，表名不区分大小写
+    let (start, candidates) = complete(&helper, "SELECT * FROM u");
+    assert_eq!(start, "SELECT * FROM ".len());
+    assert!(candidates.contains(&"UPDATE".to_string()));
+    assert!(candidates.contains(&"users".to_string()));
+    assert!(!candidates.contains(&"users_archive".to_string()));
+    let (_, candidates) = complete(&helper, "select * from O");
+    assert!(candidates.contains(&"orders".to_string()));
+
+    // 库名加点之后补全该库的表
+    let (start, candidates) = complete(&helper, "SELECT * FROM blog.");
+    assert_eq!(start, "SELECT * FROM ".len());
+    assert_eq!(candidates, ["blog.posts", "blog.users_archive"]);
+    let (_, candidates) = complete(&helper, "SELECT * FROM blog.u");
+    assert_eq!(candidates, ["blog.users_archive"]);
+    let (_, candidates) = complete(&helper, "SELECT * FROM nowhere.u");
+    assert!(candidates.is_empty());
+
+    // 旧快照在 USE 之后仍指向 shop，刷新后补全 blog 的表
+    db.execute_single_sql("USE blog").unwrap();
+    let (_, candidates) = complete(&helper, "SELECT * FROM u");
+    assert!(candidates.contains(&"users".to_string()));
+    helper.set_schema(db.schema_snapshot());
+    let (_, candidates) = complete(&helper, "SELECT * FROM u");
+    assert!(candidates.contains(&"users_archive".to_string()));
+    assert!(!candidates.contains(&"users".to_string()));
+    let (_, candidates) = complete(&helper, "SELECT * FROM shop.");
+    assert_eq!(candidates, ["shop.orders", "shop.users"]);
+}
+
+#[test]
+fn test_show_tables_of_other_database() {
+    let (db, _temp_dir) = setup();
+
+    assert_eq!(
+        table_names(db.show_tables(None).unwrap(), "shop"),
+        ["orders", "users"]
+    );
+    assert_eq!(
+        table_names(db.show_tables(Some("blog")).unwrap(), "blog"),
+        ["posts", "users_archive"]
+    );
+    // 查看其他数据库不切换当前数据库
+    assert_eq!(db.schema_snapshot().current.as_deref(), Some("shop"));
+
+    assert!(matches!(
+        db.show_tables(Some("missing")),
+        Err(DBError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_completion_of_column_names() {
+    let (mut db, _temp_dir) = setup();
+    db.execute_single_sql(
+        "CREATE TABLE customers (id INT, name VARCHAR(20), nickname VARCHAR(20))",
+    )
+    .unwrap();
+    db.execute_single_sql("ALTER TABLE orders ADD COLUMN note VARCHAR(20)")
+        .unwrap();
+    let mut helper = SQLHelper::new();
+    helper.set_schema(db.schema_snapshot());
+
+    // 选择列中补全行中 FROM 之后的表的列，光标之后的部分也计入
+    let line = "SELECT n FROM customers";
+    let pos = "SELECT n".len();
+    let history = DefaultHistory::new();
+    let (start, pairs) = helper.complete(line, pos, &Context::new(&history)).unwrap();
+    let candidates: Vec<String> = pairs.into_iter().map(|pair| pair.replacement).collect();
+    assert_eq!(start, "SELECT ".len());
+    assert!(candidates.contains(&"name".to_string()));
+    assert!(candidates.contains(&"nickname".to_string()));
+    assert!(candidates.contains(&"NOT".to_string()));
+    assert!(!candidates.contains(&"note".to_string()));
+
+    // WHERE / SET 之后补全列名，不区分大小写，不再补全表名
+    let (_, candidates) = complete(&helper, "SELECT * FROM customers WHERE NICK");
+    assert_eq!(candidates, ["nickname"]);
+    let (_, candidates) = complete(&helper, "UPDATE orders SET n");
+    assert!(candidates.contains(&"note".to_string()));
+    assert!(!candidates.contains(&"name".to_string()));
+    let (_, candidates) = complete(&helper, "SELECT * FROM customers WHERE o");
+    assert!(!candidates.contains(&"orders".to_string()));
+
+    // 多个表时合并去重，表名或别名加点之后只补全该表的列
+    let (_, candidates) = complete(&helper, "SELECT i FROM customers c JOIN orders o ON c.i");
+    assert_eq!(candidates, ["c.id"]);
+    let (_, candidates) = complete(
+        &helper,
+        "SELECT * FROM customers AS c, shop.orders WHERE orders.",
+    );
+    assert_eq!(candidates, ["orders.id", "orders.note"]);
+    let (_, candidates) = complete(&helper, "SELECT * FROM customers, orders WHERE i");
+    assert!(candidates.contains(&"id".to_string()));
+    assert_eq!(candidates.iter().filter(|c| *c == "id").count(), 1);
+
+    // FROM 之后仍然补全表名；表名未知时没有列可补全
+    let (_, candidates) = complete(&helper, "SELECT name FROM cu");
+    assert_eq!(candidates, ["customers"]);
+    let (_, candidates) = complete(&helper, "SELECT * FROM missing WHERE n");
+    assert!(!candidates.contains(&"name".to_string()));
+}
@@ -0,0 +1,90 @@
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::session::Session;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier, RwLock};
+use std::thread;
+use std::time::Duration;
+
+fn count_rows(db: &SimpleDB, session: &mut Session) -> Value {
+    let mut results = db
+        .execute_read_only("SELECT COUNT(*) FROM items", session)
+        .unwrap();
+    match results.remove(0).unwrap() {
+        QueryResult::ResultSet(rs) => rs.rows[0][0].clone(),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_concurrent_selects_block_writer() {
+    let (_temp_dir, mut db) = testing::temp_db("concurrent_db").unwrap();
+    db.execute_sql("CREATE TABLE items (id INT PRIMARY KEY, name VARCHAR(20))")
+        .unwrap();
+    let rows: Vec<String> = (0..500).map(|i| format!("({}, 'item{}')", i, i)).collect();
+    db.execute_single_sql(&format!("INSERT INTO items VALUES {}", rows.join(", ")))
+        .unwrap();
+    let db = Arc::new(RwLock::new(db));
+
+    // 两个读线程都持有读锁之后才开始查询，证明查询可以同时进行
+    let readers_ready = Arc::new(Barrier::new(3));
+    let release_readers = Arc::new(Barrier::new(3));
+    let readers: Vec<_> = (0..2)
+        .map(|_| {
+            let db = Arc::clone(&db);
+            let readers_ready = Arc::clone(&readers_ready);
+            let release_readers = Arc::clone(&release_readers);
+            thread::spawn(move || {
+                let db = db.read().unwrap();
+                let mut session = Session::new();
+                readers_ready.wait();
+                let counts: Vec<Value> = (0..20).map(|_| count_rows(&db, &mut session)).collect();
+                release_readers.wait();
+                counts
+            })
+        })
+        .collect();
+    readers_ready.wait();
+
+    // 写线程要等所有读线程释放读锁后才能写入
+    let written = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let db = Arc::clone(&db);
+        let written = Arc::clone(&written);
+        thread::spawn(move || {
+            let mut db = db.write().unwrap();
+            db.execute_single_sql("INSERT INTO items VALUES (500, 'late')")
+                .unwrap();
+            written.store(true, Ordering::SeqCst);
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert!(!written.load(Ordering::SeqCst));
+    release_readers.wait();
+
+    for reader in readers {
+        assert!(
+            reader
+                .join()
+                .unwrap()
+                .iter()
+                .all(|count| *count == Value::Int(500))
+        );
+    }
+    writer.join().unwrap();
+    assert!(written.load(Ordering::SeqCst));
+
+    // 写入完成后的查询看到新行；只读接口拒绝修改语句
+    let db = db.read().unwrap();
+    let mut session = Session::new();
+    assert_eq!(count_rows(&db, &mut session), Value::Int(501));
+    let results = db
+        .execute_read_only("DELETE FROM items WHERE id = 500", &mut session)
+        .unwrap();
+    assert!(matches!(
+        results[0],
+        Err(simple_db::error::DBError::Forbidden(_))
+    ));
+}
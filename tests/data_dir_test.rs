@@ -0,0 +1,87 @@
+use simple_db::error::DBError;
+use simple_db::storage::{DataDirEntry, SkippedDirectory, StorageEngine};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn open(dir: &Path) -> simple_db::error::Result<StorageEngine> {
+    StorageEngine::new(Some(dir), Some("app"))
+}
+
+fn config_error(dir: &Path) -> String {
+    match open(dir) {
+        Err(DBError::Config(message)) => message,
+        Err(e) => panic!("预期配置错误，实际为 {:?}", e),
+        Ok(_) => panic!("预期 {} 无法作为数据目录", dir.display()),
+    }
+}
+
+/// 以 root 运行时目录权限不起作用，这种情况下跳过权限相关的检查
+fn permissions_enforced(dir: &Path) -> bool {
+    let probe = dir.join("probe");
+    let writable = fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(probe);
+    !writable
+}
+
+#[test]
+fn test_data_dir_must_be_a_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let decoy = temp_dir.path().join("decoy");
+    fs::write(&decoy, "not a directory").unwrap();
+    let message = config_error(&decoy);
+    assert!(message.contains("是一个文件"), "{}", message);
+    assert_eq!(fs::read_to_string(&decoy).unwrap(), "not a directory");
+
+    // 不存在的目录（包括上级目录）在第一次运行时创建
+    let fresh = temp_dir.path().join("nested").join("data");
+    let storage = open(&fresh).unwrap();
+    assert!(fresh.join("app").join("data.db").is_file());
+    assert_eq!(
+        storage.databases(),
+        [DataDirEntry::Database("app".to_string())]
+    );
+}
+
+#[test]
+fn test_unrelated_directories_are_skipped() {
+    let temp_dir = TempDir::new().unwrap();
+    drop(open(temp_dir.path()).unwrap());
+    fs::create_dir_all(temp_dir.path().join("photos").join("2024")).unwrap();
+    fs::write(temp_dir.path().join("photos").join("cat.jpg"), "jpg").unwrap();
+    fs::write(temp_dir.path().join("notes.txt"), "notes").unwrap();
+
+    let storage = open(temp_dir.path()).unwrap();
+    assert_eq!(
+        storage.databases(),
+        [
+            DataDirEntry::Database("app".to_string()),
+            DataDirEntry::Skipped(SkippedDirectory {
+                name: "photos".to_string(),
+                reason: "没有数据文件或元数据文件".to_string(),
+            }),
+        ]
+    );
+    assert_eq!(storage.list_databases(), ["app"]);
+    // 跳过的目录不会被当作数据库写入任何文件
+    drop(storage);
+    assert!(!temp_dir.path().join("photos").join("data.db").exists());
+}
+
+#[test]
+fn test_read_only_data_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let read_only = temp_dir.path().join("read_only");
+    fs::create_dir(&read_only).unwrap();
+    fs::set_permissions(&read_only, fs::Permissions::from_mode(0o555)).unwrap();
+
+    if permissions_enforced(&read_only) {
+        let message = config_error(&read_only);
+        assert!(message.contains("没有权限写入数据目录"), "{}", message);
+        let message = config_error(&read_only.join("data"));
+        assert!(message.contains("没有权限创建数据目录"), "{}", message);
+    }
+    fs::set_permissions(&read_only, fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(open(&read_only).is_ok());
+}
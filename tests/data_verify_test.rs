@@ -0,0 +1,131 @@
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn verify_rows(db: &SimpleDB) -> (Vec<Vec<Value>>, usize) {
+    match db.verify() {
+        (QueryResult::ResultSet(rs), problems) => (rs.rows, problems),
+        (other, _) => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+/// 用旧的数据文件配上新的元数据：orders 多了一列，tags 的 name 列从 VARCHAR 变成了 INT
+fn stale_data_files(base_dir: &Path) {
+    let data_file = base_dir.join("shop").join("data.db");
+    let stale = base_dir.join("stale.db");
+
+    let mut db = testing::open_db(base_dir, "shop").unwrap();
+    db.execute_sql(
+        "CREATE TABLE orders (id INT PRIMARY KEY, amount INT);
+         INSERT INTO orders VALUES (1, 10), (2, 20), (3, 30);
+         CREATE TABLE tags (id INT, name VARCHAR(10));
+         INSERT INTO tags VALUES (1, 'a'), (2, NULL);
+         CREATE TABLE notes (id INT);
+         INSERT INTO notes VALUES (1);",
+    )
+    .unwrap();
+    db.save().unwrap();
+    drop(db);
+    std::fs::copy(&data_file, &stale).unwrap();
+
+    let mut db = testing::open_db(base_dir, "shop").unwrap();
+    db.execute_sql(
+        "ALTER TABLE orders ADD COLUMN note VARCHAR(10) DEFAULT 'x';
+         ALTER TABLE tags DROP COLUMN name, ADD COLUMN name INT;",
+    )
+    .unwrap();
+    db.save().unwrap();
+    drop(db);
+    std::fs::copy(&stale, &data_file).unwrap();
+}
+
+#[test]
+fn test_verify_reports_mismatched_records() {
+    let temp_dir = TempDir::new().unwrap();
+    stale_data_files(temp_dir.path());
+
+    // 不一致的数据库仍然可以打开，其余的表照常使用
+    let db = testing::open_db(temp_dir.path(), "shop").unwrap();
+    let (rows, problems) = verify_rows(&db);
+    assert_eq!(problems, 2, "{:?}", rows);
+    let row = |table: &str| {
+        rows.iter()
+            .find(|row| row[1] == Value::String(table.to_string()))
+            .unwrap_or_else(|| panic!("检查结果中没有表 {}", table))
+            .clone()
+    };
+
+    let orders = row("orders");
+    assert_eq!(
+        orders[2..6],
+        [Value::Int(3), Value::Int(3), Value::Int(0), Value::Int(0)]
+    );
+    assert!(
+        orders[6].to_string().contains("有 2 列，表结构有 3 列"),
+        "{:?}",
+        orders
+    );
+
+    // NULL 与任何列类型都相符，只有 'a' 一条记录有问题
+    let tags = row("tags");
+    assert_eq!(
+        tags[2..6],
+        [Value::Int(2), Value::Int(0), Value::Int(1), Value::Int(0)]
+    );
+    assert!(
+        tags[6]
+            .to_string()
+            .contains("列 'name' 是 INT，值 a 是 VARCHAR 类型"),
+        "{:?}",
+        tags
+    );
+
+    assert_eq!(row("notes")[6], Value::String("OK".to_string()));
+}
+
+#[test]
+fn test_queries_on_mismatched_records_fail_gracefully() {
+    let temp_dir = TempDir::new().unwrap();
+    stale_data_files(temp_dir.path());
+    let mut db = testing::open_db(temp_dir.path(), "shop").unwrap();
+
+    // 通配符、按列取值和只读取部分列的扫描都返回错误，而不是越界崩溃；
+    // 只在 WHERE 中用到缺失列的 UPDATE 和 DELETE 也报错，而不是当作没有匹配的行
+    for sql in [
+        "SELECT * FROM orders",
+        "SELECT note FROM orders",
+        "SELECT id FROM orders WHERE note = 'x'",
+        "SELECT COUNT(note) FROM orders",
+        "SELECT id, note FROM orders WHERE id = 1",
+        "UPDATE orders SET amount = 0 WHERE note = 'x'",
+        "DELETE FROM orders WHERE note = 'x'",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err();
+        assert!(
+            err.to_string().contains("数据文件与表结构不一致"),
+            "{}: {}",
+            sql,
+            err
+        );
+    }
+
+    // 存在的列仍然可以读取，失败的 UPDATE 和 DELETE 没有改动任何行
+    match db
+        .execute_single_sql("SELECT id, amount FROM orders ORDER BY id")
+        .unwrap()
+    {
+        QueryResult::ResultSet(rs) => assert_eq!(
+            rs.rows,
+            vec![
+                vec![Value::Int(1), Value::Int(10)],
+                vec![Value::Int(2), Value::Int(20)],
+                vec![Value::Int(3), Value::Int(30)],
+            ]
+        ),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+    assert!(db.execute_single_sql("SELECT * FROM notes").is_ok());
+}
@@ -0,0 +1,125 @@
+use simple_db::error::DBError;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+
+#[test]
+fn test_create_use_drop_round_trip() {
+    let (temp_dir, mut db) = testing::temp_db("main_db").unwrap();
+
+    db.execute_single_sql("CREATE DATABASE shop").unwrap();
+    db.execute_single_sql("USE shop").unwrap();
+    db.execute_single_sql("CREATE TABLE items (id INT, name VARCHAR(20))")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO items VALUES (1, 'pen')")
+        .unwrap();
+
+    // 表只属于 shop
+    db.execute_single_sql("USE main_db").unwrap();
+    assert!(db.execute_single_sql("SELECT * FROM items").is_err());
+    db.execute_single_sql("USE shop").unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT name FROM items"),
+        vec![vec![Value::String("pen".to_string())]]
+    );
+
+    // 删除当前数据库时目录一并删除
+    let shop_dir = temp_dir.path().join("shop");
+    db.save().unwrap();
+    assert!(shop_dir.is_dir());
+    db.execute_single_sql("DROP DATABASE shop").unwrap();
+    assert!(!shop_dir.exists());
+    assert!(matches!(
+        db.execute_single_sql("USE shop"),
+        Err(DBError::NotFound(_))
+    ));
+
+    // DROP SCHEMA 是 DROP DATABASE 的同义写法
+    db.execute_single_sql("CREATE SCHEMA archive").unwrap();
+    db.execute_single_sql("DROP SCHEMA archive").unwrap();
+    assert!(!temp_dir.path().join("archive").exists());
+
+    // 重新打开后被删除的数据库不会再出现
+    drop(db);
+    let mut db = testing::open_db(temp_dir.path(), "main_db").unwrap();
+    assert!(matches!(
+        db.execute_single_sql("USE shop"),
+        Err(DBError::NotFound(_))
+    ));
+    db.execute_single_sql("USE main_db").unwrap();
+}
+
+#[test]
+fn test_use_and_drop_unknown_database() {
+    let (_temp_dir, mut db) = testing::temp_db("main_db").unwrap();
+
+    assert!(matches!(
+        db.execute_single_sql("USE nonexistent"),
+        Err(DBError::NotFound(_))
+    ));
+    assert!(matches!(
+        db.execute_single_sql("DROP DATABASE nonexistent"),
+        Err(DBError::NotFound(_))
+    ));
+    // 失败的 USE 不改变当前数据库
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    db.execute_single_sql("USE main_db").unwrap();
+    assert!(query_rows(&mut db, "SELECT * FROM t").is_empty());
+}
+
+#[test]
+fn test_qualified_table_names() {
+    let (_temp_dir, mut db) = testing::temp_db("main_db").unwrap();
+
+    db.execute_single_sql("CREATE DATABASE other").unwrap();
+    db.execute_single_sql("CREATE TABLE other.t (id INT PRIMARY KEY, name VARCHAR(10))")
+        .unwrap();
+    db.execute_single_sql("CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(10))")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO `other`.`t` VALUES (1, 'a'), (2, 'b')")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO t VALUES (9, 'main')")
+        .unwrap();
+    db.execute_single_sql("UPDATE other.t SET name = 'x' WHERE id = 2")
+        .unwrap();
+    db.execute_single_sql("DELETE FROM other.t WHERE id = 1")
+        .unwrap();
+
+    // 限定名指向 other，同名的 main_db.t 不受影响，当前数据库也不变
+    assert_eq!(
+        query_rows(&mut db, "SELECT id, name FROM other.t"),
+        vec![vec![Value::Int(2), Value::String("x".to_string())]]
+    );
+    assert_eq!(
+        query_rows(&mut db, "SELECT id, name FROM t"),
+        vec![vec![Value::Int(9), Value::String("main".to_string())]]
+    );
+    assert_eq!(
+        query_rows(
+            &mut db,
+            "SELECT t.name, o.name FROM main_db.t JOIN other.t AS o ON t.id > o.id"
+        ),
+        vec![vec![
+            Value::String("main".to_string()),
+            Value::String("x".to_string())
+        ]]
+    );
+
+    // 限定的数据库不存在时报 NotFound
+    assert!(matches!(
+        db.execute_single_sql("SELECT * FROM missing.t"),
+        Err(DBError::NotFound(_))
+    ));
+    assert!(matches!(
+        db.execute_single_sql("INSERT INTO missing.t VALUES (1, 'a')"),
+        Err(DBError::NotFound(_))
+    ));
+
+    // 回滚撤销的是限定名所指数据库中的修改
+    db.execute_sql("BEGIN; INSERT INTO other.t VALUES (3, 'c'); ROLLBACK")
+        .unwrap();
+    assert_eq!(query_rows(&mut db, "SELECT id FROM other.t").len(), 1);
+
+    db.execute_single_sql("DROP TABLE other.t").unwrap();
+    assert!(db.execute_single_sql("SELECT * FROM other.t").is_err());
+    assert_eq!(query_rows(&mut db, "SELECT id FROM t").len(), 1);
+}
@@ -126,3 +126,8 @@ fn test_example_15() -> Result<(), Box<dyn std::error::Error>> {
 fn test_example_18() -> Result<(), Box<dyn std::error::Error>> {
     run_example_test(18)
 }
+
+#[test]
+fn test_example_19() -> Result<(), Box<dyn std::error::Error>> {
+    run_example_test(19)
+}
@@ -0,0 +1,83 @@
+use simple_db::error::DBError;
+use simple_db::executor::QueryResult;
+use simple_db::session::ExecutionLimits;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use simple_db::{DBConfig, SimpleDB};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn open_db(temp_dir: &TempDir, max_result_rows: usize) -> SimpleDB {
+    let config = DBConfig {
+        max_result_rows,
+        ..testing::config(temp_dir.path(), "limits_db")
+    };
+    let mut db = SimpleDB::with_config(config).expect("无法打开数据库");
+    db.execute_single_sql("CREATE TABLE t (id INT, name VARCHAR(10))")
+        .unwrap();
+    let values: Vec<String> = (1..=10).map(|i| format!("({}, 'n{}')", i, i)).collect();
+    db.execute_single_sql(&format!("INSERT INTO t VALUES {}", values.join(", ")))
+        .unwrap();
+    db
+}
+
+fn count(db: &mut SimpleDB) -> usize {
+    let previous = db.session().execution_limits();
+    db.session_mut()
+        .set_execution_limits(ExecutionLimits::default());
+    let rows = match db.execute_single_sql("SELECT id FROM t").unwrap() {
+        QueryResult::ResultSet(rs) => rs.rows.len(),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    };
+    db.session_mut().set_execution_limits(previous);
+    rows
+}
+
+#[test]
+fn test_row_limit_aborts_scans() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir, 5);
+
+    // 扫描行数超过限制时中止，不论最终返回多少行
+    for sql in [
+        "SELECT * FROM t",
+        "SELECT id FROM t WHERE id = 1",
+        "SELECT COUNT(*) FROM t",
+        "UPDATE t SET name = 'x' WHERE id > 8",
+        "DELETE FROM t WHERE id = 10",
+    ] {
+        match db.execute_single_sql(sql) {
+            Err(DBError::Execution(message)) => {
+                assert!(message.contains("扫描的行数超过限制"), "{}: {}", sql, message)
+            }
+            other => panic!("{} 应被中止，实际为 {:?}", sql, other),
+        }
+    }
+    // 过滤阶段就已中止，没有写入任何行
+    assert_eq!(count(&mut db), 10);
+    assert_eq!(db.session().execution_limits().max_rows, Some(5));
+
+    // 放宽限制后同样的语句正常执行；0 / 未设置表示不限制
+    db.session_mut().set_execution_limits(ExecutionLimits {
+        max_duration: Some(Duration::from_secs(60)),
+        max_rows: Some(10),
+    });
+    db.execute_single_sql("DELETE FROM t WHERE id = 10")
+        .unwrap();
+    db.execute_single_sql("UPDATE t SET name = 'x' WHERE id > 8")
+        .unwrap();
+    match db
+        .execute_single_sql("SELECT name FROM t WHERE id = 9")
+        .unwrap()
+    {
+        QueryResult::ResultSet(rs) => {
+            assert_eq!(rs.rows, vec![vec![Value::String("x".to_string())]])
+        }
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir, 0);
+    assert_eq!(db.session().execution_limits(), ExecutionLimits::default());
+    assert_eq!(count(&mut db), 10);
+}
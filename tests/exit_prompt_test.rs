@@ -0,0 +1,120 @@
+use simple_db::testing;
+use simple_db::{DBConfig, SimpleDB};
+use std::fs;
+use tempfile::TempDir;
+
+fn open_db(temp_dir: &TempDir) -> SimpleDB {
+    let config = DBConfig {
+        interactive: true,
+        ..testing::config(temp_dir.path(), "exit_db")
+    };
+    SimpleDB::with_config(config).expect("无法打开数据库")
+}
+
+/// 按脚本依次回答退出提示，记录显示过的提示
+fn scripted<'a>(
+    answers: &'a mut Vec<&'static str>,
+    prompts: &'a mut Vec<String>,
+) -> impl FnMut(&str) -> Option<String> + 'a {
+    move |prompt| {
+        prompts.push(prompt.to_string());
+        if answers.is_empty() {
+            None
+        } else {
+            Some(answers.remove(0).to_string())
+        }
+    }
+}
+
+#[test]
+fn test_exit_without_changes_does_not_prompt() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    db.execute_single_sql("SHOW TABLES").unwrap();
+    assert!(!db.has_unsaved_changes());
+
+    let (mut answers, mut prompts) = (vec![], vec![]);
+    assert!(db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert!(prompts.is_empty());
+}
+
+#[test]
+fn test_exit_prompt_answers() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    assert!(db.has_unsaved_changes());
+
+    // 直接回车、n 都取消退出，每次退出都重新询问
+    let (mut answers, mut prompts) = (vec!["", "n"], vec![]);
+    assert!(!db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert!(!db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert_eq!(prompts.len(), 2);
+    assert_eq!(
+        prompts[0],
+        "有未保存的更改（最后保存于 0 分钟前），确认退出? (y/N/s=保存后退出) "
+    );
+    assert!(db.has_unsaved_changes());
+
+    // s 保存后退出，之后不再询问
+    let (mut answers, mut prompts) = (vec!["s"], vec![]);
+    assert!(db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert_eq!(prompts.len(), 1);
+    assert!(!db.has_unsaved_changes());
+    assert!(db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert_eq!(prompts.len(), 1);
+
+    // .save 同样清除标记，随后的修改重新标记
+    db.execute_single_sql("INSERT INTO t VALUES (1)").unwrap();
+    assert!(db.has_unsaved_changes());
+    db.save().unwrap();
+    assert!(!db.has_unsaved_changes());
+
+    // 失败的修改语句也视为有未保存的更改，y 确认退出
+    assert!(
+        db.execute_single_sql("INSERT INTO missing VALUES (1)")
+            .is_err()
+    );
+    let (mut answers, mut prompts) = (vec!["y"], vec![]);
+    assert!(db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert_eq!(prompts.len(), 1);
+}
+
+#[test]
+fn test_eof_at_exit_prompt_exits() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+
+    // 提示时输入已结束（Ctrl+D 或管道读完）无法再回答，直接退出而不是反复询问
+    let (mut answers, mut prompts) = (vec![], vec![]);
+    assert!(db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert_eq!(prompts.len(), 1);
+
+    // 退出时照常保存
+    drop(db);
+    let mut db = open_db(&temp_dir);
+    assert!(db.execute_single_sql("SELECT * FROM t").is_ok());
+}
+
+#[test]
+fn test_save_failure_aborts_exit() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+
+    // 元数据文件的位置被目录占用，保存必然失败
+    let meta = temp_dir.path().join("exit_db").join("exit_db.meta");
+    let _ = fs::remove_file(&meta);
+    fs::create_dir(&meta).unwrap();
+
+    let (mut answers, mut prompts) = (vec!["s"], vec![]);
+    assert!(!db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert!(db.has_unsaved_changes());
+
+    // 问题解决后再次保存退出
+    fs::remove_dir(&meta).unwrap();
+    let (mut answers, mut prompts) = (vec!["s"], vec![]);
+    assert!(db.confirm_exit(scripted(&mut answers, &mut prompts)));
+    assert!(!db.has_unsaved_changes());
+}
@@ -0,0 +1,160 @@
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// 不常见或不支持的语句只能返回错误，不能让进程 panic
+#[test]
+fn test_exotic_statements_never_panic() {
+    let (_temp_dir, mut db) = testing::temp_db("exotic_db").unwrap();
+    db.execute_sql(
+        "CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(10)); \
+         CREATE TABLE ai (id INT AUTO_INCREMENT PRIMARY KEY, v INT); \
+         INSERT INTO t VALUES (1, 'a'), (2, 'b')",
+    )
+    .unwrap();
+
+    let statements = [
+        "SHOW DATABASES",
+        "SHOW TABLES",
+        "SHOW WARNINGS",
+        "SHOW VARIABLES",
+        "SHOW CREATE TABLE t",
+        "SHOW INDEX FROM t",
+        "SHOW PROCESSLIST",
+        "DESCRIBE t",
+        "EXPLAIN SELECT * FROM t WHERE id = 1",
+        "EXPLAIN DELETE FROM t WHERE id > 1",
+        "EXPLAIN UPDATE t SET name = 'x'",
+        "EXPLAIN INSERT INTO t SELECT * FROM t",
+        "EXPLAIN CREATE TABLE x (a INT)",
+        "SET autocommit = 1",
+        "SET @x = 1",
+        "SET NAMES utf8",
+        "SET sql_mode = 5",
+        "SAVEPOINT s",
+        "ROLLBACK TO s",
+        "CREATE INDEX i ON t (name)",
+        "TRUNCATE TABLE t",
+        "CREATE VIEW v AS SELECT 1",
+        "CALL p()",
+        "LOCK TABLES t READ",
+        "ANALYZE TABLE t",
+        "ALTER TABLE t RENAME TO u",
+        "SELECT (SELECT 1)",
+        "SELECT * FROM (SELECT 1) AS x",
+        "WITH x AS (SELECT 1) SELECT * FROM x",
+        "SELECT 1 FROM t, t AS u",
+        "SELECT COUNT(*) OVER () FROM t",
+        "SELECT CAST(id AS CHAR) FROM t",
+        "SELECT * FROM information_schema.tables",
+        "SELECT 1 / 0",
+        "SELECT 10 % 0",
+        "SELECT 2147483647 + 1",
+        "SELECT 2147483647 * 2",
+        "SELECT -2147483647 - 2",
+        "SELECT (-2147483647 - 1) / -1",
+        "SELECT (-2147483647 - 1) % -1",
+        "SELECT -(-2147483647 - 1)",
+        "SELECT 9223372036854775807",
+        "SELECT * FROM t LIMIT 18446744073709551615 OFFSET 18446744073709551615",
+        "SELECT SUBSTR(name, -2147483647 - 1, 2147483647) FROM t",
+        "INSERT INTO ai VALUES (2147483647, 1)",
+        "INSERT INTO ai (v) VALUES (2)",
+        "UPDATE ai SET id = id + 1",
+        "USE missing_db",
+        "DROP DATABASE exotic_db",
+        "SHOW TABLES",
+        "SELECT * FROM t",
+    ];
+    for sql in statements {
+        let outcome = catch_unwind(AssertUnwindSafe(|| db.execute_sql(sql)));
+        assert!(outcome.is_ok(), "语句导致 panic: {}", sql);
+    }
+}
+
+#[test]
+fn test_integer_overflow_is_an_error() {
+    let (_temp_dir, mut db) = testing::temp_db("exotic_db").unwrap();
+
+    // 多条语句中溢出的一条作为结果中的错误返回，不影响后续语句
+    let results = db
+        .execute_sql(
+            "SELECT 2147483647 + 1; SELECT (-2147483647 - 1) / -1; \
+             SELECT -(-2147483647 - 1); SELECT (-2147483647 - 1) % -1",
+        )
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(
+        results[0].as_ref().unwrap_err().to_string(),
+        "INT 运算结果超出范围: 2147483647 + 1"
+    );
+    assert!(results[1].is_err());
+    assert!(results[2].is_err());
+    assert!(results[3].is_ok());
+
+    // 自增计数器到达 INT 上限后报错，显式给出的值仍然可以插入
+    db.execute_sql(
+        "CREATE TABLE ai (id INT AUTO_INCREMENT PRIMARY KEY, v INT); \
+         INSERT INTO ai VALUES (2147483647, 1)",
+    )
+    .unwrap();
+    let err = db
+        .execute_single_sql("INSERT INTO ai (v) VALUES (2)")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "自增列 'id' 的值已达到 INT 上限");
+    assert!(
+        db.execute_single_sql("INSERT INTO ai VALUES (1, 3)")
+            .is_ok()
+    );
+}
+
+/// WHERE 条件求值出错时整条语句失败，不能把出错的行当作不匹配跳过
+#[test]
+fn test_errors_in_predicates_fail_the_statement() {
+    let (_temp_dir, mut db) = testing::temp_db("exotic_db").unwrap();
+    db.execute_sql(
+        "CREATE TABLE t (id INT PRIMARY KEY, a INT, name VARCHAR(10)); \
+         INSERT INTO t VALUES (1, 1, 'x'), (2, 2000000000, 'y')",
+    )
+    .unwrap();
+
+    for sql in [
+        "SELECT * FROM t WHERE a + a > 0",
+        "SELECT * FROM t WHERE id > 0 AND a * 2 > 0",
+        "UPDATE t SET name = 'z' WHERE a + a > 0",
+        "DELETE FROM t WHERE a + a > 0",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err().to_string();
+        assert!(
+            err.starts_with("INT 运算结果超出范围: 2000000000 "),
+            "{}: {}",
+            sql,
+            err
+        );
+    }
+    // 除以零和类型不匹配与出现在 SELECT 列表中时一样报错
+    for sql in [
+        "SELECT * FROM t WHERE 1 / 0 = 1",
+        "SELECT * FROM t WHERE a / (a - 1) = 0",
+        "SELECT * FROM t WHERE name = 5",
+        "DELETE FROM t WHERE name = 5",
+    ] {
+        assert!(db.execute_single_sql(sql).is_err(), "{}", sql);
+    }
+
+    // 失败的 UPDATE 和 DELETE 没有改动任何行
+    let QueryResult::ResultSet(rs) = db
+        .execute_single_sql("SELECT name FROM t ORDER BY id")
+        .unwrap()
+    else {
+        panic!("预期结果集");
+    };
+    assert_eq!(
+        rs.rows,
+        vec![
+            vec![Value::String("x".to_string())],
+            vec![Value::String("y".to_string())],
+        ]
+    );
+}
@@ -0,0 +1,171 @@
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+
+fn error_message(db: &mut SimpleDB, sql: &str) -> String {
+    match db.execute_single_sql(sql) {
+        Ok(result) => panic!("预期 {} 失败，实际为 {:?}", sql, result),
+        Err(e) => e.to_string(),
+    }
+}
+
+fn ids(db: &mut SimpleDB, sql: &str) -> Vec<Value> {
+    query_rows(db, sql)
+        .into_iter()
+        .map(|row| row[0].clone())
+        .collect()
+}
+
+#[test]
+fn test_foreign_key_enforced_on_insert_update_delete() {
+    let (_temp_dir, mut db) = testing::temp_db("fk_db").unwrap();
+    db.execute_sql(
+        "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20)); \
+         CREATE TABLE orders (id INT PRIMARY KEY, user_id INT REFERENCES users(id)); \
+         INSERT INTO users VALUES (1, 'alice'), (2, 'bob'), (3, 'carol')",
+    )
+    .unwrap();
+
+    // 引用存在的用户或 NULL 都可以，引用不存在的用户整条语句失败
+    db.execute_single_sql("INSERT INTO orders VALUES (10, 1), (11, NULL), (12, 2)")
+        .unwrap();
+    let message = error_message(&mut db, "INSERT INTO orders VALUES (13, 1), (14, 99)");
+    assert!(
+        message.contains("不能插入或修改子表的行"),
+        "{}",
+        message
+    );
+    assert!(message.contains("第 2 行"), "{}", message);
+    db.execute_single_sql("INSERT IGNORE INTO orders VALUES (13, 1), (14, 99)")
+        .unwrap();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM orders ORDER BY id"),
+        [10, 11, 12, 13].map(Value::Int)
+    );
+
+    // 修改引用列同样检查；被引用的主键不能改掉
+    let message = error_message(&mut db, "UPDATE orders SET user_id = 42 WHERE id = 11");
+    assert!(
+        message.contains("不能插入或修改子表的行"),
+        "{}",
+        message
+    );
+    db.execute_single_sql("UPDATE orders SET user_id = 3 WHERE id = 11")
+        .unwrap();
+    let message = error_message(&mut db, "UPDATE users SET id = 20 WHERE id = 2");
+    assert!(
+        message.contains("不能删除或修改父表的行"),
+        "{}",
+        message
+    );
+    db.execute_single_sql("UPDATE users SET name = 'bobby' WHERE id = 2")
+        .unwrap();
+
+    // 仍被引用的用户不能删除，没有订单引用之后可以
+    let message = error_message(&mut db, "DELETE FROM users WHERE id = 1");
+    assert!(
+        message.contains("不能删除或修改父表的行"),
+        "{}",
+        message
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM users"),
+        [1, 2, 3].map(Value::Int)
+    );
+    db.execute_single_sql("DELETE FROM orders WHERE user_id = 1")
+        .unwrap();
+    db.execute_single_sql("DELETE FROM users WHERE id = 1")
+        .unwrap();
+
+    // 被引用的表和列不能单独删除
+    let message = error_message(&mut db, "DROP TABLE users");
+    assert!(
+        message.contains("的外键引用了它"),
+        "{}",
+        message
+    );
+    assert!(error_message(&mut db, "ALTER TABLE users DROP COLUMN id").contains("外键"));
+    db.execute_single_sql("DROP TABLE users, orders").unwrap();
+}
+
+#[test]
+fn test_self_referencing_table() {
+    let (_temp_dir, mut db) = testing::temp_db("fk_db").unwrap();
+    db.execute_single_sql(
+        "CREATE TABLE employees (id INT PRIMARY KEY, manager_id INT, \
+         FOREIGN KEY (manager_id) REFERENCES employees (id))",
+    )
+    .unwrap();
+
+    // 同一条语句插入的行可以互相引用，也可以引用自己
+    db.execute_single_sql("INSERT INTO employees VALUES (1, NULL), (2, 1), (3, 3)")
+        .unwrap();
+    assert!(
+        error_message(&mut db, "INSERT INTO employees VALUES (4, 99)")
+            .contains("不能插入或修改子表的行")
+    );
+
+    // 有下属的员工不能删除；只被自己引用的行、以及与下属一起删除都可以
+    assert!(
+        error_message(&mut db, "DELETE FROM employees WHERE id = 1")
+            .contains("不能删除或修改父表的行")
+    );
+    db.execute_single_sql("DELETE FROM employees WHERE id = 3")
+        .unwrap();
+    db.execute_single_sql("UPDATE employees SET manager_id = 2 WHERE id = 2")
+        .unwrap();
+    db.execute_single_sql("DELETE FROM employees WHERE id = 1")
+        .unwrap();
+    db.execute_single_sql("DELETE FROM employees").unwrap();
+    assert!(query_rows(&mut db, "SELECT * FROM employees").is_empty());
+}
+
+#[test]
+fn test_foreign_key_definitions() {
+    let (temp_dir, mut db) = testing::temp_db("fk_db").unwrap();
+    db.execute_single_sql("CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20))")
+        .unwrap();
+
+    for (sql, expected) in [
+        (
+            "CREATE TABLE t (user_id INT REFERENCES missing(id))",
+            "被引用的表 'missing' 不存在",
+        ),
+        (
+            "CREATE TABLE t (user_id INT REFERENCES users(nope))",
+            "中没有列 'nope'",
+        ),
+        (
+            "CREATE TABLE t (name VARCHAR(20) REFERENCES users(name))",
+            "没有单列唯一索引",
+        ),
+        (
+            "CREATE TABLE t (user_id VARCHAR(20) REFERENCES users(id))",
+            "类型不兼容",
+        ),
+        (
+            "CREATE TABLE t (user_id INT REFERENCES users(id) ON DELETE CASCADE)",
+            "CASCADE",
+        ),
+    ] {
+        let message = error_message(&mut db, sql);
+        assert!(message.contains(expected), "{}: {}", sql, message);
+    }
+    assert!(query_rows(&mut db, "SHOW TABLES").len() == 1);
+
+    // 外键随目录保存，重新打开后仍然生效
+    db.execute_single_sql("CREATE TABLE orders (id INT, user_id INT REFERENCES USERS(ID))")
+        .unwrap();
+    drop(db);
+    let mut db = testing::open_db(temp_dir.path(), "fk_db").unwrap();
+    let create = query_rows(&mut db, "SHOW CREATE TABLE orders");
+    assert!(
+        create[0][1].to_string().contains(
+            "CONSTRAINT `orders_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)"
+        )
+    );
+    assert!(
+        error_message(&mut db, "INSERT INTO orders VALUES (1, 7)")
+            .contains("不能插入或修改子表的行")
+    );
+}
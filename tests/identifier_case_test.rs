@@ -0,0 +1,69 @@
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+
+fn query(db: &mut SimpleDB, sql: &str) -> (Vec<String>, Vec<Vec<Value>>) {
+    match db.execute_single_sql(sql).unwrap() {
+        QueryResult::ResultSet(rs) => (rs.columns, rs.rows),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_unquoted_identifiers_ignore_case() {
+    let (_temp_dir, mut db) = testing::temp_db("case_db").unwrap();
+
+    db.execute_single_sql("CREATE TABLE Users (ID INT PRIMARY KEY, Name VARCHAR(20))")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO USERS (id, NAME) VALUES (1, 'ann'), (2, 'bob')")
+        .unwrap();
+    db.execute_single_sql("UPDATE users SET NaMe = 'BOB' WHERE Id = 2")
+        .unwrap();
+
+    // 表名和列名按小写保存，结果列名保留查询中的写法
+    let (columns, _) = query(&mut db, "SELECT * FROM users");
+    assert_eq!(columns, vec!["id", "name"]);
+    let (columns, rows) = query(&mut db, "SELECT ID, NAME FROM USERS ORDER BY Id DESC");
+    assert_eq!(columns, vec!["ID", "NAME"]);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Int(2), Value::String("BOB".to_string())],
+            vec![Value::Int(1), Value::String("ann".to_string())],
+        ]
+    );
+    let (_, rows) = query(&mut db, "SHOW TABLES");
+    assert_eq!(rows, vec![vec![Value::String("users".to_string())]]);
+
+    // 只是大小写不同的表名视为同一张表
+    assert!(
+        db.execute_single_sql("CREATE TABLE USERS (id INT)")
+            .is_err()
+    );
+    db.execute_single_sql("DELETE FROM Users WHERE NAME = 'ann'")
+        .unwrap();
+    assert_eq!(query(&mut db, "SELECT id FROM users").1.len(), 1);
+}
+
+#[test]
+fn test_quoted_identifiers_keep_case() {
+    let (_temp_dir, mut db) = testing::temp_db("case_db").unwrap();
+
+    db.execute_single_sql("CREATE TABLE `Mixed` (`Id` INT, `Label` VARCHAR(10))")
+        .unwrap();
+    let (_, rows) = query(&mut db, "SHOW TABLES");
+    assert_eq!(rows, vec![vec![Value::String("Mixed".to_string())]]);
+    let (columns, _) = query(&mut db, "SELECT * FROM `Mixed`");
+    assert_eq!(columns, vec!["Id", "Label"]);
+
+    // 大小写混合的名称（如旧版本创建的表）仍然可以用任意大小写访问
+    db.execute_single_sql("INSERT INTO mixed (id, label) VALUES (1, 'x')")
+        .unwrap();
+    db.execute_single_sql("UPDATE MIXED SET LABEL = 'y' WHERE ID = 1")
+        .unwrap();
+    let (_, rows) = query(&mut db, "SELECT label FROM mixed WHERE id = 1");
+    assert_eq!(rows, vec![vec![Value::String("y".to_string())]]);
+    db.execute_single_sql("DROP TABLE mixed").unwrap();
+    assert!(query(&mut db, "SHOW TABLES").1.is_empty());
+}
@@ -0,0 +1,87 @@
+use simple_db::testing;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 基础目录下每个文件的修改时间和内容
+fn snapshot(base_dir: &Path) -> BTreeMap<PathBuf, (SystemTime, Vec<u8>)> {
+    let mut files = BTreeMap::new();
+    for database in std::fs::read_dir(base_dir).unwrap() {
+        for file in std::fs::read_dir(database.unwrap().path()).unwrap() {
+            let path = file.unwrap().path();
+            let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+            files.insert(path.clone(), (modified, std::fs::read(&path).unwrap()));
+        }
+    }
+    files
+}
+
+#[test]
+fn test_save_skips_unmodified_databases() {
+    let (temp_dir, mut db) = testing::temp_db("main_db").unwrap();
+    db.execute_sql(
+        "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20)); \
+         INSERT INTO users VALUES (1, 'alice'), (2, 'bob'); \
+         CREATE DATABASE other; \
+         CREATE TABLE other.t (id INT); \
+         INSERT INTO other.t VALUES (1)",
+    )
+    .unwrap();
+    db.save().unwrap();
+    let saved = snapshot(temp_dir.path());
+    assert!(
+        saved
+            .keys()
+            .any(|path| path.ends_with("main_db/main_db.meta"))
+    );
+    assert!(saved.keys().any(|path| path.ends_with("other/other.meta")));
+
+    // 只读查询之后再次保存，所有文件都没有被重写
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    db.execute_single_sql("SELECT * FROM users WHERE id > 0")
+        .unwrap();
+    db.execute_single_sql("SELECT * FROM other.t").unwrap();
+    db.save().unwrap();
+    assert_eq!(snapshot(temp_dir.path()), saved);
+
+    // 只修改一个数据库的数据时，只写入它的数据页（经过预写日志），
+    // 另一个数据库和两个元数据文件都保持不变
+    db.execute_single_sql("UPDATE users SET name = 'carol' WHERE id = 2")
+        .unwrap();
+    db.save().unwrap();
+    let updated = snapshot(temp_dir.path());
+    for (path, file) in &updated {
+        let unchanged = saved[path] == *file;
+        let page_write = path.ends_with("main_db/data.db") || path.ends_with("main_db/data.wal");
+        assert_eq!(unchanged, !page_write, "{}", path.display());
+    }
+
+    // 建表改变目录，元数据文件随之重写，重新打开后数据完整
+    db.execute_single_sql("CREATE TABLE other.u (id INT)")
+        .unwrap();
+    db.save().unwrap();
+    let created = snapshot(temp_dir.path());
+    let other_meta = created
+        .keys()
+        .find(|path| path.ends_with("other/other.meta"))
+        .unwrap();
+    assert_ne!(created[other_meta], updated[other_meta]);
+    drop(db);
+
+    let mut db = testing::open_db(temp_dir.path(), "main_db").unwrap();
+    assert!(db.execute_single_sql("SELECT * FROM other.u").is_ok());
+    db.execute_single_sql("USE main_db").unwrap();
+    let rows = match db
+        .execute_single_sql("SELECT name FROM users WHERE id = 2")
+        .unwrap()
+    {
+        simple_db::executor::QueryResult::ResultSet(rs) => rs.rows,
+        other => panic!("预期结果集，实际为 {:?}", other),
+    };
+    assert_eq!(
+        rows,
+        vec![vec![simple_db::storage::table::Value::String(
+            "carol".to_string()
+        )]]
+    );
+}
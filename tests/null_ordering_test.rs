@@ -0,0 +1,213 @@
+//! 同一份数据分别经过 WHERE 范围过滤、ORDER BY 和 MIN/MAX，检查 NULL 与
+//! 值的顺序在各处一致：
+//!
+//! - 过滤条件保持 SQL 语义，NULL 与任何值比较都不成立；
+//! - 排序使用 `Value::total_cmp` 的全序，NULL 的位置由 NULLS FIRST/LAST 决定
+//!   （默认升序在前、降序在后）；
+//! - MIN/MAX 忽略 NULL，结果等于排序后第一个/最后一个非 NULL 值。
+//!
+//! 目前还没有索引范围扫描，加入后应在这里补充同样的检查。
+
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use tempfile::TempDir;
+
+fn column(db: &mut SimpleDB, sql: &str) -> Vec<Value> {
+    match db.execute_single_sql(sql).unwrap() {
+        QueryResult::ResultSet(rs) => rs.rows.into_iter().map(|mut row| row.remove(0)).collect(),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+fn setup() -> (SimpleDB, TempDir) {
+    let (temp_dir, mut db) = testing::temp_db("ordering_db").unwrap();
+    db.execute_single_sql("CREATE TABLE t (id INT, v INT, s VARCHAR(8))")
+        .unwrap();
+    db.execute_single_sql(
+        "INSERT INTO t VALUES (1, 5, 'm'), (2, NULL, NULL), (3, 0 - 2, 'b'), (4, 7, 'z'), \
+         (5, NULL, 'a'), (6, 0, NULL), (7, 5, 'm')",
+    )
+    .unwrap();
+    (db, temp_dir)
+}
+
+#[test]
+fn test_filters_never_match_null() {
+    let (mut db, _temp_dir) = setup();
+
+    for (predicate, expected) in [
+        ("v < 5", vec![-2, 0]),
+        ("v <= 5", vec![-2, 0, 5, 5]),
+        ("v > 0", vec![5, 5, 7]),
+        ("v >= -100", vec![-2, 0, 5, 5, 7]),
+        ("v = 5", vec![5, 5]),
+        ("v <> 5", vec![-2, 0, 7]),
+    ] {
+        let values = column(
+            &mut db,
+            &format!("SELECT v FROM t WHERE {} ORDER BY v", predicate),
+        );
+        let expected: Vec<Value> = expected.into_iter().map(Value::Int).collect();
+        assert_eq!(values, expected, "WHERE {}", predicate);
+    }
+
+    // 与 NULL 字面量比较同样不成立，只有 IS NULL 能选出 NULL
+    assert!(column(&mut db, "SELECT v FROM t WHERE v = NULL").is_empty());
+    assert_eq!(column(&mut db, "SELECT id FROM t WHERE v IS NULL").len(), 2);
+}
+
+#[test]
+fn test_order_by_places_nulls_by_option() {
+    let (mut db, _temp_dir) = setup();
+    let ints = |values: &[Option<i32>]| -> Vec<Value> {
+        values
+            .iter()
+            .map(|v| v.map_or(Value::Null, Value::Int))
+            .collect()
+    };
+
+    assert_eq!(
+        column(&mut db, "SELECT v FROM t ORDER BY v"),
+        ints(&[None, None, Some(-2), Some(0), Some(5), Some(5), Some(7)])
+    );
+    assert_eq!(
+        column(&mut db, "SELECT v FROM t ORDER BY v DESC"),
+        ints(&[Some(7), Some(5), Some(5), Some(0), Some(-2), None, None])
+    );
+    assert_eq!(
+        column(&mut db, "SELECT v FROM t ORDER BY v ASC NULLS LAST"),
+        ints(&[Some(-2), Some(0), Some(5), Some(5), Some(7), None, None])
+    );
+    assert_eq!(
+        column(&mut db, "SELECT v FROM t ORDER BY v DESC NULLS FIRST"),
+        ints(&[None, None, Some(7), Some(5), Some(5), Some(0), Some(-2)])
+    );
+
+    // 排序后的非 NULL 部分与范围过滤的结果一致
+    for threshold in [-3, 0, 5, 8] {
+        let sorted = column(&mut db, "SELECT v FROM t ORDER BY v NULLS LAST");
+        let prefix: Vec<Value> = sorted
+            .into_iter()
+            .take_while(|v| matches!(v, Value::Int(n) if *n < threshold))
+            .collect();
+        let filtered = column(
+            &mut db,
+            &format!("SELECT v FROM t WHERE v < {} ORDER BY v", threshold),
+        );
+        assert_eq!(prefix, filtered, "v < {}", threshold);
+    }
+}
+
+#[test]
+fn test_min_max_match_sorted_extremes() {
+    let (mut db, _temp_dir) = setup();
+
+    for col in ["v", "s"] {
+        let sorted: Vec<Value> = column(&mut db, &format!("SELECT {0} FROM t ORDER BY {0}", col))
+            .into_iter()
+            .filter(|v| !v.is_null())
+            .collect();
+        let min = column(&mut db, &format!("SELECT MIN({}) FROM t", col));
+        let max = column(&mut db, &format!("SELECT MAX({}) FROM t", col));
+        assert_eq!(min, vec![sorted[0].clone()], "MIN({})", col);
+        assert_eq!(max, vec![sorted[sorted.len() - 1].clone()], "MAX({})", col);
+    }
+
+    // 全部为 NULL 时 MIN/MAX 为 NULL
+    assert_eq!(
+        column(&mut db, "SELECT MIN(v) FROM t WHERE v IS NULL"),
+        vec![Value::Null]
+    );
+}
+
+#[test]
+fn test_order_by_expression_nulls_and_stable_ties() {
+    let (mut db, _temp_dir) = setup();
+    let ids = |db: &mut SimpleDB, sql: &str| -> Vec<i32> {
+        column(db, sql)
+            .into_iter()
+            .map(|v| match v {
+                Value::Int(id) => id,
+                other => panic!("预期整数，实际为 {:?}", other),
+            })
+            .collect()
+    };
+
+    // 表达式、别名和位置作为排序项时同样按 NULLS FIRST/LAST 放置 NULL
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM t ORDER BY v * 2 DESC NULLS FIRST, id"
+        ),
+        vec![2, 5, 4, 1, 7, 6, 3]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id, v + 1 AS w FROM t ORDER BY w NULLS LAST, id DESC"
+        ),
+        vec![3, 6, 7, 1, 4, 5, 2]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id, s FROM t ORDER BY 2 DESC NULLS LAST, id"
+        ),
+        vec![4, 1, 7, 3, 5, 2, 6]
+    );
+
+    // 排序键相同的行保持插入顺序，与升降序和 NULL 的位置无关
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t ORDER BY v DESC NULLS FIRST"),
+        vec![2, 5, 4, 1, 7, 6, 3]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t ORDER BY s NULLS LAST"),
+        vec![5, 3, 1, 7, 4, 2, 6]
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM t ORDER BY v NULLS LAST, s DESC NULLS FIRST"
+        ),
+        vec![3, 6, 1, 7, 4, 2, 5]
+    );
+}
+
+#[test]
+fn test_mixed_types_and_missing_sort_column() {
+    let (mut db, _temp_dir) = setup();
+
+    // 同一列混有不同类型时仍是确定的全序：NULL、数字、字符串
+    let mixed = "SELECT 'b' AS x UNION ALL SELECT 2.5 UNION ALL SELECT NULL \
+                 UNION ALL SELECT 'a' UNION ALL SELECT 1";
+    assert_eq!(
+        column(&mut db, &format!("{} ORDER BY x", mixed)),
+        vec![
+            Value::Null,
+            Value::Int(1),
+            Value::Float(2.5),
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ]
+    );
+    assert_eq!(
+        column(&mut db, &format!("{} ORDER BY x DESC", mixed)),
+        vec![
+            Value::String("b".to_string()),
+            Value::String("a".to_string()),
+            Value::Float(2.5),
+            Value::Int(1),
+            Value::Null,
+        ]
+    );
+
+    // 不存在的排序列报错，而不是忽略这个排序项
+    let message = db
+        .execute_single_sql("SELECT id FROM t ORDER BY nope, id")
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("排序列 'nope' 不存在"), "{}", message);
+}
@@ -0,0 +1,139 @@
+use simple_db::storage::StorageEngine;
+use simple_db::storage::backup::{OnlineBackup, import_backup};
+use simple_db::storage::table::{ColumnDef, DataType, Value};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const SEED_ROWS: i32 = 1000;
+/// 写入线程最多写入的行数，避免测试时间失控
+const MAX_WRITES: i32 = 2000;
+
+fn column(name: &str, data_type: DataType) -> ColumnDef {
+    ColumnDef {
+        name: name.to_string(),
+        data_type,
+        not_null: false,
+        unique: false,
+        is_primary: false,
+        auto_increment: false,
+        default: None,
+        references: None,
+    }
+}
+
+fn row(id: i32, note: &str) -> Vec<Value> {
+    vec![
+        Value::Int(id),
+        Value::String(format!("{}-{}", note, "x".repeat(40))),
+    ]
+}
+
+fn sorted_rows(storage: &mut StorageEngine, table_name: &str) -> Vec<Vec<Value>> {
+    let mut rows: Vec<Vec<Value>> = storage
+        .get_all_records(table_name)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.values().to_vec())
+        .collect();
+    rows.sort_by_key(|row| match row[0] {
+        Value::Int(id) => id,
+        _ => i32::MAX,
+    });
+    rows
+}
+
+#[test]
+fn test_online_backup_with_concurrent_writer() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut storage =
+        StorageEngine::new(Some(&temp_dir.path().join("live")), Some("shop")).unwrap();
+    storage
+        .create_table(
+            "orders".to_string(),
+            vec![
+                column("id", DataType::Int(None)),
+                column("note", DataType::Varchar(Some(100))),
+            ],
+        )
+        .unwrap();
+    let mut seed_ids = Vec::new();
+    for id in 0..SEED_ROWS {
+        seed_ids.push(storage.insert_record("orders", row(id, "seed")).unwrap());
+    }
+
+    let storage = Arc::new(Mutex::new(storage));
+    let stop = Arc::new(AtomicBool::new(false));
+    let writes = Arc::new(AtomicUsize::new(0));
+
+    // 写入线程：不停插入新行，并删除已有的行
+    let writer = {
+        let storage = Arc::clone(&storage);
+        let stop = Arc::clone(&stop);
+        let writes = Arc::clone(&writes);
+        thread::spawn(move || {
+            let mut next_id = SEED_ROWS;
+            while !stop.load(Ordering::SeqCst) && next_id < SEED_ROWS + MAX_WRITES {
+                {
+                    let mut storage = storage.lock().unwrap();
+                    storage
+                        .insert_record("orders", row(next_id, "new"))
+                        .unwrap();
+                    if let Some(target) = seed_ids.pop() {
+                        storage.delete_record("orders", target).unwrap();
+                    }
+                }
+                next_id += 1;
+                writes.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_micros(200));
+            }
+        })
+    };
+
+    while writes.load(Ordering::SeqCst) < 10 {
+        assert!(!writer.is_finished(), "写入线程提前退出");
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let backup_path = temp_dir.path().join("shop.bak");
+    let (mut backup, expected, writes_at_start) = {
+        let mut storage = storage.lock().unwrap();
+        let backup = OnlineBackup::begin(&mut storage, &backup_path).unwrap();
+        let expected = sorted_rows(&mut storage, "orders");
+        (backup, expected, writes.load(Ordering::SeqCst))
+    };
+
+    // 每一步只持有一小段时间的锁，写入线程可以在两步之间继续工作；
+    // 等到写入线程确实写入后再进行下一步，避免结果依赖线程调度
+    loop {
+        let done = backup.step(&mut storage.lock().unwrap(), 1).unwrap();
+        if done {
+            break;
+        }
+        let seen = writes.load(Ordering::SeqCst);
+        while writes.load(Ordering::SeqCst) == seen {
+            assert!(!writer.is_finished(), "写入线程提前退出");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+    let summary = backup.finish(&mut storage.lock().unwrap()).unwrap();
+    let writes_during_backup = writes.load(Ordering::SeqCst) - writes_at_start;
+
+    stop.store(true, Ordering::SeqCst);
+    writer.join().unwrap();
+
+    assert!(summary.pages > 1);
+    assert!(writes_during_backup > 0, "备份期间写入被阻塞");
+
+    // 导入到全新的数据目录，内容必须恰好是备份开始时的状态
+    let mut restored =
+        StorageEngine::new(Some(&temp_dir.path().join("restored")), Some("scratch")).unwrap();
+    let name = import_backup(&mut restored, &backup_path, None).unwrap();
+    assert_eq!(name, "shop");
+    restored.use_database("shop").unwrap();
+
+    let rows = sorted_rows(&mut restored, "orders");
+    assert_eq!(rows, expected);
+}
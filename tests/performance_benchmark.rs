@@ -1,4 +1,6 @@
-use simple_db::{SimpleDB, DBConfig};
+use simple_db::SimpleDB;
+use simple_db::testing::{self, env_or_default};
+use tempfile::TempDir;
 use std::time::{Duration, Instant};
 use std::fmt;
 
@@ -72,48 +74,36 @@ struct TestConfig {
 impl TestConfig {
     fn from_env() -> Self {
         Self {
-            insert_count: get_env_or_default("PERF_INSERT_COUNT", 1000),
-            select_count: get_env_or_default("PERF_SELECT_COUNT", 100),
-            update_count: get_env_or_default("PERF_UPDATE_COUNT", 100),
-            delete_count: get_env_or_default("PERF_DELETE_COUNT", 100),
+            insert_count: env_or_default("PERF_INSERT_COUNT", 1000),
+            select_count: env_or_default("PERF_SELECT_COUNT", 100),
+            update_count: env_or_default("PERF_UPDATE_COUNT", 100),
+            delete_count: env_or_default("PERF_DELETE_COUNT", 100),
             enable_detailed_stats: std::env::var("PERF_DETAILED_STATS").unwrap_or_default() == "1",
             enable_full_scan: std::env::var("PERF_FULL_SCAN").unwrap_or("1".to_string()) == "1",
         }
     }
 }
 
-/// 从环境变量获取配置值，如果没有则使用默认值
-fn get_env_or_default(key: &str, default: usize) -> usize {
-    std::env::var(key)
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(default)
-}
-
 /// 数据库性能测试器
 struct DatabaseTester {
     db: SimpleDB,
     config: TestConfig,
+    /// 数据库所在的临时目录，测试结束时删除
+    _temp_dir: TempDir,
 }
 
 impl DatabaseTester {
     fn new(config: TestConfig) -> Result<Self, Box<dyn std::error::Error>> {
-        let db_config = DBConfig {
-            sql_file: None,
-            base_dir: Some("data/performance_test".to_string()),
-            db_name: Some("perf_test".to_string()),
-            execute: None,
-            interactive: false,
-            verbose: false,
-        };
-
-        let mut db = SimpleDB::with_config(db_config)?;
-        
+        let (temp_dir, mut db) = testing::temp_db("perf_test")?;
+
         // 准备测试环境
-        let _ = db.execute_single_sql("DROP TABLE IF EXISTS perf_table");
-        db.execute_single_sql("CREATE TABLE perf_table (id INT, name VARCHAR(50), score INT)")?;
+        testing::create_perf_table(&mut db)?;
 
-        Ok(Self { db, config })
+        Ok(Self {
+            db,
+            config,
+            _temp_dir: temp_dir,
+        })
     }
 
     fn run_complete_test(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -149,35 +139,17 @@ impl DatabaseTester {
         println!("🔄 测试1: 批量插入性能");
         let mut stats = LatencyStats::new();
         let start_time = Instant::now();
-        
-        for i in 1..=self.config.insert_count {
-            let sql = format!("INSERT INTO perf_table VALUES ({}, 'user{}', {})", i, i, i % 100);
-            
-            let start = Instant::now();
-            self.db.execute_single_sql(&sql)?;
-            let latency = start.elapsed();
-            
-            if self.config.enable_detailed_stats {
-                stats.add(latency);
-            }
-            
-            if i % 100 == 0 {
-                print!("\r插入进度: {}/{}", i, self.config.insert_count);
-                std::io::Write::flush(&mut std::io::stdout()).unwrap();
-            }
-        }
+        testing::populate_table(&mut self.db, self.config.insert_count)?;
         
         let total_duration = start_time.elapsed();
-        println!("\n插入 {} 条记录耗时: {:.2}秒", self.config.insert_count, total_duration.as_secs_f64());
+        println!("插入 {} 条记录耗时: {:.2}秒", self.config.insert_count, total_duration.as_secs_f64());
         println!("插入速度: {:.2} records/sec\n", self.config.insert_count as f64 / total_duration.as_secs_f64());
 
-        if !self.config.enable_detailed_stats {
-            // 如果没有详细统计，创建一个简单的统计
-            stats.count = self.config.insert_count;
-            stats.total = total_duration;
-            stats.min = total_duration / self.config.insert_count as u32;
-            stats.max = stats.min;
-        }
+        // 所有行一次写入，只能得到每行的平均耗时
+        stats.count = self.config.insert_count;
+        stats.total = total_duration;
+        stats.min = total_duration / self.config.insert_count.max(1) as u32;
+        stats.max = stats.min;
 
         Ok(stats)
     }
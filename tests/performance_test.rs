@@ -1,24 +1,16 @@
-use simple_db::{SimpleDB, DBConfig};
+use simple_db::testing::{self, env_or_default};
 use std::time::Instant;
 
-/// 从环境变量获取配置值，如果没有则使用默认值
-fn get_env_or_default(key: &str, default: usize) -> usize {
-    std::env::var(key)
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(default)
-}
-
 /// 简化的性能测试
 #[test]
 fn test_database_performance() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== Simple DB 性能测试 ===\n");
 
     // 从环境变量读取配置
-    let insert_count = get_env_or_default("PERF_INSERT_COUNT", 1000);
-    let query_count = get_env_or_default("PERF_QUERY_COUNT", 100);
-    let update_count = get_env_or_default("PERF_UPDATE_COUNT", 100);
-    let delete_count = get_env_or_default("PERF_DELETE_COUNT", 100);
+    let insert_count = env_or_default("PERF_INSERT_COUNT", 1000);
+    let query_count = env_or_default("PERF_QUERY_COUNT", 100);
+    let update_count = env_or_default("PERF_UPDATE_COUNT", 100);
+    let delete_count = env_or_default("PERF_DELETE_COUNT", 100);
 
     println!("配置:");
     println!("  插入数据量: {}", insert_count);
@@ -27,39 +19,21 @@ fn test_database_performance() -> Result<(), Box<dyn std::error::Error>> {
     println!("  删除次数: {}", delete_count);
     println!();
 
-    // 创建测试数据库
-    let config = DBConfig {
-        sql_file: None,
-        base_dir: Some("data/performance_test".to_string()),
-        db_name: Some("perf_test".to_string()),
-        execute: None,
-        interactive: false,
-        verbose: false,
-    };
-
-    let mut db = SimpleDB::with_config(config)?;
+    // 在临时目录中创建测试数据库
+    let (_temp_dir, mut db) = testing::temp_db("perf_test")?;
 
     // 准备测试环境
     println!("正在准备测试环境...");
-    let _ = db.execute_single_sql("DROP TABLE IF EXISTS perf_table");
-    db.execute_single_sql("CREATE TABLE perf_table (id INT, name VARCHAR(50), score INT)")?;
+    testing::create_perf_table(&mut db)?;
 
     // 测试1: 批量插入性能
     println!("测试1: 批量插入性能");
     let start = Instant::now();
     
-    for i in 1..=insert_count {
-        let sql = format!("INSERT INTO perf_table VALUES ({}, 'user{}', {})", i, i, i % 100);
-        db.execute_single_sql(&sql)?;
-        
-        if i % 100 == 0 {
-            print!("\r插入进度: {}/{}", i, insert_count);
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
-        }
-    }
+    testing::populate_table(&mut db, insert_count)?;
     
     let insert_duration = start.elapsed();
-    println!("\n插入 {} 条记录耗时: {:.2}秒", insert_count, insert_duration.as_secs_f64());
+    println!("插入 {} 条记录耗时: {:.2}秒", insert_count, insert_duration.as_secs_f64());
     println!("插入速度: {:.2} records/sec\n", insert_count as f64 / insert_duration.as_secs_f64());
 
     // 测试2: 查询性能
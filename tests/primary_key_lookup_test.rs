@@ -0,0 +1,123 @@
+//! 主键点查：`WHERE pk = 常量` 走内存中的主键索引，结果必须与全表扫描一致。
+//!
+//! 扫描路径用 `id + 0 = N` 强制触发（条件不是简单的列等于常量）。
+
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const ROWS: i32 = 2000;
+
+/// 查询结果及耗时
+type Timed = (Vec<Vec<Value>>, Duration);
+
+/// 分别用索引路径和扫描路径查询同一个条件，返回两者的结果和耗时
+fn both_paths(db: &mut SimpleDB, key: &str, extra: &str) -> (Timed, Timed) {
+    let start = Instant::now();
+    let fast = query_rows(
+        db,
+        &format!("SELECT * FROM perf_table WHERE id = {}{}", key, extra),
+    );
+    let fast_elapsed = start.elapsed();
+    let start = Instant::now();
+    let scan = query_rows(
+        db,
+        &format!("SELECT * FROM perf_table WHERE id + 0 = {}{}", key, extra),
+    );
+    ((fast, fast_elapsed), (scan, start.elapsed()))
+}
+
+fn assert_same(db: &mut SimpleDB, key: &str, extra: &str) -> Vec<Vec<Value>> {
+    let ((fast, _), (scan, _)) = both_paths(db, key, extra);
+    assert_eq!(fast, scan, "id = {}{}", key, extra);
+    fast
+}
+
+fn setup(temp_dir: &TempDir) -> SimpleDB {
+    let mut db = testing::open_db(temp_dir.path(), "pk_db").unwrap();
+    testing::populate_table(&mut db, ROWS as usize).unwrap();
+    db
+}
+
+#[test]
+fn test_primary_key_lookup_matches_scan() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+
+    let (mut fast_total, mut scan_total) = (Duration::ZERO, Duration::ZERO);
+    for key in (1..=ROWS).step_by(97) {
+        let ((fast, fast_elapsed), (scan, scan_elapsed)) =
+            both_paths(&mut db, &key.to_string(), "");
+        assert_eq!(fast, scan);
+        assert_eq!(fast.len(), 1);
+        fast_total += fast_elapsed;
+        scan_total += scan_elapsed;
+    }
+    println!(
+        "主键点查 {} 行：索引 {:?}，全表扫描 {:?}",
+        ROWS, fast_total, scan_total
+    );
+
+    // 常量在左侧、附加条件、不存在的键、不同类型的常量
+    assert_eq!(
+        query_rows(&mut db, "SELECT name FROM perf_table WHERE 5 = id"),
+        vec![vec![Value::String("user5".to_string())]]
+    );
+    assert_eq!(assert_same(&mut db, "5", " AND score = 5").len(), 1);
+    assert!(assert_same(&mut db, "5", " AND score = 6").is_empty());
+    assert!(assert_same(&mut db, &(ROWS + 1).to_string(), "").is_empty());
+    assert_eq!(assert_same(&mut db, "5.0", "").len(), 1);
+    assert!(assert_same(&mut db, "5.5", "").is_empty());
+    assert!(assert_same(&mut db, "NULL", "").is_empty());
+}
+
+#[test]
+fn test_primary_key_index_follows_writes_and_reload() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+
+    db.execute_single_sql("DELETE FROM perf_table WHERE id = 10")
+        .unwrap();
+    db.execute_single_sql("UPDATE perf_table SET id = 5000 WHERE id = 20")
+        .unwrap();
+    db.execute_single_sql("UPDATE perf_table SET name = 'renamed' WHERE id = 30")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO perf_table VALUES (10, 'again', 1)")
+        .unwrap();
+    assert_eq!(
+        assert_same(&mut db, "10", ""),
+        vec![vec![
+            Value::Int(10),
+            Value::String("again".to_string()),
+            Value::Int(1)
+        ]]
+    );
+    assert!(assert_same(&mut db, "20", "").is_empty());
+    assert_eq!(
+        assert_same(&mut db, "5000", "")[0][1],
+        Value::String("user20".to_string())
+    );
+    assert_eq!(
+        assert_same(&mut db, "30", "")[0][1],
+        Value::String("renamed".to_string())
+    );
+
+    // 整理数据页会改变记录ID
+    db.execute_single_sql("DELETE FROM perf_table WHERE score < 50")
+        .unwrap();
+    db.compact_table("perf_table").unwrap();
+    for key in ["51", "99", "5000", "1999", "10"] {
+        assert_same(&mut db, key, "");
+    }
+
+    // 从磁盘重新加载后索引重建
+    db.save().unwrap();
+    drop(db);
+    let mut db = testing::open_db(temp_dir.path(), "pk_db").unwrap();
+    for key in (1..=ROWS).step_by(37) {
+        assert_same(&mut db, &key.to_string(), "");
+    }
+    assert_eq!(assert_same(&mut db, "1999", "").len(), 1);
+}
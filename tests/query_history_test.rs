@@ -0,0 +1,115 @@
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use simple_db::{DBConfig, SimpleDB};
+use tempfile::TempDir;
+
+fn create_db(temp_dir: &TempDir, no_query_history: bool) -> SimpleDB {
+    let config = DBConfig {
+        no_query_history,
+        ..testing::config(temp_dir.path(), "history_test")
+    };
+    SimpleDB::with_config(config).expect("无法创建数据库")
+}
+
+#[test]
+fn test_query_history_records_statements() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir, false);
+
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    db.execute_single_sql("INSERT INTO t VALUES (1), (2)")
+        .unwrap();
+    db.execute_single_sql("SELECT * FROM t").unwrap();
+    assert!(db.execute_single_sql("INSERT INTO t VALUES ('x')").is_err());
+
+    let rows = query_rows(
+        &mut db,
+        "SELECT statement, rows, success FROM _query_history",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::String("CREATE TABLE t (id INT)".to_string()),
+                Value::Int(0),
+                Value::Int(1),
+            ],
+            vec![
+                Value::String("INSERT INTO t VALUES (1), (2)".to_string()),
+                Value::Int(2),
+                Value::Int(1),
+            ],
+            vec![
+                Value::String("SELECT * FROM t".to_string()),
+                Value::Int(2),
+                Value::Int(1),
+            ],
+            vec![
+                Value::String("INSERT INTO t VALUES ('x')".to_string()),
+                Value::Int(0),
+                Value::Int(0),
+            ],
+        ]
+    );
+
+    // 查询历史本身的 SELECT 不会被记录
+    assert_eq!(query_rows(&mut db, "SELECT * FROM _query_history").len(), 4);
+    assert_eq!(
+        query_rows(
+            &mut db,
+            "SELECT statement FROM _query_history WHERE success = 0"
+        )
+        .len(),
+        1
+    );
+
+    // 伪表是只读的，且不出现在 SHOW TABLES 中
+    assert!(
+        db.execute_single_sql("INSERT INTO _query_history VALUES ('x', 0, 0, 0, 1)")
+            .is_err()
+    );
+    assert!(db.execute_single_sql("DELETE FROM _query_history").is_err());
+    assert_eq!(
+        query_rows(&mut db, "SHOW TABLES"),
+        vec![vec![Value::String("t".to_string())]]
+    );
+
+    db.session_mut().clear_query_history();
+    assert!(query_rows(&mut db, "SELECT * FROM _query_history").is_empty());
+}
+
+#[test]
+fn test_query_history_can_be_disabled() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir, true);
+
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    db.execute_single_sql("SELECT * FROM t").unwrap();
+
+    assert!(db.session().query_history().is_empty());
+}
+
+#[test]
+fn test_results_carry_elapsed_time() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir, true);
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+
+    // 结果集、INSERT 和 UPDATE 带有耗时，输出末尾是行数和耗时
+    let results = db
+        .execute_sql("INSERT INTO t VALUES (1); SELECT * FROM t; UPDATE t SET id = 2")
+        .unwrap();
+    let footers: Vec<String> = results
+        .iter()
+        .map(|result| result.as_ref().unwrap().to_string())
+        .map(|text| text.lines().next_back().unwrap().to_string())
+        .collect();
+    assert!(footers[0].starts_with("Query OK, 1 row affected ("));
+    assert!(footers[1].starts_with("1 row in set ("));
+    assert!(footers[2].starts_with("Rows matched: 1  Changed: 1"));
+    match &results[1] {
+        Ok(QueryResult::ResultSet(rs)) => assert!(rs.elapsed.is_some()),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
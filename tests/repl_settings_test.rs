@@ -0,0 +1,52 @@
+use simple_db::output::OutputFormat;
+use simple_db::settings::{HISTORY_FILE_NAME, ReplSettings, SETTINGS_FILE_NAME};
+use simple_db::testing;
+use simple_db::{DBConfig, SimpleDB};
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn open_db(temp_dir: &TempDir, history_file: Option<String>) -> SimpleDB {
+    let config = DBConfig {
+        history_file,
+        ..testing::config(temp_dir.path(), "repl_db")
+    };
+    SimpleDB::with_config(config).expect("无法打开数据库")
+}
+
+#[test]
+fn test_history_and_settings_paths() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // 默认放在数据目录下，而不是当前工作目录
+    let db = open_db(&temp_dir, None);
+    assert_eq!(db.history_path(), temp_dir.path().join(HISTORY_FILE_NAME));
+    assert_eq!(db.settings_path(), temp_dir.path().join(SETTINGS_FILE_NAME));
+    drop(db);
+
+    // --history-file 只改变历史记录的位置
+    let custom = temp_dir.path().join("elsewhere").join("history.txt");
+    let db = open_db(&temp_dir, Some(custom.to_string_lossy().to_string()));
+    assert_eq!(db.history_path(), PathBuf::from(&custom));
+    assert_eq!(db.settings_path(), temp_dir.path().join(SETTINGS_FILE_NAME));
+}
+
+#[test]
+fn test_repl_settings_survive_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir, None);
+    assert_eq!(db.repl_settings(), ReplSettings::default());
+
+    let settings = ReplSettings {
+        format: OutputFormat::Csv,
+        max_rows: Some(5),
+        max_width: None,
+    };
+    db.apply_repl_settings(settings);
+    db.repl_settings().save(&db.settings_path()).unwrap();
+    drop(db);
+
+    let mut db = open_db(&temp_dir, None);
+    let loaded = ReplSettings::load(&db.settings_path()).unwrap().unwrap();
+    db.apply_repl_settings(loaded);
+    assert_eq!(db.repl_settings(), settings);
+}
@@ -0,0 +1,147 @@
+use simple_db::SimpleDB;
+use simple_db::client::Client;
+use simple_db::error::DBError;
+use simple_db::executor::QueryResult;
+use simple_db::server::Server;
+use simple_db::server::protocol::ResultFormat;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/// 批量读取测试的默认行数，可用 SERVER_BULK_ROWS 环境变量覆盖（如 50000）
+const DEFAULT_BULK_ROWS: usize = 5_000;
+
+fn bulk_rows() -> usize {
+    std::env::var("SERVER_BULK_ROWS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_BULK_ROWS)
+}
+
+/// 在后台线程中启动服务，返回监听地址；服务线程随测试进程结束
+fn start_server(mut db: SimpleDB) -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.serve(&mut db));
+    addr
+}
+
+#[test]
+fn test_every_value_round_trips_in_both_formats() {
+    let temp_dir = TempDir::new().unwrap();
+    let addr = start_server(testing::open_db(temp_dir.path(), "server_test").unwrap());
+
+    let sql = "SELECT 42, -1.5, '你好, \"world\"', TRUE, NULL";
+    let expected = vec![vec![
+        Value::Int(42),
+        Value::Float(-1.5),
+        Value::String("你好, \"world\"".to_string()),
+        Value::Boolean(true),
+        Value::Null,
+    ]];
+
+    for format in [ResultFormat::Json, ResultFormat::Binary] {
+        let mut client = Client::connect(addr, format).unwrap();
+        assert_eq!(client.format(), format);
+
+        let rs = client.query(sql).unwrap();
+        assert_eq!(rs.columns.len(), 5);
+        assert_eq!(rs.rows, expected, "{:?}", format);
+
+        // 非结果集的语句
+        let table = format!("t_{:?}", format).to_lowercase();
+        assert!(matches!(
+            client
+                .execute(&format!(
+                    "CREATE TABLE {} (id INT, name VARCHAR(10))",
+                    table
+                ))
+                .unwrap(),
+            QueryResult::Success
+        ));
+        match client
+            .execute(&format!("INSERT INTO {} VALUES (1, 'a'), (2, 'b')", table))
+            .unwrap()
+        {
+            QueryResult::Modified(last) => assert_eq!(last.rows_affected, 2),
+            other => panic!("预期 Modified，实际为 {:?}", other),
+        }
+
+        // 错误带着错误码到达客户端，连接仍然可用
+        match client.query("SELECT * FROM missing_table") {
+            Err(err) => assert!(!matches!(err, DBError::IO(_)), "{:?}", err),
+            Ok(rs) => panic!("预期错误，实际为 {:?}", rs),
+        }
+        assert_eq!(
+            client
+                .query(&format!("SELECT id FROM {}", table))
+                .unwrap()
+                .rows,
+            vec![vec![Value::Int(1)], vec![Value::Int(2)]]
+        );
+    }
+}
+
+#[test]
+fn test_bulk_read_binary_vs_json() {
+    let bulk_rows = bulk_rows();
+    let (_temp_dir, mut db) = testing::temp_db("server_test").unwrap();
+    db.execute_single_sql("CREATE TABLE bulk (id INT, label VARCHAR(32))")
+        .unwrap();
+    for start in (0..bulk_rows).step_by(1000) {
+        let values: Vec<String> = (start..(start + 1000).min(bulk_rows))
+            .map(|id| format!("({}, 'label-{}')", id, id))
+            .collect();
+        db.execute_single_sql(&format!("INSERT INTO bulk VALUES {}", values.join(", ")))
+            .unwrap();
+    }
+    let addr = start_server(db);
+
+    let timed_query = |format: ResultFormat| -> (Vec<Vec<Value>>, Duration) {
+        let mut client = Client::connect(addr, format).unwrap();
+        let started = Instant::now();
+        let rs = client.query("SELECT id, label FROM bulk").unwrap();
+        (rs.rows, started.elapsed())
+    };
+
+    let (json_rows, json_elapsed) = timed_query(ResultFormat::Json);
+    let (binary_rows, binary_elapsed) = timed_query(ResultFormat::Binary);
+    println!(
+        "{} 行: JSON {:?}, 二进制 {:?}",
+        bulk_rows, json_elapsed, binary_elapsed
+    );
+
+    assert_eq!(binary_rows.len(), bulk_rows);
+    assert_eq!(binary_rows, json_rows);
+    assert_eq!(
+        binary_rows[bulk_rows - 1],
+        vec![
+            Value::Int(bulk_rows as i32 - 1),
+            Value::String(format!("label-{}", bulk_rows - 1))
+        ]
+    );
+}
+
+#[test]
+fn test_disconnect_rolls_back_open_transaction() {
+    let (_temp_dir, mut db) = testing::temp_db("server_test").unwrap();
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    let addr = start_server(db);
+
+    let mut client = Client::connect(addr, ResultFormat::Json).unwrap();
+    client.execute("BEGIN").unwrap();
+    client.execute("INSERT INTO t VALUES (1)").unwrap();
+    drop(client);
+
+    // 服务端处理完上一个连接才接受下一个，此时未提交的插入已经回滚
+    let mut client = Client::connect(addr, ResultFormat::Json).unwrap();
+    assert!(client.query("SELECT * FROM t").unwrap().rows.is_empty());
+    client.execute("INSERT INTO t VALUES (2)").unwrap();
+    assert_eq!(
+        client.query("SELECT * FROM t").unwrap().rows,
+        vec![vec![Value::Int(2)]]
+    );
+}
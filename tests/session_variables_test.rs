@@ -0,0 +1,159 @@
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use simple_db::{DBConfig, SimpleDB};
+use tempfile::TempDir;
+
+fn create_db(temp_dir: &TempDir) -> SimpleDB {
+    let config = DBConfig {
+        // 这里的测试要用到查询历史
+        no_query_history: false,
+        ..testing::config(temp_dir.path(), "variables_test")
+    };
+    SimpleDB::with_config(config).expect("无法创建数据库")
+}
+
+fn variable(db: &mut SimpleDB, name: &str) -> String {
+    let rows = query_rows(db, &format!("SHOW VARIABLES LIKE '{}'", name));
+    match rows.as_slice() {
+        [row] => match &row[1] {
+            Value::String(value) => value.clone(),
+            other => panic!("变量值应为字符串，实际为 {:?}", other),
+        },
+        other => panic!("预期一行，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_set_and_show_each_variable() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+
+    // 默认值，按名称排序
+    let names: Vec<Value> = query_rows(&mut db, "SHOW VARIABLES")
+        .into_iter()
+        .map(|row| row[0].clone())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "access_path",
+            "index_selectivity_threshold",
+            "max_result_rows",
+            "query_history",
+            "sort_buffer_size",
+            "sql_mode",
+            "truncate_long_strings"
+        ]
+        .map(|name| Value::String(name.to_string()))
+    );
+    assert_eq!(variable(&mut db, "sql_mode"), "LENIENT");
+    assert_eq!(variable(&mut db, "query_history"), "ON");
+
+    let cases = [
+        ("SET sql_mode = 'strict'", "sql_mode", "STRICT"),
+        ("SET @@sql_mode = LENIENT", "sql_mode", "LENIENT"),
+        ("SET query_history = OFF", "query_history", "OFF"),
+        ("SET SESSION query_history = 1", "query_history", "ON"),
+        ("SET @@session.sort_buffer_size = 4096", "sort_buffer_size", "4096"),
+        ("SET sort_buffer_size = DEFAULT", "sort_buffer_size", "268435456"),
+        ("SET MAX_RESULT_ROWS = 10", "max_result_rows", "10"),
+        ("SET truncate_long_strings = ON", "truncate_long_strings", "ON"),
+        ("SET access_path = index", "access_path", "INDEX"),
+        ("SET index_selectivity_threshold = 5", "index_selectivity_threshold", "5"),
+    ];
+    for (sql, name, expected) in cases {
+        db.execute_single_sql(sql).unwrap();
+        assert_eq!(variable(&mut db, name), expected, "{}", sql);
+    }
+
+    // 一条语句设置多个变量
+    db.execute_single_sql("SET sql_mode = STRICT, max_result_rows = 0")
+        .unwrap();
+    assert_eq!(variable(&mut db, "sql_mode"), "STRICT");
+    assert_eq!(variable(&mut db, "max_result_rows"), "0");
+
+    // LIKE 过滤不区分大小写
+    assert_eq!(query_rows(&mut db, "SHOW VARIABLES LIKE 'SQL%'").len(), 1);
+    assert_eq!(query_rows(&mut db, "SHOW VARIABLES LIKE '%_size'").len(), 1);
+}
+
+#[test]
+fn test_invalid_values_rejected_without_partial_update() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+
+    let err = db
+        .execute_single_sql("SET max_result_rows = -1")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("[0, 2147483647]"), "{}", err);
+
+    let err = db
+        .execute_single_sql("SET sql_mode = 'ANSI'")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("LENIENT, STRICT"), "{}", err);
+
+    // 第二个赋值出错时第一个也不生效
+    assert!(
+        db.execute_single_sql("SET max_result_rows = 5, query_history = 'maybe'")
+            .is_err()
+    );
+    assert_eq!(variable(&mut db, "max_result_rows"), "0");
+}
+
+#[test]
+fn test_unknown_variable_warns_or_errors_by_sql_mode() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+
+    assert!(matches!(
+        db.execute_single_sql("SET no_such_variable = 1").unwrap(),
+        QueryResult::Success
+    ));
+    let warnings = query_rows(&mut db, "SHOW WARNINGS");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0][1], Value::Int(1193));
+
+    db.execute_single_sql("SET sql_mode = STRICT").unwrap();
+    let err = db
+        .execute_single_sql("SET no_such_variable = 1")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("no_such_variable"), "{}", err);
+}
+
+#[test]
+fn test_toggles_take_effect_mid_session() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    db.execute_single_sql("INSERT INTO t VALUES (1), (2), (3), (4), (5)")
+        .unwrap();
+
+    assert_eq!(query_rows(&mut db, "SELECT id FROM t").len(), 5);
+    db.execute_single_sql("SET max_result_rows = 2").unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT id FROM t ORDER BY id DESC"),
+        vec![vec![Value::Int(5)], vec![Value::Int(4)]]
+    );
+    // 与 LIMIT 同时存在时取较小者
+    assert_eq!(query_rows(&mut db, "SELECT id FROM t LIMIT 1").len(), 1);
+    assert_eq!(query_rows(&mut db, "SELECT id FROM t LIMIT 4").len(), 2);
+    db.execute_single_sql("SET max_result_rows = DEFAULT").unwrap();
+    assert_eq!(query_rows(&mut db, "SELECT id FROM t").len(), 5);
+
+    // 行过宽：宽松模式下只是警告，严格模式下直接报错
+    let wide = "CREATE TABLE wide (a VARCHAR(20000), b VARCHAR(20000))";
+    db.execute_single_sql(wide).unwrap();
+    db.execute_single_sql("DROP TABLE wide").unwrap();
+    db.execute_single_sql("SET sql_mode = STRICT").unwrap();
+    assert!(db.execute_single_sql(wide).is_err());
+
+    // 关闭查询历史会清空已有记录
+    assert!(!query_rows(&mut db, "SELECT statement FROM _query_history").is_empty());
+    db.execute_single_sql("SET query_history = OFF").unwrap();
+    db.execute_single_sql("SET query_history = ON").unwrap();
+    assert!(query_rows(&mut db, "SELECT statement FROM _query_history").len() <= 1);
+}
@@ -0,0 +1,42 @@
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+
+fn show_databases(db: &mut SimpleDB) -> Vec<String> {
+    match db.execute_single_sql("SHOW DATABASES").unwrap() {
+        QueryResult::ResultSet(rs) => {
+            assert_eq!(rs.columns, vec!["Database".to_string()]);
+            rs.rows
+                .into_iter()
+                .map(|row| match row.as_slice() {
+                    [Value::String(name)] => name.clone(),
+                    other => panic!("预期一列数据库名，实际为 {:?}", other),
+                })
+                .collect()
+        }
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_show_databases_sorted_and_includes_current() {
+    let (_temp_dir, mut db) = testing::temp_db("main_db").unwrap();
+
+    assert!(show_databases(&mut db).contains(&"main_db".to_string()));
+
+    for name in ["zeta", "alpha", "mid"] {
+        db.execute_single_sql(&format!("CREATE DATABASE {}", name))
+            .unwrap();
+    }
+    let names = show_databases(&mut db);
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+    for name in ["alpha", "main_db", "mid", "zeta"] {
+        assert!(names.contains(&name.to_string()), "缺少 {}: {:?}", name, names);
+    }
+
+    db.execute_single_sql("DROP DATABASE mid").unwrap();
+    assert!(!show_databases(&mut db).contains(&"mid".to_string()));
+}
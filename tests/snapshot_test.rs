@@ -0,0 +1,136 @@
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+
+fn table_contents(db: &mut SimpleDB) -> Vec<Vec<Vec<Value>>> {
+    vec![
+        query_rows(db, "SELECT * FROM customers ORDER BY id"),
+        query_rows(db, "SELECT * FROM orders ORDER BY id"),
+    ]
+}
+
+#[test]
+fn test_export_drop_and_restore_database() {
+    let (temp_dir, mut db) = testing::temp_db("main").unwrap();
+    db.execute_single_sql("CREATE DATABASE shop").unwrap();
+    db.execute_single_sql("USE shop").unwrap();
+    db.execute_single_sql("CREATE TABLE customers (id INT PRIMARY KEY, name VARCHAR(20))")
+        .unwrap();
+    db.execute_single_sql(
+        "CREATE TABLE orders (id INT PRIMARY KEY, customer INT, note VARCHAR(120), paid BOOLEAN)",
+    )
+    .unwrap();
+    db.execute_single_sql("INSERT INTO customers VALUES (1, 'alice'), (2, 'bob'), (3, NULL)")
+        .unwrap();
+    // 足够多的行，使 orders 占用多个页面
+    for id in 0..400 {
+        db.execute_single_sql(&format!(
+            "INSERT INTO orders VALUES ({}, {}, '{}-{}', {})",
+            id,
+            id % 3 + 1,
+            id,
+            "x".repeat(100),
+            id % 2 == 0
+        ))
+        .unwrap();
+    }
+    db.execute_single_sql("DELETE FROM orders WHERE id % 7 = 0")
+        .unwrap();
+    let expected = table_contents(&mut db);
+
+    // 导出的数据库不必是当前数据库，未保存的修改也在快照中
+    db.execute_single_sql("USE main").unwrap();
+    let snapshot = temp_dir.path().join("shop.snapshot");
+    let summary = db.export_database("shop", &snapshot).unwrap();
+    assert_eq!(summary.tables, 2);
+    assert!(summary.pages > 2, "{:?}", summary);
+
+    db.execute_single_sql("DROP DATABASE shop").unwrap();
+    assert_eq!(db.restore_backup(&snapshot, None).unwrap(), "shop");
+    db.execute_single_sql("USE shop").unwrap();
+    assert_eq!(table_contents(&mut db), expected);
+    // 主键索引随表重建
+    assert!(
+        db.execute_single_sql("INSERT INTO customers VALUES (1, 'again')")
+            .is_err()
+    );
+
+    // 同名数据库已存在时必须指定新名称
+    let err = db.restore_backup(&snapshot, None).unwrap_err();
+    assert!(err.to_string().contains("已存在"), "{}", err);
+    assert_eq!(
+        db.restore_backup(&snapshot, Some("shop_copy")).unwrap(),
+        "shop_copy"
+    );
+    db.execute_single_sql("USE shop_copy").unwrap();
+    assert_eq!(table_contents(&mut db), expected);
+
+    // 导入的数据库重新打开后仍然完整
+    db.save().unwrap();
+    drop(db);
+    let mut db = testing::open_db(temp_dir.path(), "main").unwrap();
+    db.execute_single_sql("USE shop_copy").unwrap();
+    assert_eq!(table_contents(&mut db), expected);
+}
+
+#[test]
+fn test_export_rejects_unknown_database_and_bad_files() {
+    let (temp_dir, mut db) = testing::temp_db("main").unwrap();
+    let snapshot = temp_dir.path().join("missing.snapshot");
+    assert!(db.export_database("missing", &snapshot).is_err());
+    assert!(!snapshot.exists());
+
+    let bogus = temp_dir.path().join("bogus.snapshot");
+    std::fs::write(&bogus, b"not a snapshot").unwrap();
+    let err = db.restore_backup(&bogus, Some("bogus")).unwrap_err();
+    assert!(err.to_string().contains("不是有效的备份文件"), "{}", err);
+    assert!(
+        db.execute_single_sql("USE bogus").is_err(),
+        "无效的快照不应留下空数据库"
+    );
+}
+
+#[test]
+fn test_restore_keeps_check_constraints() {
+    let (temp_dir, mut db) = testing::temp_db("main").unwrap();
+    db.execute_sql(
+        "CREATE TABLE stock (id INT PRIMARY KEY, qty INT, CHECK (qty >= 0), \
+         CONSTRAINT small CHECK (qty < 1000)); \
+         INSERT INTO stock VALUES (1, 5)",
+    )
+    .unwrap();
+    let snapshot = temp_dir.path().join("main.snapshot");
+    db.export_database("main", &snapshot).unwrap();
+    assert_eq!(db.restore_backup(&snapshot, Some("copy")).unwrap(), "copy");
+    db.execute_single_sql("USE copy").unwrap();
+
+    for sql in [
+        "INSERT INTO stock VALUES (2, -1)",
+        "INSERT INTO stock VALUES (2, 1000)",
+        "UPDATE stock SET qty = -1 WHERE id = 1",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err();
+        assert!(err.to_string().contains("is violated"), "{}: {}", sql, err);
+    }
+    assert_eq!(
+        query_rows(&mut db, "SELECT * FROM stock"),
+        vec![vec![Value::Int(1), Value::Int(5)]]
+    );
+
+    // 约束名与源数据库相同，重新打开后仍然生效
+    db.save().unwrap();
+    drop(db);
+    let mut db = testing::open_db(temp_dir.path(), "main").unwrap();
+    db.execute_single_sql("USE copy").unwrap();
+    let create = query_rows(&mut db, "SHOW CREATE TABLE stock")[0][1].to_string();
+    assert!(
+        create.contains("CONSTRAINT `stock_chk_1` CHECK"),
+        "{}",
+        create
+    );
+    assert!(create.contains("CONSTRAINT `small` CHECK"), "{}", create);
+    assert!(
+        db.execute_single_sql("INSERT INTO stock VALUES (2, -1)")
+            .is_err()
+    );
+}
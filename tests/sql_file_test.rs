@@ -0,0 +1,165 @@
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use simple_db::{DBConfig, SimpleDB};
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn config(temp_dir: &TempDir, sql_file: Option<&PathBuf>, stop_on_error: bool) -> DBConfig {
+    DBConfig {
+        sql_file: sql_file.map(|path| path.to_string_lossy().to_string()),
+        stop_on_error,
+        ..testing::config(temp_dir.path(), "script_db")
+    }
+}
+
+fn create_db(temp_dir: &TempDir, sql_file: Option<&PathBuf>, stop_on_error: bool) -> SimpleDB {
+    SimpleDB::with_config(config(temp_dir, sql_file, stop_on_error)).expect("无法创建数据库")
+}
+
+/// 写入一个路径中带空格的脚本文件
+fn write_script(temp_dir: &TempDir) -> PathBuf {
+    let dir = temp_dir.path().join("my scripts");
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("setup file.sql");
+    std::fs::write(
+        &path,
+        "-- 初始化\n\
+         CREATE TABLE t (id INT PRIMARY KEY, name VARCHAR(20));\n\
+         INSERT INTO t VALUES (1, 'a;b');\n\
+         \n\
+         INSERT INTO t VALUES (1, 'dup');\n\
+         INSERT INTO t\n  VALUES (2, 'c');\n\
+         SELECT * FROM missing;\n",
+    )
+    .unwrap();
+    path
+}
+
+fn ids(db: &mut SimpleDB) -> Vec<Value> {
+    match db.execute_single_sql("SELECT id FROM t").unwrap() {
+        QueryResult::ResultSet(rs) => rs.rows.into_iter().map(|row| row[0].clone()).collect(),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+#[test]
+fn test_sql_file_continues_after_failures() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = write_script(&temp_dir);
+    let mut db = create_db(&temp_dir, None, false);
+
+    let results = db.execute_sql_file(path.to_str().unwrap()).unwrap();
+    let lines: Vec<(usize, bool)> = results
+        .iter()
+        .map(|result| (result.line, result.result.is_ok()))
+        .collect();
+    assert_eq!(
+        lines,
+        [(2, true), (3, true), (5, false), (6, true), (8, false)]
+    );
+    assert_eq!(
+        simple_db::script::summary(&results),
+        "3 ok, 2 failed (lines 5, 8)"
+    );
+    assert_eq!(ids(&mut db), [Value::Int(1), Value::Int(2)]);
+}
+
+#[test]
+fn test_sql_file_stop_on_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = write_script(&temp_dir);
+    let mut db = create_db(&temp_dir, None, true);
+
+    let results = db.execute_sql_file(path.to_str().unwrap()).unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[2].line, 5);
+    assert!(results[2].result.is_err());
+    assert_eq!(ids(&mut db), [Value::Int(1)]);
+
+    // 文件模式以错误结束，使进程以非零状态退出
+    drop(db);
+    let mut db = create_db(&temp_dir, Some(&path), true);
+    db.execute_single_sql("DROP TABLE t").unwrap();
+    assert!(db.run().is_err());
+}
+
+#[test]
+fn test_sql_file_results_go_to_writer() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = write_script(&temp_dir);
+    let mut db = create_db(&temp_dir, Some(&path), false);
+
+    // 只有查询结果写入 writer；有语句失败时以错误结束，使进程以非零状态退出
+    let mut out = Vec::new();
+    let error = db.run_file(path.to_str().unwrap(), &mut out).unwrap_err();
+    assert!(error.to_string().contains("2 条语句执行失败"), "{}", error);
+    let output = String::from_utf8(out).unwrap();
+    assert!(!output.contains("Error"), "{}", output);
+    assert_eq!(ids(&mut db), [Value::Int(1), Value::Int(2)]);
+
+    let mut out = Vec::new();
+    db.run_command("SELECT name FROM t WHERE id = 2", &mut out)
+        .unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.contains("| c    |"), "{}", output);
+}
+
+#[test]
+fn test_output_flag_writes_results_to_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("ok.sql");
+    std::fs::write(
+        &script,
+        "CREATE TABLE t (id INT, name VARCHAR(20));\n\
+         INSERT INTO t VALUES (1, 'a'), (2, 'b');\n\
+         SELECT * FROM t;\n",
+    )
+    .unwrap();
+    let output = temp_dir.path().join("out.csv");
+
+    let mut config = config(&temp_dir, Some(&script), false);
+    config.format = simple_db::output::OutputFormat::Csv;
+    config.output = Some(output.to_string_lossy().to_string());
+    config.quiet = true;
+    let mut db = SimpleDB::with_config(config).unwrap();
+    db.run().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "id,name\n1,a\n2,b\n"
+    );
+}
+
+#[test]
+fn test_sql_file_output_is_not_truncated() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("big.sql");
+    let values: Vec<String> = (1..=1500)
+        .map(|id| format!("({}, '{}')", id, "x".repeat(100)))
+        .collect();
+    std::fs::write(
+        &script,
+        format!(
+            "CREATE TABLE t (id INT PRIMARY KEY, note VARCHAR(200));\n\
+             INSERT INTO t VALUES {};\n\
+             SELECT * FROM t;\n\
+             EXPLAIN SELECT * FROM t WHERE id > 10 AND id <= 20;\n",
+            values.join(", ")
+        ),
+    )
+    .unwrap();
+    let mut db = create_db(&temp_dir, Some(&script), false);
+
+    // 交互模式的行数和列宽限制不影响脚本的输出
+    let mut out = Vec::new();
+    db.run_file(script.to_str().unwrap(), &mut out).unwrap();
+    let output = String::from_utf8(out).unwrap();
+    assert!(output.contains(&format!("| 1500 | {} |", "x".repeat(100))));
+    assert!(!output.contains("more rows"), "{}", output);
+    assert!(!output.contains("..."), "{}", output);
+    assert!(
+        output.contains("ACCESS primary key lookup (id > 10 AND id <= 20), estimated"),
+        "{}",
+        output
+    );
+}
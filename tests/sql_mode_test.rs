@@ -0,0 +1,149 @@
+//! 布尔值与整数之间的隐式转换和比较：宽松模式按 MySQL 把布尔值当作 0/1，
+//! 严格模式下布尔值只能写入 BOOLEAN 列、只能与布尔值比较。
+
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use tempfile::TempDir;
+
+fn ids(db: &mut SimpleDB, condition: &str) -> Vec<i32> {
+    query_rows(
+        db,
+        &format!("SELECT id FROM f WHERE {} ORDER BY id", condition),
+    )
+    .into_iter()
+    .map(|row| match row[0] {
+        Value::Int(id) => id,
+        ref other => panic!("预期整数，实际为 {:?}", other),
+    })
+    .collect()
+}
+
+/// f 的列依次为 INT、BOOLEAN、INT
+fn flags_db(temp_dir: &TempDir, strict: bool) -> SimpleDB {
+    let mut db = testing::open_db(temp_dir.path(), "mode_db").unwrap();
+    db.execute_sql(
+        "CREATE TABLE f (id INT, b BOOLEAN, n INT);
+         INSERT INTO f VALUES (1, TRUE, 1), (2, FALSE, 0), (3, NULL, 2);",
+    )
+    .unwrap();
+    if strict {
+        db.execute_single_sql("SET sql_mode = 'STRICT'").unwrap();
+    }
+    db
+}
+
+#[test]
+fn test_lenient_mode_treats_booleans_as_integers() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = flags_db(&temp_dir, false);
+
+    // 写入：0/1 写入 BOOLEAN 列，TRUE/FALSE 写入 INT 列
+    db.execute_single_sql("INSERT INTO f VALUES (4, 1, TRUE), (5, 0, FALSE)")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT b, n FROM f WHERE id >= 4 ORDER BY id"),
+        vec![
+            vec![Value::Boolean(true), Value::Int(1)],
+            vec![Value::Boolean(false), Value::Int(0)],
+        ]
+    );
+    db.execute_single_sql("UPDATE f SET b = 0, n = TRUE WHERE id = 4")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT b, n FROM f WHERE id = 4"),
+        vec![vec![Value::Boolean(false), Value::Int(1)]]
+    );
+    // 0/1 以外的整数两种模式下都不能写入 BOOLEAN 列
+    assert!(
+        db.execute_single_sql("INSERT INTO f VALUES (6, 2, 0)")
+            .is_err()
+    );
+
+    // 比较：两个方向、各种比较运算和 IN 都按 0/1 比较
+    assert_eq!(ids(&mut db, "b = 1"), vec![1]);
+    assert_eq!(ids(&mut db, "1 = b"), vec![1]);
+    assert_eq!(ids(&mut db, "n = TRUE"), vec![1, 4]);
+    assert_eq!(ids(&mut db, "FALSE = n"), vec![2, 5]);
+    assert_eq!(ids(&mut db, "b < 1"), vec![2, 4, 5]);
+    assert_eq!(ids(&mut db, "n > TRUE"), vec![3]);
+    assert_eq!(ids(&mut db, "b IN (SELECT n FROM f WHERE id = 1)"), vec![1]);
+    assert_eq!(ids(&mut db, "b = n"), vec![1, 2, 5]);
+    assert_eq!(
+        query_rows(&mut db, "SELECT TRUE = 1, 0 < TRUE"),
+        vec![vec![Value::Boolean(true), Value::Boolean(true)]]
+    );
+}
+
+#[test]
+fn test_strict_mode_keeps_booleans_and_integers_apart() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = flags_db(&temp_dir, true);
+
+    // 写入：整数不能写入 BOOLEAN 列，布尔值不能写入 INT 列
+    for sql in [
+        "INSERT INTO f VALUES (4, 1, 3)",
+        "INSERT INTO f VALUES (4, 0, 3)",
+        "INSERT INTO f VALUES (1, TRUE, TRUE)",
+        "INSERT INTO f (id, n) VALUES (4, FALSE)",
+        "INSERT INTO f VALUES (4, 2, 0)",
+        "UPDATE f SET b = 1 WHERE id = 1",
+        "UPDATE f SET n = TRUE WHERE id = 1",
+        "UPDATE f SET n = b WHERE id = 1",
+        "UPDATE f SET b = n WHERE id = 1",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err().to_string();
+        assert!(err.contains("不能写入"), "{}: {}", sql, err);
+    }
+    let err = db
+        .execute_single_sql("INSERT INTO f VALUES (4, 1, 3)")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("严格模式下整数 1 不能写入 BOOLEAN 列"),
+        "{}",
+        err
+    );
+    assert_eq!(query_rows(&mut db, "SELECT * FROM f").len(), 3);
+
+    // 同类型的写入和数值之间的转换不受影响
+    db.execute_single_sql("INSERT INTO f VALUES (4, TRUE, 3.0)")
+        .unwrap();
+    db.execute_single_sql("UPDATE f SET b = FALSE, n = 5 WHERE id = 4")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT b, n FROM f WHERE id = 4"),
+        vec![vec![Value::Boolean(false), Value::Int(5)]]
+    );
+
+    // 比较：布尔值与数值比较时语句报错，布尔值之间、数值之间照常比较
+    for condition in [
+        "b = 1",
+        "1 = b",
+        "n = TRUE",
+        "FALSE = n",
+        "b < 1",
+        "n > TRUE",
+        "b IN (SELECT n FROM f WHERE id = 1)",
+        "b = n",
+    ] {
+        let sql = format!("SELECT id FROM f WHERE {}", condition);
+        let err = db.execute_single_sql(&sql).unwrap_err().to_string();
+        assert!(err.contains("不能比较"), "{}: {}", condition, err);
+    }
+    assert_eq!(ids(&mut db, "b = TRUE"), vec![1]);
+    assert_eq!(ids(&mut db, "b"), vec![1]);
+    assert_eq!(ids(&mut db, "n = 1.0"), vec![1]);
+    assert!(db.execute_single_sql("SELECT TRUE = 1").is_err());
+
+    // 静态检查同样按严格模式报告
+    let summaries = db.check_sql("SELECT id FROM f WHERE b = 1").unwrap();
+    assert!(summaries[0].error.is_some());
+
+    // 切回宽松模式后恢复按 0/1 处理
+    db.execute_single_sql("SET sql_mode = 'LENIENT'").unwrap();
+    assert_eq!(ids(&mut db, "b = 1"), vec![1]);
+    db.execute_single_sql("UPDATE f SET b = 1 WHERE id = 2")
+        .unwrap();
+    assert_eq!(ids(&mut db, "b = TRUE"), vec![1, 2]);
+}
@@ -0,0 +1,137 @@
+use simple_db::error::DBError;
+use simple_db::guard;
+use simple_db::planner::Plan;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use simple_db::{DBConfig, SimpleDB};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+fn open_db(temp_dir: &TempDir) -> SimpleDB {
+    let config = DBConfig {
+        // 这里的测试要用到查询历史
+        no_query_history: false,
+        ..testing::config(temp_dir.path(), "guard_db")
+    };
+    let mut db = SimpleDB::with_config(config).expect("无法打开数据库");
+    db.execute_single_sql("CREATE TABLE t (id INT, name VARCHAR(10))")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO t VALUES (1, 'a'), (2, 'b')")
+        .unwrap();
+    db
+}
+
+fn is_forbidden(db: &mut SimpleDB, sql: &str) -> bool {
+    matches!(db.execute_single_sql(sql), Err(DBError::Forbidden(_)))
+}
+
+#[test]
+fn test_read_only_guard() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    db.set_statement_guard(guard::read_only());
+
+    for sql in [
+        "INSERT INTO t VALUES (3, 'c')",
+        "UPDATE t SET name = 'x' WHERE id = 1",
+        "DELETE FROM t WHERE id = 1",
+        "CREATE TABLE u (id INT)",
+        "ALTER TABLE t ADD COLUMN extra INT",
+        "DROP TABLE t",
+        "SELECT * INTO copy FROM t",
+    ] {
+        assert!(is_forbidden(&mut db, sql), "{}", sql);
+    }
+    for sql in [
+        "SELECT * FROM t",
+        "SHOW TABLES",
+        "DESCRIBE t",
+        "EXPLAIN DELETE FROM t",
+        "SET max_result_rows = 10",
+    ] {
+        assert!(db.execute_single_sql(sql).is_ok(), "{}", sql);
+    }
+    assert_eq!(query_rows(&mut db, "SELECT * FROM t").len(), 2);
+
+    // 被拒绝的语句记为失败，并带有单独的标记
+    let rows = query_rows(
+        &mut db,
+        "SELECT statement, success, forbidden FROM _query_history WHERE forbidden = 1",
+    );
+    assert_eq!(rows.len(), 7);
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::String("INSERT INTO t VALUES (3, 'c')".to_string()),
+            Value::Int(0),
+            Value::Int(1),
+        ]
+    );
+    assert!(
+        query_rows(
+            &mut db,
+            "SELECT * FROM _query_history WHERE success = 0 AND forbidden = 0"
+        )
+        .is_empty()
+    );
+
+    db.clear_statement_guard();
+    db.execute_single_sql("INSERT INTO t VALUES (3, 'c')")
+        .unwrap();
+}
+
+#[test]
+fn test_require_where_guard_sees_folded_plan() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    db.set_statement_guard(guard::require_where());
+
+    for sql in [
+        "DELETE FROM t",
+        "UPDATE t SET name = 'x'",
+        // 恒真的条件在折叠后等同于没有条件
+        "DELETE FROM t WHERE 1 = 1",
+        "UPDATE t SET name = 'x' WHERE 1 = 1 OR id = 2",
+    ] {
+        let err = db.execute_single_sql(sql).unwrap_err();
+        assert!(matches!(err, DBError::Forbidden(_)), "{}", sql);
+        assert!(err.to_string().contains("WHERE"), "{}", err);
+    }
+    assert_eq!(query_rows(&mut db, "SELECT * FROM t").len(), 2);
+
+    db.execute_single_sql("UPDATE t SET name = 'x' WHERE id = 1 AND 1 = 1")
+        .unwrap();
+    db.execute_single_sql("DELETE FROM t WHERE id = 2").unwrap();
+    db.execute_single_sql("INSERT INTO t VALUES (3, 'c')")
+        .unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT * FROM t"),
+        vec![
+            vec![Value::Int(1), Value::String("x".to_string())],
+            vec![Value::Int(3), Value::String("c".to_string())],
+        ]
+    );
+}
+
+#[test]
+fn test_custom_guard_runs_before_every_statement() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    db.set_statement_guard(Box::new(move |plan: &Plan| {
+        log.lock().unwrap().push(plan.kind());
+        match plan {
+            Plan::DropTable { .. } => Err("租户不能删除表".to_string()),
+            _ => Ok(()),
+        }
+    }));
+
+    let results = db
+        .execute_sql("SELECT * FROM t; DROP TABLE t; SELECT id FROM t")
+        .unwrap();
+    assert!(results[0].is_ok() && results[2].is_ok());
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(err.to_string(), "语句被拒绝: 租户不能删除表");
+    assert_eq!(*seen.lock().unwrap(), ["SELECT", "DROP TABLE", "SELECT"]);
+}
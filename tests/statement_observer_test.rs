@@ -0,0 +1,102 @@
+use simple_db::SimpleDB;
+use simple_db::observer::StatementEvent;
+use simple_db::session::Session;
+use simple_db::testing;
+use std::sync::{Arc, Mutex};
+
+/// 观察到的事件：开始时为 (SQL, None)，结束时为 (SQL, Some(摘要))
+type Observed = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+fn observe(db: &mut SimpleDB) -> Observed {
+    let events: Observed = Arc::default();
+    let sink = Arc::clone(&events);
+    db.set_statement_observer(Box::new(move |event| {
+        if let StatementEvent::Finished { elapsed, .. } = event {
+            assert!(elapsed.as_secs() < 60);
+        }
+        sink.lock()
+            .unwrap()
+            .push((event.sql().to_string(), event.summary()));
+    }));
+    events
+}
+
+fn started(sql: &str) -> (String, Option<String>) {
+    (sql.to_string(), None)
+}
+
+fn finished(sql: &str, summary: &str) -> (String, Option<String>) {
+    (sql.to_string(), Some(summary.to_string()))
+}
+
+#[test]
+fn test_observer_sees_each_statement_of_a_script() {
+    let (_temp_dir, mut db) = testing::temp_db("observer_db").unwrap();
+    let events = observe(&mut db);
+
+    let results = db
+        .execute_sql(
+            "CREATE TABLE t (id INT, name VARCHAR(10));
+             INSERT INTO t VALUES (1, 'a'), (2, 'b');
+             UPDATE t SET name = 'c' WHERE id = 2;
+             SELECT * FROM t WHERE id > 0;
+             SELECT missing FROM t;",
+        )
+        .unwrap();
+    assert_eq!(results.len(), 5);
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            started("CREATE TABLE t (id INT, name VARCHAR(10))"),
+            finished("CREATE TABLE t (id INT, name VARCHAR(10))", "成功"),
+            started("INSERT INTO t VALUES (1, 'a'), (2, 'b')"),
+            finished("INSERT INTO t VALUES (1, 'a'), (2, 'b')", "影响 2 行"),
+            started("UPDATE t SET name = 'c' WHERE id = 2"),
+            finished(
+                "UPDATE t SET name = 'c' WHERE id = 2",
+                "匹配 1 行，变更 1 行"
+            ),
+            started("SELECT * FROM t WHERE id > 0"),
+            finished("SELECT * FROM t WHERE id > 0", "2 行"),
+            started("SELECT missing FROM t"),
+            finished("SELECT missing FROM t", "错误: 列 'missing' 不存在"),
+        ]
+    );
+}
+
+#[test]
+fn test_observer_sees_planning_errors_and_read_only_queries() {
+    let (_temp_dir, mut db) = testing::temp_db("observer_db").unwrap();
+    db.execute_single_sql("CREATE TABLE t (id INT)").unwrap();
+    let events = observe(&mut db);
+
+    // 生成计划失败时同样有结束事件，之后的语句不再执行
+    let err = db.execute_sql("SELECT id, * FROM t; SELECT 1").unwrap_err();
+    {
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2, "{:?}", events);
+        assert_eq!(events[0], started("SELECT id, * FROM t"));
+        assert_eq!(
+            events[1],
+            finished("SELECT id, * FROM t", &format!("错误: {}", err))
+        );
+    }
+
+    events.lock().unwrap().clear();
+    let mut session = Session::new();
+    db.execute_read_only("SELECT id FROM t", &mut session)
+        .unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            started("SELECT id FROM t"),
+            finished("SELECT id FROM t", "0 行")
+        ]
+    );
+
+    // 移除观察者之后不再收到事件
+    db.clear_statement_observer();
+    db.execute_single_sql("SELECT id FROM t").unwrap();
+    assert_eq!(events.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,211 @@
+use simple_db::SimpleDB;
+use simple_db::executor::QueryResult;
+use simple_db::storage::table::Value;
+use simple_db::testing;
+use tempfile::TempDir;
+
+fn create_db(temp_dir: &TempDir) -> SimpleDB {
+    let mut db = testing::open_db(temp_dir.path(), "subquery_db").expect("无法创建数据库");
+    db.execute_sql(
+        "CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20), age INT); \
+         CREATE TABLE orders (id INT PRIMARY KEY, user_id INT); \
+         INSERT INTO users VALUES (1, 'alice', 30), (2, 'bob', 20), (3, 'carol', 40), (4, 'dave', NULL); \
+         INSERT INTO orders VALUES (10, 1), (11, 3), (12, 3)",
+    )
+    .unwrap();
+    db
+}
+
+fn ids(db: &mut SimpleDB, sql: &str) -> Vec<Value> {
+    match db.execute_single_sql(sql).unwrap() {
+        QueryResult::ResultSet(rs) => rs.rows.into_iter().map(|row| row[0].clone()).collect(),
+        other => panic!("预期结果集，实际为 {:?}", other),
+    }
+}
+
+fn error_message(db: &mut SimpleDB, sql: &str) -> String {
+    match db.execute_single_sql(sql) {
+        Ok(result) => panic!("预期 {} 失败，实际为 {:?}", sql, result),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[test]
+fn test_in_and_scalar_subqueries() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders) ORDER BY id"
+        ),
+        [1, 3].map(Value::Int)
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id NOT IN (SELECT user_id FROM orders) ORDER BY id"
+        ),
+        [2, 4].map(Value::Int)
+    );
+    // AVG(age) 为 30，age 为 NULL 的行比较结果为 NULL，不匹配
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age > (SELECT AVG(age) FROM users)"
+        ),
+        [3].map(Value::Int)
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT name FROM users WHERE id = (SELECT MAX(user_id) FROM orders)"
+        ),
+        [Value::String("carol".to_string())]
+    );
+    // UNION 和嵌套的子查询
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders UNION SELECT 2) \
+             AND id IN (SELECT id FROM users WHERE age < (SELECT MAX(age) FROM users)) \
+             ORDER BY id"
+        ),
+        [1, 2].map(Value::Int)
+    );
+
+    // UPDATE 和 DELETE 的条件同样支持子查询
+    db.execute_single_sql(
+        "UPDATE users SET age = 0 WHERE id IN (SELECT user_id FROM orders WHERE id = 10)",
+    )
+    .unwrap();
+    assert_eq!(
+        ids(&mut db, "SELECT age FROM users WHERE id = 1"),
+        [Value::Int(0)]
+    );
+    db.execute_single_sql("DELETE FROM orders WHERE user_id = (SELECT MIN(id) FROM users)")
+        .unwrap();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM orders ORDER BY id"),
+        [11, 12].map(Value::Int)
+    );
+}
+
+#[test]
+fn test_empty_subquery_results() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+
+    // 空集合：IN 恒假，NOT IN 恒真，左侧为 NULL 也一样
+    assert!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders WHERE id > 99)"
+        )
+        .is_empty()
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age NOT IN (SELECT user_id FROM orders WHERE id > 99) \
+             ORDER BY id"
+        ),
+        [1, 2, 3, 4].map(Value::Int)
+    );
+
+    // 标量子查询必须恰好返回一行
+    let message = error_message(
+        &mut db,
+        "SELECT id FROM users WHERE id = (SELECT user_id FROM orders WHERE id > 99)",
+    );
+    assert!(message.contains("Subquery returns no rows"), "{}", message);
+}
+
+#[test]
+fn test_scalar_subquery_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+
+    for (sql, expected) in [
+        (
+            "SELECT id FROM users WHERE id = (SELECT user_id FROM orders)",
+            "Subquery returns more than 1 row",
+        ),
+        (
+            "SELECT id FROM users WHERE id = (SELECT id, user_id FROM orders WHERE id = 10)",
+            "Operand should contain 1 column(s)",
+        ),
+        (
+            "SELECT id FROM users WHERE id IN (SELECT * FROM orders)",
+            "Operand should contain 1 column(s)",
+        ),
+        (
+            "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders WHERE user_id = users.id)",
+            "暂不支持相关子查询",
+        ),
+        (
+            "SELECT id FROM users WHERE id IN (SELECT id FROM orders WHERE user_id = age)",
+            "暂不支持相关子查询",
+        ),
+        (
+            "SELECT (SELECT 1) FROM users",
+            "子查询只能用在 WHERE 条件中",
+        ),
+        (
+            "SELECT id FROM users WHERE id IN (SELECT user_id INTO t FROM orders)",
+            "子查询中不能使用 INTO",
+        ),
+    ] {
+        let message = error_message(&mut db, sql);
+        assert!(message.contains(expected), "{}: {}", sql, message);
+    }
+
+    // 子查询失败时语句没有修改任何行
+    let message = error_message(
+        &mut db,
+        "DELETE FROM users WHERE id = (SELECT user_id FROM orders)",
+    );
+    assert!(message.contains("more than 1 row"), "{}", message);
+    assert_eq!(ids(&mut db, "SELECT id FROM users").len(), 4);
+}
+
+#[test]
+fn test_null_in_subquery_set() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = create_db(&temp_dir);
+    db.execute_single_sql("INSERT INTO orders VALUES (13, NULL)")
+        .unwrap();
+
+    // 找到的值仍然匹配；找不到时集合中的 NULL 使结果为 NULL，NOT IN 因此不匹配任何行
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders) ORDER BY id"
+        ),
+        [1, 3].map(Value::Int)
+    );
+    assert!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE id NOT IN (SELECT user_id FROM orders)"
+        )
+        .is_empty()
+    );
+    // 左侧为 NULL 时 IN 和 NOT IN 都不匹配
+    assert!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age IN (SELECT user_id FROM orders)"
+        )
+        .is_empty()
+    );
+    assert_eq!(
+        ids(
+            &mut db,
+            "SELECT id FROM users WHERE age NOT IN (SELECT user_id FROM orders WHERE user_id IS NOT NULL) \
+             ORDER BY id"
+        ),
+        [1, 2, 3].map(Value::Int)
+    );
+}
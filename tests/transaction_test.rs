@@ -0,0 +1,199 @@
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use tempfile::TempDir;
+
+fn ids(db: &mut SimpleDB) -> Vec<i32> {
+    query_rows(db, "SELECT id FROM users ORDER BY id")
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Int(id) => id,
+            ref other => panic!("预期整数，实际为 {:?}", other),
+        })
+        .collect()
+}
+
+fn setup(temp_dir: &TempDir) -> SimpleDB {
+    let mut db = testing::open_db(temp_dir.path(), "tx_db").unwrap();
+    db.execute_single_sql("CREATE TABLE users (id INT PRIMARY KEY, name VARCHAR(20))")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO users VALUES (1, 'alice'), (2, 'bob')")
+        .unwrap();
+    db
+}
+
+#[test]
+fn test_rolled_back_insert_is_gone_after_reload() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+
+    db.execute_single_sql("BEGIN").unwrap();
+    db.execute_single_sql("INSERT INTO users VALUES (3, 'carol')")
+        .unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 3]);
+    db.execute_single_sql("ROLLBACK").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2]);
+
+    db.save().unwrap();
+    drop(db);
+    let mut db = testing::open_db(temp_dir.path(), "tx_db").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2]);
+
+    // 主键 3 没有留在索引里
+    db.execute_single_sql("INSERT INTO users VALUES (3, 'carol')")
+        .unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_rollback_restores_updates_deletes_and_created_tables() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+    let before = query_rows(&mut db, "SELECT * FROM users ORDER BY id");
+
+    // 同一行先更新再删除，回滚时重新插入的行恢复更新前的值
+    let results = db
+        .execute_sql(
+            "START TRANSACTION;
+             UPDATE users SET name = 'changed';
+             DELETE FROM users WHERE id = 1;
+             INSERT INTO users VALUES (1, 'again');
+             CREATE TABLE scratch (id INT);
+             INSERT INTO scratch VALUES (1);
+             CREATE TABLE dropped (id INT);
+             DROP TABLE dropped;",
+        )
+        .unwrap();
+    assert!(results.iter().all(|result| result.is_ok()), "{:?}", results);
+    db.execute_single_sql("ROLLBACK").unwrap();
+
+    assert_eq!(
+        query_rows(&mut db, "SELECT * FROM users ORDER BY id"),
+        before
+    );
+    assert!(db.execute_single_sql("SELECT * FROM scratch").is_err());
+    db.execute_single_sql("CREATE TABLE scratch (id INT)")
+        .unwrap();
+}
+
+#[test]
+fn test_commit_and_error_cases() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+
+    // 没有活动的事务时 COMMIT 和 ROLLBACK 什么也不做，语句照常自动提交
+    db.execute_single_sql("ROLLBACK").unwrap();
+    db.execute_single_sql("COMMIT").unwrap();
+    db.execute_single_sql("INSERT INTO users VALUES (3, 'carol')")
+        .unwrap();
+    db.execute_single_sql("ROLLBACK").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 3]);
+
+    db.execute_single_sql("BEGIN").unwrap();
+    assert!(db.execute_single_sql("BEGIN").is_err());
+    db.execute_single_sql("DELETE FROM users WHERE id = 3")
+        .unwrap();
+    // 已有表的结构变更无法撤销，事务中拒绝执行
+    assert!(db.execute_single_sql("DROP TABLE users").is_err());
+    assert!(
+        db.execute_single_sql("ALTER TABLE users ADD COLUMN age INT")
+            .is_err()
+    );
+    assert!(db.execute_single_sql("CREATE DATABASE other").is_err());
+    db.execute_single_sql("COMMIT").unwrap();
+
+    // 提交后回滚不影响已提交的修改
+    db.execute_single_sql("ROLLBACK").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2]);
+
+    // 中途出错的 SQL 文件可以整体回滚
+    let results = db
+        .execute_sql(
+            "BEGIN;
+             INSERT INTO users VALUES (4, 'dave');
+             INSERT INTO users VALUES (1, 'duplicate');
+             UPDATE users SET name = 'late';",
+        )
+        .unwrap();
+    assert!(results[2].is_err());
+    db.execute_single_sql("ROLLBACK").unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT name FROM users ORDER BY id"),
+        vec![
+            vec![Value::String("alice".to_string())],
+            vec![Value::String("bob".to_string())],
+        ]
+    );
+}
+
+#[test]
+fn test_uncommitted_transaction_is_rolled_back_on_close() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+    db.execute_single_sql("BEGIN").unwrap();
+    db.execute_single_sql("DELETE FROM users").unwrap();
+    drop(db);
+
+    let mut db = testing::open_db(temp_dir.path(), "tx_db").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2]);
+}
+
+#[test]
+fn test_save_is_refused_inside_a_transaction() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = setup(&temp_dir);
+    db.save().unwrap();
+    db.execute_single_sql("BEGIN").unwrap();
+    db.execute_single_sql("INSERT INTO users VALUES (3, 'carol')")
+        .unwrap();
+    let err = db.save().unwrap_err();
+    assert!(err.to_string().contains("事务中不能保存"), "{}", err);
+
+    // 模拟保存尝试之后崩溃：不执行任何析构，磁盘上没有未提交的行
+    std::mem::forget(db);
+    let mut db = testing::open_db(temp_dir.path(), "tx_db").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2]);
+
+    // 提交后可以保存
+    db.execute_single_sql("BEGIN").unwrap();
+    db.execute_single_sql("INSERT INTO users VALUES (3, 'carol')")
+        .unwrap();
+    db.execute_single_sql("COMMIT").unwrap();
+    db.save().unwrap();
+    std::mem::forget(db);
+    let mut db = testing::open_db(temp_dir.path(), "tx_db").unwrap();
+    assert_eq!(ids(&mut db), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_uncommitted_pages_stay_off_disk_past_buffer_pool_size() {
+    let (temp_dir, mut db) = testing::temp_db("tx_db").unwrap();
+    db.execute_single_sql("CREATE TABLE blobs (id INT, payload VARCHAR(1000))")
+        .unwrap();
+    db.execute_single_sql("INSERT INTO blobs VALUES (0, 'committed')")
+        .unwrap();
+    db.save().unwrap();
+
+    // 事务写入的数据超过缓冲池容量（1024 个 32KB 页面），置换时不能把未提交的页面写回磁盘
+    let payload = "x".repeat(900);
+    db.execute_single_sql("BEGIN").unwrap();
+    for batch in 0..40 {
+        let values: Vec<String> = (0..1000)
+            .map(|i| format!("({}, '{}')", batch * 1000 + i + 1, payload))
+            .collect();
+        db.execute_single_sql(&format!("INSERT INTO blobs VALUES {}", values.join(", ")))
+            .unwrap();
+    }
+    assert_eq!(
+        query_rows(&mut db, "SELECT COUNT(*) FROM blobs"),
+        vec![vec![Value::Int(40001)]]
+    );
+
+    // 模拟崩溃：不执行任何析构
+    std::mem::forget(db);
+    let mut db = testing::open_db(temp_dir.path(), "tx_db").unwrap();
+    assert_eq!(
+        query_rows(&mut db, "SELECT COUNT(*) FROM blobs"),
+        vec![vec![Value::Int(1)]]
+    );
+}
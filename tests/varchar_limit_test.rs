@@ -0,0 +1,175 @@
+use simple_db::SimpleDB;
+use simple_db::storage::table::Value;
+use simple_db::testing::{self, query_rows};
+use tempfile::TempDir;
+
+fn open_db(temp_dir: &TempDir) -> SimpleDB {
+    let mut db = testing::open_db(temp_dir.path(), "varchar_db").unwrap();
+    db.execute_single_sql("CREATE TABLE notes (id INT, tag VARCHAR(5))")
+        .unwrap();
+    db
+}
+
+fn tags(db: &mut SimpleDB) -> Vec<Value> {
+    query_rows(db, "SELECT tag FROM notes ORDER BY id")
+        .into_iter()
+        .map(|row| row[0].clone())
+        .collect()
+}
+
+fn warning_messages(db: &mut SimpleDB) -> Vec<Value> {
+    query_rows(db, "SHOW WARNINGS")
+        .into_iter()
+        .map(|row| row[2].clone())
+        .collect()
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn test_sql_writes_reject_or_truncate_long_strings() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+
+    // 默认拒绝，整条语句都不写入
+    let err = db
+        .execute_single_sql("INSERT INTO notes VALUES (1, 'ok'), (2, 'too long')")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Data too long for column 'tag' at row 2"),
+        "{}",
+        err
+    );
+    assert!(tags(&mut db).is_empty());
+
+    db.execute_single_sql("SET truncate_long_strings = ON")
+        .unwrap();
+    // 长度按字符计算，"ab中文字符" 截断到 5 个字符
+    db.execute_single_sql("INSERT INTO notes VALUES (1, 'ok'), (2, 'too long'), (3, 'ab中文字符')")
+        .unwrap();
+    assert_eq!(
+        warning_messages(&mut db),
+        [
+            string("Data truncated for column 'tag' at row 2"),
+            string("Data truncated for column 'tag' at row 3"),
+        ]
+    );
+    assert_eq!(
+        tags(&mut db),
+        [string("ok"), string("too l"), string("ab中文字")]
+    );
+
+    db.execute_single_sql("UPDATE notes SET tag = 'abcdefgh' WHERE id > 1")
+        .unwrap();
+    assert_eq!(warning_messages(&mut db).len(), 2);
+    assert_eq!(
+        tags(&mut db),
+        [string("ok"), string("abcde"), string("abcde")]
+    );
+
+    db.execute_single_sql("SET truncate_long_strings = OFF")
+        .unwrap();
+    let err = db
+        .execute_single_sql("UPDATE notes SET tag = 'abcdefgh'")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Data too long for column 'tag'"), "{}", err);
+    assert_eq!(tags(&mut db)[0], string("ok"));
+
+    // 检查模式与执行一致：默认报错，打开截断后是警告
+    let summaries = db
+        .check_sql("INSERT INTO notes VALUES (4, 'too long')")
+        .unwrap();
+    assert!(summaries[0].error.is_some());
+    db.execute_single_sql("SET truncate_long_strings = ON")
+        .unwrap();
+    let summaries = db
+        .check_sql("INSERT INTO notes VALUES (4, 'too long')")
+        .unwrap();
+    assert!(summaries[0].error.is_none());
+    assert_eq!(summaries[0].warnings.len(), 1);
+}
+
+#[test]
+fn test_bulk_insert_enforces_limit_at_storage_layer() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+    let rows = || {
+        vec![
+            vec![Value::Int(1), string("short")],
+            vec![Value::Int(2), string("文文文文文文")],
+        ]
+    };
+
+    let err = db.bulk_insert("notes", rows()).unwrap_err().to_string();
+    assert!(
+        err.contains("Data too long for column 'tag' at row 2"),
+        "{}",
+        err
+    );
+    assert!(tags(&mut db).is_empty());
+
+    db.execute_single_sql("SET truncate_long_strings = ON")
+        .unwrap();
+    assert_eq!(db.bulk_insert("notes", rows()).unwrap(), 2);
+    assert_eq!(
+        warning_messages(&mut db),
+        [string("Data truncated for column 'tag' at row 2")]
+    );
+    assert_eq!(tags(&mut db), [string("short"), string("文文文文文")]);
+}
+
+#[test]
+fn test_limit_counts_characters() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = open_db(&temp_dir);
+
+    // 恰好 5 个多字节字符可以写入 VARCHAR(5)，第 6 个字符超出
+    for (id, tag) in [(1, "中文字符串"), (2, "😀😀😀😀😀"), (3, "aé中😀b")] {
+        db.execute_single_sql(&format!("INSERT INTO notes VALUES ({}, '{}')", id, tag))
+            .unwrap();
+    }
+    for tag in ["中文字符串串", "😀😀😀😀😀😀"] {
+        let err = db
+            .execute_single_sql(&format!("INSERT INTO notes VALUES (9, '{}')", tag))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Data too long for column 'tag'"), "{}", err);
+    }
+    db.execute_single_sql("UPDATE notes SET tag = '一二三四五' WHERE id = 3")
+        .unwrap();
+    assert_eq!(
+        tags(&mut db),
+        [
+            string("中文字符串"),
+            string("😀😀😀😀😀"),
+            string("一二三四五")
+        ]
+    );
+
+    // 未声明长度的 VARCHAR 不限长度，显示时不带数字
+    db.execute_single_sql("CREATE TABLE docs (body VARCHAR)")
+        .unwrap();
+    db.execute_single_sql(&format!(
+        "INSERT INTO docs VALUES ('{}')",
+        "长".repeat(1000)
+    ))
+    .unwrap();
+    let types: Vec<Value> = query_rows(&mut db, "DESCRIBE docs")
+        .into_iter()
+        .map(|row| row[1].clone())
+        .collect();
+    assert_eq!(types, [string("VARCHAR")]);
+    db.save().unwrap();
+    drop(db);
+
+    // 重新打开后仍然是不限长度
+    let mut db = testing::open_db(temp_dir.path(), "varchar_db").unwrap();
+    assert_eq!(
+        query_rows(&mut db, "DESCRIBE docs")[0][1],
+        string("VARCHAR")
+    );
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+use std::process::Command;
+
+/// 以 -D warnings 检查整个 crate（含测试和示例），防止新的编译警告悄悄累积
+///
+/// 使用单独的 target 目录，避免不同的 RUSTFLAGS 让主构建缓存失效。
+#[test]
+fn test_crate_builds_without_warnings() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--all-targets", "--offline", "--quiet"])
+        .current_dir(manifest_dir)
+        .env("RUSTFLAGS", "-D warnings")
+        .env(
+            "CARGO_TARGET_DIR",
+            manifest_dir.join("target").join("warnings-check"),
+        )
+        .output()
+        .expect("无法运行 cargo check");
+
+    assert!(
+        output.status.success(),
+        "编译产生了警告:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}